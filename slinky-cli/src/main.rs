@@ -1,35 +1,282 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use std::{error::Error, path::PathBuf};
+mod serve;
+mod unified_diff;
 
-use clap::Parser;
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
 use regex::Regex;
-use slinky::{RuntimeSettings, ScriptGenerator};
+use slinky::{
+    GenerationStatistics, RuntimeSettings, ScriptExporter, ScriptFlavor, ScriptGenerator,
+    ScriptImporter, WrittenFile,
+};
 
 // TODO: Add program description to cli
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Input file
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Input file. Use `-` to read it from stdin
+    #[arg(required = true)]
+    input: Option<PathBuf>,
 
-    /// Output file. Print to stdout if missing
+    /// Output file. Print to stdout if missing or `-`
     #[arg(short, long)]
     output: Option<PathBuf>,
 
     /// Generate linker script for partial linking multiple segments.
-    /// Requires both `partial_scripts_folder` and `partial_build_segments_folder` YAML settings to be set.
+    /// Requires the `partial_scripts_folder` YAML setting and either `partial_build_segments_folder` or `partial_build_path` to be set.
     #[arg(short, long, default_value_t = false)]
     partial_linking: bool,
 
+    /// Which linker scripts to generate. `partial` is the same as
+    /// `--partial-linking`, while `both` writes the normal linker script to the
+    /// output path and the main partial linking script to `--partial-output`
+    #[arg(long, value_enum, conflicts_with = "partial_linking")]
+    mode: Option<Mode>,
+
+    /// Output file for the main partial linking script when using
+    /// `--mode both`
+    #[arg(long)]
+    partial_output: Option<PathBuf>,
+
+    /// Also write the shiftable flavor of the linker script to the given
+    /// path, which drops the fields and files marked as only matching
+    #[arg(long, conflicts_with = "partial_linking")]
+    shiftable_output: Option<PathBuf>,
+
     #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
     custom_options: Vec<(String, String)>,
 
     /// Disables the version comment emitted on linker scripts
     #[arg(long)]
     omit_version_comment: bool,
+
+    /// Embed a fingerprint of the input file and the custom options on every
+    /// generated file, see the `check-fingerprint` subcommand
+    #[arg(long)]
+    fingerprint: bool,
+
+    /// Keep backslashes on the paths of the input file as part of the file
+    /// names instead of treating them as separators
+    #[arg(long)]
+    literal_backslashes: bool,
+
+    /// Don't touch the generated files whose contents did not change, keeping
+    /// their modification time intact
+    #[arg(long)]
+    write_if_changed: bool,
+
+    /// Print a summary of what was emitted, like the amount of segments and
+    /// files skipped by their conditions, to stderr
+    #[arg(long)]
+    stats: bool,
+
+    /// Generate the given targets listed on the `targets` of the input file.
+    /// The output path should reference a custom option of the targets so
+    /// each one is written to a different file
+    #[arg(short, long, value_delimiter = ',')]
+    target: Vec<String>,
+
+    /// Generate every target listed on the `targets` of the input file
+    #[arg(long, conflicts_with = "target")]
+    all_targets: bool,
+
+    /// Treat warnings as errors, failing without generating anything
+    #[arg(long)]
+    deny_warnings: bool,
+
+    /// Verify every path referenced by the generated linker script is listed
+    /// on the dependency file and vice versa, failing otherwise
+    #[arg(long)]
+    check_dependencies: bool,
+
+    /// Write the linker script as a C source file instead, defining an array
+    /// with the given name that contains the whole script
+    #[arg(long, value_name = "ARRAY_NAME", conflicts_with = "partial_linking")]
+    c_array: Option<String>,
+
+    /// Generate everything in memory without writing any file, failing if
+    /// any of the files on disk is not up to date
+    #[arg(long, requires = "output", conflicts_with = "write_if_changed")]
+    dry_run: bool,
+
+    /// Print the differences between the files on disk and the generated
+    /// ones as unified diffs. Requires `--dry-run`
+    #[arg(long, requires = "dry_run")]
+    diff: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Full,
+    Partial,
+    Both,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Checks the input file for semantic errors without generating anything
+    Check {
+        /// Input file
+        input: PathBuf,
+
+        /// Fail if any warning is found
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+
+    /// Prints the fully resolved document, with every default value and the
+    /// entries excluded by the custom options removed
+    EffectiveConfig {
+        /// Input file
+        input: PathBuf,
+
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+    },
+
+    /// Rewrites the input file in place using a canonical field order and
+    /// formatting
+    Fmt {
+        /// Input file
+        input: PathBuf,
+
+        /// Don't write anything, fail if the input file is not formatted
+        #[arg(long)]
+        check: bool,
+
+        /// Format the input file even if some comments can't be preserved
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Evaluates the conditionals of the input file over every target, or
+    /// every combination of the `runtime_options` values, and reports the
+    /// entries which are never emitted or whose conditions have no effect
+    Coverage {
+        /// Input file
+        input: PathBuf,
+
+        /// Custom options shared by every configuration
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+
+        /// Fail if any entry is reported
+        #[arg(long)]
+        deny_findings: bool,
+    },
+
+    /// Explains how a segment is placed: why it is emitted or not, which
+    /// vram class or chain of segments determines its address, and which
+    /// files and sections end up on it
+    Explain {
+        /// Input file
+        input: PathBuf,
+
+        /// Name of the segment to explain
+        segment: String,
+
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+    },
+
+    /// Checks the fingerprint embedded on each of the given generated files
+    /// matches the one of the input file, failing if any of them is stale or
+    /// has no fingerprint
+    CheckFingerprint {
+        /// Input file
+        input: PathBuf,
+
+        /// Files generated with `--fingerprint`
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+    },
+
+    /// Reads the map file of a build and suggests which segments could be
+    /// moved to a different vram class to get every vram class under its
+    /// `max_size`
+    SuggestPlacement {
+        /// Input file
+        input: PathBuf,
+
+        /// Map file produced by GNU ld when linking with the generated script
+        map: PathBuf,
+
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+    },
+
+    /// Compares two linker scripts and reports the differences on their
+    /// segment order, file lists and symbols, ignoring the ones which don't
+    /// affect the layout. Only what can be imported into a document is
+    /// compared
+    Diff {
+        /// The original linker script
+        old: PathBuf,
+
+        /// The linker script to compare against
+        new: PathBuf,
+
+        /// Fail if any difference is found
+        #[arg(long)]
+        deny_changes: bool,
+    },
+
+    /// Compares the map files produced by GNU ld on two builds of the input
+    /// file and reports how the size of each segment and its sections changed
+    SizeDiff {
+        /// Input file
+        input: PathBuf,
+
+        /// Map file of the original build
+        old_map: PathBuf,
+
+        /// Map file of the build to compare against
+        new_map: PathBuf,
+
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+
+        /// Fail if the size of any segment grows or shrinks past a multiple of
+        /// this alignment. Accepts hexadecimal values prefixed by `0x`
+        #[arg(long, value_parser = parse_int)]
+        alignment: Option<u64>,
+    },
+
+    /// Keeps the input file parsed in memory and answers JSON-RPC requests
+    /// over HTTP on localhost, parsing the file again only when it changes
+    Serve {
+        /// Input file
+        input: PathBuf,
+
+        /// Port to listen on
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+
+        /// Default custom options, extended by the ones of each request
+        #[arg(short = 'c', long, value_parser = parse_key_val::<String, String>, value_delimiter = ',')]
+        custom_options: Vec<(String, String)>,
+
+        /// Disables the version comment emitted on linker scripts
+        #[arg(long)]
+        omit_version_comment: bool,
+    },
 }
 
 // Taken from https://github.com/clap-rs/clap/blob/f5965e586292d31b2a2cbd83f19d145180471012/examples/typed-derive.rs#L48
@@ -47,21 +294,33 @@ where
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
 
-fn create_runtime_settings(cli: &Cli) -> slinky::RuntimeSettings {
+fn parse_int(s: &str) -> Result<u64, std::num::ParseIntError> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+}
+
+fn create_runtime_settings(
+    custom_options: &[(String, String)],
+    omit_version_comment: bool,
+    write_if_changed: bool,
+) -> slinky::RuntimeSettings {
     let mut rs = slinky::RuntimeSettings::new();
 
     let regex_identifier = Regex::new(r"[a-zA-Z_][a-zA-Z0-9_]*").unwrap();
 
-    for (key, _value) in &cli.custom_options {
+    for (key, _value) in custom_options {
         if !regex_identifier.is_match(key) {
             // TODO: is there a better alternative than a plain panic?
             panic!("Invalid key for custom option: '{}'", key);
         }
     }
 
-    rs.add_custom_options(cli.custom_options.iter().cloned());
+    rs.add_custom_options(custom_options.iter().cloned());
 
-    rs.set_emit_version_comment(!cli.omit_version_comment);
+    rs.set_emit_version_comment(!omit_version_comment);
+    rs.set_write_if_changed(write_if_changed);
 
     rs
 }
@@ -71,15 +330,25 @@ fn write_script(
     document: &slinky::Document,
     rs: &RuntimeSettings,
     output: &Option<PathBuf>,
-) {
+    check_dependencies: bool,
+) -> (Vec<WrittenFile>, GenerationStatistics) {
+    let mut written = Vec::new();
+
     writer.add_whole_document(document).expect("ah?");
+    if check_dependencies {
+        writer
+            .check_dependencies()
+            .expect("The dependency file does not match the linker script");
+    }
 
     if let Some(output_path) = output {
-        writer
-            .export_linker_script_to_file(
-                &rs.escape_path(output_path).expect("Error escaping path"),
-            )
-            .expect("Error writing the linker script");
+        written.extend(
+            writer
+                .export_linker_script_to_file(
+                    &rs.escape_path(output_path).expect("Error escaping path"),
+                )
+                .expect("Error writing the linker script"),
+        );
     } else {
         println!(
             "{}",
@@ -89,28 +358,829 @@ fn write_script(
         );
     }
 
+    written.extend(
+        writer
+            .save_other_files()
+            .expect("Error writing other files listed on the document"),
+    );
+
+    (written, writer.statistics())
+}
+
+/// Prints the linker script to stdout while it is generated, instead of
+/// keeping the whole script in memory.
+fn stream_script(
+    writer: &mut slinky::LinkerWriter,
+    document: &slinky::Document,
+) -> (Vec<WrittenFile>, GenerationStatistics) {
+    writer.set_stream(std::io::stdout());
+
+    writer.add_whole_document(document).expect("ah?");
     writer
+        .finish_stream()
+        .expect("Error printing the linker script");
+    // Matches the output of `write_script`
+    println!();
+
+    let written = writer
         .save_other_files()
         .expect("Error writing other files listed on the document");
+
+    (written, writer.statistics())
+}
+
+fn write_c_array_script(
+    writer: &mut slinky::LinkerWriter,
+    document: &slinky::Document,
+    rs: &RuntimeSettings,
+    output: &Option<PathBuf>,
+    array_name: &str,
+    check_dependencies: bool,
+) -> (Vec<WrittenFile>, GenerationStatistics) {
+    let mut written = Vec::new();
+
+    writer.add_whole_document(document).expect("ah?");
+    if check_dependencies {
+        writer
+            .check_dependencies()
+            .expect("The dependency file does not match the linker script");
+    }
+
+    if let Some(output_path) = output {
+        written.push(
+            writer
+                .export_linker_script_as_c_to_file(
+                    &rs.escape_path(output_path).expect("Error escaping path"),
+                    array_name,
+                )
+                .expect("Error writing the linker script"),
+        );
+    } else {
+        print!(
+            "{}",
+            writer
+                .export_linker_script_as_c_to_string(array_name)
+                .expect("Error exporting script to string")
+        );
+    }
+
+    written.extend(
+        writer
+            .save_other_files()
+            .expect("Error writing other files listed on the document"),
+    );
+
+    (written, writer.statistics())
+}
+
+/// The statistics are the ones of the full linker script.
+fn write_both_scripts(
+    document: &slinky::Document,
+    rs: &RuntimeSettings,
+    output: &Option<PathBuf>,
+    partial_output: &Path,
+    check_dependencies: bool,
+) -> (Vec<WrittenFile>, GenerationStatistics) {
+    let mut writer = slinky::LinkerWriter::new(document, rs);
+    let (mut written, statistics) =
+        write_script(&mut writer, document, rs, output, check_dependencies);
+
+    // The files listed on the document were already written by the full
+    // linker script, so only the partial scripts and their dependency files
+    // are written here
+    let mut partial_writer = slinky::PartialLinkerWriter::new(document, rs);
+    partial_writer.add_whole_document(document).expect("ah?");
+    if check_dependencies {
+        partial_writer
+            .check_dependencies()
+            .expect("The dependency file does not match the linker script");
+    }
+
+    written.extend(
+        partial_writer
+            .get_main_writer()
+            .export_linker_script_to_file(
+                &rs.escape_path(partial_output).expect("Error escaping path"),
+            )
+            .expect("Error writing the linker script"),
+    );
+    written.extend(
+        partial_writer
+            .export_partial_scripts_to_files()
+            .expect("Error writing the partial linker scripts"),
+    );
+    written.extend(
+        partial_writer
+            .export_partial_dependencies_files()
+            .expect("Error writing the dependency files of the partial linker scripts"),
+    );
+
+    (written, statistics)
+}
+
+/// Only the linker script is written, since the rest of the files listed on
+/// the document don't depend on the flavor.
+fn write_shiftable_script(
+    document: &slinky::Document,
+    rs: &RuntimeSettings,
+    shiftable_output: &Path,
+) -> Vec<WrittenFile> {
+    let mut rs = rs.clone();
+    rs.set_flavor(ScriptFlavor::Shiftable);
+
+    let mut writer = slinky::LinkerWriter::new(document, &rs);
+    writer.add_whole_document(document).expect("ah?");
+
+    writer
+        .export_linker_script_to_file(
+            &rs.escape_path(shiftable_output)
+                .expect("Error escaping path"),
+        )
+        .expect("Error writing the shiftable linker script")
+}
+
+/// Reports the generated files which differ from the ones on disk, either
+/// by listing them or by printing their differences.
+/// Fails if any of them is not up to date.
+fn report_dry_run(written: &[WrittenFile], diff: bool) -> ExitCode {
+    let outdated: Vec<&WrittenFile> = written.iter().filter(|x| x.updated).collect();
+
+    for file in &outdated {
+        if diff {
+            let old = fs::read(&file.path).unwrap_or_default();
+            let new = file.contents.as_deref().unwrap_or_default();
+
+            print!(
+                "{}",
+                unified_diff::unified_diff(
+                    &String::from_utf8_lossy(&old),
+                    &String::from_utf8_lossy(new),
+                    &format!("{} (on disk)", file.path.display()),
+                    &format!("{} (generated)", file.path.display()),
+                )
+            );
+        } else {
+            println!("{} is not up to date", file.path.display());
+        }
+    }
+
+    if outdated.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Reads the input document, from stdin if the path is `-`.
+///
+/// Returns the name used to refer to the input on diagnostics too.
+fn read_input_document(input: &Path) -> (&Path, Result<slinky::Document, slinky::Diagnostic>) {
+    if input != Path::new("-") {
+        return (input, slinky::Document::read_file_with_diagnostics(input));
+    }
+
+    let name = Path::new("<stdin>");
+    match std::io::read_to_string(std::io::stdin()) {
+        Ok(contents) => (
+            name,
+            slinky::Document::from_yaml_str_with_diagnostics(&contents),
+        ),
+        Err(e) => (
+            name,
+            Err(slinky::Diagnostic::from_error(
+                slinky::SlinkyError::FailedRead {
+                    description: e.to_string(),
+                },
+            )),
+        ),
+    }
+}
+
+fn print_diagnostic(input: &Path, diagnostic: &slinky::Diagnostic) {
+    // Mimic the `file:line:column: message` format used by compilers so
+    // editors can jump to the reported location
+    if diagnostic.location.is_some() {
+        eprintln!("{}:{}", input.display(), diagnostic);
+    } else {
+        eprintln!("{}: {}", input.display(), diagnostic);
+    }
+}
+
+/// Prints the warnings of the document for the given runtime settings.
+/// Returns `true` if any warning was found.
+fn print_warnings(input: &Path, document: &slinky::Document, rs: &RuntimeSettings) -> bool {
+    let warnings = document.warnings(rs);
+    for warning in &warnings {
+        print_diagnostic(input, warning);
+    }
+
+    !warnings.is_empty()
+}
+
+fn check_document(input: &Path, deny_warnings: bool) -> ExitCode {
+    let document = match slinky::Document::read_file_with_diagnostics(input) {
+        Ok(d) => d,
+        Err(diagnostic) => {
+            print_diagnostic(input, &diagnostic);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let errors = document.validate();
+    for error in &errors {
+        print_diagnostic(input, &slinky::Diagnostic::from_error(error.clone()));
+    }
+
+    let rs = create_runtime_settings(&[], false, false);
+    let has_warnings = print_warnings(input, &document, &rs);
+
+    if errors.is_empty() && !(deny_warnings && has_warnings) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_effective_config(input: &Path, custom_options: &[(String, String)]) -> ExitCode {
+    let document = match slinky::Document::read_file(input) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rs = create_runtime_settings(custom_options, false, false);
+
+    if let Err(e) = rs.check_custom_options(&document.runtime_options) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match document.effective_yaml(&rs) {
+        Ok(yaml) => {
+            print!("{}", yaml);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn format_document(input: &Path, check: bool, force: bool) -> ExitCode {
+    let contents = match fs::read_to_string(input) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("{}: {}", input.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let formatted = match slinky::Document::format_yaml_str(&contents) {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            eprintln!("{}: {}", input.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if !formatted.dropped_comments.is_empty() && !force {
+        for line in &formatted.dropped_comments {
+            eprintln!(
+                "{}:{}: this comment can't be preserved by the formatter",
+                input.display(),
+                line
+            );
+        }
+        eprintln!("Use `--force` to format the file anyway, dropping those comments");
+        return ExitCode::FAILURE;
+    }
+
+    if formatted.contents == contents {
+        return ExitCode::SUCCESS;
+    }
+
+    if check {
+        eprintln!("{}: the file is not formatted", input.display());
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(e) = fs::write(input, formatted.contents) {
+        eprintln!("{}: {}", input.display(), e);
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn report_coverage(
+    input: &Path,
+    custom_options: &[(String, String)],
+    deny_findings: bool,
+) -> ExitCode {
+    let document = match slinky::Document::read_file(input) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rs = create_runtime_settings(custom_options, false, false);
+    let report = document.conditional_coverage(&rs);
+
+    println!("Evaluated {} configurations", report.configurations);
+    for entry in &report.never_emitted {
+        println!("Never emitted: {}", entry);
+    }
+    for entry in &report.always_emitted {
+        println!("Always emitted, its conditions have no effect: {}", entry);
+    }
+
+    if deny_findings && !report.is_empty() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn explain_segment(input: &Path, segment: &str, custom_options: &[(String, String)]) -> ExitCode {
+    let document = match slinky::Document::read_file(input) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rs = create_runtime_settings(custom_options, false, false);
+
+    if let Err(e) = rs.check_custom_options(&document.runtime_options) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match document.explain_segment(segment, &rs) {
+        Ok(explanation) => {
+            print!("{}", explanation);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn check_fingerprint(
+    input: &Path,
+    files: &[PathBuf],
+    custom_options: &[(String, String)],
+) -> ExitCode {
+    let document = match slinky::Document::read_file(input) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rs = create_runtime_settings(custom_options, false, false);
+
+    if let Err(e) = rs.check_custom_options(&document.runtime_options) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let expected = match document.fingerprint(&rs) {
+        Ok(fingerprint) => fingerprint,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut stale = false;
+    for file in files {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("Failed to read '{}': {}", file.display(), e);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        match slinky::read_fingerprint(&contents) {
+            Some(fingerprint) if fingerprint == expected => {}
+            Some(fingerprint) => {
+                eprintln!(
+                    "'{}' is stale: fingerprint {} does not match {}",
+                    file.display(),
+                    fingerprint,
+                    expected
+                );
+                stale = true;
+            }
+            None => {
+                eprintln!("'{}' has no fingerprint", file.display());
+                stale = true;
+            }
+        }
+    }
+
+    if stale {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn suggest_placement(input: &Path, map: &Path, custom_options: &[(String, String)]) -> ExitCode {
+    let document = match slinky::Document::read_file(input) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rs = create_runtime_settings(custom_options, false, false);
+
+    if let Err(e) = rs.check_custom_options(&document.runtime_options) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let report = match slinky::PlacementAdvisor::new(&document, &rs).suggest_from_map_file(map) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let over_budget: Vec<&slinky::VramClassUsage> = report
+        .usages
+        .iter()
+        .filter(|x| x.is_over_budget())
+        .collect();
+    if over_budget.is_empty() {
+        println!("Every vram class fits on its `max_size`");
+        return ExitCode::SUCCESS;
+    }
+
+    for usage in over_budget {
+        println!(
+            "Vram class '{}' uses {} bytes, over its `max_size` of {} bytes",
+            usage.vram_class,
+            usage.size,
+            usage.max_size.unwrap_or_default()
+        );
+
+        let mut any = false;
+        for suggestion in &report.suggestions {
+            if suggestion.from_class == usage.vram_class {
+                println!("    {}", suggestion);
+                any = true;
+            }
+        }
+        if !any {
+            println!("    No other vram class can fit its biggest segments");
+        }
+    }
+
+    ExitCode::SUCCESS
 }
 
-fn main() {
+fn size_diff(
+    input: &Path,
+    old_map: &Path,
+    new_map: &Path,
+    custom_options: &[(String, String)],
+    alignment: Option<u64>,
+) -> ExitCode {
+    let document = match slinky::Document::read_file(input) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rs = create_runtime_settings(custom_options, false, false);
+
+    if let Err(e) = rs.check_custom_options(&document.runtime_options) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let diff = match slinky::SizeDiffer::new(&document, &rs).diff_map_files(old_map, new_map) {
+        Ok(diff) => diff,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut crossed = false;
+    for segment in diff.changed() {
+        let crosses = alignment.is_some_and(|x| segment.size.crosses_alignment(x));
+        crossed |= crosses;
+
+        print!("Segment '{}': {}", segment.segment, segment.size);
+        if crosses {
+            print!(
+                ", crosses a 0x{:X} bytes boundary",
+                alignment.unwrap_or_default()
+            );
+        }
+        println!();
+
+        for (section, size) in &segment.sections {
+            if size.is_changed() {
+                println!("    {}: {}", section, size);
+            }
+        }
+    }
+
+    if crossed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn diff_scripts(old: &Path, new: &Path, deny_changes: bool) -> ExitCode {
+    let mut imported = Vec::new();
+    for path in [old, new] {
+        match slinky::Document::from_linker_script(path) {
+            Ok(script) => imported.push(script),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let diff = slinky::ScriptDiff::from_imported(&imported[0], &imported[1]);
+    for change in &diff.changes {
+        println!("{}", change);
+    }
+
+    if deny_changes && !diff.is_empty() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    // TODO: don't use expect?
-    let document = slinky::Document::read_file(&cli.input).expect("Error while parsing input file");
+    match &cli.command {
+        Some(Command::Check {
+            input,
+            deny_warnings,
+        }) => return check_document(input, *deny_warnings),
+        Some(Command::EffectiveConfig {
+            input,
+            custom_options,
+        }) => return print_effective_config(input, custom_options),
+        Some(Command::Fmt {
+            input,
+            check,
+            force,
+        }) => return format_document(input, *check, *force),
+        Some(Command::Coverage {
+            input,
+            custom_options,
+            deny_findings,
+        }) => return report_coverage(input, custom_options, *deny_findings),
+        Some(Command::Explain {
+            input,
+            segment,
+            custom_options,
+        }) => return explain_segment(input, segment, custom_options),
+        Some(Command::CheckFingerprint {
+            input,
+            files,
+            custom_options,
+        }) => return check_fingerprint(input, files, custom_options),
+        Some(Command::SuggestPlacement {
+            input,
+            map,
+            custom_options,
+        }) => return suggest_placement(input, map, custom_options),
+        Some(Command::Diff {
+            old,
+            new,
+            deny_changes,
+        }) => return diff_scripts(old, new, *deny_changes),
+        Some(Command::SizeDiff {
+            input,
+            old_map,
+            new_map,
+            custom_options,
+            alignment,
+        }) => return size_diff(input, old_map, new_map, custom_options, *alignment),
+        Some(Command::Serve {
+            input,
+            port,
+            custom_options,
+            omit_version_comment,
+        }) => {
+            let rs = create_runtime_settings(custom_options, *omit_version_comment, false);
+            return serve::serve(input, *port, rs);
+        }
+        None => {}
+    }
+
+    let input = cli.input.as_ref().expect("Missing input file");
+
+    let mode = match cli.mode {
+        Some(mode) => mode,
+        None if cli.partial_linking => Mode::Partial,
+        None => Mode::Full,
+    };
+
+    if cli.c_array.is_some() && mode != Mode::Full {
+        eprintln!("`--c-array` can only be used when generating the full linker script");
+        return ExitCode::FAILURE;
+    }
+    if cli.shiftable_output.is_some() && mode == Mode::Partial {
+        eprintln!(
+            "`--shiftable-output` can't be used when generating only the partial linker scripts"
+        );
+        return ExitCode::FAILURE;
+    }
+    if mode == Mode::Both && cli.partial_output.is_none() {
+        eprintln!("`--partial-output` is required when using `--mode both`");
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(array_name) = &cli.c_array {
+        let regex_identifier = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap();
+
+        if !regex_identifier.is_match(array_name) {
+            eprintln!("Invalid C identifier for the array name: '{}'", array_name);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // `-o -` prints to stdout, the same as not passing an output path
+    let output = cli.output.clone().filter(|x| x != Path::new("-"));
+    if cli.dry_run && output.is_none() {
+        eprintln!("`--dry-run` can't be used when printing to stdout");
+        return ExitCode::FAILURE;
+    }
+
+    let (input, document) = read_input_document(input);
+    let document = match document {
+        Ok(d) => d,
+        Err(diagnostic) => {
+            print_diagnostic(input, &diagnostic);
+            return ExitCode::FAILURE;
+        }
+    };
 
     // println!("settings {:#?}", document.settings);
 
-    let rs = create_runtime_settings(&cli);
+    let mut base_rs = create_runtime_settings(
+        &cli.custom_options,
+        cli.omit_version_comment,
+        cli.write_if_changed,
+    );
+    base_rs.set_dry_run(cli.dry_run);
+    base_rs.set_emit_fingerprint(cli.fingerprint);
+    base_rs.set_forward_slashes(!cli.literal_backslashes);
 
-    if cli.partial_linking {
-        let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    let targets: Vec<&slinky::Target> = if cli.all_targets {
+        document.targets.iter().collect()
+    } else {
+        match cli
+            .target
+            .iter()
+            .map(|name| document.target(name))
+            .collect()
+        {
+            Ok(targets) => targets,
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
 
-        write_script(&mut writer, &document, &rs, &cli.output);
+    let all_rs: Vec<RuntimeSettings> = if targets.is_empty() {
+        vec![base_rs]
     } else {
-        let mut writer = slinky::LinkerWriter::new(&document, &rs);
+        targets
+            .iter()
+            .map(|target| target.runtime_settings(&base_rs))
+            .collect()
+    };
 
-        write_script(&mut writer, &document, &rs, &cli.output);
+    for rs in &all_rs {
+        if let Err(e) = rs.check_custom_options(&document.runtime_options) {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if all_rs.len() > 1 {
+        // Every target would overwrite the output of the previous one otherwise
+        let output = match &output {
+            Some(output) => output,
+            None => {
+                eprintln!("An output path is required when generating multiple targets");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        for output in [
+            Some(output),
+            cli.partial_output.as_ref(),
+            cli.shiftable_output.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let mut seen = HashSet::new();
+            for rs in &all_rs {
+                let escaped = rs.escape_path(output).expect("Error escaping path");
+                if !seen.insert(escaped.to_string()) {
+                    eprintln!(
+                        "Multiple targets would be written to '{}'. The output path should reference a custom option of the targets",
+                        escaped
+                    );
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
     }
+
+    let mut has_warnings = false;
+    for rs in &all_rs {
+        has_warnings |= print_warnings(input, &document, rs);
+    }
+    if cli.deny_warnings && has_warnings {
+        return ExitCode::FAILURE;
+    }
+
+    let mut written = Vec::new();
+    for (index, rs) in all_rs.iter().enumerate() {
+        let (files, statistics) = if mode == Mode::Both {
+            write_both_scripts(
+                &document,
+                rs,
+                &output,
+                cli.partial_output.as_ref().expect("checked above"),
+                cli.check_dependencies,
+            )
+        } else if mode == Mode::Partial {
+            let mut writer = slinky::PartialLinkerWriter::new(&document, rs);
+
+            write_script(&mut writer, &document, rs, &output, cli.check_dependencies)
+        } else if let Some(array_name) = &cli.c_array {
+            let mut writer = slinky::LinkerWriter::new(&document, rs);
+
+            write_c_array_script(
+                &mut writer,
+                &document,
+                rs,
+                &output,
+                array_name,
+                cli.check_dependencies,
+            )
+        } else if output.is_none() && !cli.check_dependencies {
+            let mut writer = slinky::LinkerWriter::new(&document, rs);
+
+            stream_script(&mut writer, &document)
+        } else {
+            let mut writer = slinky::LinkerWriter::new(&document, rs);
+
+            write_script(&mut writer, &document, rs, &output, cli.check_dependencies)
+        };
+        written.extend(files);
+
+        if let Some(shiftable_output) = &cli.shiftable_output {
+            written.extend(write_shiftable_script(&document, rs, shiftable_output));
+        }
+
+        if cli.stats {
+            // stdout may be used by the linker script itself
+            if let Some(target) = targets.get(index) {
+                eprintln!("Target '{}':", target.name);
+            }
+            eprintln!("{}", statistics);
+        }
+    }
+
+    if cli.dry_run {
+        return report_dry_run(&written, cli.diff);
+    }
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,365 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+//! A tiny JSON-RPC 2.0 server over HTTP, keeping the parsed document in
+//! memory so editor plugins and other tools don't pay the process startup and
+//! parsing cost on every request.
+//!
+//! The document is parsed again only if the file, or any document it includes
+//! or list of files it reads, changes on disk.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serde_json::{json, Value};
+use slinky::{DocumentCache, RuntimeSettings, ScriptExporter, ScriptImporter};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Reserved for implementation-defined errors by the JSON-RPC spec. Used for
+/// every error reported by slinky itself.
+const SLINKY_ERROR: i64 = -32000;
+
+/// Requests are handled one at a time, so a client which stops sending data,
+/// or sends it too slowly, must not block every other one. The whole request
+/// must be received before this time passes.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// The request line and every header together.
+const MAX_HEADERS_SIZE: u64 = 16 * 1024;
+/// The params of every method are small, so anything bigger is not a valid
+/// request.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<slinky::SlinkyError> for RpcError {
+    fn from(e: slinky::SlinkyError) -> Self {
+        Self::new(SLINKY_ERROR, e.to_string())
+    }
+}
+
+struct Server<'a> {
+    input: &'a Path,
+    cache: DocumentCache,
+    base_rs: RuntimeSettings,
+    regex_identifier: Regex,
+}
+
+pub(crate) fn serve(input: &Path, port: u16, base_rs: RuntimeSettings) -> ExitCode {
+    let mut server = Server {
+        input,
+        cache: DocumentCache::new(1),
+        base_rs,
+        regex_identifier: Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap(),
+    };
+
+    // Fail early if the document can't be parsed at all
+    if let Err(e) = server.cache.read_file(input) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Unable to listen on port {}: {}", port, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match listener.local_addr() {
+        Ok(addr) => eprintln!("Listening on http://{}", addr),
+        Err(_) => eprintln!("Listening on port {}", port),
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = server.handle_connection(stream) {
+                    eprintln!("Error handling connection: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Error accepting connection: {}", e),
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+impl Server<'_> {
+    fn handle_connection(&mut self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut reader = BufReader::new(DeadlineStream {
+            stream: stream.try_clone()?,
+            deadline: Instant::now() + REQUEST_TIMEOUT,
+        });
+        let mut headers = (&mut reader).take(MAX_HEADERS_SIZE);
+
+        let mut request_line = String::new();
+        headers.read_line(&mut request_line)?;
+
+        let mut content_length = Some(0);
+        loop {
+            let mut header = String::new();
+            if headers.read_line(&mut header)? == 0 {
+                break;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().ok();
+                }
+            }
+        }
+
+        if headers.limit() == 0 {
+            return write_response(
+                &mut stream,
+                "431 Request Header Fields Too Large",
+                "The request headers are too large\n",
+            );
+        }
+
+        if !request_line.starts_with("POST ") {
+            return write_response(
+                &mut stream,
+                "405 Method Not Allowed",
+                "Only POST requests are supported\n",
+            );
+        }
+
+        let Some(content_length) = content_length else {
+            return write_response(&mut stream, "400 Bad Request", "Invalid Content-Length\n");
+        };
+        if content_length > MAX_BODY_SIZE {
+            return write_response(
+                &mut stream,
+                "413 Payload Too Large",
+                "The request body is too large\n",
+            );
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let response = self.handle_body(&body);
+        write_response(&mut stream, "200 OK", &response.to_string())
+    }
+
+    fn handle_body(&mut self, body: &[u8]) -> Value {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))
+            }
+        };
+
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            None => {
+                return error_response(
+                    id,
+                    RpcError::new(INVALID_REQUEST, "Missing `method` string"),
+                )
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or(json!({}));
+
+        let result = match method {
+            "generate" => self.generate(&params),
+            "validate" => self.validate(&params),
+            "explain" => self.explain(&params),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        };
+
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        }
+    }
+
+    /// Builds the runtime settings for a request from the optional
+    /// `custom_options` object and `target` name of its params.
+    fn runtime_settings(
+        &self,
+        document: &slinky::Document,
+        params: &Value,
+    ) -> Result<RuntimeSettings, RpcError> {
+        let mut rs = self.base_rs.clone();
+
+        if let Some(custom_options) = params.get("custom_options") {
+            let custom_options = custom_options.as_object().ok_or_else(|| {
+                RpcError::new(INVALID_PARAMS, "`custom_options` must be an object")
+            })?;
+
+            for (key, value) in custom_options {
+                if !self.regex_identifier.is_match(key) {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!("Invalid key for custom option: '{}'", key),
+                    ));
+                }
+                let value = value.as_str().ok_or_else(|| {
+                    RpcError::new(
+                        INVALID_PARAMS,
+                        format!("The value of the custom option '{}' must be a string", key),
+                    )
+                })?;
+
+                rs.add_custom_options([(key.clone(), value.to_string())]);
+            }
+        }
+
+        if let Some(target) = params.get("target") {
+            let target = target
+                .as_str()
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "`target` must be a string"))?;
+
+            rs = document.target(target)?.runtime_settings(&rs);
+        }
+
+        rs.check_custom_options(&document.runtime_options)?;
+
+        Ok(rs)
+    }
+
+    /// Returns the linker script generated for the given options. Other files
+    /// listed on the document are not written.
+    fn generate(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let document = self.cache.read_file(self.input)?;
+        let rs = self.runtime_settings(&document, params)?;
+
+        let mut writer = slinky::LinkerWriter::new(&document, &rs);
+        writer.add_whole_document(&document)?;
+
+        Ok(json!({ "linker_script": writer.export_linker_script_to_string()? }))
+    }
+
+    /// Returns the errors of the document and its warnings for the given
+    /// options.
+    fn validate(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let document = self.cache.read_file(self.input)?;
+        let rs = self.runtime_settings(&document, params)?;
+
+        let errors: Vec<String> = document
+            .validate()
+            .into_iter()
+            .map(|x| slinky::Diagnostic::from_error(x).to_string())
+            .collect();
+        let warnings: Vec<String> = document
+            .warnings(&rs)
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        Ok(json!({ "errors": errors, "warnings": warnings }))
+    }
+
+    /// Returns every entry of the document with the given `name`, and whether
+    /// they would be emitted with the given options.
+    fn explain(&mut self, params: &Value) -> Result<Value, RpcError> {
+        let document = self.cache.read_file(self.input)?;
+        let rs = self.runtime_settings(&document, params)?;
+
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing `name` string"))?;
+
+        let mut matches = Vec::new();
+        for segment in document.segments.iter().filter(|x| x.name == name) {
+            let emitted = rs.should_emit_entry(
+                &segment.exclude_if_any,
+                &segment.exclude_if_all,
+                &segment.include_if_any,
+                &segment.include_if_all,
+            );
+            matches.push(json!({ "kind": "segment", "emitted": emitted, "entry": segment }));
+        }
+        for vram_class in document.vram_classes.iter().filter(|x| x.name == name) {
+            matches.push(json!({ "kind": "vram_class", "entry": vram_class }));
+        }
+        for target in document.targets.iter().filter(|x| x.name == name) {
+            matches.push(json!({ "kind": "target", "entry": target }));
+        }
+        for runtime_option in document.runtime_options.iter().filter(|x| x.name == name) {
+            matches.push(json!({ "kind": "runtime_option", "entry": runtime_option }));
+        }
+
+        if matches.is_empty() {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("Nothing named '{}' on the document", name),
+            ));
+        }
+
+        Ok(Value::Array(matches))
+    }
+}
+
+/// Fails every read once the deadline passes, no matter how much data was
+/// received before it.
+struct DeadlineStream {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "the request took too long to be received",
+            ));
+        }
+
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
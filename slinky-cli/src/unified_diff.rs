@@ -0,0 +1,143 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+/// Amount of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl Line<'_> {
+    fn is_same(&self) -> bool {
+        matches!(self, Line::Same(_))
+    }
+}
+
+/// The differences between both texts in the unified format, or an empty
+/// string if they are equal.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+
+    let mut out = String::new();
+
+    let mut i = 0;
+    while let Some(first_change) = lines[i..].iter().position(|x| !x.is_same()) {
+        let first_change = i + first_change;
+
+        // Merge the changes which are close enough to share their context
+        let mut end = first_change;
+        loop {
+            while end < lines.len() && !lines[end].is_same() {
+                end += 1;
+            }
+            let next_change = lines[end..]
+                .iter()
+                .position(|x| !x.is_same())
+                .map(|x| end + x);
+            match next_change {
+                Some(next) if next - end <= 2 * CONTEXT => end = next,
+                _ => break,
+            }
+        }
+
+        let start = first_change.saturating_sub(CONTEXT);
+        let end = (end + CONTEXT).min(lines.len());
+
+        if out.is_empty() {
+            out += &format!("--- {}\n+++ {}\n", old_name, new_name);
+        }
+
+        let old_before = lines[..start]
+            .iter()
+            .filter(|x| !matches!(x, Line::Added(_)))
+            .count();
+        let new_before = lines[..start]
+            .iter()
+            .filter(|x| !matches!(x, Line::Removed(_)))
+            .count();
+        let old_count = lines[start..end]
+            .iter()
+            .filter(|x| !matches!(x, Line::Added(_)))
+            .count();
+        let new_count = lines[start..end]
+            .iter()
+            .filter(|x| !matches!(x, Line::Removed(_)))
+            .count();
+
+        // Empty ranges point to the line right before them
+        out += &format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_before + usize::from(old_count != 0),
+            old_count,
+            new_before + usize::from(new_count != 0),
+            new_count
+        );
+        for line in &lines[start..end] {
+            match line {
+                Line::Same(x) => out += &format!(" {}\n", x),
+                Line::Removed(x) => out += &format!("-{}\n", x),
+                Line::Added(x) => out += &format!("+{}\n", x),
+            }
+        }
+
+        i = end;
+    }
+
+    out
+}
+
+/// Compares both lists of lines using their longest common subsequence.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    // Only the middle part of both lists needs to be compared
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    // lengths[i][j] is the length of the longest common subsequence of
+    // old_middle[i..] and new_middle[j..]
+    let mut lengths = vec![vec![0u32; new_middle.len() + 1]; old_middle.len() + 1];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|x| Line::Same(x)).collect();
+
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() && j < new_middle.len() {
+        if old_middle[i] == new_middle[j] {
+            lines.push(Line::Same(old_middle[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            lines.push(Line::Removed(old_middle[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new_middle[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old_middle[i..].iter().map(|x| Line::Removed(x)));
+    lines.extend(new_middle[j..].iter().map(|x| Line::Added(x)));
+
+    lines.extend(old[old.len() - suffix..].iter().map(|x| Line::Same(x)));
+
+    lines
+}
@@ -0,0 +1,144 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, ChildStderr, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A `slinky-cli serve` process listening on a random port, killed on drop.
+struct Server {
+    child: Child,
+    port: u16,
+    /// Kept open so the errors logged by the server don't fail to be written.
+    _stderr: BufReader<ChildStderr>,
+}
+
+impl Server {
+    fn spawn() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_slinky-cli"))
+            .args([
+                "serve",
+                "../tests/test_cases/basic_example.yaml",
+                "--port",
+                "0",
+            ])
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Unable to start the server");
+
+        let mut stderr = BufReader::new(child.stderr.take().unwrap());
+        let mut line = String::new();
+        stderr.read_line(&mut line).unwrap();
+        let port = line
+            .trim_end()
+            .rsplit(':')
+            .next()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_else(|| panic!("Unexpected server output: {:?}", line));
+
+        Self {
+            child,
+            port,
+            _stderr: stderr,
+        }
+    }
+
+    /// Sends the raw request and returns the whole response.
+    fn request(&self, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        stream.write_all(request).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn post(&self, body: &str) -> String {
+        self.request(
+            format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_serve_generate() {
+    let server = Server::spawn();
+
+    let response = server.post(r#"{"jsonrpc": "2.0", "id": 1, "method": "generate"}"#);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains(r#""linker_script":"#), "{}", response);
+}
+
+#[test]
+fn test_serve_rejects_bad_requests() {
+    let server = Server::spawn();
+
+    let response = server.request(b"GET / HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 "), "{}", response);
+
+    let response =
+        server.request(b"POST / HTTP/1.1\r\nContent-Length: 1000000000000000000\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 413 "), "{}", response);
+
+    let response =
+        server.request(b"POST / HTTP/1.1\r\nContent-Length: 99999999999999999999\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 400 "), "{}", response);
+
+    // Exactly the size limit, so the server doesn't close the connection with
+    // unread data, which would reset it before the response is read
+    let mut huge_header = b"POST / HTTP/1.1\r\nX-Padding: ".to_vec();
+    huge_header.resize(16 * 1024, b'a');
+    let response = server.request(&huge_header);
+    assert!(response.starts_with("HTTP/1.1 431 "), "{}", response);
+
+    // None of the above should take the server down
+    let response = server.post(r#"{"jsonrpc": "2.0", "id": 1, "method": "validate"}"#);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+}
+
+#[test]
+fn test_serve_silent_client() {
+    let server = Server::spawn();
+
+    // A client which never sends anything must not block the other ones
+    let _silent = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+
+    let response = server.post(r#"{"jsonrpc": "2.0", "id": 1, "method": "validate"}"#);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+}
+
+#[test]
+fn test_serve_slow_client() {
+    let server = Server::spawn();
+
+    // A client sending a byte every now and then must not block the other
+    // ones either, even if it never stops
+    let mut slow = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    thread::spawn(move || {
+        for c in b"POST / HTTP/1.1\r\nX-Padding: ".iter().chain(&[b'a'; 60]) {
+            if slow.write_all(&[*c]).is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+    });
+
+    let start = Instant::now();
+    let response = server.post(r#"{"jsonrpc": "2.0", "id": 1, "method": "validate"}"#);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(start.elapsed() < Duration::from_secs(20));
+}
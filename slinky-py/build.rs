@@ -0,0 +1,8 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+fn main() {
+    // Python symbols are resolved when the module is loaded, which needs
+    // explicit linker arguments on macOS
+    pyo3_build_config::add_extension_module_link_args();
+}
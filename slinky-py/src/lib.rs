@@ -0,0 +1,218 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+//! Python bindings for slinky, so build scripts can generate linker scripts
+//! without spawning the CLI.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+create_exception!(slinky, SlinkyError, PyException);
+
+fn to_py_err(e: slinky::SlinkyError) -> PyErr {
+    SlinkyError::new_err(e.to_string())
+}
+
+/// A parsed slinky document.
+#[pyclass(name = "Document", module = "slinky", frozen)]
+struct PyDocument {
+    inner: slinky::Document,
+}
+
+#[pymethods]
+impl PyDocument {
+    #[staticmethod]
+    fn read_file(path: PathBuf) -> PyResult<Self> {
+        slinky::Document::read_file(&path)
+            .map(|inner| Self { inner })
+            .map_err(to_py_err)
+    }
+
+    #[staticmethod]
+    fn read_from_str(contents: &str) -> PyResult<Self> {
+        slinky::Document::read_from_str(contents)
+            .map(|inner| Self { inner })
+            .map_err(to_py_err)
+    }
+
+    /// The names of the segments, in the order they are listed.
+    #[getter]
+    fn segments(&self) -> Vec<String> {
+        self.inner.segments.iter().map(|x| x.name.clone()).collect()
+    }
+
+    /// Every semantic problem found on the document, as messages.
+    fn validate(&self) -> Vec<String> {
+        self.inner
+            .validate()
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    fn effective_yaml(&self, rs: &PyRuntimeSettings) -> PyResult<String> {
+        self.inner.effective_yaml(&rs.inner).map_err(to_py_err)
+    }
+
+    fn fingerprint(&self, rs: &PyRuntimeSettings) -> PyResult<String> {
+        self.inner.fingerprint(&rs.inner).map_err(to_py_err)
+    }
+}
+
+#[pyclass(name = "RuntimeSettings", module = "slinky")]
+struct PyRuntimeSettings {
+    inner: slinky::RuntimeSettings,
+}
+
+#[pymethods]
+impl PyRuntimeSettings {
+    #[new]
+    #[pyo3(signature = (custom_options=None))]
+    fn new(custom_options: Option<HashMap<String, String>>) -> Self {
+        let mut inner = slinky::RuntimeSettings::new();
+
+        if let Some(custom_options) = custom_options {
+            inner.add_custom_options(custom_options);
+        }
+
+        Self { inner }
+    }
+
+    fn add_custom_options(&mut self, custom_options: HashMap<String, String>) {
+        self.inner.add_custom_options(custom_options);
+    }
+
+    fn set_emit_version_comment(&mut self, emit: bool) {
+        self.inner.set_emit_version_comment(emit);
+    }
+
+    fn set_emit_fingerprint(&mut self, emit: bool) {
+        self.inner.set_emit_fingerprint(emit);
+    }
+
+    fn set_write_if_changed(&mut self, write_if_changed: bool) {
+        self.inner.set_write_if_changed(write_if_changed);
+    }
+
+    fn set_dry_run(&mut self, dry_run: bool) {
+        self.inner.set_dry_run(dry_run);
+    }
+
+    fn set_forward_slashes(&mut self, forward_slashes: bool) {
+        self.inner.set_forward_slashes(forward_slashes);
+    }
+
+    #[pyo3(signature = (source_date_epoch=None))]
+    fn set_source_date_epoch(&mut self, source_date_epoch: Option<u64>) {
+        self.inner.set_source_date_epoch(source_date_epoch);
+    }
+
+    /// `flavor` is either `"matching"` or `"shiftable"`.
+    fn set_flavor(&mut self, flavor: &str) -> PyResult<()> {
+        let flavor = match flavor {
+            "matching" => slinky::ScriptFlavor::Matching,
+            "shiftable" => slinky::ScriptFlavor::Shiftable,
+            other => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid script flavor '{}', expected 'matching' or 'shiftable'",
+                    other
+                )))
+            }
+        };
+        self.inner.set_flavor(flavor);
+
+        Ok(())
+    }
+}
+
+/// Everything produced by [`generate`]. See [`slinky::GeneratedOutputs`].
+#[pyclass(name = "GeneratedOutputs", module = "slinky", frozen, get_all)]
+struct PyGeneratedOutputs {
+    linker_script: String,
+    partial_scripts: Vec<(String, String)>,
+    partial_main_script: Option<String>,
+    dependencies: Option<String>,
+    symbols_header: Option<String>,
+    rom_symbols_header: Option<String>,
+    linker_offsets_header: Option<String>,
+    symbols_asm: Option<String>,
+    symbols_rust: Option<String>,
+    /// The paths of the files written to disk whose contents changed.
+    updated_files: Vec<PathBuf>,
+}
+
+impl From<slinky::GeneratedOutputs> for PyGeneratedOutputs {
+    fn from(outputs: slinky::GeneratedOutputs) -> Self {
+        Self {
+            linker_script: outputs.linker_script,
+            partial_scripts: outputs.partial_scripts,
+            partial_main_script: outputs.partial_main_script,
+            dependencies: outputs.dependencies,
+            symbols_header: outputs.symbols_header,
+            rom_symbols_header: outputs.rom_symbols_header,
+            linker_offsets_header: outputs.linker_offsets_header,
+            symbols_asm: outputs.symbols_asm,
+            symbols_rust: outputs.symbols_rust,
+            updated_files: outputs
+                .written
+                .into_iter()
+                .filter(|x| x.updated)
+                .map(|x| x.path)
+                .collect(),
+        }
+    }
+}
+
+/// Generates the linker scripts of the document and writes every file listed
+/// on it, like the dependency file or the symbols header.
+///
+/// `mode` is one of `"full"`, `"partial"` or `"both"`. If omitted the partial
+/// linking scripts are generated only if the document sets
+/// `partial_scripts_folder`.
+#[pyfunction]
+#[pyo3(signature = (document, rs, mode=None))]
+fn generate(
+    document: &PyDocument,
+    rs: &PyRuntimeSettings,
+    mode: Option<&str>,
+) -> PyResult<PyGeneratedOutputs> {
+    let mode = match mode {
+        None => slinky::GenerationMode::for_document(&document.inner),
+        Some("full") => slinky::GenerationMode::Full,
+        Some("partial") => slinky::GenerationMode::Partial,
+        Some("both") => slinky::GenerationMode::Both,
+        Some(other) => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid generation mode '{}', expected 'full', 'partial' or 'both'",
+                other
+            )))
+        }
+    };
+
+    slinky::generate_document(&document.inner, &rs.inner, mode)
+        .map(PyGeneratedOutputs::from)
+        .map_err(to_py_err)
+}
+
+/// Reads back the fingerprint embedded on the contents of a generated file,
+/// or `None` if it has none.
+#[pyfunction]
+fn read_fingerprint(contents: &str) -> Option<String> {
+    slinky::read_fingerprint(contents).map(str::to_string)
+}
+
+#[pymodule]
+#[pyo3(name = "slinky")]
+fn slinky_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", slinky::Version::current().to_string())?;
+    m.add("SlinkyError", m.py().get_type::<SlinkyError>())?;
+
+    m.add_class::<PyDocument>()?;
+    m.add_class::<PyRuntimeSettings>()?;
+    m.add_class::<PyGeneratedOutputs>()?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    m.add_function(wrap_pyfunction!(read_fingerprint, m)?)?;
+
+    Ok(())
+}
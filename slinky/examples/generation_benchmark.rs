@@ -0,0 +1,59 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+//! Measures how long it takes to generate the linker script of a big
+//! document, with 100 segments of 100 files each.
+//!
+//! Run with `cargo run --release -p slinky --example generation_benchmark`.
+
+use std::time::Instant;
+
+use slinky::{Document, LinkerWriter, RuntimeSettings, ScriptExporter, ScriptImporter};
+
+const SEGMENTS: usize = 100;
+const FILES_PER_SEGMENT: usize = 100;
+const ITERATIONS: u32 = 20;
+
+fn big_document() -> String {
+    let mut yaml = String::from("settings:\n  base_path: build\n\nsegments:\n");
+
+    for i in 0..SEGMENTS {
+        yaml += &format!("  - name: segment_{}\n", i);
+        if i == 0 {
+            yaml += "    fixed_vram: 0x80000400\n";
+        }
+        yaml += "    files:\n";
+        for j in 0..FILES_PER_SEGMENT {
+            yaml += &format!("      - {{ path: src/segment_{}/file_{}.o }}\n", i, j);
+        }
+    }
+
+    yaml
+}
+
+fn main() {
+    let document = Document::from_yaml_str(&big_document()).expect("Invalid document");
+    let rs = RuntimeSettings::new();
+
+    let mut script_len = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let mut writer = LinkerWriter::new(&document, &rs);
+        writer
+            .add_whole_document(&document)
+            .expect("Failed to generate");
+
+        script_len = writer
+            .export_linker_script_to_string()
+            .expect("Failed to export")
+            .len();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} files, {} bytes of linker script: {:?} per generation",
+        SEGMENTS * FILES_PER_SEGMENT,
+        script_len,
+        elapsed / ITERATIONS
+    );
+}
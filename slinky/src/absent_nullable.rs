@@ -114,4 +114,25 @@ impl<T> AbsentNullable<T> {
             AbsentNullable::Value(_v) => true,
         }
     }
+
+    /// Returns a copy of `other` if this value is absent, otherwise this
+    /// value, including an explicit `null`.
+    pub fn or_inherit(self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        match self {
+            AbsentNullable::Absent => other.clone(),
+            _ => self,
+        }
+    }
+
+    /// Like the [`From<Option<T>>`] conversion, but maps `None` to `Absent`
+    /// instead of `Null`, so the default value gets used.
+    pub fn absent_if_none(value: Option<T>) -> Self {
+        match value {
+            Some(v) => AbsentNullable::Value(v),
+            None => AbsentNullable::Absent,
+        }
+    }
 }
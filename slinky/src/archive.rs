@@ -0,0 +1,108 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::Path;
+
+use crate::{utils, SlinkyError};
+
+const ARCH_MAGIC: &[u8] = b"!<arch>\n";
+const THIN_MAGIC: &[u8] = b"!<thin>\n";
+
+const HEADER_SIZE: usize = 60;
+
+/// Reads the names of the members of the `ar` archive at `path`.
+pub(crate) fn read_archive_members(path: &Path) -> Result<Vec<String>, SlinkyError> {
+    let contents = match utils::read(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(SlinkyError::FailedFileOpen {
+                path: path.to_path_buf(),
+                description: e.to_string(),
+            })
+        }
+    };
+
+    parse_archive_members(&contents).map_err(|description| SlinkyError::InvalidArchive {
+        path: path.to_path_buf(),
+        description: description.to_string(),
+    })
+}
+
+/// Parses the member table of an `ar` archive, supporting both the GNU and
+/// BSD variants and thin archives.
+///
+/// The symbol tables and the GNU long names table are not members, so they
+/// are not returned.
+fn parse_archive_members(contents: &[u8]) -> Result<Vec<String>, &'static str> {
+    let thin = if contents.starts_with(ARCH_MAGIC) {
+        false
+    } else if contents.starts_with(THIN_MAGIC) {
+        true
+    } else {
+        return Err("missing the `!<arch>` magic");
+    };
+
+    let mut members = Vec::new();
+    let mut long_names: &[u8] = &[];
+    let mut offset = ARCH_MAGIC.len();
+
+    while offset < contents.len() {
+        let header = contents
+            .get(offset..offset + HEADER_SIZE)
+            .ok_or("truncated member header")?;
+        if &header[58..60] != b"`\n" {
+            return Err("invalid member header");
+        }
+
+        let name = String::from_utf8_lossy(&header[0..16]);
+        let name = name.trim_end();
+        let size: usize = String::from_utf8_lossy(&header[48..58])
+            .trim_end()
+            .parse()
+            .map_err(|_| "invalid member size")?;
+
+        let special = matches!(name, "/" | "//" | "/SYM64/");
+        // Thin archives only store the tables, the members live on their own
+        // files
+        let stored_size = if thin && !special { 0 } else { size };
+
+        let data_start = offset + HEADER_SIZE;
+        let data = contents
+            .get(data_start..data_start + stored_size)
+            .ok_or("truncated member contents")?;
+
+        match name {
+            "/" | "/SYM64/" | "__.SYMDEF" | "__.SYMDEF SORTED" => {}
+            "//" => long_names = data,
+            _ => {
+                if let Some(len) = name.strip_prefix("#1/") {
+                    // BSD: the name is stored at the start of the contents
+                    let len: usize = len.parse().map_err(|_| "invalid member name length")?;
+                    let raw = data.get(..len).ok_or("truncated member name")?;
+                    let raw = String::from_utf8_lossy(raw);
+                    let raw = raw.trim_end_matches('\0');
+                    if !matches!(raw, "__.SYMDEF" | "__.SYMDEF SORTED") {
+                        members.push(raw.to_string());
+                    }
+                } else if let Some(index) = name.strip_prefix('/') {
+                    // GNU: the name is an offset into the long names table
+                    let index: usize = index.parse().map_err(|_| "invalid member name offset")?;
+                    let rest = long_names
+                        .get(index..)
+                        .ok_or("invalid member name offset")?;
+                    let end = rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                    let raw = String::from_utf8_lossy(&rest[..end]);
+                    members.push(raw.trim_end_matches('/').to_string());
+                } else {
+                    members.push(name.trim_end_matches('/').to_string());
+                }
+            }
+        }
+
+        offset = data_start + stored_size;
+        // Members are aligned to 2 bytes
+        offset += offset % 2;
+    }
+
+    Ok(members)
+}
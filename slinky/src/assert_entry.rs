@@ -1,27 +1,66 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{absent_nullable::AbsentNullable, traits::Serial, Settings, SlinkyError};
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata,
+    linker_symbols_style::resolve_logical_symbols, traits::Serial, Document, Settings, SlinkyError,
+};
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
 pub struct AssertEntry {
+    /// The condition to check.
+    ///
+    /// May reference the symbols generated for the document by their `splat`
+    /// style name wrapped in `{{ }}`, like `{{boot_ROM_END}}`. See
+    /// [`AssertEntry::resolved_check`].
     pub check: String,
     pub error_message: String,
 
+    /// If true then a failed check does not fail the link. The result of the
+    /// check is stored on the symbol given by
+    /// [`AssertEntry::warning_symbol`] instead, which is verified by the
+    /// [`MapVerifier`](crate::MapVerifier).
+    pub warning: bool,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_all: Vec<(String, String)>,
 }
 
+impl AssertEntry {
+    /// The check of this assert, replacing every `{{name}}` reference with
+    /// the name given to that symbol by the `linker_symbols_style` of the
+    /// document.
+    pub fn resolved_check(&self, d: &Document) -> Result<String, SlinkyError> {
+        resolve_logical_symbols(d, &self.check)
+    }
+
+    /// The symbol storing the result of a `warning` assert, which is set to
+    /// zero if the check passed. `index` is the index of the assert on the
+    /// `asserts` of the document, with the `post_sections_asserts` numbered
+    /// after them.
+    #[must_use]
+    pub fn warning_symbol(index: usize) -> String {
+        format!("__slinky_assert_warning_{}", index)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct AssertEntrySerial {
     pub check: String,
     pub error_message: String,
 
+    #[serde(default)]
+    pub warning: AbsentNullable<bool>,
+
     #[serde(default)]
     pub include_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
@@ -30,12 +69,15 @@ pub(crate) struct AssertEntrySerial {
     pub exclude_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
     pub exclude_if_all: AbsentNullable<Vec<(String, String)>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
 }
 
 impl Serial for AssertEntrySerial {
     type Output = AssertEntry;
 
-    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+    fn unserialize(self, settings: &Settings) -> Result<Self::Output, SlinkyError> {
         if self.check.is_empty() {
             return Err(SlinkyError::EmptyValue {
                 name: "check".to_string(),
@@ -50,6 +92,8 @@ impl Serial for AssertEntrySerial {
         }
         let error_message = self.error_message;
 
+        let warning = self.warning.get_non_null("warning", || false)?;
+
         let include_if_any = self
             .include_if_any
             .get_non_null_not_empty("include_if_any", Vec::new)?;
@@ -63,9 +107,17 @@ impl Serial for AssertEntrySerial {
             .exclude_if_all
             .get_non_null_not_empty("exclude_if_all", Vec::new)?;
 
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
         Ok(Self::Output {
             check,
             error_message,
+            warning,
             include_if_any,
             include_if_all,
             exclude_if_any,
@@ -0,0 +1,51 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+//! Generates many documents in a single process.
+//!
+//! Meant for repositories generating several related linker scripts, like
+//! the ones of the game, its tools and its test roms, which would otherwise
+//! run slinky once per script.
+
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    document::SourceFiles, generate_document, utils::parallel_map, Document, GeneratedOutputs,
+    GenerationMode, RuntimeSettings, SlinkyError,
+};
+
+/// Reads each document and generates everything listed on it, like
+/// [`generate`](crate::generate), returning the result of each job in the
+/// same order they were given.
+///
+/// Every document is parsed only once, even if it is listed multiple times
+/// with different runtime settings, and the files listed on `include` are
+/// only read once across all the documents.
+///
+/// The jobs are run in parallel, so jobs writing to the same files should not
+/// be part of the same batch.
+pub fn generate_all(
+    jobs: Vec<(PathBuf, RuntimeSettings)>,
+) -> Vec<Result<GeneratedOutputs, SlinkyError>> {
+    let sources = SourceFiles::default();
+
+    let mut paths: indexmap::IndexSet<&PathBuf> = indexmap::IndexSet::new();
+    for (path, _) in &jobs {
+        paths.insert(path);
+    }
+    let paths: Vec<&PathBuf> = paths.into_iter().collect();
+
+    let documents = parallel_map(&paths, |path| {
+        Document::read_file_with_sources(path, &sources).map(Arc::new)
+    });
+
+    parallel_map(&jobs, |(path, rs)| {
+        let index = paths
+            .iter()
+            .position(|x| *x == path)
+            .expect("Every path was read above");
+        let document = documents[index].clone()?;
+
+        generate_document(&document, rs, GenerationMode::for_document(&document))
+    })
+}
@@ -0,0 +1,135 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::Write;
+
+use crate::{Document, EscapedPath, FileInfo, FileKind, RuntimeSettings, SlinkyError};
+
+/// A `binary` file entry, which must be converted into an object before
+/// linking.
+pub(crate) struct BinaryRule {
+    pub blob: EscapedPath,
+    pub object: EscapedPath,
+    pub section: String,
+}
+
+/// Collects every `binary` file emitted for the given runtime settings.
+///
+/// The document is walked directly instead of relying on the generated
+/// script, since the binary files of partially linked segments are not part
+/// of the main script.
+pub(crate) fn collect_binary_rules(
+    d: &Document,
+    rs: &RuntimeSettings,
+) -> Result<Vec<BinaryRule>, SlinkyError> {
+    let mut rules = indexmap::IndexMap::new();
+    let base_path = d.settings.base_path_escaped(rs)?;
+
+    for segment in &d.segments {
+        if !rs.should_emit_entry(
+            &segment.exclude_if_any,
+            &segment.exclude_if_all,
+            &segment.include_if_any,
+            &segment.include_if_all,
+        ) {
+            continue;
+        }
+
+        let mut segment_path = base_path.clone();
+        segment_path.push(segment.dir_escaped(rs)?);
+
+        collect_files(&segment.files, &segment_path, rs, &mut rules)?;
+    }
+
+    Ok(rules.into_values().collect())
+}
+
+fn collect_files(
+    files: &[FileInfo],
+    base_path: &EscapedPath,
+    rs: &RuntimeSettings,
+    rules: &mut indexmap::IndexMap<EscapedPath, BinaryRule>,
+) -> Result<(), SlinkyError> {
+    for file in files {
+        if !rs.should_emit_entry(
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+            &file.include_if_any,
+            &file.include_if_all,
+        ) {
+            continue;
+        }
+
+        match file.kind {
+            FileKind::Binary => {
+                let mut blob = base_path.clone();
+                blob.push(file.path_escaped(rs)?);
+
+                let object = EscapedPath::from(format!("{}.o", blob));
+
+                // The same blob may be linked more than once
+                rules.entry(object.clone()).or_insert(BinaryRule {
+                    blob,
+                    object,
+                    section: file.sections[0].clone(),
+                });
+            }
+            FileKind::Group => {
+                let mut group_path = base_path.clone();
+                group_path.push(file.dir_escaped(rs)?);
+
+                collect_files(&file.files, &group_path, rs, rules)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a Makefile fragment with a rule converting each blob into the
+/// object expected by the linker script, plus a variable listing every one
+/// of those objects.
+///
+/// The rules use the `OBJCOPY` and `OBJCOPY_BINARY_FLAGS` variables, which
+/// are expected to be defined by the including Makefile.
+pub(crate) fn write_makefile_rules(
+    dst: &mut impl Write,
+    rules: &[BinaryRule],
+) -> Result<(), SlinkyError> {
+    let mut objects = "SLINKY_BINARY_OBJECTS :=".to_string();
+    for rule in rules {
+        objects += &format!(" \\\n    {}", rule.object);
+    }
+    write_line(dst, objects)?;
+
+    for rule in rules {
+        // objcopy always places the blob on `.data`
+        let rename = if rule.section == ".data" {
+            String::new()
+        } else {
+            format!(" --rename-section .data={}", rule.section)
+        };
+
+        write_line(
+            dst,
+            format!(
+                "\n{}: {}\n\t$(OBJCOPY) -I binary $(OBJCOPY_BINARY_FLAGS){} $< $@",
+                rule.object, rule.blob, rename
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_line(dst: &mut impl Write, line: String) -> Result<(), SlinkyError> {
+    if let Err(e) = writeln!(dst, "{}", line) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents: line,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,293 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use crate::{Document, EntryPath, EntryPathComponent, FileInfo, RuntimeSettings};
+
+/// The conditionals of a document evaluated over every configuration of it.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct CoverageReport {
+    /// How many configurations were evaluated.
+    pub configurations: usize,
+
+    /// Conditional entries which are not emitted on any configuration.
+    ///
+    /// The entries inside of them are not listed.
+    pub never_emitted: Vec<EntryPath>,
+
+    /// Conditional entries which are emitted on every configuration their
+    /// parent is emitted on, so their conditions have no effect.
+    pub always_emitted: Vec<EntryPath>,
+}
+
+impl CoverageReport {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.never_emitted.is_empty() && self.always_emitted.is_empty()
+    }
+}
+
+/// The conditions of an entry, in the same order used by
+/// [`RuntimeSettings::should_emit_entry`].
+type Conditions<'a> = [&'a [(String, String)]; 4];
+
+/// Every combination of custom options the document declares.
+///
+/// The `targets` are used if the document has any. Otherwise every
+/// combination of the `values` of the `runtime_options` is generated, skipping
+/// the options which accept any value. Each configuration extends the custom
+/// options of `base`.
+pub(crate) fn configurations(d: &Document, base: &RuntimeSettings) -> Vec<RuntimeSettings> {
+    if !d.targets.is_empty() {
+        return d
+            .targets
+            .iter()
+            .map(|target| target.runtime_settings(base))
+            .collect();
+    }
+
+    let mut all_rs = vec![base.clone()];
+
+    for option in &d.runtime_options {
+        if option.values.is_empty() {
+            continue;
+        }
+
+        all_rs = all_rs
+            .iter()
+            .flat_map(|rs| {
+                option.values.iter().map(move |value| {
+                    let mut rs = rs.clone();
+                    rs.add_custom_options([(option.name.clone(), value.clone())]);
+                    rs
+                })
+            })
+            .collect();
+    }
+
+    all_rs
+}
+
+pub(crate) fn analyze_coverage(d: &Document, base: &RuntimeSettings) -> CoverageReport {
+    let all_rs = configurations(d, base);
+    let all_indices: Vec<usize> = (0..all_rs.len()).collect();
+
+    let mut analyzer = CoverageAnalyzer {
+        all_rs: &all_rs,
+        report: CoverageReport {
+            configurations: all_rs.len(),
+            ..Default::default()
+        },
+    };
+
+    for (index, segment) in d.segments.iter().enumerate() {
+        let path = vec![EntryPathComponent::Segment {
+            index,
+            name: segment.name.clone(),
+        }];
+        let emitted = analyzer.check_entry(
+            &path,
+            &all_indices,
+            [
+                &segment.exclude_if_any,
+                &segment.exclude_if_all,
+                &segment.include_if_any,
+                &segment.include_if_all,
+            ],
+        );
+
+        if let Some(gp_info) = &segment.gp_info {
+            let mut gp_info_path = path.clone();
+            gp_info_path.push(EntryPathComponent::GpInfo);
+            analyzer.check_entry(
+                &gp_info_path,
+                &emitted,
+                [
+                    &gp_info.exclude_if_any,
+                    &gp_info.exclude_if_all,
+                    &gp_info.include_if_any,
+                    &gp_info.include_if_all,
+                ],
+            );
+        }
+
+        analyzer.check_files(&path, &emitted, &segment.files);
+    }
+
+    for (index, symbol_assignment) in d.symbol_assignments.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::SymbolAssignment {
+                index,
+                name: symbol_assignment.name.clone(),
+            }],
+            &all_indices,
+            [
+                &symbol_assignment.exclude_if_any,
+                &symbol_assignment.exclude_if_all,
+                &symbol_assignment.include_if_any,
+                &symbol_assignment.include_if_all,
+            ],
+        );
+    }
+
+    for (index, required_symbol) in d.required_symbols.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::RequiredSymbol {
+                index,
+                name: required_symbol.name.clone(),
+            }],
+            &all_indices,
+            [
+                &required_symbol.exclude_if_any,
+                &required_symbol.exclude_if_all,
+                &required_symbol.include_if_any,
+                &required_symbol.include_if_all,
+            ],
+        );
+    }
+
+    for (index, assert) in d.asserts.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::Assert { index }],
+            &all_indices,
+            [
+                &assert.exclude_if_any,
+                &assert.exclude_if_all,
+                &assert.include_if_any,
+                &assert.include_if_all,
+            ],
+        );
+    }
+
+    for (index, symbol_assignment) in d.pre_sections_symbols.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::PreSectionsSymbol {
+                index,
+                name: symbol_assignment.name.clone(),
+            }],
+            &all_indices,
+            [
+                &symbol_assignment.exclude_if_any,
+                &symbol_assignment.exclude_if_all,
+                &symbol_assignment.include_if_any,
+                &symbol_assignment.include_if_all,
+            ],
+        );
+    }
+
+    for (index, symbol_assignment) in d.post_sections_symbols.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::PostSectionsSymbol {
+                index,
+                name: symbol_assignment.name.clone(),
+            }],
+            &all_indices,
+            [
+                &symbol_assignment.exclude_if_any,
+                &symbol_assignment.exclude_if_all,
+                &symbol_assignment.include_if_any,
+                &symbol_assignment.include_if_all,
+            ],
+        );
+    }
+
+    for (index, assert) in d.post_sections_asserts.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::PostSectionsAssert { index }],
+            &all_indices,
+            [
+                &assert.exclude_if_any,
+                &assert.exclude_if_all,
+                &assert.include_if_any,
+                &assert.include_if_all,
+            ],
+        );
+    }
+
+    for (index, raw_block) in d.raw.iter().enumerate() {
+        analyzer.check_entry(
+            &[EntryPathComponent::RawBlock { index }],
+            &all_indices,
+            [
+                &raw_block.exclude_if_any,
+                &raw_block.exclude_if_all,
+                &raw_block.include_if_any,
+                &raw_block.include_if_all,
+            ],
+        );
+    }
+
+    analyzer.report
+}
+
+struct CoverageAnalyzer<'a> {
+    all_rs: &'a [RuntimeSettings],
+    report: CoverageReport,
+}
+
+impl CoverageAnalyzer<'_> {
+    /// Records the entry on the report if needed and returns the indices of
+    /// the configurations it is emitted on.
+    ///
+    /// `parent_emitted` are the indices of the configurations the parent of
+    /// this entry is emitted on.
+    fn check_entry(
+        &mut self,
+        path: &[EntryPathComponent],
+        parent_emitted: &[usize],
+        conditions: Conditions,
+    ) -> Vec<usize> {
+        if conditions.iter().all(|x| x.is_empty()) || parent_emitted.is_empty() {
+            return parent_emitted.to_vec();
+        }
+
+        let [exclude_if_any, exclude_if_all, include_if_any, include_if_all] = conditions;
+        let emitted: Vec<usize> = parent_emitted
+            .iter()
+            .copied()
+            .filter(|i| {
+                self.all_rs[*i].should_emit_entry(
+                    exclude_if_any,
+                    exclude_if_all,
+                    include_if_any,
+                    include_if_all,
+                )
+            })
+            .collect();
+
+        let entry = EntryPath {
+            components: path.to_vec(),
+        };
+        if emitted.is_empty() {
+            self.report.never_emitted.push(entry);
+        } else if emitted.len() == parent_emitted.len() {
+            self.report.always_emitted.push(entry);
+        }
+
+        emitted
+    }
+
+    fn check_files(
+        &mut self,
+        parent_path: &[EntryPathComponent],
+        parent_emitted: &[usize],
+        files: &[FileInfo],
+    ) {
+        for (index, file) in files.iter().enumerate() {
+            let mut path = parent_path.to_vec();
+            path.push(EntryPathComponent::File { index });
+
+            let emitted = self.check_entry(
+                &path,
+                parent_emitted,
+                [
+                    &file.exclude_if_any,
+                    &file.exclude_if_all,
+                    &file.include_if_any,
+                    &file.include_if_all,
+                ],
+            );
+
+            self.check_files(&path, &emitted, &file.files);
+        }
+    }
+}
@@ -0,0 +1,406 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::fmt;
+
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+
+use crate::SlinkyError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A position on the yaml source of a document. Both values start at 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Identifies a single entry of the document, relative to its parent entry.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EntryPathComponent {
+    Settings,
+    VramClass {
+        index: usize,
+        name: String,
+    },
+    Segment {
+        index: usize,
+        name: String,
+    },
+    SegmentTemplate {
+        index: usize,
+        name: String,
+    },
+    GpInfo,
+    /// A file of a segment or of a group.
+    File {
+        index: usize,
+    },
+    SymbolAssignment {
+        index: usize,
+        name: String,
+    },
+    RequiredSymbol {
+        index: usize,
+        name: String,
+    },
+    Assert {
+        index: usize,
+    },
+    PreSectionsSymbol {
+        index: usize,
+        name: String,
+    },
+    PostSectionsSymbol {
+        index: usize,
+        name: String,
+    },
+    PostSectionsAssert {
+        index: usize,
+    },
+    RuntimeOption {
+        index: usize,
+        name: String,
+    },
+    Target {
+        index: usize,
+        name: String,
+    },
+    Import {
+        index: usize,
+    },
+    RawBlock {
+        index: usize,
+    },
+}
+
+impl EntryPathComponent {
+    fn yaml_path(&self) -> (&'static str, Option<usize>) {
+        match self {
+            EntryPathComponent::Settings => ("settings", None),
+            EntryPathComponent::VramClass { index, .. } => ("vram_classes", Some(*index)),
+            EntryPathComponent::Segment { index, .. } => ("segments", Some(*index)),
+            EntryPathComponent::SegmentTemplate { index, .. } => {
+                ("segment_templates", Some(*index))
+            }
+            EntryPathComponent::GpInfo => ("gp_info", None),
+            EntryPathComponent::File { index } => ("files", Some(*index)),
+            EntryPathComponent::SymbolAssignment { index, .. } => {
+                ("symbol_assignments", Some(*index))
+            }
+            EntryPathComponent::RequiredSymbol { index, .. } => ("required_symbols", Some(*index)),
+            EntryPathComponent::Assert { index } => ("asserts", Some(*index)),
+            EntryPathComponent::PreSectionsSymbol { index, .. } => {
+                ("pre_sections_symbols", Some(*index))
+            }
+            EntryPathComponent::PostSectionsSymbol { index, .. } => {
+                ("post_sections_symbols", Some(*index))
+            }
+            EntryPathComponent::PostSectionsAssert { index } => {
+                ("post_sections_asserts", Some(*index))
+            }
+            EntryPathComponent::RuntimeOption { index, .. } => ("runtime_options", Some(*index)),
+            EntryPathComponent::Target { index, .. } => ("targets", Some(*index)),
+            EntryPathComponent::Import { index } => ("imports", Some(*index)),
+            EntryPathComponent::RawBlock { index } => ("raw", Some(*index)),
+        }
+    }
+}
+
+impl fmt::Display for EntryPathComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryPathComponent::Settings => write!(f, "settings"),
+            EntryPathComponent::VramClass { name, .. } => write!(f, "vram class '{}'", name),
+            EntryPathComponent::Segment { name, .. } => write!(f, "segment '{}'", name),
+            EntryPathComponent::SegmentTemplate { name, .. } => {
+                write!(f, "segment template '{}'", name)
+            }
+            EntryPathComponent::GpInfo => write!(f, "gp_info"),
+            EntryPathComponent::File { index } => write!(f, "file index {}", index),
+            EntryPathComponent::SymbolAssignment { name, .. } => {
+                write!(f, "symbol assignment '{}'", name)
+            }
+            EntryPathComponent::RequiredSymbol { name, .. } => {
+                write!(f, "required symbol '{}'", name)
+            }
+            EntryPathComponent::Assert { index } => write!(f, "assert index {}", index),
+            EntryPathComponent::PreSectionsSymbol { name, .. } => {
+                write!(f, "pre sections symbol '{}'", name)
+            }
+            EntryPathComponent::PostSectionsSymbol { name, .. } => {
+                write!(f, "post sections symbol '{}'", name)
+            }
+            EntryPathComponent::PostSectionsAssert { index } => {
+                write!(f, "post sections assert index {}", index)
+            }
+            EntryPathComponent::RuntimeOption { name, .. } => {
+                write!(f, "runtime option '{}'", name)
+            }
+            EntryPathComponent::Target { name, .. } => write!(f, "target '{}'", name),
+            EntryPathComponent::Import { index } => write!(f, "import index {}", index),
+            EntryPathComponent::RawBlock { index } => write!(f, "raw block index {}", index),
+        }
+    }
+}
+
+/// The chain of entries leading to a specific entry of the document, starting
+/// from the top-level one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct EntryPath {
+    pub components: Vec<EntryPathComponent>,
+}
+
+impl EntryPath {
+    /// The path of the entry on the yaml of the document, like
+    /// `segments[2].files[0]`.
+    #[must_use]
+    pub fn yaml_path(&self) -> String {
+        let mut path = String::new();
+
+        for component in &self.components {
+            let (key, index) = component.yaml_path();
+
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path += key;
+            if let Some(index) = index {
+                path += &format!("[{}]", index);
+            }
+        }
+
+        path
+    }
+}
+
+impl fmt::Display for EntryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, component) in self.components.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", component)?;
+        }
+        Ok(())
+    }
+}
+
+/// A structured report of a problem found on a document, meant to be rendered
+/// by tools.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// The underlying error, without the entry information.
+    pub error: Box<SlinkyError>,
+
+    /// The entry where the problem was found, if known.
+    pub entry: Option<EntryPath>,
+    /// The field of the entry where the problem was found, if known.
+    pub field: Option<String>,
+
+    /// The position of the problem on the yaml source, if known.
+    pub location: Option<SourceLocation>,
+}
+
+impl Diagnostic {
+    /// Builds a diagnostic from the given error, without any source location.
+    pub fn from_error(error: SlinkyError) -> Self {
+        let (entry, error) = match error {
+            SlinkyError::InvalidEntry { entry, source } => (Some(entry), source),
+            error => (None, Box::new(error)),
+        };
+        let field = error.field_name().map(|x| x.to_string());
+
+        Self {
+            severity: Severity::Error,
+            error,
+            entry,
+            field,
+            location: None,
+        }
+    }
+
+    /// Builds a diagnostic from the given error, looking up the location of
+    /// the offending entry and field on the yaml `contents` of the document.
+    pub fn from_error_in_source(error: SlinkyError, contents: &str) -> Self {
+        let mut diagnostic = Self::from_error(error);
+
+        let mut path = Vec::new();
+        if let Some(entry) = &diagnostic.entry {
+            for component in &entry.components {
+                let (key, index) = component.yaml_path();
+
+                path.push(YamlPathComponent::Key(key));
+                if let Some(index) = index {
+                    path.push(YamlPathComponent::Index(index));
+                }
+            }
+        }
+        if let Some(field) = &diagnostic.field {
+            path.push(YamlPathComponent::Key(field));
+        }
+
+        diagnostic.location = locate(contents, &path);
+        diagnostic
+    }
+
+    /// Like [`from_error`](Self::from_error), but with a
+    /// [`Severity::Warning`] severity.
+    pub fn warning_from_error(error: SlinkyError) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::from_error(error)
+        }
+    }
+
+    pub(crate) fn from_yaml_error(error: serde_yaml::Error) -> Self {
+        let location = error.location().map(|x| SourceLocation {
+            line: x.line(),
+            column: x.column(),
+        });
+
+        Self {
+            severity: Severity::Error,
+            error: Box::new(SlinkyError::FailedYamlParsing {
+                description: error.to_string(),
+            }),
+            entry: None,
+            field: None,
+            location,
+        }
+    }
+}
+
+impl From<SlinkyError> for Diagnostic {
+    fn from(error: SlinkyError) -> Self {
+        Self::from_error(error)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}: ", self.severity)?;
+
+        match (&self.entry, &self.field) {
+            (Some(entry), Some(field)) => write!(f, "{}, field '{}': ", entry, field)?,
+            (Some(entry), None) => write!(f, "{}: ", entry)?,
+            (None, Some(field)) => write!(f, "field '{}': ", field)?,
+            (None, None) => {}
+        }
+
+        write!(f, "{}", self.error)
+    }
+}
+
+enum YamlPathComponent<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Finds the location of the node pointed by `path`, or the deepest node of
+/// `path` that is present on the yaml.
+///
+/// serde_yaml does not expose the position of the deserialized values, but it
+/// does attach the position of the current node to any error raised while
+/// deserializing it. So we walk the yaml and raise an error once we reached
+/// the node we are looking for.
+fn locate(contents: &str, path: &[YamlPathComponent]) -> Option<SourceLocation> {
+    let deserializer = serde_yaml::Deserializer::from_str(contents);
+
+    match (Locator { path }).deserialize(deserializer) {
+        Ok(()) => None,
+        Err(e) => e.location().map(|x| SourceLocation {
+            line: x.line(),
+            column: x.column(),
+        }),
+    }
+}
+
+struct Locator<'a, 'b> {
+    path: &'b [YamlPathComponent<'a>],
+}
+
+impl<'de> DeserializeSeed<'de> for Locator<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+// Any other kind of node is rejected by the default implementations of the
+// `Visitor` trait, which also gives us its location.
+impl<'de> Visitor<'de> for Locator<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the located node")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        if let Some((YamlPathComponent::Key(key), rest)) = self.path.split_first() {
+            while let Some(k) = map.next_key::<serde_yaml::Value>()? {
+                if k.as_str() == Some(key) {
+                    return map.next_value_seed(Locator { path: rest });
+                }
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Err(de::Error::custom("located"))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        if let Some((YamlPathComponent::Index(index), rest)) = self.path.split_first() {
+            let mut i = 0;
+            while i < *index {
+                if seq.next_element::<IgnoredAny>()?.is_none() {
+                    return Err(de::Error::custom("located"));
+                }
+                i += 1;
+            }
+            if let Some(()) = seq.next_element_seed(Locator { path: rest })? {
+                return Ok(());
+            }
+        }
+
+        Err(de::Error::custom("located"))
+    }
+}
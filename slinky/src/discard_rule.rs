@@ -0,0 +1,67 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, FileKind, SlinkyError,
+};
+
+/// Sections of a specific file which are placed on the `/DISCARD/` block.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize)]
+pub struct DiscardRule {
+    /// The file pattern, used verbatim on the linker script.
+    pub file: String,
+    pub sections: Vec<String>,
+}
+
+impl DiscardRule {
+    /// The input section description discarding the sections of this file.
+    ///
+    /// Archives use the `archive:` syntax, so the sections of every member of
+    /// the archive are matched.
+    #[must_use]
+    pub fn input_section_description(&self) -> String {
+        let separator = match FileKind::from_path(Path::new(&self.file)) {
+            FileKind::Archive => ":",
+            _ => "",
+        };
+
+        format!("{}{}({});", self.file, separator, self.sections.join(" "))
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DiscardRuleSerial {
+    pub file: String,
+    #[serde(default)]
+    pub sections: AbsentNullable<Vec<String>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl DiscardRuleSerial {
+    pub fn unserialize(self) -> Result<DiscardRule, SlinkyError> {
+        if self.file.is_empty() {
+            return Err(SlinkyError::EmptyValue {
+                name: "file".to_string(),
+            });
+        }
+
+        let sections = self.sections.get("sections")?;
+        if sections.is_empty() || sections.iter().any(|x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "sections".to_string(),
+            });
+        }
+
+        Ok(DiscardRule {
+            file: self.file,
+            sections,
+        })
+    }
+}
@@ -1,36 +1,252 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use std::{fs, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    absent_nullable::AbsentNullable, assert_entry::AssertEntrySerial,
-    required_symbol::RequiredSymbolSerial, segment::SegmentSerial, settings::SettingsSerial,
-    symbol_assignment::SymbolAssignmentSerial, traits::Serial, vram_class::VramClassSerial,
-    AssertEntry, KeepSections, RequiredSymbol, Segment, Settings, SlinkyError, SymbolAssignment,
-    VramClass,
+    absent_nullable::AbsentNullable,
+    assert_entry::AssertEntrySerial,
+    coverage, explain, fingerprint, formatter,
+    ignored_metadata::IgnoredMetadata,
+    import::ImportSerial,
+    ld_script_importer,
+    raw_block::RawBlockSerial,
+    required_symbol::RequiredSymbolSerial,
+    resolved_document,
+    runtime_option::RuntimeOptionSerial,
+    segment,
+    segment::SegmentSerial,
+    settings::SettingsSerial,
+    symbol_assignment::SymbolAssignmentSerial,
+    target::TargetSerial,
+    toolchain_profile::ToolchainProfile,
+    traits::{unserialize_entries, Serial},
+    utils, validation, version,
+    vram_class::VramClassSerial,
+    yaml_writer, AssertEntry, CoverageReport, Diagnostic, EntryPathComponent, EscapedPath,
+    FileInfo, FileKind, FormattedYaml, Import, ImportedLinkerScript, KeepSections, RawBlock,
+    RequiredSymbol, RuntimeOption, RuntimeSettings, Segment, SegmentExplanation, Settings,
+    SlinkyError, SymbolAssignment, Target, Version, VramClass,
 };
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct Document {
+    /// The oldest slinky version able to handle this document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_slinky_version: Option<Version>,
+
     pub settings: Settings,
 
     pub vram_classes: Vec<VramClass>,
 
     pub segments: Vec<Segment>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_inputs: Vec<PathBuf>,
     pub symbol_assignments: Vec<SymbolAssignment>,
     pub required_symbols: Vec<RequiredSymbol>,
     pub asserts: Vec<AssertEntry>,
+
+    /// Assignments written right before the `SECTIONS` block, so they can't
+    /// depend on the placement of the segments.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pre_sections_symbols: Vec<SymbolAssignment>,
+    /// Assignments written right after the `SECTIONS` block, before the
+    /// `symbol_assignments`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_sections_symbols: Vec<SymbolAssignment>,
+    /// Asserts written right after the `post_sections_symbols`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_sections_asserts: Vec<AssertEntry>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub runtime_options: Vec<RuntimeOption>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<Target>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<Import>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub raw: Vec<RawBlock>,
+
+    /// Every document merged into this one through `include`, recursively.
+    ///
+    /// The entries of the included documents are already part of this one,
+    /// this list is only kept to track them as dependencies.
+    #[serde(skip)]
+    pub included_files: Vec<PathBuf>,
+
+    /// The file this document was read from, if it was read from a file.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 impl Document {
     pub fn read_file(path: &Path) -> Result<Self, SlinkyError> {
-        let f = match fs::File::open(path) {
-            Ok(f) => f,
+        Self::read_file_with_sources(path, &SourceFiles::default())
+    }
+
+    /// Like [`Document::read_file`], but the documents listed on `include`
+    /// are read through `sources`, so they can be shared with other
+    /// documents.
+    pub(crate) fn read_file_with_sources(
+        path: &Path,
+        sources: &SourceFiles,
+    ) -> Result<Self, SlinkyError> {
+        let document_serial = DocumentSerial::read_file(path)?;
+
+        let mut included = IncludedFiles::new(Some(path)).with_sources(sources);
+        document_serial
+            .merge_includes(&include_dir(path), &mut included)?
+            .unserialize()
+            .map(|d| included.attach_to(d))
+    }
+
+    /// Parses a document from a yaml string, like [`Document::read_file`].
+    ///
+    /// The paths listed on `include` are relative to the current directory.
+    pub fn from_yaml_str(contents: &str) -> Result<Self, SlinkyError> {
+        let document_serial = DocumentSerial::from_yaml_str(contents)?;
+
+        let mut included = IncludedFiles::new(None);
+        document_serial
+            .merge_includes(Path::new(""), &mut included)?
+            .unserialize()
+            .map(|d| included.attach_to(d))
+    }
+
+    /// Same as [`Document::from_yaml_str`], named after the other `read`
+    /// functions.
+    pub fn read_from_str(contents: &str) -> Result<Self, SlinkyError> {
+        Self::from_yaml_str(contents)
+    }
+
+    /// Reads a document from anything implementing [`Read`], like stdin.
+    ///
+    /// The paths listed on `include` are relative to the current directory.
+    pub fn read_from_reader(mut reader: impl Read) -> Result<Self, SlinkyError> {
+        let mut contents = String::new();
+
+        if let Err(e) = reader.read_to_string(&mut contents) {
+            return Err(SlinkyError::FailedRead {
+                description: e.to_string(),
+            });
+        }
+
+        Self::from_yaml_str(&contents)
+    }
+
+    /// Like [`Document::read_file`], but returns a [`Diagnostic`] pointing to
+    /// the location of the problem on the yaml file.
+    pub fn read_file_with_diagnostics(path: &Path) -> Result<Self, Diagnostic> {
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                return Err(Diagnostic::from_error(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                }))
+            }
+        };
+
+        let document_serial: DocumentSerial = match serde_yaml::from_str(&contents) {
+            Ok(d) => d,
+            Err(e) => {
+                version::check_unparsed_document(&contents).map_err(Diagnostic::from_error)?;
+                return Err(Diagnostic::from_yaml_error(e));
+            }
+        };
+
+        let mut included = IncludedFiles::new(Some(path));
+        let document_serial = document_serial
+            .merge_includes(&include_dir(path), &mut included)
+            .map_err(Diagnostic::from_error)?;
+
+        document_serial
+            .unserialize()
+            .map(|d| included.attach_to(d))
+            .map_err(|e| Diagnostic::from_error_in_source(e, &contents))
+    }
+
+    /// Like [`Document::from_yaml_str`], but returns a [`Diagnostic`] pointing
+    /// to the location of the problem on the yaml string.
+    pub fn from_yaml_str_with_diagnostics(contents: &str) -> Result<Self, Diagnostic> {
+        let document_serial: DocumentSerial = match serde_yaml::from_str(contents) {
+            Ok(d) => d,
+            Err(e) => {
+                version::check_unparsed_document(contents).map_err(Diagnostic::from_error)?;
+                return Err(Diagnostic::from_yaml_error(e));
+            }
+        };
+
+        let mut included = IncludedFiles::new(None);
+        let document_serial = document_serial
+            .merge_includes(Path::new(""), &mut included)
+            .map_err(Diagnostic::from_error)?;
+
+        document_serial
+            .unserialize()
+            .map(|d| included.attach_to(d))
+            .map_err(|e| Diagnostic::from_error_in_source(e, contents))
+    }
+
+    /// Rewrites the yaml of a document in a canonical key order and style.
+    ///
+    /// Comments are carried over to the formatted yaml when the line they
+    /// were attached to is still present, otherwise they are reported on
+    /// [`FormattedYaml::dropped_comments`].
+    ///
+    /// The documents listed on `include` are not read.
+    pub fn format_yaml_str(contents: &str) -> Result<FormattedYaml, SlinkyError> {
+        formatter::format_yaml_str(contents)
+    }
+
+    /// Writes the document as canonical yaml, the same way
+    /// [`Document::format_yaml_str`] would, omitting every value which is the
+    /// same as its default.
+    ///
+    /// Useful to turn a document built programmatically, or imported from a
+    /// splat yaml, into one maintained by hand. The files of a `files_from`
+    /// list and the entries of the `include`d documents are written inline.
+    pub fn write_yaml(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let contents = self.write_yaml_to_string()?;
+
+        if let Err(e) = dst.write_all(contents.as_bytes()) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn write_yaml_to_string(&self) -> Result<String, SlinkyError> {
+        yaml_writer::write_yaml(self)
+    }
+
+    /// Builds a best-effort document from the `SECTIONS` block of an existing
+    /// GNU LD linker script.
+    ///
+    /// Constructs that can't be represented on a document are skipped and
+    /// reported on [`ImportedLinkerScript::unsupported`].
+    pub fn from_linker_script(path: &Path) -> Result<ImportedLinkerScript, SlinkyError> {
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => contents,
             Err(e) => {
                 return Err(SlinkyError::FailedFileOpen {
                     path: path.to_path_buf(),
@@ -38,45 +254,773 @@ impl Document {
                 })
             }
         };
-        let document_serial: DocumentSerial = match serde_yaml::from_reader(f) {
-            Ok(d) => d,
+
+        Self::from_linker_script_str(&contents)
+    }
+
+    /// Like [`Document::from_linker_script`], but reading the linker script
+    /// from a string.
+    pub fn from_linker_script_str(contents: &str) -> Result<ImportedLinkerScript, SlinkyError> {
+        ld_script_importer::import_linker_script(contents)
+    }
+
+    /// Converts a splat yaml into a document, mapping each splat segment into
+    /// a [`Segment`] and each one of its subsegments into a [`FileInfo`].
+    ///
+    /// [`FileInfo`]: crate::FileInfo
+    #[cfg(feature = "splat")]
+    pub fn from_splat_yaml(path: &Path) -> Result<Self, SlinkyError> {
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                })
+            }
+        };
+
+        Self::from_splat_yaml_str(&contents)
+    }
+
+    /// Like [`Document::from_splat_yaml`], but reading the splat yaml from a
+    /// string.
+    #[cfg(feature = "splat")]
+    pub fn from_splat_yaml_str(contents: &str) -> Result<Self, SlinkyError> {
+        crate::splat_importer::import_splat_yaml(contents)
+    }
+
+    /// Performs semantic checks which can't be done while parsing the
+    /// document, like duplicated segments, cycles on the `follows_classes` of
+    /// vram classes or references to undefined segments.
+    ///
+    /// Returns every problem found instead of stopping at the first one. An
+    /// empty list means the document is valid.
+    pub fn validate(&self) -> Vec<SlinkyError> {
+        validation::validate_document(self)
+    }
+
+    /// Looks for suspicious constructs which are legal but likely a mistake,
+    /// like segments without files after evaluating the conditionals of the
+    /// runtime settings or sections referenced by `section_order` which are
+    /// discarded by `sections_denylist`.
+    ///
+    /// Every returned diagnostic has a [`Severity::Warning`] severity.
+    ///
+    /// [`Severity::Warning`]: crate::Severity::Warning
+    pub fn warnings(&self, rs: &RuntimeSettings) -> Vec<Diagnostic> {
+        validation::collect_warnings(self, rs)
+            .into_iter()
+            .map(Diagnostic::warning_from_error)
+            .collect()
+    }
+
+    /// Returns the runtime settings of every configuration declared by the
+    /// document, extending the custom options of `base`.
+    ///
+    /// These are the `targets` if the document has any, otherwise every
+    /// combination of the `values` of the `runtime_options`.
+    pub fn configurations(&self, base: &RuntimeSettings) -> Vec<RuntimeSettings> {
+        coverage::configurations(self, base)
+    }
+
+    /// Evaluates the conditionals of every entry over all the
+    /// [configurations](Self::configurations) of the document, looking for
+    /// entries which are never emitted or whose conditions have no effect.
+    pub fn conditional_coverage(&self, base: &RuntimeSettings) -> CoverageReport {
+        coverage::analyze_coverage(self, base)
+    }
+
+    /// Explains how the given segment is placed for the given runtime
+    /// settings: why it is emitted or not, which chain of segments or vram
+    /// classes determines its address, and which files and sections end up
+    /// on it.
+    pub fn explain_segment(
+        &self,
+        name: &str,
+        rs: &RuntimeSettings,
+    ) -> Result<SegmentExplanation, SlinkyError> {
+        explain::explain_segment(self, rs, name)
+    }
+
+    /// Returns the document as slinky will act on it for the given runtime
+    /// settings.
+    ///
+    /// Entries excluded by the runtime settings are dropped, custom options
+    /// are replaced on every path and the conditions of the remaining entries
+    /// are cleared.
+    pub fn effective(&self, rs: &RuntimeSettings) -> Result<Self, SlinkyError> {
+        resolved_document::resolve_document(self, rs)
+    }
+
+    /// Returns the segments emitted for the given runtime settings, like the
+    /// ones of the [effective](Self::effective) document.
+    ///
+    /// Only the files emitted for the runtime settings are kept, including
+    /// the ones inside groups, and the custom options are replaced on every
+    /// path. Paths are still relative to the `base_path` and the `dir` of the
+    /// segment and its groups.
+    pub fn resolved_segments(&self, rs: &RuntimeSettings) -> Result<Vec<Segment>, SlinkyError> {
+        resolved_document::resolve_segments(self, rs)
+    }
+
+    /// Returns the path of the `startup_file`, prefixed by the `base_path` and
+    /// with its custom options replaced.
+    pub fn startup_file_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.startup_file {
+            Some(startup_file) => {
+                let mut path = self.settings.base_path_escaped(rs)?;
+                path.push(rs.escape_path(startup_file)?);
+                Ok(Some(path))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the paths of the `extra_inputs`, prefixed by the `base_path`
+    /// and with their custom options replaced.
+    pub fn extra_inputs_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Vec<EscapedPath>, SlinkyError> {
+        let base_path = self.settings.base_path_escaped(rs)?;
+
+        self.extra_inputs
+            .iter()
+            .map(|x| {
+                let mut path = base_path.clone();
+                path.push(rs.escape_path(x)?);
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Returns the segment with the given name.
+    #[must_use]
+    pub fn segment(&self, name: &str) -> Option<&Segment> {
+        self.segments.iter().find(|x| x.name == name)
+    }
+
+    /// Returns the segment with the given name, to be tweaked in place.
+    ///
+    /// Nothing is re-checked after the segment is modified, prefer the other
+    /// mutators of the document when possible.
+    pub fn segment_mut(&mut self, name: &str) -> Option<&mut Segment> {
+        self.segments.iter_mut().find(|x| x.name == name)
+    }
+
+    /// Appends a new segment to the document.
+    ///
+    /// The segment is placed and checked the same way as the ones read from
+    /// the yaml. If any check fails the document is left untouched.
+    pub fn add_segment(&mut self, segment: Segment) -> Result<(), SlinkyError> {
+        self.insert_segment(self.segments.len(), segment)
+    }
+
+    /// Like [`Document::add_segment`], but the segment is inserted at the
+    /// given index instead.
+    ///
+    /// The index is clamped to the amount of segments of the document.
+    pub fn insert_segment(
+        &mut self,
+        index: usize,
+        mut segment: Segment,
+    ) -> Result<(), SlinkyError> {
+        if self.segment(&segment.name).is_some() {
+            return Err(SlinkyError::DuplicatedSegment {
+                segment: Cow::from(segment.name),
+            });
+        }
+
+        if let Some(vram_class_name) = &segment.vram_class {
+            if let Some(vram_class) = self
+                .vram_classes
+                .iter()
+                .find(|x| x.name == *vram_class_name)
+            {
+                segment.pass_down_keep_sections(&vram_class.keep_sections);
+            }
+        }
+
+        let index = index.min(self.segments.len());
+        self.mutate(|d| d.segments.insert(index, segment))
+    }
+
+    /// Removes the segment with the given name from the document and
+    /// returns it.
+    ///
+    /// Fails if any other segment references it through `follows_segment` or
+    /// `noload_merge`, or if any expression of the document uses its
+    /// symbols.
+    pub fn remove_segment(&mut self, name: &str) -> Result<Segment, SlinkyError> {
+        let index = match self.segments.iter().position(|x| x.name == name) {
+            Some(index) => index,
+            None => {
+                return Err(SlinkyError::MissingSegment {
+                    segment: name.to_string(),
+                })
+            }
+        };
+
+        for segment in &self.segments {
+            if segment.follows_segment.as_deref() == Some(name) {
+                return Err(SlinkyError::MissingFollowedSegment {
+                    segment: Cow::from(segment.name.clone()),
+                    follows_segment: Cow::from(name.to_string()),
+                });
+            }
+            if segment.noload_merge.as_deref() == Some(name) {
+                return Err(SlinkyError::MissingNoloadMergeSegment {
+                    segment: Cow::from(segment.name.clone()),
+                    noload_merge: Cow::from(name.to_string()),
+                });
+            }
+        }
+
+        self.mutate(|d| d.segments.remove(index))
+    }
+
+    /// Appends a file to the segment with the given name.
+    ///
+    /// The `keep_sections` of the segment is passed down to the file, like
+    /// it is done for the files read from the yaml.
+    pub fn add_file_to_segment(
+        &mut self,
+        segment_name: &str,
+        mut file: FileInfo,
+    ) -> Result<(), SlinkyError> {
+        let segment = match self.segment_mut(segment_name) {
+            Some(segment) => segment,
+            None => {
+                return Err(SlinkyError::MissingSegment {
+                    segment: segment_name.to_string(),
+                })
+            }
+        };
+
+        if segment.external {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "external".to_string(),
+                field2: "files".to_string(),
+            });
+        }
+
+        file.pass_down_keep_sections(&segment.keep_sections);
+        segment.files.push(file);
+
+        Ok(())
+    }
+
+    /// Modifies the settings of the document.
+    ///
+    /// The segments are re-sorted and the expressions of the document are
+    /// re-checked afterwards. If any check fails the previous settings are
+    /// restored.
+    ///
+    /// The values each segment inherited from the settings when the document
+    /// was read are not updated, use [`Document::segment_mut`] to change
+    /// those.
+    pub fn update_settings(&mut self, f: impl FnOnce(&mut Settings)) -> Result<(), SlinkyError> {
+        self.mutate(|d| f(&mut d.settings))
+    }
+
+    /// Applies `f` and redoes the ordering and checks performed while reading
+    /// the document, restoring the previous state if any of them fails.
+    fn mutate<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> Result<T, SlinkyError> {
+        let previous = self.clone();
+        let result = f(self);
+
+        match self
+            .reorder_segments()
+            .and_then(|()| self.check_expressions())
+        {
+            Ok(()) => Ok(result),
+            Err(e) => {
+                *self = previous;
+                Err(e)
+            }
+        }
+    }
+
+    fn reorder_segments(&mut self) -> Result<(), SlinkyError> {
+        if self.settings.sort_segments_by_follows {
+            self.segments = validation::sort_segments_by_dependencies(
+                std::mem::take(&mut self.segments),
+                &self.settings.linker_symbols_style,
+            )?;
+        } else {
+            validation::check_rom_order(&self.segments)?;
+        }
+        Ok(())
+    }
+
+    /// Only checked, since they are resolved while generating.
+    fn check_expressions(&self) -> Result<(), SlinkyError> {
+        for (index, segment) in self.segments.iter().enumerate() {
+            segment.resolved_fixed_symbol(self).map_err(|e| {
+                e.in_entry(EntryPathComponent::Segment {
+                    index,
+                    name: segment.name.clone(),
+                })
+            })?;
+        }
+        for (index, symbol_assignment) in self.symbol_assignments.iter().enumerate() {
+            symbol_assignment.resolved_value(self).map_err(|e| {
+                e.in_entry(EntryPathComponent::SymbolAssignment {
+                    index,
+                    name: symbol_assignment.name.clone(),
+                })
+            })?;
+        }
+        for (index, assert_entry) in self.asserts.iter().enumerate() {
+            assert_entry
+                .resolved_check(self)
+                .map_err(|e| e.in_entry(EntryPathComponent::Assert { index }))?;
+        }
+        for (index, symbol_assignment) in self.post_sections_symbols.iter().enumerate() {
+            symbol_assignment.resolved_value(self).map_err(|e| {
+                e.in_entry(EntryPathComponent::PostSectionsSymbol {
+                    index,
+                    name: symbol_assignment.name.clone(),
+                })
+            })?;
+        }
+        for (index, assert_entry) in self.post_sections_asserts.iter().enumerate() {
+            assert_entry
+                .resolved_check(self)
+                .map_err(|e| e.in_entry(EntryPathComponent::PostSectionsAssert { index }))?;
+        }
+
+        Ok(())
+    }
+
+    /// The segments assigned to the given vram class, in the order they are
+    /// listed.
+    pub fn segments_in_vram_class<'a>(
+        &'a self,
+        vram_class: &'a str,
+    ) -> impl Iterator<Item = &'a Segment> + 'a {
+        self.segments
+            .iter()
+            .filter(move |x| x.vram_class.as_deref() == Some(vram_class))
+    }
+
+    /// Every file of every segment, including the ones nested inside groups
+    /// and archives, in the order they are listed.
+    pub fn files(&self) -> impl Iterator<Item = &FileInfo> {
+        NestedFiles {
+            stack: self.segments.iter().map(|x| x.files.iter()).rev().collect(),
+        }
+    }
+
+    /// The lists the files of the segments were read from, through
+    /// `files_from`.
+    pub fn files_lists(&self) -> impl Iterator<Item = &PathBuf> {
+        self.segments.iter().filter_map(|x| x.files_from.as_ref())
+    }
+
+    /// Like [`Document::files`], but only the files of the given kind.
+    pub fn files_of_kind(&self, kind: FileKind) -> impl Iterator<Item = &FileInfo> {
+        self.files().filter(move |x| x.kind == kind)
+    }
+
+    /// Returns the target with the given name, listed on `targets`.
+    pub fn target(&self, name: &str) -> Result<&Target, SlinkyError> {
+        match self.targets.iter().find(|x| x.name == name) {
+            Some(target) => Ok(target),
+            None => Err(SlinkyError::MissingTarget {
+                target: name.to_string(),
+            }),
+        }
+    }
+
+    /// A hash of the [effective](Self::effective) document, the custom options
+    /// and the version of slinky, which changes whenever the generated files
+    /// could change.
+    ///
+    /// Embedded on the generated files if
+    /// [`emit_fingerprint`](RuntimeSettings::emit_fingerprint) is enabled.
+    pub fn fingerprint(&self, rs: &RuntimeSettings) -> Result<String, SlinkyError> {
+        fingerprint::fingerprint(self, rs)
+    }
+
+    /// Serializes the [effective](Self::effective) document as yaml, including
+    /// every default value.
+    pub fn effective_yaml(&self, rs: &RuntimeSettings) -> Result<String, SlinkyError> {
+        match serde_yaml::to_string(&self.effective(rs)?) {
+            Ok(yaml) => Ok(yaml),
+            Err(e) => Err(SlinkyError::FailedYamlSerialization {
+                description: e.to_string(),
+            }),
+        }
+    }
+}
+
+/// The directory the paths of the `include` of a document are relative to.
+fn include_dir(path: &Path) -> PathBuf {
+    path.parent().map_or_else(PathBuf::new, Path::to_path_buf)
+}
+
+/// Depth-first iterator over a list of files and the ones nested on them.
+struct NestedFiles<'a> {
+    stack: Vec<std::slice::Iter<'a, FileInfo>>,
+}
+
+impl<'a> Iterator for NestedFiles<'a> {
+    type Item = &'a FileInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(file) => {
+                    self.stack.push(file.files.iter());
+                    return Some(file);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// The contents of the documents read through `include`, keyed by their
+/// canonical path, so documents including the same files only read them once.
+#[derive(Default)]
+pub(crate) struct SourceFiles {
+    contents: Mutex<HashMap<PathBuf, Arc<String>>>,
+}
+
+impl SourceFiles {
+    fn read(&self, path: &Path) -> Result<Arc<String>, SlinkyError> {
+        let key = canonical_path(path);
+
+        if let Some(contents) = self.lock().get(&key) {
+            return Ok(contents.clone());
+        }
+
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => Arc::new(contents),
             Err(e) => {
-                return Err(SlinkyError::FailedYamlParsing {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
                     description: e.to_string(),
                 })
             }
         };
+        self.lock().insert(key, contents.clone());
 
-        document_serial.unserialize()
+        Ok(contents)
     }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Arc<String>>> {
+        // The map is always left in a consistent state
+        self.contents
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Keeps track of the documents merged through `include`, rejecting the ones
+/// included more than once, which includes cycles.
+struct IncludedFiles<'a> {
+    root: Option<PathBuf>,
+    seen: HashSet<PathBuf>,
+    paths: Vec<PathBuf>,
+    sources: Option<&'a SourceFiles>,
 }
 
-#[derive(Deserialize, PartialEq, Debug)]
+impl<'a> IncludedFiles<'a> {
+    fn new(root: Option<&Path>) -> Self {
+        Self {
+            root: root.map(Path::to_path_buf),
+            seen: root.into_iter().map(canonical_path).collect(),
+            paths: Vec::new(),
+            sources: None,
+        }
+    }
+
+    fn with_sources(mut self, sources: &'a SourceFiles) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    fn read(&self, path: &Path) -> Result<DocumentSerial, SlinkyError> {
+        match self.sources {
+            Some(sources) => DocumentSerial::from_file_contents(path, &sources.read(path)?),
+            None => DocumentSerial::read_file(path),
+        }
+    }
+
+    fn insert(&mut self, path: &Path) -> Result<(), SlinkyError> {
+        if !self.seen.insert(canonical_path(path)) {
+            return Err(SlinkyError::DuplicatedInclude {
+                path: path.to_path_buf(),
+            });
+        }
+        self.paths.push(path.to_path_buf());
+        Ok(())
+    }
+
+    fn attach_to(self, mut d: Document) -> Document {
+        d.included_files = self.paths;
+        d.path = self.root;
+        d
+    }
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    utils::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Appends the entries of an included document to the ones of the including
+/// document.
+fn append_entries<T>(entries: &mut AbsentNullable<Vec<T>>, other: AbsentNullable<Vec<T>>) {
+    if let AbsentNullable::Value(other) = other {
+        match entries {
+            AbsentNullable::Value(entries) => entries.extend(other),
+            _ => *entries = AbsentNullable::Value(other),
+        }
+    }
+}
+
+/// The assignments of `pre_sections_symbols` and `post_sections_symbols` are
+/// already placed by the list they belong to.
+fn check_region_symbols(
+    symbol_assignments: &[SymbolAssignment],
+    region: &str,
+    component: impl Fn(usize, &SymbolAssignment) -> EntryPathComponent,
+) -> Result<(), SlinkyError> {
+    for (index, x) in symbol_assignments.iter().enumerate() {
+        let field = if x.placement.is_some() {
+            "placement"
+        } else if x.inside_segment.is_some() {
+            "inside_segment"
+        } else {
+            continue;
+        };
+
+        return Err(SlinkyError::InvalidFieldCombo {
+            field1: field.to_string(),
+            field2: region.to_string(),
+        }
+        .in_entry(component(index, x)));
+    }
+
+    Ok(())
+}
+
+/// The settings a segment is unserialized with.
+///
+/// The default sections come from the toolchain profile of the segment, if
+/// any, and the ones of its vram class take precedence over them.
+pub(crate) fn segment_settings<'a>(
+    settings: &'a Settings,
+    vram_classes: &[VramClass],
+    segment: &SegmentSerial,
+) -> Cow<'a, Settings> {
+    let toolchain = match &segment.toolchain {
+        AbsentNullable::Absent => settings.toolchain.as_deref(),
+        AbsentNullable::Null => None,
+        AbsentNullable::Value(toolchain) => Some(toolchain.as_str()),
+    };
+    let profile = toolchain.and_then(ToolchainProfile::from_name);
+    let vram_class = match &segment.vram_class {
+        AbsentNullable::Value(vram_class) => vram_classes
+            .iter()
+            .find(|x| x.name == *vram_class)
+            .filter(|x| x.alloc_sections.is_some() || x.noload_sections.is_some()),
+        _ => None,
+    };
+
+    if profile.is_none() && vram_class.is_none() {
+        return Cow::Borrowed(settings);
+    }
+
+    let mut segment_settings = settings.clone();
+    if let Some(profile) = profile {
+        profile.apply(&mut segment_settings);
+    }
+    if let Some(vram_class) = vram_class {
+        if let Some(alloc_sections) = &vram_class.alloc_sections {
+            segment_settings.alloc_sections = alloc_sections.clone();
+        }
+        if let Some(noload_sections) = &vram_class.noload_sections {
+            segment_settings.noload_sections = noload_sections.clone();
+        }
+    }
+    Cow::Owned(segment_settings)
+}
+
+#[derive(Deserialize, PartialEq, Debug, Default)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct DocumentSerial {
+    #[serde(default)]
+    pub required_slinky_version: AbsentNullable<Version>,
+    #[serde(default)]
+    pub include: AbsentNullable<Vec<PathBuf>>,
+
     #[serde(default)]
     pub settings: AbsentNullable<SettingsSerial>,
 
     #[serde(default)]
     pub vram_classes: AbsentNullable<Vec<VramClassSerial>>,
 
+    #[serde(default)]
+    pub segment_templates: AbsentNullable<Vec<SegmentSerial>>,
+    #[serde(default)]
     pub segments: Vec<SegmentSerial>,
 
     #[serde(default)]
     pub entry: AbsentNullable<String>,
     #[serde(default)]
+    pub startup_file: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub extra_inputs: AbsentNullable<Vec<PathBuf>>,
+    #[serde(default)]
     pub symbol_assignments: AbsentNullable<Vec<SymbolAssignmentSerial>>,
     #[serde(default)]
     pub required_symbols: AbsentNullable<Vec<RequiredSymbolSerial>>,
     #[serde(default)]
     pub asserts: AbsentNullable<Vec<AssertEntrySerial>>,
+
+    #[serde(default)]
+    pub pre_sections_symbols: AbsentNullable<Vec<SymbolAssignmentSerial>>,
+    #[serde(default)]
+    pub post_sections_symbols: AbsentNullable<Vec<SymbolAssignmentSerial>>,
+    #[serde(default)]
+    pub post_sections_asserts: AbsentNullable<Vec<AssertEntrySerial>>,
+
+    #[serde(default)]
+    pub runtime_options: AbsentNullable<Vec<RuntimeOptionSerial>>,
+
+    #[serde(default)]
+    pub targets: AbsentNullable<Vec<TargetSerial>>,
+
+    #[serde(default)]
+    pub imports: AbsentNullable<Vec<ImportSerial>>,
+
+    #[serde(default)]
+    pub raw: AbsentNullable<Vec<RawBlockSerial>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
 }
 
 impl DocumentSerial {
+    fn read_file(path: &Path) -> Result<Self, SlinkyError> {
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                })
+            }
+        };
+
+        Self::from_file_contents(path, &contents)
+    }
+
+    fn from_file_contents(path: &Path, contents: &str) -> Result<Self, SlinkyError> {
+        match Self::from_yaml_str(contents) {
+            Err(SlinkyError::FailedYamlParsing { description }) => {
+                Err(SlinkyError::FailedYamlParsing {
+                    description: format!("{}: {}", path.display(), description),
+                })
+            }
+            x => x,
+        }
+    }
+
+    /// Parses the yaml of a document without reading its `include`s.
+    ///
+    /// If the document can't be parsed because it requires a newer slinky
+    /// then that is reported instead.
+    pub(crate) fn from_yaml_str(contents: &str) -> Result<Self, SlinkyError> {
+        match serde_yaml::from_str(contents) {
+            Ok(d) => Ok(d),
+            Err(e) => {
+                version::check_unparsed_document(contents)?;
+                Err(SlinkyError::FailedYamlParsing {
+                    description: e.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Reads every document listed on `include`, recursively, and appends
+    /// their entries after the ones of this document, in the order they are
+    /// listed.
+    ///
+    /// Only the main document can set `settings`, `entry` and
+    /// `startup_file`.
+    fn merge_includes(
+        mut self,
+        base_dir: &Path,
+        included: &mut IncludedFiles,
+    ) -> Result<Self, SlinkyError> {
+        // Checked before anything else, since the rest of the document may
+        // rely on a newer slinky
+        if let AbsentNullable::Value(required) = &self.required_slinky_version {
+            Version::check_required(required)?;
+        }
+
+        let include = std::mem::take(&mut self.include).get_non_null("include", Vec::new)?;
+
+        for path in include {
+            let path = base_dir.join(path);
+            included.insert(&path)?;
+
+            let other = included
+                .read(&path)?
+                .merge_includes(&include_dir(&path), included)?;
+
+            for (field, has_value) in [
+                ("settings", other.settings.has_value()),
+                ("entry", other.entry.has_value()),
+                ("startup_file", other.startup_file.has_value()),
+            ] {
+                if has_value {
+                    return Err(SlinkyError::InvalidIncludedField {
+                        path,
+                        field: field.to_string(),
+                    });
+                }
+            }
+
+            append_entries(&mut self.vram_classes, other.vram_classes);
+            append_entries(&mut self.segment_templates, other.segment_templates);
+            self.segments.extend(other.segments);
+            append_entries(&mut self.extra_inputs, other.extra_inputs);
+            append_entries(&mut self.symbol_assignments, other.symbol_assignments);
+            append_entries(&mut self.required_symbols, other.required_symbols);
+            append_entries(&mut self.asserts, other.asserts);
+            append_entries(&mut self.pre_sections_symbols, other.pre_sections_symbols);
+            append_entries(&mut self.post_sections_symbols, other.post_sections_symbols);
+            append_entries(&mut self.post_sections_asserts, other.post_sections_asserts);
+            append_entries(&mut self.runtime_options, other.runtime_options);
+            append_entries(&mut self.targets, other.targets);
+            append_entries(&mut self.imports, other.imports);
+            append_entries(&mut self.raw, other.raw);
+        }
+
+        Ok(self)
+    }
+
     pub fn unserialize(self) -> Result<Document, SlinkyError> {
+        let required_slinky_version = self
+            .required_slinky_version
+            .get_non_null_no_default("required_slinky_version")?;
+
         let settings = match self.settings.get_non_null_no_default("settings")? {
             None => Settings::default(),
-            Some(v) => v.unserialize()?,
+            Some(v) => v
+                .unserialize()
+                .map_err(|e| e.in_entry(EntryPathComponent::Settings))?,
         };
 
         if self.segments.is_empty() {
@@ -85,29 +1029,143 @@ impl DocumentSerial {
             });
         }
 
-        let vram_classes = self
-            .vram_classes
-            .get_non_null("vram_classes", Vec::new)?
-            .unserialize(&settings)?;
+        let vram_classes = unserialize_entries(
+            self.vram_classes.get_non_null("vram_classes", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::VramClass {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
 
-        let mut segments = self.segments.unserialize(&settings)?;
+        let segments = segment::apply_segment_templates(
+            self.segment_templates
+                .get_non_null("segment_templates", Vec::new)?,
+            self.segments,
+        )?;
+        let mut segments = segments
+            .into_iter()
+            .enumerate()
+            .map(|(index, x)| {
+                let segment_settings = segment_settings(&settings, &vram_classes, &x);
+                let component = EntryPathComponent::Segment {
+                    index,
+                    name: x.name.clone(),
+                };
+                x.unserialize(&segment_settings)
+                    .map_err(|e| e.in_entry(component))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if settings.sort_segments_by_follows {
+            segments = validation::sort_segments_by_dependencies(
+                segments,
+                &settings.linker_symbols_style,
+            )?;
+        } else {
+            validation::check_rom_order(&segments)?;
+        }
 
         let entry = self.entry.get_non_null_no_default("entry")?;
+        let startup_file = self.startup_file.get_non_null_no_default("startup_file")?;
+        let extra_inputs = self.extra_inputs.get_non_null("extra_inputs", Vec::new)?;
+
+        let symbol_assignments = unserialize_entries(
+            self.symbol_assignments
+                .get_non_null("symbol_assignments", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::SymbolAssignment {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
+
+        let required_symbols = unserialize_entries(
+            self.required_symbols
+                .get_non_null("required_symbols", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::RequiredSymbol {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
 
-        let symbol_assignments = self
-            .symbol_assignments
-            .get_non_null("symbol_assignments", Vec::new)?
-            .unserialize(&settings)?;
+        let asserts = unserialize_entries(
+            self.asserts.get_non_null("asserts", Vec::new)?,
+            &settings,
+            |index, _| EntryPathComponent::Assert { index },
+        )?;
 
-        let required_symbols = self
-            .required_symbols
-            .get_non_null("required_symbols", Vec::new)?
-            .unserialize(&settings)?;
+        let pre_sections_symbols = unserialize_entries(
+            self.pre_sections_symbols
+                .get_non_null("pre_sections_symbols", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::PreSectionsSymbol {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
+        check_region_symbols(&pre_sections_symbols, "pre_sections_symbols", |index, x| {
+            EntryPathComponent::PreSectionsSymbol {
+                index,
+                name: x.name.clone(),
+            }
+        })?;
+
+        let post_sections_symbols = unserialize_entries(
+            self.post_sections_symbols
+                .get_non_null("post_sections_symbols", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::PostSectionsSymbol {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
+        check_region_symbols(
+            &post_sections_symbols,
+            "post_sections_symbols",
+            |index, x| EntryPathComponent::PostSectionsSymbol {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
+
+        let post_sections_asserts = unserialize_entries(
+            self.post_sections_asserts
+                .get_non_null("post_sections_asserts", Vec::new)?,
+            &settings,
+            |index, _| EntryPathComponent::PostSectionsAssert { index },
+        )?;
+
+        let runtime_options = unserialize_entries(
+            self.runtime_options
+                .get_non_null("runtime_options", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::RuntimeOption {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
+
+        let targets = unserialize_entries(
+            self.targets.get_non_null("targets", Vec::new)?,
+            &settings,
+            |index, x| EntryPathComponent::Target {
+                index,
+                name: x.name.clone(),
+            },
+        )?;
 
-        let asserts = self
-            .asserts
-            .get_non_null("asserts", Vec::new)?
-            .unserialize(&settings)?;
+        let imports = unserialize_entries(
+            self.imports.get_non_null("imports", Vec::new)?,
+            &settings,
+            |index, _| EntryPathComponent::Import { index },
+        )?;
+
+        let raw = unserialize_entries(
+            self.raw.get_non_null("raw", Vec::new)?,
+            &settings,
+            |index, _| EntryPathComponent::RawBlock { index },
+        )?;
 
         for segment in segments.iter_mut() {
             if let Some(vram_class_name) = &segment.vram_class {
@@ -119,14 +1177,30 @@ impl DocumentSerial {
             }
         }
 
-        Ok(Document {
+        let document = Document {
+            required_slinky_version,
             settings,
             vram_classes,
             segments,
             entry,
+            startup_file,
+            extra_inputs,
             symbol_assignments,
             required_symbols,
             asserts,
-        })
+            pre_sections_symbols,
+            post_sections_symbols,
+            post_sections_asserts,
+            runtime_options,
+            targets,
+            imports,
+            raw,
+            included_files: Vec::new(),
+            path: None,
+        };
+
+        document.check_expressions()?;
+
+        Ok(document)
     }
 }
@@ -0,0 +1,145 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{Document, SlinkyError};
+
+struct CachedDocument {
+    modified: SystemTime,
+    /// The modification time of every document merged through `include` and
+    /// every list read through `files_from`, or `None` if it didn't exist.
+    dependencies: Vec<(PathBuf, Option<SystemTime>)>,
+    cached_at: Instant,
+    document: Arc<Document>,
+}
+
+impl CachedDocument {
+    fn dependencies_changed(&self) -> bool {
+        self.dependencies
+            .iter()
+            .any(|(path, modified)| modified_time(path).ok() != *modified)
+    }
+}
+
+fn modified_time(path: &Path) -> std::io::Result<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified())
+}
+
+/// Memoizes parsed documents, keyed by their path and modification time.
+///
+/// Meant for long-lived processes embedding slinky (language servers, build
+/// daemons, etc) which may request the same document many times, avoiding
+/// re-parsing it if the file has not changed on disk. The documents it
+/// includes and the lists of `files_from` are checked too.
+///
+/// The cache holds at most `max_entries` documents, evicting the least
+/// recently used one when full. Optionally, entries can expire after a given
+/// amount of time.
+pub struct DocumentCache {
+    entries: indexmap::IndexMap<PathBuf, CachedDocument>,
+
+    max_entries: usize,
+    time_to_live: Option<Duration>,
+}
+
+impl DocumentCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: indexmap::IndexMap::new(),
+
+            max_entries,
+            time_to_live: None,
+        }
+    }
+
+    /// Expire the cached documents after the given amount of time, even if the
+    /// file has not been modified.
+    #[must_use]
+    pub fn with_time_to_live(mut self, time_to_live: Duration) -> Self {
+        self.time_to_live = Some(time_to_live);
+        self
+    }
+
+    /// Returns the cached document for the given path, reading and parsing
+    /// the file again only if it or any of its dependencies has been modified
+    /// since it was cached, or if the cached entry has expired.
+    pub fn read_file(&mut self, path: &Path) -> Result<Arc<Document>, SlinkyError> {
+        let modified = match modified_time(path) {
+            Ok(m) => m,
+            Err(e) => {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                })
+            }
+        };
+
+        if let Some(cached) = self.entries.shift_remove(path) {
+            let expired = match self.time_to_live {
+                Some(ttl) => cached.cached_at.elapsed() >= ttl,
+                None => false,
+            };
+
+            if cached.modified == modified && !expired && !cached.dependencies_changed() {
+                let document = cached.document.clone();
+
+                // Mark it as the most recently used one
+                self.entries.insert(path.to_path_buf(), cached);
+                return Ok(document);
+            }
+        }
+
+        let document = Arc::new(Document::read_file(path)?);
+
+        if self.max_entries == 0 {
+            return Ok(document);
+        }
+        while self.entries.len() >= self.max_entries {
+            self.entries.shift_remove_index(0);
+        }
+
+        let dependencies = document
+            .included_files
+            .iter()
+            .chain(document.files_lists())
+            .map(|x| (x.clone(), modified_time(x).ok()))
+            .collect();
+        self.entries.insert(
+            path.to_path_buf(),
+            CachedDocument {
+                modified,
+                dependencies,
+                cached_at: Instant::now(),
+                document: document.clone(),
+            },
+        );
+
+        Ok(document)
+    }
+
+    /// Removes the given path from the cache, forcing it to be parsed again
+    /// the next time it is requested.
+    pub fn invalidate(&mut self, path: &Path) {
+        self.entries.shift_remove(path);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,187 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::Path;
+
+use crate::{utils, SlinkyError};
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ELFDATA2MSB: u8 = 2;
+
+const SHN_XINDEX: u64 = 0xFFFF;
+const SHT_NULL: u64 = 0;
+const SHF_ALLOC: u64 = 0x2;
+
+/// Reads the names of the sections of the ELF object at `path` which occupy
+/// memory at runtime, the ones with the `SHF_ALLOC` flag.
+pub(crate) fn read_alloc_sections(path: &Path) -> Result<Vec<String>, SlinkyError> {
+    let contents = match utils::read(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(SlinkyError::FailedFileOpen {
+                path: path.to_path_buf(),
+                description: e.to_string(),
+            })
+        }
+    };
+
+    parse_alloc_sections(&contents).map_err(|description| SlinkyError::InvalidObject {
+        path: path.to_path_buf(),
+        description: description.to_string(),
+    })
+}
+
+/// Reads integers of the given width from an ELF file, honoring its
+/// endianness.
+struct Reader<'a> {
+    contents: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn read(&self, offset: u64, width: usize) -> Result<u64, &'static str> {
+        let start = usize::try_from(offset).map_err(|_| "offset out of bounds")?;
+        let bytes = start
+            .checked_add(width)
+            .and_then(|end| self.contents.get(start..end))
+            .ok_or("offset out of bounds")?;
+
+        let mut value = 0;
+        for i in 0..width {
+            let byte = if self.big_endian {
+                bytes[i]
+            } else {
+                bytes[width - 1 - i]
+            };
+            value = (value << 8) | u64::from(byte);
+        }
+        Ok(value)
+    }
+}
+
+/// Offsets and widths of the fields used from the ELF header and the section
+/// headers, which depend on the class of the file.
+struct Layout {
+    addr_width: usize,
+    e_shoff: u64,
+    e_shentsize: u64,
+    e_shnum: u64,
+    e_shstrndx: u64,
+    sh_type: u64,
+    sh_flags: u64,
+    sh_offset: u64,
+    sh_size: u64,
+    sh_link: u64,
+}
+
+const LAYOUT_32: Layout = Layout {
+    addr_width: 4,
+    e_shoff: 0x20,
+    e_shentsize: 0x2E,
+    e_shnum: 0x30,
+    e_shstrndx: 0x32,
+    sh_type: 0x04,
+    sh_flags: 0x08,
+    sh_offset: 0x10,
+    sh_size: 0x14,
+    sh_link: 0x18,
+};
+
+const LAYOUT_64: Layout = Layout {
+    addr_width: 8,
+    e_shoff: 0x28,
+    e_shentsize: 0x3A,
+    e_shnum: 0x3C,
+    e_shstrndx: 0x3E,
+    sh_type: 0x04,
+    sh_flags: 0x08,
+    sh_offset: 0x18,
+    sh_size: 0x20,
+    sh_link: 0x28,
+};
+
+fn parse_alloc_sections(contents: &[u8]) -> Result<Vec<String>, &'static str> {
+    if !contents.starts_with(ELF_MAGIC) || contents.len() < 6 {
+        return Err("missing the ELF magic");
+    }
+
+    let layout = match contents[4] {
+        ELFCLASS32 => &LAYOUT_32,
+        ELFCLASS64 => &LAYOUT_64,
+        _ => return Err("unknown ELF class"),
+    };
+    let big_endian = match contents[5] {
+        ELFDATA2LSB => false,
+        ELFDATA2MSB => true,
+        _ => return Err("unknown ELF data encoding"),
+    };
+    let r = Reader {
+        contents,
+        big_endian,
+    };
+    let width = layout.addr_width;
+
+    let shoff = r.read(layout.e_shoff, width)?;
+    if shoff == 0 {
+        return Ok(Vec::new());
+    }
+    let shentsize = r.read(layout.e_shentsize, 2)?;
+    // The values come from the file itself, so they may overflow
+    let header = |index: u64, field: u64| {
+        index
+            .checked_mul(shentsize)
+            .and_then(|x| x.checked_add(shoff))
+            .and_then(|x| x.checked_add(field))
+            .ok_or("offset out of bounds")
+    };
+
+    // Files with too many sections store the real values on the first section
+    // header
+    let mut shnum = r.read(layout.e_shnum, 2)?;
+    if shnum == 0 {
+        shnum = r.read(header(0, layout.sh_size)?, width)?;
+    }
+    let mut shstrndx = r.read(layout.e_shstrndx, 2)?;
+    if shstrndx == SHN_XINDEX {
+        shstrndx = r.read(header(0, layout.sh_link)?, 4)?;
+    }
+    if shstrndx >= shnum {
+        return Err("invalid section names table index");
+    }
+
+    let strtab_offset = r.read(header(shstrndx, layout.sh_offset)?, width)?;
+    let strtab_size = r.read(header(shstrndx, layout.sh_size)?, width)?;
+    let strtab = usize::try_from(strtab_offset)
+        .ok()
+        .zip(
+            strtab_offset
+                .checked_add(strtab_size)
+                .and_then(|end| usize::try_from(end).ok()),
+        )
+        .and_then(|(start, end)| contents.get(start..end))
+        .ok_or("section names table out of bounds")?;
+
+    let mut sections = Vec::new();
+    for index in 1..shnum {
+        let sh_type = r.read(header(index, layout.sh_type)?, 4)?;
+        let sh_flags = r.read(header(index, layout.sh_flags)?, width)?;
+        if sh_type == SHT_NULL || sh_flags & SHF_ALLOC == 0 {
+            continue;
+        }
+
+        // `sh_name` is always the first field
+        let name_offset = r.read(header(index, 0)?, 4)?;
+        let name = usize::try_from(name_offset)
+            .ok()
+            .and_then(|start| strtab.get(start..))
+            .ok_or("section name out of bounds")?;
+        let end = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+        sections.push(String::from_utf8_lossy(&name[..end]).to_string());
+    }
+
+    Ok(sections)
+}
@@ -3,17 +3,39 @@
 
 use std::{borrow::Cow, path::PathBuf};
 
+use crate::{EntryPath, EntryPathComponent};
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, thiserror::Error)]
 pub enum SlinkyError {
     #[error("Unable to open file '{path}', because '{description}'")]
     FailedFileOpen { path: PathBuf, description: String },
 
+    #[error("Invalid version '{version}', expected 'major.minor.patch'")]
+    InvalidVersion { version: String },
+
+    #[error("The document requires slinky {required} or newer, but this is slinky {current}")]
+    UnsupportedSlinkyVersion { required: String, current: String },
+
+    #[error("The file '{path}' is included more than once")]
+    DuplicatedInclude { path: PathBuf },
+
+    #[error(
+        "The included file '{path}' sets '{field}', which is only allowed on the main document"
+    )]
+    InvalidIncludedField { path: PathBuf, field: String },
+
     #[error("Failed to write, because '{description}'.\n Contents were: '{contents}'")]
     FailedWrite {
         description: String,
         contents: String,
     },
 
+    #[error("Failed to read, because '{description}'.")]
+    FailedRead { description: String },
+
+    #[error("The linker script was already streamed, so it is not available anymore")]
+    StreamedLinkerScript,
+
     #[error("Failed to convert string, because '{description}'.")]
     FailedStringConversion { description: String },
 
@@ -23,18 +45,46 @@ pub enum SlinkyError {
     #[error("Unable parse yaml: {description}")]
     FailedYamlParsing { description: String },
 
+    #[error("Unable to parse the files list '{path}': {description}")]
+    FailedFilesListParsing { path: PathBuf, description: String },
+
+    #[error("Unable to serialize yaml: {description}")]
+    FailedYamlSerialization { description: String },
+
+    #[error("Unable to parse linker script: {description}")]
+    FailedLinkerScriptParsing { description: String },
+
+    #[error("Unable to convert splat yaml: {description}")]
+    FailedSplatYamlConversion { description: String },
+
     #[error("Non-nullable attribute '{name}' was null")]
     NullValueOnNonNull { name: String },
 
     #[error("The attribute '{name}' should not be empty")]
     EmptyValue { name: String },
 
+    #[error("The range of '{name}' is empty, its start 0x{start:08X} should be smaller than its end 0x{end:08X}")]
+    EmptyRange { name: String, start: u64, end: u64 },
+
+    #[error("Value '{value}' is not allowed on field '{name}'. Allowed values: {allowed}")]
+    InvalidFieldValue {
+        name: String,
+        value: String,
+        allowed: String,
+    },
+
+    #[error("The reserved size 0x{reserved:X} is bigger than the max size 0x{max_size:X}")]
+    ReservedOverMaxSize { reserved: u32, max_size: u32 },
+
     #[error("Field '{field1}' can't be combined with '{field2}'")]
     InvalidFieldCombo { field1: String, field2: String },
 
     #[error("Field '{name}' is required")]
     MissingRequiredField { name: String },
 
+    #[error("Field '{name}' should contain the `{{segment}}` placeholder")]
+    MissingSegmentPlaceholder { name: String },
+
     #[error("Field '{required}' is required if field '{other}' is given")]
     MissingRequiredFieldCombo { required: String, other: String },
 
@@ -47,6 +97,26 @@ pub enum SlinkyError {
         custom_option: String,
     },
 
+    #[error("The section '{section}' is part of both the '{group1}' and the '{group2}' subgroups of 'sections_subgroups'")]
+    SectionInMultipleSubgroups {
+        section: String,
+        group1: String,
+        group2: String,
+    },
+
+    #[error("The section '{section}' is part of its own subgroup on 'sections_subgroups'")]
+    SectionsSubgroupsCycle { section: String },
+
+    #[error("The section '{section}' is listed on '{field_name}', but it is also part of the '{group}' subgroup of 'sections_subgroups'")]
+    SubgroupSectionIsTopLevel {
+        section: String,
+        group: String,
+        field_name: String,
+    },
+
+    #[error("Can't remove the section '{section}' from '{field_name}', since it is not part of the inherited sections")]
+    RemovedMissingSection { field_name: String, section: String },
+
     #[error("Field '{field_name}' refences the section '{section}', but that section is not present on segment '{segment}'")]
     MissingSectionForSegment {
         field_name: Cow<'static, str>,
@@ -59,4 +129,255 @@ pub enum SlinkyError {
         segment: Cow<'static, str>,
         vram_class: Cow<'static, str>,
     },
+
+    #[error("Segment '{segment}' is placed at the fixed vram 0x{fixed_vram:08X}, but the previous segment '{previous_segment}' ends after that address")]
+    FixedVramOverlap {
+        segment: Cow<'static, str>,
+        fixed_vram: u64,
+        previous_segment: Cow<'static, str>,
+    },
+
+    #[error("Segment '{segment}' is declared more than once")]
+    DuplicatedSegment { segment: Cow<'static, str> },
+
+    #[error("Vram class '{vram_class}' is declared more than once")]
+    DuplicatedVramClass { vram_class: Cow<'static, str> },
+
+    #[error("File '{path}' is listed more than once, the last time on segment '{segment}'")]
+    DuplicatedFile {
+        path: PathBuf,
+        segment: Cow<'static, str>,
+    },
+
+    #[error("Segment '{segment}' follows the undefined segment '{follows_segment}'")]
+    MissingFollowedSegment {
+        segment: Cow<'static, str>,
+        follows_segment: Cow<'static, str>,
+    },
+
+    #[error(
+        "Segment '{segment}' follows the segment '{follows_segment}', which is declared after it"
+    )]
+    SegmentFollowsLaterSegment {
+        segment: Cow<'static, str>,
+        follows_segment: Cow<'static, str>,
+    },
+
+    #[error(
+        "Segment '{segment}' ends up following itself through `follows_segment` or `fixed_symbol`"
+    )]
+    SegmentCycle { segment: Cow<'static, str> },
+
+    #[error(
+        "Segment '{segment}' has a lower `rom_order` than '{previous}', which is placed before it"
+    )]
+    UnorderedRomOrder {
+        segment: Cow<'static, str>,
+        previous: Cow<'static, str>,
+    },
+
+    #[error("The `rom_order` of segment '{segment}' can't be kept while placing every segment after its dependencies")]
+    RomOrderConflict { segment: Cow<'static, str> },
+
+    #[error(
+        "Segment '{segment}' merges its noload sections into the segment '{noload_merge}', which does not exist or is not emitted"
+    )]
+    MissingNoloadMergeSegment {
+        segment: Cow<'static, str>,
+        noload_merge: Cow<'static, str>,
+    },
+
+    #[error(
+        "Segment '{segment}' merges its noload sections into the segment '{noload_merge}', which merges its own noload sections somewhere else"
+    )]
+    ChainedNoloadMerge {
+        segment: Cow<'static, str>,
+        noload_merge: Cow<'static, str>,
+    },
+
+    #[error("Segment template '{template}' does not exist")]
+    MissingSegmentTemplate { template: String },
+
+    #[error("Segment template '{template}' is declared more than once")]
+    DuplicatedSegmentTemplate { template: String },
+
+    #[error("A raw block is placed after the undefined segment '{segment}'")]
+    MissingRawBlockSegment { segment: Cow<'static, str> },
+
+    #[error("Symbol assignment '{symbol}' is placed inside the undefined segment '{segment}'")]
+    MissingSymbolAssignmentSegment {
+        symbol: Cow<'static, str>,
+        segment: Cow<'static, str>,
+    },
+
+    #[error("Vram class '{vram_class}' follows the undefined vram class '{follows_class}'")]
+    MissingFollowedVramClass {
+        vram_class: Cow<'static, str>,
+        follows_class: Cow<'static, str>,
+    },
+
+    #[error("Vram class '{vram_class}' ends up following itself through `follows_classes`")]
+    VramClassCycle { vram_class: Cow<'static, str> },
+
+    #[error("Target '{target}' is not listed on the document")]
+    MissingTarget { target: String },
+
+    #[error("Segment '{segment}' is not listed on the document")]
+    MissingSegment { segment: String },
+
+    #[error("Target '{target}' is declared more than once")]
+    DuplicatedTarget { target: Cow<'static, str> },
+
+    #[error("Runtime option '{option}' is declared more than once")]
+    DuplicatedRuntimeOption { option: Cow<'static, str> },
+
+    #[error("Custom option '{option}' is not declared on `runtime_options`")]
+    UnknownCustomOption { option: String },
+
+    #[error("Value '{value}' is not allowed for the custom option '{option}'. Allowed values: {allowed}")]
+    InvalidCustomOptionValue {
+        option: String,
+        value: String,
+        allowed: String,
+    },
+
+    #[error("Symbol '{symbol}' is not generated by the imported document '{path}'")]
+    MissingImportedSymbol { symbol: String, path: PathBuf },
+
+    #[error("Segment '{segment}' does not have any file")]
+    EmptySegment { segment: Cow<'static, str> },
+
+    #[error("The fragment '{path}' of the external segment '{segment}' does not define the symbol '{symbol}'")]
+    MissingFragmentSymbol {
+        segment: String,
+        symbol: String,
+        path: PathBuf,
+    },
+
+    #[error("Path '{path}' is referenced by the linker script but it is not listed on the dependency file")]
+    UndeclaredDependency { path: String },
+
+    #[error("Path '{path}' is listed on the dependency file but it is not referenced by the linker script")]
+    UnreferencedDependency { path: String },
+
+    #[error("Field '{field_name}' references the section '{section}', but that section is discarded by `sections_denylist`")]
+    DiscardedSectionReferenced {
+        field_name: Cow<'static, str>,
+        section: Cow<'static, str>,
+    },
+
+    #[error("Section '{section}' is listed on `alloc_sections` of segment '{segment}', but it is a noload section")]
+    NoloadSectionInAllocSections {
+        section: Cow<'static, str>,
+        segment: Cow<'static, str>,
+    },
+
+    #[error(
+        "Symbol '{symbol}' referenced through `{{{{{symbol}}}}}` is not generated by the document"
+    )]
+    UnknownLogicalSymbol { symbol: String },
+
+    #[error("Symbol '{symbol}' is named like a generated symbol, but it is not generated by the document")]
+    UnknownGeneratedSymbol { symbol: String },
+
+    #[error("Value '{value}' has a `{{{{` without its matching `}}}}`")]
+    UnclosedLogicalSymbol { value: String },
+
+    #[error("Can't deduce the `kind` of file '{path}', since its extension '{extension}' is not listed on `file_kind_overrides`")]
+    UnknownFileExtension { path: PathBuf, extension: String },
+
+    #[error("Condition ('{key}', '{value}') is not declared on `valid_conditions`")]
+    UndeclaredCondition {
+        name: String,
+        key: String,
+        value: String,
+    },
+
+    #[error("Symbol '{symbol}' depends on the placement of the segments, so it can't be assigned on `pre_sections_symbols`")]
+    PreSectionsSymbolReferencesSections { symbol: String },
+
+    #[error("Unable to read the members of the archive '{path}', because '{description}'")]
+    InvalidArchive { path: PathBuf, description: String },
+
+    #[error("The archive '{path}' does not have any member matching '{member}'")]
+    MissingArchiveMember { path: PathBuf, member: String },
+
+    #[error("Unable to read the sections of the object '{path}', because '{description}'")]
+    InvalidObject { path: PathBuf, description: String },
+
+    #[error("The object '{path}' has a '{section}' section, which is not placed by the segment '{segment}'")]
+    UnplacedObjectSection {
+        path: PathBuf,
+        section: String,
+        segment: Cow<'static, str>,
+    },
+
+    #[error("{entry}: {source}")]
+    InvalidEntry {
+        entry: EntryPath,
+        source: Box<SlinkyError>,
+    },
+}
+
+impl SlinkyError {
+    /// Attaches the entry that produced this error. Since entries are
+    /// unserialized from the innermost one outwards, the component is placed
+    /// at the start of the path.
+    pub(crate) fn in_entry(self, component: EntryPathComponent) -> Self {
+        match self {
+            SlinkyError::InvalidEntry { mut entry, source } => {
+                entry.components.insert(0, component);
+                SlinkyError::InvalidEntry { entry, source }
+            }
+            error => SlinkyError::InvalidEntry {
+                entry: EntryPath {
+                    components: vec![component],
+                },
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// The name of the field of the document which produced this error, if
+    /// known.
+    pub fn field_name(&self) -> Option<&str> {
+        // Some errors describe the field in a human readable way instead of
+        // just using its name
+        fn plain(name: &str) -> Option<&str> {
+            if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+                Some(name)
+            } else {
+                None
+            }
+        }
+
+        match self {
+            SlinkyError::NullValueOnNonNull { name }
+            | SlinkyError::EmptyValue { name }
+            | SlinkyError::MissingRequiredField { name }
+            | SlinkyError::MissingSegmentPlaceholder { name }
+            | SlinkyError::EmptyRange { name, .. }
+            | SlinkyError::UndeclaredCondition { name, .. }
+            | SlinkyError::InvalidFieldValue { name, .. } => plain(name),
+            SlinkyError::InvalidFieldCombo { field1, field2 } => {
+                plain(field1).or_else(|| plain(field2))
+            }
+            SlinkyError::MissingRequiredFieldCombo { other, .. } => plain(other),
+            SlinkyError::RemovedMissingSection { field_name, .. } => plain(field_name),
+            SlinkyError::SectionInMultipleSubgroups { .. }
+            | SlinkyError::SectionsSubgroupsCycle { .. }
+            | SlinkyError::SubgroupSectionIsTopLevel { .. } => Some("sections_subgroups"),
+            SlinkyError::MissingSectionForSegment { field_name, .. }
+            | SlinkyError::DiscardedSectionReferenced { field_name, .. } => plain(field_name),
+            SlinkyError::NoloadSectionInAllocSections { .. } => Some("alloc_sections"),
+            SlinkyError::ReservedOverMaxSize { .. } => Some("reserved"),
+            SlinkyError::UnknownFileExtension { .. } => Some("path"),
+            SlinkyError::MissingArchiveMember { .. } => Some("subfile"),
+            SlinkyError::PreSectionsSymbolReferencesSections { .. } => Some("value"),
+            SlinkyError::UnplacedObjectSection { .. } => Some("path"),
+            SlinkyError::FailedFilesListParsing { .. } => Some("files_from"),
+            SlinkyError::InvalidEntry { source, .. } => source.field_name(),
+            _ => None,
+        }
+    }
 }
@@ -3,7 +3,7 @@
 
 use std::{
     fmt::Display,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
@@ -29,7 +29,7 @@ impl AsMut<PathBuf> for EscapedPath {
 
 impl From<String> for EscapedPath {
     fn from(value: String) -> Self {
-        Self(PathBuf::from(value))
+        Self::normalized(PathBuf::from(value))
     }
 }
 
@@ -50,14 +50,25 @@ impl<'a> IntoIterator for &'a EscapedPath {
 
 impl Display for EscapedPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut is_first = true;
+        let mut needs_separator = false;
 
         for x in self.0.components() {
-            if !is_first {
-                write!(f, "/")?;
+            match x {
+                // The root directory already is a separator
+                Component::RootDir => write!(f, "/")?,
+                // Windows' prefixes are kept as is, i.e. `C:`
+                Component::Prefix(prefix) => write!(f, "{}", prefix.as_os_str().to_string_lossy())?,
+                _ => {
+                    if needs_separator {
+                        write!(f, "/")?;
+                    }
+                    write!(f, "{}", x.as_os_str().to_string_lossy())?;
+                }
             }
-            is_first = false;
-            write!(f, "{}", x.as_os_str().to_string_lossy())?;
+            needs_separator = matches!(
+                x,
+                Component::CurDir | Component::ParentDir | Component::Normal(_)
+            );
         }
         Ok(())
     }
@@ -80,6 +91,47 @@ impl EscapedPath {
     }
 
     pub fn push(&mut self, path: EscapedPath) {
-        self.0.push(path.0)
+        self.0.push(path.0);
+        self.0 = Self::normalized(std::mem::take(&mut self.0)).0;
+    }
+
+    /// Lexically removes every `.` component and collapses every `..` with
+    /// its preceding component.
+    ///
+    /// Leading `..` components of relative paths are kept, while `..`
+    /// components which would go above the root directory are dropped.
+    ///
+    /// The filesystem is not consulted, so a `..` following a symlink to a
+    /// directory is resolved relative to the symlink instead of its target.
+    pub(crate) fn normalized(path: PathBuf) -> Self {
+        let mut new_path = PathBuf::new();
+        let mut normal_components = 0usize;
+
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if normal_components > 0 {
+                        new_path.pop();
+                        normal_components -= 1;
+                    } else if !new_path.has_root() {
+                        new_path.push(component);
+                    }
+                }
+                Component::Normal(_) => {
+                    new_path.push(component);
+                    normal_components += 1;
+                }
+                Component::RootDir | Component::Prefix(_) => new_path.push(component),
+            }
+        }
+
+        if new_path.as_os_str().is_empty() && !path.as_os_str().is_empty() {
+            // Every component cancelled each other, so the path points to the
+            // current directory
+            new_path.push(Component::CurDir);
+        }
+
+        Self(new_path)
     }
 }
@@ -0,0 +1,548 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::fmt;
+
+use crate::{
+    Document, EntryPath, EntryPathComponent, EscapedPath, FileInfo, FileKind, RuntimeSettings,
+    Segment, SlinkyError,
+};
+
+/// Why an entry is emitted or not for the given runtime settings, following
+/// the same rules as [`RuntimeSettings::should_emit_entry`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EmitReason {
+    /// The entry has no conditions.
+    Unconditional,
+    /// The entry only has `exclude_if_*` conditions and none of them matched.
+    NotExcluded,
+    /// The given `include_if_*` condition matched.
+    Included { condition: String },
+    /// The given `exclude_if_*` condition matched.
+    Excluded { condition: String },
+    /// The entry has `include_if_*` conditions, but none of them matched.
+    NotIncluded,
+}
+
+impl EmitReason {
+    pub fn new(
+        rs: &RuntimeSettings,
+        exclude_if_any: &[(String, String)],
+        exclude_if_all: &[(String, String)],
+        include_if_any: &[(String, String)],
+        include_if_all: &[(String, String)],
+    ) -> Self {
+        let matches = |(key, value): &(String, String)| rs.custom_options().get(key) == Some(value);
+
+        if let Some((key, value)) = exclude_if_any.iter().find(|x| matches(x)) {
+            return EmitReason::Excluded {
+                condition: format!("exclude_if_any ({}={})", key, value),
+            };
+        }
+        if !exclude_if_all.is_empty() && exclude_if_all.iter().all(matches) {
+            return EmitReason::Excluded {
+                condition: format!("exclude_if_all ({})", describe_options(exclude_if_all)),
+            };
+        }
+
+        if include_if_any.is_empty() && include_if_all.is_empty() {
+            if exclude_if_any.is_empty() && exclude_if_all.is_empty() {
+                return EmitReason::Unconditional;
+            }
+            return EmitReason::NotExcluded;
+        }
+
+        if let Some((key, value)) = include_if_any.iter().find(|x| matches(x)) {
+            return EmitReason::Included {
+                condition: format!("include_if_any ({}={})", key, value),
+            };
+        }
+        if !include_if_all.is_empty() && include_if_all.iter().all(matches) {
+            return EmitReason::Included {
+                condition: format!("include_if_all ({})", describe_options(include_if_all)),
+            };
+        }
+
+        EmitReason::NotIncluded
+    }
+
+    #[must_use]
+    pub fn is_emitted(&self) -> bool {
+        !matches!(self, EmitReason::Excluded { .. } | EmitReason::NotIncluded)
+    }
+}
+
+fn describe_options(options: &[(String, String)]) -> String {
+    options
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for EmitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmitReason::Unconditional => write!(f, "emitted, it has no conditions"),
+            EmitReason::NotExcluded => write!(f, "emitted, no exclude condition matched"),
+            EmitReason::Included { condition } => write!(f, "emitted, matched {}", condition),
+            EmitReason::Excluded { condition } => write!(f, "excluded, matched {}", condition),
+            EmitReason::NotIncluded => write!(f, "excluded, no include condition matched"),
+        }
+    }
+}
+
+/// A single link of the chain determining the vram address of a segment.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PlacementStep {
+    FixedVram {
+        segment: String,
+        vram: u64,
+    },
+    FixedSymbol {
+        segment: String,
+        symbol: String,
+    },
+    FollowsSegment {
+        segment: String,
+        follows: String,
+    },
+    /// The segment shares the address of the first emitted segment of its
+    /// `overlay_group`.
+    OverlayGroup {
+        segment: String,
+        overlay_group: String,
+        first: String,
+    },
+    /// The segment has no placement of its own, so it is placed right after
+    /// the previous emitted segment.
+    PreviousSegment {
+        segment: String,
+        previous: String,
+    },
+    /// The segment has no placement of its own and nothing is emitted before
+    /// it.
+    FirstSegment {
+        segment: String,
+    },
+    VramClass {
+        segment: String,
+        vram_class: String,
+    },
+    VramClassFixedVram {
+        vram_class: String,
+        vram: u64,
+    },
+    VramClassFixedSymbol {
+        vram_class: String,
+        symbol: String,
+    },
+    VramClassFollows {
+        vram_class: String,
+        follows_classes: Vec<String>,
+        follows_symbols: Vec<String>,
+    },
+}
+
+impl fmt::Display for PlacementStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementStep::FixedVram { segment, vram } => {
+                write!(f, "Segment '{}' has the fixed vram 0x{:08X}", segment, vram)
+            }
+            PlacementStep::FixedSymbol { segment, symbol } => {
+                write!(f, "Segment '{}' is placed at the symbol '{}'", segment, symbol)
+            }
+            PlacementStep::FollowsSegment { segment, follows } => {
+                write!(f, "Segment '{}' follows segment '{}'", segment, follows)
+            }
+            PlacementStep::OverlayGroup {
+                segment,
+                overlay_group,
+                first,
+            } => write!(
+                f,
+                "Segment '{}' shares the address of segment '{}', the first one of the overlay group '{}'",
+                segment, first, overlay_group
+            ),
+            PlacementStep::PreviousSegment { segment, previous } => write!(
+                f,
+                "Segment '{}' is placed after the previous segment '{}'",
+                segment, previous
+            ),
+            PlacementStep::FirstSegment { segment } => write!(
+                f,
+                "Segment '{}' is the first emitted segment, so it starts at the initial location counter",
+                segment
+            ),
+            PlacementStep::VramClass {
+                segment,
+                vram_class,
+            } => write!(
+                f,
+                "Segment '{}' is placed at the start of the vram class '{}'",
+                segment, vram_class
+            ),
+            PlacementStep::VramClassFixedVram { vram_class, vram } => write!(
+                f,
+                "Vram class '{}' has the fixed vram 0x{:08X}",
+                vram_class, vram
+            ),
+            PlacementStep::VramClassFixedSymbol { vram_class, symbol } => write!(
+                f,
+                "Vram class '{}' is placed at the symbol '{}'",
+                vram_class, symbol
+            ),
+            PlacementStep::VramClassFollows {
+                vram_class,
+                follows_classes,
+                follows_symbols,
+            } => {
+                let follows: Vec<String> = follows_classes
+                    .iter()
+                    .map(|x| format!("vram class '{}'", x))
+                    .chain(follows_symbols.iter().map(|x| format!("symbol '{}'", x)))
+                    .collect();
+                write!(
+                    f,
+                    "Vram class '{}' is placed after the end of {}",
+                    vram_class,
+                    follows.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// A file of the explained segment.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileExplanation {
+    /// The entry of the document of this file.
+    pub entry: EntryPath,
+    /// The file using the same syntax used on the linker script, so archive
+    /// members are written as `path/to/lib.a:member.o`. Groups are described
+    /// by their `dir`.
+    pub file: String,
+    pub reason: EmitReason,
+    /// Each section this file contributes, along the section of the segment
+    /// it is placed on. Empty if the file is not emitted.
+    pub sections: Vec<(String, String)>,
+}
+
+/// The resolved placement of a segment for the given runtime settings.
+///
+/// See [`Document::explain_segment`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SegmentExplanation {
+    pub segment: String,
+    pub reason: EmitReason,
+    /// The chain of placements determining the vram address of the segment,
+    /// starting with the segment itself. Empty if the segment is not emitted.
+    pub placement: Vec<PlacementStep>,
+    /// Every file of the segment, including the files of the groups. The
+    /// files of excluded groups are not listed.
+    pub files: Vec<FileExplanation>,
+}
+
+impl fmt::Display for SegmentExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Segment '{}': {}", self.segment, self.reason)?;
+
+        if !self.placement.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Placement:")?;
+            for step in &self.placement {
+                writeln!(f, "    {}", step)?;
+            }
+        }
+
+        if !self.files.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Files:")?;
+        }
+        for file in &self.files {
+            writeln!(
+                f,
+                "    {} ({}): {}",
+                file.file,
+                file.entry.yaml_path(),
+                file.reason
+            )?;
+            for (section, placed_on) in &file.sections {
+                if section == placed_on {
+                    writeln!(f, "        {}", section)?;
+                } else {
+                    writeln!(f, "        {} -> {}", section, placed_on)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn explain_segment(
+    d: &Document,
+    rs: &RuntimeSettings,
+    name: &str,
+) -> Result<SegmentExplanation, SlinkyError> {
+    let (index, segment) = match d.segments.iter().enumerate().find(|(_, x)| x.name == name) {
+        Some(x) => x,
+        None => {
+            return Err(SlinkyError::MissingSegment {
+                segment: name.to_string(),
+            })
+        }
+    };
+
+    let reason = segment_reason(rs, segment);
+    if !reason.is_emitted() {
+        return Ok(SegmentExplanation {
+            segment: segment.name.clone(),
+            reason,
+            placement: Vec::new(),
+            files: Vec::new(),
+        });
+    }
+
+    let mut base_path = d.settings.base_path_escaped(rs)?;
+    base_path.push(segment.dir_escaped(rs)?);
+
+    let mut files = Vec::new();
+    explain_files(
+        rs,
+        segment,
+        &segment.files,
+        &[EntryPathComponent::Segment {
+            index,
+            name: segment.name.clone(),
+        }],
+        &base_path,
+        &mut files,
+    )?;
+
+    Ok(SegmentExplanation {
+        segment: segment.name.clone(),
+        reason,
+        placement: explain_placement(d, rs, index),
+        files,
+    })
+}
+
+fn segment_reason(rs: &RuntimeSettings, segment: &Segment) -> EmitReason {
+    EmitReason::new(
+        rs,
+        &segment.exclude_if_any,
+        &segment.exclude_if_all,
+        &segment.include_if_any,
+        &segment.include_if_all,
+    )
+}
+
+/// Mirrors how the [`LinkerWriter`](crate::LinkerWriter) picks the vram
+/// address of each segment, following the chain until a fixed address, a
+/// symbol or the start of the script is reached.
+fn explain_placement(d: &Document, rs: &RuntimeSettings, index: usize) -> Vec<PlacementStep> {
+    let emitted = |i: &usize| segment_reason(rs, &d.segments[*i]).is_emitted();
+
+    let mut steps = Vec::new();
+    let mut visited = Vec::new();
+    let mut current = index;
+
+    while !visited.contains(&current) {
+        visited.push(current);
+        let segment = &d.segments[current];
+
+        if let Some(overlay_group) = &segment.overlay_group {
+            // Only consecutive segments are grouped together
+            let first = (0..=current)
+                .rev()
+                .take_while(|i| d.segments[*i].overlay_group.as_ref() == Some(overlay_group))
+                .filter(emitted)
+                .last()
+                .unwrap_or(current);
+
+            if first != current {
+                steps.push(PlacementStep::OverlayGroup {
+                    segment: segment.name.clone(),
+                    overlay_group: overlay_group.clone(),
+                    first: d.segments[first].name.clone(),
+                });
+                current = first;
+                continue;
+            }
+        }
+
+        if let Some(vram) = segment.fixed_vram {
+            steps.push(PlacementStep::FixedVram {
+                segment: segment.name.clone(),
+                vram,
+            });
+        } else if let Some(symbol) = &segment.fixed_symbol {
+            steps.push(PlacementStep::FixedSymbol {
+                segment: segment.name.clone(),
+                symbol: symbol.clone(),
+            });
+        } else if let Some(follows) = &segment.follows_segment {
+            steps.push(PlacementStep::FollowsSegment {
+                segment: segment.name.clone(),
+                follows: follows.clone(),
+            });
+            if let Some(i) = d.segments.iter().position(|x| x.name == *follows) {
+                current = i;
+                continue;
+            }
+        } else if let Some(vram_class_name) = &segment.vram_class {
+            steps.push(PlacementStep::VramClass {
+                segment: segment.name.clone(),
+                vram_class: vram_class_name.clone(),
+            });
+            if let Some(vram_class) = d.vram_classes.iter().find(|x| x.name == *vram_class_name) {
+                if let Some(vram) = vram_class.fixed_vram {
+                    steps.push(PlacementStep::VramClassFixedVram {
+                        vram_class: vram_class.name.clone(),
+                        vram,
+                    });
+                } else if let Some(symbol) = &vram_class.fixed_symbol {
+                    steps.push(PlacementStep::VramClassFixedSymbol {
+                        vram_class: vram_class.name.clone(),
+                        symbol: symbol.clone(),
+                    });
+                } else {
+                    steps.push(PlacementStep::VramClassFollows {
+                        vram_class: vram_class.name.clone(),
+                        follows_classes: vram_class.follows_classes.clone(),
+                        follows_symbols: vram_class.follows_symbols.clone(),
+                    });
+                }
+            }
+        } else {
+            match (0..current).rev().find(emitted) {
+                Some(previous) => {
+                    steps.push(PlacementStep::PreviousSegment {
+                        segment: segment.name.clone(),
+                        previous: d.segments[previous].name.clone(),
+                    });
+                    current = previous;
+                    continue;
+                }
+                None => steps.push(PlacementStep::FirstSegment {
+                    segment: segment.name.clone(),
+                }),
+            }
+        }
+
+        break;
+    }
+
+    steps
+}
+
+fn explain_files(
+    rs: &RuntimeSettings,
+    segment: &Segment,
+    files: &[FileInfo],
+    parent_path: &[EntryPathComponent],
+    base_path: &EscapedPath,
+    out: &mut Vec<FileExplanation>,
+) -> Result<(), SlinkyError> {
+    for (index, file) in files.iter().enumerate() {
+        let mut path = parent_path.to_vec();
+        path.push(EntryPathComponent::File { index });
+
+        let reason = EmitReason::new(
+            rs,
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+            &file.include_if_any,
+            &file.include_if_all,
+        );
+        let emitted = reason.is_emitted();
+
+        let mut file_path = base_path.clone();
+        let description = match file.kind {
+            FileKind::Object | FileKind::Binary => {
+                file_path.push(file.path_escaped(rs)?);
+                file_path.to_string()
+            }
+            FileKind::Archive => {
+                file_path.push(file.path_escaped(rs)?);
+                format!("{}:{}", file_path, file.subfile)
+            }
+            FileKind::Group => {
+                file_path.push(file.dir_escaped(rs)?);
+                format!("group {}", file_path)
+            }
+            FileKind::Pad => format!(". += 0x{:X};", file.pad_amount),
+            FileKind::LinkerOffset => format!("linker offset {}", file.linker_offset_name),
+            FileKind::Align => "align".to_string(),
+        };
+
+        let sections = if emitted {
+            file_sections(segment, file)
+        } else {
+            Vec::new()
+        };
+
+        out.push(FileExplanation {
+            entry: EntryPath {
+                components: path.clone(),
+            },
+            file: description,
+            reason,
+            sections,
+        });
+
+        if emitted && file.kind == FileKind::Group {
+            explain_files(rs, segment, &file.files, &path, &file_path, out)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The sections the file contributes and the section of the segment each
+/// one is placed on, following its `section_order` and the
+/// `sections_subgroups` of the segment.
+fn file_sections(segment: &Segment, file: &FileInfo) -> Vec<(String, String)> {
+    let mut sections = Vec::new();
+
+    match file.kind {
+        FileKind::Object | FileKind::Archive => {
+            for section in segment
+                .alloc_sections
+                .iter()
+                .chain(&segment.noload_sections)
+            {
+                let placed_on = file.section_order.get(section).unwrap_or(section);
+                push_section_with_subgroups(segment, section, placed_on, &mut sections);
+            }
+        }
+        FileKind::Pad | FileKind::Binary | FileKind::LinkerOffset | FileKind::Align => {
+            for section in &file.sections {
+                sections.push((section.clone(), section.clone()));
+            }
+        }
+        FileKind::Group => {}
+    }
+
+    sections
+}
+
+fn push_section_with_subgroups(
+    segment: &Segment,
+    section: &str,
+    placed_on: &str,
+    sections: &mut Vec<(String, String)>,
+) {
+    if sections.iter().any(|(x, _)| x == section) {
+        return;
+    }
+    sections.push((section.to_string(), placed_on.to_string()));
+
+    if let Some(subgroup) = segment.sections_subgroups.get(section) {
+        for other in subgroup {
+            push_section_with_subgroups(segment, other, placed_on, sections);
+        }
+    }
+}
@@ -1,67 +1,185 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
 };
 
 use crate::{
-    absent_nullable::AbsentNullable, file_kind::FileKind, traits::Serial, EscapedPath,
-    KeepSections, RuntimeSettings, Settings, SlinkyError,
+    absent_nullable::AbsentNullable,
+    file_kind::FileKind,
+    ignored_metadata::IgnoredMetadata,
+    traits::{unserialize_entries, Serial},
+    utils, EntryPathComponent, EscapedPath, KeepSections, RuntimeSettings, Segment, Settings,
+    SlinkyError,
 };
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct FileInfo {
+    // Only the fields relevant to the file's kind are serialized
+    #[serde(skip_serializing_if = "utils::is_empty_path")]
     pub path: PathBuf,
 
     pub kind: FileKind,
 
     // Used for archives
+    #[serde(skip_serializing_if = "subfile_is_default")]
     pub subfile: String,
+    /// Every member of the archive is placed here and kept, instead of only
+    /// the ones selected through `subfile`.
+    #[serde(skip_serializing_if = "utils::is_false")]
+    pub whole_archive: bool,
 
+    #[serde(skip_serializing_if = "utils::is_zero")]
     pub pad_amount: u32,
-    pub section: String,
-
+    #[serde(skip_serializing_if = "utils::is_zero")]
+    pub align_amount: u32,
+    #[serde(
+        rename = "section",
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_sections"
+    )]
+    pub sections: Vec<String>,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub linker_offset_name: String,
 
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "utils::serialize_sorted_map"
+    )]
     pub section_order: HashMap<String, String>,
 
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "utils::serialize_sorted_map"
+    )]
+    pub subsection_order: HashMap<String, Vec<String>>,
+
+    /// Input section patterns of this file which are emitted verbatim within
+    /// another section of the segment. Each key is the pattern and its value
+    /// the section where it is placed.
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "utils::serialize_sorted_map"
+    )]
+    pub section_rename: HashMap<String, String>,
+
+    /// The size each section of this file is padded to. The linking fails if
+    /// the section of the file is bigger than it.
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "utils::serialize_sorted_map"
+    )]
+    pub pad_to_size: HashMap<String, u32>,
+
+    /// The known alignment of each section of this file. Used to reorder the
+    /// files of the sections with `order_by: alignment_desc`.
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "utils::serialize_sorted_map"
+    )]
+    pub alignment: HashMap<String, u32>,
+
+    /// Sections this file must not have. The linking fails if any of them is
+    /// not empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub forbid_sections: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_value: Option<u32>,
+
+    /// Overrides the segment's `wildcard_sections` and `sections_wildcard`
+    /// for this file. Groups pass it down to the files which don't set it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wildcard_sections: Option<bool>,
+
+    /// Overrides the segment's `emit_file_symbols` for this file. Groups pass
+    /// it down to the files which don't set it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emit_file_symbols: Option<bool>,
+
     // Used for groups
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub files: Vec<FileInfo>,
+    #[serde(skip_serializing_if = "utils::is_empty_path")]
     pub dir: PathBuf,
 
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_all: Vec<(String, String)>,
 
+    /// If true then this file is only emitted on the matching flavor of the
+    /// linker script and it is dropped from the shiftable one.
+    #[serde(skip_serializing_if = "utils::is_false")]
+    pub only_matching: bool,
+
     // The default value of the following members come from Segment
     // (or the upper FileInfo if this file is part of a group)
+    #[serde(skip_serializing_if = "KeepSections::is_absent")]
     pub keep_sections: KeepSections,
 }
 
+fn subfile_is_default(subfile: &str) -> bool {
+    subfile.is_empty() || subfile == "*"
+}
+
+// A single section is written as a plain string, like most documents do
+fn serialize_sections<S>(sections: &[String], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match sections {
+        [section] => serializer.serialize_str(section),
+        _ => sections.serialize(serializer),
+    }
+}
+
 impl FileInfo {
     pub fn new_object(p: PathBuf) -> Self {
         Self {
             path: p,
             kind: FileKind::Object,
             subfile: "".into(),
+            whole_archive: false,
             pad_amount: 0,
-            section: "".into(),
+            align_amount: 0,
+            sections: Vec::new(),
             linker_offset_name: "".into(),
             section_order: HashMap::new(),
+            subsection_order: HashMap::new(),
+            section_rename: HashMap::new(),
+            pad_to_size: HashMap::new(),
+            alignment: HashMap::new(),
+            forbid_sections: Vec::new(),
+            fill_value: None,
+            wildcard_sections: None,
+            emit_file_symbols: None,
             files: Vec::new(),
             dir: PathBuf::new(),
             include_if_any: Vec::new(),
             include_if_all: Vec::new(),
             exclude_if_any: Vec::new(),
             exclude_if_all: Vec::new(),
+            only_matching: false,
             keep_sections: KeepSections::default(),
         }
     }
 
+    /// Whether this file is placed on the given section. Only meaningful for
+    /// the kinds which use [`sections`](FileInfo::sections).
+    #[must_use]
+    pub fn targets_section(&self, section: &str) -> bool {
+        self.sections.iter().any(|x| x == section)
+    }
+
     pub fn pass_down_keep_sections(&mut self, keep_sections: &KeepSections) {
         if *keep_sections == KeepSections::Absent {
             return;
@@ -77,6 +195,60 @@ impl FileInfo {
             }
         }
     }
+
+    pub(crate) fn pass_down_wildcard_sections(&mut self, wildcard_sections: bool) {
+        if self.wildcard_sections.is_none() {
+            self.wildcard_sections = Some(wildcard_sections);
+
+            if self.kind == FileKind::Group {
+                self.files
+                    .iter_mut()
+                    .for_each(|f| f.pass_down_wildcard_sections(wildcard_sections));
+            }
+        }
+    }
+
+    pub(crate) fn pass_down_emit_file_symbols(&mut self, emit_file_symbols: bool) {
+        if self.emit_file_symbols.is_none() {
+            self.emit_file_symbols = Some(emit_file_symbols);
+
+            if self.kind == FileKind::Group {
+                self.files
+                    .iter_mut()
+                    .for_each(|f| f.pass_down_emit_file_symbols(emit_file_symbols));
+            }
+        }
+    }
+
+    /// Drops every file of this group which is only emitted on the matching
+    /// flavor of the linker script.
+    pub(crate) fn retain_shiftable_files(&mut self) {
+        self.files.retain(|f| !f.only_matching);
+        self.files
+            .iter_mut()
+            .for_each(|f| f.retain_shiftable_files());
+    }
+
+    /// Whether the sections of this file are bracketed with their own start
+    /// and end symbols.
+    #[must_use]
+    pub fn emits_file_symbols(&self, segment: &Segment) -> bool {
+        matches!(self.kind, FileKind::Object | FileKind::Archive)
+            && self.emit_file_symbols.unwrap_or(segment.emit_file_symbols)
+    }
+
+    /// Whether the input section spec of this file for the given section
+    /// must use a wildcard (`*`) as suffix.
+    #[must_use]
+    pub fn uses_wildcard(&self, segment: &Segment, section: &str) -> bool {
+        self.wildcard_sections.unwrap_or_else(|| {
+            segment
+                .sections_wildcard
+                .get(section)
+                .copied()
+                .unwrap_or(segment.wildcard_sections)
+        })
+    }
 }
 
 impl FileInfo {
@@ -89,7 +261,16 @@ impl FileInfo {
     }
 }
 
+/// The `section` of a file, which can be either a single section or a list of
+/// them.
 #[derive(Deserialize, PartialEq, Debug)]
+#[serde(untagged)]
+pub(crate) enum FileSectionsSerial {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Deserialize, PartialEq, Debug, Default)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct FileInfoSerial {
     #[serde(default)]
@@ -100,11 +281,15 @@ pub(crate) struct FileInfoSerial {
 
     #[serde(default)]
     pub subfile: AbsentNullable<String>,
+    #[serde(default)]
+    pub whole_archive: AbsentNullable<bool>,
 
     #[serde(default)]
     pub pad_amount: AbsentNullable<u32>,
     #[serde(default)]
-    pub section: AbsentNullable<String>,
+    pub align_amount: AbsentNullable<u32>,
+    #[serde(default)]
+    pub section: AbsentNullable<FileSectionsSerial>,
 
     #[serde(default)]
     pub linker_offset_name: AbsentNullable<String>,
@@ -112,6 +297,30 @@ pub(crate) struct FileInfoSerial {
     #[serde(default)]
     pub section_order: AbsentNullable<HashMap<String, String>>,
 
+    #[serde(default)]
+    pub subsection_order: AbsentNullable<HashMap<String, Vec<String>>>,
+
+    #[serde(default)]
+    pub section_rename: AbsentNullable<HashMap<String, String>>,
+
+    #[serde(default)]
+    pub pad_to_size: AbsentNullable<HashMap<String, u32>>,
+
+    #[serde(default)]
+    pub alignment: AbsentNullable<HashMap<String, u32>>,
+
+    #[serde(default)]
+    pub forbid_sections: AbsentNullable<Vec<String>>,
+
+    #[serde(default)]
+    pub fill_value: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub wildcard_sections: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub emit_file_symbols: AbsentNullable<bool>,
+
     #[serde(default)]
     pub files: AbsentNullable<Vec<FileInfoSerial>>,
     #[serde(default)]
@@ -126,8 +335,14 @@ pub(crate) struct FileInfoSerial {
     #[serde(default)]
     pub exclude_if_all: AbsentNullable<Vec<(String, String)>>,
 
+    #[serde(default)]
+    pub only_matching: AbsentNullable<bool>,
+
     #[serde(default)]
     pub keep_sections: KeepSections,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
 }
 
 impl Serial for FileInfoSerial {
@@ -137,7 +352,7 @@ impl Serial for FileInfoSerial {
         // Since a `kind` can be deduced from a `path` (which requires a `path`) then we need to do both simultaneously
         let (path, kind) = match self.kind.get_non_null_no_default("kind")? {
             Some(k) => match k {
-                FileKind::Object | FileKind::Archive => {
+                FileKind::Object | FileKind::Archive | FileKind::Binary => {
                     let p = self.path.get("path")?;
 
                     if p == Path::new("") {
@@ -148,11 +363,13 @@ impl Serial for FileInfoSerial {
 
                     (p, k)
                 }
-                FileKind::Pad | FileKind::LinkerOffset | FileKind::Group => {
+                FileKind::Pad | FileKind::Align | FileKind::LinkerOffset | FileKind::Group => {
                     // doesn't allow paths
                     if self.path.has_value() {
                         return Err(SlinkyError::InvalidFieldCombo {
-                            field1: "`kind: pad`, `kind: linker_offset` or `kind: group`".into(),
+                            field1:
+                                "`kind: pad`, `kind: align`, `kind: linker_offset` or `kind: group`"
+                                    .into(),
                             field2: "path".into(),
                         });
                     }
@@ -169,13 +386,43 @@ impl Serial for FileInfoSerial {
                     });
                 }
 
-                let k = FileKind::from_path(&p);
+                let k = FileKind::from_path_with_overrides(&p, &settings.file_kind_overrides)?;
                 (p, k)
             }
         };
 
+        let whole_archive = match kind {
+            FileKind::Object
+            | FileKind::LinkerOffset
+            | FileKind::Pad
+            | FileKind::Align
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.whole_archive.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "whole_archive".into(),
+                        field2: "non `kind: archive`".into(),
+                    });
+                }
+                false
+            }
+            FileKind::Archive => self.whole_archive.get_non_null("whole_archive", || false)?,
+        };
+        if whole_archive && self.subfile.has_value() {
+            // A single member can't be the whole archive
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "subfile".into(),
+                field2: "whole_archive".into(),
+            });
+        }
+
         let subfile = match kind {
-            FileKind::Object | FileKind::LinkerOffset | FileKind::Pad | FileKind::Group => {
+            FileKind::Object
+            | FileKind::LinkerOffset
+            | FileKind::Pad
+            | FileKind::Align
+            | FileKind::Group
+            | FileKind::Binary => {
                 if self.subfile.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "subfile".into(),
@@ -188,7 +435,12 @@ impl Serial for FileInfoSerial {
         };
 
         let pad_amount = match kind {
-            FileKind::Object | FileKind::LinkerOffset | FileKind::Archive | FileKind::Group => {
+            FileKind::Object
+            | FileKind::LinkerOffset
+            | FileKind::Archive
+            | FileKind::Align
+            | FileKind::Group
+            | FileKind::Binary => {
                 if self.pad_amount.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "pad_amount".into(),
@@ -200,21 +452,71 @@ impl Serial for FileInfoSerial {
             FileKind::Pad => self.pad_amount.get("pad_amount")?,
         };
 
-        let section = match kind {
+        let align_amount = match kind {
+            FileKind::Object
+            | FileKind::LinkerOffset
+            | FileKind::Archive
+            | FileKind::Pad
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.align_amount.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "align_amount".into(),
+                        field2: "non `kind: align`".into(),
+                    });
+                }
+                0
+            }
+            FileKind::Align => self.align_amount.get("align_amount")?,
+        };
+
+        let sections = match kind {
             FileKind::Object | FileKind::Archive | FileKind::Group => {
                 if self.section.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "section".into(),
-                        field2: "non `kind: pad or kind: linker_offset`".into(),
+                        field2: "non `kind: pad`, `kind: align`, `kind: linker_offset` or `kind: binary`".into(),
                     });
                 }
-                "".into()
+                Vec::new()
+            }
+            FileKind::Pad | FileKind::Align | FileKind::LinkerOffset => {
+                let sections = match self.section.get("section")? {
+                    FileSectionsSerial::Single(section) => vec![section],
+                    FileSectionsSerial::Multiple(sections) => sections,
+                };
+                if sections.is_empty() {
+                    return Err(SlinkyError::EmptyValue {
+                        name: "section".to_string(),
+                    });
+                }
+                sections
             }
-            FileKind::Pad | FileKind::LinkerOffset => self.section.get("section")?,
+            FileKind::Binary => match self.section.get_non_null("section", || {
+                FileSectionsSerial::Single(".data".to_string())
+            })? {
+                FileSectionsSerial::Single(section) if section.is_empty() => {
+                    return Err(SlinkyError::EmptyValue {
+                        name: "section".to_string(),
+                    })
+                }
+                FileSectionsSerial::Single(section) => vec![section],
+                FileSectionsSerial::Multiple(_) => {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "a list of sections".into(),
+                        field2: "`kind: binary`".into(),
+                    })
+                }
+            },
         };
 
         let linker_offset_name = match kind {
-            FileKind::Object | FileKind::Pad | FileKind::Archive | FileKind::Group => {
+            FileKind::Object
+            | FileKind::Pad
+            | FileKind::Align
+            | FileKind::Archive
+            | FileKind::Group
+            | FileKind::Binary => {
                 if self.linker_offset_name.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "linker_offset_name".into(),
@@ -227,7 +529,11 @@ impl Serial for FileInfoSerial {
         };
 
         let section_order = match kind {
-            FileKind::Pad | FileKind::LinkerOffset | FileKind::Group => {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
                 if self.section_order.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "section_order".into(),
@@ -241,8 +547,184 @@ impl Serial for FileInfoSerial {
                 .get_non_null("section_order", HashMap::default)?,
         };
 
+        let subsection_order = match kind {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.subsection_order.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "subsection_order".into(),
+                        field2: "non `kind: object` or `kind: archive`".into(),
+                    });
+                }
+                HashMap::default()
+            }
+            FileKind::Object | FileKind::Archive => self
+                .subsection_order
+                .get_non_null("subsection_order", HashMap::default)?,
+        };
+
+        let section_rename = match kind {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.section_rename.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "section_rename".into(),
+                        field2: "non `kind: object` or `kind: archive`".into(),
+                    });
+                }
+                HashMap::default()
+            }
+            FileKind::Object | FileKind::Archive => self
+                .section_rename
+                .get_non_null("section_rename", HashMap::default)?,
+        };
+        if section_rename
+            .iter()
+            .any(|(pattern, section)| pattern.is_empty() || section.is_empty())
+        {
+            return Err(SlinkyError::EmptyValue {
+                name: "section_rename".into(),
+            });
+        }
+
+        let pad_to_size = match kind {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.pad_to_size.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "pad_to_size".into(),
+                        field2: "non `kind: object` or `kind: archive`".into(),
+                    });
+                }
+                HashMap::default()
+            }
+            FileKind::Object | FileKind::Archive => self
+                .pad_to_size
+                .get_non_null("pad_to_size", HashMap::default)?,
+        };
+
+        let alignment = match kind {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.alignment.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "alignment".into(),
+                        field2: "non `kind: object` or `kind: archive`".into(),
+                    });
+                }
+                HashMap::default()
+            }
+            FileKind::Object | FileKind::Archive => {
+                self.alignment.get_non_null("alignment", HashMap::default)?
+            }
+        };
+        if let Some(value) = alignment.values().find(|x| !x.is_power_of_two()) {
+            return Err(SlinkyError::InvalidFieldValue {
+                name: "alignment".into(),
+                value: format!("0x{:X}", value),
+                allowed: "powers of two".into(),
+            });
+        }
+
+        let forbid_sections = match kind {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.forbid_sections.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "forbid_sections".into(),
+                        field2: "non `kind: object` or `kind: archive`".into(),
+                    });
+                }
+                Vec::new()
+            }
+            FileKind::Object | FileKind::Archive => self
+                .forbid_sections
+                .get_non_null("forbid_sections", Vec::new)?,
+        };
+        if forbid_sections.iter().any(|x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "forbid_sections".into(),
+            });
+        }
+        // A forbidden section can't be padded to any size
+        if forbid_sections.iter().any(|x| pad_to_size.contains_key(x)) {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "forbid_sections".into(),
+                field2: "pad_to_size".into(),
+            });
+        }
+
+        let fill_value = match kind {
+            FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Group
+            | FileKind::Binary => {
+                if self.fill_value.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "fill_value".into(),
+                        field2: "non `kind: object` or `kind: archive`".into(),
+                    });
+                }
+                None
+            }
+            FileKind::Object | FileKind::Archive => {
+                self.fill_value.get_non_null_no_default("fill_value")?
+            }
+        };
+
+        let wildcard_sections = match kind {
+            FileKind::Pad | FileKind::Align | FileKind::LinkerOffset | FileKind::Binary => {
+                if self.wildcard_sections.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "wildcard_sections".into(),
+                        field2: "non `kind: object`, `kind: archive` or `kind: group`".into(),
+                    });
+                }
+                None
+            }
+            FileKind::Object | FileKind::Archive | FileKind::Group => self
+                .wildcard_sections
+                .get_non_null_no_default("wildcard_sections")?,
+        };
+
+        let emit_file_symbols = match kind {
+            FileKind::Pad | FileKind::Align | FileKind::LinkerOffset | FileKind::Binary => {
+                if self.emit_file_symbols.has_value() {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "emit_file_symbols".into(),
+                        field2: "non `kind: object`, `kind: archive` or `kind: group`".into(),
+                    });
+                }
+                None
+            }
+            FileKind::Object | FileKind::Archive | FileKind::Group => self
+                .emit_file_symbols
+                .get_non_null_no_default("emit_file_symbols")?,
+        };
+
         let mut files = match kind {
-            FileKind::Object | FileKind::Archive | FileKind::Pad | FileKind::LinkerOffset => {
+            FileKind::Object
+            | FileKind::Archive
+            | FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Binary => {
                 if self.files.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "files".into(),
@@ -251,11 +733,20 @@ impl Serial for FileInfoSerial {
                 }
                 Vec::default()
             }
-            FileKind::Group => self.files.get("files")?.unserialize(settings)?,
+            FileKind::Group => {
+                unserialize_entries(self.files.get("files")?, settings, |index, _| {
+                    EntryPathComponent::File { index }
+                })?
+            }
         };
 
         let dir = match kind {
-            FileKind::Object | FileKind::Archive | FileKind::Pad | FileKind::LinkerOffset => {
+            FileKind::Object
+            | FileKind::Archive
+            | FileKind::Pad
+            | FileKind::Align
+            | FileKind::LinkerOffset
+            | FileKind::Binary => {
                 if self.dir.has_value() {
                     return Err(SlinkyError::InvalidFieldCombo {
                         field1: "dir".into(),
@@ -280,6 +771,15 @@ impl Serial for FileInfoSerial {
             .exclude_if_all
             .get_non_null_not_empty("exclude_if_all", Vec::new)?;
 
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
+        let only_matching = self.only_matching.get_non_null("only_matching", || false)?;
+
         let keep_sections = self.keep_sections;
 
         // Pass down the current `keep_sections` to any file of this group that may not have defined it
@@ -289,20 +789,45 @@ impl Serial for FileInfoSerial {
                 .for_each(|f| f.pass_down_keep_sections(&keep_sections));
         }
 
+        // Same for `wildcard_sections`
+        if let Some(wildcard_sections) = wildcard_sections {
+            files
+                .iter_mut()
+                .for_each(|f| f.pass_down_wildcard_sections(wildcard_sections));
+        }
+
+        // Same for `emit_file_symbols`
+        if let Some(emit_file_symbols) = emit_file_symbols {
+            files
+                .iter_mut()
+                .for_each(|f| f.pass_down_emit_file_symbols(emit_file_symbols));
+        }
+
         Ok(Self::Output {
             path,
             kind,
             subfile,
+            whole_archive,
             pad_amount,
-            section,
+            align_amount,
+            sections,
             linker_offset_name,
             section_order,
+            subsection_order,
+            section_rename,
+            pad_to_size,
+            alignment,
+            forbid_sections,
+            fill_value,
+            wildcard_sections,
+            emit_file_symbols,
             files,
             dir,
             include_if_any,
             include_if_all,
             exclude_if_any,
             exclude_if_all,
+            only_matching,
             keep_sections,
         })
     }
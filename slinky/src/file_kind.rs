@@ -1,18 +1,22 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, PartialEq, Debug, Clone)]
+use crate::SlinkyError;
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum FileKind {
     Object,
     Archive,
     Pad,
+    Align,
     LinkerOffset,
     Group,
+    Binary,
 }
 
 impl FileKind {
@@ -27,4 +31,50 @@ impl FileKind {
             },
         }
     }
+
+    /// Like [`FileKind::from_path`], but looking up the extension on the
+    /// `file_kind_overrides` setting first.
+    ///
+    /// Once any override is registered the classification becomes strict:
+    /// extensions which are neither listed there nor known by slinky are
+    /// errors instead of being treated as objects.
+    pub fn from_path_with_overrides(
+        path: &Path,
+        overrides: &HashMap<String, FileKind>,
+    ) -> Result<Self, SlinkyError> {
+        let ext = match path.extension().and_then(|x| x.to_str()) {
+            None => return Ok(Self::Object),
+            Some(ext) => ext,
+        };
+
+        if let Some(kind) = overrides.get(ext) {
+            return Ok(kind.clone());
+        }
+        match ext {
+            "o" | "a" => Ok(Self::from_path(path)),
+            _ if overrides.is_empty() => Ok(Self::Object),
+            _ => Err(SlinkyError::UnknownFileExtension {
+                path: path.to_path_buf(),
+                extension: ext.to_string(),
+            }),
+        }
+    }
+
+    /// The name of the kind, as written on the yaml file.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Object => "object",
+            Self::Archive => "archive",
+            Self::Pad => "pad",
+            Self::Align => "align",
+            Self::LinkerOffset => "linker_offset",
+            Self::Group => "group",
+            Self::Binary => "binary",
+        }
+    }
+
+    /// Whether the files of this kind point to a `path`.
+    pub(crate) fn has_path(&self) -> bool {
+        matches!(self, Self::Object | Self::Archive | Self::Binary)
+    }
 }
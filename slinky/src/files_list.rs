@@ -0,0 +1,69 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{absent_nullable::AbsentNullable, file_info::FileInfoSerial, utils, SlinkyError};
+
+/// An entry of a json files list, either just the path of the file or the
+/// same fields as the `files` of a segment.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FilesListEntry {
+    Path(PathBuf),
+    File(Box<FileInfoSerial>),
+}
+
+/// Reads the list of files of a segment from the `files_from` file.
+///
+/// A `.json` file has a list of entries, each one either a path or an object
+/// like the ones of `files`. Any other file is plain text, with a path per
+/// line. Empty lines and lines starting with `#` are skipped.
+pub(crate) fn read_files_list(path: &Path) -> Result<Vec<FileInfoSerial>, SlinkyError> {
+    let contents = match utils::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(SlinkyError::FailedFileOpen {
+                path: path.to_path_buf(),
+                description: e.to_string(),
+            })
+        }
+    };
+
+    if path.extension().map_or(false, |x| x == "json") {
+        // json is valid yaml
+        let entries: Vec<FilesListEntry> = match serde_yaml::from_str(&contents) {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(SlinkyError::FailedFilesListParsing {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                })
+            }
+        };
+
+        return Ok(entries
+            .into_iter()
+            .map(|entry| match entry {
+                FilesListEntry::Path(p) => path_entry(p),
+                FilesListEntry::File(file) => *file,
+            })
+            .collect());
+    }
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| path_entry(PathBuf::from(line)))
+        .collect())
+}
+
+fn path_entry(path: PathBuf) -> FileInfoSerial {
+    FileInfoSerial {
+        path: AbsentNullable::Value(path),
+        ..Default::default()
+    }
+}
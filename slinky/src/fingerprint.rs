@@ -0,0 +1,68 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use crate::{version, Document, RuntimeSettings, ScriptFlavor, SlinkyError};
+
+/// Text preceding the fingerprint on the comment of the generated files.
+pub(crate) const FINGERPRINT_MARKER: &str = "slinky-fingerprint: ";
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+const FNV_PRIME: u64 = 0x100000001B3;
+
+/// FNV-1a, used instead of the hashers of the standard library since those
+/// are not guaranteed to give the same result between Rust versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+/// Hashes the effective document, the custom options and the version of
+/// slinky.
+pub(crate) fn fingerprint(d: &Document, rs: &RuntimeSettings) -> Result<String, SlinkyError> {
+    let mut hasher = Fnv1a::new();
+
+    hasher.write(version::Version::current().to_string().as_bytes());
+    hasher.write(b"\n");
+    hasher.write(d.effective_yaml(rs)?.as_bytes());
+
+    let mut options: Vec<(&String, &String)> = rs.custom_options().iter().collect();
+    options.sort();
+    for (key, value) in options {
+        hasher.write(format!("\n{}={}", key, value).as_bytes());
+    }
+    // Keeps the fingerprints of the matching scripts unchanged
+    if rs.flavor() == ScriptFlavor::Shiftable {
+        hasher.write(b"\nflavor=shiftable");
+    }
+
+    Ok(format!("{:016x}", hasher.0))
+}
+
+/// Reads back the fingerprint embedded on the contents of a generated file.
+///
+/// `None` if the file has no fingerprint comment.
+pub fn read_fingerprint(contents: &str) -> Option<&str> {
+    contents.lines().find_map(|line| {
+        let start = line.find(FINGERPRINT_MARKER)? + FINGERPRINT_MARKER.len();
+        let rest = &line[start..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            None
+        } else {
+            Some(&rest[..len])
+        }
+    })
+}
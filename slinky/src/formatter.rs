@@ -0,0 +1,1170 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::collections::{HashMap, HashSet};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::{document::DocumentSerial, SlinkyError};
+
+/// The result of formatting a yaml document with
+/// [`crate::Document::format_yaml_str`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormattedYaml {
+    /// The canonical yaml of the document.
+    pub contents: String,
+
+    /// The lines (starting at 1) of the comments of the original yaml which
+    /// could not be carried over to the formatted one.
+    pub dropped_comments: Vec<usize>,
+}
+
+/// Flow style entries longer than this are written in block style instead.
+const MAX_FLOW_LINE_LENGTH: usize = 100;
+
+/// Integers which are counts instead of addresses or sizes, so they are kept
+/// in decimal when there is no original spelling for them.
+const DECIMAL_KEYS: &[&str] = &[
+    "hex_address_width",
+    "offset",
+    "rom_order",
+    "script_blank_lines_between_groups",
+    "script_blank_lines_between_segments",
+    "script_indent_width",
+];
+
+/// Characters which can't be used on plain scalars inside flow collections.
+const FLOW_INDICATORS: [char; 5] = [',', '[', ']', '{', '}'];
+
+const DOCUMENT_KEYS: &[&str] = &[
+    "required_slinky_version",
+    "include",
+    "settings",
+    "vram_classes",
+    "segment_templates",
+    "segments",
+    "entry",
+    "startup_file",
+    "extra_inputs",
+    "symbol_assignments",
+    "required_symbols",
+    "asserts",
+    "pre_sections_symbols",
+    "post_sections_symbols",
+    "post_sections_asserts",
+    "runtime_options",
+    "targets",
+    "imports",
+    "raw",
+    "metadata",
+];
+
+pub(crate) const SETTINGS_KEYS: &[&str] = &[
+    "preset",
+    "base_path",
+    "file_kind_overrides",
+    "valid_conditions",
+    "linker_symbols_style",
+    "hardcoded_gp_value",
+    "d_path",
+    "target_path",
+    "ninja_path",
+    "symbols_header_path",
+    "symbols_header_type",
+    "symbols_header_as_array",
+    "symbols_header_rom_path",
+    "symbols_header_linker_offsets_path",
+    "symbols_header_keep_user_additions",
+    "linker_script_banner",
+    "d_banner",
+    "symbols_header_banner",
+    "symbols_asm_path",
+    "symbols_asm_dialect",
+    "symbols_rust_path",
+    "vram_class_slack_symbols",
+    "vram_class_slack_report_path",
+    "memory_map_path",
+    "binary_rules_path",
+    "objects_list_path",
+    "toolchain_objects_path",
+    "symbols_manifest_path",
+    "segment_table_path",
+    "segment_table_header_path",
+    "common_script_path",
+    "gdb_script_path",
+    "bss_symbols",
+    "bss_symbols_start_name",
+    "bss_symbols_size_name",
+    "rom_model",
+    "rom_end_symbol",
+    "rom_size_symbol",
+    "total_bss_size_symbol",
+    "rom_size_alignment",
+    "rom_min_size",
+    "sections_allowlist",
+    "sections_allowlist_extra",
+    "emit_debug_sections",
+    "sections_denylist",
+    "discard_wildcard_section",
+    "discard",
+    "single_segment_mode",
+    "insert_after",
+    "insert_before",
+    "partial_scripts_folder",
+    "partial_build_segments_folder",
+    "partial_build_path",
+    "toolchain",
+    "alloc_sections",
+    "noload_sections",
+    "subalign",
+    "segment_start_align",
+    "segment_end_align",
+    "noload_start_align",
+    "section_start_align",
+    "section_end_align",
+    "sections_start_alignment",
+    "sections_end_alignment",
+    "sections_subalign",
+    "sections_header_align",
+    "wildcard_sections",
+    "sections_wildcard",
+    "input_section_patterns",
+    "fill_value",
+    "sections_fill_value",
+    "sections_order_by",
+    "sections_subgroups",
+    "keep_linker_offset_anchors",
+    "check_fixed_vram_overlaps",
+    "hex_uppercase",
+    "hex_address_width",
+    "hex_alignments",
+    "script_indent_width",
+    "script_indent_with_tabs",
+    "script_braces_on_same_line",
+    "script_blank_lines_between_segments",
+    "script_blank_lines_between_groups",
+    "script_trailing_newline",
+    "generated_symbols_provide",
+    "generated_symbols_hidden",
+    "generated_symbols_absolute",
+    "segment_size_symbols",
+    "sections_kind_symbols",
+    "sections_kind_size_symbols",
+    "section_symbols",
+    "section_size_symbols",
+    "search_paths",
+    "libraries",
+    "libraries_group",
+    "skip_empty_sections",
+    "deterministic_output",
+    "sort_dependencies",
+    "d_generated_files",
+    "check_archive_members",
+    "check_object_sections",
+    "sort_segments_by_follows",
+    "group_required_symbols",
+    "aligned_section_start_symbols",
+    "metadata",
+];
+
+pub(crate) const SEGMENT_KEYS: &[&str] = &[
+    "name",
+    "extends",
+    "symbol_name",
+    "fixed_vram",
+    "fixed_symbol",
+    "follows_segment",
+    "vram_class",
+    "dir",
+    "gp_info",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "alloc_sections",
+    "noload_sections",
+    "subalign",
+    "segment_start_align",
+    "segment_end_align",
+    "noload_start_align",
+    "section_start_align",
+    "section_end_align",
+    "sections_start_alignment",
+    "sections_end_alignment",
+    "sections_subalign",
+    "sections_header_align",
+    "wildcard_sections",
+    "sections_wildcard",
+    "fill_value",
+    "sections_fill_value",
+    "sections_order_by",
+    "sections_subgroups",
+    "keep_linker_offset_anchors",
+    "check_fixed_vram_overlaps",
+    "compressed",
+    "external",
+    "fragment_path",
+    "reserved_rom_size",
+    "toolchain",
+    "check_noload_contiguity",
+    "overlay_group",
+    "noload_merge",
+    "rom_order",
+    "group",
+    "comment",
+    "emit_file_symbols",
+    "suppress_rom_symbols",
+    "suppress_vram_symbols",
+    "suppress_symbols",
+    "only_matching_fields",
+    "keep_sections",
+    "metadata",
+    "files_from",
+    "files",
+];
+
+pub(crate) const FILE_KEYS: &[&str] = &[
+    "path",
+    "kind",
+    "subfile",
+    "whole_archive",
+    "pad_amount",
+    "align_amount",
+    "section",
+    "linker_offset_name",
+    "section_order",
+    "subsection_order",
+    "section_rename",
+    "pad_to_size",
+    "alignment",
+    "forbid_sections",
+    "fill_value",
+    "wildcard_sections",
+    "emit_file_symbols",
+    "dir",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "only_matching",
+    "keep_sections",
+    "metadata",
+    "files",
+];
+
+pub(crate) const VRAM_CLASS_KEYS: &[&str] = &[
+    "name",
+    "fixed_vram",
+    "fixed_symbol",
+    "follows_classes",
+    "follows_symbols",
+    "keep_sections",
+    "max_size",
+    "reserved",
+    "high_water_symbol",
+    "high_water_limit",
+    "no_cross_refs",
+    "metadata",
+];
+
+const GP_INFO_KEYS: &[&str] = &[
+    "section",
+    "offset",
+    "provide",
+    "hidden",
+    "small_data_sections",
+    "midpoint",
+    "check_range",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "metadata",
+];
+
+pub(crate) const SYMBOL_ASSIGNMENT_KEYS: &[&str] = &[
+    "name",
+    "value",
+    "alias_of",
+    "provide",
+    "hidden",
+    "placement",
+    "inside_segment",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "metadata",
+];
+
+pub(crate) const REQUIRED_SYMBOL_KEYS: &[&str] = &[
+    "name",
+    "expected_value",
+    "expected_range",
+    "provide_fallback",
+    "comment",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "metadata",
+];
+
+pub(crate) const ASSERT_KEYS: &[&str] = &[
+    "check",
+    "error_message",
+    "warning",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "metadata",
+];
+
+pub(crate) const RUNTIME_OPTION_KEYS: &[&str] = &["name", "values", "description", "metadata"];
+
+pub(crate) const TARGET_KEYS: &[&str] = &["name", "custom_options", "metadata"];
+
+pub(crate) const IMPORT_KEYS: &[&str] = &["path", "symbols", "metadata"];
+
+pub(crate) const RAW_BLOCK_KEYS: &[&str] = &[
+    "placement",
+    "after_segment",
+    "include_if_any",
+    "include_if_all",
+    "exclude_if_any",
+    "exclude_if_all",
+    "contents",
+    "metadata",
+];
+
+const SECTION_ENTRY_KEYS: &[&str] = &[
+    "name",
+    "start_align",
+    "end_align",
+    "fill_value",
+    "wildcard",
+    "order_by",
+    "keep",
+];
+
+const DISCARD_RULE_KEYS: &[&str] = &["file", "sections", "metadata"];
+
+/// The kind of entry a yaml node represents, which decides the order of its
+/// keys and the style used to write it.
+#[derive(Clone, Copy, PartialEq)]
+enum Node {
+    Document,
+    Settings,
+    Segment,
+    File,
+    VramClass,
+    GpInfo,
+    SymbolAssignment,
+    RequiredSymbol,
+    Assert,
+    RuntimeOption,
+    Target,
+    Import,
+    RawBlock,
+    SectionEntry,
+    DiscardRule,
+    /// Free-form values, like `metadata`, which are written as they are.
+    Raw,
+}
+
+impl Node {
+    fn keys(self) -> &'static [&'static str] {
+        match self {
+            Node::Document => DOCUMENT_KEYS,
+            Node::Settings => SETTINGS_KEYS,
+            Node::Segment => SEGMENT_KEYS,
+            Node::File => FILE_KEYS,
+            Node::VramClass => VRAM_CLASS_KEYS,
+            Node::GpInfo => GP_INFO_KEYS,
+            Node::SymbolAssignment => SYMBOL_ASSIGNMENT_KEYS,
+            Node::RequiredSymbol => REQUIRED_SYMBOL_KEYS,
+            Node::Assert => ASSERT_KEYS,
+            Node::RuntimeOption => RUNTIME_OPTION_KEYS,
+            Node::Target => TARGET_KEYS,
+            Node::Import => IMPORT_KEYS,
+            Node::RawBlock => RAW_BLOCK_KEYS,
+            Node::SectionEntry => SECTION_ENTRY_KEYS,
+            Node::DiscardRule => DISCARD_RULE_KEYS,
+            Node::Raw => &[],
+        }
+    }
+
+    /// The kind of the value of the given key of this node, or the kind of
+    /// the items if the value is a list.
+    fn child(self, key: &str) -> Node {
+        match (self, key) {
+            (_, "metadata") => Node::Raw,
+            (Node::Document, "settings") => Node::Settings,
+            (Node::Document, "vram_classes") => Node::VramClass,
+            (Node::Document, "segments") | (Node::Document, "segment_templates") => Node::Segment,
+            (Node::Document, "symbol_assignments")
+            | (Node::Document, "pre_sections_symbols")
+            | (Node::Document, "post_sections_symbols") => Node::SymbolAssignment,
+            (Node::Document, "required_symbols") => Node::RequiredSymbol,
+            (Node::Document, "asserts") | (Node::Document, "post_sections_asserts") => Node::Assert,
+            (Node::Document, "runtime_options") => Node::RuntimeOption,
+            (Node::Document, "targets") => Node::Target,
+            (Node::Document, "imports") => Node::Import,
+            (Node::Document, "raw") => Node::RawBlock,
+            (Node::Segment, "files") | (Node::File, "files") => Node::File,
+            (Node::Segment, "gp_info") => Node::GpInfo,
+            (Node::Settings, "alloc_sections")
+            | (Node::Settings, "noload_sections")
+            | (Node::Segment, "alloc_sections")
+            | (Node::Segment, "noload_sections") => Node::SectionEntry,
+            (Node::Settings, "discard") => Node::DiscardRule,
+            _ => Node::Raw,
+        }
+    }
+
+    /// Whether the entries of this kind may be written in flow style when
+    /// they are short enough.
+    fn prefers_flow(self) -> bool {
+        matches!(
+            self,
+            Node::File | Node::VramClass | Node::SectionEntry | Node::DiscardRule | Node::Raw
+        )
+    }
+}
+
+pub(crate) fn format_yaml_str(contents: &str) -> Result<FormattedYaml, SlinkyError> {
+    // The document is not unserialized, since the documents merged through
+    // `include` may be incomplete on their own
+    let document_serial = DocumentSerial::from_yaml_str(contents)?;
+
+    let value: Value = match serde_yaml::from_str(contents) {
+        Ok(value) => value,
+        Err(e) => {
+            return Err(SlinkyError::FailedYamlParsing {
+                description: e.to_string(),
+            })
+        }
+    };
+
+    let emitter = Emitter {
+        numbers: NumberSpellings::collect(contents),
+        hex_integers: false,
+    };
+    let mut lines = Vec::new();
+    if let Value::Mapping(mapping) = &value {
+        emitter.write_document(&mut lines, mapping);
+    }
+
+    let dropped_comments = reattach_comments(contents, &mut lines);
+
+    let mut formatted = lines.join("\n");
+    formatted.push('\n');
+
+    // Formatting must never change the meaning of the document
+    if DocumentSerial::from_yaml_str(&formatted).as_ref() != Ok(&document_serial) {
+        return Err(SlinkyError::FailedYamlSerialization {
+            description: "the formatted document does not match the original one".to_string(),
+        });
+    }
+
+    Ok(FormattedYaml {
+        contents: formatted,
+        dropped_comments,
+    })
+}
+
+/// Writes a document which was not read from yaml, like a serialized
+/// [`crate::Document`], in the canonical key order and style.
+///
+/// There is no original spelling to preserve, so integers are written in hex
+/// unless they are small or counts.
+pub(crate) fn emit_yaml_value(document: &Mapping) -> String {
+    let emitter = Emitter {
+        numbers: NumberSpellings::default(),
+        hex_integers: true,
+    };
+    let mut lines = Vec::new();
+    emitter.write_document(&mut lines, document);
+
+    let mut emitted = lines.join("\n");
+    emitted.push('\n');
+    emitted
+}
+
+struct Emitter {
+    numbers: NumberSpellings,
+    /// Write the integers without a known spelling in hex.
+    hex_integers: bool,
+}
+
+impl Emitter {
+    fn write_document(&self, lines: &mut Vec<String>, mapping: &Mapping) {
+        for (i, (key, value)) in sorted_entries(mapping, Node::Document).enumerate() {
+            if i != 0 {
+                lines.push(String::new());
+            }
+
+            let kind = Node::Document.child(key_str(key).unwrap_or_default());
+            self.write_key_value(lines, 0, key, value, kind);
+
+            // Segments are usually long, so separate them for readability
+            if kind == Node::Segment {
+                if let Value::Sequence(_) = value {
+                    separate_list_items(lines);
+                }
+            }
+        }
+    }
+
+    fn write_key_value(
+        &self,
+        lines: &mut Vec<String>,
+        indent: usize,
+        key: &Value,
+        value: &Value,
+        kind: Node,
+    ) {
+        let prefix = format!("{}{}:", " ".repeat(indent), self.scalar(key, false, None));
+        let key = key_str(key);
+
+        match value {
+            Value::Sequence(items) if !items.is_empty() => {
+                if !items.iter().any(is_mapping) {
+                    let line = format!("{} {}", prefix, self.flow(value, key, kind));
+                    if line.len() <= MAX_FLOW_LINE_LENGTH {
+                        lines.push(line);
+                        return;
+                    }
+                }
+                lines.push(prefix);
+                self.write_block_sequence(lines, indent + 2, items, kind, key);
+            }
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                if kind.prefers_flow() && is_flowable(value) {
+                    let line = format!("{} {}", prefix, self.flow(value, key, kind));
+                    if line.len() <= MAX_FLOW_LINE_LENGTH {
+                        lines.push(line);
+                        return;
+                    }
+                }
+                lines.push(prefix);
+                self.write_block_mapping(lines, indent + 2, mapping, kind);
+            }
+            _ => lines.push(format!("{} {}", prefix, self.inline(value, key, kind))),
+        }
+    }
+
+    fn write_block_mapping(
+        &self,
+        lines: &mut Vec<String>,
+        indent: usize,
+        mapping: &Mapping,
+        kind: Node,
+    ) {
+        for (key, value) in sorted_entries(mapping, kind) {
+            let child = kind.child(key_str(key).unwrap_or_default());
+            self.write_key_value(lines, indent, key, value, child);
+        }
+    }
+
+    fn write_block_sequence(
+        &self,
+        lines: &mut Vec<String>,
+        indent: usize,
+        items: &[Value],
+        kind: Node,
+        key: Option<&str>,
+    ) {
+        let item_prefix = format!("{}- ", " ".repeat(indent));
+
+        for item in items {
+            match item {
+                Value::Mapping(mapping) if !mapping.is_empty() => {
+                    if kind.prefers_flow() && is_flowable(item) {
+                        let line = format!("{}{}", item_prefix, self.flow(item, key, kind));
+                        // Splitting a single key doesn't make it any shorter
+                        if line.len() <= MAX_FLOW_LINE_LENGTH || mapping.len() == 1 {
+                            lines.push(line);
+                            continue;
+                        }
+                    }
+
+                    // The first key goes on the same line as the dash
+                    let start = lines.len();
+                    self.write_block_mapping(lines, indent + 2, mapping, kind);
+                    lines[start].replace_range(..indent + 2, &item_prefix);
+                }
+                _ => lines.push(format!("{}{}", item_prefix, self.inline(item, key, kind))),
+            }
+        }
+    }
+
+    /// Writes a value on a single line of a block collection.
+    fn inline(&self, value: &Value, key: Option<&str>, kind: Node) -> String {
+        match value {
+            Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_) => {
+                self.flow(value, key, kind)
+            }
+            _ => self.scalar(value, false, key),
+        }
+    }
+
+    /// Writes a value in flow style. `key` is the key the value belongs to.
+    fn flow(&self, value: &Value, key: Option<&str>, kind: Node) -> String {
+        match value {
+            Value::Sequence(items) => {
+                let items: Vec<String> = items.iter().map(|x| self.flow(x, key, kind)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Mapping(mapping) => {
+                if mapping.is_empty() {
+                    return "{}".to_string();
+                }
+                let entries: Vec<String> = sorted_entries(mapping, kind)
+                    .map(|(k, v)| {
+                        let child = kind.child(key_str(k).unwrap_or_default());
+                        format!(
+                            "{}: {}",
+                            self.scalar(k, true, None),
+                            self.flow(v, key_str(k), child)
+                        )
+                    })
+                    .collect();
+                format!("{{ {} }}", entries.join(", "))
+            }
+            Value::Tagged(tagged) => {
+                format!("{} {}", tagged.tag, self.flow(&tagged.value, key, kind))
+            }
+            _ => self.scalar(value, true, key),
+        }
+    }
+
+    fn scalar(&self, value: &Value, in_flow: bool, key: Option<&str>) -> String {
+        match value {
+            Value::Null => "null".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => match n.as_u64() {
+                Some(x) => match self.numbers.get(key, x) {
+                    Some(spelling) => spelling.to_string(),
+                    None if self.hex_integers
+                        && x >= 10
+                        && !key.map_or(false, |k| DECIMAL_KEYS.contains(&k)) =>
+                    {
+                        format!("0x{:X}", x)
+                    }
+                    None => n.to_string(),
+                },
+                None => n.to_string(),
+            },
+            Value::String(s) => quote_string(s, in_flow),
+            _ => self.flow(value, key, Node::Raw),
+        }
+    }
+}
+
+/// Returns the entries of the mapping in the canonical order of the given
+/// kind. Unknown keys are kept after the known ones in their original order.
+fn sorted_entries(mapping: &Mapping, kind: Node) -> impl Iterator<Item = (&Value, &Value)> {
+    let keys = kind.keys();
+    let position = |key: &Value| {
+        key_str(key)
+            .and_then(|k| keys.iter().position(|x| *x == k))
+            .unwrap_or(keys.len())
+    };
+
+    let mut entries: Vec<(&Value, &Value)> = mapping.iter().collect();
+    // Stable, so unknown keys keep their relative order
+    entries.sort_by_key(|(k, _)| position(k));
+    entries.into_iter()
+}
+
+fn key_str(key: &Value) -> Option<&str> {
+    key.as_str()
+}
+
+fn is_mapping(value: &Value) -> bool {
+    matches!(value, Value::Mapping(_))
+}
+
+/// Whether the value can be written in a single line.
+fn is_flowable(value: &Value) -> bool {
+    match value {
+        Value::Sequence(items) => items.iter().all(is_flowable),
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .all(|(k, v)| !matches!(k, Value::Sequence(_) | Value::Mapping(_)) && is_flowable(v)),
+        Value::String(s) => !s.contains('\n'),
+        _ => true,
+    }
+}
+
+/// Inserts an empty line between the items of the last block sequence.
+fn separate_list_items(lines: &mut Vec<String>) {
+    let start = match lines.iter().rposition(|x| !x.starts_with(' ')) {
+        Some(start) => start + 1,
+        None => return,
+    };
+
+    let mut i = start + 1;
+    while i < lines.len() {
+        if lines[i].starts_with("  - ") {
+            lines.insert(i, String::new());
+            i += 1;
+        }
+        i += 1;
+    }
+}
+
+fn quote_string(s: &str, in_flow: bool) -> String {
+    let needs_double_quotes = s.contains('\n')
+        || s.chars().any(|c| c.is_control())
+        || (in_flow && s.contains(FLOW_INDICATORS));
+
+    if !needs_double_quotes {
+        if is_safe_plain_scalar(s, in_flow) {
+            return s.to_string();
+        }
+        if let Ok(serialized) = serde_yaml::to_string(s) {
+            let serialized = serialized.trim_end_matches('\n');
+            if !serialized.contains('\n') {
+                return serialized.to_string();
+            }
+        }
+    }
+
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Whether the string can be written without quotes and still be read back
+/// as the same string.
+fn is_safe_plain_scalar(s: &str, in_flow: bool) -> bool {
+    let first = match s.chars().next() {
+        Some(first) => first,
+        None => return false,
+    };
+
+    if first.is_whitespace()
+        || s.ends_with(|c: char| c.is_whitespace() || c == ':')
+        || "-?:,[]{}#&*!|>'\"%@`".contains(first)
+        || s.contains(": ")
+        || s.contains(" #")
+        || (in_flow && s.contains(FLOW_INDICATORS))
+    {
+        return false;
+    }
+
+    // Values like `null`, `true` or `0x10` would not be read as strings
+    matches!(serde_yaml::from_str::<Value>(s), Ok(Value::String(x)) if x == s)
+}
+
+/// The way every integer was written on the original yaml, so hexadecimal
+/// values are kept as such.
+#[derive(Default)]
+struct NumberSpellings {
+    /// Keyed by the key the integer was assigned to.
+    by_key: HashMap<(String, u64), String>,
+    /// Used for integers whose key is not known. If the same value was
+    /// written in multiple ways then the first one wins.
+    any: HashMap<u64, String>,
+}
+
+impl NumberSpellings {
+    fn collect(contents: &str) -> Self {
+        let mut numbers = Self::default();
+
+        for line in contents.lines() {
+            let line = split_comment(line).content;
+            let bytes = line.as_bytes();
+
+            let mut i = 0;
+            while i < bytes.len() {
+                let starts_token =
+                    i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+                if !starts_token || !bytes[i].is_ascii_digit() {
+                    i += 1;
+                    continue;
+                }
+
+                let is_hex =
+                    bytes[i] == b'0' && matches!(bytes.get(i + 1), Some(b'x') | Some(b'X'));
+                let start = i;
+                i += if is_hex { 2 } else { 0 };
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+
+                let token = &line[start..i];
+                let value = if is_hex {
+                    u64::from_str_radix(&token[2..], 16).ok()
+                } else {
+                    token.parse().ok()
+                };
+                if let Some(value) = value {
+                    if let Some(key) = preceding_key(&line[..start]) {
+                        numbers
+                            .by_key
+                            .entry((key.to_string(), value))
+                            .or_insert_with(|| token.to_string());
+                    }
+                    numbers
+                        .any
+                        .entry(value)
+                        .or_insert_with(|| token.to_string());
+                }
+            }
+        }
+
+        numbers
+    }
+
+    fn get(&self, key: Option<&str>, value: u64) -> Option<&str> {
+        key.and_then(|k| self.by_key.get(&(k.to_string(), value)))
+            .or_else(|| self.any.get(&value))
+            .map(|x| x.as_str())
+    }
+}
+
+/// Returns the last key written before the given part of a line.
+fn preceding_key(before: &str) -> Option<&str> {
+    let colon = before.rfind(": ")?;
+    before[..colon]
+        .rsplit(|c: char| c.is_whitespace() || matches!(c, '{' | '[' | ',' | '-'))
+        .next()
+        .filter(|x| !x.is_empty())
+}
+
+struct SourceLine<'a> {
+    content: &'a str,
+    comment: Option<&'a str>,
+}
+
+/// Splits a yaml line into its content and its comment, if any.
+fn split_comment(line: &str) -> SourceLine<'_> {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            Some('\'') if c == '\'' && matches!(chars.peek(), Some((_, '\''))) => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => {
+                if c == '#' && previous.is_whitespace() {
+                    return SourceLine {
+                        content: line[..i].trim_end(),
+                        comment: Some(line[i..].trim_end()),
+                    };
+                }
+                // Quotes can only start a scalar, not appear in the middle of
+                // a plain one
+                if (c == '\'' || c == '"')
+                    && (previous.is_whitespace() || matches!(previous, '[' | '{' | ','))
+                {
+                    quote = Some(c);
+                }
+            }
+        }
+        previous = c;
+    }
+
+    SourceLine {
+        content: line.trim_end(),
+        comment: None,
+    }
+}
+
+/// Reduces a line to a form which is not affected by the formatting, so the
+/// same line can be found on the original and the formatted yaml.
+///
+/// Whitespace and quotes are dropped, and the keys of single line flow
+/// mappings are sorted since the formatter reorders them.
+fn normalize(line: &str) -> String {
+    let stripped: String = line
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '"' && *c != '\'')
+        .collect();
+
+    match (stripped.find('{'), stripped.rfind('}')) {
+        (Some(start), Some(end)) if start < end => {
+            let mut entries: Vec<&str> = stripped[start + 1..end].split(',').collect();
+            entries.sort_unstable();
+            format!(
+                "{}{{{}}}{}",
+                &stripped[..start],
+                entries.join(","),
+                &stripped[end + 1..]
+            )
+        }
+        _ => stripped,
+    }
+}
+
+/// Carries the comments of the original yaml over to the formatted lines.
+///
+/// Every comment is attached to the line it was written on, or to the next
+/// one for comments on their own line. Those lines are looked up on the
+/// formatted yaml by their contents, so comments on entries that were
+/// rewritten can't be carried over. Their line numbers are returned.
+fn reattach_comments(contents: &str, lines: &mut Vec<String>) -> Vec<usize> {
+    let normalized: Vec<String> = lines.iter().map(|x| normalize(x)).collect();
+
+    let mut leading: HashMap<usize, Vec<String>> = HashMap::new();
+    let mut trailing: HashMap<usize, String> = HashMap::new();
+    let mut dropped = Vec::new();
+
+    let source: Vec<(usize, SourceLine)> = (1..).zip(contents.lines().map(split_comment)).collect();
+    let source_contents: Vec<String> = source
+        .iter()
+        .filter(|(_, x)| !x.content.trim().is_empty())
+        .map(|(_, x)| normalize(x.content))
+        .collect();
+    let mut matches = vec![None; source_contents.len()];
+    match_lines(&source_contents, &normalized, 0, 0, &mut matches);
+
+    // Comments on their own lines, waiting for the next line with contents
+    let mut pending: Vec<(usize, String)> = Vec::new();
+    let mut blank_after_pending = false;
+    // Single empty lines are kept, so the entries can still be grouped
+    let mut blank_before = false;
+    let mut separated: HashSet<usize> = HashSet::new();
+    let mut content_index = 0;
+
+    for (line_number, line) in source {
+        if line.content.trim().is_empty() {
+            match line.comment {
+                Some(comment) => {
+                    if blank_after_pending {
+                        pending.push((line_number, String::new()));
+                    }
+                    pending.push((line_number, comment.to_string()));
+                    blank_after_pending = false;
+                }
+                None => {
+                    blank_after_pending = !pending.is_empty();
+                    blank_before |= pending.is_empty();
+                }
+            }
+            continue;
+        }
+
+        // The comments at the top of the file stay there
+        if content_index == 0 && !pending.is_empty() {
+            let comments = leading.entry(0).or_default();
+            comments.extend(pending.drain(..).map(|(_, x)| x));
+            if blank_after_pending {
+                comments.push(String::new());
+            }
+        }
+
+        match matches[content_index] {
+            Some(index) => {
+                if blank_before {
+                    separated.insert(index);
+                }
+                if !pending.is_empty() {
+                    let comments = leading.entry(index).or_default();
+                    comments.extend(pending.drain(..).map(|(_, x)| x));
+                    if blank_after_pending {
+                        comments.push(String::new());
+                    }
+                }
+                if let Some(comment) = line.comment {
+                    trailing.insert(index, comment.to_string());
+                }
+            }
+            None => {
+                dropped.extend(
+                    pending
+                        .drain(..)
+                        .filter(|(_, x)| !x.is_empty())
+                        .map(|(x, _)| x),
+                );
+                if line.comment.is_some() {
+                    dropped.push(line_number);
+                }
+            }
+        }
+        content_index += 1;
+        blank_after_pending = false;
+        blank_before = false;
+    }
+
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    for (index, line) in lines.drain(..).enumerate() {
+        if separated.contains(&index) && result.last().map_or(false, |x| !x.is_empty()) {
+            result.push(String::new());
+        }
+        if let Some(comments) = leading.remove(&index) {
+            let indent = " ".repeat(line.len() - line.trim_start().len());
+            for comment in comments {
+                if comment.is_empty() {
+                    result.push(comment);
+                } else {
+                    result.push(format!("{}{}", indent, comment));
+                }
+            }
+        }
+
+        match trailing.remove(&index) {
+            Some(comment) => result.push(format!("{} {}", line, comment)),
+            None => result.push(line),
+        }
+    }
+
+    // Comments at the end of the file
+    if !pending.is_empty() {
+        if !result.is_empty() {
+            result.push(String::new());
+        }
+        result.extend(pending.into_iter().map(|(_, x)| x));
+    }
+
+    *lines = result;
+    dropped
+}
+
+/// Pairs the equal lines of `a` and `b`, keeping their relative order, and
+/// stores the index on `b` of every matched line of `a` on `matches`.
+///
+/// This is a patience diff: the lines that are unique on both sides are
+/// used as anchors and the gaps between them are matched recursively.
+fn match_lines(
+    a: &[String],
+    b: &[String],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut [Option<usize>],
+) {
+    let mut a = a;
+    let mut b = b;
+    let mut a_offset = a_offset;
+    let mut b_offset = b_offset;
+
+    // Common prefix and suffix
+    while let (Some(x), Some(y)) = (a.first(), b.first()) {
+        if x != y {
+            break;
+        }
+        matches[a_offset] = Some(b_offset);
+        a = &a[1..];
+        b = &b[1..];
+        a_offset += 1;
+        b_offset += 1;
+    }
+    while let (Some(x), Some(y)) = (a.last(), b.last()) {
+        if x != y {
+            break;
+        }
+        matches[a_offset + a.len() - 1] = Some(b_offset + b.len() - 1);
+        a = &a[..a.len() - 1];
+        b = &b[..b.len() - 1];
+    }
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+
+    let anchors = unique_anchors(a, b);
+    if anchors.is_empty() {
+        // Nothing to anchor on, so just match greedily
+        let mut cursor = 0;
+        for (i, x) in a.iter().enumerate() {
+            if let Some(j) = b[cursor..].iter().position(|y| y == x) {
+                matches[a_offset + i] = Some(b_offset + cursor + j);
+                cursor += j + 1;
+            }
+        }
+        return;
+    }
+
+    let mut a_start = 0;
+    let mut b_start = 0;
+    for (i, j) in anchors {
+        match_lines(
+            &a[a_start..i],
+            &b[b_start..j],
+            a_offset + a_start,
+            b_offset + b_start,
+            matches,
+        );
+        matches[a_offset + i] = Some(b_offset + j);
+        a_start = i + 1;
+        b_start = j + 1;
+    }
+    match_lines(
+        &a[a_start..],
+        &b[b_start..],
+        a_offset + a_start,
+        b_offset + b_start,
+        matches,
+    );
+}
+
+/// Returns the longest increasing sequence of pairs of lines that appear
+/// exactly once on both `a` and `b`.
+fn unique_anchors(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
+    let mut counts: HashMap<&str, (usize, usize, usize)> = HashMap::new();
+    for (i, x) in a.iter().enumerate() {
+        let entry = counts.entry(x).or_insert((0, 0, 0));
+        entry.0 += 1;
+        entry.2 = i;
+    }
+    for y in b {
+        if let Some(entry) = counts.get_mut(y.as_str()) {
+            entry.1 += 1;
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (j, y) in b.iter().enumerate() {
+        if let Some((1, 1, i)) = counts.get(y.as_str()) {
+            pairs.push((*i, j));
+        }
+    }
+    // `pairs` is sorted by `j`, so look for the longest increasing run of `i`
+    pairs.sort_unstable_by_key(|(_, j)| *j);
+
+    // Patience sorting: `tails[k]` is the index on `pairs` of the smallest
+    // tail of an increasing sequence of length `k + 1`
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, (i, _)) in pairs.iter().enumerate() {
+        let k = tails.partition_point(|&t| pairs[t].0 < *i);
+        if k > 0 {
+            previous[index] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(index);
+        } else {
+            tails[k] = index;
+        }
+    }
+
+    let mut anchors = Vec::with_capacity(tails.len());
+    let mut current = tails.last().copied();
+    while let Some(index) = current {
+        anchors.push(pairs[index]);
+        current = previous[index];
+    }
+    anchors.reverse();
+    anchors
+}
@@ -0,0 +1,65 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::Write;
+
+use crate::{EscapedPath, SlinkyError};
+
+/// A segment whose symbols are loaded on demand by the GDB script.
+pub(crate) struct GdbSegment {
+    pub name: String,
+    /// The partially linked object of the segment.
+    pub object: EscapedPath,
+    /// Each section of the object along with the linker symbol placed at its
+    /// start.
+    pub sections: Vec<(String, String)>,
+}
+
+/// Writes a GDB script defining a `slinky_load_<segment>` and a
+/// `slinky_unload_<segment>` command for each segment.
+///
+/// The address of each section is read from the linker symbols of the linked
+/// elf at the time the command runs, so the script doesn't need to be
+/// regenerated when the layout changes.
+pub(crate) fn write_gdb_script(
+    dst: &mut impl Write,
+    segments: &[GdbSegment],
+) -> Result<(), SlinkyError> {
+    let mut contents = String::new();
+
+    contents += "# Load the symbols of a segment with `slinky_load_<segment>` once it is\n";
+    contents += "# placed in memory, and unload them with `slinky_unload_<segment>` before\n";
+    contents += "# another segment takes its place.\n";
+
+    for segment in segments {
+        let mut load = format!("add-symbol-file {}", segment.object);
+        for (section, symbol) in &segment.sections {
+            load += &format!(" -s {} &{}", section, symbol);
+        }
+
+        contents += "\n";
+        contents += &format!("define slinky_load_{}\n", segment.name);
+        contents += &format!("    {}\n", load);
+        contents += "end\n";
+        contents += &format!("document slinky_load_{}\n", segment.name);
+        contents += &format!("Loads the symbols of the segment '{}'.\n", segment.name);
+        contents += "end\n";
+
+        contents += "\n";
+        contents += &format!("define slinky_unload_{}\n", segment.name);
+        contents += &format!("    remove-symbol-file {}\n", segment.object);
+        contents += "end\n";
+        contents += &format!("document slinky_unload_{}\n", segment.name);
+        contents += &format!("Unloads the symbols of the segment '{}'.\n", segment.name);
+        contents += "end\n";
+    }
+
+    if let Err(e) = write!(dst, "{}", contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::Path;
+
+use crate::{
+    Document, GenerationStatistics, LinkerWriter, PartialLinkerWriter, RuntimeSettings,
+    ScriptExporter, ScriptImporter, SlinkyError, WrittenFile,
+};
+
+/// Which linker scripts are generated by [`generate_with_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GenerationMode {
+    /// A single linker script linking every file of every segment.
+    Full,
+    /// A linker script for partially linking each segment, plus the main
+    /// linker script which links the partially linked segments together.
+    ///
+    /// Requires the `partial_scripts_folder` setting and either
+    /// `partial_build_segments_folder` or `partial_build_path`.
+    Partial,
+    /// Both of the above, generated from the same parsed document.
+    Both,
+}
+
+impl GenerationMode {
+    /// The mode used by [`generate`], which depends on whether the document
+    /// sets `partial_scripts_folder`.
+    #[must_use]
+    pub fn for_document(document: &Document) -> Self {
+        if document.settings.partial_scripts_folder.is_some() {
+            Self::Partial
+        } else {
+            Self::Full
+        }
+    }
+}
+
+/// Everything produced by [`generate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratedOutputs {
+    /// The main linker script. It is not written to disk, since its path is
+    /// not part of the document.
+    pub linker_script: String,
+
+    /// The name of each partially linked segment and its linker script.
+    ///
+    /// Empty unless the partial linking scripts were generated.
+    pub partial_scripts: Vec<(String, String)>,
+
+    /// The main linker script of the partial linking, which links the
+    /// partially linked segments together.
+    ///
+    /// Only set on [`GenerationMode::Both`], since `linker_script` already
+    /// is this script on [`GenerationMode::Partial`].
+    pub partial_main_script: Option<String>,
+
+    /// The dependency file of the main linker script, if the document sets
+    /// `d_path`.
+    pub dependencies: Option<String>,
+
+    /// The symbols header, if the document sets `symbols_header_path`.
+    pub symbols_header: Option<String>,
+
+    /// The ROM symbols header, if the document sets
+    /// `symbols_header_rom_path`.
+    pub rom_symbols_header: Option<String>,
+
+    /// The linker offsets header, if the document sets
+    /// `symbols_header_linker_offsets_path`.
+    pub linker_offsets_header: Option<String>,
+
+    /// The assembler include file, if the document sets `symbols_asm_path`.
+    pub symbols_asm: Option<String>,
+
+    /// The Rust bindings, if the document sets `symbols_rust_path`.
+    pub symbols_rust: Option<String>,
+
+    /// A summary of what was emitted on `linker_script`.
+    pub statistics: GenerationStatistics,
+
+    /// Every file written to disk, like the dependency files, the symbols
+    /// header or the partial linker scripts.
+    pub written: Vec<WrittenFile>,
+}
+
+impl GeneratedOutputs {
+    fn new(writer: &LinkerWriter, d: &Document, rs: &RuntimeSettings) -> Result<Self, SlinkyError> {
+        let dependencies = match d.settings.target_path_escaped(rs)? {
+            Some(target_path) if d.settings.d_path.is_some() => {
+                Some(writer.export_dependencies_file_to_string(&target_path)?)
+            }
+            _ => None,
+        };
+
+        let symbols_header = if d.settings.symbols_header_path.is_some() {
+            Some(writer.export_symbol_header_to_string()?)
+        } else {
+            None
+        };
+
+        let rom_symbols_header = if d.settings.symbols_header_rom_path.is_some() {
+            Some(writer.export_rom_symbol_header_to_string()?)
+        } else {
+            None
+        };
+
+        let linker_offsets_header = if d.settings.symbols_header_linker_offsets_path.is_some() {
+            Some(writer.export_linker_offsets_symbol_header_to_string()?)
+        } else {
+            None
+        };
+
+        let symbols_asm = if d.settings.symbols_asm_path.is_some() {
+            Some(writer.export_symbols_asm_to_string()?)
+        } else {
+            None
+        };
+
+        let symbols_rust = if d.settings.symbols_rust_path.is_some() {
+            Some(writer.export_symbols_rust_to_string()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            linker_script: writer.export_linker_script_to_string()?,
+            partial_scripts: Vec::new(),
+            partial_main_script: None,
+            dependencies,
+            symbols_header,
+            rom_symbols_header,
+            linker_offsets_header,
+            symbols_asm,
+            symbols_rust,
+            statistics: writer.statistics(),
+            written: Vec::new(),
+        })
+    }
+}
+
+/// Reads the document at the given path and generates everything listed on
+/// it.
+///
+/// Every file whose path is part of the document is written to disk, while
+/// the linker scripts and the main generated files are also returned as
+/// strings.
+///
+/// The [`PartialLinkerWriter`] is used if the document sets
+/// `partial_scripts_folder`.
+pub fn generate(
+    document_path: &Path,
+    rs: &RuntimeSettings,
+) -> Result<GeneratedOutputs, SlinkyError> {
+    let document = Document::read_file(document_path)?;
+
+    generate_document(&document, rs, GenerationMode::for_document(&document))
+}
+
+/// Like [`generate`], but the generated linker scripts are picked by `mode`
+/// instead of by the document.
+pub fn generate_with_mode(
+    document_path: &Path,
+    rs: &RuntimeSettings,
+    mode: GenerationMode,
+) -> Result<GeneratedOutputs, SlinkyError> {
+    let document = Document::read_file(document_path)?;
+
+    generate_document(&document, rs, mode)
+}
+
+/// Generates the linker scripts picked by `mode` for an already parsed
+/// document.
+///
+/// On [`GenerationMode::Both`] the files listed on the document, like the
+/// dependency file or the symbols header, are the ones of the full linker
+/// script, while the partial linking only writes its partial scripts and their
+/// dependency files.
+pub fn generate_document(
+    document: &Document,
+    rs: &RuntimeSettings,
+    mode: GenerationMode,
+) -> Result<GeneratedOutputs, SlinkyError> {
+    rs.check_custom_options(&document.runtime_options)?;
+
+    match mode {
+        GenerationMode::Full => {
+            let mut writer = LinkerWriter::new(document, rs);
+            writer.add_whole_document(document)?;
+
+            let mut outputs = GeneratedOutputs::new(&writer, document, rs)?;
+            outputs.written.extend(writer.save_other_files()?);
+
+            Ok(outputs)
+        }
+        GenerationMode::Partial => {
+            let mut writer = PartialLinkerWriter::new(document, rs);
+            writer.add_whole_document(document)?;
+
+            let mut outputs = GeneratedOutputs::new(writer.get_main_writer(), document, rs)?;
+            add_partial_scripts(&mut outputs, &writer)?;
+            outputs.written.extend(writer.save_other_files()?);
+
+            Ok(outputs)
+        }
+        GenerationMode::Both => {
+            let mut writer = LinkerWriter::new(document, rs);
+            writer.add_whole_document(document)?;
+
+            let mut partial_writer = PartialLinkerWriter::new(document, rs);
+            partial_writer.add_whole_document(document)?;
+
+            let mut outputs = GeneratedOutputs::new(&writer, document, rs)?;
+            outputs.partial_main_script = Some(
+                partial_writer
+                    .get_main_writer()
+                    .export_linker_script_to_string()?,
+            );
+            add_partial_scripts(&mut outputs, &partial_writer)?;
+            outputs.written.extend(writer.save_other_files()?);
+            outputs
+                .written
+                .extend(partial_writer.export_partial_dependencies_files()?);
+
+            Ok(outputs)
+        }
+    }
+}
+
+fn add_partial_scripts(
+    outputs: &mut GeneratedOutputs,
+    writer: &PartialLinkerWriter,
+) -> Result<(), SlinkyError> {
+    for (partial, name) in writer.get_partial_writers() {
+        outputs
+            .partial_scripts
+            .push((name.clone(), partial.export_linker_script_to_string()?));
+    }
+
+    outputs
+        .written
+        .extend(writer.export_partial_scripts_to_files()?);
+
+    Ok(())
+}
@@ -1,11 +1,14 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{absent_nullable::AbsentNullable, traits::Serial, Settings, SlinkyError};
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, traits::Serial, Settings,
+    SlinkyError,
+};
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
 pub struct GpInfo {
     // The relative section to emit the `_gp` symbol
     pub section: String,
@@ -19,9 +22,24 @@ pub struct GpInfo {
     /// Can be used with `provide`.
     pub hidden: bool,
 
+    /// The sections addressed relative to `_gp`. Only `section` is used if
+    /// empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub small_data_sections: Vec<String>,
+    /// Places `_gp` on the middle of the small data sections instead of using
+    /// `section` and `offset`.
+    pub midpoint: bool,
+    /// Emits an `ASSERT` making sure every small data section is reachable
+    /// from `_gp`.
+    pub check_range: bool,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_all: Vec<(String, String)>,
 }
 
@@ -41,7 +59,19 @@ const fn gp_info_default_hidden() -> bool {
     false
 }
 
-#[derive(Deserialize, PartialEq, Debug)]
+const fn gp_info_default_small_data_sections() -> Vec<String> {
+    Vec::new()
+}
+
+const fn gp_info_default_midpoint() -> bool {
+    false
+}
+
+const fn gp_info_default_check_range() -> bool {
+    false
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct GpInfoSerial {
     #[serde(default)]
@@ -54,6 +84,13 @@ pub(crate) struct GpInfoSerial {
     #[serde(default)]
     pub hidden: AbsentNullable<bool>,
 
+    #[serde(default)]
+    pub small_data_sections: AbsentNullable<Vec<String>>,
+    #[serde(default)]
+    pub midpoint: AbsentNullable<bool>,
+    #[serde(default)]
+    pub check_range: AbsentNullable<bool>,
+
     #[serde(default)]
     pub include_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
@@ -62,12 +99,27 @@ pub(crate) struct GpInfoSerial {
     pub exclude_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
     pub exclude_if_all: AbsentNullable<Vec<(String, String)>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl GpInfo {
+    /// Every section addressed relative to `_gp`.
+    #[must_use]
+    pub fn gp_relative_sections(&self) -> &[String] {
+        if self.small_data_sections.is_empty() {
+            std::slice::from_ref(&self.section)
+        } else {
+            &self.small_data_sections
+        }
+    }
 }
 
 impl Serial for GpInfoSerial {
     type Output = GpInfo;
 
-    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+    fn unserialize(self, settings: &Settings) -> Result<Self::Output, SlinkyError> {
         let section = {
             let s = self
                 .section
@@ -87,6 +139,16 @@ impl Serial for GpInfoSerial {
             .get_non_null("provide", gp_info_default_provide)?;
         let hidden = self.hidden.get_non_null("hidden", gp_info_default_hidden)?;
 
+        let small_data_sections = self
+            .small_data_sections
+            .get_non_null_not_empty("small_data_sections", gp_info_default_small_data_sections)?;
+        let midpoint = self
+            .midpoint
+            .get_non_null("midpoint", gp_info_default_midpoint)?;
+        let check_range = self
+            .check_range
+            .get_non_null("check_range", gp_info_default_check_range)?;
+
         let include_if_any = self
             .include_if_any
             .get_non_null_not_empty("include_if_any", Vec::new)?;
@@ -100,11 +162,21 @@ impl Serial for GpInfoSerial {
             .exclude_if_all
             .get_non_null_not_empty("exclude_if_all", Vec::new)?;
 
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
         Ok(Self::Output {
             section,
             offset,
             provide,
             hidden,
+            small_data_sections,
+            midpoint,
+            check_range,
             include_if_any,
             include_if_all,
             exclude_if_any,
@@ -0,0 +1,21 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{de::IgnoredAny, Deserialize, Deserializer};
+
+/// Accepts any value and throws it away.
+///
+/// Used for the `metadata` field of every entry of the document, allowing
+/// external tools to annotate documents with their own data without being
+/// rejected by `deny_unknown_fields`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Default)]
+pub(crate) struct IgnoredMetadata;
+
+impl<'de> Deserialize<'de> for IgnoredMetadata {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IgnoredAny::deserialize(deserializer).map(|_| IgnoredMetadata)
+    }
+}
@@ -0,0 +1,132 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, traits::Serial, utils,
+    Document, EscapedPath, LinkerWriter, RuntimeSettings, ScriptImporter, Settings, SlinkyError,
+};
+
+/// A reference to the symbols generated by another document, usually the one
+/// of a different binary of the same project.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct Import {
+    /// Path to the other slinky document, or to a JSON manifest listing its
+    /// symbols.
+    pub path: PathBuf,
+
+    /// The symbols of the other document used by this document.
+    pub symbols: Vec<String>,
+}
+
+impl Import {
+    pub fn path_escaped(&self, rs: &RuntimeSettings) -> Result<EscapedPath, SlinkyError> {
+        rs.escape_path(&self.path)
+    }
+
+    /// Returns every symbol the imported document generates when using the
+    /// given runtime settings.
+    ///
+    /// If the path has a `json` extension then it is read as a manifest
+    /// instead, which is expected to be an object with a `symbols` list.
+    pub fn available_symbols(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<indexmap::IndexSet<String>, SlinkyError> {
+        let escaped_path = self.path_escaped(rs)?;
+        let path: &Path = escaped_path.as_ref();
+
+        if path.extension().map_or(false, |x| x == "json") {
+            return read_manifest(path);
+        }
+
+        let mut document = Document::read_file(path)?;
+        // Only the symbols of the imported document itself are relevant, and
+        // this avoids infinite loops on documents importing each other.
+        document.imports.clear();
+
+        let mut writer = LinkerWriter::new(&document, rs);
+        writer.add_whole_document(&document)?;
+
+        Ok(writer.get_linker_symbols().clone())
+    }
+
+    /// Checks every symbol listed on this import is generated by the imported
+    /// document.
+    pub fn check_symbols(&self, rs: &RuntimeSettings) -> Result<(), SlinkyError> {
+        let available = self.available_symbols(rs)?;
+
+        for symbol in &self.symbols {
+            if !available.contains(symbol) {
+                return Err(SlinkyError::MissingImportedSymbol {
+                    symbol: symbol.clone(),
+                    path: self.path.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct SymbolsManifest {
+    symbols: Vec<String>,
+}
+
+fn read_manifest(path: &Path) -> Result<indexmap::IndexSet<String>, SlinkyError> {
+    let contents = match utils::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(SlinkyError::FailedFileOpen {
+                path: path.to_path_buf(),
+                description: e.to_string(),
+            })
+        }
+    };
+
+    // JSON is a subset of YAML
+    match serde_yaml::from_str::<SymbolsManifest>(&contents) {
+        Ok(manifest) => Ok(manifest.symbols.into_iter().collect()),
+        Err(e) => Err(SlinkyError::FailedYamlParsing {
+            description: e.to_string(),
+        }),
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ImportSerial {
+    pub path: PathBuf,
+
+    #[serde(default)]
+    pub symbols: AbsentNullable<Vec<String>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl Serial for ImportSerial {
+    type Output = Import;
+
+    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+        if self.path == Path::new("") {
+            return Err(SlinkyError::EmptyValue {
+                name: "path".to_string(),
+            });
+        }
+        let path = self.path;
+
+        let symbols = self.symbols.get("symbols")?;
+        if symbols.is_empty() {
+            return Err(SlinkyError::EmptyValue {
+                name: "symbols".to_string(),
+            });
+        }
+
+        Ok(Self::Output { path, symbols })
+    }
+}
@@ -1,24 +1,42 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 
 // use crate::{absent_nullable::AbsentNullable, traits::Serial, Settings, SlinkyError};
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 #[serde(untagged)]
 pub enum KeepSections {
+    #[default]
     #[serde(skip)]
     Absent,
     All(bool),
     WhichOnes(HashSet<String>),
 }
 
-impl Default for KeepSections {
-    fn default() -> Self {
-        Self::Absent
+impl KeepSections {
+    pub(crate) fn is_absent(&self) -> bool {
+        *self == KeepSections::Absent
+    }
+}
+
+impl Serialize for KeepSections {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            KeepSections::Absent => serializer.serialize_none(),
+            KeepSections::All(all) => serializer.serialize_bool(*all),
+            // Sorted, so the output is stable
+            KeepSections::WhichOnes(which_ones) => which_ones
+                .iter()
+                .collect::<BTreeSet<_>>()
+                .serialize(serializer),
+        }
     }
 }
@@ -0,0 +1,1204 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    absent_nullable::AbsentNullable,
+    assert_entry::AssertEntrySerial,
+    discard_rule::DiscardRuleSerial,
+    document::DocumentSerial,
+    file_info::{FileInfoSerial, FileSectionsSerial},
+    ignored_metadata::IgnoredMetadata,
+    linker_symbols_style::resolve_symbol_expression,
+    required_symbol::RequiredSymbolSerial,
+    segment::SegmentSerial,
+    settings::SettingsSerial,
+    symbol_assignment::SymbolAssignmentSerial,
+    Document, FileKind, KeepSections, Settings, SlinkyError,
+};
+
+/// The result of importing an existing GNU LD linker script.
+#[derive(PartialEq, Debug)]
+pub struct ImportedLinkerScript {
+    /// Best-effort document reconstructed from the linker script.
+    pub document: Document,
+
+    /// Human readable descriptions of every construct of the linker script
+    /// that can't be represented on a slinky document, and that got skipped
+    /// during the import.
+    pub unsupported: Vec<String>,
+}
+
+pub(crate) fn import_linker_script(contents: &str) -> Result<ImportedLinkerScript, SlinkyError> {
+    let stripped = strip_comments(contents);
+
+    let mut importer = Importer::default();
+    importer.parse_top_level(&mut Cursor::new(&stripped))?;
+
+    importer.finish()
+}
+
+/// Comments only start at the beginning of a token, so globs like
+/// `build/src/*.o` are left alone.
+fn strip_comments(contents: &str) -> String {
+    let mut out = String::with_capacity(contents.len());
+    let mut rest = contents;
+
+    while let Some(start) = rest.find("/*") {
+        let at_token_start = rest[..start]
+            .bytes()
+            .last()
+            .or_else(|| out.bytes().last())
+            .map_or(true, |c| {
+                c.is_ascii_whitespace() || b"{}();,=:".contains(&c)
+            });
+        if !at_token_start {
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        out.push_str(&rest[..start]);
+        out.push(' ');
+
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+
+    out
+}
+
+fn parse_number(value: &str) -> Option<u32> {
+    let value = value.trim();
+
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(kilos) = value.strip_suffix('K') {
+        kilos.parse::<u32>().ok()?.checked_mul(1024)
+    } else if let Some(megas) = value.strip_suffix('M') {
+        megas.parse::<u32>().ok()?.checked_mul(1024 * 1024)
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Like [`parse_number`], but allowing 64 bits hexadecimal values, used by
+/// addresses.
+fn parse_address(value: &str) -> Option<u64> {
+    let value = value.trim();
+
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => parse_number(value).map(u64::from),
+    }
+}
+
+fn is_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Removes a single pair of parenthesis wrapping the whole expression, if any.
+fn strip_wrapping_parens(expr: &str) -> &str {
+    let expr = expr.trim();
+
+    if !expr.starts_with('(') || !expr.ends_with(')') {
+        return expr;
+    }
+
+    let mut depth = 0;
+    for (i, c) in expr.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 && i + 1 != expr.len() {
+                    // The first parenthesis is closed before the end of the expression
+                    return expr;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    expr[1..expr.len() - 1].trim()
+}
+
+/// Splits `text` on `separator`, ignoring the ones inside parenthesis or strings.
+fn split_top_level(text: &str, separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            _ if !in_string && depth == 0 && separator(c) => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+struct Cursor<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        self.src[self.pos..].starts_with(s)
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn is_eof(&mut self) -> bool {
+        self.skip_ws();
+        self.pos >= self.src.len()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), SlinkyError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn error(&self, description: &str) -> SlinkyError {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+
+        SlinkyError::FailedLinkerScriptParsing {
+            description: format!("{} (line {})", description, line),
+        }
+    }
+
+    /// Reads a single word, like a command, a symbol, a path or a section name.
+    fn word(&mut self) -> &'a str {
+        self.skip_ws();
+
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_whitespace() || b"{}();,=\"".contains(&c) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        let mut word = &self.src[start..self.pos];
+        // A trailing colon is the separator of an output section, not part of its name
+        if word.len() > 1 && word.ends_with(':') {
+            self.pos -= 1;
+            word = &word[..word.len() - 1];
+        }
+        word
+    }
+
+    /// Consumes an assignment operator, if there's one.
+    fn assignment_op(&mut self) -> Option<&'a str> {
+        self.skip_ws();
+
+        for op in ["+=", "-=", "*=", "/=", "<<=", ">>=", "&=", "|="] {
+            if self.starts_with(op) {
+                self.pos += op.len();
+                return Some(op);
+            }
+        }
+        if self.starts_with("=") && !self.starts_with("==") {
+            self.pos += 1;
+            return Some("=");
+        }
+
+        None
+    }
+
+    /// Expects a parenthesized group and returns its contents.
+    fn parenthesized(&mut self) -> Result<&'a str, SlinkyError> {
+        self.expect(b'(')?;
+
+        let start = self.pos;
+        let mut depth = 1;
+        let mut in_string = false;
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'"' => in_string = !in_string,
+                b'(' if !in_string => depth += 1,
+                b')' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(&self.src[start..self.pos - 1]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Err(self.error("unterminated parenthesis"))
+    }
+
+    /// Reads an expression until the next `;` (which is consumed) or `}` (which isn't).
+    fn expression(&mut self) -> &'a str {
+        let start = self.pos;
+        let mut depth = 0;
+        let mut in_string = false;
+
+        while let Some(c) = self.peek() {
+            match c {
+                b'"' => in_string = !in_string,
+                b'(' if !in_string => depth += 1,
+                b')' if !in_string => depth -= 1,
+                b';' if !in_string && depth == 0 => {
+                    self.pos += 1;
+                    return self.src[start..self.pos - 1].trim();
+                }
+                b'}' if !in_string && depth == 0 => break,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+
+        self.src[start..self.pos].trim()
+    }
+
+    /// Skips everything until the next `{`, which isn't consumed.
+    fn skip_until_block(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == b'{' {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips a `{ }` block, including nested blocks.
+    fn skip_block(&mut self) -> Result<(), SlinkyError> {
+        self.expect(b'{')?;
+
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Err(self.error("unterminated block"))
+    }
+}
+
+enum ImportedFileEntry {
+    File {
+        path: PathBuf,
+        subfile: Option<String>,
+        keep: HashSet<String>,
+    },
+    Pad {
+        amount: u32,
+        section: String,
+    },
+}
+
+#[derive(Default)]
+struct ImportedSegment {
+    name: String,
+
+    fixed_vram: Option<u64>,
+    fixed_symbol: Option<String>,
+    subalign: Option<u32>,
+    fill_value: Option<u32>,
+
+    files: ImportedFiles,
+
+    alloc_sections: Vec<String>,
+    noload_sections: Vec<String>,
+    has_noload: bool,
+
+    wildcard_sections: Option<bool>,
+}
+
+/// The files of a segment, in the order they appear on the script.
+///
+/// A file shows up once per section, and new entries are placed right after
+/// the last one processed, so the entries are kept as a linked list indexed by
+/// file to avoid searching and shifting the whole list on every section.
+#[derive(Default)]
+struct ImportedFiles {
+    entries: Vec<ImportedFileEntry>,
+    /// The entry following each one of `entries`.
+    next: Vec<Option<usize>>,
+    first: Option<usize>,
+    last: Option<usize>,
+
+    by_file: HashMap<(PathBuf, Option<String>), usize>,
+}
+
+impl ImportedFiles {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn find_file(&self, path: &Path, subfile: &Option<String>) -> Option<usize> {
+        self.by_file
+            .get(&(path.to_path_buf(), subfile.clone()))
+            .copied()
+    }
+
+    /// Inserts the entry after the one at `prev`, or at the start of the list
+    /// if `None`. Returns the index of the new entry.
+    fn insert_after(&mut self, prev: Option<usize>, entry: ImportedFileEntry) -> usize {
+        let index = self.entries.len();
+
+        if let ImportedFileEntry::File { path, subfile, .. } = &entry {
+            self.by_file.insert((path.clone(), subfile.clone()), index);
+        }
+        self.entries.push(entry);
+
+        let next = match prev {
+            Some(prev) => self.next[prev].replace(index),
+            None => self.first.replace(index),
+        };
+        self.next.push(next);
+        if next.is_none() {
+            self.last = Some(index);
+        }
+
+        index
+    }
+
+    fn into_ordered(self) -> Vec<ImportedFileEntry> {
+        let mut entries: Vec<Option<ImportedFileEntry>> =
+            self.entries.into_iter().map(Some).collect();
+        let mut ordered = Vec::with_capacity(entries.len());
+
+        let mut current = self.first;
+        while let Some(index) = current {
+            ordered.extend(entries[index].take());
+            current = self.next[index];
+        }
+        ordered
+    }
+}
+
+#[derive(Default)]
+struct Importer {
+    segments: Vec<ImportedSegment>,
+
+    sections_allowlist: Vec<String>,
+    sections_denylist: Vec<String>,
+    discard_wildcard_section: bool,
+    discard: Vec<DiscardRuleSerial>,
+    hardcoded_gp_value: Option<u64>,
+
+    entry: Option<String>,
+    symbol_assignments: Vec<SymbolAssignmentSerial>,
+    required_symbols: Vec<RequiredSymbolSerial>,
+    asserts: Vec<AssertEntrySerial>,
+
+    /// Address set by a `. = 0x...;` statement, used by the next output section.
+    pending_vram: Option<u64>,
+
+    unsupported: Vec<String>,
+}
+
+impl Importer {
+    fn parse_top_level(&mut self, cursor: &mut Cursor) -> Result<(), SlinkyError> {
+        while !cursor.is_eof() {
+            if cursor.eat(b';') {
+                continue;
+            }
+
+            let word = cursor.word();
+            match word {
+                "" => return Err(cursor.error("unexpected character")),
+                "SECTIONS" => {
+                    cursor.expect(b'{')?;
+                    self.parse_sections(cursor)?;
+                }
+                "ENTRY" => {
+                    self.entry = Some(cursor.parenthesized()?.trim().to_string());
+                    cursor.eat(b';');
+                }
+                "ASSERT" => self.parse_assert(cursor)?,
+                "EXTERN" => {
+                    for name in split_top_level(cursor.parenthesized()?, |c| {
+                        c.is_ascii_whitespace() || c == ','
+                    }) {
+                        self.required_symbols.push(RequiredSymbolSerial {
+                            name: name.to_string(),
+                            expected_value: AbsentNullable::Absent,
+                            expected_range: AbsentNullable::Absent,
+                            provide_fallback: AbsentNullable::Absent,
+                            comment: AbsentNullable::Absent,
+                            include_if_any: AbsentNullable::Absent,
+                            include_if_all: AbsentNullable::Absent,
+                            exclude_if_any: AbsentNullable::Absent,
+                            exclude_if_all: AbsentNullable::Absent,
+                            metadata: IgnoredMetadata,
+                        });
+                    }
+                    cursor.eat(b';');
+                }
+                "PROVIDE" | "HIDDEN" | "PROVIDE_HIDDEN" => {
+                    let inner = cursor.parenthesized()?;
+                    cursor.eat(b';');
+
+                    match inner.split_once('=') {
+                        Some((name, value)) => self.add_symbol_assignment(
+                            name.trim(),
+                            value.trim(),
+                            word != "HIDDEN",
+                            word != "PROVIDE",
+                        ),
+                        None => self
+                            .unsupported
+                            .push(format!("Malformed statement `{}({})`", word, inner)),
+                    }
+                }
+                "MEMORY" | "PHDRS" | "VERSION" => {
+                    cursor.skip_block()?;
+                    self.unsupported.push(format!("`{}` block", word));
+                }
+                _ => {
+                    if let Some(op) = cursor.assignment_op() {
+                        let value = cursor.expression();
+
+                        if op == "=" {
+                            self.add_symbol_assignment(word, value, false, false);
+                        } else {
+                            self.unsupported
+                                .push(format!("Symbol assignment `{} {} {};`", word, op, value));
+                        }
+                    } else {
+                        let args = if cursor.peek() == Some(b'(') {
+                            cursor.parenthesized()?
+                        } else {
+                            ""
+                        };
+                        cursor.eat(b';');
+
+                        self.unsupported
+                            .push(format!("Command `{}({})`", word, args.trim()));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_symbol_assignment(&mut self, name: &str, value: &str, provide: bool, hidden: bool) {
+        self.symbol_assignments.push(SymbolAssignmentSerial {
+            name: name.to_string(),
+            value: AbsentNullable::Value(value.to_string()),
+            alias_of: AbsentNullable::Absent,
+            provide: AbsentNullable::Value(provide),
+            hidden: AbsentNullable::Value(hidden),
+            placement: AbsentNullable::Absent,
+            inside_segment: AbsentNullable::Absent,
+            include_if_any: AbsentNullable::Absent,
+            include_if_all: AbsentNullable::Absent,
+            exclude_if_any: AbsentNullable::Absent,
+            exclude_if_all: AbsentNullable::Absent,
+            metadata: IgnoredMetadata,
+        });
+    }
+
+    fn parse_assert(&mut self, cursor: &mut Cursor) -> Result<(), SlinkyError> {
+        let inner = cursor.parenthesized()?;
+        cursor.eat(b';');
+
+        let args = split_top_level(inner, |c| c == ',');
+        if args.len() != 2 {
+            self.unsupported
+                .push(format!("Malformed statement `ASSERT({})`", inner));
+            return Ok(());
+        }
+
+        let check = strip_wrapping_parens(args[0]);
+        let message = args[1].trim_matches('"');
+        let message = message.strip_prefix("Error: ").unwrap_or(message);
+
+        // Required symbols are emitted alongside an assert, avoid duplicating it.
+        if self
+            .required_symbols
+            .iter()
+            .any(|x| check == format!("DEFINED({})", x.name))
+        {
+            return Ok(());
+        }
+
+        self.asserts.push(AssertEntrySerial {
+            check: check.to_string(),
+            error_message: message.to_string(),
+            warning: AbsentNullable::Absent,
+            include_if_any: AbsentNullable::Absent,
+            include_if_all: AbsentNullable::Absent,
+            exclude_if_any: AbsentNullable::Absent,
+            exclude_if_all: AbsentNullable::Absent,
+            metadata: IgnoredMetadata,
+        });
+
+        Ok(())
+    }
+
+    fn parse_sections(&mut self, cursor: &mut Cursor) -> Result<(), SlinkyError> {
+        loop {
+            if cursor.is_eof() {
+                return Err(cursor.error("unterminated `SECTIONS` block"));
+            }
+            if cursor.eat(b'}') {
+                return Ok(());
+            }
+            if cursor.eat(b';') {
+                continue;
+            }
+
+            let word = cursor.word();
+            match word {
+                "" => return Err(cursor.error("unexpected character")),
+                "ASSERT" => self.parse_assert(cursor)?,
+                "PROVIDE" | "HIDDEN" | "PROVIDE_HIDDEN" | "INCLUDE" => {
+                    let inner = cursor.parenthesized()?;
+                    cursor.eat(b';');
+
+                    self.unsupported
+                        .push(format!("`{}({})` inside `SECTIONS`", word, inner));
+                }
+                "OVERLAY" => {
+                    // The address and the `AT` of the header
+                    cursor.skip_until_block();
+                    cursor.skip_block()?;
+                    self.unsupported.push("`OVERLAY` block".to_string());
+                }
+                _ => {
+                    if let Some(op) = cursor.assignment_op() {
+                        let value = cursor.expression();
+                        self.section_level_assignment(word, op, value);
+                    } else {
+                        self.parse_output_section(cursor, word)?;
+                    }
+                }
+            }
+        }
+    }
+
+    fn section_level_assignment(&mut self, symbol: &str, op: &str, value: &str) {
+        match (symbol, op) {
+            // The rom position is handled by slinky itself
+            ("__romPos", _) => {}
+            (".", "=") => match parse_address(value) {
+                Some(address) => self.pending_vram = Some(address),
+                None => self
+                    .unsupported
+                    .push(format!("Location counter assignment `. = {};`", value)),
+            },
+            ("_gp", "=") if parse_address(value).is_some() => {
+                self.hardcoded_gp_value = parse_address(value);
+            }
+            _ => self
+                .unsupported
+                .push(format!("Symbol assignment `{} {} {};`", symbol, op, value)),
+        }
+    }
+
+    fn parse_output_section(&mut self, cursor: &mut Cursor, name: &str) -> Result<(), SlinkyError> {
+        // Everything between the name and the colon is the address and the type.
+        let header_start = cursor.pos;
+        let mut depth = 0;
+        loop {
+            match cursor.peek() {
+                None => return Err(cursor.error("unterminated output section header")),
+                Some(b'(') => depth += 1,
+                Some(b')') => depth -= 1,
+                Some(b':') if depth == 0 => break,
+                _ => {}
+            }
+            cursor.pos += 1;
+        }
+        let mut address = cursor.src[header_start..cursor.pos].trim();
+        cursor.pos += 1;
+
+        let mut noload = false;
+        if address.ends_with(')') {
+            if let Some(type_start) = address.rfind('(') {
+                let section_type = address[type_start + 1..address.len() - 1].trim();
+
+                match section_type {
+                    "NOLOAD" => {
+                        noload = true;
+                        address = address[..type_start].trim();
+                    }
+                    "DSECT" | "COPY" | "INFO" | "OVERLAY" | "READONLY" => {
+                        self.unsupported.push(format!(
+                            "Output section type `({})` of `{}`",
+                            section_type, name
+                        ));
+                        address = address[..type_start].trim();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut subalign = None;
+        loop {
+            cursor.skip_ws();
+            if cursor.peek() == Some(b'{') {
+                break;
+            }
+
+            let attribute = cursor.word();
+            match attribute {
+                "" => return Err(cursor.error("expected '{'")),
+                // The load address is computed by slinky
+                "AT" => {
+                    cursor.parenthesized()?;
+                }
+                "SUBALIGN" => {
+                    let value = cursor.parenthesized()?;
+                    subalign = parse_number(value);
+                    if subalign.is_none() {
+                        self.unsupported
+                            .push(format!("`SUBALIGN({})` of `{}`", value, name));
+                    }
+                }
+                _ => {
+                    let args = if cursor.peek() == Some(b'(') {
+                        format!("({})", cursor.parenthesized()?)
+                    } else {
+                        "".to_string()
+                    };
+                    self.unsupported.push(format!(
+                        "Output section attribute `{}{}` of `{}`",
+                        attribute, args, name
+                    ));
+                }
+            }
+        }
+        cursor.expect(b'{')?;
+
+        let body = self.parse_output_section_body(cursor, name)?;
+
+        // Trailing attributes, like `> region`, `AT> region`, `:phdr` or `=fill`
+        loop {
+            cursor.skip_ws();
+
+            let attribute = if cursor.eat(b'=') {
+                format!("={}", cursor.word())
+            } else if cursor.starts_with(">") || cursor.starts_with("AT>") {
+                let mut attribute = cursor.word().to_string();
+                if attribute.ends_with('>') {
+                    attribute += cursor.word();
+                }
+                attribute
+            } else if cursor.starts_with(":") {
+                cursor.pos += 1;
+                format!(":{}", cursor.word())
+            } else {
+                break;
+            };
+
+            self.unsupported.push(format!(
+                "Output section attribute `{}` of `{}`",
+                attribute, name
+            ));
+        }
+
+        if name == "/DISCARD/" {
+            for (file, sections, _) in &body.descriptions {
+                if file == "*" {
+                    for section in sections {
+                        if section == "*" {
+                            self.discard_wildcard_section = true;
+                        } else {
+                            self.sections_denylist.push(section.clone());
+                        }
+                    }
+                } else {
+                    // `libfoo.a:` matches every member of the archive, which
+                    // is how archives are discarded
+                    let file = match file.strip_suffix(':') {
+                        Some(archive)
+                            if FileKind::from_path(Path::new(archive)) == FileKind::Archive =>
+                        {
+                            archive
+                        }
+                        Some(_) => {
+                            self.unsupported
+                                .push(format!("Discarding sections of every member of `{}`", file));
+                            continue;
+                        }
+                        None => file.as_str(),
+                    };
+
+                    self.discard.push(DiscardRuleSerial {
+                        file: file.to_string(),
+                        sections: AbsentNullable::Value(sections.clone()),
+                        metadata: IgnoredMetadata,
+                    });
+                }
+            }
+            return Ok(());
+        }
+
+        if !body.descriptions.is_empty() && body.descriptions.iter().all(|(f, _, _)| f == "*") {
+            if address == "0" {
+                self.sections_allowlist.push(name.to_string());
+            } else {
+                self.unsupported.push(format!(
+                    "Output section `{}` only contains wildcard input sections",
+                    name
+                ));
+            }
+            return Ok(());
+        }
+
+        self.add_output_section(name, address, noload, subalign, body);
+
+        Ok(())
+    }
+
+    fn parse_output_section_body(
+        &mut self,
+        cursor: &mut Cursor,
+        name: &str,
+    ) -> Result<OutputSectionBody, SlinkyError> {
+        let mut body = OutputSectionBody::default();
+
+        loop {
+            if cursor.is_eof() {
+                return Err(cursor.error(&format!("unterminated output section `{}`", name)));
+            }
+            if cursor.eat(b'}') {
+                return Ok(body);
+            }
+            if cursor.eat(b';') {
+                continue;
+            }
+
+            let word = cursor.word();
+            match word {
+                "" => return Err(cursor.error("unexpected character")),
+                "FILL" => {
+                    let value = cursor.parenthesized()?;
+                    body.fill_value = parse_number(value);
+                    if body.fill_value.is_none() {
+                        self.unsupported
+                            .push(format!("`FILL({})` inside `{}`", value, name));
+                    }
+                }
+                "KEEP" => {
+                    let inner = cursor.parenthesized()?;
+                    let mut inner_cursor = Cursor::new(inner);
+
+                    while !inner_cursor.is_eof() {
+                        let file = inner_cursor.word();
+                        if file.is_empty() || inner_cursor.peek() != Some(b'(') {
+                            self.unsupported
+                                .push(format!("`KEEP({})` inside `{}`", inner, name));
+                            break;
+                        }
+                        let sections = inner_cursor.parenthesized()?;
+                        self.add_input_description(&mut body, name, file, sections, true);
+                    }
+                }
+                "ASSERT" => self.parse_assert(cursor)?,
+                _ => {
+                    if let Some(op) = cursor.assignment_op() {
+                        let value = cursor.expression();
+
+                        match (word, op, parse_number(value)) {
+                            (".", "+=", Some(amount)) => match &body.current_section {
+                                Some(section) => body.pads.push((
+                                    body.descriptions.len(),
+                                    amount,
+                                    section.clone(),
+                                )),
+                                None => self.unsupported.push(format!(
+                                    "Padding `. += {};` before any input section inside `{}`",
+                                    value, name
+                                )),
+                            },
+                            _ => self.unsupported.push(format!(
+                                "Symbol assignment `{} {} {};` inside `{}`",
+                                word, op, value, name
+                            )),
+                        }
+                    } else if cursor.starts_with(":(") {
+                        // `archive:(...)`, matching every member of the archive.
+                        // The colon is not part of the word
+                        cursor.pos += 1;
+                        let sections = cursor.parenthesized()?;
+                        let file = format!("{}:", word);
+                        self.add_input_description(&mut body, name, &file, sections, false);
+                    } else if cursor.peek() == Some(b'(') {
+                        let sections = cursor.parenthesized()?;
+
+                        if word.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+                            // Commands like `LONG(...)` or `SORT(...)`
+                            self.unsupported
+                                .push(format!("`{}({})` inside `{}`", word, sections, name));
+                        } else {
+                            self.add_input_description(&mut body, name, word, sections, false);
+                        }
+                    } else {
+                        self.unsupported
+                            .push(format!("`{}` inside `{}`", word, name));
+                    }
+                    cursor.eat(b';');
+                }
+            }
+        }
+    }
+
+    fn add_input_description(
+        &mut self,
+        body: &mut OutputSectionBody,
+        name: &str,
+        file: &str,
+        sections: &str,
+        keep: bool,
+    ) {
+        let mut section_names = Vec::new();
+
+        for section in split_top_level(sections, |c| c.is_ascii_whitespace()) {
+            if section.contains('(') {
+                self.unsupported.push(format!(
+                    "Input section `{}({})` inside `{}`",
+                    file, sections, name
+                ));
+                continue;
+            }
+            section_names.push(section.to_string());
+        }
+
+        if let Some(last) = section_names.last() {
+            body.current_section = Some(last.trim_end_matches('*').to_string());
+        }
+        body.descriptions
+            .push((file.to_string(), section_names, keep));
+    }
+
+    fn add_output_section(
+        &mut self,
+        name: &str,
+        address: &str,
+        noload: bool,
+        subalign: Option<u32>,
+        body: OutputSectionBody,
+    ) {
+        let seg_name = name.strip_prefix('.').unwrap_or(name);
+
+        let attach_to_previous = noload
+            && match self.segments.last() {
+                Some(prev) => {
+                    seg_name.strip_suffix(".noload") == Some(&prev.name)
+                        || (address.is_empty() && !prev.has_noload)
+                }
+                None => false,
+            };
+
+        if !attach_to_previous {
+            let mut segment = ImportedSegment {
+                name: seg_name.to_string(),
+                subalign,
+                ..Default::default()
+            };
+
+            if address.is_empty() {
+                segment.fixed_vram = self.pending_vram;
+            } else if let Some(vram) = parse_address(address) {
+                segment.fixed_vram = Some(vram);
+            } else if is_identifier(address) {
+                segment.fixed_symbol = Some(address.to_string());
+            } else {
+                self.unsupported.push(format!(
+                    "Address expression `{}` of output section `{}`",
+                    address, name
+                ));
+            }
+
+            self.segments.push(segment);
+        }
+        self.pending_vram = None;
+
+        let segment = self
+            .segments
+            .last_mut()
+            .expect("A segment was pushed above");
+
+        if noload {
+            segment.has_noload = true;
+        }
+        if body.fill_value.is_some() {
+            segment.fill_value = body.fill_value;
+        }
+
+        let mut pads = body.pads.into_iter().peekable();
+        // Index on the file list of the last file we processed, paddings are placed after it
+        let mut last_index = None;
+        for (i, (file, sections, keep)) in body.descriptions.into_iter().enumerate() {
+            while let Some((_, amount, section)) = pads.next_if(|(pos, _, _)| *pos <= i) {
+                last_index = Some(
+                    segment
+                        .files
+                        .insert_after(last_index, ImportedFileEntry::Pad { amount, section }),
+                );
+            }
+
+            let (path, subfile) = match file.split_once(':') {
+                Some((archive, member)) => (archive, Some(member.to_string())),
+                None => (file.as_str(), None),
+            };
+
+            if path.contains(['*', '?', '[']) {
+                self.unsupported.push(format!(
+                    "Wildcard file pattern `{}` inside `{}`",
+                    file, name
+                ));
+                continue;
+            }
+            let path = PathBuf::from(path);
+
+            let index = match segment.files.find_file(&path, &subfile) {
+                Some(index) => index,
+                None => segment.files.insert_after(
+                    last_index.or(segment.files.last),
+                    ImportedFileEntry::File {
+                        path,
+                        subfile,
+                        keep: HashSet::new(),
+                    },
+                ),
+            };
+            last_index = Some(index);
+
+            for section in sections {
+                let wildcard = section.ends_with('*');
+                let section = section.trim_end_matches('*').to_string();
+
+                match segment.wildcard_sections {
+                    None => segment.wildcard_sections = Some(wildcard),
+                    Some(w) if w != wildcard => self.unsupported.push(format!(
+                        "Mixing wildcard and non wildcard input sections inside `{}`",
+                        name
+                    )),
+                    Some(_) => {}
+                }
+
+                let list = if noload {
+                    &mut segment.noload_sections
+                } else {
+                    &mut segment.alloc_sections
+                };
+                if !list.contains(&section) {
+                    list.push(section.clone());
+                }
+
+                if keep {
+                    if let ImportedFileEntry::File { keep, .. } = &mut segment.files.entries[index]
+                    {
+                        keep.insert(section);
+                    }
+                }
+            }
+        }
+        for (_, amount, section) in pads {
+            last_index = Some(
+                segment
+                    .files
+                    .insert_after(last_index, ImportedFileEntry::Pad { amount, section }),
+            );
+        }
+    }
+
+    fn finish(self) -> Result<ImportedLinkerScript, SlinkyError> {
+        let mut unsupported = self.unsupported;
+
+        let mut segments = Vec::with_capacity(self.segments.len());
+        // Checked once the document exists, see below
+        let mut fixed_symbols = Vec::new();
+        for segment in self.segments {
+            if segment.files.is_empty() {
+                unsupported.push(format!(
+                    "Output section `.{}` does not have any file, so it was skipped",
+                    segment.name
+                ));
+                continue;
+            }
+
+            let files = segment
+                .files
+                .into_ordered()
+                .into_iter()
+                .map(|entry| match entry {
+                    ImportedFileEntry::File {
+                        path,
+                        subfile,
+                        keep,
+                    } => FileInfoSerial {
+                        kind: match subfile {
+                            Some(_) => AbsentNullable::Value(FileKind::Archive),
+                            None => AbsentNullable::Absent,
+                        },
+                        path: AbsentNullable::Value(path),
+                        subfile: AbsentNullable::absent_if_none(subfile),
+                        keep_sections: if keep.is_empty() {
+                            KeepSections::Absent
+                        } else {
+                            KeepSections::WhichOnes(keep)
+                        },
+                        ..Default::default()
+                    },
+                    ImportedFileEntry::Pad { amount, section } => FileInfoSerial {
+                        kind: AbsentNullable::Value(FileKind::Pad),
+                        pad_amount: AbsentNullable::Value(amount),
+                        section: AbsentNullable::Value(FileSectionsSerial::Single(section)),
+                        ..Default::default()
+                    },
+                })
+                .collect();
+
+            fixed_symbols.push(segment.fixed_symbol);
+            segments.push(SegmentSerial {
+                name: segment.name,
+                files,
+                fixed_vram: AbsentNullable::absent_if_none(segment.fixed_vram),
+                subalign: segment.subalign.into(),
+                fill_value: segment.fill_value.into(),
+                alloc_sections: AbsentNullable::Value(segment.alloc_sections.into()),
+                noload_sections: AbsentNullable::Value(segment.noload_sections.into()),
+                wildcard_sections: AbsentNullable::Value(segment.wildcard_sections.unwrap_or(true)),
+                ..Default::default()
+            });
+        }
+
+        // Avoid emitting the extra sections twice
+        let sections_allowlist_extra = Settings::default()
+            .sections_allowlist_extra
+            .into_iter()
+            .filter(|x| !self.sections_allowlist.contains(x))
+            .collect();
+
+        let settings = SettingsSerial {
+            hardcoded_gp_value: self.hardcoded_gp_value.into(),
+            sections_allowlist: AbsentNullable::Value(self.sections_allowlist),
+            sections_allowlist_extra: AbsentNullable::Value(sections_allowlist_extra),
+            sections_denylist: AbsentNullable::Value(self.sections_denylist),
+            discard_wildcard_section: AbsentNullable::Value(self.discard_wildcard_section),
+            discard: AbsentNullable::Value(self.discard),
+            ..Default::default()
+        };
+
+        let document_serial = DocumentSerial {
+            settings: AbsentNullable::Value(settings),
+            segments,
+            entry: AbsentNullable::absent_if_none(self.entry),
+            symbol_assignments: AbsentNullable::Value(self.symbol_assignments),
+            required_symbols: AbsentNullable::Value(self.required_symbols),
+            asserts: AbsentNullable::Value(self.asserts),
+            ..Default::default()
+        };
+
+        let mut document = document_serial.unserialize()?;
+
+        // A symbol named like a generated one which the imported document
+        // doesn't generate (i.e. because of a custom `symbol_name`) can't be
+        // represented, since it would make the document invalid
+        for (index, fixed_symbol) in fixed_symbols.into_iter().enumerate() {
+            let Some(fixed_symbol) = fixed_symbol else {
+                continue;
+            };
+
+            if resolve_symbol_expression(&document, &fixed_symbol).is_ok() {
+                document.segments[index].fixed_symbol = Some(fixed_symbol);
+            } else {
+                unsupported.push(format!(
+                    "Address `{}` of output section `.{}`, which is named like a symbol the imported document doesn't generate",
+                    fixed_symbol, document.segments[index].name
+                ));
+            }
+        }
+
+        Ok(ImportedLinkerScript {
+            document,
+            unsupported,
+        })
+    }
+}
+
+#[derive(Default)]
+struct OutputSectionBody {
+    /// Input section descriptions, as `(file pattern, sections, is kept)`
+    descriptions: Vec<(String, Vec<String>, bool)>,
+    /// Paddings, as `(index into descriptions, amount, section)`
+    pads: Vec<(usize, u32, String)>,
+
+    fill_value: Option<u32>,
+    current_section: Option<String>,
+}
@@ -2,53 +2,119 @@
 /* SPDX-License-Identifier: MIT */
 
 mod absent_nullable;
+mod diagnostic;
 mod error;
 mod escaped_path;
+mod ignored_metadata;
 mod traits;
 mod utils;
 
+mod archive;
+mod elf;
+
 mod linker_symbols_style;
+mod order_by;
+mod preset;
+mod rom_model;
 mod settings;
+mod symbols_asm_dialect;
 
 mod assert_entry;
+mod discard_rule;
 mod file_info;
 mod file_kind;
+mod files_list;
 mod gp_info;
+mod import;
 mod keep_sections;
+mod raw_block;
 mod required_symbol;
+mod runtime_option;
+mod section_entry;
 mod segment;
 mod symbol_assignment;
+mod target;
+mod toolchain_profile;
 
 mod vram_class;
 
 mod document;
+#[cfg(feature = "fs")]
+mod document_cache;
+mod formatter;
+mod ld_script_importer;
+mod resolved_document;
+#[cfg(feature = "splat")]
+mod splat_importer;
+mod validation;
+mod yaml_writer;
 
 mod script_buffer;
+mod script_item;
 
 mod linker_writer;
 mod partial_linker_writer;
 
+mod generate;
+
+mod binary_rules;
+mod coverage;
+mod explain;
+mod fingerprint;
+mod gdb_script;
+mod map_verifier;
+mod memory_map;
+mod objects_list;
+mod placement_advisor;
+mod script_diff;
+mod segment_table;
+mod size_diff;
+mod statistics;
+mod symbols_manifest;
+
 mod runtime_settings;
+mod script_flavor;
+mod written_file;
 
+#[cfg(feature = "fs")]
+pub mod batch;
 pub mod version;
 
+#[cfg(feature = "test_fixtures")]
+pub mod test_fixtures;
+
+pub use diagnostic::{Diagnostic, EntryPath, EntryPathComponent, Severity, SourceLocation};
 pub use error::SlinkyError;
 pub use escaped_path::EscapedPath;
+pub use version::Version;
 
 pub use linker_symbols_style::LinkerSymbolsStyle;
+pub use order_by::OrderBy;
+pub use preset::Preset;
+pub use rom_model::RomModel;
 pub use settings::Settings;
+pub use symbols_asm_dialect::SymbolsAsmDialect;
 
 pub use assert_entry::AssertEntry;
+pub use discard_rule::DiscardRule;
 pub use file_info::FileInfo;
 pub use file_kind::FileKind;
+pub use import::Import;
 pub use keep_sections::KeepSections;
+pub use raw_block::{RawBlock, RawBlockPlacement};
 pub use required_symbol::RequiredSymbol;
+pub use runtime_option::RuntimeOption;
 pub use segment::Segment;
-pub use symbol_assignment::SymbolAssignment;
+pub use symbol_assignment::{SymbolAssignment, SymbolAssignmentPlacement};
+pub use target::Target;
 
 pub use vram_class::VramClass;
 
 pub use document::Document;
+#[cfg(feature = "fs")]
+pub use document_cache::DocumentCache;
+pub use formatter::FormattedYaml;
+pub use ld_script_importer::ImportedLinkerScript;
 
 pub use traits::ScriptExporter;
 pub use traits::ScriptGenerator;
@@ -56,5 +122,25 @@ pub use traits::ScriptImporter;
 
 pub use linker_writer::LinkerWriter;
 pub use partial_linker_writer::PartialLinkerWriter;
+pub use script_item::ScriptItem;
+
+pub use generate::{
+    generate, generate_document, generate_with_mode, GeneratedOutputs, GenerationMode,
+};
+
+pub use coverage::CoverageReport;
+pub use explain::{EmitReason, FileExplanation, PlacementStep, SegmentExplanation};
+pub use fingerprint::read_fingerprint;
+pub use map_verifier::{MapIssue, MapReport, MapVerifier};
+pub use objects_list::ObjectsListFormat;
+pub use placement_advisor::{
+    PlacementAdvisor, PlacementReport, PlacementSuggestion, VramClassUsage,
+};
+pub use script_diff::{ScriptChange, ScriptDiff};
+pub use size_diff::{SegmentSizeDiff, SizeDelta, SizeDiff, SizeDiffer};
+pub use statistics::GenerationStatistics;
+pub use symbols_manifest::SymbolOrigin;
 
 pub use runtime_settings::RuntimeSettings;
+pub use script_flavor::ScriptFlavor;
+pub use written_file::WrittenFile;
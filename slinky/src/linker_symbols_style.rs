@@ -1,11 +1,11 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::utils;
+use crate::{utils, Document, RomModel, SlinkyError};
 
-#[derive(Deserialize, PartialEq, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum LinkerSymbolsStyle {
     Splat,
@@ -34,6 +34,13 @@ impl LinkerSymbolsStyle {
         }
     }
 
+    pub fn segment_compressed_size(&self, seg_name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_COMPRESSED_SIZE", seg_name),
+            LinkerSymbolsStyle::Makerom => format!("_{}SegmentCompressedSize", seg_name),
+        }
+    }
+
     pub fn segment_vram_start(&self, seg_name: &str) -> String {
         match self {
             LinkerSymbolsStyle::Splat => format!("{}_VRAM", seg_name),
@@ -55,6 +62,82 @@ impl LinkerSymbolsStyle {
         }
     }
 
+    pub fn binary_start(&self, name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_BIN_START", name),
+            LinkerSymbolsStyle::Makerom => format!("_{}BinStart", name),
+        }
+    }
+
+    pub fn binary_end(&self, name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_BIN_END", name),
+            LinkerSymbolsStyle::Makerom => format!("_{}BinEnd", name),
+        }
+    }
+
+    pub fn binary_size(&self, name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_BIN_SIZE", name),
+            LinkerSymbolsStyle::Makerom => format!("_{}BinSize", name),
+        }
+    }
+
+    /// The symbol marking the start of the section of a file which uses
+    /// `pad_to_size`.
+    pub fn file_pad_start(&self, seg_name: &str, file_name: &str, section_type: &str) -> String {
+        let sec = self.convert_section_name_to_linker_format(section_type);
+
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_{}{}_PAD_START", seg_name, file_name, sec),
+            LinkerSymbolsStyle::Makerom => {
+                format!("_{}Segment{}{}PadStart", seg_name, file_name, sec)
+            }
+        }
+    }
+
+    /// The symbol marking the start of a section of a file which uses
+    /// `forbid_sections`.
+    pub fn file_forbid_start(&self, seg_name: &str, file_name: &str, section_type: &str) -> String {
+        let sec = self.convert_section_name_to_linker_format(section_type);
+
+        match self {
+            LinkerSymbolsStyle::Splat => {
+                format!("{}_{}{}_FORBID_START", seg_name, file_name, sec)
+            }
+            LinkerSymbolsStyle::Makerom => {
+                format!("_{}Segment{}{}ForbidStart", seg_name, file_name, sec)
+            }
+        }
+    }
+
+    /// The symbol marking the start of a section of a file of a segment which
+    /// uses `emit_file_symbols`.
+    pub fn file_section_start(
+        &self,
+        seg_name: &str,
+        file_name: &str,
+        section_type: &str,
+    ) -> String {
+        let sec = self.convert_section_name_to_linker_format(section_type);
+
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_{}{}_START", seg_name, file_name, sec),
+            LinkerSymbolsStyle::Makerom => {
+                format!("_{}Segment{}{}Start", seg_name, file_name, sec)
+            }
+        }
+    }
+
+    pub fn file_section_end(&self, seg_name: &str, file_name: &str, section_type: &str) -> String {
+        let sec = self.convert_section_name_to_linker_format(section_type);
+
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_{}{}_END", seg_name, file_name, sec),
+            LinkerSymbolsStyle::Makerom => format!("_{}Segment{}{}End", seg_name, file_name, sec),
+        }
+    }
+
     fn convert_section_name_to_linker_format(&self, section_type: &str) -> String {
         match self {
             LinkerSymbolsStyle::Splat => section_type.replace('.', "_").to_uppercase(),
@@ -98,6 +181,20 @@ impl LinkerSymbolsStyle {
         }
     }
 
+    pub fn segment_bss_vram_start(&self, seg_name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_BSS_VRAM_START", seg_name),
+            LinkerSymbolsStyle::Makerom => format!("_{}SegmentBssVramStart", seg_name),
+        }
+    }
+
+    pub fn segment_bss_vram_size(&self, seg_name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_BSS_VRAM_SIZE", seg_name),
+            LinkerSymbolsStyle::Makerom => format!("_{}SegmentBssVramSize", seg_name),
+        }
+    }
+
     pub fn linker_offset(&self, name: &str) -> String {
         match self {
             LinkerSymbolsStyle::Splat => format!("{}_OFFSET", name),
@@ -105,6 +202,17 @@ impl LinkerSymbolsStyle {
         }
     }
 
+    /// The linker offset symbol of a `linker_offset` entry which is emitted
+    /// on multiple sections.
+    pub fn section_linker_offset(&self, name: &str, section_type: &str) -> String {
+        let sec = self.convert_section_name_to_linker_format(section_type);
+
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}{}_OFFSET", name, sec),
+            LinkerSymbolsStyle::Makerom => format!("_{}{}Offset", name, sec),
+        }
+    }
+
     pub fn vram_class_start(&self, name: &str) -> String {
         match self {
             LinkerSymbolsStyle::Splat => format!("{}_VRAM_CLASS_START", name),
@@ -119,6 +227,20 @@ impl LinkerSymbolsStyle {
         }
     }
 
+    pub fn segment_vram_class_slack(&self, seg_name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_VRAM_CLASS_SLACK", seg_name),
+            LinkerSymbolsStyle::Makerom => format!("_{}SegmentVramClassSlack", seg_name),
+        }
+    }
+
+    pub fn vram_class_high_water(&self, name: &str) -> String {
+        match self {
+            LinkerSymbolsStyle::Splat => format!("{}_VRAM_CLASS_HIGH_WATER", name),
+            LinkerSymbolsStyle::Makerom => format!("_{}VramClassHighWater", name),
+        }
+    }
+
     pub fn vram_class_size(&self, name: &str) -> String {
         match self {
             LinkerSymbolsStyle::Splat => format!("{}_VRAM_CLASS_SIZE", name),
@@ -126,3 +248,191 @@ impl LinkerSymbolsStyle {
         }
     }
 }
+
+/// Replaces every `{{name}}` reference of `value` with the name given to that
+/// symbol by the `linker_symbols_style` of the document, see
+/// [`resolve_logical_symbol`].
+pub(crate) fn resolve_logical_symbols(d: &Document, value: &str) -> Result<String, SlinkyError> {
+    let mut resolved = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => {
+                return Err(SlinkyError::UnclosedLogicalSymbol {
+                    value: value.to_string(),
+                })
+            }
+        };
+
+        let symbol = rest[start + 2..end].trim();
+        match resolve_logical_symbol(d, symbol) {
+            Some(name) => {
+                resolved += &rest[..start];
+                resolved += &name;
+            }
+            None => {
+                return Err(SlinkyError::UnknownLogicalSymbol {
+                    symbol: symbol.to_string(),
+                })
+            }
+        }
+
+        rest = &rest[end + 2..];
+    }
+    resolved += rest;
+
+    Ok(resolved)
+}
+
+/// Resolves a linker script expression, like `{{boot_VRAM_END}} + 0x400` or
+/// `boot_VRAM_END + 0x400`.
+///
+/// Besides the `{{name}}` references, every identifier named like a symbol
+/// generated for the document on the `splat` style is replaced with its name
+/// on the style used by the document. The ones named like the symbols of a
+/// segment which doesn't exist are reported, since the linker would fail to
+/// find them anyways. Any other identifier is left as is.
+pub(crate) fn resolve_symbol_expression(
+    d: &Document,
+    expression: &str,
+) -> Result<String, SlinkyError> {
+    let expression = resolve_logical_symbols(d, expression)?;
+
+    let mut resolved = String::new();
+    let mut rest = expression.as_str();
+
+    while let Some(c) = rest.chars().next() {
+        let token_len = if c.is_ascii_alphanumeric() || c == '_' {
+            rest.find(|x: char| !x.is_ascii_alphanumeric() && x != '_')
+                .unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        let (token, remaining) = rest.split_at(token_len);
+
+        // Numbers like `0x400` are not identifiers
+        let is_identifier = c.is_ascii_alphabetic() || c == '_';
+        if let Some(name) = is_identifier
+            .then(|| resolve_logical_symbol(d, token))
+            .flatten()
+        {
+            resolved += &name;
+        } else if is_identifier && is_generated_symbol_name(token) {
+            return Err(SlinkyError::UnknownGeneratedSymbol {
+                symbol: token.to_string(),
+            });
+        } else {
+            resolved += token;
+        }
+
+        rest = remaining;
+    }
+
+    Ok(resolved)
+}
+
+/// The linker script builtins which query the output sections.
+const SECTION_FUNCTIONS: [&str; 4] = ["ADDR", "LOADADDR", "SIZEOF", "ALIGNOF"];
+
+/// Whether `expression` depends on something placed by the `SECTIONS` block,
+/// like the symbols generated for the segments and vram classes or the
+/// address of an output section.
+pub(crate) fn references_sections(d: &Document, expression: &str) -> bool {
+    if expression.contains("{{") {
+        return true;
+    }
+
+    expression
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|x| x.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+        .any(|token| {
+            SECTION_FUNCTIONS.contains(&token)
+                || is_generated_symbol_name(token)
+                || resolve_logical_symbol(d, token).is_some()
+        })
+}
+
+type NameFn = fn(&LinkerSymbolsStyle, &str) -> String;
+type SectionNameFn = fn(&LinkerSymbolsStyle, &str, &str) -> String;
+
+const SEGMENT_SYMBOLS: [NameFn; 10] = [
+    LinkerSymbolsStyle::segment_rom_start,
+    LinkerSymbolsStyle::segment_rom_end,
+    LinkerSymbolsStyle::segment_rom_size,
+    LinkerSymbolsStyle::segment_compressed_size,
+    LinkerSymbolsStyle::segment_vram_start,
+    LinkerSymbolsStyle::segment_vram_end,
+    LinkerSymbolsStyle::segment_vram_size,
+    LinkerSymbolsStyle::segment_bss_vram_start,
+    LinkerSymbolsStyle::segment_bss_vram_size,
+    LinkerSymbolsStyle::segment_vram_class_slack,
+];
+const SECTION_SYMBOLS: [SectionNameFn; 3] = [
+    LinkerSymbolsStyle::segment_section_start,
+    LinkerSymbolsStyle::segment_section_end,
+    LinkerSymbolsStyle::segment_section_size,
+];
+const VRAM_CLASS_SYMBOLS: [NameFn; 4] = [
+    LinkerSymbolsStyle::vram_class_start,
+    LinkerSymbolsStyle::vram_class_end,
+    LinkerSymbolsStyle::vram_class_high_water,
+    LinkerSymbolsStyle::vram_class_size,
+];
+
+/// Whether `symbol` looks like the `splat` style name of a symbol generated
+/// for a segment, like `boot_VRAM_END`.
+///
+/// The vram classes are left out, since their symbols may be defined by
+/// another linker script.
+fn is_generated_symbol_name(symbol: &str) -> bool {
+    SEGMENT_SYMBOLS.iter().any(|f| {
+        let suffix = f(&LinkerSymbolsStyle::Splat, "");
+        symbol.len() > suffix.len() && symbol.ends_with(&suffix)
+    })
+}
+
+/// Looks for a symbol generated for the document whose `splat` style name is
+/// `symbol`, returning its name on the style used by the document.
+pub(crate) fn resolve_logical_symbol(d: &Document, symbol: &str) -> Option<String> {
+    let logical = LinkerSymbolsStyle::Splat;
+    let style = &d.settings.linker_symbols_style;
+    // The rom symbols come first, and they are not emitted without a rom
+    let segment_symbols = match d.settings.rom_model {
+        RomModel::Rom => &SEGMENT_SYMBOLS[..],
+        RomModel::None => &SEGMENT_SYMBOLS[4..],
+    };
+
+    for segment in &d.segments {
+        let name = &segment.symbol_name;
+
+        for f in segment_symbols {
+            if f(&logical, name) == symbol {
+                return Some(f(style, name));
+            }
+        }
+
+        for section in segment
+            .alloc_sections
+            .iter()
+            .chain(&segment.noload_sections)
+        {
+            for f in SECTION_SYMBOLS {
+                if f(&logical, name, section) == symbol {
+                    return Some(f(style, name, section));
+                }
+            }
+        }
+    }
+
+    for vram_class in &d.vram_classes {
+        for f in VRAM_CLASS_SYMBOLS {
+            if f(&logical, &vram_class.name) == symbol {
+                return Some(f(style, &vram_class.name));
+            }
+        }
+    }
+
+    None
+}
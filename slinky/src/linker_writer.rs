@@ -2,23 +2,140 @@
 /* SPDX-License-Identifier: MIT */
 
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use crate::{
-    utils, version, AssertEntry, Document, EscapedPath, FileInfo, FileKind, KeepSections,
-    RequiredSymbol, RuntimeSettings, ScriptExporter, ScriptGenerator, ScriptImporter, Segment,
-    SlinkyError, SymbolAssignment, VramClass,
+    utils, version, AssertEntry, Document, EntryPath, EntryPathComponent, EscapedPath, FileInfo,
+    FileKind, GenerationStatistics, Import, KeepSections, LinkerSymbolsStyle, OrderBy, RawBlock,
+    RawBlockPlacement, RequiredSymbol, RomModel, RuntimeSettings, ScriptExporter, ScriptGenerator,
+    ScriptImporter, Segment, SlinkyError, SymbolAssignment, SymbolAssignmentPlacement,
+    SymbolOrigin, VramClass, WrittenFile,
 };
 
+use crate::archive;
+use crate::binary_rules;
+use crate::fingerprint;
+use crate::gdb_script::{self, GdbSegment};
+use crate::linker_symbols_style;
+use crate::memory_map::{MemoryMap, MemoryMapSegment, MemoryMapVramClass};
+use crate::objects_list::{self, ObjectsListFormat};
 use crate::script_buffer::ScriptBuffer;
+use crate::script_item::{self, ScriptItem};
+use crate::segment;
+use crate::segment_table;
+use crate::symbols_manifest;
+
+/// Everything placed between this line and the closing `#endif` of the
+/// symbols header is kept when regenerating it, see
+/// `symbols_header_keep_user_additions`.
+const SYMBOLS_HEADER_USER_ADDITIONS_MARKER: &str =
+    "/* Additions below this line are kept when regenerating this header */";
+
+// The non-loaded sections used by debuggers, mirroring the ones listed on the
+// default linker scripts of GNU ld.
+const DEBUG_SECTIONS: [&str; 31] = [
+    ".mdebug",
+    ".pdr",
+    ".comment",
+    ".gnu.attributes",
+    // DWARF 1
+    ".debug",
+    ".line",
+    // GNU DWARF 1 extensions
+    ".debug_srcinfo",
+    ".debug_sfnames",
+    // DWARF 1.1 and DWARF 2
+    ".debug_aranges",
+    ".debug_pubnames",
+    // DWARF 2
+    ".debug_info",
+    ".debug_abbrev",
+    ".debug_line",
+    ".debug_frame",
+    ".debug_str",
+    ".debug_loc",
+    ".debug_macinfo",
+    // SGI/MIPS DWARF 2 extensions
+    ".debug_weaknames",
+    ".debug_funcnames",
+    ".debug_typenames",
+    ".debug_varnames",
+    // DWARF 3
+    ".debug_pubtypes",
+    ".debug_ranges",
+    // DWARF 5
+    ".debug_addr",
+    ".debug_line_str",
+    ".debug_loclists",
+    ".debug_macro",
+    ".debug_names",
+    ".debug_rnglists",
+    ".debug_str_offsets",
+    ".debug_sup",
+];
 
 pub struct LinkerWriter<'a> {
     buffer: ScriptBuffer,
+    // The parts of the script moved to the `common_script_path`, if set
+    common_script: Option<ScriptBuffer>,
 
     // Used for dependency generation
     files_paths: indexmap::IndexSet<EscapedPath>,
+    // Files generated alongside this script which are not listed on the
+    // settings, like the partial scripts
+    extra_generated_files: Vec<EscapedPath>,
+    // The objects and archives referenced by the script, a subset of
+    // `files_paths`, both in total and per segment
+    objects_paths: indexmap::IndexSet<EscapedPath>,
+    segment_objects_paths: indexmap::IndexMap<String, indexmap::IndexSet<EscapedPath>>,
+    toolchain_objects_paths: indexmap::IndexMap<String, indexmap::IndexSet<EscapedPath>>,
+
+    // The escaped paths of the files, keyed by the base path they are
+    // relative to and their unescaped path. Every file is emitted once per
+    // section, so escaping them each time adds up on big documents
+    escaped_paths: HashMap<EscapedPath, HashMap<PathBuf, EscapedPath>>,
+
+    // The members of each archive read by `check_archive_members`, so every
+    // archive is read once
+    archives_members: HashMap<EscapedPath, Vec<String>>,
 
     vram_classes: indexmap::IndexMap<String, VramClass>,
+    // vram classes which have at least one segment that will be emitted
+    used_vram_classes: indexmap::IndexSet<String>,
+    // The symbol names of the emitted segments of each vram class
+    vram_class_members: indexmap::IndexMap<String, Vec<String>>,
+
+    // Symbols which must be provided by the user, like the size of compressed
+    // segments, and the entry they come from
+    compressed_size_symbols: Vec<(String, SymbolOrigin)>,
+
+    // Set after emitting a linker offset, so the next file gets `KEEP`'d
+    keep_next_linker_offset_anchor: bool,
+
+    raw_blocks: Vec<RawBlock>,
+
+    // Computed once since it hashes the whole document. `None` if
+    // `emit_fingerprint` is disabled
+    fingerprint: Option<Result<String, SlinkyError>>,
+
+    // The value of the last `FILL` emitted on the current output section
+    current_fill_value: Option<u32>,
+
+    // The name, symbol name and fixed vram of the last emitted segment of each
+    // vram class, `None` being the segments without a vram class
+    previous_segments: indexmap::IndexMap<Option<String>, (String, String, Option<u64>)>,
+
+    // The names of every segment emitted so far, in order
+    emitted_segments: Vec<String>,
+
+    // The emitted segments with a `noload_merge`, keyed by the segment their
+    // noload sections are placed on
+    noload_merges: indexmap::IndexMap<String, Vec<Segment>>,
+
+    // Where each emitted segment was placed, for the memory map report
+    memory_map_segments: Vec<MemoryMapSegment>,
 
     single_segment: bool,
     reference_partial_objects: bool,
@@ -38,30 +155,97 @@ impl<'a> LinkerWriter<'a> {
             vram_classes.insert(vram_class.name.clone(), vram_class.clone());
         }
 
-        let mut buffer = ScriptBuffer::new();
+        let mut used_vram_classes = indexmap::IndexSet::new();
+        for segment in &d.segments {
+            if let Some(vram_class_name) = &segment.vram_class {
+                if rs.should_emit_entry(
+                    &segment.exclude_if_any,
+                    &segment.exclude_if_all,
+                    &segment.include_if_any,
+                    &segment.include_if_all,
+                ) {
+                    used_vram_classes.insert(vram_class_name.clone());
+                }
+            }
+        }
+
+        let suppressed_symbols: HashSet<String> = d
+            .segments
+            .iter()
+            .flat_map(|x| x.suppressed_symbols(&d.settings.linker_symbols_style))
+            .collect();
+
+        let mut buffer = ScriptBuffer::new(&d.settings);
+        buffer.set_suppressed_linker_symbols(suppressed_symbols.clone());
 
-        if rs.emit_version_comment() {
+        let fingerprint = if rs.emit_fingerprint() {
+            Some(d.fingerprint(rs))
+        } else {
+            None
+        };
+        if let Some(Ok(fingerprint)) = &fingerprint {
             buffer.writeln(&format!(
-                "/* Generated by slinky {}.{}.{} */",
-                version::VERSION_MAJOR,
-                version::VERSION_MINOR,
-                version::VERSION_PATCH
+                "/* {}{} */",
+                fingerprint::FINGERPRINT_MARKER,
+                fingerprint
             ));
             buffer.write_empty_line();
         }
 
+        if let Some(banner) = expand_banner(rs, &d.settings.linker_script_banner) {
+            for line in banner.lines() {
+                buffer.writeln(line);
+            }
+            buffer.write_empty_line();
+        }
+
+        let common_script = d.settings.common_script_path.as_ref().map(|_| {
+            let mut common_script = ScriptBuffer::new(&d.settings);
+            common_script.set_suppressed_linker_symbols(suppressed_symbols);
+            common_script
+        });
+
         Self {
             buffer,
+            common_script,
 
             files_paths: indexmap::IndexSet::new(),
+            extra_generated_files: Vec::new(),
+            objects_paths: indexmap::IndexSet::new(),
+            segment_objects_paths: indexmap::IndexMap::new(),
+            toolchain_objects_paths: indexmap::IndexMap::new(),
+
+            escaped_paths: HashMap::new(),
+
+            archives_members: HashMap::new(),
 
             vram_classes,
+            used_vram_classes,
+            vram_class_members: indexmap::IndexMap::new(),
+
+            compressed_size_symbols: Vec::new(),
+
+            keep_next_linker_offset_anchor: false,
+
+            raw_blocks: Vec::new(),
+
+            fingerprint,
+
+            current_fill_value: None,
+
+            previous_segments: indexmap::IndexMap::new(),
+
+            emitted_segments: Vec::new(),
+
+            noload_merges: indexmap::IndexMap::new(),
+
+            memory_map_segments: Vec::new(),
 
             single_segment: false,
             reference_partial_objects: false,
 
-            emit_sections_kind_symbols: true,
-            emit_section_symbols: true,
+            emit_sections_kind_symbols: d.settings.sections_kind_symbols,
+            emit_section_symbols: d.settings.section_symbols,
 
             d,
             rs,
@@ -75,20 +259,70 @@ impl<'a> LinkerWriter<'a> {
 
         s
     }
+
+    /// Writes the linker script to `dst` while it is generated, after each
+    /// segment, instead of keeping the whole script in memory.
+    ///
+    /// [`LinkerWriter::finish_stream`] must be called after adding the
+    /// document to write the rest of the script. The linker script exporters
+    /// only export what has not been streamed yet, and
+    /// [`ScriptExporter::check_dependencies`] can't be used.
+    pub fn set_stream(&mut self, dst: impl Write + Send + Sync + 'static) {
+        self.buffer.set_stream(dst);
+    }
+
+    /// Writes the rest of the linker script to the stream given to
+    /// [`LinkerWriter::set_stream`], and flushes it.
+    pub fn finish_stream(&mut self) -> Result<(), SlinkyError> {
+        self.fingerprint()?;
+
+        self.buffer.flush_stream()
+    }
 }
 
 impl ScriptImporter for LinkerWriter<'_> {
+    fn add_all_raw_blocks(&mut self, raw_blocks: &[RawBlock]) -> Result<(), SlinkyError> {
+        for raw_block in raw_blocks {
+            if self.rs.should_emit_entry(
+                &raw_block.exclude_if_any,
+                &raw_block.exclude_if_all,
+                &raw_block.include_if_any,
+                &raw_block.include_if_all,
+            ) {
+                self.raw_blocks.push(raw_block.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_all_imports(&mut self, imports: &[Import]) -> Result<(), SlinkyError> {
+        // Imported symbols are provided by the other binary at link time, so
+        // nothing is emitted for them. We only make sure they still exist.
+        for import in imports {
+            import.check_symbols(self.rs)?;
+        }
+
+        Ok(())
+    }
+
     fn add_all_segments(&mut self, segments: &[Segment]) -> Result<(), SlinkyError> {
+        let segments = &segment::segments_for_flavor(segments, self.rs.flavor())[..];
+
         if self.d.settings.single_segment_mode {
             // TODO: change assert to proper error
             assert!(segments.len() == 1);
 
+            self.write_inputs()?;
+            self.write_placed_symbol_assignments(|x| {
+                x.placement == Some(SymbolAssignmentPlacement::BeforeSections)
+            })?;
+            self.write_pre_sections_symbols()?;
+            self.write_raw_blocks(|x| x.placement == Some(RawBlockPlacement::BeforeSections));
             self.add_single_segment(&segments[0])?;
         } else {
             self.begin_sections()?;
-            for segment in segments {
-                self.add_segment(segment)?;
-            }
+            self.add_segments(segments)?;
             self.end_sections()?;
         }
 
@@ -100,7 +334,7 @@ impl ScriptImporter for LinkerWriter<'_> {
             self.buffer.write_empty_line();
         }
 
-        self.buffer.writeln(&format!("ENTRY({});", entry));
+        write!(self.buffer, "ENTRY({});", entry);
 
         Ok(())
     }
@@ -109,17 +343,19 @@ impl ScriptImporter for LinkerWriter<'_> {
         &mut self,
         symbol_assignments: &[SymbolAssignment],
     ) -> Result<(), SlinkyError> {
-        if symbol_assignments.is_empty() {
-            return Ok(());
+        // The ones which don't depend on the custom options are the same for
+        // every generated script
+        if self.common_script.is_some() {
+            let (common, rest): (Vec<_>, Vec<_>) = symbol_assignments
+                .iter()
+                .partition(|x| x.is_unconditional());
+
+            self.write_on_common_script(|w| w.add_symbol_assignments_block(&common))?;
+            return self.add_symbol_assignments_block(&rest);
         }
 
-        self.begin_symbol_assignments()?;
-        for symbol_assignment in symbol_assignments {
-            self.add_symbol_assignment(symbol_assignment)?;
-        }
-        self.end_symbol_assignments()?;
-
-        Ok(())
+        let symbol_assignments: Vec<_> = symbol_assignments.iter().collect();
+        self.add_symbol_assignments_block(&symbol_assignments)
     }
 
     fn add_all_required_symbols(
@@ -131,8 +367,12 @@ impl ScriptImporter for LinkerWriter<'_> {
         }
 
         self.begin_required_symbols()?;
-        for required_symbol in required_symbols {
-            self.add_required_symbol(required_symbol)?;
+        if self.d.settings.group_required_symbols {
+            self.add_required_symbols_group(required_symbols)?;
+        } else {
+            for required_symbol in required_symbols {
+                self.add_required_symbol(required_symbol)?;
+            }
         }
         self.end_required_symbols()?;
 
@@ -145,8 +385,8 @@ impl ScriptImporter for LinkerWriter<'_> {
         }
 
         self.begin_asserts()?;
-        for assert_entry in asserts {
-            self.add_assert(assert_entry)?;
+        for (index, assert_entry) in asserts.iter().enumerate() {
+            self.add_assert(index, assert_entry)?;
         }
         self.end_asserts()?;
 
@@ -155,10 +395,15 @@ impl ScriptImporter for LinkerWriter<'_> {
 }
 
 impl ScriptExporter for LinkerWriter<'_> {
-    fn export_linker_script_to_file(&self, path: &EscapedPath) -> Result<(), SlinkyError> {
-        let mut f = utils::create_file_and_parents(path.as_ref())?;
+    fn export_linker_script_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<Vec<WrittenFile>, SlinkyError> {
+        let mut s = Vec::new();
 
-        self.export_linker_script(&mut f)
+        self.export_linker_script(&mut s)?;
+
+        Ok(vec![self.write_file(path, &s)?])
     }
 
     fn export_linker_script_to_string(&self) -> Result<String, SlinkyError> {
@@ -174,36 +419,232 @@ impl ScriptExporter for LinkerWriter<'_> {
         }
     }
 
-    fn save_other_files(&self) -> Result<(), SlinkyError> {
+    fn save_other_files(&self) -> Result<Vec<WrittenFile>, SlinkyError> {
+        let mut written = Vec::new();
+
         if let Some(d_path) = &self.d.settings.d_path_escaped(self.rs)? {
             if let Some(target_path) = &self.d.settings.target_path_escaped(self.rs)? {
-                self.export_dependencies_file_to_file(d_path, target_path)?;
+                written.push(self.export_dependencies_file_to_file(d_path, target_path)?);
             }
         }
 
+        if let Some(ninja_path) = &self.d.settings.ninja_path_escaped(self.rs)? {
+            written.push(self.export_ninja_file_to_file(ninja_path)?);
+        }
+
         if let Some(symbols_header_path) = &self.d.settings.symbols_header_path_escaped(self.rs)? {
-            self.export_symbol_header_to_file(symbols_header_path)?;
+            written.push(self.export_symbol_header_to_file(symbols_header_path)?);
+        }
+
+        if let Some(rom_path) = &self.d.settings.symbols_header_rom_path_escaped(self.rs)? {
+            written.push(self.export_rom_symbol_header_to_file(rom_path)?);
+        }
+
+        if let Some(linker_offsets_path) = &self
+            .d
+            .settings
+            .symbols_header_linker_offsets_path_escaped(self.rs)?
+        {
+            written.push(self.export_linker_offsets_symbol_header_to_file(linker_offsets_path)?);
+        }
+
+        if let Some(symbols_asm_path) = &self.d.settings.symbols_asm_path_escaped(self.rs)? {
+            written.push(self.export_symbols_asm_to_file(symbols_asm_path)?);
+        }
+
+        if let Some(symbols_rust_path) = &self.d.settings.symbols_rust_path_escaped(self.rs)? {
+            written.push(self.export_symbols_rust_to_file(symbols_rust_path)?);
+        }
+
+        if let Some(report_path) = &self
+            .d
+            .settings
+            .vram_class_slack_report_path_escaped(self.rs)?
+        {
+            written.push(self.export_vram_class_slack_report_to_file(report_path)?);
+        }
+
+        if let Some(memory_map_path) = &self.d.settings.memory_map_path_escaped(self.rs)? {
+            written.push(self.export_memory_map_to_file(memory_map_path)?);
+        }
+
+        if let Some(binary_rules_path) = &self.d.settings.binary_rules_path_escaped(self.rs)? {
+            written.push(self.export_binary_rules_to_file(binary_rules_path)?);
+        }
+
+        if let Some(objects_list_path) = &self.d.settings.objects_list_path_escaped(self.rs)? {
+            written.push(self.export_objects_list_to_file(objects_list_path)?);
+        }
+
+        if let Some(toolchain_objects_path) =
+            &self.d.settings.toolchain_objects_path_escaped(self.rs)?
+        {
+            written.push(self.export_toolchain_objects_to_file(toolchain_objects_path)?);
+        }
+
+        if let Some(symbols_manifest_path) =
+            &self.d.settings.symbols_manifest_path_escaped(self.rs)?
+        {
+            written.push(self.export_symbols_manifest_to_file(symbols_manifest_path)?);
+        }
+
+        if let Some(segment_table_header_path) =
+            &self.d.settings.segment_table_header_path_escaped(self.rs)?
+        {
+            written.push(self.export_segment_table_header_to_file(segment_table_header_path)?);
+        }
+
+        if let Some(segment_table_path) = &self.d.settings.segment_table_path_escaped(self.rs)? {
+            written.push(self.export_segment_table_to_file(segment_table_path)?);
+        }
+
+        if let Some(common_script_path) = &self.d.settings.common_script_path_escaped(self.rs)? {
+            written.push(self.export_common_script_to_file(common_script_path)?);
+        }
+
+        if let Some(gdb_script_path) = &self.d.settings.gdb_script_path_escaped(self.rs)? {
+            written.push(self.export_gdb_script_to_file(gdb_script_path)?);
+        }
+
+        Ok(written)
+    }
+
+    fn check_dependencies(&self) -> Result<(), SlinkyError> {
+        if self.buffer.is_streamed() {
+            return Err(SlinkyError::StreamedLinkerScript);
+        }
+
+        // Parse the paths back from the script instead of trusting
+        // `files_paths`, since that is exactly what we want to verify
+        let referenced: indexmap::IndexSet<EscapedPath> =
+            self.buffer.lines().flat_map(referenced_paths).collect();
+
+        if let Some(p) = referenced.difference(&self.files_paths).next() {
+            return Err(SlinkyError::UndeclaredDependency {
+                path: p.to_string(),
+            });
+        }
+        if let Some(p) = self.files_paths.difference(&referenced).next() {
+            return Err(SlinkyError::UnreferencedDependency {
+                path: p.to_string(),
+            });
         }
 
         Ok(())
     }
+
+    fn statistics(&self) -> GenerationStatistics {
+        let mut stats = GenerationStatistics::new(self.d, self.rs);
+        stats.linker_symbols = self.buffer.get_linker_symbols().len();
+
+        stats
+    }
 }
 
 impl ScriptGenerator for LinkerWriter<'_> {}
 
 impl LinkerWriter<'_> {
+    fn write_file(&self, path: &EscapedPath, contents: &[u8]) -> Result<WrittenFile, SlinkyError> {
+        if self.rs.dry_run() {
+            return Ok(WrittenFile::new_dry_run(path, contents));
+        }
+
+        let updated =
+            utils::write_file_contents(path.as_ref(), contents, self.rs.write_if_changed())?;
+
+        Ok(WrittenFile::new(path, updated))
+    }
+
     pub fn export_linker_script(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
-        for line in self.buffer.get_buffer() {
+        self.fingerprint()?;
+
+        let contents = self.buffer.get_contents();
+
+        if let Err(e) = dst.write_all(contents.as_bytes()) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: contents.into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Writes the linker script as a C source file, defining a `const char`
+    /// array with the given name that contains the whole script and an
+    /// `unsigned int` with the same name plus a `_len` suffix holding its
+    /// length, mimicking the output of `xxd -i`.
+    pub fn export_linker_script_as_c(
+        &self,
+        dst: &mut impl Write,
+        array_name: &str,
+    ) -> Result<(), SlinkyError> {
+        self.fingerprint()?;
+
+        let mut lines = vec![format!("const char {}[] =", array_name)];
+        let mut script_lines = self.buffer.lines().peekable();
+        while let Some(line) = script_lines.next() {
+            let newline = if script_lines.peek().is_some() || self.buffer.trailing_newline() {
+                "\\n"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "    \"{}{}\"",
+                utils::escape_c_string(line),
+                newline
+            ));
+        }
+        if self.buffer.is_empty() {
+            lines.push("    \"\"".to_string());
+        }
+        if let Some(last) = lines.last_mut() {
+            last.push(';');
+        }
+        lines.push(format!(
+            "const unsigned int {}_len = sizeof({}) - 1;",
+            array_name, array_name
+        ));
+
+        for line in lines {
             if let Err(e) = writeln!(dst, "{}", line) {
                 return Err(SlinkyError::FailedWrite {
                     description: e.to_string(),
-                    contents: line.into(),
+                    contents: line,
                 });
             }
         }
 
         Ok(())
     }
+
+    pub fn export_linker_script_as_c_to_file(
+        &self,
+        path: &EscapedPath,
+        array_name: &str,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_linker_script_as_c(&mut s, array_name)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_linker_script_as_c_to_string(
+        &self,
+        array_name: &str,
+    ) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_linker_script_as_c(&mut s, array_name)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
 }
 
 impl LinkerWriter<'_> {
@@ -212,17 +653,13 @@ impl LinkerWriter<'_> {
         dst: &mut impl Write,
         target_path: &EscapedPath,
     ) -> Result<(), SlinkyError> {
-        if self.rs.emit_version_comment() {
-            if let Err(e) = write!(
-                dst,
-                "# Generated by slinky {}.{}.{}\n\n",
-                version::VERSION_MAJOR,
-                version::VERSION_MINOR,
-                version::VERSION_PATCH
-            ) {
+        self.write_fingerprint(dst, "# ", "")?;
+
+        if let Some(banner) = expand_banner(self.rs, &self.d.settings.d_banner) {
+            if let Err(e) = write!(dst, "{}\n\n", banner) {
                 return Err(SlinkyError::FailedWrite {
                     description: e.to_string(),
-                    contents: "Version comment".to_string(),
+                    contents: banner,
                 });
             }
         }
@@ -234,7 +671,21 @@ impl LinkerWriter<'_> {
             });
         }
 
-        for p in &self.files_paths {
+        // The documents merged through `include` and the lists of
+        // `files_from` are dependencies too
+        let included_files: Vec<EscapedPath> = self
+            .d
+            .included_files
+            .iter()
+            .chain(self.d.files_lists())
+            .map(|p| EscapedPath::from(p.display().to_string()))
+            .collect();
+        let mut files_paths = self.output_order(self.files_paths.iter().chain(&included_files));
+        if self.d.settings.sort_dependencies {
+            files_paths.sort();
+        }
+
+        for p in &files_paths {
             if let Err(e) = write!(dst, " \\\n    {}", p) {
                 return Err(SlinkyError::FailedWrite {
                     description: e.to_string(),
@@ -250,7 +701,7 @@ impl LinkerWriter<'_> {
             });
         }
 
-        for p in &self.files_paths {
+        for p in &files_paths {
             if let Err(e) = writeln!(dst, "{}:", p) {
                 return Err(SlinkyError::FailedWrite {
                     description: e.to_string(),
@@ -259,17 +710,98 @@ impl LinkerWriter<'_> {
             }
         }
 
+        if self.d.settings.d_generated_files {
+            self.write_generated_files_rule(dst)?;
+        }
+
+        Ok(())
+    }
+
+    /// Declares every file generated by slinky as depending on the document
+    /// and the documents it includes, so they are regenerated when any of
+    /// them changes.
+    fn write_generated_files_rule(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let generated = self.generated_files()?;
+        let documents: Vec<EscapedPath> = self
+            .d
+            .path
+            .iter()
+            .chain(&self.d.included_files)
+            .chain(self.d.files_lists())
+            .map(|p| EscapedPath::from(p.display().to_string()))
+            .collect();
+
+        if generated.is_empty() || documents.is_empty() {
+            return Ok(());
+        }
+
+        let mut contents = format!(
+            "\n{}:",
+            generated
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        for p in &documents {
+            contents += &format!(" \\\n    {}", p);
+        }
+        contents += "\n";
+
+        if let Err(e) = write!(dst, "{}", contents) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents,
+            });
+        }
+
         Ok(())
     }
 
+    /// Every file written by [`ScriptExporter::save_other_files`] besides the
+    /// dependency file itself, in the order they are written.
+    fn generated_files(&self) -> Result<Vec<EscapedPath>, SlinkyError> {
+        let settings = &self.d.settings;
+
+        let mut generated = Vec::new();
+        for p in [
+            settings.symbols_header_path_escaped(self.rs)?,
+            settings.symbols_header_rom_path_escaped(self.rs)?,
+            settings.symbols_header_linker_offsets_path_escaped(self.rs)?,
+            settings.symbols_asm_path_escaped(self.rs)?,
+            settings.symbols_rust_path_escaped(self.rs)?,
+            settings.vram_class_slack_report_path_escaped(self.rs)?,
+            settings.memory_map_path_escaped(self.rs)?,
+            settings.binary_rules_path_escaped(self.rs)?,
+            settings.objects_list_path_escaped(self.rs)?,
+            settings.toolchain_objects_path_escaped(self.rs)?,
+            settings.symbols_manifest_path_escaped(self.rs)?,
+            settings.segment_table_header_path_escaped(self.rs)?,
+            settings.segment_table_path_escaped(self.rs)?,
+            settings.common_script_path_escaped(self.rs)?,
+            settings.gdb_script_path_escaped(self.rs)?,
+            settings.ninja_path_escaped(self.rs)?,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            generated.push(p);
+        }
+        generated.extend(self.extra_generated_files.iter().cloned());
+
+        Ok(generated)
+    }
+
     pub fn export_dependencies_file_to_file(
         &self,
         path: &EscapedPath,
         target_path: &EscapedPath,
-    ) -> Result<(), SlinkyError> {
-        let mut f = utils::create_file_and_parents(path.as_ref())?;
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
 
-        self.export_dependencies_file(&mut f, target_path)
+        self.export_dependencies_file(&mut s, target_path)?;
+
+        self.write_file(path, &s)
     }
 
     pub fn export_dependencies_file_to_string(
@@ -290,71 +822,92 @@ impl LinkerWriter<'_> {
 }
 
 impl LinkerWriter<'_> {
-    pub fn export_symbol_header(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
-        if self.rs.emit_version_comment() {
-            if let Err(e) = write!(
-                dst,
-                "/* Generated by slinky {}.{}.{} */\n\n",
-                version::VERSION_MAJOR,
-                version::VERSION_MINOR,
-                version::VERSION_PATCH
-            ) {
-                return Err(SlinkyError::FailedWrite {
-                    description: e.to_string(),
-                    contents: "Version comment".to_string(),
-                });
-            }
+    /// Writes a Ninja build file fragment, meant to be `include`d by the main
+    /// `build.ninja`.
+    ///
+    /// The fragment lists every object referenced by the linker script on the
+    /// `slinky_objects` variable, to be used as the implicit dependencies of
+    /// the link edge, and declares a build edge using the `slinky` rule which
+    /// regenerates the linker script and every other generated file whenever
+    /// the document, any of its included documents or any of its files lists
+    /// changes. The path of the
+    /// linker script is not part of the document, so it is taken from the
+    /// `slinky_script` variable.
+    pub fn export_ninja_file(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.write_fingerprint(dst, "# ", "")?;
+
+        let mut objects = self.output_order(self.files_paths.iter());
+        if self.d.settings.sort_dependencies {
+            objects.sort();
         }
 
-        if let Err(e) = write!(
-            dst,
-            "#ifndef HEADER_SYMBOLS_H\n#define HEADER_SYMBOLS_H\n\n"
-        ) {
-            return Err(SlinkyError::FailedWrite {
-                description: e.to_string(),
-                contents: "".into(),
-            });
+        let mut contents = "slinky_objects =".to_string();
+        for p in &objects {
+            contents += &format!(" $\n    {}", ninja_escape(&p.to_string()));
         }
+        contents += "\n";
+
+        let documents: Vec<EscapedPath> = self
+            .d
+            .path
+            .iter()
+            .chain(&self.d.included_files)
+            .chain(self.d.files_lists())
+            .map(|p| EscapedPath::from(p.display().to_string()))
+            .collect();
+
+        if let Some((document, included)) = documents.split_first() {
+            let mut outputs = vec!["$slinky_script".to_string()];
+            if self.d.settings.target_path.is_some() {
+                if let Some(d_path) = self.d.settings.d_path_escaped(self.rs)? {
+                    outputs.push(ninja_escape(&d_path.to_string()));
+                }
+            }
+            outputs.extend(
+                self.generated_files()?
+                    .iter()
+                    .map(|p| ninja_escape(&p.to_string())),
+            );
 
-        let arr_suffix = if self.d.settings.symbols_header_as_array {
-            "[]"
-        } else {
-            ""
-        };
-
-        for sym in self.get_linker_symbols() {
-            if let Err(e) = writeln!(
-                dst,
-                "extern {} {}{};",
-                self.d.settings.symbols_header_type, sym, arr_suffix
-            ) {
-                return Err(SlinkyError::FailedWrite {
-                    description: e.to_string(),
-                    contents: sym.into(),
-                });
+            contents += &format!(
+                "\nbuild {}: slinky {}",
+                outputs.join(" "),
+                ninja_escape(&document.to_string())
+            );
+            if !included.is_empty() {
+                contents += " |";
+                for p in included {
+                    contents += &format!(" $\n    {}", ninja_escape(&p.to_string()));
+                }
             }
+            contents += "\n";
         }
 
-        if let Err(e) = write!(dst, "\n#endif\n") {
+        if let Err(e) = write!(dst, "{}", contents) {
             return Err(SlinkyError::FailedWrite {
                 description: e.to_string(),
-                contents: "".into(),
+                contents,
             });
         }
 
         Ok(())
     }
 
-    pub fn export_symbol_header_to_file(&self, path: &EscapedPath) -> Result<(), SlinkyError> {
-        let mut f = utils::create_file_and_parents(path.as_ref())?;
+    pub fn export_ninja_file_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_ninja_file(&mut s)?;
 
-        self.export_symbol_header(&mut f)
+        self.write_file(path, &s)
     }
 
-    pub fn export_symbol_header_to_string(&self) -> Result<String, SlinkyError> {
+    pub fn export_ninja_file_to_string(&self) -> Result<String, SlinkyError> {
         let mut s = Vec::new();
 
-        self.export_symbol_header(&mut s)?;
+        self.export_ninja_file(&mut s)?;
 
         match String::from_utf8(s) {
             Err(e) => Err(SlinkyError::FailedStringConversion {
@@ -365,11 +918,1102 @@ impl LinkerWriter<'_> {
     }
 }
 
-// Getters / Setters
 impl LinkerWriter<'_> {
-    #[must_use]
-    pub fn get_linker_symbols(&self) -> &indexmap::IndexSet<String> {
-        self.buffer.get_linker_symbols()
+    /// Writes a C header declaring every generated linker symbol.
+    ///
+    /// The ROM symbols and the linker offsets are left out if they are
+    /// exported on their own headers, see `symbols_header_rom_path` and
+    /// `symbols_header_linker_offsets_path`.
+    pub fn export_symbol_header(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.export_symbol_header_with_user_additions(dst, &[])
+    }
+
+    /// Like [`export_symbol_header`](Self::export_symbol_header), but the
+    /// given lines are placed below the user additions marker.
+    ///
+    /// The lines are ignored unless `symbols_header_keep_user_additions` is
+    /// enabled.
+    pub fn export_symbol_header_with_user_additions(
+        &self,
+        dst: &mut impl Write,
+        user_additions: &[String],
+    ) -> Result<(), SlinkyError> {
+        let (rom_symbols, linker_offsets) = self.categorized_linker_symbols();
+        let split_rom = self.d.settings.symbols_header_rom_path.is_some();
+        let split_linker_offsets = self.d.settings.symbols_header_linker_offsets_path.is_some();
+
+        let symbols = self.get_linker_symbols().iter().filter(|sym| {
+            let is_split = (split_rom && rom_symbols.contains(*sym))
+                || (split_linker_offsets && linker_offsets.contains(*sym));
+            !is_split
+        });
+
+        let user_additions = if self.d.settings.symbols_header_keep_user_additions {
+            Some(user_additions)
+        } else {
+            None
+        };
+
+        self.write_symbol_header(dst, "HEADER_SYMBOLS_H", symbols, user_additions)
+    }
+
+    /// If `symbols_header_keep_user_additions` is enabled then the lines
+    /// placed below the user additions marker of the existing file are kept.
+    pub fn export_symbol_header_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        let user_additions = if self.d.settings.symbols_header_keep_user_additions {
+            read_symbol_header_user_additions(path.as_ref())?
+        } else {
+            Vec::new()
+        };
+        self.export_symbol_header_with_user_additions(&mut s, &user_additions)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_symbol_header_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbol_header(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes a C header declaring the ROM symbols of the segments and the
+    /// `rom_end_symbol` and `rom_size_symbol`.
+    pub fn export_rom_symbol_header(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let (rom_symbols, _) = self.categorized_linker_symbols();
+
+        let symbols = self
+            .get_linker_symbols()
+            .iter()
+            .filter(|sym| rom_symbols.contains(*sym));
+
+        self.write_symbol_header(dst, "HEADER_ROM_SYMBOLS_H", symbols, None)
+    }
+
+    pub fn export_rom_symbol_header_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_rom_symbol_header(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_rom_symbol_header_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_rom_symbol_header(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes a C header declaring the symbols of the `linker_offset` files.
+    pub fn export_linker_offsets_symbol_header(
+        &self,
+        dst: &mut impl Write,
+    ) -> Result<(), SlinkyError> {
+        let (_, linker_offsets) = self.categorized_linker_symbols();
+
+        let symbols = self
+            .get_linker_symbols()
+            .iter()
+            .filter(|sym| linker_offsets.contains(*sym));
+
+        self.write_symbol_header(dst, "HEADER_LINKER_OFFSETS_H", symbols, None)
+    }
+
+    pub fn export_linker_offsets_symbol_header_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_linker_offsets_symbol_header(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_linker_offsets_symbol_header_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_linker_offsets_symbol_header(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    fn write_symbol_header<'s>(
+        &self,
+        dst: &mut impl Write,
+        guard: &str,
+        symbols: impl Iterator<Item = &'s String>,
+        user_additions: Option<&[String]>,
+    ) -> Result<(), SlinkyError> {
+        self.write_fingerprint(dst, "/* ", " */")?;
+
+        if let Some(banner) = expand_banner(self.rs, &self.d.settings.symbols_header_banner) {
+            if let Err(e) = write!(dst, "{}\n\n", banner) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: banner,
+                });
+            }
+        }
+
+        if let Err(e) = write!(dst, "#ifndef {}\n#define {}\n\n", guard, guard) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        let arr_suffix = if self.d.settings.symbols_header_as_array {
+            "[]"
+        } else {
+            ""
+        };
+
+        for sym in symbols {
+            if let Err(e) = writeln!(
+                dst,
+                "extern {} {}{};",
+                self.d.settings.symbols_header_type, sym, arr_suffix
+            ) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: sym.into(),
+                });
+            }
+        }
+
+        if let Some(user_additions) = user_additions {
+            if let Err(e) = write!(dst, "\n{}\n", SYMBOLS_HEADER_USER_ADDITIONS_MARKER) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: SYMBOLS_HEADER_USER_ADDITIONS_MARKER.into(),
+                });
+            }
+
+            for line in user_additions {
+                if let Err(e) = writeln!(dst, "{}", line) {
+                    return Err(SlinkyError::FailedWrite {
+                        description: e.to_string(),
+                        contents: line.into(),
+                    });
+                }
+            }
+        }
+
+        if let Err(e) = write!(dst, "\n#endif\n") {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The names the ROM symbols and the linker offsets may take, derived
+    /// from the document instead of the generated script, since those
+    /// symbols are emitted from many places.
+    fn categorized_linker_symbols(&self) -> (HashSet<String>, HashSet<String>) {
+        let settings = &self.d.settings;
+        let style = &settings.linker_symbols_style;
+
+        let mut rom_symbols = HashSet::new();
+        let mut linker_offsets = HashSet::new();
+
+        for segment in &self.d.segments {
+            if self.has_rom() {
+                rom_symbols.insert(style.segment_rom_start(&segment.symbol_name));
+                rom_symbols.insert(style.segment_rom_end(&segment.symbol_name));
+                rom_symbols.insert(style.segment_rom_size(&segment.symbol_name));
+            }
+
+            collect_linker_offsets(&segment.files, style, &mut linker_offsets);
+        }
+        rom_symbols.extend(settings.rom_end_symbol.clone());
+        rom_symbols.extend(settings.rom_size_symbol.clone());
+
+        (rom_symbols, linker_offsets)
+    }
+
+    /// Whether the segments are laid out on a rom, tracked by `__romPos`.
+    fn has_rom(&self) -> bool {
+        self.d.settings.rom_model == RomModel::Rom
+    }
+}
+
+/// Reads the lines placed between the user additions marker and the closing
+/// `#endif` of an existing symbols header. Trailing empty lines are dropped.
+///
+/// Nothing is kept if the file does not exist or has no marker.
+fn read_symbol_header_user_additions(path: &std::path::Path) -> Result<Vec<String>, SlinkyError> {
+    let contents = match utils::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(SlinkyError::FailedFileOpen {
+                path: path.to_path_buf(),
+                description: e.to_string(),
+            })
+        }
+    };
+
+    let lines: Vec<&str> = contents.lines().collect();
+    let start = match lines
+        .iter()
+        .position(|x| x.trim() == SYMBOLS_HEADER_USER_ADDITIONS_MARKER)
+    {
+        Some(i) => i + 1,
+        None => return Ok(Vec::new()),
+    };
+    let end = match lines.iter().rposition(|x| x.trim() == "#endif") {
+        Some(i) if i >= start => i,
+        _ => lines.len(),
+    };
+
+    let mut user_additions: Vec<String> = lines[start..end].iter().map(|x| x.to_string()).collect();
+    while user_additions.last().map_or(false, |x| x.trim().is_empty()) {
+        user_additions.pop();
+    }
+
+    Ok(user_additions)
+}
+
+fn collect_linker_offsets(
+    files: &[FileInfo],
+    style: &LinkerSymbolsStyle,
+    linker_offsets: &mut HashSet<String>,
+) {
+    for file in files {
+        match file.kind {
+            FileKind::LinkerOffset => {
+                if file.sections.len() > 1 {
+                    for section in &file.sections {
+                        linker_offsets
+                            .insert(style.section_linker_offset(&file.linker_offset_name, section));
+                    }
+                } else {
+                    linker_offsets.insert(style.linker_offset(&file.linker_offset_name));
+                }
+            }
+            FileKind::Group => collect_linker_offsets(&file.files, style, linker_offsets),
+            _ => {}
+        }
+    }
+}
+
+impl LinkerWriter<'_> {
+    /// Writes an assembler include file declaring the same symbols listed on
+    /// the symbols header, using the `symbols_asm_dialect` of the document.
+    pub fn export_symbols_asm(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.write_fingerprint(dst, "/* ", " */")?;
+
+        if self.rs.emit_version_comment() {
+            if let Err(e) = write!(
+                dst,
+                "/* Generated by slinky {}.{}.{} */\n\n",
+                version::VERSION_MAJOR,
+                version::VERSION_MINOR,
+                version::VERSION_PATCH
+            ) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: "Version comment".to_string(),
+                });
+            }
+        }
+
+        if let Err(e) = write!(
+            dst,
+            ".ifndef HEADER_SYMBOLS_INC\n.set HEADER_SYMBOLS_INC, 1\n\n"
+        ) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        for sym in self.get_linker_symbols() {
+            if let Err(e) = writeln!(
+                dst,
+                "{}",
+                self.d.settings.symbols_asm_dialect.declaration(sym)
+            ) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: sym.into(),
+                });
+            }
+        }
+
+        if let Err(e) = write!(dst, "\n.endif\n") {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn export_symbols_asm_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbols_asm(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_symbols_asm_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbols_asm(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+}
+
+impl LinkerWriter<'_> {
+    /// Writes a Rust source file declaring the same symbols listed on the
+    /// symbols header as `u8` statics of an `extern "C"` block.
+    ///
+    /// Only the address of each symbol is meaningful, so they are expected to
+    /// be used through `core::ptr::addr_of!`.
+    pub fn export_symbols_rust(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.write_fingerprint(dst, "// ", "")?;
+
+        if self.rs.emit_version_comment() {
+            if let Err(e) = write!(
+                dst,
+                "// Generated by slinky {}.{}.{}\n\n",
+                version::VERSION_MAJOR,
+                version::VERSION_MINOR,
+                version::VERSION_PATCH
+            ) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: "Version comment".to_string(),
+                });
+            }
+        }
+
+        if let Err(e) = write!(dst, "#[allow(non_upper_case_globals)]\nextern \"C\" {{\n") {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        for sym in self.get_linker_symbols() {
+            if let Err(e) = writeln!(dst, "    pub static {}: u8;", sym) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: sym.into(),
+                });
+            }
+        }
+
+        if let Err(e) = writeln!(dst, "}}") {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn export_symbols_rust_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbols_rust(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_symbols_rust_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbols_rust(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+}
+
+impl LinkerWriter<'_> {
+    /// Writes a CSV listing the slack symbol of every segment of each vram
+    /// class, allowing to look up their values on the built elf.
+    pub fn export_vram_class_slack_report(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let style = &self.d.settings.linker_symbols_style;
+
+        if let Err(e) = writeln!(dst, "vram_class,segment,slack_symbol") {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: "".into(),
+            });
+        }
+
+        for (vram_class_name, members) in &self.vram_class_members {
+            for member in members {
+                let line = format!(
+                    "{},{},{}",
+                    vram_class_name,
+                    member,
+                    style.segment_vram_class_slack(member)
+                );
+
+                if let Err(e) = writeln!(dst, "{}", line) {
+                    return Err(SlinkyError::FailedWrite {
+                        description: e.to_string(),
+                        contents: line,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn export_vram_class_slack_report_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_vram_class_slack_report(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_vram_class_slack_report_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_vram_class_slack_report(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+}
+
+impl LinkerWriter<'_> {
+    /// Writes a Makefile fragment with the rules converting every `binary`
+    /// file of the document into the object referenced by the linker script.
+    pub fn export_binary_rules(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.write_fingerprint(dst, "# ", "")?;
+
+        if self.rs.emit_version_comment() {
+            if let Err(e) = write!(
+                dst,
+                "# Generated by slinky {}.{}.{}\n\n",
+                version::VERSION_MAJOR,
+                version::VERSION_MINOR,
+                version::VERSION_PATCH
+            ) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: "Version comment".to_string(),
+                });
+            }
+        }
+
+        let rules = binary_rules::collect_binary_rules(self.d, self.rs)?;
+
+        binary_rules::write_makefile_rules(dst, &rules)
+    }
+
+    pub fn export_binary_rules_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_binary_rules(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_binary_rules_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_binary_rules(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+}
+
+impl LinkerWriter<'_> {
+    /// Writes every object and archive referenced by the script, like the
+    /// files of the segments, the `startup_file`, the `extra_inputs` and the
+    /// libraries given by path.
+    pub fn export_objects_list(
+        &self,
+        dst: &mut impl Write,
+        format: ObjectsListFormat,
+    ) -> Result<(), SlinkyError> {
+        objects_list::write_objects_list(dst, self.output_order(&self.objects_paths), format)
+    }
+
+    /// Writes the objects and archives referenced by the given segment.
+    ///
+    /// The list is empty if the segment was not emitted.
+    pub fn export_segment_objects_list(
+        &self,
+        dst: &mut impl Write,
+        segment: &str,
+        format: ObjectsListFormat,
+    ) -> Result<(), SlinkyError> {
+        let paths = match self.segment_objects_paths.get(segment) {
+            Some(paths) => self.output_order(paths),
+            None => Vec::new(),
+        };
+
+        objects_list::write_objects_list(dst, paths, format)
+    }
+
+    /// Writes the objects and archives referenced by the segments tagged with
+    /// the given `toolchain`.
+    pub fn export_toolchain_objects_list(
+        &self,
+        dst: &mut impl Write,
+        toolchain: &str,
+        format: ObjectsListFormat,
+    ) -> Result<(), SlinkyError> {
+        let paths = match self.toolchain_objects_paths.get(toolchain) {
+            Some(paths) => self.output_order(paths),
+            None => Vec::new(),
+        };
+
+        objects_list::write_objects_list(dst, paths, format)
+    }
+
+    /// Writes a Makefile fragment with a variable for each `toolchain` used
+    /// by the segments, listing the objects and archives built with it.
+    pub fn export_toolchain_objects(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.write_fingerprint(dst, "# ", "")?;
+
+        if self.rs.emit_version_comment() {
+            if let Err(e) = write!(
+                dst,
+                "# Generated by slinky {}.{}.{}\n\n",
+                version::VERSION_MAJOR,
+                version::VERSION_MINOR,
+                version::VERSION_PATCH
+            ) {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: "Version comment".to_string(),
+                });
+            }
+        }
+
+        let toolchains: Vec<(&String, Vec<&EscapedPath>)> = self
+            .toolchain_objects_paths
+            .iter()
+            .map(|(toolchain, paths)| (toolchain, self.output_order(paths)))
+            .collect();
+
+        objects_list::write_toolchain_variables(dst, &toolchains)
+    }
+
+    pub fn export_toolchain_objects_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_toolchain_objects(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_toolchain_objects_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_toolchain_objects(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    pub fn export_toolchain_objects_list_to_string(
+        &self,
+        toolchain: &str,
+        format: ObjectsListFormat,
+    ) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_toolchain_objects_list(&mut s, toolchain, format)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes a JSON manifest listing every generated symbol, which can be
+    /// used as the `path` of an [`Import`], along with the entry of the
+    /// document defining each symbol.
+    pub fn export_symbols_manifest(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let symbols = self
+            .get_linker_symbols()
+            .iter()
+            .zip(self.buffer.get_linker_symbol_origins());
+
+        symbols_manifest::write_symbols_manifest(dst, symbols)
+    }
+
+    pub fn export_symbols_manifest_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbols_manifest(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_symbols_manifest_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_symbols_manifest(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes a GDB script with commands to load and unload the symbols of
+    /// each emitted segment which shares its vram with other segments, through
+    /// a `vram_class` or an `overlay_group`, from its partially linked object.
+    pub fn export_gdb_script(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let style = &self.d.settings.linker_symbols_style;
+        let linker_symbols = self.get_linker_symbols();
+        let base_path = self.d.settings.base_path_escaped(self.rs)?;
+
+        let mut segments = Vec::new();
+        for segment in &self.d.segments {
+            // Handwritten segments are not partially linked
+            if segment.external
+                || (segment.vram_class.is_none() && segment.overlay_group.is_none())
+                || !self.emitted_segments.contains(&segment.name)
+            {
+                continue;
+            }
+
+            let mut object = base_path.clone();
+            match self
+                .d
+                .settings
+                .partial_build_object_path_escaped(self.rs, &segment.name)?
+            {
+                Some(p) => object.extend(&p),
+                None => {
+                    return Err(SlinkyError::MissingAnyOfOptionalFields {
+                        fields: "'partial_build_segments_folder', 'partial_build_path'".to_string(),
+                    })
+                }
+            }
+
+            let sections = segment
+                .alloc_sections
+                .iter()
+                .chain(&segment.noload_sections)
+                .filter_map(|section| {
+                    let symbol = style.segment_section_start(&segment.symbol_name, section);
+                    linker_symbols
+                        .contains(&symbol)
+                        .then(|| (section.clone(), symbol))
+                })
+                .collect();
+
+            segments.push(GdbSegment {
+                name: segment.name.clone(),
+                object,
+                sections,
+            });
+        }
+
+        gdb_script::write_gdb_script(dst, &segments)
+    }
+
+    pub fn export_gdb_script_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_gdb_script(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_gdb_script_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_gdb_script(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes a C header declaring the table of the segments, its entry type
+    /// and an id for each segment.
+    pub fn export_segment_table_header(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let entries = segment_table::collect_segment_table(self.d, self.rs);
+
+        self.write_fingerprint(dst, "/* ", " */")?;
+        segment_table::write_segment_table_header(dst, &entries)
+    }
+
+    pub fn export_segment_table_header_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_segment_table_header(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_segment_table_header_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_segment_table_header(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes a C source file defining the table of the segments, with the
+    /// name, the ROM and VRAM symbols and the flags of each segment.
+    ///
+    /// The header is included by its file name if
+    /// `segment_table_header_path` is set, otherwise the source declares
+    /// everything by itself.
+    pub fn export_segment_table(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let settings = &self.d.settings;
+        let entries = segment_table::collect_segment_table(self.d, self.rs);
+
+        let header_name = match settings.segment_table_header_path_escaped(self.rs)? {
+            Some(p) => AsRef::<Path>::as_ref(&p)
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string()),
+            None => None,
+        };
+
+        self.write_fingerprint(dst, "/* ", " */")?;
+        segment_table::write_segment_table_source(
+            dst,
+            &entries,
+            header_name.as_deref(),
+            &settings.symbols_header_type,
+            settings.symbols_header_as_array,
+        )
+    }
+
+    pub fn export_segment_table_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_segment_table(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_segment_table_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_segment_table(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes the parts of the linker script moved to the
+    /// `common_script_path`, which the linker script `INCLUDE`s.
+    ///
+    /// It doesn't depend on the custom options, so every script generated
+    /// from the same document can share it. Writes nothing if the
+    /// `common_script_path` setting is not set.
+    pub fn export_common_script(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        let contents = match &self.common_script {
+            Some(common_script) => common_script.get_contents(),
+            None => return Ok(()),
+        };
+
+        if let Err(e) = dst.write_all(contents.as_bytes()) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: contents.into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn export_common_script_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_common_script(&mut s)?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_common_script_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_common_script(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    /// Writes the objects list in the format given by the extension of the
+    /// path, see [`ObjectsListFormat::from_path`].
+    pub fn export_objects_list_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_objects_list(&mut s, ObjectsListFormat::from_path(path))?;
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_objects_list_to_string(
+        &self,
+        format: ObjectsListFormat,
+    ) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_objects_list(&mut s, format)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    pub fn export_segment_objects_list_to_string(
+        &self,
+        segment: &str,
+        format: ObjectsListFormat,
+    ) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_segment_objects_list(&mut s, segment, format)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+}
+
+impl LinkerWriter<'_> {
+    fn memory_map(&self) -> MemoryMap<'_> {
+        let style = &self.d.settings.linker_symbols_style;
+
+        let vram_classes = self
+            .vram_classes
+            .values()
+            .filter(|x| x.emitted)
+            .map(|vram_class| MemoryMapVramClass {
+                name: vram_class.name.clone(),
+                start: style.vram_class_start(&vram_class.name),
+                end: style.vram_class_end(&vram_class.name),
+                members: self
+                    .memory_map_segments
+                    .iter()
+                    .filter(|x| x.vram_class.as_ref() == Some(&vram_class.name))
+                    .map(|x| x.name.clone())
+                    .collect(),
+            })
+            .collect();
+
+        MemoryMap {
+            segments: &self.memory_map_segments,
+            vram_classes,
+        }
+    }
+
+    /// Writes a CSV listing the rom and vram symbols of every segment and
+    /// vram class.
+    pub fn export_memory_map_csv(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.memory_map().write_csv(dst)
+    }
+
+    /// Writes the rom and vram symbols of every segment and vram class as
+    /// markdown tables.
+    pub fn export_memory_map_table(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        self.memory_map().write_table(dst)
+    }
+
+    /// Writes the memory map as a CSV if the path has a `csv` extension, or
+    /// as markdown tables otherwise.
+    pub fn export_memory_map_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<WrittenFile, SlinkyError> {
+        let mut s = Vec::new();
+
+        let is_csv = AsRef::<std::path::Path>::as_ref(path)
+            .extension()
+            .map_or(false, |x| x.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            self.export_memory_map_csv(&mut s)?;
+        } else {
+            self.export_memory_map_table(&mut s)?;
+        }
+
+        self.write_file(path, &s)
+    }
+
+    pub fn export_memory_map_csv_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_memory_map_csv(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+
+    pub fn export_memory_map_table_to_string(&self) -> Result<String, SlinkyError> {
+        let mut s = Vec::new();
+
+        self.export_memory_map_table(&mut s)?;
+
+        match String::from_utf8(s) {
+            Err(e) => Err(SlinkyError::FailedStringConversion {
+                description: e.to_string(),
+            }),
+            Ok(ret) => Ok(ret),
+        }
+    }
+}
+
+// Getters / Setters
+impl LinkerWriter<'_> {
+    #[must_use]
+    pub fn get_linker_symbols(&self) -> &indexmap::IndexSet<String> {
+        self.buffer.get_linker_symbols()
+    }
+
+    /// The statements of the generated linker script, in order.
+    ///
+    /// Fails if the script was already streamed.
+    pub fn script_items(&self) -> Result<impl Iterator<Item = ScriptItem<'_>>, SlinkyError> {
+        if self.buffer.is_streamed() {
+            return Err(SlinkyError::StreamedLinkerScript);
+        }
+
+        Ok(script_item::script_items(self.buffer.get_contents()))
+    }
+
+    /// The entry of the document which defines the given generated symbol.
+    #[must_use]
+    pub fn get_linker_symbol_origin(&self, symbol: &str) -> Option<&SymbolOrigin> {
+        self.buffer
+            .get_linker_symbols()
+            .get_index_of(symbol)
+            .and_then(|i| self.buffer.get_linker_symbol_origins().get(i))
+    }
+
+    /// Lists a file generated alongside this script on the rule added by
+    /// `d_generated_files`.
+    pub(crate) fn add_generated_file(&mut self, path: EscapedPath) {
+        self.extra_generated_files.push(path);
     }
 
     pub fn set_emit_sections_kind_symbols(&mut self, value: bool) {
@@ -391,17 +2035,270 @@ impl LinkerWriter<'_> {
     }
 }
 
-// semi internal functions
-impl LinkerWriter<'_> {
-    pub(crate) fn begin_sections(&mut self) -> Result<(), SlinkyError> {
-        self.buffer.writeln("SECTIONS");
-        self.buffer.begin_block();
+// semi internal functions
+impl LinkerWriter<'_> {
+    pub(crate) fn begin_sections(&mut self) -> Result<(), SlinkyError> {
+        self.write_inputs()?;
+        self.write_placed_symbol_assignments(|x| {
+            x.placement == Some(SymbolAssignmentPlacement::BeforeSections)
+        })?;
+        self.write_pre_sections_symbols()?;
+        self.write_raw_blocks(|x| x.placement == Some(RawBlockPlacement::BeforeSections));
+
+        self.buffer.writeln("SECTIONS");
+        self.buffer.begin_block();
+
+        self.write_discard_rules();
+
+        if self.has_rom() {
+            write!(self.buffer, "__romPos = {};", self.buffer.hex(0u32));
+        }
+
+        if let Some(hardcoded_gp_value) = self.d.settings.hardcoded_gp_value {
+            write!(
+                self.buffer,
+                "_gp = {};",
+                self.buffer.address(hardcoded_gp_value)
+            );
+        }
+
+        if self.has_rom() || self.d.settings.hardcoded_gp_value.is_some() {
+            self.buffer.write_empty_line();
+        }
+
+        Ok(())
+    }
+
+    /// The sections discarded from specific files go on their own `/DISCARD/`
+    /// block at the start of `SECTIONS`, since the linker places each input
+    /// section on the first description matching it and these must take
+    /// precedence over the wildcards of the rest of the script.
+    fn write_discard_rules(&mut self) {
+        if self.d.settings.discard.is_empty() {
+            return;
+        }
+
+        self.buffer.writeln("/DISCARD/ :");
+        self.buffer.begin_block();
+        for rule in &self.d.settings.discard {
+            self.buffer.writeln(&rule.input_section_description());
+        }
+        self.buffer.end_block();
+
+        self.buffer.write_empty_line();
+    }
+
+    /// Writes the raw blocks matching the given placement, each one followed by
+    /// an empty line.
+    fn write_raw_blocks(&mut self, placement: impl Fn(&RawBlock) -> bool) {
+        for raw_block in &self.raw_blocks {
+            if placement(raw_block) {
+                let index = self
+                    .d
+                    .raw
+                    .iter()
+                    .position(|x| x == raw_block)
+                    .unwrap_or_default();
+                let previous_origin = std::mem::replace(
+                    self.buffer.origin_mut(),
+                    SymbolOrigin {
+                        entry: EntryPath {
+                            components: vec![EntryPathComponent::RawBlock { index }],
+                        },
+                        field: Some("contents".to_string()),
+                    },
+                );
+
+                self.buffer.write_raw(&raw_block.contents);
+                self.buffer.write_empty_line();
+
+                self.buffer.set_origin(previous_origin);
+            }
+        }
+    }
+
+    /// Writes the symbol assignments of the document matching the given
+    /// placement, followed by an empty line if any was written.
+    ///
+    /// The assignments without a custom placement are written by
+    /// `add_all_symbol_assignments` instead, after the `SECTIONS` block.
+    fn write_placed_symbol_assignments(
+        &mut self,
+        placement: impl Fn(&SymbolAssignment) -> bool,
+    ) -> Result<(), SlinkyError> {
+        let d = self.d;
+        let mut any = false;
+
+        for symbol_assignment in &d.symbol_assignments {
+            if placement(symbol_assignment)
+                && self.rs.should_emit_entry(
+                    &symbol_assignment.exclude_if_any,
+                    &symbol_assignment.exclude_if_all,
+                    &symbol_assignment.include_if_any,
+                    &symbol_assignment.include_if_all,
+                )
+            {
+                self.write_symbol_assignment(symbol_assignment)?;
+                any = true;
+            }
+        }
+
+        if any {
+            self.buffer.write_empty_line();
+        }
+
+        Ok(())
+    }
+
+    /// Writes the `pre_sections_symbols` of the document, followed by an empty
+    /// line if any was written.
+    ///
+    /// Nothing is placed yet at this point, so the assignments which depend on
+    /// the segments are rejected instead of producing a script which fails to
+    /// link.
+    fn write_pre_sections_symbols(&mut self) -> Result<(), SlinkyError> {
+        let d = self.d;
+        let mut any = false;
+
+        for (index, symbol_assignment) in d.pre_sections_symbols.iter().enumerate() {
+            if !self.rs.should_emit_entry(
+                &symbol_assignment.exclude_if_any,
+                &symbol_assignment.exclude_if_all,
+                &symbol_assignment.include_if_any,
+                &symbol_assignment.include_if_all,
+            ) {
+                continue;
+            }
+
+            if symbol_assignment.alias_of.is_some()
+                || linker_symbols_style::references_sections(d, &symbol_assignment.value)
+            {
+                return Err(SlinkyError::PreSectionsSymbolReferencesSections {
+                    symbol: symbol_assignment.name.clone(),
+                }
+                .in_entry(EntryPathComponent::PreSectionsSymbol {
+                    index,
+                    name: symbol_assignment.name.clone(),
+                }));
+            }
+
+            self.write_symbol_assignment(symbol_assignment)?;
+            any = true;
+        }
+
+        if any {
+            self.buffer.write_empty_line();
+        }
+
+        Ok(())
+    }
+
+    /// Writes the `post_sections_symbols` of the document followed by its
+    /// `post_sections_asserts`, right after the `SECTIONS` block.
+    fn write_post_sections_regions(&mut self) -> Result<(), SlinkyError> {
+        let d = self.d;
+
+        let symbol_assignments: Vec<&SymbolAssignment> = d
+            .post_sections_symbols
+            .iter()
+            .filter(|x| {
+                self.rs.should_emit_entry(
+                    &x.exclude_if_any,
+                    &x.exclude_if_all,
+                    &x.include_if_any,
+                    &x.include_if_all,
+                )
+            })
+            .collect();
+        if !symbol_assignments.is_empty() {
+            self.buffer.write_empty_line();
+            for symbol_assignment in symbol_assignments {
+                self.write_symbol_assignment(symbol_assignment)?;
+            }
+        }
+
+        let asserts: Vec<(usize, &AssertEntry)> = d
+            .post_sections_asserts
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| {
+                self.rs.should_emit_entry(
+                    &x.exclude_if_any,
+                    &x.exclude_if_all,
+                    &x.include_if_any,
+                    &x.include_if_all,
+                )
+            })
+            .collect();
+        if !asserts.is_empty() {
+            self.buffer.write_empty_line();
+            for (index, assert_entry) in asserts {
+                // Numbered after the `asserts`, so the symbols of the
+                // `warning` ones don't clash
+                self.add_assert(d.asserts.len() + index, assert_entry)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a handwritten piece of linker script at the current position,
+    /// matching the indentation of the generated script.
+    ///
+    /// Symbols assigned by the block are tracked like the generated ones, so
+    /// they are also listed on the symbols header.
+    pub fn add_raw_block(&mut self, contents: &str) {
+        self.buffer.write_raw(contents);
+    }
+
+    fn write_inputs(&mut self) -> Result<(), SlinkyError> {
+        let settings = &self.d.settings;
+
+        let startup_file = self.d.startup_file_escaped(self.rs)?;
+        let extra_inputs = self.d.extra_inputs_escaped(self.rs)?;
+        let libraries = settings.libraries_escaped(self.rs)?;
+
+        if startup_file.is_none()
+            && extra_inputs.is_empty()
+            && settings.search_paths.is_empty()
+            && libraries.is_empty()
+        {
+            return Ok(());
+        }
+
+        // The startup file is always linked first, regardless of the order
+        // of the object files on the command line
+        if let Some(startup_file) = startup_file {
+            write!(self.buffer, "STARTUP({});", startup_file);
+            self.add_object_path(None, startup_file);
+        }
 
-        self.buffer.writeln("__romPos = 0x0;");
+        for search_path in settings.search_paths_escaped(self.rs)? {
+            write!(self.buffer, "SEARCH_DIR(\"{}\");", search_path);
+        }
 
-        if let Some(hardcoded_gp_value) = self.d.settings.hardcoded_gp_value {
-            self.buffer
-                .writeln(&format!("_gp = 0x{:08X};", hardcoded_gp_value));
+        if !extra_inputs.is_empty() || !libraries.is_empty() {
+            let command = if settings.libraries_group {
+                "GROUP"
+            } else {
+                "INPUT"
+            };
+            let inputs: Vec<String> = extra_inputs
+                .iter()
+                .chain(libraries.iter().map(|(x, _)| x))
+                .map(|x| x.to_string())
+                .collect();
+
+            write!(self.buffer, "{}({});", command, inputs.join(" "));
+
+            for extra_input in extra_inputs {
+                self.add_object_path(None, extra_input);
+            }
+            for (library, is_path) in libraries {
+                if is_path {
+                    self.add_object_path(None, library);
+                }
+            }
         }
 
         self.buffer.write_empty_line();
@@ -410,14 +2307,28 @@ impl LinkerWriter<'_> {
     }
 
     pub(crate) fn end_sections(&mut self) -> Result<(), SlinkyError> {
-        let style = &self.d.settings.linker_symbols_style;
+        // The rom position is not tracked on single segment mode
         let mut need_ln = false;
+        if !self.single_segment {
+            need_ln = self.write_rom_summary_symbols();
+            need_ln |= self.write_rom_size_asserts();
+        }
+        let mut vram_class_sizes_need_ln = need_ln;
 
-        for (vram_class_name, vram_class) in &self.vram_classes {
-            if !vram_class.emitted {
+        let style = &self.d.settings.linker_symbols_style;
+
+        for vram_class_name in self.output_order(self.vram_classes.keys()) {
+            if !self.vram_classes[vram_class_name].emitted {
                 continue;
             }
 
+            if vram_class_sizes_need_ln {
+                self.buffer.write_empty_line();
+                vram_class_sizes_need_ln = false;
+            }
+
+            self.buffer
+                .set_origin(vram_class_origin(self.d, vram_class_name));
             self.buffer.write_linker_symbol(
                 &style.vram_class_size(vram_class_name),
                 &format!(
@@ -430,32 +2341,194 @@ impl LinkerWriter<'_> {
             need_ln = true;
         }
 
-        if !self.d.settings.sections_allowlist.is_empty() {
+        let mut vram_class_asserts_need_ln = need_ln;
+        for (vram_class_name, vram_class) in &self.vram_classes {
+            if !vram_class.emitted {
+                continue;
+            }
+
+            if let Some(high_water_limit) = vram_class.high_water_limit {
+                if vram_class_asserts_need_ln {
+                    self.buffer.write_empty_line();
+                    vram_class_asserts_need_ln = false;
+                }
+
+                let limit = self.buffer.address(high_water_limit);
+                self.buffer.write_assert(
+                    &format!(
+                        "{} <= {}",
+                        style.vram_class_high_water(vram_class_name),
+                        limit
+                    ),
+                    &format!("The vram class '{}' goes past {}", vram_class_name, limit),
+                );
+            }
+
+            if let (Some(max_size), Some(reserved)) = (vram_class.max_size, vram_class.reserved) {
+                if vram_class_asserts_need_ln {
+                    self.buffer.write_empty_line();
+                    vram_class_asserts_need_ln = false;
+                }
+
+                let budget = self.buffer.hex(max_size - reserved);
+                self.buffer
+                    .set_origin(vram_class_origin(self.d, vram_class_name));
+                self.buffer.write_assert(
+                    &format!("{} <= {}", style.vram_class_size(vram_class_name), budget),
+                    &format!(
+                        "The vram class '{}' does not fit on its budget of {} bytes, which leaves {} bytes reserved",
+                        vram_class_name,
+                        budget,
+                        self.buffer.hex(reserved)
+                    ),
+                );
+            }
+        }
+
+        if self.d.settings.vram_class_slack_symbols {
+            for (vram_class_name, members) in &self.vram_class_members {
+                if need_ln {
+                    self.buffer.write_empty_line();
+                }
+
+                let vram_class_sym_end = style.vram_class_end(vram_class_name);
+                for member in members {
+                    if let Some(segment) = self.d.segments.iter().find(|x| &x.symbol_name == member)
+                    {
+                        self.buffer.set_origin(self.segment_origin(segment));
+                    }
+                    self.buffer.write_linker_symbol(
+                        &style.segment_vram_class_slack(member),
+                        &format!(
+                            "{} - {}",
+                            vram_class_sym_end,
+                            style.segment_vram_end(member)
+                        ),
+                    );
+                }
+
+                need_ln = true;
+            }
+        }
+
+        let common_script_path = self.d.settings.common_script_path_escaped(self.rs)?;
+        if common_script_path.is_some() {
+            self.write_on_common_script(|w| {
+                w.buffer.writeln("SECTIONS");
+                w.buffer.begin_block();
+                w.write_trailing_sections(false);
+                w.buffer.end_block();
+
+                Ok(())
+            })?;
+        } else {
+            self.write_trailing_sections(need_ln);
+        }
+
+        self.buffer.end_block();
+
+        // Included after the `SECTIONS` block, so the wildcards of the common
+        // script only match what the rest of the script didn't place
+        if let Some(common_script_path) = common_script_path {
+            self.buffer.write_empty_line();
+            write!(self.buffer, "INCLUDE \"{}\"", common_script_path);
+            self.files_paths.insert(common_script_path);
+        }
+
+        if let Some(section) = &self.d.settings.insert_after {
+            write!(self.buffer, "INSERT AFTER {};", section);
+        } else if let Some(section) = &self.d.settings.insert_before {
+            write!(self.buffer, "INSERT BEFORE {};", section);
+        }
+
+        self.write_no_cross_refs();
+
+        // The partial scripts only place their own segment, the regions
+        // belong to the main script
+        if !self.single_segment || self.d.settings.single_segment_mode {
+            self.write_post_sections_regions()?;
+        }
+
+        if !self.compressed_size_symbols.is_empty() {
+            self.buffer.write_empty_line();
+
+            for (sym, origin) in &self.compressed_size_symbols {
+                self.buffer.set_origin(origin.clone());
+                self.buffer.write_required_linker_symbol(sym);
+            }
+        }
+
+        if self
+            .raw_blocks
+            .iter()
+            .any(|x| x.placement == Some(RawBlockPlacement::AfterSections))
+        {
+            self.buffer.write_empty_line();
+            self.write_raw_blocks(|x| x.placement == Some(RawBlockPlacement::AfterSections));
+        }
+
+        self.buffer.finish();
+
+        Ok(())
+    }
+
+    /// Writes the sections placed after every segment, like the debug
+    /// sections and the `/DISCARD/` block.
+    fn write_trailing_sections(&mut self, mut need_ln: bool) {
+        // An augmentation script is merged into the default script of the
+        // linker, which already takes care of the sections that aren't
+        // described by the document
+        let is_augmentation =
+            self.d.settings.insert_after.is_some() || self.d.settings.insert_before.is_some();
+
+        if !is_augmentation && !self.d.settings.sections_allowlist.is_empty() {
+            if need_ln {
+                self.buffer.write_empty_line();
+            }
+
+            for sect in self.output_order(&self.d.settings.sections_allowlist) {
+                self.buffer.write_single_entry_section(sect, "0");
+            }
+
+            need_ln = true;
+        }
+
+        if !is_augmentation && !self.d.settings.sections_allowlist_extra.is_empty() {
             if need_ln {
                 self.buffer.write_empty_line();
             }
 
-            for sect in &self.d.settings.sections_allowlist {
+            for sect in self.output_order(&self.d.settings.sections_allowlist_extra) {
                 self.buffer.write_single_entry_section(sect, "0");
             }
 
             need_ln = true;
         }
 
-        if !self.d.settings.sections_allowlist_extra.is_empty() {
+        if !is_augmentation && self.d.settings.emit_debug_sections {
             if need_ln {
                 self.buffer.write_empty_line();
             }
 
-            for sect in &self.d.settings.sections_allowlist_extra {
+            let settings = &self.d.settings;
+            for sect in DEBUG_SECTIONS {
+                // Don't emit the same section twice, and let the user discard
+                // any debug section they don't want
+                if settings.sections_allowlist.iter().any(|x| x == sect)
+                    || settings.sections_allowlist_extra.iter().any(|x| x == sect)
+                    || settings.sections_denylist.iter().any(|x| x == sect)
+                {
+                    continue;
+                }
+
                 self.buffer.write_single_entry_section(sect, "0");
             }
 
             need_ln = true;
         }
 
-        if self.d.settings.discard_wildcard_section || !self.d.settings.sections_denylist.is_empty()
-        {
+        let discard_wildcard_section = !is_augmentation && self.d.settings.discard_wildcard_section;
+        if discard_wildcard_section || !self.d.settings.sections_denylist.is_empty() {
             if need_ln {
                 self.buffer.write_empty_line();
             }
@@ -463,21 +2536,70 @@ impl LinkerWriter<'_> {
             self.buffer.writeln("/DISCARD/ :");
             self.buffer.begin_block();
 
-            for sect in &self.d.settings.sections_denylist {
-                self.buffer.writeln(&format!("*({});", sect));
+            for sect in self.output_order(&self.d.settings.sections_denylist) {
+                write!(self.buffer, "*({});", sect);
             }
 
-            if self.d.settings.discard_wildcard_section {
+            if discard_wildcard_section {
                 self.buffer.writeln("*(*);")
             }
 
             self.buffer.end_block();
         }
+    }
 
-        self.buffer.end_block();
-        self.buffer.finish();
+    /// Writes on the common script instead of the linker script, tracking the
+    /// linker symbols as if they were written on the latter.
+    fn write_on_common_script(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), SlinkyError>,
+    ) -> Result<(), SlinkyError> {
+        let mut common = match self.common_script.take() {
+            Some(common) => common,
+            None => return f(self),
+        };
 
-        Ok(())
+        std::mem::swap(&mut self.buffer, &mut common);
+        let result = f(self);
+        std::mem::swap(&mut self.buffer, &mut common);
+
+        self.buffer.take_linker_symbols(&mut common);
+        self.common_script = Some(common);
+
+        result
+    }
+
+    /// Writes a `NOCROSSREFS` directive for each vram class asking for it.
+    ///
+    /// Only the alloc section of each segment is listed, since listing its
+    /// noload section too would reject the references of a segment to its own
+    /// bss. External segments are skipped since their sections are unknown.
+    fn write_no_cross_refs(&mut self) {
+        for (vram_class_name, vram_class) in &self.vram_classes {
+            if !vram_class.no_cross_refs {
+                continue;
+            }
+
+            let sections: Vec<String> = self
+                .d
+                .segments
+                .iter()
+                .filter(|x| {
+                    x.vram_class.as_ref() == Some(vram_class_name)
+                        && !x.external
+                        && self.emitted_segments.contains(&x.name)
+                })
+                .map(|x| format!(".{}", x.name))
+                .collect();
+
+            // A single section can't reference any other one
+            if sections.len() < 2 {
+                continue;
+            }
+
+            self.buffer.write_empty_line();
+            write!(self.buffer, "NOCROSSREFS({});", sections.join(" "));
+        }
     }
 
     pub(crate) fn add_segment(&mut self, segment: &Segment) -> Result<(), SlinkyError> {
@@ -492,19 +2614,27 @@ impl LinkerWriter<'_> {
 
         assert!(!self.single_segment);
 
+        self.buffer.set_origin(self.segment_origin(segment));
+
+        if let Some(comment) = &segment.comment {
+            self.buffer.write_comment(comment);
+        }
+
         let style = &self.d.settings.linker_symbols_style;
 
         // rom segment symbols
-        let main_seg_rom_sym_start: String = style.segment_rom_start(&segment.name);
-        let main_seg_rom_sym_end: String = style.segment_rom_end(&segment.name);
-        let main_seg_rom_sym_size: String = style.segment_rom_size(&segment.name);
+        let main_seg_rom_sym_start: String = style.segment_rom_start(&segment.symbol_name);
+        let main_seg_rom_sym_end: String = style.segment_rom_end(&segment.symbol_name);
+        let main_seg_rom_sym_size: String = style.segment_rom_size(&segment.symbol_name);
 
         // vram segment symbols
-        let main_seg_sym_start: String = style.segment_vram_start(&segment.name);
-        let main_seg_sym_end: String = style.segment_vram_end(&segment.name);
-        let main_seg_sym_size: String = style.segment_vram_size(&segment.name);
+        let main_seg_sym_start: String = style.segment_vram_start(&segment.symbol_name);
+        let main_seg_sym_end: String = style.segment_vram_end(&segment.symbol_name);
+        let main_seg_sym_size: String = style.segment_vram_size(&segment.symbol_name);
 
         if let Some(vram_class_name) = &segment.vram_class {
+            let follows_classes_ends = self.follows_classes_ends(vram_class_name);
+
             let vram_class = match self.vram_classes.get_mut(vram_class_name) {
                 Some(vc) => vc,
                 None => {
@@ -518,82 +2648,491 @@ impl LinkerWriter<'_> {
             if !vram_class.emitted {
                 let vram_class_sym = style.vram_class_start(vram_class_name);
 
+                let segment_origin = std::mem::replace(
+                    self.buffer.origin_mut(),
+                    vram_class_origin(self.d, vram_class_name),
+                );
+
                 if let Some(fixed_vram) = vram_class.fixed_vram {
                     self.buffer
-                        .write_linker_symbol(&vram_class_sym, &format!("0x{:08X}", fixed_vram));
+                        .write_linker_symbol(&vram_class_sym, &self.buffer.address(fixed_vram));
                 } else if let Some(fixed_symbol) = &vram_class.fixed_symbol {
                     self.buffer
                         .write_linker_symbol(&vram_class_sym, fixed_symbol);
                 } else {
                     self.buffer
-                        .write_linker_symbol(&vram_class_sym, "0x00000000");
-                    for other_class_name in &vram_class.follows_classes {
-                        self.buffer.write_symbol_max_self(
-                            &vram_class_sym,
-                            &style.vram_class_end(other_class_name),
-                        );
+                        .write_linker_symbol(&vram_class_sym, &self.buffer.address(0u32));
+                    for other_class_end in &follows_classes_ends {
+                        self.buffer
+                            .write_symbol_max_self(&vram_class_sym, other_class_end);
                     }
                 }
-                self.buffer
-                    .write_linker_symbol(&style.vram_class_end(vram_class_name), "0x00000000");
+                self.buffer.write_linker_symbol(
+                    &style.vram_class_end(vram_class_name),
+                    &self.buffer.address(0u32),
+                );
+                if vram_class.high_water_symbol {
+                    self.buffer.write_linker_symbol(
+                        &style.vram_class_high_water(vram_class_name),
+                        &self.buffer.address(0u32),
+                    );
+                }
 
                 self.buffer.write_empty_line();
+                self.buffer.set_origin(segment_origin);
 
                 vram_class.emitted = true;
             }
         }
 
+        if segment.check_fixed_vram_overlaps {
+            self.check_fixed_vram_overlap(segment)?;
+        }
+        let has_rom = self.has_rom();
+        self.emitted_segments.push(segment.name.clone());
+        self.memory_map_segments.push(MemoryMapSegment {
+            name: segment.name.clone(),
+            vram_class: segment.vram_class.clone(),
+            rom_start: if has_rom {
+                main_seg_rom_sym_start.clone()
+            } else {
+                String::new()
+            },
+            rom_end: if has_rom {
+                main_seg_rom_sym_end.clone()
+            } else {
+                String::new()
+            },
+            vram_address: self.segment_vram_address(segment)?,
+            vram_start: main_seg_sym_start.clone(),
+            vram_end: main_seg_sym_end.clone(),
+        });
+        self.previous_segments.insert(
+            segment.vram_class.clone(),
+            (
+                segment.name.clone(),
+                segment.symbol_name.clone(),
+                segment.fixed_vram,
+            ),
+        );
+
         if let Some(segment_start_align) = segment.segment_start_align {
-            self.buffer.align_symbol("__romPos", segment_start_align);
+            if has_rom {
+                self.buffer.align_symbol("__romPos", segment_start_align);
+            }
             self.buffer.align_symbol(".", segment_start_align);
         }
 
-        self.buffer
-            .write_linker_symbol(&main_seg_rom_sym_start, "__romPos");
-        self.buffer
-            .write_linker_symbol(&main_seg_sym_start, &format!("ADDR(.{})", segment.name));
+        if has_rom {
+            self.buffer
+                .write_linker_symbol(&main_seg_rom_sym_start, "__romPos");
+        }
 
-        // Emit alloc segment
-        self.write_segment(segment, &segment.alloc_sections, false)?;
+        if segment.external {
+            let mut required_symbols = vec![main_seg_sym_start.as_str(), &main_seg_sym_end];
+            if has_rom {
+                required_symbols.push(&main_seg_rom_sym_end);
+            }
+            self.write_external_segment(segment, &required_symbols)?;
+
+            if has_rom {
+                write!(self.buffer, "__romPos = {};", main_seg_rom_sym_end);
+            }
+        } else {
+            self.buffer
+                .write_linker_symbol(&main_seg_sym_start, &format!("ADDR(.{})", segment.name));
+
+            // Emit alloc segment
+            self.write_segment(segment, &segment.alloc_sections, false)?;
+
+            self.buffer.write_empty_line();
+
+            // Emit noload segment
+            self.write_noload_segment(segment)?;
+
+            // Track it before the end alignment of the segment, which does
+            // not take any memory
+            if let Some(high_water_sym) = self.vram_class_high_water(segment) {
+                self.buffer.write_symbol_max_self(&high_water_sym, ".");
+            }
+
+            if self.d.settings.bss_symbols {
+                self.write_bss_symbols(segment);
+            }
+
+            self.write_small_data_symbols(segment);
+
+            if segment.compressed {
+                // The rom size of a compressed segment is only known after compressing it
+                let compressed_size_sym = style.segment_compressed_size(&segment.symbol_name);
+
+                write!(self.buffer, "__romPos += {};", compressed_size_sym);
+                self.compressed_size_symbols
+                    .push((compressed_size_sym, self.segment_origin(segment)));
+            } else if has_rom {
+                write!(self.buffer, "__romPos += SIZEOF(.{});", segment.name);
+            }
+        }
+
+        if let Some(segment_end_align) = segment.segment_end_align {
+            if has_rom {
+                self.buffer.align_symbol("__romPos", segment_end_align);
+            }
+            self.buffer.align_symbol(".", segment_end_align);
+        }
+
+        let with_size = self.d.settings.segment_size_symbols;
+        if segment.external {
+            // The end symbols are defined by the fragment itself
+            if with_size {
+                let pairs = [
+                    (&main_seg_sym_start, &main_seg_sym_end, &main_seg_sym_size),
+                    (
+                        &main_seg_rom_sym_start,
+                        &main_seg_rom_sym_end,
+                        &main_seg_rom_sym_size,
+                    ),
+                ];
+                let count = if has_rom { 2 } else { 1 };
+                for (start, end, size) in &pairs[..count] {
+                    self.buffer
+                        .write_linker_symbol(size, &format!("ABSOLUTE({} - {})", end, start));
+                }
+            }
+        } else {
+            self.write_sym_end_size(
+                &main_seg_sym_start,
+                &main_seg_sym_end,
+                &main_seg_sym_size,
+                ".",
+                with_size,
+            );
+
+            if has_rom {
+                self.write_sym_end_size(
+                    &main_seg_rom_sym_start,
+                    &main_seg_rom_sym_end,
+                    &main_seg_rom_sym_size,
+                    "__romPos",
+                    with_size,
+                );
+            }
+        }
+
+        if let Some(reserved_rom_size) = segment.reserved_rom_size {
+            // The rom end symbol keeps the actual end of the contents
+            let size = self.buffer.hex(reserved_rom_size);
+            let rom_size = if with_size {
+                main_seg_rom_sym_size.clone()
+            } else {
+                format!("({} - {})", main_seg_rom_sym_end, main_seg_rom_sym_start)
+            };
+            self.buffer.write_assert(
+                &format!("{} <= {}", rom_size, size),
+                &format!(
+                    "Segment '{}' does not fit on its reserved rom size of {} bytes",
+                    segment.name, size
+                ),
+            );
+            write!(
+                self.buffer,
+                "__romPos = {} + {};",
+                main_seg_rom_sym_start, size
+            );
+        }
+
+        if let Some(vram_class_name) = &segment.vram_class {
+            self.buffer.write_empty_line();
+
+            let vram_class_sym_end = style.vram_class_end(vram_class_name);
+            self.buffer
+                .write_symbol_max_self(&vram_class_sym_end, &main_seg_sym_end);
+
+            // The fragment of external segments is opaque, so use its end
+            if segment.external {
+                if let Some(high_water_sym) = self.vram_class_high_water(segment) {
+                    self.buffer
+                        .write_symbol_max_self(&high_water_sym, &main_seg_sym_end);
+                }
+            }
+
+            self.vram_class_members
+                .entry(vram_class_name.clone())
+                .or_default()
+                .push(segment.symbol_name.clone());
+        }
+
+        self.buffer.write_segment_separator();
+
+        let name = &segment.name;
+        self.write_placed_symbol_assignments(|x| x.inside_segment.as_ref() == Some(name))?;
+        self.write_raw_blocks(|x| x.after_segment.as_ref() == Some(name));
+
+        Ok(())
+    }
+
+    /// Adds every segment, grouping the consecutive segments that share an
+    /// `overlay_group` into a single `OVERLAY` statement.
+    pub(crate) fn add_segments(&mut self, segments: &[Segment]) -> Result<(), SlinkyError> {
+        for segment in segments {
+            if let Some(shared) = &segment.noload_merge {
+                if self.rs.should_emit_entry(
+                    &segment.exclude_if_any,
+                    &segment.exclude_if_all,
+                    &segment.include_if_any,
+                    &segment.include_if_all,
+                ) {
+                    self.noload_merges
+                        .entry(shared.clone())
+                        .or_default()
+                        .push(segment.clone());
+                }
+            }
+        }
+
+        let mut current_group: Option<&String> = None;
+        let mut i = 0;
+        while i < segments.len() {
+            let segment = &segments[i];
+            let len = match &segment.overlay_group {
+                Some(group) => segments[i..]
+                    .iter()
+                    .take_while(|x| x.overlay_group.as_ref() == Some(group))
+                    .count(),
+                None => 1,
+            };
+
+            // Segments which are not emitted don't split a group
+            let emitted = segments[i..i + len].iter().any(|x| {
+                self.rs.should_emit_entry(
+                    &x.exclude_if_any,
+                    &x.exclude_if_all,
+                    &x.include_if_any,
+                    &x.include_if_all,
+                )
+            });
+            if emitted && current_group != segment.group.as_ref() {
+                if let Some(group) = current_group {
+                    self.buffer.write_group_end(group);
+                }
+                current_group = segment.group.as_ref();
+                if let Some(group) = current_group {
+                    self.buffer.write_group_start(group);
+                }
+            }
+
+            if segment.overlay_group.is_some() {
+                self.add_overlay_group(&segments[i..i + len])?;
+            } else {
+                self.add_segment(segment)?;
+            }
+            i += len;
+
+            self.buffer.stream_pending()?;
+        }
+        if let Some(group) = current_group {
+            self.buffer.write_group_end(group);
+        }
+
+        // The noload sections would be left for the linker to place otherwise
+        for (shared, merged) in &self.noload_merges {
+            if !self.emitted_segments.contains(shared) {
+                return Err(SlinkyError::MissingNoloadMergeSegment {
+                    segment: Cow::from(merged[0].name.clone()),
+                    noload_merge: Cow::from(shared.clone()),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn add_overlay_group(&mut self, segments: &[Segment]) -> Result<(), SlinkyError> {
+        let members: Vec<&Segment> = segments
+            .iter()
+            .filter(|x| {
+                self.rs.should_emit_entry(
+                    &x.exclude_if_any,
+                    &x.exclude_if_all,
+                    &x.include_if_any,
+                    &x.include_if_all,
+                )
+            })
+            .collect();
+        let (first, last) = match (members.first(), members.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(()),
+        };
+
+        assert!(!self.single_segment);
+
+        let style = &self.d.settings.linker_symbols_style;
+
+        if first.check_fixed_vram_overlaps {
+            self.check_fixed_vram_overlap(first)?;
+        }
+
+        let has_rom = self.has_rom();
+        let vram_address = self.segment_vram_address(first)?;
+        for segment in &members {
+            let (rom_start, rom_end) = if has_rom {
+                (
+                    style.segment_rom_start(&segment.symbol_name),
+                    style.segment_rom_end(&segment.symbol_name),
+                )
+            } else {
+                (String::new(), String::new())
+            };
+
+            self.emitted_segments.push(segment.name.clone());
+            self.memory_map_segments.push(MemoryMapSegment {
+                name: segment.name.clone(),
+                vram_class: None,
+                rom_start,
+                rom_end,
+                vram_address: vram_address.clone(),
+                vram_start: style.segment_vram_start(&segment.symbol_name),
+                vram_end: style.segment_vram_end(&segment.symbol_name),
+            });
+            self.previous_segments.insert(
+                None,
+                (
+                    segment.name.clone(),
+                    segment.symbol_name.clone(),
+                    segment.fixed_vram,
+                ),
+            );
+        }
+
+        if let Some(segment_start_align) = first.segment_start_align {
+            if has_rom {
+                self.buffer.align_symbol("__romPos", segment_start_align);
+            }
+            self.buffer.align_symbol(".", segment_start_align);
+        }
+
+        let mut line = "OVERLAY".to_string();
+        if let Some(vram_address) = &vram_address {
+            line += &format!(" {}", vram_address);
+        }
+        line += if has_rom { " : AT(__romPos)" } else { " :" };
+        self.buffer.writeln(&line);
+        self.buffer.begin_block();
+
+        for (i, segment) in members.iter().enumerate() {
+            if i != 0 {
+                self.buffer.write_empty_line();
+            }
+
+            self.buffer.set_origin(self.segment_origin(segment));
+
+            if let Some(comment) = &segment.comment {
+                self.buffer.write_comment(comment);
+            }
+
+            // The sections of an `OVERLAY` can't have an address, so every
+            // symbol has to be defined inside of them
+            write!(self.buffer, ".{}", segment.name);
+            self.buffer.begin_block();
+            self.write_sections_kind_start(segment, false);
+            self.write_segment_contents(segment, &segment.alloc_sections)?;
+            self.write_sections_kind_end(segment, false);
+            self.buffer.end_block();
+        }
 
+        self.buffer.end_block();
         self.buffer.write_empty_line();
 
-        // Emit noload segment
-        self.write_segment(segment, &segment.noload_sections, true)?;
+        let with_size = self.d.settings.segment_size_symbols;
+        for segment in &members {
+            self.buffer.set_origin(self.segment_origin(segment));
+
+            let style = &self.d.settings.linker_symbols_style;
+
+            let main_seg_rom_sym_start = style.segment_rom_start(&segment.symbol_name);
+            let main_seg_rom_sym_end = style.segment_rom_end(&segment.symbol_name);
+            let main_seg_rom_sym_size = style.segment_rom_size(&segment.symbol_name);
+
+            let main_seg_sym_start = style.segment_vram_start(&segment.symbol_name);
+            let main_seg_sym_end = style.segment_vram_end(&segment.symbol_name);
+            let main_seg_sym_size = style.segment_vram_size(&segment.symbol_name);
 
-        self.buffer.write_empty_line();
+            // Symbols defined by the linker for each section of the `OVERLAY`
+            if has_rom {
+                self.buffer.write_linker_symbol(
+                    &main_seg_rom_sym_start,
+                    &overlay_load_symbol("__load_start_", &segment.name),
+                );
+            }
+            self.buffer
+                .write_linker_symbol(&main_seg_sym_start, &format!("ADDR(.{})", segment.name));
+
+            // The noload part of every member starts right after its own
+            // alloc part, overlapping the rest of the group
+            write!(
+                self.buffer,
+                ". = ADDR(.{}) + SIZEOF(.{});",
+                segment.name, segment.name
+            );
+            self.buffer.write_empty_line();
 
-        self.buffer
-            .writeln(&format!("__romPos += SIZEOF(.{});", segment.name));
+            self.write_noload_segment(segment)?;
 
-        if let Some(segment_end_align) = segment.segment_end_align {
-            self.buffer.align_symbol("__romPos", segment_end_align);
-            self.buffer.align_symbol(".", segment_end_align);
-        }
+            if self.d.settings.bss_symbols {
+                self.write_bss_symbols(segment);
+            }
 
-        self.write_sym_end_size(
-            &main_seg_sym_start,
-            &main_seg_sym_end,
-            &main_seg_sym_size,
-            ".",
-        );
+            self.write_small_data_symbols(segment);
 
-        self.write_sym_end_size(
-            &main_seg_rom_sym_start,
-            &main_seg_rom_sym_end,
-            &main_seg_rom_sym_size,
-            "__romPos",
-        );
+            self.write_sym_end_size(
+                &main_seg_sym_start,
+                &main_seg_sym_end,
+                &main_seg_sym_size,
+                ".",
+                with_size,
+            );
+            if has_rom {
+                self.write_sym_end_size(
+                    &main_seg_rom_sym_start,
+                    &main_seg_rom_sym_end,
+                    &main_seg_rom_sym_size,
+                    &overlay_load_symbol("__load_stop_", &segment.name),
+                    with_size,
+                );
+            }
 
-        if let Some(vram_class_name) = &segment.vram_class {
             self.buffer.write_empty_line();
+        }
+
+        // Continue after the biggest member of the group
+        for segment in &members {
+            let style = &self.d.settings.linker_symbols_style;
 
-            let vram_class_sym_end = style.vram_class_end(vram_class_name);
             self.buffer
-                .write_symbol_max_self(&vram_class_sym_end, &main_seg_sym_end);
+                .write_symbol_max_self(".", &style.segment_vram_end(&segment.symbol_name));
+        }
+        if has_rom {
+            write!(
+                self.buffer,
+                "__romPos = {};",
+                overlay_load_symbol("__load_stop_", &last.name)
+            );
         }
 
-        self.buffer.write_empty_line();
+        if let Some(segment_end_align) = last.segment_end_align {
+            if has_rom {
+                self.buffer.align_symbol("__romPos", segment_end_align);
+            }
+            self.buffer.align_symbol(".", segment_end_align);
+        }
+
+        self.buffer.write_segment_separator();
+
+        for segment in &members {
+            let name = &segment.name;
+            self.write_placed_symbol_assignments(|x| x.inside_segment.as_ref() == Some(name))?;
+            self.write_raw_blocks(|x| x.after_segment.as_ref() == Some(name));
+        }
 
         Ok(())
     }
@@ -603,11 +3142,23 @@ impl LinkerWriter<'_> {
         assert!(!self.single_segment);
         self.single_segment = true;
 
+        if segment.external {
+            // There is nothing to generate, the whole segment is handwritten
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "external".to_string(),
+                field2: "single_segment_mode".to_string(),
+            });
+        }
+
         self.buffer.writeln("SECTIONS");
         self.buffer.begin_block();
 
+        self.write_discard_rules();
+
+        self.buffer.set_origin(self.segment_origin(segment));
+
         if let Some(fixed_vram) = segment.fixed_vram {
-            self.buffer.writeln(&format!(". = 0x{:08X};", fixed_vram));
+            write!(self.buffer, ". = {};", self.buffer.address(fixed_vram));
             self.buffer.write_empty_line();
         }
 
@@ -621,11 +3172,38 @@ impl LinkerWriter<'_> {
 
         self.buffer.write_empty_line();
 
+        // The partial linking scripts also use this function, but the
+        // assignments belong to the main linker script on that case
+        if self.d.settings.single_segment_mode {
+            let name = &segment.name;
+            self.write_placed_symbol_assignments(|x| x.inside_segment.as_ref() == Some(name))?;
+        }
+
         self.end_sections()?;
 
         Ok(())
     }
 
+    fn add_symbol_assignments_block(
+        &mut self,
+        symbol_assignments: &[&SymbolAssignment],
+    ) -> Result<(), SlinkyError> {
+        if !symbol_assignments
+            .iter()
+            .any(|x| x.is_placed_after_sections())
+        {
+            return Ok(());
+        }
+
+        self.begin_symbol_assignments()?;
+        for symbol_assignment in symbol_assignments {
+            self.add_symbol_assignment(symbol_assignment)?;
+        }
+        self.end_symbol_assignments()?;
+
+        Ok(())
+    }
+
     pub(crate) fn begin_symbol_assignments(&mut self) -> Result<(), SlinkyError> {
         if !self.buffer.is_empty() {
             self.buffer.write_empty_line();
@@ -647,17 +3225,46 @@ impl LinkerWriter<'_> {
             &symbol_assignment.exclude_if_all,
             &symbol_assignment.include_if_any,
             &symbol_assignment.include_if_all,
-        ) {
+        ) || !symbol_assignment.is_placed_after_sections()
+        {
+            return Ok(());
+        }
+
+        self.write_symbol_assignment(symbol_assignment)
+    }
+
+    /// Aliases are also tracked as linker symbols, so they are exported along
+    /// the generated symbols.
+    fn write_symbol_assignment(
+        &mut self,
+        symbol_assignment: &SymbolAssignment,
+    ) -> Result<(), SlinkyError> {
+        let value = symbol_assignment.resolved_value(self.d)?;
+
+        if symbol_assignment.alias_of.is_none() {
+            self.buffer.write_symbol_assignment(
+                &symbol_assignment.name,
+                &value,
+                symbol_assignment.provide,
+                symbol_assignment.hidden,
+            );
             return Ok(());
         }
 
-        self.buffer.write_symbol_assignment(
+        let previous_origin = std::mem::replace(
+            self.buffer.origin_mut(),
+            symbol_assignment_origin(self.d, symbol_assignment),
+        );
+
+        self.buffer.write_symbol_alias(
             &symbol_assignment.name,
-            &symbol_assignment.value,
+            &value,
             symbol_assignment.provide,
             symbol_assignment.hidden,
         );
 
+        self.buffer.set_origin(previous_origin);
+
         Ok(())
     }
 
@@ -686,11 +3293,94 @@ impl LinkerWriter<'_> {
             return Ok(());
         }
 
-        self.buffer.write_required_symbol(&required_symbol.name);
+        if let Some(comment) = &required_symbol.comment {
+            self.buffer.write_comment(comment);
+        }
+
+        let name = &required_symbol.name;
+
+        match &required_symbol.provide_fallback {
+            Some(fallback) => {
+                // The `EXTERN` makes the `PROVIDE` kick in if nothing else
+                // defines the symbol, so it is always defined
+                write!(self.buffer, "EXTERN({});", name);
+                self.buffer
+                    .write_symbol_assignment(name, fallback, true, false);
+            }
+            None => self.buffer.write_required_symbol(name),
+        }
+
+        self.write_required_symbol_expectations(required_symbol);
+
+        Ok(())
+    }
+
+    /// Lists every required symbol on a single `EXTERN` statement, keeping the
+    /// order of the document, followed by the checks of each symbol.
+    fn add_required_symbols_group(
+        &mut self,
+        required_symbols: &[RequiredSymbol],
+    ) -> Result<(), SlinkyError> {
+        let emitted: Vec<&RequiredSymbol> = required_symbols
+            .iter()
+            .filter(|x| {
+                self.rs.should_emit_entry(
+                    &x.exclude_if_any,
+                    &x.exclude_if_all,
+                    &x.include_if_any,
+                    &x.include_if_all,
+                )
+            })
+            .collect();
+        if emitted.is_empty() {
+            return Ok(());
+        }
+
+        let names: Vec<(&str, Option<&str>)> = emitted
+            .iter()
+            .map(|x| (x.name.as_str(), x.comment.as_deref()))
+            .collect();
+        self.buffer.write_extern_group(&names);
+
+        for required_symbol in emitted {
+            let name = &required_symbol.name;
+
+            match &required_symbol.provide_fallback {
+                Some(fallback) => self
+                    .buffer
+                    .write_symbol_assignment(name, fallback, true, false),
+                None => self.buffer.write_defined_assert(name),
+            }
+
+            self.write_required_symbol_expectations(required_symbol);
+        }
 
         Ok(())
     }
 
+    fn write_required_symbol_expectations(&mut self, required_symbol: &RequiredSymbol) {
+        let name = &required_symbol.name;
+
+        if let Some(expected_value) = required_symbol.expected_value {
+            let value = self.buffer.address(expected_value);
+            self.buffer.write_assert(
+                &format!("{} == {}", name, value),
+                &format!("Required symbol '{}' is not at {}", name, value),
+            );
+        }
+        if let Some((start, end)) = required_symbol.expected_range {
+            let start = self.buffer.address(start);
+            let end = self.buffer.address(end);
+            self.buffer.write_assert(
+                &format!("{} >= {} && {} < {}", name, start, name, end),
+                &format!(
+                    "Required symbol '{}' is outside of the range [{}, {})",
+                    name, start, end
+                ),
+            );
+        }
+    }
+
     pub(crate) fn begin_asserts(&mut self) -> Result<(), SlinkyError> {
         if !self.buffer.is_empty() {
             self.buffer.write_empty_line();
@@ -703,7 +3393,13 @@ impl LinkerWriter<'_> {
         Ok(())
     }
 
-    pub(crate) fn add_assert(&mut self, assert_entry: &AssertEntry) -> Result<(), SlinkyError> {
+    /// `index` is the index of the assert on the `asserts` of the document,
+    /// used to name the symbol of `warning` asserts.
+    pub(crate) fn add_assert(
+        &mut self,
+        index: usize,
+        assert_entry: &AssertEntry,
+    ) -> Result<(), SlinkyError> {
         if !self.rs.should_emit_entry(
             &assert_entry.exclude_if_any,
             &assert_entry.exclude_if_all,
@@ -713,8 +3409,15 @@ impl LinkerWriter<'_> {
             return Ok(());
         }
 
-        self.buffer
-            .write_assert(&assert_entry.check, &assert_entry.error_message);
+        let check = assert_entry.resolved_check(self.d)?;
+
+        if assert_entry.warning {
+            self.buffer
+                .write_assert_warning(&AssertEntry::warning_symbol(index), &check);
+        } else {
+            self.buffer
+                .write_assert(&check, &assert_entry.error_message);
+        }
 
         Ok(())
     }
@@ -722,19 +3425,431 @@ impl LinkerWriter<'_> {
 
 // internal functions
 impl LinkerWriter<'_> {
-    fn write_sym_end_size(&mut self, start: &str, end: &str, size: &str, value: &str) {
-        self.buffer.write_linker_symbol(end, value);
+    /// Returns the expressions for the end of every class the given vram
+    /// class should be placed after.
+    ///
+    /// Classes without any emitted segment (i.e. all of them got excluded by
+    /// the runtime settings) never get their symbols defined, so referencing
+    /// them would produce an unlinkable script. An empty class ends at the
+    /// same place where it starts, so its fixed address or the end of the
+    /// classes it follows is used instead.
+    fn follows_classes_ends(&self, vram_class_name: &str) -> Vec<String> {
+        let style = &self.d.settings.linker_symbols_style;
+        let mut ends = Vec::new();
+        let mut visited = indexmap::IndexSet::new();
+        let mut pending: Vec<&str> = match self.vram_classes.get(vram_class_name) {
+            Some(vram_class) => {
+                ends.extend(vram_class.follows_symbols.iter().cloned());
+                vram_class
+                    .follows_classes
+                    .iter()
+                    .rev()
+                    .map(|x| x.as_str())
+                    .collect()
+            }
+            None => return ends,
+        };
+
+        while let Some(other_class_name) = pending.pop() {
+            if !visited.insert(other_class_name) {
+                continue;
+            }
+
+            let other_class = match self.vram_classes.get(other_class_name) {
+                Some(other_class) if !self.used_vram_classes.contains(other_class_name) => {
+                    other_class
+                }
+                _ => {
+                    ends.push(style.vram_class_end(other_class_name));
+                    continue;
+                }
+            };
+
+            if let Some(fixed_vram) = other_class.fixed_vram {
+                ends.push(self.buffer.address(fixed_vram));
+            } else if let Some(fixed_symbol) = &other_class.fixed_symbol {
+                ends.push(fixed_symbol.clone());
+            } else {
+                ends.extend(other_class.follows_symbols.iter().cloned());
+                pending.extend(other_class.follows_classes.iter().rev().map(|x| x.as_str()));
+            }
+        }
+
+        ends
+    }
+
+    /// Makes sure a segment placed at a fixed vram does not overlap the
+    /// previous segment of its vram class.
+    ///
+    /// Errors out right away if the overlap can be determined at generation
+    /// time, otherwise emits an `ASSERT` so the linker checks it.
+    fn check_fixed_vram_overlap(&mut self, segment: &Segment) -> Result<(), SlinkyError> {
+        let fixed_vram = match segment.fixed_vram {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let (prev_name, prev_symbol_name, prev_fixed_vram) =
+            match self.previous_segments.get(&segment.vram_class) {
+                Some(prev) => prev,
+                None => return Ok(()),
+            };
+
+        if let Some(prev_fixed_vram) = prev_fixed_vram {
+            // The previous segment starts after this one, so it can't end before it
+            if *prev_fixed_vram > fixed_vram {
+                return Err(SlinkyError::FixedVramOverlap {
+                    segment: Cow::from(segment.name.clone()),
+                    fixed_vram,
+                    previous_segment: Cow::from(prev_name.clone()),
+                });
+            }
+        }
+
+        let style = &self.d.settings.linker_symbols_style;
+        self.buffer.write_assert(
+            &format!(
+                "{} <= {}",
+                style.segment_vram_end(prev_symbol_name),
+                self.buffer.address(fixed_vram)
+            ),
+            &format!(
+                "Segment `{}` overlaps with the fixed vram of segment `{}` ({})",
+                prev_name,
+                segment.name,
+                self.buffer.address(fixed_vram)
+            ),
+        );
+        self.buffer.write_empty_line();
+
+        Ok(())
+    }
+
+    fn section_fill_value(segment: &Segment, section: &str) -> Option<u32> {
+        match segment.sections_fill_value.get(section) {
+            Some(fill_value) => Some(*fill_value),
+            None => segment.fill_value,
+        }
+    }
+
+    fn write_fill(&mut self, fill_value: u32) {
+        write!(self.buffer, "FILL({});", self.buffer.address(fill_value));
+        self.current_fill_value = Some(fill_value);
+    }
+
+    /// Emits a new `FILL` statement only if the fill value actually changes.
+    fn switch_fill_value(&mut self, fill_value: Option<u32>) {
+        if fill_value == self.current_fill_value {
+            return;
+        }
+
+        // Going back to no fill value, so use the linker's default instead
+        self.write_fill(fill_value.unwrap_or(0));
+        self.current_fill_value = fill_value;
+    }
+
+    /// Emits the symbols describing the whole image. Returns `true` if any
+    /// symbol was emitted.
+    fn write_rom_summary_symbols(&mut self) -> bool {
+        let settings = &self.d.settings;
+        let mut emitted = false;
+
+        if let Some(rom_end_symbol) = &settings.rom_end_symbol {
+            self.buffer.set_origin(settings_origin("rom_end_symbol"));
+            self.buffer
+                .write_provided_linker_symbol(rom_end_symbol, "__romPos");
+            emitted = true;
+        }
+
+        if let Some(rom_size_symbol) = &settings.rom_size_symbol {
+            // The rom always starts at zero
+            self.buffer.set_origin(settings_origin("rom_size_symbol"));
+            self.buffer
+                .write_provided_linker_symbol(rom_size_symbol, "__romPos");
+            emitted = true;
+        }
+
+        if let Some(total_bss_size_symbol) = &settings.total_bss_size_symbol {
+            let value = if self.emitted_segments.is_empty() {
+                "0".to_string()
+            } else {
+                // The merged segments are already counted by the segment they
+                // are merged into
+                self.emitted_segments
+                    .iter()
+                    .filter(|name| {
+                        !self
+                            .noload_merges
+                            .values()
+                            .flatten()
+                            .any(|x| x.name == **name)
+                    })
+                    .map(|name| format!("SIZEOF(.{}.noload)", name))
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            };
+
+            self.buffer
+                .set_origin(settings_origin("total_bss_size_symbol"));
+            self.buffer
+                .write_provided_linker_symbol(total_bss_size_symbol, &value);
+            emitted = true;
+        }
+
+        emitted
+    }
+
+    /// Emits the `_gp` symbols and checks which need the whole small data
+    /// range of the segment to be known.
+    fn write_small_data_symbols(&mut self, segment: &Segment) {
+        let gp_info = match &segment.gp_info {
+            Some(gp_info) => gp_info,
+            None => return,
+        };
+        // The range is computed from the section symbols
+        if !self.emit_section_symbols
+            || !self.rs.should_emit_entry(
+                &gp_info.exclude_if_any,
+                &gp_info.exclude_if_all,
+                &gp_info.include_if_any,
+                &gp_info.include_if_all,
+            )
+            || !(gp_info.midpoint || gp_info.check_range)
+        {
+            return;
+        }
+
+        let gp_relative_sections = gp_info.gp_relative_sections();
+        let mut sections = segment
+            .alloc_sections
+            .iter()
+            .chain(&segment.noload_sections)
+            .filter(|x| gp_relative_sections.contains(x));
+        let (first, last) = match sections.next() {
+            Some(first) => (first, sections.next_back().unwrap_or(first)),
+            None => return,
+        };
+
+        let style = &self.d.settings.linker_symbols_style;
+        let start = style.segment_section_start(&segment.symbol_name, first);
+        let end = style.segment_section_end(&segment.symbol_name, last);
+
+        if gp_info.midpoint {
+            self.buffer.write_symbol_assignment(
+                "_gp",
+                &format!("{} + ({} - {}) / 2", start, end, start),
+                gp_info.provide,
+                gp_info.hidden,
+            );
+        }
+
+        if gp_info.check_range {
+            // `$gp` relative accesses use a signed 16 bits offset
+            self.buffer.write_assert(
+                &format!("{} >= _gp - 0x8000 && {} <= _gp + 0x8000", start, end),
+                &format!(
+                    "The small data of the segment '{}' can't be fully addressed from _gp",
+                    segment.name
+                ),
+            );
+        }
+
+        self.buffer.write_empty_line();
+    }
+
+    fn vram_class_high_water(&self, segment: &Segment) -> Option<String> {
+        let vram_class_name = segment.vram_class.as_ref()?;
+        let vram_class = self.vram_classes.get(vram_class_name)?;
+
+        if vram_class.high_water_symbol {
+            Some(
+                self.d
+                    .settings
+                    .linker_symbols_style
+                    .vram_class_high_water(vram_class_name),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn write_rom_size_asserts(&mut self) -> bool {
+        let settings = &self.d.settings;
+        let mut emitted = false;
+
+        if let Some(rom_size_alignment) = settings.rom_size_alignment {
+            let alignment = self.buffer.hex(rom_size_alignment);
+            self.buffer.write_assert(
+                &format!("__romPos % {} == 0", alignment),
+                &format!("The rom size must be a multiple of {}", alignment),
+            );
+            emitted = true;
+        }
+
+        if let Some(rom_min_size) = settings.rom_min_size {
+            let size = self.buffer.hex(rom_min_size);
+            self.buffer.write_assert(
+                &format!("__romPos >= {}", size),
+                &format!("The rom must be at least {} bytes long", size),
+            );
+            emitted = true;
+        }
 
+        emitted
+    }
+
+    fn bss_symbols_names(&self, segment: &Segment) -> (String, String) {
+        let settings = &self.d.settings;
+        let style = &settings.linker_symbols_style;
+
+        let start_sym = match &settings.bss_symbols_start_name {
+            Some(template) => template.replace("{segment}", &segment.symbol_name),
+            None => style.segment_bss_vram_start(&segment.symbol_name),
+        };
+        let size_sym = match &settings.bss_symbols_size_name {
+            Some(template) => template.replace("{segment}", &segment.symbol_name),
+            None => style.segment_bss_vram_size(&segment.symbol_name),
+        };
+
+        (start_sym, size_sym)
+    }
+
+    fn write_bss_symbols(&mut self, segment: &Segment) {
+        // Already defined inside of the segment its noload sections are
+        // merged into
+        if segment.noload_merge.is_some() {
+            return;
+        }
+
+        let (start_sym, size_sym) = self.bss_symbols_names(segment);
+
+        self.buffer
+            .write_linker_symbol(&start_sym, &format!("ADDR(.{}.noload)", segment.name));
         self.buffer
-            .write_linker_symbol(size, &format!("ABSOLUTE({} - {})", end, start));
+            .write_linker_symbol(&size_sym, &format!("SIZEOF(.{}.noload)", segment.name));
+
+        self.buffer.write_empty_line();
+    }
+
+    fn write_sym_end_size(
+        &mut self,
+        start: &str,
+        end: &str,
+        size: &str,
+        value: &str,
+        with_size: bool,
+    ) {
+        self.buffer.write_linker_symbol(end, value);
+
+        if with_size {
+            self.buffer
+                .write_linker_symbol(size, &format!("ABSOLUTE({} - {})", end, start));
+        }
+    }
+
+    /// Emits the noload output section of the segment, followed by the noload
+    /// sections of every segment merged into it.
+    ///
+    /// Nothing is emitted if the noload sections of this segment are merged
+    /// into another one instead.
+    fn write_noload_segment(&mut self, segment: &Segment) -> Result<(), SlinkyError> {
+        if segment.noload_merge.is_some() {
+            return Ok(());
+        }
+
+        self.write_segment_start(segment, true)?;
+        self.write_segment_contents(segment, &segment.noload_sections)?;
+        if let Some(merged) = self.noload_merges.get(&segment.name).cloned() {
+            for merged_segment in &merged {
+                self.write_merged_noload(merged_segment)?;
+            }
+            self.buffer.set_origin(self.segment_origin(segment));
+        }
+        self.write_segment_end(segment, true);
+
+        self.buffer.write_empty_line();
+
+        if segment.check_noload_contiguity && !segment.noload_sections.is_empty() {
+            self.write_noload_contiguity_assert(segment);
+            self.buffer.write_empty_line();
+        }
+
+        Ok(())
+    }
+
+    /// Emits the noload sections of a segment inside the noload output
+    /// section of its `noload_merge` segment.
+    ///
+    /// The noload sections kind symbols and the bss symbols of the segment
+    /// are defined in place, since there is no output section of its own to
+    /// reference.
+    fn write_merged_noload(&mut self, segment: &Segment) -> Result<(), SlinkyError> {
+        let bss_symbols = if self.d.settings.bss_symbols {
+            Some(self.bss_symbols_names(segment))
+        } else {
+            None
+        };
+
+        self.buffer.write_empty_line();
+        self.buffer.set_origin(self.segment_origin(segment));
+
+        self.write_sections_kind_start(segment, true);
+        if let Some((start_sym, _)) = &bss_symbols {
+            self.buffer.write_linker_symbol(start_sym, ".");
+        }
+
+        self.write_segment_contents(segment, &segment.noload_sections)?;
+
+        if let Some((start_sym, size_sym)) = &bss_symbols {
+            self.buffer.write_empty_line();
+            self.buffer
+                .write_linker_symbol(size_sym, &format!("ABSOLUTE(. - {})", start_sym));
+        }
+        self.write_sections_kind_end(segment, true);
+
+        Ok(())
+    }
+
+    /// Checks the noload section starts right after the alloc one, only
+    /// allowing the padding required by the alignment of the noload section.
+    fn write_noload_contiguity_assert(&mut self, segment: &Segment) {
+        let alloc = format!(".{}", segment.name);
+        let noload = format!(".{}.noload", segment.name);
+
+        let mut expected = format!("ADDR({}) + SIZEOF({})", alloc, alloc);
+        if let Some(noload_start_align) = segment.noload_start_align {
+            expected = format!(
+                "ALIGN({}, {})",
+                expected,
+                self.buffer.alignment(noload_start_align, true)
+            );
+        }
+        expected = format!("ALIGN({}, ALIGNOF({}))", expected, noload);
+
+        self.buffer.write_assert(
+            &format!(
+                "SIZEOF({}) == 0 || ADDR({}) == {}",
+                noload, noload, expected
+            ),
+            &format!(
+                "Something was placed between the alloc and noload sections of segment '{}'",
+                segment.name
+            ),
+        );
     }
 
     fn write_sections_kind_start(&mut self, segment: &Segment, noload: bool) {
+        if noload {
+            if let Some(noload_start_align) = segment.noload_start_align {
+                self.buffer.align_symbol(".", noload_start_align);
+                self.buffer.write_empty_line();
+            }
+        }
+
         if self.emit_sections_kind_symbols {
             let style = &self.d.settings.linker_symbols_style;
 
             let seg_sym_suffix = if noload { "noload" } else { "alloc" };
-            let seg_sym = format!("{}_{}", segment.name, seg_sym_suffix);
+            let seg_sym = format!("{}_{}", segment.symbol_name, seg_sym_suffix);
 
             let seg_sym_start = style.segment_vram_start(&seg_sym);
 
@@ -751,13 +3866,14 @@ impl LinkerWriter<'_> {
             let style = &self.d.settings.linker_symbols_style;
 
             let seg_sym_suffix = if noload { "noload" } else { "alloc" };
-            let seg_sym = format!("{}_{}", segment.name, seg_sym_suffix);
+            let seg_sym = format!("{}_{}", segment.symbol_name, seg_sym_suffix);
 
             let seg_sym_start = style.segment_vram_start(&seg_sym);
             let seg_sym_end = style.segment_vram_end(&seg_sym);
             let seg_sym_size = style.segment_vram_size(&seg_sym);
 
-            self.write_sym_end_size(&seg_sym_start, &seg_sym_end, &seg_sym_size, ".");
+            let with_size = self.d.settings.sections_kind_size_symbols;
+            self.write_sym_end_size(&seg_sym_start, &seg_sym_end, &seg_sym_size, ".", with_size);
         }
     }
 
@@ -776,22 +3892,38 @@ impl LinkerWriter<'_> {
                     &gp_info.exclude_if_all,
                     &gp_info.include_if_any,
                     &gp_info.include_if_all,
-                ) && gp_info.section == *section
+                ) && !gp_info.midpoint
+                    && gp_info.section == *section
                 {
                     self.buffer.write_symbol_assignment(
                         "_gp",
-                        &format!(". + 0x{:X}", gp_info.offset),
+                        &format!(". + {}", self.buffer.hex(gp_info.offset as u32)),
                         gp_info.provide,
                         gp_info.hidden,
                     );
                 }
             }
 
+            // The linker may pad the location counter before placing the
+            // first input section, so `.` is not always where the section
+            // starts
+            let value = if !self.d.settings.aligned_section_start_symbols {
+                ".".to_string()
+            } else if self.d.settings.single_segment_mode {
+                // Each section is its own output section on this mode
+                format!("ADDR({})", section)
+            } else {
+                if let Some(subalign) = segment.subalign {
+                    self.buffer.align_symbol(".", subalign);
+                }
+                ".".to_string()
+            };
+
             let style = &self.d.settings.linker_symbols_style;
 
-            let section_start_sym = style.segment_section_start(&segment.name, section);
+            let section_start_sym = style.segment_section_start(&segment.symbol_name, section);
 
-            self.buffer.write_linker_symbol(&section_start_sym, ".");
+            self.buffer.write_linker_symbol(&section_start_sym, &value);
         }
     }
 
@@ -806,15 +3938,22 @@ impl LinkerWriter<'_> {
 
             let style = &self.d.settings.linker_symbols_style;
 
-            let section_start_sym = style.segment_section_start(&segment.name, section);
-            let section_end_sym = style.segment_section_end(&segment.name, section);
-            let section_size_sym = style.segment_section_size(&segment.name, section);
-
-            self.write_sym_end_size(&section_start_sym, &section_end_sym, &section_size_sym, ".");
+            let section_start_sym = style.segment_section_start(&segment.symbol_name, section);
+            let section_end_sym = style.segment_section_end(&segment.symbol_name, section);
+            let section_size_sym = style.segment_section_size(&segment.symbol_name, section);
+
+            let with_size = self.d.settings.section_size_symbols;
+            self.write_sym_end_size(
+                &section_start_sym,
+                &section_end_sym,
+                &section_size_sym,
+                ".",
+                with_size,
+            );
         }
     }
 
-    fn write_segment_start(&mut self, segment: &Segment, noload: bool) {
+    fn write_segment_start(&mut self, segment: &Segment, noload: bool) -> Result<(), SlinkyError> {
         let style = &self.d.settings.linker_symbols_style;
 
         self.write_sections_kind_start(segment, noload);
@@ -825,25 +3964,65 @@ impl LinkerWriter<'_> {
         if noload {
             line += " (NOLOAD) :";
         } else {
-            if let Some(fixed_vram) = segment.fixed_vram {
-                line += &format!(" 0x{:08X}", fixed_vram);
-            } else if let Some(fixed_symbol) = &segment.fixed_symbol {
-                line += &format!(" {}", fixed_symbol);
-            } else if let Some(follows_segment) = &segment.follows_segment {
-                line += &format!(" {}", style.segment_vram_end(follows_segment));
-            } else if let Some(vram_class) = &segment.vram_class {
-                line += &format!(" {}", style.vram_class_start(vram_class));
+            if let Some(vram_address) = self.segment_vram_address(segment)? {
+                line += &format!(" {}", vram_address);
             }
 
-            line += &format!(" : AT({})", style.segment_rom_start(&segment.name));
+            if self.has_rom() {
+                let rom_start = style.segment_rom_start(&segment.symbol_name);
+                // Avoid depending on a symbol defined by the contents of the
+                // segment itself
+                if self.buffer.is_suppressed_linker_symbol(&rom_start) {
+                    line += " : AT(__romPos)";
+                } else {
+                    line += &format!(" : AT({})", rom_start);
+                }
+            } else {
+                line += " :";
+            }
         }
 
         if let Some(subalign) = segment.subalign {
-            line += &format!(" SUBALIGN({})", subalign);
+            line += &format!(" SUBALIGN({})", self.buffer.alignment(subalign, false));
         }
 
         self.buffer.writeln(&line);
         self.buffer.begin_block();
+
+        Ok(())
+    }
+
+    /// The expression used as the vram address of the segment, if any.
+    fn segment_vram_address(&self, segment: &Segment) -> Result<Option<String>, SlinkyError> {
+        let style = &self.d.settings.linker_symbols_style;
+
+        Ok(if let Some(fixed_vram) = segment.fixed_vram {
+            Some(self.buffer.address(fixed_vram))
+        } else if let Some(fixed_symbol) = segment.resolved_fixed_symbol(self.d)? {
+            // Keeps expressions like `a + 0x400` as a single address
+            if fixed_symbol
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                Some(fixed_symbol)
+            } else {
+                Some(format!("({})", fixed_symbol))
+            }
+        } else if let Some(follows_segment) = &segment.follows_segment {
+            let follows_symbol_name = self
+                .d
+                .segments
+                .iter()
+                .find(|x| x.name == *follows_segment)
+                .map_or(follows_segment, |x| &x.symbol_name);
+
+            Some(style.segment_vram_end(follows_symbol_name))
+        } else {
+            segment
+                .vram_class
+                .as_ref()
+                .map(|vram_class| style.vram_class_start(vram_class))
+        })
     }
 
     fn write_segment_end(&mut self, segment: &Segment, noload: bool) {
@@ -852,6 +4031,20 @@ impl LinkerWriter<'_> {
         self.write_sections_kind_end(segment, noload);
     }
 
+    /// The input section spec of a file for the given section, using the
+    /// `input_section_patterns` of the section if any.
+    fn input_section_spec(&self, segment: &Segment, section: &str, wildcard: &str) -> String {
+        let spec = match self.d.settings.input_section_patterns.get(section) {
+            Some(patterns) => patterns.join(" "),
+            None => format!("{}{}", section, wildcard),
+        };
+
+        match segment.sections_order_by.get(section) {
+            Some(OrderBy::AlignmentDesc) => format!("SORT_BY_ALIGNMENT({})", spec),
+            None => spec,
+        }
+    }
+
     fn emit_file(
         &mut self,
         file: &FileInfo,
@@ -871,9 +4064,17 @@ impl LinkerWriter<'_> {
 
         let style = &self.d.settings.linker_symbols_style;
 
-        let wildcard = if segment.wildcard_sections { "*" } else { "" };
+        let wildcard = if file.uses_wildcard(segment, section) {
+            "*"
+        } else {
+            ""
+        };
+
+        let keep_anchor = self.keep_next_linker_offset_anchor
+            && matches!(file.kind, FileKind::Object | FileKind::Archive);
 
         let (left_side, right_side) = match &file.keep_sections {
+            _ if keep_anchor || file.whole_archive => ("KEEP(", ")"),
             KeepSections::Absent => ("", ""),
             KeepSections::All(all) => {
                 if *all {
@@ -883,7 +4084,7 @@ impl LinkerWriter<'_> {
                 }
             }
             KeepSections::WhichOnes(which_ones) => {
-                if which_ones.contains(section) {
+                if which_ones.iter().any(|x| utils::glob_matches(x, section)) {
                     ("KEEP(", ")")
                 } else {
                     ("", "")
@@ -891,50 +4092,202 @@ impl LinkerWriter<'_> {
             }
         };
 
+        if keep_anchor {
+            self.keep_next_linker_offset_anchor = false;
+        }
+
+        // Restore the fill value of the section after this file
+        let previous_fill_value = self.current_fill_value;
+        if let Some(fill_value) = file.fill_value {
+            self.switch_fill_value(Some(fill_value));
+        }
+
+        let file_symbols = if file.emits_file_symbols(segment) {
+            // Named after the path relative to `base_path`, so the directories
+            // of the segment and the groups are part of the name
+            let path = self.join_escaped_path(base_path, &file.path)?;
+            let root = self.d.settings.base_path_escaped(self.rs)?;
+            let path: &Path = path.as_ref();
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            let name = file_path_symbol_name(file, relative);
+            let start = style.file_section_start(&segment.symbol_name, &name, section);
+            let end = style.file_section_end(&segment.symbol_name, &name, section);
+
+            self.buffer.origin_mut().field = Some("emit_file_symbols".to_string());
+            self.buffer.write_linker_symbol(&start, ".");
+            self.buffer.origin_mut().field = None;
+
+            Some(end)
+        } else {
+            None
+        };
+
+        let pad_start = match file.pad_to_size.get(section) {
+            Some(size) if matches!(file.kind, FileKind::Object | FileKind::Archive) => {
+                let sym = style.file_pad_start(&segment.name, &file_symbol_name(file), section);
+
+                self.buffer.origin_mut().field = Some("pad_to_size".to_string());
+                self.buffer.write_linker_symbol(&sym, ".");
+                self.buffer.origin_mut().field = None;
+
+                Some((sym, *size))
+            }
+            _ => None,
+        };
+
+        let forbid_start = if file.forbid_sections.iter().any(|x| x == section) {
+            let sym = style.file_forbid_start(&segment.name, &file_symbol_name(file), section);
+
+            self.buffer.origin_mut().field = Some("forbid_sections".to_string());
+            self.buffer.write_linker_symbol(&sym, ".");
+            self.buffer.origin_mut().field = None;
+
+            Some(sym)
+        } else {
+            None
+        };
+
         // TODO: figure out glob support
         match file.kind {
             FileKind::Object => {
-                let mut path = base_path.clone();
-                path.push(file.path_escaped(self.rs)?);
-
-                self.buffer.writeln(&format!(
-                    "{}{}({}{}){};",
-                    left_side, path, section, wildcard, right_side
-                ));
-                if !self.files_paths.contains(&path) {
-                    self.files_paths.insert(path);
+                let path = self.join_escaped_path(base_path, &file.path)?;
+
+                // The explicitly ordered subsections are placed before the
+                // wildcard so the latter only matches the remaining ones
+                if let Some(subsections) = file.subsection_order.get(section) {
+                    for subsection in subsections {
+                        write!(
+                            self.buffer,
+                            "{}{}({}){};",
+                            left_side, path, subsection, right_side
+                        );
+                    }
+                }
+                for pattern in renamed_patterns(file, section) {
+                    write!(
+                        self.buffer,
+                        "{}{}({}){};",
+                        left_side, path, pattern, right_side
+                    );
                 }
+
+                write!(
+                    self.buffer,
+                    "{}{}({}){};",
+                    left_side,
+                    path,
+                    self.input_section_spec(segment, section, wildcard),
+                    right_side
+                );
+                self.add_object_path(Some(segment), path);
             }
             FileKind::Archive => {
-                let mut path = base_path.clone();
-                path.push(file.path_escaped(self.rs)?);
-
-                self.buffer.writeln(&format!(
-                    "{}{}:{}({}{}){};",
-                    left_side, path, file.subfile, section, wildcard, right_side
-                ));
-                if !self.files_paths.contains(&path) {
-                    self.files_paths.insert(path);
+                let path = self.join_escaped_path(base_path, &file.path)?;
+                if self.d.settings.check_archive_members && file.subfile != "*" {
+                    self.check_archive_member(&path, &file.subfile)?;
+                }
+
+                if let Some(subsections) = file.subsection_order.get(section) {
+                    for subsection in subsections {
+                        write!(
+                            self.buffer,
+                            "{}{}:{}({}){};",
+                            left_side, path, file.subfile, subsection, right_side
+                        );
+                    }
+                }
+                for pattern in renamed_patterns(file, section) {
+                    write!(
+                        self.buffer,
+                        "{}{}:{}({}){};",
+                        left_side, path, file.subfile, pattern, right_side
+                    );
+                }
+
+                write!(
+                    self.buffer,
+                    "{}{}:{}({}){};",
+                    left_side,
+                    path,
+                    file.subfile,
+                    self.input_section_spec(segment, section, wildcard),
+                    right_side
+                );
+                self.add_object_path(Some(segment), path);
+            }
+            FileKind::Binary => {
+                if file.targets_section(section) {
+                    let path = self.join_escaped_path(base_path, &file.path)?;
+
+                    // `objcopy -I binary` names its symbols after the path
+                    // of the input file, mangling anything that is not valid
+                    // on an identifier
+                    let mangled = mangle_binary_name(&path.to_string());
+                    let name = mangle_binary_name(
+                        &file
+                            .path
+                            .file_stem()
+                            .map_or(String::new(), |x| x.to_string_lossy().to_string()),
+                    );
+
+                    let object = EscapedPath::from(format!("{}.o", path));
+
+                    self.buffer.origin_mut().field = Some("path".to_string());
+
+                    // Nothing references the blob directly, so it must survive
+                    // gc-sections on its own
+                    write!(self.buffer, "KEEP({}({}));", object, section);
+
+                    self.buffer.write_linker_symbol(
+                        &style.binary_start(&name),
+                        &format!("_binary_{}_start", mangled),
+                    );
+                    self.buffer.write_linker_symbol(
+                        &style.binary_end(&name),
+                        &format!("_binary_{}_end", mangled),
+                    );
+                    self.buffer.write_linker_symbol(
+                        &style.binary_size(&name),
+                        &format!("_binary_{}_size", mangled),
+                    );
+
+                    self.buffer.origin_mut().field = None;
+
+                    self.add_object_path(Some(segment), object);
                 }
             }
             FileKind::Pad => {
-                if file.section == section {
-                    self.buffer
-                        .writeln(&format!(". += 0x{:X};", file.pad_amount));
+                if file.targets_section(section) {
+                    write!(self.buffer, ". += {};", self.buffer.hex(file.pad_amount));
+                }
+            }
+            FileKind::Align => {
+                if file.targets_section(section) {
+                    self.buffer.align_symbol(".", file.align_amount);
                 }
             }
             FileKind::LinkerOffset => {
-                if file.section == section {
-                    self.buffer
-                        .write_linker_symbol(&style.linker_offset(&file.linker_offset_name), ".");
+                if file.targets_section(section) {
+                    // Each section gets its own symbol, otherwise the last
+                    // one would override the rest
+                    let sym = if file.sections.len() > 1 {
+                        style.section_linker_offset(&file.linker_offset_name, section)
+                    } else {
+                        style.linker_offset(&file.linker_offset_name)
+                    };
+                    self.buffer.origin_mut().field = Some("linker_offset_name".to_string());
+                    self.buffer.write_linker_symbol(&sym, ".");
+                    self.buffer.origin_mut().field = None;
+
+                    // Avoid gc-sections removing the data this symbol is meant to mark
+                    self.keep_next_linker_offset_anchor = segment.keep_linker_offset_anchors;
                 }
             }
             FileKind::Group => {
-                let mut new_base_path = base_path.clone();
-
-                new_base_path.push(file.dir_escaped(self.rs)?);
+                let new_base_path = self.join_escaped_path(base_path, &file.dir)?;
 
-                for file_of_group in &file.files {
+                for (i, file_of_group) in file.files.iter().enumerate() {
+                    self.push_file_origin(i);
                     self.emit_section_for_file(
                         file_of_group,
                         segment,
@@ -942,13 +4295,136 @@ impl LinkerWriter<'_> {
                         sections,
                         &new_base_path,
                     )?;
+                    self.pop_file_origin();
                 }
             }
         }
 
+        // Placed before the padding, so only the contents of the file are
+        // bracketed
+        if let Some(end) = file_symbols {
+            self.buffer.origin_mut().field = Some("emit_file_symbols".to_string());
+            self.buffer.write_linker_symbol(&end, ".");
+            self.buffer.origin_mut().field = None;
+        }
+
+        if let Some((sym, size)) = pad_start {
+            let size = self.buffer.hex(size);
+
+            self.buffer.write_assert(
+                &format!(". <= {} + {}", sym, size),
+                &format!(
+                    "Section {} of '{}' is bigger than its pad_to_size {}",
+                    section,
+                    file.path.display(),
+                    size
+                ),
+            );
+            write!(self.buffer, ". = {} + {};", sym, size);
+        }
+
+        if let Some(sym) = forbid_start {
+            self.buffer.write_assert(
+                &format!(". == {}", sym),
+                &format!(
+                    "Section {} of '{}' is forbidden but it is not empty",
+                    section,
+                    file.path.display()
+                ),
+            );
+        }
+
+        if file.fill_value.is_some() {
+            self.switch_fill_value(previous_fill_value);
+        }
+
         Ok(())
     }
 
+    /// The origin of the symbols generated for the given segment.
+    fn segment_origin(&self, segment: &Segment) -> SymbolOrigin {
+        let index = self
+            .d
+            .segments
+            .iter()
+            .position(|x| x.name == segment.name)
+            .unwrap_or_default();
+
+        SymbolOrigin {
+            entry: EntryPath {
+                components: vec![EntryPathComponent::Segment {
+                    index,
+                    name: segment.name.clone(),
+                }],
+            },
+            field: None,
+        }
+    }
+
+    fn push_file_origin(&mut self, index: usize) {
+        self.buffer
+            .origin_mut()
+            .entry
+            .components
+            .push(EntryPathComponent::File { index });
+    }
+
+    fn pop_file_origin(&mut self) {
+        self.buffer.origin_mut().entry.components.pop();
+    }
+
+    /// Makes sure the archive at `path` has at least one member matching the
+    /// `subfile` pattern, so a typo fails now instead of silently linking
+    /// nothing.
+    fn check_archive_member(
+        &mut self,
+        path: &EscapedPath,
+        subfile: &str,
+    ) -> Result<(), SlinkyError> {
+        if !self.archives_members.contains_key(path) {
+            let members = archive::read_archive_members(path.as_ref())?;
+            self.archives_members.insert(path.clone(), members);
+        }
+
+        if self.archives_members[path]
+            .iter()
+            .any(|member| utils::glob_matches(subfile, member))
+        {
+            Ok(())
+        } else {
+            Err(SlinkyError::MissingArchiveMember {
+                path: path.0.clone(),
+                member: subfile.to_string(),
+            })
+        }
+    }
+
+    /// Escapes `path` and appends it to `base_path`, reusing the result of
+    /// previous calls.
+    fn join_escaped_path(
+        &mut self,
+        base_path: &EscapedPath,
+        path: &Path,
+    ) -> Result<EscapedPath, SlinkyError> {
+        if let Some(joined) = self
+            .escaped_paths
+            .get(base_path)
+            .and_then(|paths| paths.get(path))
+        {
+            return Ok(joined.clone());
+        }
+
+        let mut joined = base_path.clone();
+        joined.push(self.rs.escape_path(path)?);
+
+        self.escaped_paths
+            .entry(base_path.clone())
+            .or_default()
+            .insert(path.to_path_buf(), joined.clone());
+
+        Ok(joined)
+    }
+
     fn emit_section_for_file(
         &mut self,
         file: &FileInfo,
@@ -1018,39 +4494,178 @@ impl LinkerWriter<'_> {
             base_path.push(segment.dir_escaped(self.rs)?);
         }
 
-        for file in &segment.files {
+        let mut files: Vec<(usize, &FileInfo)> = segment.files.iter().enumerate().collect();
+        if segment.sections_order_by.get(section) == Some(&OrderBy::AlignmentDesc) {
+            // Stable, so the files without a known alignment keep their
+            // order after the rest
+            files.sort_by_key(|(_, file)| std::cmp::Reverse(file.alignment.get(section)));
+        }
+
+        for (i, file) in files {
+            self.push_file_origin(i);
             self.emit_section_for_file(file, segment, section, sections, &base_path)?;
+            self.pop_file_origin();
+        }
+
+        // Anchors only apply within the same section
+        self.keep_next_linker_offset_anchor = false;
+
+        Ok(())
+    }
+
+    /// Includes the handwritten fragment of an external segment, which must
+    /// define the given symbols.
+    ///
+    /// The location counter is moved to the vram address of the segment
+    /// beforehand, so the fragment only needs to place its output sections at
+    /// `.` and load them at the rom start symbol of the segment.
+    fn write_external_segment(
+        &mut self,
+        segment: &Segment,
+        required_symbols: &[&str],
+    ) -> Result<(), SlinkyError> {
+        let fragment_path = match segment.fragment_path_escaped(self.rs)? {
+            Some(p) => p,
+            None => {
+                return Err(SlinkyError::MissingRequiredFieldCombo {
+                    required: "fragment_path".to_string(),
+                    other: "external".to_string(),
+                })
+            }
+        };
+
+        let contents = match utils::read_to_string(&fragment_path.0) {
+            Ok(contents) => contents,
+            Err(e) => {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: fragment_path.0.clone(),
+                    description: e.to_string(),
+                })
+            }
+        };
+        for symbol in required_symbols {
+            if !defines_symbol(&contents, symbol) {
+                return Err(SlinkyError::MissingFragmentSymbol {
+                    segment: segment.name.clone(),
+                    symbol: symbol.to_string(),
+                    path: fragment_path.0.clone(),
+                });
+            }
+        }
+
+        if let Some(vram_address) = self.segment_vram_address(segment)? {
+            write!(self.buffer, ". = {};", vram_address);
+        }
+        write!(self.buffer, "INCLUDE \"{}\"", fragment_path);
+        for symbol in required_symbols {
+            self.buffer.write_required_linker_symbol(symbol);
         }
 
+        self.files_paths.insert(fragment_path);
+
         Ok(())
     }
 
+    /// Tracks an object or archive referenced by the script, optionally as
+    /// part of the given segment.
+    fn add_object_path(&mut self, segment: Option<&Segment>, path: EscapedPath) {
+        if let Some(segment) = segment {
+            self.segment_objects_paths
+                .entry(segment.name.clone())
+                .or_default()
+                .insert(path.clone());
+
+            if let Some(toolchain) = &segment.toolchain {
+                self.toolchain_objects_paths
+                    .entry(toolchain.clone())
+                    .or_default()
+                    .insert(path.clone());
+            }
+        }
+
+        self.files_paths.insert(path.clone());
+        self.objects_paths.insert(path);
+    }
+
+    /// Sorts the given entries if `deterministic_output` is enabled, otherwise
+    /// they are kept in the order they were given on the document.
+    fn output_order<T: Ord>(&self, entries: impl IntoIterator<Item = T>) -> Vec<T> {
+        let mut entries: Vec<T> = entries.into_iter().collect();
+
+        if self.d.settings.deterministic_output {
+            entries.sort();
+        }
+
+        entries
+    }
+
+    /// Sections without any file entry are only skipped if requested, and
+    /// never if `_gp` depends on them.
+    fn should_skip_empty_section(&self, segment: &Segment, section: &str) -> bool {
+        if !self.d.settings.skip_empty_sections {
+            return false;
+        }
+
+        match &segment.gp_info {
+            Some(gp_info) => {
+                gp_info.section != section
+                    && !gp_info.gp_relative_sections().iter().any(|x| x == section)
+            }
+            None => true,
+        }
+    }
+
     fn write_segment(
         &mut self,
         segment: &Segment,
         sections: &[String],
         noload: bool,
     ) -> Result<(), SlinkyError> {
-        self.write_segment_start(segment, noload);
+        self.write_segment_start(segment, noload)?;
+        self.write_segment_contents(segment, sections)?;
+        self.write_segment_end(segment, noload);
 
+        Ok(())
+    }
+
+    fn write_segment_contents(
+        &mut self,
+        segment: &Segment,
+        sections: &[String],
+    ) -> Result<(), SlinkyError> {
+        self.current_fill_value = None;
         if let Some(fill_value) = segment.fill_value {
-            self.buffer.writeln(&format!("FILL(0x{:08X});", fill_value));
+            self.write_fill(fill_value);
         }
 
-        for (i, section) in sections.iter().enumerate() {
+        let mut need_ln = false;
+        for section in sections {
+            let position = self.buffer.position();
+            let fill_value = self.current_fill_value;
+            if need_ln {
+                self.buffer.write_empty_line();
+            }
+
+            self.switch_fill_value(Self::section_fill_value(segment, section));
+
             self.write_section_symbol_start(segment, section);
 
+            let files_position = self.buffer.position();
             self.emit_section(segment, section, sections)?;
 
+            if !self.buffer.written_since(files_position)
+                && self.should_skip_empty_section(segment, section)
+            {
+                self.buffer.rollback(position);
+                self.current_fill_value = fill_value;
+                continue;
+            }
+
             self.write_section_symbol_end(segment, section);
 
-            if i + 1 < sections.len() {
-                self.buffer.write_empty_line();
-            }
+            need_ln = true;
         }
 
-        self.write_segment_end(segment, noload);
-
         Ok(())
     }
 
@@ -1062,32 +4677,53 @@ impl LinkerWriter<'_> {
     ) -> Result<(), SlinkyError> {
         self.write_sections_kind_start(segment, noload);
 
-        for (i, section) in sections.iter().enumerate() {
+        let mut need_ln = false;
+        for section in sections {
             let mut line = String::new();
 
+            let position = self.buffer.position();
+            if need_ln {
+                self.buffer.write_empty_line();
+            }
+
             self.write_section_symbol_start(segment, section);
 
             line += &format!("{}{} :", section, if noload { " (NOLOAD)" } else { "" });
 
-            if let Some(subalign) = segment.subalign {
-                line += &format!(" SUBALIGN({})", subalign);
+            if let Some(align) = segment.sections_header_align.get(section) {
+                line += &format!(" ALIGN({})", self.buffer.alignment(*align, false));
+            }
+
+            if let Some(subalign) = segment
+                .sections_subalign
+                .get(section)
+                .or(segment.subalign.as_ref())
+            {
+                line += &format!(" SUBALIGN({})", self.buffer.alignment(*subalign, false));
             }
 
             self.buffer.writeln(&line);
             self.buffer.begin_block();
 
-            if let Some(fill_value) = segment.fill_value {
-                self.buffer.writeln(&format!("FILL(0x{:08X});", fill_value));
+            self.current_fill_value = None;
+            if let Some(fill_value) = Self::section_fill_value(segment, section) {
+                self.write_fill(fill_value);
             }
 
+            let files_position = self.buffer.position();
             self.emit_section(segment, section, sections)?;
 
+            if !self.buffer.written_since(files_position)
+                && self.should_skip_empty_section(segment, section)
+            {
+                self.buffer.rollback(position);
+                continue;
+            }
+
             self.buffer.end_block();
             self.write_section_symbol_end(segment, section);
 
-            if i + 1 < sections.len() {
-                self.buffer.write_empty_line();
-            }
+            need_ln = true;
         }
 
         self.write_sections_kind_end(segment, noload);
@@ -1095,3 +4731,270 @@ impl LinkerWriter<'_> {
         Ok(())
     }
 }
+
+/// The origin of the symbols generated for the given setting.
+fn settings_origin(field: &str) -> SymbolOrigin {
+    SymbolOrigin {
+        entry: EntryPath {
+            components: vec![EntryPathComponent::Settings],
+        },
+        field: Some(field.to_string()),
+    }
+}
+
+/// The origin of the symbols generated for the given vram class.
+fn vram_class_origin(d: &Document, vram_class_name: &str) -> SymbolOrigin {
+    let index = d
+        .vram_classes
+        .iter()
+        .position(|x| x.name == vram_class_name)
+        .unwrap_or_default();
+
+    SymbolOrigin {
+        entry: EntryPath {
+            components: vec![EntryPathComponent::VramClass {
+                index,
+                name: vram_class_name.to_string(),
+            }],
+        },
+        field: None,
+    }
+}
+
+/// The origin of an alias declared by the given symbol assignment.
+fn symbol_assignment_origin(d: &Document, symbol_assignment: &SymbolAssignment) -> SymbolOrigin {
+    let name = symbol_assignment.name.clone();
+    let component = match d
+        .post_sections_symbols
+        .iter()
+        .position(|x| x == symbol_assignment)
+    {
+        Some(index) => EntryPathComponent::PostSectionsSymbol { index, name },
+        None => EntryPathComponent::SymbolAssignment {
+            index: d
+                .symbol_assignments
+                .iter()
+                .position(|x| x == symbol_assignment)
+                .unwrap_or_default(),
+            name,
+        },
+    };
+
+    SymbolOrigin {
+        entry: EntryPath {
+            components: vec![component],
+        },
+        field: Some("alias_of".to_string()),
+    }
+}
+
+impl LinkerWriter<'_> {
+    fn fingerprint(&self) -> Result<Option<&str>, SlinkyError> {
+        match &self.fingerprint {
+            None => Ok(None),
+            Some(Ok(fingerprint)) => Ok(Some(fingerprint)),
+            Some(Err(e)) => Err(e.clone()),
+        }
+    }
+
+    /// Writes the fingerprint comment if `emit_fingerprint` is enabled, using
+    /// the comment syntax of the generated file.
+    fn write_fingerprint(
+        &self,
+        dst: &mut impl Write,
+        comment_start: &str,
+        comment_end: &str,
+    ) -> Result<(), SlinkyError> {
+        let fingerprint = match self.fingerprint()? {
+            Some(fingerprint) => fingerprint,
+            None => return Ok(()),
+        };
+
+        let comment = format!(
+            "{}{}{}{}",
+            comment_start,
+            fingerprint::FINGERPRINT_MARKER,
+            fingerprint,
+            comment_end
+        );
+
+        if let Err(e) = write!(dst, "{}\n\n", comment) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: comment,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the `{version}` placeholder of a banner template.
+///
+/// `None` if the template is unset or if version comments are disabled.
+fn expand_banner(rs: &RuntimeSettings, template: &Option<String>) -> Option<String> {
+    if !rs.emit_version_comment() {
+        return None;
+    }
+
+    template.as_ref().map(|x| {
+        let banner = x.replace("{version}", &version::Version::current().to_string());
+        // Only look at the clock if asked to
+        if banner.contains("{date}") {
+            banner.replace("{date}", &rs.banner_date())
+        } else {
+            banner
+        }
+    })
+}
+
+/// The symbol defined by the linker for a section of an `OVERLAY`, which
+/// drops every character of the section name not valid on a C identifier.
+fn overlay_load_symbol(prefix: &str, segment_name: &str) -> String {
+    let name: String = segment_name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+
+    format!("{}{}", prefix, name)
+}
+
+/// Replaces every character that can't be used on a symbol name with an
+/// underscore, the same way objcopy does for binary inputs.
+fn mangle_binary_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// The name used on the per-file symbols of `pad_to_size` and
+/// `forbid_sections`.
+///
+/// Archive members are named after the member instead, since multiple members
+/// of the same archive may be listed.
+fn file_symbol_name(file: &FileInfo) -> String {
+    let name_path = match file.kind {
+        FileKind::Archive if file.subfile != "*" => Path::new(&file.subfile),
+        _ => file.path.as_path(),
+    };
+    mangle_binary_name(
+        &name_path
+            .file_stem()
+            .map_or(String::new(), |x| x.to_string_lossy().to_string()),
+    )
+}
+
+/// The patterns of `section_rename` placed within the given section, sorted
+/// so the output does not depend on the order of the map.
+fn renamed_patterns<'f>(file: &'f FileInfo, section: &str) -> Vec<&'f str> {
+    let mut patterns: Vec<&str> = file
+        .section_rename
+        .iter()
+        .filter(|(_, target)| *target == section)
+        .map(|(pattern, _)| pattern.as_str())
+        .collect();
+    patterns.sort_unstable();
+    patterns
+}
+
+/// The name used on the symbols of `emit_file_symbols`.
+///
+/// The whole path of the file is used, without its extension, so files with
+/// the same name on different directories don't clash. Archive members are
+/// named after both the archive and the member.
+fn file_path_symbol_name(file: &FileInfo, path: &Path) -> String {
+    let mut name = path.with_extension("").to_string_lossy().to_string();
+    if file.kind == FileKind::Archive && file.subfile != "*" {
+        let member = Path::new(&file.subfile).with_extension("");
+        name.push('_');
+        name.push_str(&member.to_string_lossy());
+    }
+    mangle_binary_name(&name)
+}
+
+/// Escapes the characters which have a special meaning on the paths of a
+/// Ninja build statement.
+fn ninja_escape(path: &str) -> String {
+    path.replace('$', "$$")
+        .replace(' ', "$ ")
+        .replace(':', "$:")
+}
+
+/// Checks if the linker script fragment assigns a value to the given symbol,
+/// either directly or through `PROVIDE` and family.
+fn defines_symbol(contents: &str, symbol: &str) -> bool {
+    contents.match_indices(symbol).any(|(index, _)| {
+        let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+
+        if contents[..index]
+            .chars()
+            .next_back()
+            .map_or(false, is_identifier)
+        {
+            return false;
+        }
+
+        let rest = contents[index + symbol.len()..].trim_start();
+        rest.starts_with('=') && !rest.starts_with("==")
+    })
+}
+
+/// Returns the input files referenced by a line of a generated linker script.
+fn referenced_paths(line: &str) -> Vec<EscapedPath> {
+    let line = line.trim();
+
+    // Libraries referenced by name are looked up by the linker on the search
+    // paths, so only the explicit paths are dependencies
+    if let Some(inputs) = line
+        .strip_prefix("INPUT(")
+        .or_else(|| line.strip_prefix("GROUP("))
+    {
+        return inputs
+            .trim_end_matches(';')
+            .trim_end_matches(')')
+            .split_whitespace()
+            .filter(|x| !x.starts_with("-l"))
+            .map(|x| EscapedPath::from(x.to_string()))
+            .collect();
+    }
+
+    referenced_path(line).into_iter().collect()
+}
+
+fn referenced_path(line: &str) -> Option<EscapedPath> {
+    if let Some(path) = line.strip_prefix("STARTUP(") {
+        return path
+            .strip_suffix(");")
+            .map(|x| EscapedPath::from(x.to_string()));
+    }
+    if let Some(path) = line.strip_prefix("INCLUDE \"") {
+        return path
+            .strip_suffix('"')
+            .map(|x| EscapedPath::from(x.to_string()));
+    }
+
+    // Symbol assignments may reference sections, but never files
+    if line.contains('=') {
+        return None;
+    }
+
+    let line = line.strip_prefix("KEEP(").unwrap_or(line);
+    let (path, _) = line.split_once('(')?;
+
+    // Output section headers, wildcards and commands like `FILL` or `ENTRY`
+    if path.is_empty()
+        || path.starts_with('*')
+        || path.contains(char::is_whitespace)
+        || path.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+    {
+        return None;
+    }
+
+    // Drop the member of archives
+    let path = match path.rsplit_once(':') {
+        Some((archive, _member)) => archive,
+        None => path,
+    };
+
+    Some(EscapedPath::from(path.to_string()))
+}
@@ -0,0 +1,434 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{collections::HashMap, fmt, path::Path};
+
+use crate::{
+    utils, AssertEntry, Document, EscapedPath, FileInfo, FileKind, RuntimeSettings, Segment,
+    SlinkyError,
+};
+
+/// A problem found while cross-checking a map file against the document.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapIssue {
+    /// The output section of the segment is not present on the map file.
+    MissingSegment { segment: String },
+    /// None of the sections of this file ended up on the final build.
+    ///
+    /// `file` uses the same syntax used on the linker script, so archive
+    /// members are written as `path/to/lib.a:member.o`.
+    FileWithoutSections { segment: String, file: String },
+    /// The linker placed the segment on a different address than its
+    /// `fixed_vram`.
+    FixedVramNotHonored {
+        segment: String,
+        expected: u64,
+        actual: u64,
+    },
+    /// The rom ranges of both segments overlap.
+    RomOverlap {
+        segment: String,
+        other_segment: String,
+    },
+    /// The check of a `warning` assert failed.
+    AssertWarning { index: usize, message: String },
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::MissingSegment { segment } => {
+                write!(f, "Segment '{}' is not present on the map file", segment)
+            }
+            MapIssue::FileWithoutSections { segment, file } => write!(
+                f,
+                "File '{}' of segment '{}' did not contribute any section",
+                file, segment
+            ),
+            MapIssue::FixedVramNotHonored {
+                segment,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Segment '{}' was expected at 0x{:08X}, but it was placed at 0x{:08X}",
+                segment, expected, actual
+            ),
+            MapIssue::RomOverlap {
+                segment,
+                other_segment,
+            } => write!(
+                f,
+                "The rom of segment '{}' overlaps with the rom of segment '{}'",
+                segment, other_segment
+            ),
+            MapIssue::AssertWarning { index, message } => {
+                write!(f, "Warning on assert index {}: {}", index, message)
+            }
+        }
+    }
+}
+
+/// The result of cross-checking a map file against the document.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct MapReport {
+    pub issues: Vec<MapIssue>,
+}
+
+impl MapReport {
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Parses the map file produced by GNU ld when linking with a generated
+/// linker script and checks the linker did what the document asked for.
+///
+/// The following is checked:
+/// - Every emitted segment is present on the map.
+/// - Every listed object or archive member contributed at least one non empty
+///   section to its segment.
+/// - Segments with a `fixed_vram` were placed at that address.
+/// - The rom of the segments does not overlap. Vram overlaps are not checked
+///   since overlays are expected to share the same vram.
+/// - The checks of the `warning` asserts passed.
+pub struct MapVerifier<'a> {
+    d: &'a Document,
+    rs: &'a RuntimeSettings,
+}
+
+impl<'a> MapVerifier<'a> {
+    pub fn new(d: &'a Document, rs: &'a RuntimeSettings) -> Self {
+        Self { d, rs }
+    }
+
+    pub fn verify_map_file(&self, path: &Path) -> Result<MapReport, SlinkyError> {
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                })
+            }
+        };
+
+        self.verify_map_str(&contents)
+    }
+
+    pub fn verify_map_str(&self, contents: &str) -> Result<MapReport, SlinkyError> {
+        let map = MapFile::parse(contents);
+        let mut report = MapReport::default();
+
+        let segments: Vec<&Segment> = self
+            .d
+            .segments
+            .iter()
+            .filter(|segment| {
+                self.rs.should_emit_entry(
+                    &segment.exclude_if_any,
+                    &segment.exclude_if_all,
+                    &segment.include_if_any,
+                    &segment.include_if_all,
+                )
+            })
+            .collect();
+
+        let mut rom_ranges = Vec::new();
+
+        for segment in &segments {
+            // Single segment mode does not emit a section per segment
+            let output_sections: Vec<&OutputSection> = if self.d.settings.single_segment_mode {
+                map.sections.iter().collect()
+            } else {
+                let alloc_name = format!(".{}", segment.name);
+                let noload_name = format!(".{}.noload", segment.name);
+
+                let alloc = map.sections.iter().find(|x| x.name == alloc_name);
+                match alloc {
+                    None => report.issues.push(MapIssue::MissingSegment {
+                        segment: segment.name.clone(),
+                    }),
+                    Some(alloc) => {
+                        if let Some(fixed_vram) = segment.fixed_vram {
+                            // 32 bits addresses may be sign extended on 64 bits maps
+                            if alloc.vram != fixed_vram && alloc.vram & 0xFFFFFFFF != fixed_vram {
+                                report.issues.push(MapIssue::FixedVramNotHonored {
+                                    segment: segment.name.clone(),
+                                    expected: fixed_vram,
+                                    actual: alloc.vram,
+                                });
+                            }
+                        }
+                        if alloc.size != 0 {
+                            rom_ranges.push((&segment.name, alloc.rom, alloc.rom + alloc.size));
+                        }
+                    }
+                }
+
+                map.sections
+                    .iter()
+                    .filter(|x| x.name == alloc_name || x.name == noload_name)
+                    .collect()
+            };
+
+            let mut base_path = self.d.settings.base_path_escaped(self.rs)?;
+            base_path.push(segment.dir_escaped(self.rs)?);
+
+            let mut files = Vec::new();
+            self.collect_files(&segment.files, &base_path, &mut files)?;
+
+            for (file, map_name) in files {
+                let contributed = output_sections.iter().any(|section| {
+                    section
+                        .inputs
+                        .iter()
+                        .any(|input| input.size != 0 && map_name.matches(&input.file))
+                });
+
+                if !contributed {
+                    report.issues.push(MapIssue::FileWithoutSections {
+                        segment: segment.name.clone(),
+                        file,
+                    });
+                }
+            }
+        }
+
+        rom_ranges.sort_by_key(|(_, start, _)| *start);
+        for pair in rom_ranges.windows(2) {
+            let (prev_name, _, prev_end) = pair[0];
+            let (name, start, _) = pair[1];
+
+            if start < prev_end {
+                report.issues.push(MapIssue::RomOverlap {
+                    segment: name.clone(),
+                    other_segment: prev_name.clone(),
+                });
+            }
+        }
+
+        // The `post_sections_asserts` are numbered after the `asserts`
+        let asserts = self.d.asserts.iter().chain(&self.d.post_sections_asserts);
+        for (index, assert_entry) in asserts.enumerate() {
+            if !assert_entry.warning
+                || !self.rs.should_emit_entry(
+                    &assert_entry.exclude_if_any,
+                    &assert_entry.exclude_if_all,
+                    &assert_entry.include_if_any,
+                    &assert_entry.include_if_all,
+                )
+            {
+                continue;
+            }
+
+            let symbol = AssertEntry::warning_symbol(index);
+            if map.symbols.get(&symbol).map_or(false, |x| *x != 0) {
+                report.issues.push(MapIssue::AssertWarning {
+                    index,
+                    message: assert_entry.error_message.clone(),
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn collect_files(
+        &self,
+        files: &[FileInfo],
+        base_path: &EscapedPath,
+        out: &mut Vec<(String, MapFileName)>,
+    ) -> Result<(), SlinkyError> {
+        for file in files {
+            if !self.rs.should_emit_entry(
+                &file.exclude_if_any,
+                &file.exclude_if_all,
+                &file.include_if_any,
+                &file.include_if_all,
+            ) {
+                continue;
+            }
+
+            match file.kind {
+                FileKind::Object => {
+                    let mut path = base_path.clone();
+                    path.push(file.path_escaped(self.rs)?);
+
+                    out.push((path.to_string(), MapFileName::Object(path.to_string())));
+                }
+                FileKind::Binary => {
+                    let mut path = base_path.clone();
+                    path.push(file.path_escaped(self.rs)?);
+
+                    // The blob is linked through the object generated by objcopy
+                    let object = format!("{}.o", path);
+                    out.push((object.clone(), MapFileName::Object(object)));
+                }
+                FileKind::Archive => {
+                    let mut path = base_path.clone();
+                    path.push(file.path_escaped(self.rs)?);
+
+                    let member = if file.subfile.is_empty() || file.subfile == "*" {
+                        None
+                    } else {
+                        Some(file.subfile.clone())
+                    };
+                    out.push((
+                        format!("{}:{}", path, file.subfile),
+                        MapFileName::Archive(path.to_string(), member),
+                    ));
+                }
+                FileKind::Group => {
+                    let mut new_base_path = base_path.clone();
+                    new_base_path.push(file.dir_escaped(self.rs)?);
+
+                    self.collect_files(&file.files, &new_base_path, out)?;
+                }
+                FileKind::Pad | FileKind::LinkerOffset | FileKind::Align => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// How a file of the document is named on the map file.
+enum MapFileName {
+    Object(String),
+    // GNU ld names archive members as `path/to/lib.a(member.o)`
+    Archive(String, Option<String>),
+}
+
+impl MapFileName {
+    fn matches(&self, map_file: &str) -> bool {
+        match self {
+            MapFileName::Object(path) => map_file == path,
+            MapFileName::Archive(path, member) => {
+                let rest = match map_file
+                    .strip_prefix(path.as_str())
+                    .and_then(|x| x.strip_prefix('('))
+                    .and_then(|x| x.strip_suffix(')'))
+                {
+                    Some(rest) => rest,
+                    None => return false,
+                };
+
+                match member {
+                    Some(member) => rest == member,
+                    None => true,
+                }
+            }
+        }
+    }
+}
+
+struct InputSection {
+    size: u64,
+    file: String,
+}
+
+pub(crate) struct OutputSection {
+    pub name: String,
+    pub vram: u64,
+    pub rom: u64,
+    pub size: u64,
+    inputs: Vec<InputSection>,
+}
+
+pub(crate) struct MapFile {
+    pub sections: Vec<OutputSection>,
+    /// The last value assigned to each symbol assignment of the linker script.
+    pub symbols: HashMap<String, u64>,
+}
+
+impl MapFile {
+    pub fn parse(contents: &str) -> Self {
+        // Only the memory map part is relevant, the rest of the file lists
+        // archive members, discarded sections, etc.
+        let memory_map = match contents.find("Linker script and memory map") {
+            Some(start) => &contents[start..],
+            None => contents,
+        };
+
+        let mut sections: Vec<OutputSection> = Vec::new();
+        let mut symbols = HashMap::new();
+        let mut lines = memory_map.lines().skip(1).peekable();
+
+        while let Some(line) = lines.next() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut tokens: Vec<&str> = line.split_whitespace().collect();
+            let is_output_section = !line.starts_with(' ');
+            let is_input_section = !is_output_section && !line.starts_with("  ");
+
+            if !is_output_section && !is_input_section {
+                // Symbol assignments and symbols of the input sections
+                if let [value, name, "=", ..] = tokens[..] {
+                    if let Some(value) = parse_hex(value) {
+                        symbols.insert(name.to_string(), value);
+                    }
+                }
+                continue;
+            }
+
+            // Long section names push the rest of the information to the next line
+            if tokens.len() == 1 {
+                if let Some(next) = lines.peek() {
+                    let next_tokens: Vec<&str> = next.split_whitespace().collect();
+                    if next.starts_with("  ")
+                        && next_tokens.len() >= 2
+                        && parse_hex(next_tokens[0]).is_some()
+                        && parse_hex(next_tokens[1]).is_some()
+                    {
+                        tokens.extend(next_tokens);
+                        lines.next();
+                    }
+                }
+            }
+
+            if tokens.len() < 3 {
+                continue;
+            }
+            let (address, size) = match (parse_hex(tokens[1]), parse_hex(tokens[2])) {
+                (Some(address), Some(size)) => (address, size),
+                _ => continue,
+            };
+
+            if is_output_section {
+                let rom = match tokens[3..] {
+                    ["load", "address", load_address, ..] => {
+                        parse_hex(load_address).unwrap_or(address)
+                    }
+                    _ => address,
+                };
+
+                sections.push(OutputSection {
+                    name: tokens[0].to_string(),
+                    vram: address,
+                    rom,
+                    size,
+                    inputs: Vec::new(),
+                });
+            } else if tokens.len() >= 4 && tokens[0] != "*fill*" {
+                if let Some(section) = sections.last_mut() {
+                    section.inputs.push(InputSection {
+                        size,
+                        file: tokens[3..].join(" "),
+                    });
+                }
+            }
+        }
+
+        Self { sections, symbols }
+    }
+}
+
+fn parse_hex(value: &str) -> Option<u64> {
+    value
+        .strip_prefix("0x")
+        .and_then(|x| u64::from_str_radix(x, 16).ok())
+}
@@ -0,0 +1,174 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::Write;
+
+use crate::SlinkyError;
+
+/// The symbols describing where a segment was placed.
+pub(crate) struct MemoryMapSegment {
+    pub name: String,
+    pub vram_class: Option<String>,
+
+    pub rom_start: String,
+    pub rom_end: String,
+
+    /// The expression used as the address of the segment, if any.
+    pub vram_address: Option<String>,
+    pub vram_start: String,
+    pub vram_end: String,
+}
+
+pub(crate) struct MemoryMapVramClass {
+    pub name: String,
+
+    pub start: String,
+    pub end: String,
+
+    pub members: Vec<String>,
+}
+
+pub(crate) struct MemoryMap<'a> {
+    pub segments: &'a [MemoryMapSegment],
+    pub vram_classes: Vec<MemoryMapVramClass>,
+}
+
+impl MemoryMap<'_> {
+    /// Every segment and vram class is written as a row, distinguished by the
+    /// `kind` column. The rom columns are empty for vram classes.
+    pub fn write_csv(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        write_line(
+            dst,
+            "kind,name,vram_class,rom_start,rom_end,vram_address,vram_start,vram_end".to_string(),
+        )?;
+
+        for segment in self.segments {
+            write_line(
+                dst,
+                format!(
+                    "segment,{},{},{},{},{},{},{}",
+                    segment.name,
+                    segment.vram_class.as_deref().unwrap_or_default(),
+                    segment.rom_start,
+                    segment.rom_end,
+                    segment.vram_address.as_deref().unwrap_or_default(),
+                    segment.vram_start,
+                    segment.vram_end
+                ),
+            )?;
+        }
+
+        for vram_class in &self.vram_classes {
+            write_line(
+                dst,
+                format!(
+                    "vram_class,{},,,,,{},{}",
+                    vram_class.name, vram_class.start, vram_class.end
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the segments and vram classes as markdown tables.
+    pub fn write_table(&self, dst: &mut impl Write) -> Result<(), SlinkyError> {
+        write_line(dst, "# Memory map\n\n## Segments\n".to_string())?;
+
+        let segment_rows: Vec<Vec<&str>> = self
+            .segments
+            .iter()
+            .map(|x| {
+                vec![
+                    x.name.as_str(),
+                    x.vram_class.as_deref().unwrap_or_default(),
+                    &x.rom_start,
+                    &x.rom_end,
+                    x.vram_address.as_deref().unwrap_or_default(),
+                    &x.vram_start,
+                    &x.vram_end,
+                ]
+            })
+            .collect();
+        write_table(
+            dst,
+            &[
+                "Segment",
+                "Vram class",
+                "Rom start",
+                "Rom end",
+                "Vram address",
+                "Vram start",
+                "Vram end",
+            ],
+            &segment_rows,
+        )?;
+
+        if self.vram_classes.is_empty() {
+            return Ok(());
+        }
+
+        write_line(dst, "\n## Vram classes\n".to_string())?;
+
+        let members: Vec<String> = self
+            .vram_classes
+            .iter()
+            .map(|x| x.members.join(", "))
+            .collect();
+        let vram_class_rows: Vec<Vec<&str>> = self
+            .vram_classes
+            .iter()
+            .zip(&members)
+            .map(|(x, members)| vec![x.name.as_str(), &x.start, &x.end, members])
+            .collect();
+        write_table(
+            dst,
+            &["Vram class", "Start", "End", "Members"],
+            &vram_class_rows,
+        )
+    }
+}
+
+fn write_line(dst: &mut impl Write, line: String) -> Result<(), SlinkyError> {
+    if let Err(e) = writeln!(dst, "{}", line) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents: line,
+        });
+    }
+
+    Ok(())
+}
+
+/// Writes a markdown table, padding every column so it is readable as plain
+/// text too.
+fn write_table(
+    dst: &mut impl Write,
+    header: &[&str],
+    rows: &[Vec<&str>],
+) -> Result<(), SlinkyError> {
+    let mut widths: Vec<usize> = header.iter().map(|x| x.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: &[&str]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    write_line(dst, format_row(header))?;
+    let separators: Vec<String> = widths.iter().map(|x| "-".repeat(*x)).collect();
+    write_line(dst, format!("| {} |", separators.join(" | ")))?;
+    for row in rows {
+        write_line(dst, format_row(row))?;
+    }
+
+    Ok(())
+}
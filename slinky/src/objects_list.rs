@@ -0,0 +1,109 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::Write;
+
+use crate::{EscapedPath, SlinkyError};
+
+/// How the paths of an objects list are written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ObjectsListFormat {
+    /// One path per line.
+    Lines,
+    /// A single line with every path separated by a space, as expected by
+    /// the response files used by Ninja's `rspfile`. Paths containing spaces
+    /// are quoted.
+    NinjaRsp,
+}
+
+impl ObjectsListFormat {
+    /// `NinjaRsp` if the path has a `rsp` extension, `Lines` otherwise.
+    #[must_use]
+    pub fn from_path(path: &EscapedPath) -> Self {
+        let is_rsp = AsRef::<std::path::Path>::as_ref(path)
+            .extension()
+            .map_or(false, |x| x.eq_ignore_ascii_case("rsp"));
+
+        if is_rsp {
+            Self::NinjaRsp
+        } else {
+            Self::Lines
+        }
+    }
+}
+
+pub(crate) fn write_objects_list<'a>(
+    dst: &mut impl Write,
+    paths: impl IntoIterator<Item = &'a EscapedPath>,
+    format: ObjectsListFormat,
+) -> Result<(), SlinkyError> {
+    let paths: Vec<String> = paths.into_iter().map(|x| x.to_string()).collect();
+
+    let contents = match format {
+        ObjectsListFormat::Lines => paths.iter().map(|x| format!("{}\n", x)).collect(),
+        ObjectsListFormat::NinjaRsp => {
+            let quoted: Vec<String> = paths
+                .iter()
+                .map(|x| {
+                    if x.contains(' ') {
+                        format!("\"{}\"", x)
+                    } else {
+                        x.clone()
+                    }
+                })
+                .collect();
+            format!("{}\n", quoted.join(" "))
+        }
+    };
+
+    if let Err(e) = write!(dst, "{}", contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents,
+        });
+    }
+
+    Ok(())
+}
+
+/// Writes a `SLINKY_<TOOLCHAIN>_OBJECTS` Makefile variable for each
+/// toolchain. The name of the toolchain is uppercased and every character
+/// which can't be used on a variable name is replaced with an underscore.
+pub(crate) fn write_toolchain_variables(
+    dst: &mut impl Write,
+    toolchains: &[(&String, Vec<&EscapedPath>)],
+) -> Result<(), SlinkyError> {
+    let mut contents = String::new();
+
+    for (i, (toolchain, paths)) in toolchains.iter().enumerate() {
+        if i != 0 {
+            contents += "\n";
+        }
+
+        let name: String = toolchain
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+
+        contents += &format!("SLINKY_{}_OBJECTS :=", name);
+        for path in paths {
+            contents += &format!(" \\\n    {}", path);
+        }
+        contents += "\n";
+    }
+
+    if let Err(e) = write!(dst, "{}", contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents,
+        });
+    }
+
+    Ok(())
+}
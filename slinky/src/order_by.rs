@@ -0,0 +1,15 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{Deserialize, Serialize};
+
+/// How the input sections placed on a section are ordered.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBy {
+    /// The patterns are wrapped in `SORT_BY_ALIGNMENT`, so the linker places
+    /// the most aligned input sections first, and the files with a known
+    /// `alignment` for the section are emitted from the most aligned to the
+    /// least aligned one.
+    AlignmentDesc,
+}
@@ -2,8 +2,9 @@
 /* SPDX-License-Identifier: MIT */
 
 use crate::{
-    AssertEntry, Document, EscapedPath, FileInfo, LinkerWriter, RequiredSymbol, RuntimeSettings,
-    ScriptExporter, ScriptGenerator, ScriptImporter, Segment, SlinkyError, SymbolAssignment,
+    segment, utils, AssertEntry, Document, EscapedPath, FileInfo, GenerationStatistics, Import,
+    LinkerWriter, RawBlock, RequiredSymbol, RuntimeSettings, ScriptExporter, ScriptGenerator,
+    ScriptImporter, Segment, SlinkyError, SymbolAssignment, WrittenFile,
 };
 
 pub struct PartialLinkerWriter<'a> {
@@ -29,47 +30,91 @@ impl<'a> PartialLinkerWriter<'a> {
 }
 
 impl ScriptImporter for PartialLinkerWriter<'_> {
+    fn add_all_imports(&mut self, imports: &[Import]) -> Result<(), SlinkyError> {
+        self.main_writer.add_all_imports(imports)
+    }
+
+    fn add_all_raw_blocks(&mut self, raw_blocks: &[RawBlock]) -> Result<(), SlinkyError> {
+        self.main_writer.add_all_raw_blocks(raw_blocks)
+    }
+
     fn add_all_segments(&mut self, segments: &[Segment]) -> Result<(), SlinkyError> {
-        let partial_build_segments_folder = match &self.d.settings.partial_build_segments_folder {
-            Some(p) => p,
-            None => {
-                return Err(SlinkyError::MissingRequiredField {
-                    name: "partial_build_segments_folder".to_string(),
-                })
-            }
-        };
+        if self.d.settings.partial_build_segments_folder.is_none()
+            && self.d.settings.partial_build_path.is_none()
+        {
+            return Err(SlinkyError::MissingAnyOfOptionalFields {
+                fields: "'partial_build_segments_folder', 'partial_build_path'".to_string(),
+            });
+        }
 
         self.main_writer.begin_sections()?;
 
-        self.partial_writers.reserve(segments.len());
-        for segment in segments {
-            if !self.rs.should_emit_entry(
-                &segment.exclude_if_any,
-                &segment.exclude_if_all,
-                &segment.include_if_any,
-                &segment.include_if_all,
-            ) {
-                continue;
-            }
-
-            let mut partial_writer = LinkerWriter::new(self.d, self.rs);
+        let segments = segment::segments_for_flavor(segments, self.rs.flavor());
+        let segments: Vec<&Segment> = segments
+            .iter()
+            .filter(|segment| {
+                self.rs.should_emit_entry(
+                    &segment.exclude_if_any,
+                    &segment.exclude_if_all,
+                    &segment.include_if_any,
+                    &segment.include_if_all,
+                )
+            })
+            .collect();
+
+        // Handwritten segments are not partially linked
+        let partial_segments: Vec<&Segment> =
+            segments.iter().copied().filter(|x| !x.external).collect();
+
+        // Each partial script only depends on its own segment, so they are
+        // generated concurrently. The results keep the order of the segments,
+        // so the first error of the document is the one reported.
+        let (d, rs) = (self.d, self.rs);
+        let partial_writers = utils::parallel_map(&partial_segments, |segment| {
+            let mut partial_writer = LinkerWriter::new(d, rs);
 
             partial_writer.set_emit_sections_kind_symbols(false);
             partial_writer.set_emit_section_symbols(false);
 
-            partial_writer.add_single_segment(segment)?;
+            partial_writer
+                .add_single_segment(segment)
+                .map(|_| (partial_writer, segment.name.clone()))
+        });
+
+        let partial_scripts_folder = self.d.settings.partial_scripts_folder_escaped(self.rs)?;
+
+        self.partial_writers.reserve(partial_writers.len());
+        for partial_writer in partial_writers {
+            let (partial_writer, name) = partial_writer?;
+
+            if let Some(folder) = &partial_scripts_folder {
+                let mut p = folder.clone();
+                p.push(EscapedPath::from(format!("{}.ld", name)));
+                self.main_writer.add_generated_file(p);
+            }
+
+            self.partial_writers.push((partial_writer, name));
+        }
 
-            self.partial_writers
-                .push((partial_writer, segment.name.clone()));
+        // Collected first, so the overlay groups are emitted as a whole
+        let mut main_segments = Vec::new();
 
-            let mut p = partial_build_segments_folder.clone();
+        for segment in segments {
+            if segment.external {
+                main_segments.push(segment.clone());
+                continue;
+            }
 
-            p.push(&format!("{}.o", segment.name));
+            let p = self
+                .d
+                .settings
+                .partial_build_object_path(&segment.name)
+                .expect("Checked above");
 
-            self.main_writer
-                .add_segment(&segment.clone_with_new_files(vec![FileInfo::new_object(p)]))?;
+            main_segments.push(segment.clone_with_new_files(vec![FileInfo::new_object(p)]));
         }
 
+        self.main_writer.add_segments(&main_segments)?;
         self.main_writer.end_sections()?;
 
         Ok(())
@@ -100,7 +145,58 @@ impl ScriptImporter for PartialLinkerWriter<'_> {
 }
 
 impl ScriptExporter for PartialLinkerWriter<'_> {
-    fn export_linker_script_to_file(&self, path: &EscapedPath) -> Result<(), SlinkyError> {
+    fn export_linker_script_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<Vec<WrittenFile>, SlinkyError> {
+        let mut written = self.main_writer.export_linker_script_to_file(path)?;
+
+        written.extend(self.export_partial_scripts_to_files()?);
+
+        Ok(written)
+    }
+
+    fn export_linker_script_to_string(&self) -> Result<String, SlinkyError> {
+        let mut out = Vec::new();
+
+        out.push(self.main_writer.export_linker_script_to_string()?);
+
+        for (partial, _name) in &self.partial_writers {
+            out.push(partial.export_linker_script_to_string()?);
+        }
+
+        Ok(out.join("\n"))
+    }
+
+    fn save_other_files(&self) -> Result<Vec<WrittenFile>, SlinkyError> {
+        let mut written = self.main_writer.save_other_files()?;
+
+        written.extend(self.export_partial_dependencies_files()?);
+
+        Ok(written)
+    }
+
+    fn check_dependencies(&self) -> Result<(), SlinkyError> {
+        self.main_writer.check_dependencies()?;
+
+        for (partial, _name) in &self.partial_writers {
+            partial.check_dependencies()?;
+        }
+
+        Ok(())
+    }
+
+    fn statistics(&self) -> GenerationStatistics {
+        self.main_writer.statistics()
+    }
+}
+
+impl ScriptGenerator for PartialLinkerWriter<'_> {}
+
+impl PartialLinkerWriter<'_> {
+    /// Writes the linker script of each partially linked segment to the
+    /// `partial_scripts_folder`.
+    pub fn export_partial_scripts_to_files(&self) -> Result<Vec<WrittenFile>, SlinkyError> {
         let partial_scripts_folder =
             match self.d.settings.partial_scripts_folder_escaped(self.rs)? {
                 Some(p) => p,
@@ -111,45 +207,26 @@ impl ScriptExporter for PartialLinkerWriter<'_> {
                 }
             };
 
-        self.main_writer.export_linker_script_to_file(path)?;
-
-        for (partial, name) in &self.partial_writers {
+        let results = utils::parallel_map(&self.partial_writers, |(partial, name)| {
             let mut p = partial_scripts_folder.clone();
 
             p.push(EscapedPath::from(format!("{}.ld", name)));
 
-            partial.export_linker_script_to_file(&p)?;
-        }
+            partial.export_linker_script_to_file(&p)
+        });
 
-        Ok(())
-    }
-
-    fn export_linker_script_to_string(&self) -> Result<String, SlinkyError> {
-        let mut out = Vec::new();
-
-        out.push(self.main_writer.export_linker_script_to_string()?);
-
-        for (partial, _name) in &self.partial_writers {
-            out.push(partial.export_linker_script_to_string()?);
+        let mut written = Vec::new();
+        for result in results {
+            written.extend(result?);
         }
 
-        Ok(out.join("\n"))
+        Ok(written)
     }
 
-    fn save_other_files(&self) -> Result<(), SlinkyError> {
+    /// Writes the dependency file of each partially linked segment to the
+    /// `partial_scripts_folder`, if the document sets `d_path`.
+    pub fn export_partial_dependencies_files(&self) -> Result<Vec<WrittenFile>, SlinkyError> {
         let base_path = self.d.settings.base_path_escaped(self.rs)?;
-        let partial_build_segments_folder = match self
-            .d
-            .settings
-            .partial_build_segments_folder_escaped(self.rs)?
-        {
-            Some(p) => p,
-            None => {
-                return Err(SlinkyError::MissingRequiredField {
-                    name: "partial_build_segments_folder".to_string(),
-                })
-            }
-        };
         let partial_scripts_folder =
             match self.d.settings.partial_scripts_folder_escaped(self.rs)? {
                 Some(p) => p,
@@ -160,38 +237,46 @@ impl ScriptExporter for PartialLinkerWriter<'_> {
                 }
             };
 
-        self.main_writer.save_other_files()?;
+        if self.d.settings.d_path.is_none() {
+            return Ok(Vec::new());
+        }
 
-        if self.d.settings.d_path.is_some() {
-            for (partial, name) in &self.partial_writers {
-                let mut target_path = base_path.clone();
+        let results = utils::parallel_map(&self.partial_writers, |(partial, name)| {
+            let mut target_path = base_path.clone();
 
-                target_path.extend(&partial_build_segments_folder);
-                target_path.push(EscapedPath::from(format!("{}.o", name)));
+            match self
+                .d
+                .settings
+                .partial_build_object_path_escaped(self.rs, name)?
+            {
+                Some(p) => target_path.extend(&p),
+                None => {
+                    return Err(SlinkyError::MissingAnyOfOptionalFields {
+                        fields: "'partial_build_segments_folder', 'partial_build_path'".to_string(),
+                    })
+                }
+            }
 
-                let mut d_path = partial_scripts_folder.clone();
+            let mut d_path = partial_scripts_folder.clone();
 
-                d_path.push(EscapedPath::from(format!("{}.d", name)));
+            d_path.push(EscapedPath::from(format!("{}.d", name)));
 
-                partial.export_dependencies_file_to_file(&d_path, &target_path)?;
-            }
-        }
+            partial.export_dependencies_file_to_file(&d_path, &target_path)
+        });
 
-        Ok(())
+        results.into_iter().collect()
     }
 }
 
-impl ScriptGenerator for PartialLinkerWriter<'_> {}
-
 // Getters / Setters
 impl PartialLinkerWriter<'_> {
     #[must_use]
-    pub fn get_main_writer(&self) -> &LinkerWriter {
+    pub fn get_main_writer(&self) -> &LinkerWriter<'_> {
         &self.main_writer
     }
 
     #[must_use]
-    pub fn get_partial_writers(&self) -> &Vec<(LinkerWriter, String)> {
+    pub fn get_partial_writers(&self) -> &Vec<(LinkerWriter<'_>, String)> {
         &self.partial_writers
     }
 }
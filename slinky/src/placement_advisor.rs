@@ -0,0 +1,201 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{fmt, path::Path};
+
+use crate::{map_verifier::MapFile, utils, Document, RuntimeSettings, SlinkyError};
+
+/// How much memory a vram class used on the final build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VramClassUsage {
+    pub vram_class: String,
+    /// The size of the biggest segment of this vram class.
+    pub size: u64,
+    /// The `max_size` of the vram class minus its `reserved` bytes.
+    pub max_size: Option<u32>,
+}
+
+impl VramClassUsage {
+    #[must_use]
+    pub fn is_over_budget(&self) -> bool {
+        self.max_size
+            .map_or(false, |max_size| self.size > max_size as u64)
+    }
+}
+
+/// Moving `segment` from `from_class` to `to_class` would help relieving
+/// `from_class`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlacementSuggestion {
+    pub segment: String,
+    pub segment_size: u64,
+
+    pub from_class: String,
+    /// How the size of `from_class` would change after the move.
+    pub from_class_delta: i64,
+
+    pub to_class: String,
+    /// How the size of `to_class` would change after the move.
+    pub to_class_delta: i64,
+}
+
+impl fmt::Display for PlacementSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Move segment '{}' ({} bytes) from '{}' ({:+}) to '{}' ({:+})",
+            self.segment,
+            self.segment_size,
+            self.from_class,
+            self.from_class_delta,
+            self.to_class,
+            self.to_class_delta
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PlacementReport {
+    /// The usage of every vram class of the document, in the same order.
+    pub usages: Vec<VramClassUsage>,
+    /// The candidate moves for every over budget vram class, sorted by how
+    /// much they would grow the destination vram class.
+    pub suggestions: Vec<PlacementSuggestion>,
+}
+
+/// Uses the map file produced by GNU ld to measure the vram classes of the
+/// document and suggests which segments could be moved between vram classes
+/// to get every vram class under its `max_size`.
+///
+/// Only the segments that are bigger than the budget of their vram class are
+/// considered, since they are the ones that make the class go over budget.
+/// A vram class is a candidate destination only if the segment fits on its
+/// `max_size`, or if it does not have a `max_size` at all.
+pub struct PlacementAdvisor<'a> {
+    d: &'a Document,
+    rs: &'a RuntimeSettings,
+}
+
+impl<'a> PlacementAdvisor<'a> {
+    pub fn new(d: &'a Document, rs: &'a RuntimeSettings) -> Self {
+        Self { d, rs }
+    }
+
+    pub fn suggest_from_map_file(&self, path: &Path) -> Result<PlacementReport, SlinkyError> {
+        let contents = match utils::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                return Err(SlinkyError::FailedFileOpen {
+                    path: path.to_path_buf(),
+                    description: e.to_string(),
+                })
+            }
+        };
+
+        Ok(self.suggest_from_map_str(&contents))
+    }
+
+    pub fn suggest_from_map_str(&self, contents: &str) -> PlacementReport {
+        let map = MapFile::parse(contents);
+
+        // (segment, vram class, size)
+        let mut members: Vec<(&str, &str, u64)> = Vec::new();
+        for segment in &self.d.segments {
+            if !self.rs.should_emit_entry(
+                &segment.exclude_if_any,
+                &segment.exclude_if_all,
+                &segment.include_if_any,
+                &segment.include_if_all,
+            ) {
+                continue;
+            }
+
+            if let Some(vram_class) = &segment.vram_class {
+                let alloc_name = format!(".{}", segment.name);
+                let noload_name = format!(".{}.noload", segment.name);
+
+                let size = map
+                    .sections
+                    .iter()
+                    .filter(|x| x.name == alloc_name || x.name == noload_name)
+                    .map(|x| x.size)
+                    .sum();
+
+                members.push((&segment.name, vram_class, size));
+            }
+        }
+
+        let class_size = |vram_class: &str, excluded_segment: Option<&str>| -> u64 {
+            members
+                .iter()
+                .filter(|(segment, class, _)| {
+                    *class == vram_class && Some(*segment) != excluded_segment
+                })
+                .map(|(_, _, size)| *size)
+                .max()
+                .unwrap_or(0)
+        };
+
+        let usages: Vec<VramClassUsage> = self
+            .d
+            .vram_classes
+            .iter()
+            .map(|vram_class| VramClassUsage {
+                vram_class: vram_class.name.clone(),
+                size: class_size(&vram_class.name, None),
+                max_size: vram_class
+                    .max_size
+                    .map(|max_size| max_size - vram_class.reserved.unwrap_or(0)),
+            })
+            .collect();
+
+        let mut suggestions = Vec::new();
+        for usage in usages.iter().filter(|x| x.is_over_budget()) {
+            let max_size = usage.max_size.unwrap_or_default() as u64;
+            let mut candidates = Vec::new();
+
+            for (segment, _, size) in members
+                .iter()
+                .filter(|(_, class, size)| *class == usage.vram_class && *size > max_size)
+            {
+                let from_class_delta =
+                    class_size(&usage.vram_class, Some(segment)) as i64 - usage.size as i64;
+
+                for other in &usages {
+                    if other.vram_class == usage.vram_class {
+                        continue;
+                    }
+                    if other
+                        .max_size
+                        .map_or(false, |other_max| *size > other_max as u64)
+                    {
+                        continue;
+                    }
+
+                    candidates.push(PlacementSuggestion {
+                        segment: segment.to_string(),
+                        segment_size: *size,
+                        from_class: usage.vram_class.clone(),
+                        from_class_delta,
+                        to_class: other.vram_class.clone(),
+                        to_class_delta: other.size.max(*size) as i64 - other.size as i64,
+                    });
+                }
+            }
+
+            candidates.sort_by(|a, b| {
+                (a.to_class_delta, &a.segment, &a.to_class).cmp(&(
+                    b.to_class_delta,
+                    &b.segment,
+                    &b.to_class,
+                ))
+            });
+            suggestions.extend(candidates);
+        }
+
+        PlacementReport {
+            usages,
+            suggestions,
+        }
+    }
+}
@@ -0,0 +1,45 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{Deserialize, Serialize};
+
+use crate::Settings;
+
+/// A set of defaults for the settings, tailored to a given platform.
+///
+/// Presets only change the default values, so every setting can still be
+/// overridden on the document.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    N64,
+}
+
+impl Preset {
+    /// Returns the settings used when the document doesn't specify a value.
+    pub fn default_settings(&self) -> Settings {
+        let mut settings = Settings {
+            preset: Some(*self),
+            ..Default::default()
+        };
+
+        match self {
+            Preset::N64 => {
+                // The PI DMA engine transfers data in 16 bytes chunks
+                settings.segment_start_align = Some(0x10);
+                settings.segment_end_align = Some(0x10);
+
+                settings
+                    .sections_denylist
+                    .extend([".mdebug.*".to_string(), ".gptab.*".to_string()]);
+
+                settings.rom_size_alignment = Some(0x10);
+                // The boot code checksums the first MiB of the rom after
+                // itself, so the rom must be at least that long
+                settings.rom_min_size = Some(0x101000);
+            }
+        }
+
+        settings
+    }
+}
@@ -0,0 +1,129 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, traits::Serial, Settings,
+    SlinkyError,
+};
+
+/// Where a [`RawBlock`] is placed on the generated linker script, besides
+/// after a given segment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawBlockPlacement {
+    /// Right before the `SECTIONS` block.
+    BeforeSections,
+    /// Right after the `SECTIONS` block.
+    AfterSections,
+}
+
+/// A handwritten piece of linker script, copied verbatim into the generated
+/// one.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize)]
+pub struct RawBlock {
+    pub contents: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placement: Option<RawBlockPlacement>,
+    /// The name of the segment this block is placed after, inside the
+    /// `SECTIONS` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_segment: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_if_all: Vec<(String, String)>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawBlockSerial {
+    pub contents: String,
+
+    #[serde(default)]
+    pub placement: AbsentNullable<RawBlockPlacement>,
+    #[serde(default)]
+    pub after_segment: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub include_if_any: AbsentNullable<Vec<(String, String)>>,
+    #[serde(default)]
+    pub include_if_all: AbsentNullable<Vec<(String, String)>>,
+    #[serde(default)]
+    pub exclude_if_any: AbsentNullable<Vec<(String, String)>>,
+    #[serde(default)]
+    pub exclude_if_all: AbsentNullable<Vec<(String, String)>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl Serial for RawBlockSerial {
+    type Output = RawBlock;
+
+    fn unserialize(self, settings: &Settings) -> Result<Self::Output, SlinkyError> {
+        if self.contents.trim().is_empty() {
+            return Err(SlinkyError::EmptyValue {
+                name: "contents".to_string(),
+            });
+        }
+        let contents = self.contents;
+
+        let placement = self.placement.get_non_null_no_default("placement")?;
+        let after_segment = self
+            .after_segment
+            .get_non_null_no_default("after_segment")?;
+
+        match (&placement, &after_segment) {
+            (Some(_), Some(_)) => {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "placement".into(),
+                    field2: "after_segment".into(),
+                })
+            }
+            (None, None) => {
+                return Err(SlinkyError::MissingAnyOfOptionalFields {
+                    fields: "'placement', 'after_segment'".into(),
+                })
+            }
+            _ => {}
+        }
+
+        let include_if_any = self
+            .include_if_any
+            .get_non_null_not_empty("include_if_any", Vec::new)?;
+        let include_if_all = self
+            .include_if_all
+            .get_non_null_not_empty("include_if_all", Vec::new)?;
+        let exclude_if_any = self
+            .exclude_if_any
+            .get_non_null_not_empty("exclude_if_any", Vec::new)?;
+        let exclude_if_all = self
+            .exclude_if_all
+            .get_non_null_not_empty("exclude_if_all", Vec::new)?;
+
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
+        Ok(Self::Output {
+            contents,
+            placement,
+            after_segment,
+            include_if_any,
+            include_if_all,
+            exclude_if_any,
+            exclude_if_all,
+        })
+    }
+}
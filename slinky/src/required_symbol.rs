@@ -1,18 +1,41 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{absent_nullable::AbsentNullable, traits::Serial, Settings, SlinkyError};
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, traits::Serial, Settings,
+    SlinkyError,
+};
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
 pub struct RequiredSymbol {
     /// Name of the symbol
     pub name: String,
 
+    /// The address the symbol must have, checked with an `ASSERT`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_value: Option<u64>,
+    /// The `[start, end)` range the address of the symbol must be in, checked
+    /// with an `ASSERT`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_range: Option<(u64, u64)>,
+    /// Value given to the symbol through a `PROVIDE` if no linked file
+    /// defines it, instead of failing the link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provide_fallback: Option<String>,
+
+    /// Written as a comment next to the symbol on the linker script.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_all: Vec<(String, String)>,
 }
 
@@ -21,6 +44,16 @@ pub struct RequiredSymbol {
 pub(crate) struct RequiredSymbolSerial {
     pub name: String,
 
+    #[serde(default)]
+    pub expected_value: AbsentNullable<u64>,
+    #[serde(default)]
+    pub expected_range: AbsentNullable<(u64, u64)>,
+    #[serde(default)]
+    pub provide_fallback: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub comment: AbsentNullable<String>,
+
     #[serde(default)]
     pub include_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
@@ -29,12 +62,15 @@ pub(crate) struct RequiredSymbolSerial {
     pub exclude_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
     pub exclude_if_all: AbsentNullable<Vec<(String, String)>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
 }
 
 impl Serial for RequiredSymbolSerial {
     type Output = RequiredSymbol;
 
-    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+    fn unserialize(self, settings: &Settings) -> Result<Self::Output, SlinkyError> {
         if self.name.is_empty() {
             return Err(SlinkyError::EmptyValue {
                 name: "name".to_string(),
@@ -42,6 +78,44 @@ impl Serial for RequiredSymbolSerial {
         }
         let name = self.name;
 
+        let expected_value = self
+            .expected_value
+            .get_non_null_no_default("expected_value")?;
+        let expected_range = self
+            .expected_range
+            .get_non_null_no_default("expected_range")?;
+        if expected_value.is_some() && expected_range.is_some() {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "expected_value".into(),
+                field2: "expected_range".into(),
+            });
+        }
+        if let Some((start, end)) = expected_range {
+            if start >= end {
+                return Err(SlinkyError::EmptyRange {
+                    name: "expected_range".to_string(),
+                    start,
+                    end,
+                });
+            }
+        }
+
+        let provide_fallback = self
+            .provide_fallback
+            .get_non_null_no_default("provide_fallback")?;
+        if provide_fallback.as_ref().map_or(false, |x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "provide_fallback".to_string(),
+            });
+        }
+
+        let comment = self.comment.get_non_null_no_default("comment")?;
+        if comment.as_ref().map_or(false, |x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "comment".to_string(),
+            });
+        }
+
         let include_if_any = self
             .include_if_any
             .get_non_null_not_empty("include_if_any", Vec::new)?;
@@ -55,8 +129,19 @@ impl Serial for RequiredSymbolSerial {
             .exclude_if_all
             .get_non_null_not_empty("exclude_if_all", Vec::new)?;
 
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
         Ok(Self::Output {
             name,
+            expected_value,
+            expected_range,
+            provide_fallback,
+            comment,
             include_if_any,
             include_if_all,
             exclude_if_any,
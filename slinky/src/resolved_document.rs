@@ -0,0 +1,273 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    AssertEntry, Document, FileInfo, RuntimeSettings, Segment, SlinkyError, SymbolAssignment,
+};
+
+/// Applies the runtime settings to the whole document, dropping every entry
+/// that would not be emitted and replacing the custom options on paths.
+///
+/// Since the conditions were already evaluated, they are cleared from every
+/// entry that was kept.
+pub(crate) fn resolve_document(
+    d: &Document,
+    rs: &RuntimeSettings,
+) -> Result<Document, SlinkyError> {
+    let mut settings = d.settings.clone();
+    settings.base_path = escape(rs, &settings.base_path)?;
+    for path in [
+        &mut settings.d_path,
+        &mut settings.target_path,
+        &mut settings.ninja_path,
+        &mut settings.symbols_header_path,
+        &mut settings.symbols_header_rom_path,
+        &mut settings.symbols_header_linker_offsets_path,
+        &mut settings.symbols_asm_path,
+        &mut settings.symbols_rust_path,
+        &mut settings.vram_class_slack_report_path,
+        &mut settings.memory_map_path,
+        &mut settings.binary_rules_path,
+        &mut settings.objects_list_path,
+        &mut settings.toolchain_objects_path,
+        &mut settings.symbols_manifest_path,
+        &mut settings.segment_table_path,
+        &mut settings.segment_table_header_path,
+        &mut settings.common_script_path,
+        &mut settings.gdb_script_path,
+        &mut settings.partial_scripts_folder,
+        &mut settings.partial_build_segments_folder,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *path = escape(rs, path)?;
+    }
+    for path in settings.search_paths.iter_mut() {
+        *path = escape(rs, path)?;
+    }
+    for library in settings.libraries.iter_mut() {
+        *library = rs.escape_path(Path::new(library))?.to_string();
+    }
+    if let Some(path) = &mut settings.partial_build_path {
+        // `{segment}` is replaced per segment when generating, not by the
+        // custom options
+        let mut template_rs = rs.clone();
+        template_rs.add_custom_options([("segment".to_string(), "{segment}".to_string())]);
+        *path = escape(&template_rs, path)?;
+    }
+
+    let segments = resolve_segments(d, rs)?;
+
+    let symbol_assignments = resolve_symbol_assignments(&d.symbol_assignments, rs);
+
+    let mut required_symbols = Vec::new();
+    for required_symbol in &d.required_symbols {
+        if rs.should_emit_entry(
+            &required_symbol.exclude_if_any,
+            &required_symbol.exclude_if_all,
+            &required_symbol.include_if_any,
+            &required_symbol.include_if_all,
+        ) {
+            let mut required_symbol = required_symbol.clone();
+            required_symbol.include_if_any.clear();
+            required_symbol.include_if_all.clear();
+            required_symbol.exclude_if_any.clear();
+            required_symbol.exclude_if_all.clear();
+            required_symbols.push(required_symbol);
+        }
+    }
+
+    let asserts = resolve_asserts(&d.asserts, rs);
+
+    let startup_file = match &d.startup_file {
+        Some(path) => Some(escape(rs, path)?),
+        None => None,
+    };
+    let mut extra_inputs = Vec::new();
+    for path in &d.extra_inputs {
+        extra_inputs.push(escape(rs, path)?);
+    }
+
+    let mut imports = Vec::new();
+    for import in &d.imports {
+        let mut import = import.clone();
+        import.path = escape(rs, &import.path)?;
+        imports.push(import);
+    }
+
+    let mut raw = Vec::new();
+    for raw_block in &d.raw {
+        if rs.should_emit_entry(
+            &raw_block.exclude_if_any,
+            &raw_block.exclude_if_all,
+            &raw_block.include_if_any,
+            &raw_block.include_if_all,
+        ) {
+            let mut raw_block = raw_block.clone();
+            raw_block.include_if_any.clear();
+            raw_block.include_if_all.clear();
+            raw_block.exclude_if_any.clear();
+            raw_block.exclude_if_all.clear();
+            raw.push(raw_block);
+        }
+    }
+
+    Ok(Document {
+        required_slinky_version: d.required_slinky_version,
+        settings,
+        vram_classes: d.vram_classes.clone(),
+        segments,
+        entry: d.entry.clone(),
+        startup_file,
+        extra_inputs,
+        symbol_assignments,
+        required_symbols,
+        asserts,
+        pre_sections_symbols: resolve_symbol_assignments(&d.pre_sections_symbols, rs),
+        post_sections_symbols: resolve_symbol_assignments(&d.post_sections_symbols, rs),
+        post_sections_asserts: resolve_asserts(&d.post_sections_asserts, rs),
+        runtime_options: d.runtime_options.clone(),
+        targets: d.targets.clone(),
+        imports,
+        raw,
+        included_files: d.included_files.clone(),
+        path: d.path.clone(),
+    })
+}
+
+fn resolve_symbol_assignments(
+    symbol_assignments: &[SymbolAssignment],
+    rs: &RuntimeSettings,
+) -> Vec<SymbolAssignment> {
+    let mut resolved = Vec::new();
+    for symbol_assignment in symbol_assignments {
+        if rs.should_emit_entry(
+            &symbol_assignment.exclude_if_any,
+            &symbol_assignment.exclude_if_all,
+            &symbol_assignment.include_if_any,
+            &symbol_assignment.include_if_all,
+        ) {
+            let mut symbol_assignment = symbol_assignment.clone();
+            symbol_assignment.include_if_any.clear();
+            symbol_assignment.include_if_all.clear();
+            symbol_assignment.exclude_if_any.clear();
+            symbol_assignment.exclude_if_all.clear();
+            resolved.push(symbol_assignment);
+        }
+    }
+    resolved
+}
+
+fn resolve_asserts(asserts: &[AssertEntry], rs: &RuntimeSettings) -> Vec<AssertEntry> {
+    let mut resolved = Vec::new();
+    for assert_entry in asserts {
+        if rs.should_emit_entry(
+            &assert_entry.exclude_if_any,
+            &assert_entry.exclude_if_all,
+            &assert_entry.include_if_any,
+            &assert_entry.include_if_all,
+        ) {
+            let mut assert_entry = assert_entry.clone();
+            assert_entry.include_if_any.clear();
+            assert_entry.include_if_all.clear();
+            assert_entry.exclude_if_any.clear();
+            assert_entry.exclude_if_all.clear();
+            resolved.push(assert_entry);
+        }
+    }
+    resolved
+}
+
+/// Applies the runtime settings to the segments of the document, like
+/// [`resolve_document`] does.
+pub(crate) fn resolve_segments(
+    d: &Document,
+    rs: &RuntimeSettings,
+) -> Result<Vec<Segment>, SlinkyError> {
+    let mut segments = Vec::new();
+
+    for segment in &d.segments {
+        if !rs.should_emit_entry(
+            &segment.exclude_if_any,
+            &segment.exclude_if_all,
+            &segment.include_if_any,
+            &segment.include_if_all,
+        ) {
+            continue;
+        }
+
+        segments.push(resolve_segment(segment, rs)?);
+    }
+
+    Ok(segments)
+}
+
+fn resolve_segment(segment: &Segment, rs: &RuntimeSettings) -> Result<Segment, SlinkyError> {
+    let mut segment = segment.clone_with_new_files(resolve_files(&segment.files, rs)?);
+
+    segment.dir = escape(rs, &segment.dir)?;
+    if let Some(fragment_path) = &mut segment.fragment_path {
+        *fragment_path = escape(rs, fragment_path)?;
+    }
+    segment.include_if_any.clear();
+    segment.include_if_all.clear();
+    segment.exclude_if_any.clear();
+    segment.exclude_if_all.clear();
+
+    segment.gp_info = match segment.gp_info.take() {
+        Some(mut gp_info) => {
+            if rs.should_emit_entry(
+                &gp_info.exclude_if_any,
+                &gp_info.exclude_if_all,
+                &gp_info.include_if_any,
+                &gp_info.include_if_all,
+            ) {
+                gp_info.include_if_any.clear();
+                gp_info.include_if_all.clear();
+                gp_info.exclude_if_any.clear();
+                gp_info.exclude_if_all.clear();
+                Some(gp_info)
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+
+    Ok(segment)
+}
+
+fn resolve_files(files: &[FileInfo], rs: &RuntimeSettings) -> Result<Vec<FileInfo>, SlinkyError> {
+    let mut resolved = Vec::new();
+
+    for file in files {
+        if !rs.should_emit_entry(
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+            &file.include_if_any,
+            &file.include_if_all,
+        ) {
+            continue;
+        }
+
+        let mut file = file.clone();
+        file.path = escape(rs, &file.path)?;
+        file.dir = escape(rs, &file.dir)?;
+        file.files = resolve_files(&file.files, rs)?;
+        file.include_if_any.clear();
+        file.include_if_all.clear();
+        file.exclude_if_any.clear();
+        file.exclude_if_all.clear();
+
+        resolved.push(file);
+    }
+
+    Ok(resolved)
+}
+
+fn escape(rs: &RuntimeSettings, path: &Path) -> Result<PathBuf, SlinkyError> {
+    Ok(rs.escape_path(path)?.0)
+}
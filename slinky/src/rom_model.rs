@@ -0,0 +1,16 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{Deserialize, Serialize};
+
+/// How the generated linker script places the segments on the binary.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RomModel {
+    /// The segments are laid out one after the other on a ROM, tracked by the
+    /// `__romPos` symbol and placed with `AT()` load addresses.
+    Rom,
+    /// There's no ROM at all, like on ELFs for PC ports or test harnesses.
+    /// Only the vram layout is emitted.
+    None,
+}
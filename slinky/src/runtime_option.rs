@@ -0,0 +1,91 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, traits::Serial, Settings,
+    SlinkyError,
+};
+
+/// Declares a custom option the document expects to receive, allowing to
+/// reject misspelled options or values instead of silently ignoring them.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct RuntimeOption {
+    pub name: String,
+
+    /// The values allowed for this option. Any value is allowed if empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Checks the given custom option is declared and its value is allowed.
+///
+/// Nothing is checked if no option is declared at all.
+pub(crate) fn check_custom_option(
+    runtime_options: &[RuntimeOption],
+    key: &str,
+    value: &str,
+) -> Result<(), SlinkyError> {
+    if runtime_options.is_empty() {
+        return Ok(());
+    }
+
+    match runtime_options.iter().find(|x| x.name == key) {
+        None => Err(SlinkyError::UnknownCustomOption {
+            option: key.to_string(),
+        }),
+        Some(option) => {
+            if option.values.is_empty() || option.values.iter().any(|x| x == value) {
+                Ok(())
+            } else {
+                Err(SlinkyError::InvalidCustomOptionValue {
+                    option: key.to_string(),
+                    value: value.to_string(),
+                    allowed: option.values.join(", "),
+                })
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RuntimeOptionSerial {
+    pub name: String,
+
+    #[serde(default)]
+    pub values: AbsentNullable<Vec<String>>,
+
+    #[serde(default)]
+    pub description: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl Serial for RuntimeOptionSerial {
+    type Output = RuntimeOption;
+
+    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+        if self.name.is_empty() {
+            return Err(SlinkyError::EmptyValue {
+                name: "name".to_string(),
+            });
+        }
+        let name = self.name;
+
+        let values = self.values.get_non_null_not_empty("values", Vec::new)?;
+
+        let description = self.description.get_non_null_no_default("description")?;
+
+        Ok(Self::Output {
+            name,
+            values,
+            description,
+        })
+    }
+}
@@ -7,13 +7,25 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{EscapedPath, SlinkyError};
+use crate::{runtime_option, utils, EscapedPath, RuntimeOption, ScriptFlavor, SlinkyError};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct RuntimeSettings {
     custom_options: HashMap<String, String>,
 
     emit_version_comment: bool,
+
+    emit_fingerprint: bool,
+
+    write_if_changed: bool,
+
+    dry_run: bool,
+
+    forward_slashes: bool,
+
+    flavor: ScriptFlavor,
+
+    source_date_epoch: Option<u64>,
 }
 
 impl Default for RuntimeSettings {
@@ -28,6 +40,20 @@ impl RuntimeSettings {
             custom_options: HashMap::new(),
 
             emit_version_comment: true,
+
+            emit_fingerprint: false,
+
+            write_if_changed: false,
+
+            dry_run: false,
+
+            forward_slashes: true,
+
+            flavor: ScriptFlavor::Matching,
+
+            source_date_epoch: std::env::var("SOURCE_DATE_EPOCH")
+                .ok()
+                .and_then(|x| x.trim().parse().ok()),
         }
     }
 
@@ -42,6 +68,24 @@ impl RuntimeSettings {
         self.custom_options.extend(others);
     }
 
+    /// Rejects any custom option not declared on `runtime_options`, or whose
+    /// value is not one of the allowed ones.
+    ///
+    /// Nothing is checked if `runtime_options` is empty.
+    pub fn check_custom_options(
+        &self,
+        runtime_options: &[RuntimeOption],
+    ) -> Result<(), SlinkyError> {
+        let mut keys: Vec<&String> = self.custom_options.keys().collect();
+        keys.sort();
+
+        for key in keys {
+            runtime_option::check_custom_option(runtime_options, key, &self.custom_options[key])?;
+        }
+
+        Ok(())
+    }
+
     pub fn emit_version_comment(&self) -> bool {
         self.emit_version_comment
     }
@@ -49,6 +93,90 @@ impl RuntimeSettings {
     pub fn set_emit_version_comment(&mut self, emit: bool) {
         self.emit_version_comment = emit;
     }
+
+    /// If enabled, a comment with the [fingerprint](crate::Document::fingerprint)
+    /// of the document is placed at the top of every generated file which
+    /// supports comments, so stale files can be detected with
+    /// [`read_fingerprint`](crate::read_fingerprint) without generating them
+    /// again.
+    pub fn emit_fingerprint(&self) -> bool {
+        self.emit_fingerprint
+    }
+
+    pub fn set_emit_fingerprint(&mut self, emit: bool) {
+        self.emit_fingerprint = emit;
+    }
+
+    /// If enabled, files whose contents would not change are not written
+    /// again, keeping their modification time intact. Avoids build systems
+    /// like Make from relinking everything when nothing actually changed.
+    pub fn write_if_changed(&self) -> bool {
+        self.write_if_changed
+    }
+
+    pub fn set_write_if_changed(&mut self, write_if_changed: bool) {
+        self.write_if_changed = write_if_changed;
+    }
+
+    /// If enabled, nothing is written to disk. Instead, the contents of each
+    /// file are returned on its [`WrittenFile`](crate::WrittenFile), so they
+    /// can be compared against the ones on disk.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// If enabled, backslashes on paths are treated as separators on every
+    /// platform, so documents written with Windows-style paths produce the
+    /// same output anywhere. The generated files always use forward slashes.
+    ///
+    /// Disabling it keeps backslashes as part of the file names on platforms
+    /// where they are valid file name characters. Enabled by default.
+    pub fn forward_slashes(&self) -> bool {
+        self.forward_slashes
+    }
+
+    pub fn set_forward_slashes(&mut self, forward_slashes: bool) {
+        self.forward_slashes = forward_slashes;
+    }
+
+    /// The flavor of the generated linker scripts. Defaults to
+    /// [`ScriptFlavor::Matching`].
+    pub fn flavor(&self) -> ScriptFlavor {
+        self.flavor
+    }
+
+    pub fn set_flavor(&mut self, flavor: ScriptFlavor) {
+        self.flavor = flavor;
+    }
+
+    /// The time, in seconds since the Unix epoch, used for the `{date}` of
+    /// the banners instead of the current one, so builds can be reproduced.
+    ///
+    /// Taken from the `SOURCE_DATE_EPOCH` environment variable by default.
+    /// Nothing else on the generated files depends on the time they were
+    /// generated at.
+    pub fn source_date_epoch(&self) -> Option<u64> {
+        self.source_date_epoch
+    }
+
+    pub fn set_source_date_epoch(&mut self, source_date_epoch: Option<u64>) {
+        self.source_date_epoch = source_date_epoch;
+    }
+
+    /// The date written on the `{date}` of the banners, as `YYYY-MM-DD` in
+    /// UTC.
+    pub(crate) fn banner_date(&self) -> String {
+        let seconds = self
+            .source_date_epoch
+            .or_else(utils::current_unix_time)
+            .unwrap_or_default();
+
+        utils::format_date(seconds)
+    }
 }
 
 impl RuntimeSettings {
@@ -69,10 +197,21 @@ impl RuntimeSettings {
     /// Replace all the `{key}` instances on the `path` argument with the corresponding value specified on the global `custom_options`.
     ///
     /// If the `key` is not present on the custom options then it returns an error.
+    ///
+    /// The resulting path is normalized, so `.` and `..` components are
+    /// collapsed.
     pub fn escape_path(&self, path: &Path) -> Result<EscapedPath, SlinkyError> {
         let mut new_path = PathBuf::new();
 
-        for component in path.iter() {
+        // Windows already treats backslashes as separators
+        let separated_path = match path.to_str() {
+            Some(p) if self.forward_slashes && !cfg!(windows) && p.contains('\\') => {
+                PathBuf::from(p.replace('\\', "/"))
+            }
+            _ => path.to_path_buf(),
+        };
+
+        for component in separated_path.iter() {
             // &OsStr is dumb so we convert each component into &str, hopefully the conversion isn't noticeable on runtime
             if let Some(c) = component.to_str() {
                 if c.starts_with('{') && c.ends_with('}') {
@@ -119,7 +258,7 @@ impl RuntimeSettings {
             }
         }
 
-        Ok(EscapedPath(new_path))
+        Ok(EscapedPath::normalized(new_path))
     }
 
     pub fn should_emit_entry(
@@ -1,42 +1,168 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt,
+    io::{BufWriter, Write},
+};
+
+use crate::{Settings, SlinkyError, SymbolOrigin};
+
+/// A point of the buffer which can be returned to with
+/// [`ScriptBuffer::rollback`].
+#[derive(Clone, Copy)]
+pub(crate) struct ScriptBufferPosition {
+    len: usize,
+    linker_symbols: usize,
+    indent_level: i32,
+}
+
 pub(crate) struct ScriptBuffer {
     indent_level: i32,
-    buffer: Vec<String>,
+    // Every line written so far, each one terminated by a newline. A single
+    // string avoids an allocation per line on big documents
+    buffer: String,
+
+    // If set, the lines of the buffer are moved here every time
+    // `stream_pending` is called
+    stream: Option<Box<dyn Write + Send + Sync>>,
+    // Whether anything was already moved to the stream
+    streamed: bool,
 
     linker_symbols: indexmap::IndexSet<String>,
+    // Where each of the `linker_symbols` comes from, in the same order
+    linker_symbol_origins: Vec<SymbolOrigin>,
+    // The origin of the linker symbols written from now on
+    origin: SymbolOrigin,
+    // Generated symbols defined somewhere else, which are tracked but never
+    // written
+    suppressed_linker_symbols: HashSet<String>,
+
+    hex_uppercase: bool,
+    hex_address_width: usize,
+    hex_alignments: Option<bool>,
+
+    provide_linker_symbols: bool,
+    hide_linker_symbols: bool,
+    absolute_linker_symbols: bool,
+
+    // What is written once per indentation level
+    indentation: String,
+    braces_on_same_line: bool,
+    blank_lines_between_segments: u32,
+    blank_lines_between_groups: u32,
+    trailing_newline: bool,
 }
 
 impl ScriptBuffer {
-    pub fn new() -> Self {
+    pub fn new(settings: &Settings) -> Self {
         Self {
             indent_level: 0,
-            buffer: Vec::new(),
+            buffer: String::new(),
+
+            stream: None,
+            streamed: false,
 
             linker_symbols: indexmap::IndexSet::new(),
+            linker_symbol_origins: Vec::new(),
+            origin: SymbolOrigin::default(),
+            suppressed_linker_symbols: HashSet::new(),
+
+            hex_uppercase: settings.hex_uppercase,
+            hex_address_width: settings.hex_address_width as usize,
+            hex_alignments: settings.hex_alignments,
+
+            provide_linker_symbols: settings.generated_symbols_provide,
+            hide_linker_symbols: settings.generated_symbols_hidden,
+            absolute_linker_symbols: settings.generated_symbols_absolute,
+
+            indentation: if settings.script_indent_with_tabs {
+                "\t".to_string()
+            } else {
+                " ".repeat(settings.script_indent_width as usize)
+            },
+            braces_on_same_line: settings.script_braces_on_same_line,
+            blank_lines_between_segments: settings.script_blank_lines_between_segments,
+            blank_lines_between_groups: settings.script_blank_lines_between_groups,
+            trailing_newline: settings.script_trailing_newline,
+        }
+    }
+}
+
+impl ScriptBuffer {
+    /// Formats a value as hexadecimal, without any padding.
+    #[must_use]
+    pub fn hex(&self, value: impl Into<u64>) -> String {
+        self.padded_hex(value.into(), 0)
+    }
+
+    /// Formats an address-like value (vrams, fill values, etc) as hexadecimal,
+    /// zero-padded to the width requested by the settings.
+    #[must_use]
+    pub fn address(&self, value: impl Into<u64>) -> String {
+        self.padded_hex(value.into(), self.hex_address_width)
+    }
+
+    /// Formats an alignment value. `hex_by_default` is used when the settings
+    /// do not force a specific format.
+    #[must_use]
+    pub fn alignment(&self, value: u32, hex_by_default: bool) -> String {
+        if self.hex_alignments.unwrap_or(hex_by_default) {
+            self.hex(value)
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn padded_hex(&self, value: u64, width: usize) -> String {
+        if self.hex_uppercase {
+            format!("0x{:0width$X}", value, width = width)
+        } else {
+            format!("0x{:0width$x}", value, width = width)
         }
     }
 }
 
 impl ScriptBuffer {
     pub fn write_empty_line(&mut self) {
-        self.buffer.push("".to_string());
+        self.buffer.push('\n');
     }
 
     pub fn writeln(&mut self, line: &str) {
-        let mut temp = String::new();
+        self.write_indentation();
+        self.buffer += line;
+        self.buffer.push('\n');
+    }
+
+    /// Writes a whole line at the current indentation, formatting it directly
+    /// into the buffer.
+    ///
+    /// Allows using the `write!` macro, like `write!(buffer, "{} = .;", sym)`,
+    /// without building a temporary `String` for each line.
+    pub fn write_fmt(&mut self, args: fmt::Arguments<'_>) {
+        self.write_indentation();
+        fmt::Write::write_fmt(&mut self.buffer, args).expect("Writing to a String can't fail");
+        self.buffer.push('\n');
+    }
 
+    fn write_indentation(&mut self) {
         for _i in 0..self.indent_level {
-            temp += "    ";
+            self.buffer += &self.indentation;
         }
-
-        temp += line;
-        self.buffer.push(temp);
     }
 
+    /// Opens a block after the header written on the previous line.
     pub fn begin_block(&mut self) {
-        self.writeln("{");
+        let after_header = self.buffer.ends_with('\n') && !self.buffer.ends_with("\n\n");
+
+        if self.braces_on_same_line && after_header {
+            self.buffer.pop();
+            self.buffer += " {\n";
+        } else {
+            self.writeln("{");
+        }
         self.indent_level += 1;
     }
 
@@ -46,16 +172,111 @@ impl ScriptBuffer {
         self.writeln("}");
     }
 
+    /// Writes handwritten linker script lines at the current indentation.
+    ///
+    /// Symbols assigned at the start of a line are tracked like the generated
+    /// ones.
+    pub fn write_raw(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim_end();
+
+            if line.is_empty() {
+                self.write_empty_line();
+                continue;
+            }
+            self.writeln(line);
+
+            if let Some((name, value)) = line.trim_start().split_once('=') {
+                let name = name.trim_end();
+                let is_identifier = name
+                    .chars()
+                    .next()
+                    .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+                // Skip comparisons like `a == b`
+                if is_identifier && !value.starts_with('=') {
+                    self.insert_linker_symbol(name);
+                }
+            }
+        }
+    }
+
+    /// Separates a segment from whatever follows it.
+    pub fn write_segment_separator(&mut self) {
+        for _i in 0..self.blank_lines_between_segments {
+            self.write_empty_line();
+        }
+    }
+
+    pub fn write_group_start(&mut self, group: &str) {
+        self.write_comment(&format!("#region {}", group));
+    }
+
+    /// Closes a group of segments, separating it from whatever follows it.
+    pub fn write_group_end(&mut self, group: &str) {
+        self.write_comment(&format!("#endregion {}", group));
+        for _i in 0..self.blank_lines_between_groups {
+            self.write_empty_line();
+        }
+    }
+
     pub fn write_single_entry_section(&mut self, section: &str, address: &str) {
-        self.writeln(&format!("{} {} : {{ *({}); }}", section, address, section));
+        write!(self, "{} {} : {{ *({}); }}", section, address, section);
     }
 
     pub fn write_linker_symbol(&mut self, symbol: &str, value: &str) {
         // TODO: check `symbol` is a valid C identifier
 
-        self.write_symbol_assignment(symbol, value, false, false);
+        if self.suppressed_linker_symbols.contains(symbol) {
+            self.insert_linker_symbol(symbol);
+            return;
+        }
+
+        let value = self.linker_symbol_value(value);
+        self.write_symbol_assignment(
+            symbol,
+            &value,
+            self.provide_linker_symbols,
+            self.hide_linker_symbols,
+        );
+
+        self.insert_linker_symbol(symbol);
+    }
+
+    /// Like [`write_linker_symbol`](Self::write_linker_symbol), but wrapped in
+    /// a `PROVIDE` statement.
+    pub fn write_provided_linker_symbol(&mut self, symbol: &str, value: &str) {
+        if self.suppressed_linker_symbols.contains(symbol) {
+            self.insert_linker_symbol(symbol);
+            return;
+        }
+
+        let value = self.linker_symbol_value(value);
+        self.write_symbol_assignment(symbol, &value, true, self.hide_linker_symbols);
 
-        self.linker_symbols.insert(symbol.to_string());
+        self.insert_linker_symbol(symbol);
+    }
+
+    /// Writes a symbol which aliases a generated symbol, tracking it as a
+    /// linker symbol too.
+    pub fn write_symbol_alias(&mut self, symbol: &str, value: &str, provide: bool, hidden: bool) {
+        self.write_symbol_assignment(symbol, value, provide, hidden);
+
+        self.insert_linker_symbol(symbol);
+    }
+
+    /// Wraps the value of a generated symbol in `ABSOLUTE` if requested by the
+    /// settings, unless it already is absolute, like plain numbers.
+    fn linker_symbol_value<'v>(&self, value: &'v str) -> Cow<'v, str> {
+        let is_absolute =
+            value.starts_with("ABSOLUTE(") || value.starts_with(|c: char| c.is_ascii_digit());
+
+        if self.absolute_linker_symbols && !is_absolute {
+            Cow::from(format!("ABSOLUTE({})", value))
+        } else {
+            Cow::from(value)
+        }
     }
 
     pub fn write_symbol_assignment(
@@ -65,38 +286,141 @@ impl ScriptBuffer {
         provide: bool,
         hidden: bool,
     ) {
-        let line = match (provide, hidden) {
-            (true, true) => format!("PROVIDE_HIDDEN({} = {});", symbol, value),
-            (true, false) => format!("PROVIDE({} = {});", symbol, value),
-            (false, true) => format!("HIDDEN({} = {});", symbol, value),
-            (false, false) => format!("{} = {};", symbol, value),
-        };
-
-        self.writeln(&line);
+        match (provide, hidden) {
+            (true, true) => write!(self, "PROVIDE_HIDDEN({} = {});", symbol, value),
+            (true, false) => write!(self, "PROVIDE({} = {});", symbol, value),
+            (false, true) => write!(self, "HIDDEN({} = {});", symbol, value),
+            (false, false) => write!(self, "{} = {};", symbol, value),
+        }
     }
 
     pub fn align_symbol(&mut self, symbol: &str, align_value: u32) {
-        self.writeln(&format!(
-            "{} = ALIGN({}, 0x{:X});",
-            symbol, symbol, align_value
-        ));
+        let alignment = self.alignment(align_value, true);
+        write!(self, "{} = ALIGN({}, {});", symbol, symbol, alignment);
     }
 
     pub fn write_symbol_max_self(&mut self, symbol: &str, other_sym: &str) {
-        self.writeln(&format!("{} = MAX({}, {});", symbol, symbol, other_sym));
+        write!(self, "{} = MAX({}, {});", symbol, symbol, other_sym);
     }
 
     pub fn write_assert(&mut self, cond: &str, error_msg: &str) {
-        self.writeln(&format!("ASSERT(({}), \"Error: {}\");", cond, error_msg));
+        write!(self, "ASSERT(({}), \"Error: {}\");", cond, error_msg);
+    }
+
+    /// GNU ld has no way to emit a non-fatal diagnostic, so the result of the
+    /// check is stored on a symbol instead, which is zero if the check passed.
+    pub fn write_assert_warning(&mut self, symbol: &str, cond: &str) {
+        write!(self, "{} = ({}) ? 0 : 1;", symbol, cond);
     }
 
     pub fn write_required_symbol(&mut self, name: &str) {
-        self.writeln(&format!("EXTERN({});", name));
+        write!(self, "EXTERN({});", name);
+        self.write_defined_assert(name);
+    }
+
+    pub fn write_defined_assert(&mut self, name: &str) {
         self.write_assert(
             &format!("DEFINED({})", name),
             &format!("Required symbol '{}' was not linked", name),
         );
     }
+
+    /// Writes a single `EXTERN` statement listing every given symbol in
+    /// order, one per line, each one preceded by its comment if it has any.
+    pub fn write_extern_group(&mut self, symbols: &[(&str, Option<&str>)]) {
+        self.writeln("EXTERN(");
+        self.indent_level += 1;
+        for (name, comment) in symbols {
+            if let Some(comment) = comment {
+                self.write_comment(comment);
+            }
+            self.writeln(name);
+        }
+        self.indent_level -= 1;
+        self.writeln(");");
+    }
+
+    pub fn write_comment(&mut self, comment: &str) {
+        for line in comment.lines() {
+            // Avoid ending the comment early
+            write!(self, "/* {} */", line.trim_end().replace("*/", "* /"));
+        }
+    }
+
+    /// Requires a linker symbol which is provided externally, but that still
+    /// should be listed among the generated symbols.
+    pub fn write_required_linker_symbol(&mut self, name: &str) {
+        self.write_required_symbol(name);
+
+        self.insert_linker_symbol(name);
+    }
+
+    fn insert_linker_symbol(&mut self, name: &str) {
+        if self.linker_symbols.insert(name.to_string()) {
+            self.linker_symbol_origins.push(self.origin.clone());
+        }
+    }
+
+    /// Generated symbols which are only tracked, since they are defined
+    /// somewhere else.
+    pub fn set_suppressed_linker_symbols(&mut self, symbols: HashSet<String>) {
+        self.suppressed_linker_symbols = symbols;
+    }
+
+    pub fn is_suppressed_linker_symbol(&self, symbol: &str) -> bool {
+        self.suppressed_linker_symbols.contains(symbol)
+    }
+
+    /// Sets the origin of the linker symbols written from now on.
+    pub fn set_origin(&mut self, origin: SymbolOrigin) {
+        self.origin = origin;
+    }
+
+    pub fn origin_mut(&mut self) -> &mut SymbolOrigin {
+        &mut self.origin
+    }
+
+    /// Moves the linker symbols written on another buffer to this one, as if
+    /// they were written here.
+    pub fn take_linker_symbols(&mut self, other: &mut ScriptBuffer) {
+        let symbols = std::mem::take(&mut other.linker_symbols);
+        let origins = std::mem::take(&mut other.linker_symbol_origins);
+
+        for (name, origin) in symbols.into_iter().zip(origins) {
+            if self.linker_symbols.insert(name) {
+                self.linker_symbol_origins.push(origin);
+            }
+        }
+    }
+}
+
+impl ScriptBuffer {
+    #[must_use]
+    pub fn position(&self) -> ScriptBufferPosition {
+        ScriptBufferPosition {
+            len: self.buffer.len(),
+            linker_symbols: self.linker_symbols.len(),
+            indent_level: self.indent_level,
+        }
+    }
+
+    /// Returns if anything was written after the given position.
+    #[must_use]
+    pub fn written_since(&self, position: ScriptBufferPosition) -> bool {
+        self.buffer.len() > position.len
+    }
+
+    /// Discards everything written after the given position, including the
+    /// linker symbols.
+    pub fn rollback(&mut self, position: ScriptBufferPosition) {
+        // Streamed lines can't be taken back
+        debug_assert!(position.len <= self.buffer.len());
+
+        self.buffer.truncate(position.len);
+        self.linker_symbols.truncate(position.linker_symbols);
+        self.linker_symbol_origins.truncate(position.linker_symbols);
+        self.indent_level = position.indent_level;
+    }
 }
 
 impl ScriptBuffer {
@@ -106,9 +430,71 @@ impl ScriptBuffer {
 }
 
 impl ScriptBuffer {
+    pub fn set_stream(&mut self, dst: impl Write + Send + Sync + 'static) {
+        self.stream = Some(Box::new(BufWriter::new(dst)));
+    }
+
+    /// Moves every line written so far to the stream, if there's one.
+    ///
+    /// Nothing written before calling this can be rolled back afterwards.
+    pub fn stream_pending(&mut self) -> Result<(), SlinkyError> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+
+        if let Err(e) = stream.write_all(self.buffer.as_bytes()) {
+            return Err(SlinkyError::FailedWrite {
+                description: e.to_string(),
+                contents: std::mem::take(&mut self.buffer),
+            });
+        }
+        self.streamed |= !self.buffer.is_empty();
+        self.buffer.clear();
+
+        Ok(())
+    }
+
+    /// Like [`stream_pending`](Self::stream_pending), but also flushes the
+    /// stream.
+    pub fn flush_stream(&mut self) -> Result<(), SlinkyError> {
+        if !self.trailing_newline && self.buffer.ends_with('\n') {
+            self.buffer.pop();
+        }
+        self.stream_pending()?;
+
+        if let Some(stream) = &mut self.stream {
+            if let Err(e) = stream.flush() {
+                return Err(SlinkyError::FailedWrite {
+                    description: e.to_string(),
+                    contents: "".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ScriptBuffer {
+    /// The whole script, with a newline at the end of every line except
+    /// the last one if the settings ask for no trailing newline.
     #[must_use]
-    pub fn get_buffer(&self) -> &[String] {
-        &self.buffer
+    pub fn get_contents(&self) -> &str {
+        if self.trailing_newline {
+            &self.buffer
+        } else {
+            self.buffer.strip_suffix('\n').unwrap_or(&self.buffer)
+        }
+    }
+
+    #[must_use]
+    pub fn trailing_newline(&self) -> bool {
+        self.trailing_newline
+    }
+
+    pub fn lines(&self) -> std::str::Lines<'_> {
+        self.buffer.lines()
     }
 
     #[must_use]
@@ -116,8 +502,20 @@ impl ScriptBuffer {
         &self.linker_symbols
     }
 
+    #[must_use]
+    pub fn get_linker_symbol_origins(&self) -> &[SymbolOrigin] {
+        &self.linker_symbol_origins
+    }
+
+    /// Whether any line was already moved to the stream.
+    #[must_use]
+    pub fn is_streamed(&self) -> bool {
+        self.streamed
+    }
+
+    /// Whether nothing has been written, including the streamed lines.
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+        self.buffer.is_empty() && !self.streamed
     }
 }
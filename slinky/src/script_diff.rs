@@ -0,0 +1,384 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{collections::HashMap, fmt, path::Path};
+
+use crate::{Document, FileInfo, FileKind, ImportedLinkerScript, Segment, SlinkyError};
+
+/// A layout-affecting difference between two linker scripts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ScriptChange {
+    SegmentAdded {
+        segment: String,
+    },
+    SegmentRemoved {
+        segment: String,
+    },
+    /// The segment is present on both scripts, but its position relative to
+    /// the other segments changed.
+    SegmentMoved {
+        segment: String,
+    },
+    /// An attribute of the segment, like its `fixed_vram` or its sections,
+    /// changed.
+    SegmentAttributeChanged {
+        segment: String,
+        attribute: String,
+        old: String,
+        new: String,
+    },
+    /// `file` uses the same syntax used on the linker script, so archive
+    /// members are written as `path/to/lib.a:member.o`.
+    FileAdded {
+        segment: String,
+        file: String,
+    },
+    FileRemoved {
+        segment: String,
+        file: String,
+    },
+    /// The file is present on both scripts, but its position relative to the
+    /// other files of the segment changed.
+    FileMoved {
+        segment: String,
+        file: String,
+    },
+    SymbolAdded {
+        symbol: String,
+        value: String,
+    },
+    SymbolRemoved {
+        symbol: String,
+    },
+    SymbolChanged {
+        symbol: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl fmt::Display for ScriptChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptChange::SegmentAdded { segment } => {
+                write!(f, "Added segment '{}'", segment)
+            }
+            ScriptChange::SegmentRemoved { segment } => {
+                write!(f, "Removed segment '{}'", segment)
+            }
+            ScriptChange::SegmentMoved { segment } => {
+                write!(f, "Moved segment '{}'", segment)
+            }
+            ScriptChange::SegmentAttributeChanged {
+                segment,
+                attribute,
+                old,
+                new,
+            } => write!(
+                f,
+                "Changed `{}` of segment '{}' from {} to {}",
+                attribute, segment, old, new
+            ),
+            ScriptChange::FileAdded { segment, file } => {
+                write!(f, "Added file '{}' to segment '{}'", file, segment)
+            }
+            ScriptChange::FileRemoved { segment, file } => {
+                write!(f, "Removed file '{}' from segment '{}'", file, segment)
+            }
+            ScriptChange::FileMoved { segment, file } => {
+                write!(f, "Moved file '{}' of segment '{}'", file, segment)
+            }
+            ScriptChange::SymbolAdded { symbol, value } => {
+                write!(f, "Added symbol '{}' = {}", symbol, value)
+            }
+            ScriptChange::SymbolRemoved { symbol } => {
+                write!(f, "Removed symbol '{}'", symbol)
+            }
+            ScriptChange::SymbolChanged { symbol, old, new } => {
+                write!(f, "Changed symbol '{}' from {} to {}", symbol, old, new)
+            }
+        }
+    }
+}
+
+/// The semantic differences between two linker scripts.
+///
+/// Both scripts are read with the same importer used by
+/// [`Document::from_linker_script`], so only what can be represented on a
+/// document is compared. Formatting, comments and the symbols generated for
+/// each segment are ignored.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ScriptDiff {
+    pub changes: Vec<ScriptChange>,
+}
+
+impl ScriptDiff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn from_linker_scripts(old: &Path, new: &Path) -> Result<Self, SlinkyError> {
+        let old = Document::from_linker_script(old)?;
+        let new = Document::from_linker_script(new)?;
+
+        Ok(Self::from_imported(&old, &new))
+    }
+
+    pub fn from_linker_script_strs(old: &str, new: &str) -> Result<Self, SlinkyError> {
+        let old = Document::from_linker_script_str(old)?;
+        let new = Document::from_linker_script_str(new)?;
+
+        Ok(Self::from_imported(&old, &new))
+    }
+
+    #[must_use]
+    pub fn from_imported(old: &ImportedLinkerScript, new: &ImportedLinkerScript) -> Self {
+        let mut diff = Self::default();
+
+        diff.diff_segments(&old.document.segments, &new.document.segments);
+        diff.diff_symbols(&old.document, &new.document);
+
+        diff
+    }
+
+    fn diff_segments(&mut self, old: &[Segment], new: &[Segment]) {
+        let old_names: Vec<&str> = old.iter().map(|x| x.name.as_str()).collect();
+        let new_names: Vec<&str> = new.iter().map(|x| x.name.as_str()).collect();
+
+        let order = diff_sequences(&old_names, &new_names);
+        for name in order.removed {
+            self.changes.push(ScriptChange::SegmentRemoved {
+                segment: name.to_string(),
+            });
+        }
+        for name in order.added {
+            self.changes.push(ScriptChange::SegmentAdded {
+                segment: name.to_string(),
+            });
+        }
+        for name in order.moved {
+            self.changes.push(ScriptChange::SegmentMoved {
+                segment: name.to_string(),
+            });
+        }
+
+        for new_segment in new {
+            if let Some(old_segment) = old.iter().find(|x| x.name == new_segment.name) {
+                self.diff_segment(old_segment, new_segment);
+            }
+        }
+    }
+
+    fn diff_segment(&mut self, old: &Segment, new: &Segment) {
+        let attributes = [
+            (
+                "fixed_vram",
+                old.fixed_vram.map(|x| format!("0x{:08X}", x)),
+                new.fixed_vram.map(|x| format!("0x{:08X}", x)),
+            ),
+            (
+                "fixed_symbol",
+                old.fixed_symbol.clone(),
+                new.fixed_symbol.clone(),
+            ),
+            (
+                "subalign",
+                old.subalign.map(|x| x.to_string()),
+                new.subalign.map(|x| x.to_string()),
+            ),
+            (
+                "fill_value",
+                old.fill_value.map(|x| format!("0x{:08X}", x)),
+                new.fill_value.map(|x| format!("0x{:08X}", x)),
+            ),
+            (
+                "alloc_sections",
+                Some(format!("{:?}", old.alloc_sections)),
+                Some(format!("{:?}", new.alloc_sections)),
+            ),
+            (
+                "noload_sections",
+                Some(format!("{:?}", old.noload_sections)),
+                Some(format!("{:?}", new.noload_sections)),
+            ),
+        ];
+
+        for (attribute, old_value, new_value) in attributes {
+            if old_value != new_value {
+                self.changes.push(ScriptChange::SegmentAttributeChanged {
+                    segment: new.name.clone(),
+                    attribute: attribute.to_string(),
+                    old: old_value.unwrap_or_else(|| "nothing".to_string()),
+                    new: new_value.unwrap_or_else(|| "nothing".to_string()),
+                });
+            }
+        }
+
+        let old_files = file_descriptions(&old.files);
+        let new_files = file_descriptions(&new.files);
+
+        let order = diff_sequences(&old_files, &new_files);
+        for file in order.removed {
+            self.changes.push(ScriptChange::FileRemoved {
+                segment: new.name.clone(),
+                file,
+            });
+        }
+        for file in order.added {
+            self.changes.push(ScriptChange::FileAdded {
+                segment: new.name.clone(),
+                file,
+            });
+        }
+        for file in order.moved {
+            self.changes.push(ScriptChange::FileMoved {
+                segment: new.name.clone(),
+                file,
+            });
+        }
+    }
+
+    fn diff_symbols(&mut self, old: &Document, new: &Document) {
+        for old_symbol in &old.symbol_assignments {
+            if !new
+                .symbol_assignments
+                .iter()
+                .any(|x| x.name == old_symbol.name)
+            {
+                self.changes.push(ScriptChange::SymbolRemoved {
+                    symbol: old_symbol.name.clone(),
+                });
+            }
+        }
+
+        for new_symbol in &new.symbol_assignments {
+            let new_value = normalize_expression(&new_symbol.value);
+
+            match old
+                .symbol_assignments
+                .iter()
+                .find(|x| x.name == new_symbol.name)
+            {
+                None => self.changes.push(ScriptChange::SymbolAdded {
+                    symbol: new_symbol.name.clone(),
+                    value: new_value,
+                }),
+                Some(old_symbol) => {
+                    let old_value = normalize_expression(&old_symbol.value);
+
+                    if old_value != new_value {
+                        self.changes.push(ScriptChange::SymbolChanged {
+                            symbol: new_symbol.name.clone(),
+                            old: old_value,
+                            new: new_value,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Describes each file using the same syntax used on the linker script.
+fn file_descriptions(files: &[FileInfo]) -> Vec<String> {
+    let mut descriptions = Vec::new();
+
+    for file in files {
+        match file.kind {
+            FileKind::Pad => descriptions.push(format!(
+                ". += 0x{:X}; ({})",
+                file.pad_amount,
+                file.sections.join(" ")
+            )),
+            FileKind::Archive if !file.subfile.is_empty() => {
+                descriptions.push(format!("{}:{}", file.path.display(), file.subfile))
+            }
+            _ if !file.files.is_empty() => descriptions.extend(file_descriptions(&file.files)),
+            _ => descriptions.push(file.path.display().to_string()),
+        }
+    }
+
+    descriptions
+}
+
+/// Removes the whitespace of an expression, so only semantic changes are
+/// reported.
+fn normalize_expression(expr: &str) -> String {
+    expr.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+struct SequenceDiff<T> {
+    removed: Vec<T>,
+    added: Vec<T>,
+    moved: Vec<T>,
+}
+
+/// Compares two sequences using their longest common subsequence.
+///
+/// The elements outside of it which are present on both sequences are
+/// reported as moved, in the order of the new sequence.
+fn diff_sequences<T: Clone + Eq + std::hash::Hash>(old: &[T], new: &[T]) -> SequenceDiff<T> {
+    // lengths[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..]
+    let mut lengths = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_leftovers = Vec::new();
+    let mut new_leftovers = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            old_leftovers.push(&old[i]);
+            i += 1;
+        } else {
+            new_leftovers.push(&new[j]);
+            j += 1;
+        }
+    }
+    old_leftovers.extend(&old[i..]);
+    new_leftovers.extend(&new[j..]);
+
+    // How many times each element is available to be matched as a move
+    let mut available: HashMap<&T, usize> = HashMap::new();
+    for x in &old_leftovers {
+        *available.entry(*x).or_default() += 1;
+    }
+
+    let mut diff = SequenceDiff {
+        removed: Vec::new(),
+        added: Vec::new(),
+        moved: Vec::new(),
+    };
+    for x in new_leftovers {
+        match available.get_mut(x) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                diff.moved.push(x.clone());
+            }
+            _ => diff.added.push(x.clone()),
+        }
+    }
+    for x in old_leftovers {
+        match available.get_mut(x) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                diff.removed.push(x.clone());
+            }
+            _ => {}
+        }
+    }
+
+    diff
+}
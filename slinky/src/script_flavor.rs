@@ -0,0 +1,19 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+/// Which flavor of linker script is generated from a document.
+///
+/// Decompilation projects usually need a linker script which reproduces the
+/// original binary byte by byte, and another one where the code can freely
+/// grow or shrink.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Default)]
+pub enum ScriptFlavor {
+    /// Everything listed on the document is emitted.
+    #[default]
+    Matching,
+    /// The file entries marked as `only_matching` are skipped, the fields
+    /// listed on the `only_matching_fields` of each segment are ignored and
+    /// the files are not padded with `pad_to_size`, so the addresses are free
+    /// to shift.
+    Shiftable,
+}
@@ -0,0 +1,207 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+/// A single statement of a generated linker script.
+///
+/// Lets tools post-process or pretty-print the script without having to
+/// parse it again. See [`LinkerWriter::script_items`].
+///
+/// [`LinkerWriter::script_items`]: crate::LinkerWriter::script_items
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ScriptItem<'a> {
+    /// A blank line.
+    Empty,
+
+    /// A `/* */` comment, without its delimiters.
+    Comment(&'a str),
+
+    /// The header of an output section, like the one starting each segment,
+    /// or of an `OVERLAY` statement.
+    SectionStart {
+        /// The name of the output section, like `.boot` or `.boot.noload`, or
+        /// `OVERLAY`.
+        name: &'a str,
+        /// The whole header, like `.boot : AT(boot_ROM_START)`.
+        header: &'a str,
+    },
+
+    /// An opening brace, either on its own line or at the end of a header.
+    BlockStart,
+
+    /// A closing brace.
+    BlockEnd,
+
+    /// An assignment to a symbol, including the ones wrapped in `PROVIDE`,
+    /// `PROVIDE_HIDDEN` or `HIDDEN`.
+    SymbolAssignment {
+        name: &'a str,
+        /// Either `=` or a compound operator, like `+=`.
+        operator: &'a str,
+        value: &'a str,
+        provide: bool,
+        hidden: bool,
+    },
+
+    /// An input section description, placing the sections of a file.
+    FileEntry {
+        /// The path of the file or archive. Can also be a wildcard, like `*`.
+        path: &'a str,
+        /// The member of the archive, if any.
+        member: Option<&'a str>,
+        /// The input section pattern, like `.text*`.
+        section: &'a str,
+        /// Whether the entry is wrapped in `KEEP`.
+        keep: bool,
+    },
+
+    /// An `ASSERT` statement.
+    Assert {
+        condition: &'a str,
+        message: &'a str,
+    },
+
+    /// Any other statement, like `SECTIONS`, `FILL`, `ENTRY` or the lines of
+    /// raw blocks, without its indentation.
+    Other(&'a str),
+}
+
+/// Splits the contents of a linker script into its statements.
+pub(crate) fn script_items(contents: &str) -> impl Iterator<Item = ScriptItem<'_>> {
+    contents.lines().flat_map(line_items)
+}
+
+/// A line is a single statement, except for headers followed by the opening
+/// brace of their block.
+fn line_items(line: &str) -> Vec<ScriptItem<'_>> {
+    let line = line.trim();
+
+    if line != "{" {
+        if let Some(header) = line.strip_suffix(" {") {
+            return vec![statement(header), ScriptItem::BlockStart];
+        }
+    }
+
+    vec![statement(line)]
+}
+
+fn statement(line: &str) -> ScriptItem<'_> {
+    match line {
+        "" => return ScriptItem::Empty,
+        "{" => return ScriptItem::BlockStart,
+        "}" => return ScriptItem::BlockEnd,
+        _ => {}
+    }
+
+    if let Some(comment) = line.strip_prefix("/* ").and_then(|x| x.strip_suffix(" */")) {
+        return ScriptItem::Comment(comment);
+    }
+
+    if let Some(item) = assert(line)
+        .or_else(|| symbol_assignment(line))
+        .or_else(|| section_start(line))
+        .or_else(|| file_entry(line))
+    {
+        return item;
+    }
+
+    ScriptItem::Other(line)
+}
+
+fn assert(line: &str) -> Option<ScriptItem<'_>> {
+    let inner = line.strip_prefix("ASSERT(")?.strip_suffix(");")?;
+    let (condition, message) = inner.rsplit_once(", \"")?;
+
+    Some(ScriptItem::Assert {
+        condition: condition
+            .strip_prefix('(')
+            .and_then(|x| x.strip_suffix(')'))
+            .unwrap_or(condition),
+        message: message.strip_suffix('"')?,
+    })
+}
+
+fn symbol_assignment(line: &str) -> Option<ScriptItem<'_>> {
+    let wrappers = [
+        ("PROVIDE_HIDDEN(", true, true),
+        ("PROVIDE(", true, false),
+        ("HIDDEN(", false, true),
+    ];
+
+    let (assignment, provide, hidden) = wrappers
+        .iter()
+        .find_map(|(prefix, provide, hidden)| {
+            line.strip_prefix(prefix)
+                .and_then(|x| x.strip_suffix(");"))
+                .map(|x| (x, *provide, *hidden))
+        })
+        .unwrap_or((line.strip_suffix(';')?, false, false));
+
+    let (name, rest) = assignment.split_once(' ')?;
+    let (operator, value) = rest.split_once(' ')?;
+    if !["=", "+=", "-=", "*=", "/=", "<<=", ">>=", "&=", "|="].contains(&operator) {
+        return None;
+    }
+    let is_symbol = name == "."
+        || (name
+            .chars()
+            .next()
+            .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    if !is_symbol {
+        return None;
+    }
+
+    Some(ScriptItem::SymbolAssignment {
+        name,
+        operator,
+        value,
+        provide,
+        hidden,
+    })
+}
+
+fn section_start(line: &str) -> Option<ScriptItem<'_>> {
+    // Also rules out the regions of `MEMORY`, like `ram : ORIGIN = 0x0`
+    if line.ends_with(';') || line.contains('=') {
+        return None;
+    }
+    // The sections of an `OVERLAY` only have their name
+    let is_overlay_section = line.starts_with('.') && !line.contains(char::is_whitespace);
+    if !line.ends_with(" :") && !line.contains(" : ") && !is_overlay_section {
+        return None;
+    }
+
+    let name = line.split_whitespace().next()?;
+    Some(ScriptItem::SectionStart { name, header: line })
+}
+
+fn file_entry(line: &str) -> Option<ScriptItem<'_>> {
+    let entry = line.strip_suffix(';')?;
+    let (entry, keep) = match entry.strip_prefix("KEEP(") {
+        Some(kept) => (kept.strip_suffix(')')?, true),
+        None => (entry, false),
+    };
+
+    let (file, section) = entry.split_once('(')?;
+    let section = section.strip_suffix(')')?;
+
+    // Commands like `FILL` or `ENTRY`
+    if file.is_empty()
+        || file.contains(char::is_whitespace)
+        || file.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+    {
+        return None;
+    }
+
+    let (path, member) = match file.rsplit_once(':') {
+        Some((archive, member)) => (archive, Some(member)),
+        None => (file, None),
+    };
+
+    Some(ScriptItem::FileEntry {
+        path,
+        member,
+        section,
+        keep,
+    })
+}
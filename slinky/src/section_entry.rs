@@ -0,0 +1,315 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::{absent_nullable::AbsentNullable, KeepSections, OrderBy, SlinkyError};
+
+/// The value of `alloc_sections` or `noload_sections`. It can either be the
+/// full list of sections or a set of modifications to the inherited list.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[serde(untagged)]
+pub(crate) enum SectionEntriesSerial {
+    List(Vec<SectionEntrySerial>),
+    Modified(SectionEntriesModificationSerial),
+}
+
+impl From<Vec<String>> for SectionEntriesSerial {
+    fn from(names: Vec<String>) -> Self {
+        SectionEntriesSerial::List(names.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Modifications applied to the inherited list of sections, so only the
+/// differences with it have to be written down.
+///
+/// The sections of `remove` are removed first, then the ones of `prepend`
+/// and `append` are added to the start and the end of the list.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SectionEntriesModificationSerial {
+    #[serde(default)]
+    pub remove: AbsentNullable<Vec<String>>,
+    #[serde(default)]
+    pub prepend: AbsentNullable<Vec<SectionEntrySerial>>,
+    #[serde(default)]
+    pub append: AbsentNullable<Vec<SectionEntrySerial>>,
+}
+
+/// An entry of `alloc_sections` or `noload_sections`. It can either be the
+/// bare name of the section or a mapping with the name and extra properties
+/// of the section.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[serde(untagged)]
+pub(crate) enum SectionEntrySerial {
+    Name(String),
+    Detailed(SectionEntryDetailedSerial),
+}
+
+impl From<String> for SectionEntrySerial {
+    fn from(name: String) -> Self {
+        SectionEntrySerial::Name(name)
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SectionEntryDetailedSerial {
+    pub name: String,
+
+    #[serde(default)]
+    pub start_align: AbsentNullable<u32>,
+    #[serde(default)]
+    pub end_align: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub fill_value: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub wildcard: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub order_by: AbsentNullable<OrderBy>,
+
+    #[serde(default)]
+    pub keep: AbsentNullable<bool>,
+}
+
+/// The per-section properties gathered from the mapping form of the entries.
+#[derive(Default)]
+pub(crate) struct SectionsProperties {
+    pub start_alignment: HashMap<String, u32>,
+    pub end_alignment: HashMap<String, u32>,
+    pub fill_value: HashMap<String, u32>,
+    pub wildcard: HashMap<String, bool>,
+    pub order_by: HashMap<String, OrderBy>,
+    pub keep: HashSet<String>,
+}
+
+impl SectionsProperties {
+    pub fn is_empty(&self) -> bool {
+        self.start_alignment.is_empty()
+            && self.end_alignment.is_empty()
+            && self.fill_value.is_empty()
+            && self.wildcard.is_empty()
+            && self.order_by.is_empty()
+            && self.keep.is_empty()
+    }
+
+    /// Overrides the values of the given maps with the ones of the entries,
+    /// and adds the sections marked as `keep` to `keep_sections`.
+    ///
+    /// `keep_sections` is `None` for the entries which can't keep sections,
+    /// like the ones from the settings.
+    pub fn apply(
+        self,
+        sections_start_alignment: &mut HashMap<String, u32>,
+        sections_end_alignment: &mut HashMap<String, u32>,
+        sections_fill_value: &mut HashMap<String, u32>,
+        sections_wildcard: &mut HashMap<String, bool>,
+        sections_order_by: &mut HashMap<String, OrderBy>,
+        keep_sections: Option<&mut KeepSections>,
+    ) -> Result<(), SlinkyError> {
+        sections_start_alignment.extend(self.start_alignment);
+        sections_end_alignment.extend(self.end_alignment);
+        sections_fill_value.extend(self.fill_value);
+        sections_wildcard.extend(self.wildcard);
+        sections_order_by.extend(self.order_by);
+
+        if self.keep.is_empty() {
+            return Ok(());
+        }
+
+        match keep_sections {
+            None => Err(SlinkyError::InvalidFieldCombo {
+                field1: "keep".to_string(),
+                field2: "settings".to_string(),
+            }),
+            Some(KeepSections::All(true)) => Ok(()),
+            Some(KeepSections::WhichOnes(which_ones)) => {
+                which_ones.extend(self.keep);
+                Ok(())
+            }
+            Some(keep_sections) => {
+                *keep_sections = KeepSections::WhichOnes(self.keep);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Splits the entries into the plain list of section names and their
+/// properties. If the list is absent then `default` is used instead, which
+/// is also the list modified by the modifications form.
+pub(crate) fn unserialize_section_entries<F>(
+    entries: AbsentNullable<SectionEntriesSerial>,
+    name: &str,
+    default: F,
+    properties: &mut SectionsProperties,
+) -> Result<Vec<String>, SlinkyError>
+where
+    F: FnOnce() -> Vec<String>,
+{
+    let entries = match entries {
+        AbsentNullable::Absent => return Ok(default()),
+        AbsentNullable::Null => {
+            return Err(SlinkyError::NullValueOnNonNull {
+                name: name.to_string(),
+            })
+        }
+        AbsentNullable::Value(entries) => entries,
+    };
+
+    let modification = match entries {
+        SectionEntriesSerial::List(entries) => {
+            return unserialize_entries_list(entries, properties)
+        }
+        SectionEntriesSerial::Modified(modification) => modification,
+    };
+
+    let mut names = default();
+
+    for section in modification.remove.get_non_null("remove", Vec::new)? {
+        match names.iter().position(|x| *x == section) {
+            Some(index) => {
+                names.remove(index);
+            }
+            None => {
+                return Err(SlinkyError::RemovedMissingSection {
+                    field_name: name.to_string(),
+                    section,
+                })
+            }
+        }
+    }
+
+    let prepend = unserialize_entries_list(
+        modification.prepend.get_non_null("prepend", Vec::new)?,
+        properties,
+    )?;
+    names.splice(0..0, prepend);
+
+    let append = unserialize_entries_list(
+        modification.append.get_non_null("append", Vec::new)?,
+        properties,
+    )?;
+    names.extend(append);
+
+    Ok(names)
+}
+
+fn unserialize_entries_list(
+    entries: Vec<SectionEntrySerial>,
+    properties: &mut SectionsProperties,
+) -> Result<Vec<String>, SlinkyError> {
+    let mut names = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        match entry {
+            SectionEntrySerial::Name(name) => names.push(name),
+            SectionEntrySerial::Detailed(entry) => {
+                if entry.name.is_empty() {
+                    return Err(SlinkyError::EmptyValue {
+                        name: "name".to_string(),
+                    });
+                }
+
+                if let Some(start_align) = entry
+                    .start_align
+                    .get_optional_nullable("start_align", || None)?
+                {
+                    properties
+                        .start_alignment
+                        .insert(entry.name.clone(), start_align);
+                }
+                if let Some(end_align) = entry
+                    .end_align
+                    .get_optional_nullable("end_align", || None)?
+                {
+                    properties
+                        .end_alignment
+                        .insert(entry.name.clone(), end_align);
+                }
+                if let Some(fill_value) = entry
+                    .fill_value
+                    .get_optional_nullable("fill_value", || None)?
+                {
+                    properties.fill_value.insert(entry.name.clone(), fill_value);
+                }
+                if let Some(wildcard) = entry.wildcard.get_optional_nullable("wildcard", || None)? {
+                    properties.wildcard.insert(entry.name.clone(), wildcard);
+                }
+                if let Some(order_by) = entry.order_by.get_optional_nullable("order_by", || None)? {
+                    properties.order_by.insert(entry.name.clone(), order_by);
+                }
+                if entry.keep.get_non_null("keep", || false)? {
+                    properties.keep.insert(entry.name.clone());
+                }
+
+                names.push(entry.name);
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+/// Makes sure `sections_subgroups` emits each section at most once.
+///
+/// A section can't be part of multiple subgroups, can't be part of its own
+/// subgroup (even through nested subgroups) and can't be part of a subgroup
+/// while also being listed on one of the given lists of sections.
+pub(crate) fn check_sections_subgroups(
+    subgroups: &HashMap<String, Vec<String>>,
+    sections: &[(&str, &[String])],
+) -> Result<(), SlinkyError> {
+    // Sorted, so the reported error doesn't depend on the order of the map
+    let mut groups: Vec<(&String, &Vec<String>)> = subgroups.iter().collect();
+    groups.sort_unstable();
+
+    let mut parents: HashMap<&str, &str> = HashMap::new();
+    for (group, members) in &groups {
+        for member in members.iter() {
+            if let Some(other) = parents.insert(member, group) {
+                return Err(SlinkyError::SectionInMultipleSubgroups {
+                    section: member.clone(),
+                    group1: other.to_string(),
+                    group2: group.to_string(),
+                });
+            }
+        }
+    }
+
+    for (group, _) in &groups {
+        let mut current = group.as_str();
+        // Each section has a single parent, so the chain either ends or
+        // loops within `parents.len()` steps
+        for _ in 0..parents.len() {
+            match parents.get(current) {
+                None => break,
+                Some(parent) if *parent == group.as_str() => {
+                    return Err(SlinkyError::SectionsSubgroupsCycle {
+                        section: group.to_string(),
+                    })
+                }
+                Some(parent) => current = parent,
+            }
+        }
+    }
+
+    for (field_name, list) in sections {
+        for section in list.iter() {
+            if let Some(group) = parents.get(section.as_str()) {
+                return Err(SlinkyError::SubgroupSectionIsTopLevel {
+                    section: section.clone(),
+                    group: group.to_string(),
+                    field_name: field_name.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
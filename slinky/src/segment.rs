@@ -3,48 +3,92 @@
 
 use std::{borrow::Cow, collections::HashMap, path::PathBuf};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     absent_nullable::AbsentNullable,
     file_info::{FileInfo, FileInfoSerial},
+    files_list,
     gp_info::{GpInfo, GpInfoSerial},
-    traits::Serial,
-    EscapedPath, KeepSections, RuntimeSettings, Settings, SlinkyError,
+    ignored_metadata::IgnoredMetadata,
+    linker_symbols_style::resolve_symbol_expression,
+    section_entry::{
+        check_sections_subgroups, unserialize_section_entries, SectionEntriesSerial,
+        SectionsProperties,
+    },
+    traits::{unserialize_entries, Serial},
+    utils, Document, EntryPathComponent, EscapedPath, KeepSections, LinkerSymbolsStyle, OrderBy,
+    RomModel, RuntimeSettings, ScriptFlavor, Settings, SlinkyError,
 };
 
-#[derive(PartialEq, Debug, Clone)]
+/// Adjusts the segments of a document to the given flavor of the linker
+/// script. The matching flavor uses them as they are.
+pub(crate) fn segments_for_flavor(
+    segments: &[Segment],
+    flavor: ScriptFlavor,
+) -> Cow<'_, [Segment]> {
+    match flavor {
+        ScriptFlavor::Matching => Cow::Borrowed(segments),
+        ScriptFlavor::Shiftable => {
+            Cow::Owned(segments.iter().map(Segment::clone_as_shiftable).collect())
+        }
+    }
+}
+
+/// The fields which can be listed on `only_matching_fields`.
+const ONLY_MATCHING_ALLOWED_FIELDS: [&str; 3] = ["fixed_vram", "fixed_symbol", "reserved_rom_size"];
+
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct Segment {
     /// Name of the segment
     pub name: String,
 
+    /// Name used for the generated linker symbols of this segment.
+    /// Defaults to `name`.
+    pub symbol_name: String,
+
     /// List of files corresponding to this segment
     pub files: Vec<FileInfo>,
 
+    /// An external list the files of this segment were read from, after the
+    /// ones listed on the document. Those files are already part of `files`.
+    #[serde(skip)]
+    pub files_from: Option<PathBuf>,
+
     /// If not None then forces the segment to have a fixed vram address instead of following the previous segment.
     /// Not compatible with `fixed_symbol`, `follows_segment` or `vram_class`.
-    pub fixed_vram: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_vram: Option<u64>,
 
     /// If not None then forces the segment's vram address to be same as the address of the given symbol instead of following the previous segment.
+    /// Can also be an expression, like `other_VRAM_END + 0x400`, see [`Segment::resolved_fixed_symbol`].
     /// Not compatible with `fixed_vram`, `follows_segment` or `vram_class`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fixed_symbol: Option<String>,
 
     /// If not None then forces the segment's vram address to be after the specified segment instead of following the previous one.
     /// Not compatible with `fixed_vram`, `fixed_symbol` or `vram_class`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub follows_segment: Option<String>,
 
     /// If not None then forces the segment's vram address to be same as the specified vram class instead of following the previous one.
     /// Not compatible with `fixed_vram`, `fixed_symbol` or `follows_segment`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub vram_class: Option<String>,
 
     /// Used as a prefix for all the files emitted for this Segment.
     pub dir: PathBuf,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub gp_info: Option<GpInfo>,
 
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_all: Vec<(String, String)>,
 
     // The default value of the following members come from Settings
@@ -54,18 +98,113 @@ pub struct Segment {
     pub subalign: Option<u32>,
     pub segment_start_align: Option<u32>,
     pub segment_end_align: Option<u32>,
+    pub noload_start_align: Option<u32>,
     pub section_start_align: Option<u32>,
     pub section_end_align: Option<u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
     pub sections_start_alignment: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
     pub sections_end_alignment: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_subalign: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_header_align: HashMap<String, u32>,
 
     pub wildcard_sections: bool,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_wildcard: HashMap<String, bool>,
 
     pub fill_value: Option<u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_fill_value: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_order_by: HashMap<String, OrderBy>,
 
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
     pub sections_subgroups: HashMap<String, Vec<String>>,
 
+    pub keep_linker_offset_anchors: bool,
+
+    pub check_fixed_vram_overlaps: bool,
+
+    pub compressed: bool,
+
+    /// If true then the contents of this segment are taken from the
+    /// handwritten linker script fragment at `fragment_path` instead of being
+    /// generated from `files`.
+    pub external: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment_path: Option<PathBuf>,
+
+    /// The amount of rom this segment always takes, no matter its actual
+    /// size, so the rom address of the following segments is stable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserved_rom_size: Option<u32>,
+
+    /// A tag naming how the files of this segment are built, like `ido`,
+    /// `gcc` or `asm`. It is carried into the other generated files, so
+    /// per-toolchain build rules can be derived from the document.
+    ///
+    /// Known toolchains (`ido`, `gcc_modern`, `armips` and `psyq`) also pick
+    /// the default sections of the segment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+
+    /// If true then an `ASSERT` is emitted checking that nothing was placed
+    /// between the alloc and the noload sections of this segment.
+    pub check_noload_contiguity: bool,
+
+    /// Consecutive segments sharing the same `overlay_group` are emitted
+    /// together inside a single `OVERLAY` statement, so all of them start at
+    /// the same vram address while being placed one after the other on rom.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overlay_group: Option<String>,
+
+    /// The name of another segment where the noload sections of this segment
+    /// are placed instead, next to the noload sections of every other segment
+    /// merged into it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noload_merge: Option<String>,
+
+    /// Segments with a `rom_order` are always placed on the rom in increasing
+    /// order, even when the segments are reordered by their dependencies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rom_order: Option<u32>,
+
+    /// Consecutive segments sharing the same `group` are wrapped with
+    /// `#region` and `#endregion` comments on the linker script.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    /// Written as a comment right before the segment on the linker script.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// If true then every section of each file of this segment is bracketed
+    /// with its own start and end symbols. Files can override it.
+    pub emit_file_symbols: bool,
+
+    /// If true then the rom start, end and size symbols of this segment are
+    /// not defined by the linker script, since something else, like
+    /// hand-written assembly, already defines them. They are still tracked as
+    /// generated symbols, so they are listed on the symbols header.
+    pub suppress_rom_symbols: bool,
+    /// Same as `suppress_rom_symbols`, but for the vram start, end and size
+    /// symbols.
+    pub suppress_vram_symbols: bool,
+    /// Any other symbol generated for this segment which must not be defined
+    /// by the linker script.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppress_symbols: Vec<String>,
+
+    /// Fields of this segment which are only honored by the matching flavor
+    /// of the linker script. The shiftable flavor behaves as if they were
+    /// never set.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub only_matching_fields: Vec<String>,
+
     // The default value of the following members come from the corresponding VramClass
+    #[serde(skip_serializing_if = "KeepSections::is_absent")]
     pub keep_sections: KeepSections,
 }
 
@@ -73,7 +212,9 @@ impl Segment {
     pub fn clone_with_new_files(&self, new_files: Vec<FileInfo>) -> Self {
         Self {
             name: self.name.clone(),
+            symbol_name: self.symbol_name.clone(),
             files: new_files,
+            files_from: self.files_from.clone(),
             fixed_vram: self.fixed_vram,
             fixed_symbol: self.fixed_symbol.clone(),
             follows_segment: self.follows_segment.clone(),
@@ -89,17 +230,63 @@ impl Segment {
             subalign: self.subalign,
             segment_start_align: self.segment_start_align,
             segment_end_align: self.segment_end_align,
+            noload_start_align: self.noload_start_align,
             section_start_align: self.section_start_align,
             section_end_align: self.section_end_align,
             sections_start_alignment: self.sections_start_alignment.clone(),
             sections_end_alignment: self.sections_end_alignment.clone(),
+            sections_subalign: self.sections_subalign.clone(),
+            sections_header_align: self.sections_header_align.clone(),
             wildcard_sections: self.wildcard_sections,
+            sections_wildcard: self.sections_wildcard.clone(),
             fill_value: self.fill_value,
+            sections_fill_value: self.sections_fill_value.clone(),
+            sections_order_by: self.sections_order_by.clone(),
             sections_subgroups: self.sections_subgroups.clone(),
+            keep_linker_offset_anchors: self.keep_linker_offset_anchors,
+            check_fixed_vram_overlaps: self.check_fixed_vram_overlaps,
+            compressed: self.compressed,
+            external: self.external,
+            fragment_path: self.fragment_path.clone(),
+            reserved_rom_size: self.reserved_rom_size,
+            toolchain: self.toolchain.clone(),
+            check_noload_contiguity: self.check_noload_contiguity,
+            overlay_group: self.overlay_group.clone(),
+            noload_merge: self.noload_merge.clone(),
+            rom_order: self.rom_order,
+            group: self.group.clone(),
+            comment: self.comment.clone(),
+            emit_file_symbols: self.emit_file_symbols,
+            suppress_rom_symbols: self.suppress_rom_symbols,
+            suppress_vram_symbols: self.suppress_vram_symbols,
+            suppress_symbols: self.suppress_symbols.clone(),
+            only_matching_fields: self.only_matching_fields.clone(),
             keep_sections: self.keep_sections.clone(),
         }
     }
 
+    /// Returns a copy of this segment without the fields and files which
+    /// are only honored by the matching flavor of the linker script.
+    fn clone_as_shiftable(&self) -> Self {
+        let mut segment = self.clone();
+
+        for field in &self.only_matching_fields {
+            match field.as_str() {
+                "fixed_vram" => segment.fixed_vram = None,
+                "fixed_symbol" => segment.fixed_symbol = None,
+                "reserved_rom_size" => segment.reserved_rom_size = None,
+                _ => {}
+            }
+        }
+        segment.files.retain(|f| !f.only_matching);
+        segment
+            .files
+            .iter_mut()
+            .for_each(|f| f.retain_shiftable_files());
+
+        segment
+    }
+
     pub fn pass_down_keep_sections(&mut self, keep_sections: &KeepSections) {
         if *keep_sections == KeepSections::Absent {
             return;
@@ -119,16 +306,73 @@ impl Segment {
     pub fn dir_escaped(&self, rs: &RuntimeSettings) -> Result<EscapedPath, SlinkyError> {
         rs.escape_path(&self.dir)
     }
+
+    pub fn fragment_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.fragment_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The `fixed_symbol` of this segment, with the symbols generated for the
+    /// document it references renamed to the `linker_symbols_style` of the
+    /// document.
+    ///
+    /// Those references can be either wrapped in `{{ }}` or bare `splat`
+    /// style names, like `boot_VRAM_END`. Referencing a `splat` style name
+    /// which isn't generated for the document is an error.
+    pub fn resolved_fixed_symbol(&self, d: &Document) -> Result<Option<String>, SlinkyError> {
+        self.fixed_symbol
+            .as_ref()
+            .map(|x| resolve_symbol_expression(d, x))
+            .transpose()
+    }
+
+    /// The generated symbols of this segment which the linker script must not
+    /// define, from `suppress_rom_symbols`, `suppress_vram_symbols` and
+    /// `suppress_symbols`.
+    pub fn suppressed_symbols(&self, style: &LinkerSymbolsStyle) -> Vec<String> {
+        let mut symbols = Vec::new();
+
+        if self.suppress_rom_symbols {
+            symbols.push(style.segment_rom_start(&self.symbol_name));
+            symbols.push(style.segment_rom_end(&self.symbol_name));
+            symbols.push(style.segment_rom_size(&self.symbol_name));
+        }
+        if self.suppress_vram_symbols {
+            symbols.push(style.segment_vram_start(&self.symbol_name));
+            symbols.push(style.segment_vram_end(&self.symbol_name));
+            symbols.push(style.segment_vram_size(&self.symbol_name));
+        }
+        symbols.extend(self.suppress_symbols.iter().cloned());
+
+        symbols
+    }
 }
 
-#[derive(Deserialize, PartialEq, Debug)]
+#[derive(Deserialize, PartialEq, Debug, Default)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct SegmentSerial {
     pub name: String,
+
+    /// The name of the segment template this segment takes the values of
+    /// its absent attributes from.
+    #[serde(default)]
+    pub extends: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub symbol_name: AbsentNullable<String>,
+
+    #[serde(default)]
     pub files: Vec<FileInfoSerial>,
+    #[serde(default)]
+    pub files_from: AbsentNullable<PathBuf>,
 
     #[serde(default)]
-    pub fixed_vram: AbsentNullable<u32>,
+    pub fixed_vram: AbsentNullable<u64>,
 
     #[serde(default)]
     pub fixed_symbol: AbsentNullable<String>,
@@ -156,9 +400,9 @@ pub(crate) struct SegmentSerial {
 
     // The default of the following come from Options
     #[serde(default)]
-    pub alloc_sections: AbsentNullable<Vec<String>>,
+    pub alloc_sections: AbsentNullable<SectionEntriesSerial>,
     #[serde(default)]
-    pub noload_sections: AbsentNullable<Vec<String>>,
+    pub noload_sections: AbsentNullable<SectionEntriesSerial>,
 
     #[serde(default)]
     pub subalign: AbsentNullable<u32>,
@@ -167,6 +411,8 @@ pub(crate) struct SegmentSerial {
     #[serde(default)]
     pub segment_end_align: AbsentNullable<u32>,
     #[serde(default)]
+    pub noload_start_align: AbsentNullable<u32>,
+    #[serde(default)]
     pub section_start_align: AbsentNullable<u32>,
     #[serde(default)]
     pub section_end_align: AbsentNullable<u32>,
@@ -174,18 +420,268 @@ pub(crate) struct SegmentSerial {
     pub sections_start_alignment: AbsentNullable<HashMap<String, u32>>,
     #[serde(default)]
     pub sections_end_alignment: AbsentNullable<HashMap<String, u32>>,
+    #[serde(default)]
+    pub sections_subalign: AbsentNullable<HashMap<String, u32>>,
+    #[serde(default)]
+    pub sections_header_align: AbsentNullable<HashMap<String, u32>>,
 
     #[serde(default)]
     pub wildcard_sections: AbsentNullable<bool>,
+    #[serde(default)]
+    pub sections_wildcard: AbsentNullable<HashMap<String, bool>>,
 
     #[serde(default)]
     pub fill_value: AbsentNullable<u32>,
+    #[serde(default)]
+    pub sections_fill_value: AbsentNullable<HashMap<String, u32>>,
+    #[serde(default)]
+    pub sections_order_by: AbsentNullable<HashMap<String, OrderBy>>,
 
     #[serde(default)]
     pub sections_subgroups: AbsentNullable<HashMap<String, Vec<String>>>,
 
+    #[serde(default)]
+    pub keep_linker_offset_anchors: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub check_fixed_vram_overlaps: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub compressed: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub external: AbsentNullable<bool>,
+    #[serde(default)]
+    pub fragment_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub reserved_rom_size: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub toolchain: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub check_noload_contiguity: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub overlay_group: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub noload_merge: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub rom_order: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub group: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub comment: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub emit_file_symbols: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub suppress_rom_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub suppress_vram_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub suppress_symbols: AbsentNullable<Vec<String>>,
+
+    #[serde(default)]
+    pub only_matching_fields: AbsentNullable<Vec<String>>,
+
     #[serde(default)]
     pub keep_sections: KeepSections,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl SegmentSerial {
+    /// Fills every absent attribute of this segment with the value of
+    /// `template`. The name and the files, including `files_from`, are never
+    /// inherited.
+    fn inherit(self, template: &SegmentSerial) -> Self {
+        let keep_sections = if self.keep_sections.is_absent() {
+            template.keep_sections.clone()
+        } else {
+            self.keep_sections
+        };
+
+        Self {
+            name: self.name,
+            extends: AbsentNullable::Absent,
+            symbol_name: self.symbol_name.or_inherit(&template.symbol_name),
+            files: self.files,
+            files_from: self.files_from,
+            fixed_vram: self.fixed_vram.or_inherit(&template.fixed_vram),
+            fixed_symbol: self.fixed_symbol.or_inherit(&template.fixed_symbol),
+            follows_segment: self.follows_segment.or_inherit(&template.follows_segment),
+            vram_class: self.vram_class.or_inherit(&template.vram_class),
+            dir: self.dir.or_inherit(&template.dir),
+            gp_info: self.gp_info.or_inherit(&template.gp_info),
+            include_if_any: self.include_if_any.or_inherit(&template.include_if_any),
+            include_if_all: self.include_if_all.or_inherit(&template.include_if_all),
+            exclude_if_any: self.exclude_if_any.or_inherit(&template.exclude_if_any),
+            exclude_if_all: self.exclude_if_all.or_inherit(&template.exclude_if_all),
+            alloc_sections: self.alloc_sections.or_inherit(&template.alloc_sections),
+            noload_sections: self.noload_sections.or_inherit(&template.noload_sections),
+            subalign: self.subalign.or_inherit(&template.subalign),
+            segment_start_align: self
+                .segment_start_align
+                .or_inherit(&template.segment_start_align),
+            segment_end_align: self
+                .segment_end_align
+                .or_inherit(&template.segment_end_align),
+            noload_start_align: self
+                .noload_start_align
+                .or_inherit(&template.noload_start_align),
+            section_start_align: self
+                .section_start_align
+                .or_inherit(&template.section_start_align),
+            section_end_align: self
+                .section_end_align
+                .or_inherit(&template.section_end_align),
+            sections_start_alignment: self
+                .sections_start_alignment
+                .or_inherit(&template.sections_start_alignment),
+            sections_end_alignment: self
+                .sections_end_alignment
+                .or_inherit(&template.sections_end_alignment),
+            sections_subalign: self
+                .sections_subalign
+                .or_inherit(&template.sections_subalign),
+            sections_header_align: self
+                .sections_header_align
+                .or_inherit(&template.sections_header_align),
+            wildcard_sections: self
+                .wildcard_sections
+                .or_inherit(&template.wildcard_sections),
+            sections_wildcard: self
+                .sections_wildcard
+                .or_inherit(&template.sections_wildcard),
+            fill_value: self.fill_value.or_inherit(&template.fill_value),
+            sections_fill_value: self
+                .sections_fill_value
+                .or_inherit(&template.sections_fill_value),
+            sections_order_by: self
+                .sections_order_by
+                .or_inherit(&template.sections_order_by),
+            sections_subgroups: self
+                .sections_subgroups
+                .or_inherit(&template.sections_subgroups),
+            keep_linker_offset_anchors: self
+                .keep_linker_offset_anchors
+                .or_inherit(&template.keep_linker_offset_anchors),
+            check_fixed_vram_overlaps: self
+                .check_fixed_vram_overlaps
+                .or_inherit(&template.check_fixed_vram_overlaps),
+            compressed: self.compressed.or_inherit(&template.compressed),
+            external: self.external.or_inherit(&template.external),
+            fragment_path: self.fragment_path.or_inherit(&template.fragment_path),
+            reserved_rom_size: self
+                .reserved_rom_size
+                .or_inherit(&template.reserved_rom_size),
+            toolchain: self.toolchain.or_inherit(&template.toolchain),
+            check_noload_contiguity: self
+                .check_noload_contiguity
+                .or_inherit(&template.check_noload_contiguity),
+            overlay_group: self.overlay_group.or_inherit(&template.overlay_group),
+            noload_merge: self.noload_merge.or_inherit(&template.noload_merge),
+            rom_order: self.rom_order.or_inherit(&template.rom_order),
+            group: self.group.or_inherit(&template.group),
+            comment: self.comment.or_inherit(&template.comment),
+            emit_file_symbols: self
+                .emit_file_symbols
+                .or_inherit(&template.emit_file_symbols),
+            suppress_rom_symbols: self
+                .suppress_rom_symbols
+                .or_inherit(&template.suppress_rom_symbols),
+            suppress_vram_symbols: self
+                .suppress_vram_symbols
+                .or_inherit(&template.suppress_vram_symbols),
+            suppress_symbols: self.suppress_symbols.or_inherit(&template.suppress_symbols),
+            only_matching_fields: self
+                .only_matching_fields
+                .or_inherit(&template.only_matching_fields),
+            keep_sections,
+            metadata: self.metadata,
+        }
+    }
+
+    /// Replaces the `extends` of this segment with the attributes of the
+    /// referenced template, which must be one of `templates`.
+    fn extend_from(mut self, templates: &[SegmentSerial]) -> Result<Self, SlinkyError> {
+        let extends = std::mem::take(&mut self.extends);
+        let template_name = match extends.get_non_null_no_default("extends")? {
+            Some(name) => name,
+            None => return Ok(self),
+        };
+
+        match templates.iter().find(|x| x.name == template_name) {
+            Some(template) => Ok(self.inherit(template)),
+            None => Err(SlinkyError::MissingSegmentTemplate {
+                template: template_name,
+            }),
+        }
+    }
+}
+
+/// Applies the `segment_templates` to the segments extending them.
+///
+/// A template can extend another template as long as the latter is declared
+/// before it, which rules out cycles.
+pub(crate) fn apply_segment_templates(
+    templates: Vec<SegmentSerial>,
+    segments: Vec<SegmentSerial>,
+) -> Result<Vec<SegmentSerial>, SlinkyError> {
+    let mut resolved: Vec<SegmentSerial> = Vec::with_capacity(templates.len());
+
+    for (index, template) in templates.into_iter().enumerate() {
+        let entry = EntryPathComponent::SegmentTemplate {
+            index,
+            name: template.name.clone(),
+        };
+
+        let result = if template.name.is_empty() {
+            Err(SlinkyError::EmptyValue {
+                name: "name".to_string(),
+            })
+        } else if resolved.iter().any(|x| x.name == template.name) {
+            Err(SlinkyError::DuplicatedSegmentTemplate {
+                template: template.name.clone(),
+            })
+        } else if !template.files.is_empty() {
+            Err(SlinkyError::InvalidFieldCombo {
+                field1: "files".to_string(),
+                field2: "segment_templates".to_string(),
+            })
+        } else if template.files_from.has_value() {
+            Err(SlinkyError::InvalidFieldCombo {
+                field1: "files_from".to_string(),
+                field2: "segment_templates".to_string(),
+            })
+        } else {
+            template.extend_from(&resolved)
+        };
+
+        resolved.push(result.map_err(|e| e.in_entry(entry))?);
+    }
+
+    segments
+        .into_iter()
+        .enumerate()
+        .map(|(index, segment)| {
+            let entry = EntryPathComponent::Segment {
+                index,
+                name: segment.name.clone(),
+            };
+            segment
+                .extend_from(&resolved)
+                .map_err(|e| e.in_entry(entry))
+        })
+        .collect()
 }
 
 impl Serial for SegmentSerial {
@@ -199,13 +695,65 @@ impl Serial for SegmentSerial {
         }
         let name = self.name;
 
-        if self.files.is_empty() {
+        let symbol_name = self
+            .symbol_name
+            .get_non_null_not_empty("symbol_name", || name.clone())?;
+
+        let external = self.external.get_non_null("external", || false)?;
+        let fragment_path = self
+            .fragment_path
+            .get_non_null_no_default("fragment_path")?;
+
+        let files_from = self.files_from.get_non_null_no_default("files_from")?;
+        if files_from
+            .as_ref()
+            .map_or(false, |x| x.as_os_str().is_empty())
+        {
+            return Err(SlinkyError::EmptyValue {
+                name: "files_from".to_string(),
+            });
+        }
+
+        if external {
+            if !self.files.is_empty() {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "external".to_string(),
+                    field2: "files".to_string(),
+                });
+            }
+            if files_from.is_some() {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "external".to_string(),
+                    field2: "files_from".to_string(),
+                });
+            }
+            if fragment_path.is_none() {
+                return Err(SlinkyError::MissingRequiredFieldCombo {
+                    required: "fragment_path".to_string(),
+                    other: "external".to_string(),
+                });
+            }
+        } else if fragment_path.is_some() {
+            return Err(SlinkyError::MissingRequiredFieldCombo {
+                required: "external".to_string(),
+                other: "fragment_path".to_string(),
+            });
+        }
+
+        // The files of the list go after the ones of the document
+        let mut files_serial = self.files;
+        if let Some(files_from) = &files_from {
+            files_serial.extend(files_list::read_files_list(files_from)?);
+        }
+        if !external && files_serial.is_empty() {
             return Err(SlinkyError::EmptyValue {
                 name: "files".to_string(),
             });
         }
 
-        let mut files = self.files.unserialize(settings)?;
+        let mut files = unserialize_entries(files_serial, settings, |index, _| {
+            EntryPathComponent::File { index }
+        })?;
 
         let fixed_vram = self.fixed_vram.get_non_null_no_default("fixed_vram")?;
 
@@ -270,13 +818,25 @@ impl Serial for SegmentSerial {
         let gp_info = self
             .gp_info
             .get_non_null_no_default("gp_info")?
-            .unserialize(settings)?;
+            .unserialize(settings)
+            .map_err(|e| e.in_entry(EntryPathComponent::GpInfo))?;
         if gp_info.is_some() && settings.hardcoded_gp_value.is_some() {
             return Err(SlinkyError::InvalidFieldCombo {
                 field1: "segment.gp_info".to_string(),
                 field2: "settings.hardcoded_gp_value".to_string(),
             });
         }
+        // The small data range is computed from the section symbols
+        if gp_info
+            .as_ref()
+            .map_or(false, |x| x.midpoint || x.check_range)
+            && !settings.section_symbols
+        {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "segment.gp_info".to_string(),
+                field2: "settings.section_symbols".to_string(),
+            });
+        }
 
         let include_if_any = self
             .include_if_any
@@ -291,23 +851,42 @@ impl Serial for SegmentSerial {
             .exclude_if_all
             .get_non_null_not_empty("exclude_if_all", Vec::new)?;
 
-        let alloc_sections = self
-            .alloc_sections
-            .get_non_null("alloc_sections", || settings.alloc_sections.clone())?;
-        let noload_sections = self
-            .noload_sections
-            .get_non_null("noload_sections", || settings.noload_sections.clone())?;
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
+        let mut sections_properties = SectionsProperties::default();
+        let alloc_sections = unserialize_section_entries(
+            self.alloc_sections,
+            "alloc_sections",
+            || settings.alloc_sections.clone(),
+            &mut sections_properties,
+        )?;
+        let noload_sections = unserialize_section_entries(
+            self.noload_sections,
+            "noload_sections",
+            || settings.noload_sections.clone(),
+            &mut sections_properties,
+        )?;
 
         if let Some(gp) = &gp_info {
-            if !alloc_sections.contains(&gp.section) && !noload_sections.contains(&gp.section) {
-                return Err(SlinkyError::MissingSectionForSegment {
-                    field_name: Cow::from("gp_info"),
-                    section: Cow::from(gp_info.unwrap().section),
-                    segment: Cow::from(name),
-                });
+            for section in std::iter::once(&gp.section).chain(&gp.small_data_sections) {
+                if !alloc_sections.contains(section) && !noload_sections.contains(section) {
+                    return Err(SlinkyError::MissingSectionForSegment {
+                        field_name: Cow::from("gp_info"),
+                        section: Cow::from(section.clone()),
+                        segment: Cow::from(name),
+                    });
+                }
             }
         }
 
+        // An explicit `null` disables every `SUBALIGN` of this segment,
+        // including the per section ones inherited from the settings
+        let subalign_disabled = matches!(self.subalign, AbsentNullable::Null);
         let subalign = self
             .subalign
             .get_optional_nullable("subalign", || settings.subalign)?;
@@ -320,6 +899,10 @@ impl Serial for SegmentSerial {
             .segment_end_align
             .get_optional_nullable("segment_end_align", || settings.segment_end_align)?;
 
+        let noload_start_align = self
+            .noload_start_align
+            .get_optional_nullable("noload_start_align", || settings.noload_start_align)?;
+
         let section_start_align = self
             .section_start_align
             .get_optional_nullable("section_start_align", || settings.section_start_align)?;
@@ -328,31 +911,247 @@ impl Serial for SegmentSerial {
             .section_end_align
             .get_optional_nullable("section_end_align", || settings.section_end_align)?;
 
-        let sections_start_alignment = self
+        let mut sections_start_alignment = self
             .sections_start_alignment
             .get_non_null("sections_start_alignment", || {
                 settings.sections_start_alignment.clone()
             })?;
 
-        let sections_end_alignment = self
+        let mut sections_end_alignment = self
             .sections_end_alignment
             .get_non_null("sections_end_alignment", || {
                 settings.sections_end_alignment.clone()
             })?;
 
+        let sections_subalign = self
+            .sections_subalign
+            .get_non_null("sections_subalign", || {
+                if subalign_disabled {
+                    HashMap::new()
+                } else {
+                    settings.sections_subalign.clone()
+                }
+            })?;
+        let sections_header_align = self
+            .sections_header_align
+            .get_non_null("sections_header_align", || {
+                settings.sections_header_align.clone()
+            })?;
+
+        // Every section is its own output section only on single segment mode
+        for (name, value) in [
+            ("sections_subalign", &sections_subalign),
+            ("sections_header_align", &sections_header_align),
+        ] {
+            if !value.is_empty() && !settings.single_segment_mode {
+                return Err(SlinkyError::MissingRequiredFieldCombo {
+                    required: "single_segment_mode".to_string(),
+                    other: name.to_string(),
+                });
+            }
+        }
+
         let wildcard_sections = self
             .wildcard_sections
             .get_non_null("wildcard_sections", || settings.wildcard_sections)?;
+        let mut sections_wildcard = self
+            .sections_wildcard
+            .get_non_null("sections_wildcard", || settings.sections_wildcard.clone())?;
 
         let fill_value = self
             .fill_value
             .get_optional_nullable("fill_value", || settings.fill_value)?;
+        let mut sections_fill_value = self
+            .sections_fill_value
+            .get_non_null("sections_fill_value", || {
+                settings.sections_fill_value.clone()
+            })?;
+        let mut sections_order_by = self
+            .sections_order_by
+            .get_non_null("sections_order_by", || settings.sections_order_by.clone())?;
+
+        let mut keep_sections = self.keep_sections;
 
-        let keep_sections = self.keep_sections;
+        sections_properties.apply(
+            &mut sections_start_alignment,
+            &mut sections_end_alignment,
+            &mut sections_fill_value,
+            &mut sections_wildcard,
+            &mut sections_order_by,
+            Some(&mut keep_sections),
+        )?;
 
         let sections_subgroups = self
             .sections_subgroups
             .get_non_null("sections_subgroups", || settings.sections_subgroups.clone())?;
+        check_sections_subgroups(
+            &sections_subgroups,
+            &[
+                ("alloc_sections", &alloc_sections),
+                ("noload_sections", &noload_sections),
+            ],
+        )?;
+
+        let keep_linker_offset_anchors = self
+            .keep_linker_offset_anchors
+            .get_non_null("keep_linker_offset_anchors", || {
+                settings.keep_linker_offset_anchors
+            })?;
+
+        let check_fixed_vram_overlaps = self
+            .check_fixed_vram_overlaps
+            .get_non_null("check_fixed_vram_overlaps", || {
+                settings.check_fixed_vram_overlaps
+            })?;
+
+        let compressed = self.compressed.get_non_null("compressed", || false)?;
+        if compressed && external {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "compressed".to_string(),
+                field2: "external".to_string(),
+            });
+        }
+        if compressed && settings.rom_model == RomModel::None {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "compressed".to_string(),
+                field2: "rom_model".to_string(),
+            });
+        }
+
+        let reserved_rom_size = self
+            .reserved_rom_size
+            .get_non_null_no_default("reserved_rom_size")?;
+        if reserved_rom_size.is_some() && settings.single_segment_mode {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "reserved_rom_size".to_string(),
+                field2: "single_segment_mode".to_string(),
+            });
+        }
+        if reserved_rom_size.is_some() && settings.rom_model == RomModel::None {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "reserved_rom_size".to_string(),
+                field2: "rom_model".to_string(),
+            });
+        }
+
+        let toolchain = self
+            .toolchain
+            .get_optional_nullable("toolchain", || settings.toolchain.clone())?;
+        if toolchain.as_ref().map_or(false, |x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "toolchain".to_string(),
+            });
+        }
+
+        let check_noload_contiguity = self
+            .check_noload_contiguity
+            .get_non_null("check_noload_contiguity", || false)?;
+        if check_noload_contiguity && external {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "check_noload_contiguity".to_string(),
+                field2: "external".to_string(),
+            });
+        }
+
+        let overlay_group = self
+            .overlay_group
+            .get_non_null_no_default("overlay_group")?;
+        if let Some(group) = &overlay_group {
+            if group.is_empty() {
+                return Err(SlinkyError::EmptyValue {
+                    name: "overlay_group".to_string(),
+                });
+            }
+
+            // The members of an `OVERLAY` are always placed by the linker
+            let invalid_combos = [
+                ("vram_class", vram_class.is_some()),
+                ("external", external),
+                ("compressed", compressed),
+                ("reserved_rom_size", reserved_rom_size.is_some()),
+                ("settings.single_segment_mode", settings.single_segment_mode),
+            ];
+            for (field, is_set) in invalid_combos {
+                if is_set {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "overlay_group".to_string(),
+                        field2: field.to_string(),
+                    });
+                }
+            }
+        }
+
+        let noload_merge = self.noload_merge.get_non_null_no_default("noload_merge")?;
+        if let Some(shared) = &noload_merge {
+            if shared.is_empty() {
+                return Err(SlinkyError::EmptyValue {
+                    name: "noload_merge".to_string(),
+                });
+            }
+
+            // There is no noload section left to check or handwrite
+            let invalid_combos = [
+                ("external", external),
+                ("check_noload_contiguity", check_noload_contiguity),
+                ("settings.single_segment_mode", settings.single_segment_mode),
+            ];
+            for (field, is_set) in invalid_combos {
+                if is_set {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: "noload_merge".to_string(),
+                        field2: field.to_string(),
+                    });
+                }
+            }
+        }
+
+        let rom_order = self.rom_order.get_non_null_no_default("rom_order")?;
+
+        let group = self.group.get_non_null_no_default("group")?;
+        if group.as_ref().map_or(false, |x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "group".to_string(),
+            });
+        }
+        let comment = self.comment.get_non_null_no_default("comment")?;
+
+        let emit_file_symbols = self
+            .emit_file_symbols
+            .get_non_null("emit_file_symbols", || false)?;
+        if emit_file_symbols && external {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "emit_file_symbols".to_string(),
+                field2: "external".to_string(),
+            });
+        }
+
+        let suppress_rom_symbols = self
+            .suppress_rom_symbols
+            .get_non_null("suppress_rom_symbols", || false)?;
+        let suppress_vram_symbols = self
+            .suppress_vram_symbols
+            .get_non_null("suppress_vram_symbols", || false)?;
+        let suppress_symbols = self
+            .suppress_symbols
+            .get_non_null_not_empty("suppress_symbols", Vec::new)?;
+        if suppress_symbols.iter().any(|x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "suppress_symbols".to_string(),
+            });
+        }
+
+        let only_matching_fields = self
+            .only_matching_fields
+            .get_non_null_not_empty("only_matching_fields", Vec::new)?;
+        for field in &only_matching_fields {
+            if !ONLY_MATCHING_ALLOWED_FIELDS.contains(&field.as_str()) {
+                return Err(SlinkyError::InvalidFieldValue {
+                    name: "only_matching_fields".to_string(),
+                    value: field.clone(),
+                    allowed: ONLY_MATCHING_ALLOWED_FIELDS.join(", "),
+                });
+            }
+        }
 
         // Pass down the current `keep_sections` to files that may not have defined it
         if keep_sections != KeepSections::Absent {
@@ -363,7 +1162,9 @@ impl Serial for SegmentSerial {
 
         Ok(Self::Output {
             name,
+            symbol_name,
             files,
+            files_from,
             fixed_vram,
             fixed_symbol,
             follows_segment,
@@ -379,13 +1180,37 @@ impl Serial for SegmentSerial {
             subalign,
             segment_start_align,
             segment_end_align,
+            noload_start_align,
             section_start_align,
             section_end_align,
             sections_start_alignment,
             sections_end_alignment,
+            sections_subalign,
+            sections_header_align,
             wildcard_sections,
+            sections_wildcard,
             fill_value,
+            sections_fill_value,
+            sections_order_by,
             sections_subgroups,
+            keep_linker_offset_anchors,
+            check_fixed_vram_overlaps,
+            compressed,
+            external,
+            fragment_path,
+            reserved_rom_size,
+            toolchain,
+            check_noload_contiguity,
+            overlay_group,
+            noload_merge,
+            rom_order,
+            group,
+            comment,
+            emit_file_symbols,
+            suppress_rom_symbols,
+            suppress_vram_symbols,
+            suppress_symbols,
+            only_matching_fields,
             keep_sections,
         })
     }
@@ -0,0 +1,199 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::Write;
+
+use crate::{Document, RuntimeSettings, SlinkyError};
+
+const FLAG_OVERLAY: &str = "SEGMENT_FLAG_OVERLAY";
+const FLAG_NOLOAD: &str = "SEGMENT_FLAG_NOLOAD";
+const FLAG_COMPRESSED: &str = "SEGMENT_FLAG_COMPRESSED";
+
+/// A row of the segment table.
+pub(crate) struct SegmentTableEntry {
+    pub name: String,
+    pub id: String,
+    pub rom_start: String,
+    pub rom_end: String,
+    pub vram_start: String,
+    pub vram_end: String,
+    pub flags: Vec<&'static str>,
+}
+
+/// Collects every segment emitted for the given runtime settings, in the
+/// same order they are placed on the linker script.
+pub(crate) fn collect_segment_table(d: &Document, rs: &RuntimeSettings) -> Vec<SegmentTableEntry> {
+    let style = &d.settings.linker_symbols_style;
+
+    d.segments
+        .iter()
+        .filter(|segment| {
+            rs.should_emit_entry(
+                &segment.exclude_if_any,
+                &segment.exclude_if_all,
+                &segment.include_if_any,
+                &segment.include_if_all,
+            )
+        })
+        .map(|segment| {
+            let mut flags = Vec::new();
+            if segment.vram_class.is_some() || segment.overlay_group.is_some() {
+                flags.push(FLAG_OVERLAY);
+            }
+            if segment.alloc_sections.is_empty() {
+                flags.push(FLAG_NOLOAD);
+            }
+            if segment.compressed {
+                flags.push(FLAG_COMPRESSED);
+            }
+
+            SegmentTableEntry {
+                name: segment.name.clone(),
+                id: segment_id(&segment.name),
+                rom_start: style.segment_rom_start(&segment.symbol_name),
+                rom_end: style.segment_rom_end(&segment.symbol_name),
+                vram_start: style.segment_vram_start(&segment.symbol_name),
+                vram_end: style.segment_vram_end(&segment.symbol_name),
+                flags,
+            }
+        })
+        .collect()
+}
+
+/// The name of the segment is uppercased and every character which can't be
+/// used on an identifier is replaced with an underscore.
+fn segment_id(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("SEGMENT_ID_{}", name)
+}
+
+/// The type of the table, its flags and the ids of the segments.
+fn declarations(entries: &[SegmentTableEntry]) -> String {
+    let mut contents = String::new();
+
+    contents += "typedef struct SegmentTableEntry {\n";
+    contents += "    const char *name;\n";
+    contents += "    void *romStart;\n";
+    contents += "    void *romEnd;\n";
+    contents += "    void *vramStart;\n";
+    contents += "    void *vramEnd;\n";
+    contents += "    unsigned int flags;\n";
+    contents += "} SegmentTableEntry;\n\n";
+
+    contents += &format!("#define {} (1 << 0)\n", FLAG_OVERLAY);
+    contents += &format!("#define {} (1 << 1)\n", FLAG_NOLOAD);
+    contents += &format!("#define {} (1 << 2)\n\n", FLAG_COMPRESSED);
+
+    contents += "typedef enum SegmentId {\n";
+    for entry in entries {
+        contents += &format!("    {},\n", entry.id);
+    }
+    contents += "    SEGMENT_ID_MAX\n";
+    contents += "} SegmentId;\n\n";
+
+    contents += "extern const SegmentTableEntry gSegmentTable[SEGMENT_ID_MAX];\n";
+
+    contents
+}
+
+pub(crate) fn write_segment_table_header(
+    dst: &mut impl Write,
+    entries: &[SegmentTableEntry],
+) -> Result<(), SlinkyError> {
+    let contents = format!(
+        "#ifndef HEADER_SEGMENT_TABLE_H\n#define HEADER_SEGMENT_TABLE_H\n\n{}\n#endif\n",
+        declarations(entries)
+    );
+
+    if let Err(e) = write!(dst, "{}", contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents,
+        });
+    }
+
+    Ok(())
+}
+
+/// The source includes `header_name` if given, otherwise it declares
+/// everything declared by the header by itself.
+///
+/// The linker symbols are declared the same way they are declared on the
+/// symbols header.
+pub(crate) fn write_segment_table_source(
+    dst: &mut impl Write,
+    entries: &[SegmentTableEntry],
+    header_name: Option<&str>,
+    symbols_type: &str,
+    symbols_as_array: bool,
+) -> Result<(), SlinkyError> {
+    let mut contents = match header_name {
+        Some(header_name) => format!("#include \"{}\"\n\n", header_name),
+        None => format!("{}\n", declarations(entries)),
+    };
+
+    let (arr_suffix, address_of) = if symbols_as_array {
+        ("[]", "")
+    } else {
+        ("", "&")
+    };
+
+    for entry in entries {
+        for sym in [
+            &entry.rom_start,
+            &entry.rom_end,
+            &entry.vram_start,
+            &entry.vram_end,
+        ] {
+            contents += &format!("extern {} {}{};\n", symbols_type, sym, arr_suffix);
+        }
+    }
+    if !entries.is_empty() {
+        contents += "\n";
+    }
+
+    // Designated initializers are avoided since old compilers, like IDO, don't
+    // support them. The rows follow the order of the ids instead
+    contents += "const SegmentTableEntry gSegmentTable[SEGMENT_ID_MAX] = {\n";
+    for entry in entries {
+        let flags = if entry.flags.is_empty() {
+            "0".to_string()
+        } else {
+            entry.flags.join(" | ")
+        };
+
+        contents += &format!(
+            "    {{ \"{}\", {}{}, {}{}, {}{}, {}{}, {} }},\n",
+            entry.name,
+            address_of,
+            entry.rom_start,
+            address_of,
+            entry.rom_end,
+            address_of,
+            entry.vram_start,
+            address_of,
+            entry.vram_end,
+            flags
+        );
+    }
+    contents += "};\n";
+
+    if let Err(e) = write!(dst, "{}", contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents,
+        });
+    }
+
+    Ok(())
+}
@@ -1,61 +1,244 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
-use std::{collections::HashMap, path::PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    absent_nullable::AbsentNullable, linker_symbols_style::LinkerSymbolsStyle, EscapedPath,
-    RuntimeSettings, SlinkyError,
+    absent_nullable::AbsentNullable,
+    discard_rule::{DiscardRule, DiscardRuleSerial},
+    ignored_metadata::IgnoredMetadata,
+    linker_symbols_style::LinkerSymbolsStyle,
+    order_by::OrderBy,
+    preset::Preset,
+    rom_model::RomModel,
+    section_entry::{
+        check_sections_subgroups, unserialize_section_entries, SectionEntriesSerial,
+        SectionsProperties,
+    },
+    symbols_asm_dialect::SymbolsAsmDialect,
+    utils, EscapedPath, FileKind, RuntimeSettings, SlinkyError,
 };
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<Preset>,
+
     pub base_path: PathBuf,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub file_kind_overrides: HashMap<String, FileKind>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub valid_conditions: HashMap<String, Vec<String>>,
     pub linker_symbols_style: LinkerSymbolsStyle,
 
-    pub hardcoded_gp_value: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardcoded_gp_value: Option<u64>,
 
+    pub linker_script_banner: Option<String>,
+    pub d_banner: Option<String>,
+    pub symbols_header_banner: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub d_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub target_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ninja_path: Option<PathBuf>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub symbols_header_path: Option<PathBuf>,
     pub symbols_header_type: String,
     pub symbols_header_as_array: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols_header_rom_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols_header_linker_offsets_path: Option<PathBuf>,
+    pub symbols_header_keep_user_additions: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols_asm_path: Option<PathBuf>,
+    pub symbols_asm_dialect: SymbolsAsmDialect,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols_rust_path: Option<PathBuf>,
+
+    pub vram_class_slack_symbols: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vram_class_slack_report_path: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_map_path: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_rules_path: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub objects_list_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toolchain_objects_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols_manifest_path: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_table_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_table_header_path: Option<PathBuf>,
+
+    /// Moves the parts of the linker script which don't depend on the custom
+    /// options to this path, `INCLUDE`d by the linker script.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub common_script_path: Option<PathBuf>,
+
+    /// Writes a GDB script to this path, with commands to load the symbols of
+    /// each segment sharing its vram with other segments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gdb_script_path: Option<PathBuf>,
+
+    pub bss_symbols: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bss_symbols_start_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bss_symbols_size_name: Option<String>,
+
+    pub rom_model: RomModel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rom_end_symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rom_size_symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_bss_size_symbol: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rom_size_alignment: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rom_min_size: Option<u32>,
 
     pub sections_allowlist: Vec<String>,
     pub sections_allowlist_extra: Vec<String>,
+    pub emit_debug_sections: bool,
     pub sections_denylist: Vec<String>,
     pub discard_wildcard_section: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub discard: Vec<DiscardRule>,
 
     pub single_segment_mode: bool,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_before: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub partial_scripts_folder: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub partial_build_segments_folder: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_build_path: Option<PathBuf>,
 
     // Options passed down to each segment
+    /// The default `toolchain` of the segments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
     pub alloc_sections: Vec<String>,
     pub noload_sections: Vec<String>,
 
     pub subalign: Option<u32>,
     pub segment_start_align: Option<u32>,
     pub segment_end_align: Option<u32>,
+    pub noload_start_align: Option<u32>,
     pub section_start_align: Option<u32>,
     pub section_end_align: Option<u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
     pub sections_start_alignment: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
     pub sections_end_alignment: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_subalign: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_header_align: HashMap<String, u32>,
 
     pub wildcard_sections: bool,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_wildcard: HashMap<String, bool>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub input_section_patterns: HashMap<String, Vec<String>>,
 
     pub fill_value: Option<u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_fill_value: HashMap<String, u32>,
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub sections_order_by: HashMap<String, OrderBy>,
 
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
     pub sections_subgroups: HashMap<String, Vec<String>>,
+
+    pub keep_linker_offset_anchors: bool,
+
+    pub check_fixed_vram_overlaps: bool,
+
+    pub hex_uppercase: bool,
+    pub hex_address_width: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hex_alignments: Option<bool>,
+
+    pub script_indent_width: u32,
+    pub script_indent_with_tabs: bool,
+    pub script_braces_on_same_line: bool,
+    pub script_blank_lines_between_segments: u32,
+    pub script_blank_lines_between_groups: u32,
+    pub script_trailing_newline: bool,
+
+    pub generated_symbols_provide: bool,
+    pub generated_symbols_hidden: bool,
+    pub generated_symbols_absolute: bool,
+
+    pub segment_size_symbols: bool,
+    pub sections_kind_symbols: bool,
+    pub sections_kind_size_symbols: bool,
+    pub section_symbols: bool,
+    pub section_size_symbols: bool,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub search_paths: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub libraries: Vec<String>,
+    pub libraries_group: bool,
+
+    pub skip_empty_sections: bool,
+
+    pub deterministic_output: bool,
+    pub sort_dependencies: bool,
+    pub d_generated_files: bool,
+    pub check_archive_members: bool,
+    pub check_object_sections: bool,
+
+    pub sort_segments_by_follows: bool,
+
+    pub group_required_symbols: bool,
+
+    pub aligned_section_start_symbols: bool,
+}
+
+const fn settings_default_preset() -> Option<Preset> {
+    None
 }
 
 fn settings_default_base_path() -> PathBuf {
     PathBuf::new()
 }
 
+fn settings_default_file_kind_overrides() -> HashMap<String, FileKind> {
+    HashMap::new()
+}
+
+fn settings_default_valid_conditions() -> HashMap<String, Vec<String>> {
+    HashMap::new()
+}
+
 const fn settings_default_linker_symbols_style() -> LinkerSymbolsStyle {
     LinkerSymbolsStyle::Splat
 }
@@ -68,10 +251,26 @@ const fn settings_default_target_path() -> Option<PathBuf> {
     None
 }
 
+const fn settings_default_ninja_path() -> Option<PathBuf> {
+    None
+}
+
 const fn settings_default_symbols_header_path() -> Option<PathBuf> {
     None
 }
 
+fn settings_default_linker_script_banner() -> Option<String> {
+    Some("/* Generated by slinky {version} */".to_string())
+}
+
+fn settings_default_d_banner() -> Option<String> {
+    Some("# Generated by slinky {version}".to_string())
+}
+
+fn settings_default_symbols_header_banner() -> Option<String> {
+    Some("/* Generated by slinky {version} */".to_string())
+}
+
 fn settings_default_symbols_header_type() -> String {
     "char".to_string()
 }
@@ -80,7 +279,111 @@ const fn settings_default_symbols_header_as_array() -> bool {
     true
 }
 
-const fn settings_default_hardcoded_gp_value() -> Option<u32> {
+const fn settings_default_symbols_header_rom_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_symbols_header_linker_offsets_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_symbols_header_keep_user_additions() -> bool {
+    false
+}
+
+const fn settings_default_symbols_asm_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_symbols_asm_dialect() -> SymbolsAsmDialect {
+    SymbolsAsmDialect::Extern
+}
+
+const fn settings_default_symbols_rust_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_vram_class_slack_symbols() -> bool {
+    false
+}
+
+const fn settings_default_vram_class_slack_report_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_memory_map_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_binary_rules_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_objects_list_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_toolchain_objects_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_symbols_manifest_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_segment_table_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_segment_table_header_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_common_script_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_gdb_script_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_bss_symbols() -> bool {
+    false
+}
+
+const fn settings_default_bss_symbols_start_name() -> Option<String> {
+    None
+}
+
+const fn settings_default_bss_symbols_size_name() -> Option<String> {
+    None
+}
+
+const fn settings_default_rom_model() -> RomModel {
+    RomModel::Rom
+}
+
+const fn settings_default_rom_end_symbol() -> Option<String> {
+    None
+}
+
+const fn settings_default_rom_size_symbol() -> Option<String> {
+    None
+}
+
+const fn settings_default_total_bss_size_symbol() -> Option<String> {
+    None
+}
+
+const fn settings_default_rom_size_alignment() -> Option<u32> {
+    None
+}
+
+const fn settings_default_rom_min_size() -> Option<u32> {
+    None
+}
+
+const fn settings_default_hardcoded_gp_value() -> Option<u64> {
     None
 }
 
@@ -92,6 +395,10 @@ fn settings_default_sections_allowlist_extra() -> Vec<String> {
     vec![".symtab".into(), ".strtab".into(), ".shstrtab".into()]
 }
 
+const fn settings_default_emit_debug_sections() -> bool {
+    false
+}
+
 fn settings_default_sections_denylist() -> Vec<String> {
     vec![
         ".reginfo".into(),
@@ -108,10 +415,22 @@ const fn settings_default_discard_wildcard_section() -> bool {
     true
 }
 
+const fn settings_default_discard() -> Vec<DiscardRule> {
+    Vec::new()
+}
+
 const fn settings_default_single_segment_mode() -> bool {
     false
 }
 
+const fn settings_default_insert_after() -> Option<String> {
+    None
+}
+
+const fn settings_default_insert_before() -> Option<String> {
+    None
+}
+
 const fn settings_default_partial_scripts_folder() -> Option<PathBuf> {
     None
 }
@@ -120,6 +439,14 @@ const fn settings_default_partial_build_segments_folder() -> Option<PathBuf> {
     None
 }
 
+const fn settings_default_partial_build_path() -> Option<PathBuf> {
+    None
+}
+
+const fn settings_default_toolchain() -> Option<String> {
+    None
+}
+
 fn settings_default_alloc_sections() -> Vec<String> {
     vec![
         ".text".into(),
@@ -150,6 +477,10 @@ const fn settings_default_segment_end_align() -> Option<u32> {
     None
 }
 
+const fn settings_default_noload_start_align() -> Option<u32> {
+    None
+}
+
 const fn settings_default_section_start_align() -> Option<u32> {
     None
 }
@@ -166,69 +497,351 @@ fn settings_default_sections_end_alignment() -> HashMap<String, u32> {
     HashMap::new()
 }
 
+fn settings_default_sections_subalign() -> HashMap<String, u32> {
+    HashMap::new()
+}
+
+fn settings_default_sections_header_align() -> HashMap<String, u32> {
+    HashMap::new()
+}
+
 const fn settings_default_wildcard_sections() -> bool {
     true
 }
 
+fn settings_default_sections_wildcard() -> HashMap<String, bool> {
+    HashMap::new()
+}
+
+fn settings_default_input_section_patterns() -> HashMap<String, Vec<String>> {
+    HashMap::new()
+}
+
 const fn settings_default_fill_value() -> Option<u32> {
     Some(0)
 }
 
+fn settings_default_sections_fill_value() -> HashMap<String, u32> {
+    HashMap::new()
+}
+
+fn settings_default_sections_order_by() -> HashMap<String, OrderBy> {
+    HashMap::new()
+}
+
 fn settings_default_subsections_groups() -> HashMap<String, Vec<String>> {
     HashMap::new()
 }
 
+const fn settings_default_keep_linker_offset_anchors() -> bool {
+    false
+}
+
+const fn settings_default_check_fixed_vram_overlaps() -> bool {
+    false
+}
+
+const fn settings_default_hex_uppercase() -> bool {
+    true
+}
+
+const fn settings_default_hex_address_width() -> u32 {
+    8
+}
+
+const fn settings_default_hex_alignments() -> Option<bool> {
+    None
+}
+
+const fn settings_default_script_indent_width() -> u32 {
+    4
+}
+
+const fn settings_default_script_indent_with_tabs() -> bool {
+    false
+}
+
+const fn settings_default_script_braces_on_same_line() -> bool {
+    false
+}
+
+const fn settings_default_script_blank_lines_between_segments() -> u32 {
+    1
+}
+
+const fn settings_default_script_blank_lines_between_groups() -> u32 {
+    1
+}
+
+const fn settings_default_script_trailing_newline() -> bool {
+    true
+}
+
+const fn settings_default_generated_symbols_provide() -> bool {
+    false
+}
+
+const fn settings_default_generated_symbols_hidden() -> bool {
+    false
+}
+
+const fn settings_default_generated_symbols_absolute() -> bool {
+    false
+}
+
+const fn settings_default_segment_size_symbols() -> bool {
+    true
+}
+
+const fn settings_default_sections_kind_symbols() -> bool {
+    true
+}
+
+const fn settings_default_sections_kind_size_symbols() -> bool {
+    true
+}
+
+const fn settings_default_section_symbols() -> bool {
+    true
+}
+
+const fn settings_default_section_size_symbols() -> bool {
+    true
+}
+
+const fn settings_default_search_paths() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+const fn settings_default_libraries() -> Vec<String> {
+    Vec::new()
+}
+
+const fn settings_default_libraries_group() -> bool {
+    false
+}
+
+const fn settings_default_skip_empty_sections() -> bool {
+    false
+}
+
+const fn settings_default_deterministic_output() -> bool {
+    false
+}
+
+const fn settings_default_sort_dependencies() -> bool {
+    false
+}
+
+const fn settings_default_d_generated_files() -> bool {
+    false
+}
+
+const fn settings_default_check_archive_members() -> bool {
+    false
+}
+
+const fn settings_default_check_object_sections() -> bool {
+    false
+}
+
+const fn settings_default_sort_segments_by_follows() -> bool {
+    false
+}
+
+const fn settings_default_group_required_symbols() -> bool {
+    false
+}
+
+const fn settings_default_aligned_section_start_symbols() -> bool {
+    false
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            preset: settings_default_preset(),
+
             base_path: settings_default_base_path(),
+            file_kind_overrides: settings_default_file_kind_overrides(),
+            valid_conditions: settings_default_valid_conditions(),
             linker_symbols_style: settings_default_linker_symbols_style(),
 
             hardcoded_gp_value: settings_default_hardcoded_gp_value(),
 
+            linker_script_banner: settings_default_linker_script_banner(),
+            d_banner: settings_default_d_banner(),
+            symbols_header_banner: settings_default_symbols_header_banner(),
+
             d_path: settings_default_d_path(),
             target_path: settings_default_target_path(),
+            ninja_path: settings_default_ninja_path(),
 
             symbols_header_path: settings_default_symbols_header_path(),
             symbols_header_type: settings_default_symbols_header_type(),
             symbols_header_as_array: settings_default_symbols_header_as_array(),
+            symbols_header_rom_path: settings_default_symbols_header_rom_path(),
+            symbols_header_linker_offsets_path: settings_default_symbols_header_linker_offsets_path(
+            ),
+            symbols_header_keep_user_additions: settings_default_symbols_header_keep_user_additions(
+            ),
+
+            symbols_asm_path: settings_default_symbols_asm_path(),
+            symbols_asm_dialect: settings_default_symbols_asm_dialect(),
+
+            symbols_rust_path: settings_default_symbols_rust_path(),
+
+            vram_class_slack_symbols: settings_default_vram_class_slack_symbols(),
+            vram_class_slack_report_path: settings_default_vram_class_slack_report_path(),
+
+            memory_map_path: settings_default_memory_map_path(),
+            binary_rules_path: settings_default_binary_rules_path(),
+            objects_list_path: settings_default_objects_list_path(),
+            toolchain_objects_path: settings_default_toolchain_objects_path(),
+            symbols_manifest_path: settings_default_symbols_manifest_path(),
+
+            segment_table_path: settings_default_segment_table_path(),
+            segment_table_header_path: settings_default_segment_table_header_path(),
+
+            common_script_path: settings_default_common_script_path(),
+
+            gdb_script_path: settings_default_gdb_script_path(),
+
+            bss_symbols: settings_default_bss_symbols(),
+            bss_symbols_start_name: settings_default_bss_symbols_start_name(),
+            bss_symbols_size_name: settings_default_bss_symbols_size_name(),
+
+            rom_model: settings_default_rom_model(),
+            rom_end_symbol: settings_default_rom_end_symbol(),
+            rom_size_symbol: settings_default_rom_size_symbol(),
+            total_bss_size_symbol: settings_default_total_bss_size_symbol(),
+
+            rom_size_alignment: settings_default_rom_size_alignment(),
+            rom_min_size: settings_default_rom_min_size(),
 
             sections_allowlist: settings_default_sections_allowlist(),
             sections_allowlist_extra: settings_default_sections_allowlist_extra(),
+            emit_debug_sections: settings_default_emit_debug_sections(),
             sections_denylist: settings_default_sections_denylist(),
             discard_wildcard_section: settings_default_discard_wildcard_section(),
+            discard: settings_default_discard(),
 
             single_segment_mode: settings_default_single_segment_mode(),
 
+            insert_after: settings_default_insert_after(),
+            insert_before: settings_default_insert_before(),
+
             partial_scripts_folder: settings_default_partial_scripts_folder(),
             partial_build_segments_folder: settings_default_partial_build_segments_folder(),
+            partial_build_path: settings_default_partial_build_path(),
 
+            toolchain: settings_default_toolchain(),
             alloc_sections: settings_default_alloc_sections(),
             noload_sections: settings_default_noload_sections(),
 
             subalign: settings_default_subalign(),
             segment_start_align: settings_default_segment_start_align(),
             segment_end_align: settings_default_segment_end_align(),
+            noload_start_align: settings_default_noload_start_align(),
             section_start_align: settings_default_section_start_align(),
             section_end_align: settings_default_section_end_align(),
             sections_start_alignment: settings_default_sections_start_alignment(),
             sections_end_alignment: settings_default_sections_end_alignment(),
+            sections_subalign: settings_default_sections_subalign(),
+            sections_header_align: settings_default_sections_header_align(),
 
             wildcard_sections: settings_default_wildcard_sections(),
+            sections_wildcard: settings_default_sections_wildcard(),
+            input_section_patterns: settings_default_input_section_patterns(),
 
             fill_value: settings_default_fill_value(),
+            sections_fill_value: settings_default_sections_fill_value(),
+            sections_order_by: settings_default_sections_order_by(),
 
             sections_subgroups: settings_default_subsections_groups(),
-        }
-    }
-}
 
-impl Settings {
-    pub fn base_path_escaped(&self, rs: &RuntimeSettings) -> Result<EscapedPath, SlinkyError> {
-        rs.escape_path(&self.base_path)
-    }
+            keep_linker_offset_anchors: settings_default_keep_linker_offset_anchors(),
+            check_fixed_vram_overlaps: settings_default_check_fixed_vram_overlaps(),
 
-    pub fn d_path_escaped(&self, rs: &RuntimeSettings) -> Result<Option<EscapedPath>, SlinkyError> {
+            hex_uppercase: settings_default_hex_uppercase(),
+            hex_address_width: settings_default_hex_address_width(),
+            hex_alignments: settings_default_hex_alignments(),
+
+            script_indent_width: settings_default_script_indent_width(),
+            script_indent_with_tabs: settings_default_script_indent_with_tabs(),
+            script_braces_on_same_line: settings_default_script_braces_on_same_line(),
+            script_blank_lines_between_segments:
+                settings_default_script_blank_lines_between_segments(),
+            script_blank_lines_between_groups: settings_default_script_blank_lines_between_groups(),
+            script_trailing_newline: settings_default_script_trailing_newline(),
+
+            generated_symbols_provide: settings_default_generated_symbols_provide(),
+            generated_symbols_hidden: settings_default_generated_symbols_hidden(),
+            generated_symbols_absolute: settings_default_generated_symbols_absolute(),
+
+            segment_size_symbols: settings_default_segment_size_symbols(),
+            sections_kind_symbols: settings_default_sections_kind_symbols(),
+            sections_kind_size_symbols: settings_default_sections_kind_size_symbols(),
+            section_symbols: settings_default_section_symbols(),
+            section_size_symbols: settings_default_section_size_symbols(),
+
+            search_paths: settings_default_search_paths(),
+            libraries: settings_default_libraries(),
+            libraries_group: settings_default_libraries_group(),
+
+            skip_empty_sections: settings_default_skip_empty_sections(),
+
+            deterministic_output: settings_default_deterministic_output(),
+            sort_dependencies: settings_default_sort_dependencies(),
+            d_generated_files: settings_default_d_generated_files(),
+            check_archive_members: settings_default_check_archive_members(),
+            check_object_sections: settings_default_check_object_sections(),
+
+            sort_segments_by_follows: settings_default_sort_segments_by_follows(),
+
+            group_required_symbols: settings_default_group_required_symbols(),
+
+            aligned_section_start_symbols: settings_default_aligned_section_start_symbols(),
+        }
+    }
+}
+
+impl Settings {
+    /// Checks every condition of an entry is declared on `valid_conditions`.
+    ///
+    /// Nothing is checked if no condition is declared at all.
+    pub(crate) fn check_conditions(
+        &self,
+        conditions: [(&str, &[(String, String)]); 4],
+    ) -> Result<(), SlinkyError> {
+        if self.valid_conditions.is_empty() {
+            return Ok(());
+        }
+
+        for (name, list) in conditions {
+            for (key, value) in list {
+                let declared = self
+                    .valid_conditions
+                    .get(key)
+                    .map_or(false, |values| values.is_empty() || values.contains(value));
+                if !declared {
+                    return Err(SlinkyError::UndeclaredCondition {
+                        name: name.to_string(),
+                        key: key.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn base_path_escaped(&self, rs: &RuntimeSettings) -> Result<EscapedPath, SlinkyError> {
+        rs.escape_path(&self.base_path)
+    }
+
+    pub fn d_path_escaped(&self, rs: &RuntimeSettings) -> Result<Option<EscapedPath>, SlinkyError> {
         match &self.d_path {
             Some(p) => Ok(Some(rs.escape_path(p)?)),
             None => Ok(None),
@@ -245,6 +858,16 @@ impl Settings {
         }
     }
 
+    pub fn ninja_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.ninja_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn symbols_header_path_escaped(
         &self,
         rs: &RuntimeSettings,
@@ -255,6 +878,146 @@ impl Settings {
         }
     }
 
+    pub fn symbols_header_rom_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.symbols_header_rom_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn symbols_header_linker_offsets_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.symbols_header_linker_offsets_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn symbols_asm_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.symbols_asm_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn symbols_rust_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.symbols_rust_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn vram_class_slack_report_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.vram_class_slack_report_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn memory_map_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.memory_map_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn binary_rules_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.binary_rules_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn objects_list_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.objects_list_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn toolchain_objects_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.toolchain_objects_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn symbols_manifest_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.symbols_manifest_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn segment_table_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.segment_table_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn segment_table_header_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.segment_table_header_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn common_script_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.common_script_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn gdb_script_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match &self.gdb_script_path {
+            Some(p) => Ok(Some(rs.escape_path(p)?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn partial_scripts_folder_escaped(
         &self,
         rs: &RuntimeSettings,
@@ -274,23 +1037,83 @@ impl Settings {
             None => Ok(None),
         }
     }
+
+    /// The path of the built partial object of the given segment, relative to
+    /// `base_path`.
+    ///
+    /// Uses `partial_build_path` if set, replacing its `{segment}` placeholder
+    /// with the name of the segment. Otherwise the object is named after the
+    /// segment inside `partial_build_segments_folder`.
+    pub fn partial_build_object_path(&self, segment: &str) -> Option<PathBuf> {
+        if let Some(template) = &self.partial_build_path {
+            return Some(PathBuf::from(
+                template.to_string_lossy().replace("{segment}", segment),
+            ));
+        }
+
+        self.partial_build_segments_folder
+            .as_ref()
+            .map(|folder| folder.join(format!("{}.o", segment)))
+    }
+
+    pub fn search_paths_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Vec<EscapedPath>, SlinkyError> {
+        self.search_paths
+            .iter()
+            .map(|x| rs.escape_path(x))
+            .collect()
+    }
+
+    /// Returns every entry of `libraries` with its custom options replaced,
+    /// and whether it is a path instead of a `-l` library name.
+    pub fn libraries_escaped(
+        &self,
+        rs: &RuntimeSettings,
+    ) -> Result<Vec<(EscapedPath, bool)>, SlinkyError> {
+        self.libraries
+            .iter()
+            .map(|x| Ok((rs.escape_path(Path::new(x))?, !x.starts_with("-l"))))
+            .collect()
+    }
+
+    pub fn partial_build_object_path_escaped(
+        &self,
+        rs: &RuntimeSettings,
+        segment: &str,
+    ) -> Result<Option<EscapedPath>, SlinkyError> {
+        match self.partial_build_object_path(segment) {
+            Some(p) => Ok(Some(rs.escape_path(&p)?)),
+            None => Ok(None),
+        }
+    }
 }
 
-#[derive(Deserialize, PartialEq, Debug)]
+#[derive(Deserialize, PartialEq, Debug, Default)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct SettingsSerial {
+    #[serde(default)]
+    pub preset: AbsentNullable<Preset>,
+
     #[serde(default)]
     pub base_path: AbsentNullable<PathBuf>,
     #[serde(default)]
+    pub file_kind_overrides: AbsentNullable<HashMap<String, FileKind>>,
+    #[serde(default)]
+    pub valid_conditions: AbsentNullable<HashMap<String, Vec<String>>>,
+    #[serde(default)]
     pub linker_symbols_style: AbsentNullable<LinkerSymbolsStyle>,
 
     #[serde(default)]
-    pub hardcoded_gp_value: AbsentNullable<u32>,
+    pub hardcoded_gp_value: AbsentNullable<u64>,
 
     #[serde(default)]
     pub d_path: AbsentNullable<PathBuf>,
     #[serde(default)]
     pub target_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub ninja_path: AbsentNullable<PathBuf>,
 
     #[serde(default)]
     pub symbols_header_path: AbsentNullable<PathBuf>,
@@ -298,29 +1121,111 @@ pub(crate) struct SettingsSerial {
     pub symbols_header_type: AbsentNullable<String>,
     #[serde(default)]
     pub symbols_header_as_array: AbsentNullable<bool>,
+    #[serde(default)]
+    pub symbols_header_rom_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub symbols_header_linker_offsets_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub symbols_header_keep_user_additions: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub linker_script_banner: AbsentNullable<String>,
+    #[serde(default)]
+    pub d_banner: AbsentNullable<String>,
+    #[serde(default)]
+    pub symbols_header_banner: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub symbols_asm_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub symbols_asm_dialect: AbsentNullable<SymbolsAsmDialect>,
+
+    #[serde(default)]
+    pub symbols_rust_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub vram_class_slack_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub vram_class_slack_report_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub memory_map_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub binary_rules_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub objects_list_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub toolchain_objects_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub symbols_manifest_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub segment_table_path: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub segment_table_header_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub common_script_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub gdb_script_path: AbsentNullable<PathBuf>,
+
+    #[serde(default)]
+    pub bss_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub bss_symbols_start_name: AbsentNullable<String>,
+    #[serde(default)]
+    pub bss_symbols_size_name: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub rom_model: AbsentNullable<RomModel>,
+    #[serde(default)]
+    pub rom_end_symbol: AbsentNullable<String>,
+    #[serde(default)]
+    pub rom_size_symbol: AbsentNullable<String>,
+    #[serde(default)]
+    pub total_bss_size_symbol: AbsentNullable<String>,
+
+    #[serde(default)]
+    pub rom_size_alignment: AbsentNullable<u32>,
+    #[serde(default)]
+    pub rom_min_size: AbsentNullable<u32>,
 
     #[serde(default)]
     pub sections_allowlist: AbsentNullable<Vec<String>>,
     #[serde(default)]
     pub sections_allowlist_extra: AbsentNullable<Vec<String>>,
     #[serde(default)]
+    pub emit_debug_sections: AbsentNullable<bool>,
+    #[serde(default)]
     pub sections_denylist: AbsentNullable<Vec<String>>,
     #[serde(default)]
     pub discard_wildcard_section: AbsentNullable<bool>,
+    #[serde(default)]
+    pub discard: AbsentNullable<Vec<DiscardRuleSerial>>,
 
     #[serde(default)]
     pub single_segment_mode: AbsentNullable<bool>,
 
+    #[serde(default)]
+    pub insert_after: AbsentNullable<String>,
+    #[serde(default)]
+    pub insert_before: AbsentNullable<String>,
+
     #[serde(default)]
     pub partial_scripts_folder: AbsentNullable<PathBuf>,
     #[serde(default)]
     pub partial_build_segments_folder: AbsentNullable<PathBuf>,
+    #[serde(default)]
+    pub partial_build_path: AbsentNullable<PathBuf>,
 
     // Options passed down to each Segment
     #[serde(default)]
-    pub alloc_sections: AbsentNullable<Vec<String>>,
+    pub toolchain: AbsentNullable<String>,
     #[serde(default)]
-    pub noload_sections: AbsentNullable<Vec<String>>,
+    pub alloc_sections: AbsentNullable<SectionEntriesSerial>,
+    #[serde(default)]
+    pub noload_sections: AbsentNullable<SectionEntriesSerial>,
 
     #[serde(default)]
     pub subalign: AbsentNullable<u32>,
@@ -329,6 +1234,8 @@ pub(crate) struct SettingsSerial {
     #[serde(default)]
     pub segment_end_align: AbsentNullable<u32>,
     #[serde(default)]
+    pub noload_start_align: AbsentNullable<u32>,
+    #[serde(default)]
     pub section_start_align: AbsentNullable<u32>,
     #[serde(default)]
     pub section_end_align: AbsentNullable<u32>,
@@ -336,77 +1243,451 @@ pub(crate) struct SettingsSerial {
     pub sections_start_alignment: AbsentNullable<HashMap<String, u32>>,
     #[serde(default)]
     pub sections_end_alignment: AbsentNullable<HashMap<String, u32>>,
+    #[serde(default)]
+    pub sections_subalign: AbsentNullable<HashMap<String, u32>>,
+    #[serde(default)]
+    pub sections_header_align: AbsentNullable<HashMap<String, u32>>,
 
     #[serde(default)]
     pub wildcard_sections: AbsentNullable<bool>,
+    #[serde(default)]
+    pub sections_wildcard: AbsentNullable<HashMap<String, bool>>,
+    #[serde(default)]
+    pub input_section_patterns: AbsentNullable<HashMap<String, Vec<String>>>,
 
     #[serde(default)]
     pub fill_value: AbsentNullable<u32>,
+    #[serde(default)]
+    pub sections_fill_value: AbsentNullable<HashMap<String, u32>>,
+    #[serde(default)]
+    pub sections_order_by: AbsentNullable<HashMap<String, OrderBy>>,
 
     #[serde(default)]
     pub sections_subgroups: AbsentNullable<HashMap<String, Vec<String>>>,
+
+    #[serde(default)]
+    pub keep_linker_offset_anchors: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub check_fixed_vram_overlaps: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub hex_uppercase: AbsentNullable<bool>,
+    #[serde(default)]
+    pub hex_address_width: AbsentNullable<u32>,
+    #[serde(default)]
+    pub hex_alignments: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub script_indent_width: AbsentNullable<u32>,
+    #[serde(default)]
+    pub script_indent_with_tabs: AbsentNullable<bool>,
+    #[serde(default)]
+    pub script_braces_on_same_line: AbsentNullable<bool>,
+    #[serde(default)]
+    pub script_blank_lines_between_segments: AbsentNullable<u32>,
+    #[serde(default)]
+    pub script_blank_lines_between_groups: AbsentNullable<u32>,
+    #[serde(default)]
+    pub script_trailing_newline: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub generated_symbols_provide: AbsentNullable<bool>,
+    #[serde(default)]
+    pub generated_symbols_hidden: AbsentNullable<bool>,
+    #[serde(default)]
+    pub generated_symbols_absolute: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub segment_size_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub sections_kind_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub sections_kind_size_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub section_symbols: AbsentNullable<bool>,
+    #[serde(default)]
+    pub section_size_symbols: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub search_paths: AbsentNullable<Vec<PathBuf>>,
+    #[serde(default)]
+    pub libraries: AbsentNullable<Vec<String>>,
+    #[serde(default)]
+    pub libraries_group: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub skip_empty_sections: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub deterministic_output: AbsentNullable<bool>,
+    #[serde(default)]
+    pub sort_dependencies: AbsentNullable<bool>,
+    #[serde(default)]
+    pub d_generated_files: AbsentNullable<bool>,
+    #[serde(default)]
+    pub check_archive_members: AbsentNullable<bool>,
+    #[serde(default)]
+    pub check_object_sections: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub sort_segments_by_follows: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub group_required_symbols: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub aligned_section_start_symbols: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
 }
 
 impl SettingsSerial {
     pub fn unserialize(self) -> Result<Settings, SlinkyError> {
+        let preset = self
+            .preset
+            .get_optional_nullable("preset", settings_default_preset)?;
+        // Every value not given by the document comes from the preset
+        let defaults = match preset {
+            Some(preset) => preset.default_settings(),
+            None => Settings::default(),
+        };
+
         let base_path = self
             .base_path
-            .get_non_null("base_path", settings_default_base_path)?;
-        let linker_symbols_style = self.linker_symbols_style.get_non_null(
-            "linker_symbols_style",
-            settings_default_linker_symbols_style,
-        )?;
+            .get_non_null("base_path", || defaults.base_path)?;
+        let file_kind_overrides = self
+            .file_kind_overrides
+            .get_non_null("file_kind_overrides", || defaults.file_kind_overrides)?;
+        for (extension, kind) in &file_kind_overrides {
+            if extension.is_empty() {
+                return Err(SlinkyError::EmptyValue {
+                    name: "file_kind_overrides".to_string(),
+                });
+            }
+            if !kind.has_path() {
+                return Err(SlinkyError::InvalidFieldValue {
+                    name: "file_kind_overrides".to_string(),
+                    value: kind.name().to_string(),
+                    allowed: "object, archive, binary".to_string(),
+                });
+            }
+        }
+        let valid_conditions = self
+            .valid_conditions
+            .get_non_null("valid_conditions", || defaults.valid_conditions)?;
+        if valid_conditions
+            .iter()
+            .any(|(key, values)| key.is_empty() || values.iter().any(|x| x.is_empty()))
+        {
+            return Err(SlinkyError::EmptyValue {
+                name: "valid_conditions".to_string(),
+            });
+        }
+        let linker_symbols_style = self
+            .linker_symbols_style
+            .get_non_null("linker_symbols_style", || defaults.linker_symbols_style)?;
 
         let hardcoded_gp_value = self
             .hardcoded_gp_value
-            .get_optional_nullable("hardcoded_gp_value", settings_default_hardcoded_gp_value)?;
+            .get_optional_nullable("hardcoded_gp_value", || defaults.hardcoded_gp_value)?;
 
         let d_path = self
             .d_path
-            .get_optional_nullable("d_path", settings_default_d_path)?;
+            .get_optional_nullable("d_path", || defaults.d_path)?;
         let target_path = self
             .target_path
-            .get_optional_nullable("target_path", settings_default_target_path)?;
+            .get_optional_nullable("target_path", || defaults.target_path)?;
+        let ninja_path = self
+            .ninja_path
+            .get_optional_nullable("ninja_path", || defaults.ninja_path)?;
 
         let symbols_header_path = self
             .symbols_header_path
-            .get_optional_nullable("symbols_header_path", settings_default_symbols_header_path)?;
+            .get_optional_nullable("symbols_header_path", || defaults.symbols_header_path)?;
         let symbols_header_type = self
             .symbols_header_type
-            .get_non_null("symbols_header_type", settings_default_symbols_header_type)?;
-        let symbols_header_as_array = self.symbols_header_as_array.get_non_null(
-            "symbols_header_as_array",
-            settings_default_symbols_header_as_array,
-        )?;
+            .get_non_null("symbols_header_type", || defaults.symbols_header_type)?;
+        let symbols_header_as_array = self
+            .symbols_header_as_array
+            .get_non_null("symbols_header_as_array", || {
+                defaults.symbols_header_as_array
+            })?;
+        let symbols_header_rom_path = self
+            .symbols_header_rom_path
+            .get_optional_nullable("symbols_header_rom_path", || {
+                defaults.symbols_header_rom_path
+            })?;
+        let symbols_header_linker_offsets_path = self
+            .symbols_header_linker_offsets_path
+            .get_optional_nullable("symbols_header_linker_offsets_path", || {
+                defaults.symbols_header_linker_offsets_path
+            })?;
+        let symbols_header_keep_user_additions = self
+            .symbols_header_keep_user_additions
+            .get_non_null("symbols_header_keep_user_additions", || {
+                defaults.symbols_header_keep_user_additions
+            })?;
+
+        let linker_script_banner = self
+            .linker_script_banner
+            .get_optional_nullable("linker_script_banner", || defaults.linker_script_banner)?;
+        let d_banner = self
+            .d_banner
+            .get_optional_nullable("d_banner", || defaults.d_banner)?;
+        let symbols_header_banner = self
+            .symbols_header_banner
+            .get_optional_nullable("symbols_header_banner", || defaults.symbols_header_banner)?;
+        for (name, banner) in [
+            ("linker_script_banner", &linker_script_banner),
+            ("d_banner", &d_banner),
+            ("symbols_header_banner", &symbols_header_banner),
+        ] {
+            if banner.as_ref().map_or(false, |x| x.is_empty()) {
+                return Err(SlinkyError::EmptyValue {
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        let symbols_asm_path = self
+            .symbols_asm_path
+            .get_optional_nullable("symbols_asm_path", || defaults.symbols_asm_path)?;
+        let symbols_asm_dialect = self
+            .symbols_asm_dialect
+            .get_non_null("symbols_asm_dialect", || defaults.symbols_asm_dialect)?;
+
+        let symbols_rust_path = self
+            .symbols_rust_path
+            .get_optional_nullable("symbols_rust_path", || defaults.symbols_rust_path)?;
+
+        let vram_class_slack_symbols = self
+            .vram_class_slack_symbols
+            .get_non_null("vram_class_slack_symbols", || {
+                defaults.vram_class_slack_symbols
+            })?;
+        let vram_class_slack_report_path = self
+            .vram_class_slack_report_path
+            .get_optional_nullable("vram_class_slack_report_path", || {
+                defaults.vram_class_slack_report_path
+            })?;
+
+        if vram_class_slack_report_path.is_some() && !vram_class_slack_symbols {
+            return Err(SlinkyError::MissingRequiredFieldCombo {
+                required: "vram_class_slack_symbols".to_string(),
+                other: "vram_class_slack_report_path".to_string(),
+            });
+        }
+
+        let memory_map_path = self
+            .memory_map_path
+            .get_optional_nullable("memory_map_path", || defaults.memory_map_path)?;
+        let binary_rules_path = self
+            .binary_rules_path
+            .get_optional_nullable("binary_rules_path", || defaults.binary_rules_path)?;
+        let objects_list_path = self
+            .objects_list_path
+            .get_optional_nullable("objects_list_path", || defaults.objects_list_path)?;
+        let toolchain_objects_path = self
+            .toolchain_objects_path
+            .get_optional_nullable("toolchain_objects_path", || defaults.toolchain_objects_path)?;
+        let symbols_manifest_path = self
+            .symbols_manifest_path
+            .get_optional_nullable("symbols_manifest_path", || defaults.symbols_manifest_path)?;
+
+        let segment_table_path = self
+            .segment_table_path
+            .get_optional_nullable("segment_table_path", || defaults.segment_table_path)?;
+        let segment_table_header_path = self
+            .segment_table_header_path
+            .get_optional_nullable("segment_table_header_path", || {
+                defaults.segment_table_header_path
+            })?;
+
+        let common_script_path = self
+            .common_script_path
+            .get_optional_nullable("common_script_path", || defaults.common_script_path)?;
+
+        let gdb_script_path = self
+            .gdb_script_path
+            .get_optional_nullable("gdb_script_path", || defaults.gdb_script_path)?;
+
+        let bss_symbols = self
+            .bss_symbols
+            .get_non_null("bss_symbols", || defaults.bss_symbols)?;
+        let bss_symbols_start_name = self
+            .bss_symbols_start_name
+            .get_optional_nullable("bss_symbols_start_name", || defaults.bss_symbols_start_name)?;
+        let bss_symbols_size_name = self
+            .bss_symbols_size_name
+            .get_optional_nullable("bss_symbols_size_name", || defaults.bss_symbols_size_name)?;
+
+        for (name, value) in [
+            ("bss_symbols_start_name", &bss_symbols_start_name),
+            ("bss_symbols_size_name", &bss_symbols_size_name),
+        ] {
+            if value.is_some() && !bss_symbols {
+                return Err(SlinkyError::MissingRequiredFieldCombo {
+                    required: "bss_symbols".to_string(),
+                    other: name.to_string(),
+                });
+            }
+        }
+
+        let rom_model = self
+            .rom_model
+            .get_non_null("rom_model", || defaults.rom_model)?;
+        let rom_end_symbol = self
+            .rom_end_symbol
+            .get_optional_nullable("rom_end_symbol", || defaults.rom_end_symbol)?;
+        let rom_size_symbol = self
+            .rom_size_symbol
+            .get_optional_nullable("rom_size_symbol", || defaults.rom_size_symbol)?;
+        let total_bss_size_symbol = self
+            .total_bss_size_symbol
+            .get_optional_nullable("total_bss_size_symbol", || defaults.total_bss_size_symbol)?;
+
+        let rom_size_alignment = self
+            .rom_size_alignment
+            .get_optional_nullable("rom_size_alignment", || defaults.rom_size_alignment)?;
+        let rom_min_size = self
+            .rom_min_size
+            .get_optional_nullable("rom_min_size", || defaults.rom_min_size)?;
+
+        for (name, value) in [
+            ("rom_end_symbol", &rom_end_symbol),
+            ("rom_size_symbol", &rom_size_symbol),
+            ("total_bss_size_symbol", &total_bss_size_symbol),
+        ] {
+            if let Some(value) = value {
+                if value.is_empty() {
+                    return Err(SlinkyError::EmptyValue {
+                        name: name.to_string(),
+                    });
+                }
+            }
+        }
+        // Without a rom there's nothing to measure
+        if rom_model == RomModel::None {
+            for (name, is_set) in [
+                ("rom_end_symbol", rom_end_symbol.is_some()),
+                ("rom_size_symbol", rom_size_symbol.is_some()),
+                ("rom_size_alignment", rom_size_alignment.is_some()),
+                ("rom_min_size", rom_min_size.is_some()),
+                ("segment_table_path", segment_table_path.is_some()),
+            ] {
+                if is_set {
+                    return Err(SlinkyError::InvalidFieldCombo {
+                        field1: name.to_string(),
+                        field2: "rom_model".to_string(),
+                    });
+                }
+            }
+        }
 
         let sections_allowlist = self
             .sections_allowlist
-            .get_non_null("sections_allowlist", settings_default_sections_allowlist)?;
-        let sections_allowlist_extra = self.sections_allowlist_extra.get_non_null(
-            "sections_allowlist_extra",
-            settings_default_sections_allowlist_extra,
-        )?;
+            .get_non_null("sections_allowlist", || defaults.sections_allowlist)?;
+        let sections_allowlist_extra = self
+            .sections_allowlist_extra
+            .get_non_null("sections_allowlist_extra", || {
+                defaults.sections_allowlist_extra
+            })?;
+        let emit_debug_sections = self
+            .emit_debug_sections
+            .get_non_null("emit_debug_sections", || defaults.emit_debug_sections)?;
         let sections_denylist = self
             .sections_denylist
-            .get_non_null("sections_denylist", settings_default_sections_denylist)?;
-        let discard_wildcard_section = self.discard_wildcard_section.get_non_null(
-            "discard_wildcard_section",
-            settings_default_discard_wildcard_section,
-        )?;
+            .get_non_null("sections_denylist", || defaults.sections_denylist)?;
+        let discard_wildcard_section = self
+            .discard_wildcard_section
+            .get_non_null("discard_wildcard_section", || {
+                defaults.discard_wildcard_section
+            })?;
+        let discard = match self.discard.get_non_null_no_default("discard")? {
+            Some(rules) => rules
+                .into_iter()
+                .map(DiscardRuleSerial::unserialize)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => defaults.discard,
+        };
 
         let single_segment_mode = self
             .single_segment_mode
-            .get_non_null("single_segment_mode", settings_default_single_segment_mode)?;
+            .get_non_null("single_segment_mode", || defaults.single_segment_mode)?;
+
+        let insert_after = self
+            .insert_after
+            .get_optional_nullable("insert_after", || defaults.insert_after)?;
+        let insert_before = self
+            .insert_before
+            .get_optional_nullable("insert_before", || defaults.insert_before)?;
+        for (name, section) in [
+            ("insert_after", &insert_after),
+            ("insert_before", &insert_before),
+        ] {
+            if section.as_ref().map_or(false, |x| x.is_empty()) {
+                return Err(SlinkyError::EmptyValue {
+                    name: name.to_string(),
+                });
+            }
+        }
+        if insert_after.is_some() && insert_before.is_some() {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "insert_after".to_string(),
+                field2: "insert_before".to_string(),
+            });
+        }
+        if (insert_after.is_some() || insert_before.is_some()) && single_segment_mode {
+            let field1 = if insert_after.is_some() {
+                "insert_after"
+            } else {
+                "insert_before"
+            };
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: field1.to_string(),
+                field2: "single_segment_mode".to_string(),
+            });
+        }
+        // Neither kind of script has the `SECTIONS` parts moved to the common
+        // script
+        if common_script_path.is_some() {
+            let other = if single_segment_mode {
+                Some("single_segment_mode")
+            } else if insert_after.is_some() {
+                Some("insert_after")
+            } else if insert_before.is_some() {
+                Some("insert_before")
+            } else {
+                None
+            };
+            if let Some(other) = other {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "common_script_path".to_string(),
+                    field2: other.to_string(),
+                });
+            }
+        }
 
-        let partial_scripts_folder = self.partial_scripts_folder.get_optional_nullable(
-            "partial_scripts_folder",
-            settings_default_partial_scripts_folder,
-        )?;
-        let partial_build_segments_folder =
-            self.partial_build_segments_folder.get_optional_nullable(
-                "partial_build_segments_folder",
-                settings_default_partial_build_segments_folder,
-            )?;
+        let partial_scripts_folder = self
+            .partial_scripts_folder
+            .get_optional_nullable("partial_scripts_folder", || defaults.partial_scripts_folder)?;
+        let partial_build_segments_folder = self
+            .partial_build_segments_folder
+            .get_optional_nullable("partial_build_segments_folder", || {
+                defaults.partial_build_segments_folder
+            })?;
+        let partial_build_path = self
+            .partial_build_path
+            .get_optional_nullable("partial_build_path", || defaults.partial_build_path)?;
+        if let Some(partial_build_path) = &partial_build_path {
+            if !partial_build_path.to_string_lossy().contains("{segment}") {
+                return Err(SlinkyError::MissingSegmentPlaceholder {
+                    name: "partial_build_path".to_string(),
+                });
+            }
+        }
 
         if d_path.is_some() && target_path.is_none() {
             return Err(SlinkyError::MissingRequiredFieldCombo {
@@ -415,89 +1696,404 @@ impl SettingsSerial {
             });
         }
 
-        let alloc_sections = self
-            .alloc_sections
-            .get_non_null("alloc_sections", settings_default_alloc_sections)?;
-        let noload_sections = self
-            .noload_sections
-            .get_non_null("noload_sections", settings_default_noload_sections)?;
+        let toolchain = self
+            .toolchain
+            .get_optional_nullable("toolchain", || defaults.toolchain)?;
+        if toolchain.as_ref().map_or(false, |x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "toolchain".to_string(),
+            });
+        }
+
+        let mut sections_properties = SectionsProperties::default();
+        let alloc_sections = unserialize_section_entries(
+            self.alloc_sections,
+            "alloc_sections",
+            || defaults.alloc_sections,
+            &mut sections_properties,
+        )?;
+        let noload_sections = unserialize_section_entries(
+            self.noload_sections,
+            "noload_sections",
+            || defaults.noload_sections,
+            &mut sections_properties,
+        )?;
 
         let subalign = self
             .subalign
-            .get_optional_nullable("subalign", settings_default_subalign)?;
+            .get_optional_nullable("subalign", || defaults.subalign)?;
 
         let segment_start_align = self
             .segment_start_align
-            .get_optional_nullable("segment_start_align", settings_default_segment_start_align)?;
+            .get_optional_nullable("segment_start_align", || defaults.segment_start_align)?;
 
         let segment_end_align = self
             .segment_end_align
-            .get_optional_nullable("segment_end_align", settings_default_segment_end_align)?;
+            .get_optional_nullable("segment_end_align", || defaults.segment_end_align)?;
+
+        let noload_start_align = self
+            .noload_start_align
+            .get_optional_nullable("noload_start_align", || defaults.noload_start_align)?;
 
         let section_start_align = self
             .section_start_align
-            .get_optional_nullable("section_start_align", settings_default_section_start_align)?;
+            .get_optional_nullable("section_start_align", || defaults.section_start_align)?;
 
         let section_end_align = self
             .section_end_align
-            .get_optional_nullable("section_end_align", settings_default_section_end_align)?;
-
-        let sections_start_alignment = self.sections_start_alignment.get_non_null(
-            "sections_start_alignment",
-            settings_default_sections_start_alignment,
-        )?;
-
-        let sections_end_alignment = self.sections_end_alignment.get_non_null(
-            "sections_end_alignment",
-            settings_default_sections_end_alignment,
-        )?;
+            .get_optional_nullable("section_end_align", || defaults.section_end_align)?;
+
+        let mut sections_start_alignment = self
+            .sections_start_alignment
+            .get_non_null("sections_start_alignment", || {
+                defaults.sections_start_alignment
+            })?;
+
+        let mut sections_end_alignment = self
+            .sections_end_alignment
+            .get_non_null("sections_end_alignment", || defaults.sections_end_alignment)?;
+
+        // Every section is its own output section only on single segment mode
+        let sections_subalign = self
+            .sections_subalign
+            .get_non_null("sections_subalign", || defaults.sections_subalign)?;
+        if !sections_subalign.is_empty() && !single_segment_mode {
+            return Err(SlinkyError::MissingRequiredFieldCombo {
+                required: "single_segment_mode".to_string(),
+                other: "sections_subalign".to_string(),
+            });
+        }
+        let sections_header_align = self
+            .sections_header_align
+            .get_non_null("sections_header_align", || defaults.sections_header_align)?;
+        if !sections_header_align.is_empty() && !single_segment_mode {
+            return Err(SlinkyError::MissingRequiredFieldCombo {
+                required: "single_segment_mode".to_string(),
+                other: "sections_header_align".to_string(),
+            });
+        }
 
         let wildcard_sections = self
             .wildcard_sections
-            .get_non_null("wildcard_sections", settings_default_wildcard_sections)?;
+            .get_non_null("wildcard_sections", || defaults.wildcard_sections)?;
+        let mut sections_wildcard = self
+            .sections_wildcard
+            .get_non_null("sections_wildcard", || defaults.sections_wildcard)?;
+        let input_section_patterns = self
+            .input_section_patterns
+            .get_non_null("input_section_patterns", || defaults.input_section_patterns)?;
+        for (section, patterns) in &input_section_patterns {
+            if section.is_empty() || patterns.is_empty() || patterns.iter().any(|x| x.is_empty()) {
+                return Err(SlinkyError::EmptyValue {
+                    name: "input_section_patterns".to_string(),
+                });
+            }
+        }
 
         let fill_value = self
             .fill_value
-            .get_optional_nullable("fill_value", settings_default_fill_value)?;
+            .get_optional_nullable("fill_value", || defaults.fill_value)?;
+        let mut sections_fill_value = self
+            .sections_fill_value
+            .get_non_null("sections_fill_value", || defaults.sections_fill_value)?;
+        let mut sections_order_by = self
+            .sections_order_by
+            .get_non_null("sections_order_by", || defaults.sections_order_by)?;
+
+        sections_properties.apply(
+            &mut sections_start_alignment,
+            &mut sections_end_alignment,
+            &mut sections_fill_value,
+            &mut sections_wildcard,
+            &mut sections_order_by,
+            None,
+        )?;
 
         let sections_subgroups = self
             .sections_subgroups
-            .get_non_null("sections_subgroups", settings_default_subsections_groups)?;
+            .get_non_null("sections_subgroups", || defaults.sections_subgroups)?;
+        // Checked against the sections by each segment, since they can
+        // override them
+        check_sections_subgroups(&sections_subgroups, &[])?;
+
+        let keep_linker_offset_anchors = self
+            .keep_linker_offset_anchors
+            .get_non_null("keep_linker_offset_anchors", || {
+                defaults.keep_linker_offset_anchors
+            })?;
+
+        let check_fixed_vram_overlaps = self
+            .check_fixed_vram_overlaps
+            .get_non_null("check_fixed_vram_overlaps", || {
+                defaults.check_fixed_vram_overlaps
+            })?;
+
+        let hex_uppercase = self
+            .hex_uppercase
+            .get_non_null("hex_uppercase", || defaults.hex_uppercase)?;
+        let hex_address_width = self
+            .hex_address_width
+            .get_non_null("hex_address_width", || defaults.hex_address_width)?;
+        let hex_alignments = self
+            .hex_alignments
+            .get_optional_nullable("hex_alignments", || defaults.hex_alignments)?;
+
+        let script_indent_width = self
+            .script_indent_width
+            .get_non_null("script_indent_width", || defaults.script_indent_width)?;
+        let script_indent_with_tabs = self
+            .script_indent_with_tabs
+            .get_non_null("script_indent_with_tabs", || {
+                defaults.script_indent_with_tabs
+            })?;
+        let script_braces_on_same_line = self
+            .script_braces_on_same_line
+            .get_non_null("script_braces_on_same_line", || {
+                defaults.script_braces_on_same_line
+            })?;
+        let script_blank_lines_between_segments = self
+            .script_blank_lines_between_segments
+            .get_non_null("script_blank_lines_between_segments", || {
+                defaults.script_blank_lines_between_segments
+            })?;
+        let script_blank_lines_between_groups = self
+            .script_blank_lines_between_groups
+            .get_non_null("script_blank_lines_between_groups", || {
+                defaults.script_blank_lines_between_groups
+            })?;
+        let script_trailing_newline = self
+            .script_trailing_newline
+            .get_non_null("script_trailing_newline", || {
+                defaults.script_trailing_newline
+            })?;
+
+        let generated_symbols_provide = self
+            .generated_symbols_provide
+            .get_non_null("generated_symbols_provide", || {
+                defaults.generated_symbols_provide
+            })?;
+        let generated_symbols_hidden = self
+            .generated_symbols_hidden
+            .get_non_null("generated_symbols_hidden", || {
+                defaults.generated_symbols_hidden
+            })?;
+        let generated_symbols_absolute = self
+            .generated_symbols_absolute
+            .get_non_null("generated_symbols_absolute", || {
+                defaults.generated_symbols_absolute
+            })?;
+
+        let segment_size_symbols = self
+            .segment_size_symbols
+            .get_non_null("segment_size_symbols", || defaults.segment_size_symbols)?;
+        let sections_kind_symbols = self
+            .sections_kind_symbols
+            .get_non_null("sections_kind_symbols", || defaults.sections_kind_symbols)?;
+        let sections_kind_size_symbols = self
+            .sections_kind_size_symbols
+            .get_non_null("sections_kind_size_symbols", || {
+                defaults.sections_kind_size_symbols
+            })?;
+        let section_symbols = self
+            .section_symbols
+            .get_non_null("section_symbols", || defaults.section_symbols)?;
+        let section_size_symbols = self
+            .section_size_symbols
+            .get_non_null("section_size_symbols", || defaults.section_size_symbols)?;
+
+        let search_paths = self
+            .search_paths
+            .get_non_null("search_paths", || defaults.search_paths)?;
+        let libraries = self
+            .libraries
+            .get_non_null("libraries", || defaults.libraries)?;
+        for library in &libraries {
+            if library.is_empty() || library == "-l" {
+                return Err(SlinkyError::EmptyValue {
+                    name: "libraries".to_string(),
+                });
+            }
+        }
+        let libraries_group = self
+            .libraries_group
+            .get_non_null("libraries_group", || defaults.libraries_group)?;
+
+        let skip_empty_sections = self
+            .skip_empty_sections
+            .get_non_null("skip_empty_sections", || defaults.skip_empty_sections)?;
+
+        let deterministic_output = self
+            .deterministic_output
+            .get_non_null("deterministic_output", || defaults.deterministic_output)?;
+        let sort_dependencies = self
+            .sort_dependencies
+            .get_non_null("sort_dependencies", || defaults.sort_dependencies)?;
+        let d_generated_files = self
+            .d_generated_files
+            .get_non_null("d_generated_files", || defaults.d_generated_files)?;
+        let check_archive_members = self
+            .check_archive_members
+            .get_non_null("check_archive_members", || defaults.check_archive_members)?;
+        let check_object_sections = self
+            .check_object_sections
+            .get_non_null("check_object_sections", || defaults.check_object_sections)?;
+
+        let sort_segments_by_follows = self
+            .sort_segments_by_follows
+            .get_non_null("sort_segments_by_follows", || {
+                defaults.sort_segments_by_follows
+            })?;
+
+        let group_required_symbols = self
+            .group_required_symbols
+            .get_non_null("group_required_symbols", || defaults.group_required_symbols)?;
+
+        let aligned_section_start_symbols = self
+            .aligned_section_start_symbols
+            .get_non_null("aligned_section_start_symbols", || {
+                defaults.aligned_section_start_symbols
+            })?;
+
+        // The script loads the partially linked object of each segment, using
+        // the section symbols as the addresses of its sections
+        if gdb_script_path.is_some() {
+            if partial_build_segments_folder.is_none() && partial_build_path.is_none() {
+                return Err(SlinkyError::MissingAnyOfOptionalFields {
+                    fields: "'partial_build_segments_folder', 'partial_build_path'".to_string(),
+                });
+            }
+            if !section_symbols {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "gdb_script_path".to_string(),
+                    field2: "section_symbols".to_string(),
+                });
+            }
+        }
 
         Ok(Settings {
+            preset,
+
             base_path,
+            file_kind_overrides,
+            valid_conditions,
             linker_symbols_style,
             hardcoded_gp_value,
 
             d_path,
             target_path,
+            ninja_path,
 
             symbols_header_path,
             symbols_header_type,
             symbols_header_as_array,
+            symbols_header_rom_path,
+            symbols_header_linker_offsets_path,
+            symbols_header_keep_user_additions,
+
+            linker_script_banner,
+            d_banner,
+            symbols_header_banner,
+
+            symbols_asm_path,
+            symbols_asm_dialect,
+
+            symbols_rust_path,
+
+            vram_class_slack_symbols,
+            vram_class_slack_report_path,
+
+            memory_map_path,
+            binary_rules_path,
+            objects_list_path,
+            toolchain_objects_path,
+            symbols_manifest_path,
+
+            segment_table_path,
+            segment_table_header_path,
+
+            common_script_path,
+
+            gdb_script_path,
+
+            bss_symbols,
+            bss_symbols_start_name,
+            bss_symbols_size_name,
+
+            rom_model,
+            rom_end_symbol,
+            rom_size_symbol,
+            total_bss_size_symbol,
+
+            rom_size_alignment,
+            rom_min_size,
 
             sections_allowlist,
             sections_allowlist_extra,
+            emit_debug_sections,
             sections_denylist,
             discard_wildcard_section,
+            discard,
 
             single_segment_mode,
 
+            insert_after,
+            insert_before,
+
             partial_scripts_folder,
             partial_build_segments_folder,
+            partial_build_path,
 
+            toolchain,
             alloc_sections,
             noload_sections,
             subalign,
             segment_start_align,
             segment_end_align,
+            noload_start_align,
             section_start_align,
             section_end_align,
             sections_start_alignment,
             sections_end_alignment,
+            sections_subalign,
+            sections_header_align,
             wildcard_sections,
+            sections_wildcard,
+            input_section_patterns,
             fill_value,
+            sections_fill_value,
+            sections_order_by,
             sections_subgroups,
+            keep_linker_offset_anchors,
+            check_fixed_vram_overlaps,
+            hex_uppercase,
+            hex_address_width,
+            hex_alignments,
+            script_indent_width,
+            script_indent_with_tabs,
+            script_braces_on_same_line,
+            script_blank_lines_between_segments,
+            script_blank_lines_between_groups,
+            script_trailing_newline,
+            generated_symbols_provide,
+            generated_symbols_hidden,
+            generated_symbols_absolute,
+            segment_size_symbols,
+            sections_kind_symbols,
+            sections_kind_size_symbols,
+            section_symbols,
+            section_size_symbols,
+            search_paths,
+            libraries,
+            libraries_group,
+            skip_empty_sections,
+            deterministic_output,
+            sort_dependencies,
+            d_generated_files,
+            check_archive_members,
+            check_object_sections,
+            sort_segments_by_follows,
+            group_required_symbols,
+            aligned_section_start_symbols,
         })
     }
 }
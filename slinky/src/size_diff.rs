@@ -0,0 +1,183 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{fmt, path::Path};
+
+use crate::{map_verifier::MapFile, utils, Document, RuntimeSettings, Segment, SlinkyError};
+
+/// The size of something on the old and the new build. `None` if it is not
+/// present on that build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct SizeDelta {
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+}
+
+impl SizeDelta {
+    #[must_use]
+    pub fn is_changed(&self) -> bool {
+        self.old != self.new
+    }
+
+    /// How many bytes it grew, negative if it shrunk.
+    #[must_use]
+    pub fn delta(&self) -> i64 {
+        self.new.unwrap_or(0) as i64 - self.old.unwrap_or(0) as i64
+    }
+
+    /// Whether the size grew or shrunk past a multiple of `alignment`, so
+    /// everything placed after it is moved by a whole `alignment`.
+    #[must_use]
+    pub fn crosses_alignment(&self, alignment: u64) -> bool {
+        let aligned = |size: Option<u64>| size.map(|x| (x + alignment - 1) / alignment);
+
+        alignment != 0 && aligned(self.old) != aligned(self.new)
+    }
+}
+
+impl fmt::Display for SizeDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = |size: Option<u64>| match size {
+            Some(size) => format!("0x{:X}", size),
+            None => "none".to_string(),
+        };
+
+        write!(
+            f,
+            "{} -> {} ({:+})",
+            size(self.old),
+            size(self.new),
+            self.delta()
+        )
+    }
+}
+
+/// How the size of a segment and each one of its sections changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SegmentSizeDiff {
+    pub segment: String,
+    /// The size of every output section of the segment, alloc and noload.
+    pub size: SizeDelta,
+    /// Each section of the segment along with its size, measured with the
+    /// section start and end symbols. Sections without those symbols on
+    /// either build are not listed.
+    pub sections: Vec<(String, SizeDelta)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct SizeDiff {
+    /// Every segment of the document emitted on either build, in the same
+    /// order.
+    pub segments: Vec<SegmentSizeDiff>,
+}
+
+impl SizeDiff {
+    /// The segments whose size or the size of any of their sections changed.
+    pub fn changed(&self) -> impl Iterator<Item = &SegmentSizeDiff> {
+        self.segments
+            .iter()
+            .filter(|x| x.size.is_changed() || x.sections.iter().any(|(_, s)| s.is_changed()))
+    }
+}
+
+/// Compares the map files produced by GNU ld on two builds of the same
+/// document, pairing the output sections and the generated symbols of each
+/// segment on both builds.
+pub struct SizeDiffer<'a> {
+    d: &'a Document,
+    rs: &'a RuntimeSettings,
+}
+
+impl<'a> SizeDiffer<'a> {
+    pub fn new(d: &'a Document, rs: &'a RuntimeSettings) -> Self {
+        Self { d, rs }
+    }
+
+    pub fn diff_map_files(&self, old: &Path, new: &Path) -> Result<SizeDiff, SlinkyError> {
+        let mut contents = Vec::new();
+
+        for path in [old, new] {
+            match utils::read_to_string(path) {
+                Ok(x) => contents.push(x),
+                Err(e) => {
+                    return Err(SlinkyError::FailedFileOpen {
+                        path: path.to_path_buf(),
+                        description: e.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(self.diff_map_strs(&contents[0], &contents[1]))
+    }
+
+    pub fn diff_map_strs(&self, old: &str, new: &str) -> SizeDiff {
+        let old = MapFile::parse(old);
+        let new = MapFile::parse(new);
+        let style = &self.d.settings.linker_symbols_style;
+
+        let mut segments = Vec::new();
+        for segment in &self.d.segments {
+            if !self.rs.should_emit_entry(
+                &segment.exclude_if_any,
+                &segment.exclude_if_all,
+                &segment.include_if_any,
+                &segment.include_if_all,
+            ) {
+                continue;
+            }
+
+            let size = SizeDelta {
+                old: segment_size(&old, segment),
+                new: segment_size(&new, segment),
+            };
+            if size.old.is_none() && size.new.is_none() {
+                continue;
+            }
+
+            let sections = segment
+                .alloc_sections
+                .iter()
+                .chain(&segment.noload_sections)
+                .filter_map(|section| {
+                    let start = style.segment_section_start(&segment.symbol_name, section);
+                    let end = style.segment_section_end(&segment.symbol_name, section);
+                    let section_size =
+                        |map: &MapFile| match (map.symbols.get(&start), map.symbols.get(&end)) {
+                            (Some(start), Some(end)) => Some(end.saturating_sub(*start)),
+                            _ => None,
+                        };
+
+                    let delta = SizeDelta {
+                        old: section_size(&old),
+                        new: section_size(&new),
+                    };
+                    if delta.old.is_none() && delta.new.is_none() {
+                        None
+                    } else {
+                        Some((section.clone(), delta))
+                    }
+                })
+                .collect();
+
+            segments.push(SegmentSizeDiff {
+                segment: segment.name.clone(),
+                size,
+                sections,
+            });
+        }
+
+        SizeDiff { segments }
+    }
+}
+
+fn segment_size(map: &MapFile, segment: &Segment) -> Option<u64> {
+    let alloc_name = format!(".{}", segment.name);
+    let noload_name = format!(".{}.noload", segment.name);
+
+    map.sections
+        .iter()
+        .filter(|x| x.name == alloc_name || x.name == noload_name)
+        .map(|x| x.size)
+        .reduce(|a, b| a + b)
+}
@@ -0,0 +1,355 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::PathBuf;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    absent_nullable::AbsentNullable, document::DocumentSerial, file_info::FileInfoSerial,
+    ignored_metadata::IgnoredMetadata, segment::SegmentSerial, settings::SettingsSerial,
+    vram_class::VramClassSerial, Document, FileKind, KeepSections, SlinkyError,
+};
+
+pub(crate) fn import_splat_yaml(contents: &str) -> Result<Document, SlinkyError> {
+    let root: Value = match serde_yaml::from_str(contents) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(SlinkyError::FailedYamlParsing {
+                description: e.to_string(),
+            })
+        }
+    };
+
+    let options = match root.get("options") {
+        None | Some(Value::Null) => Mapping::new(),
+        Some(Value::Mapping(m)) => m.clone(),
+        Some(_) => return Err(conversion_error("`options` must be a mapping")),
+    };
+    let paths = SplatPaths::new(&options)?;
+
+    let splat_segments = match root.get("segments") {
+        Some(Value::Sequence(s)) => s,
+        _ => return Err(conversion_error("`segments` must be a list")),
+    };
+
+    let mut vram_classes: Vec<VramClassSerial> = Vec::new();
+    let mut segments = Vec::new();
+
+    for splat_segment in splat_segments {
+        let entry = SplatEntry::parse(splat_segment)?;
+
+        // Entries without a type only mark the end of the rom
+        let kind = match &entry.kind {
+            Some(kind) => kind.as_str(),
+            None => continue,
+        };
+        let name = entry.name()?;
+
+        let mut files = Vec::new();
+        match &entry.subsegments {
+            Some(subsegments) => {
+                for subsegment in subsegments {
+                    let sub = SplatEntry::parse(subsegment)?;
+                    if let Some(file) = paths.file_for_entry(&sub, &entry.dir)? {
+                        if !files.contains(&file) {
+                            files.push(file);
+                        }
+                    }
+                }
+            }
+            None => {
+                if let Some(file) = paths.file_for_entry(&entry, &entry.dir)? {
+                    files.push(file);
+                }
+            }
+        }
+
+        if files.is_empty() {
+            // Nothing to link, like the rom end marker or `pad`s
+            if kind != "code" {
+                continue;
+            }
+            return Err(conversion_error(&format!(
+                "Segment `{}` does not have any linkable subsegment",
+                name
+            )));
+        }
+
+        let mut segment = SegmentSerial {
+            name,
+            files: files
+                .into_iter()
+                .map(|(path, subfile)| FileInfoSerial {
+                    kind: match subfile {
+                        Some(_) => AbsentNullable::Value(FileKind::Archive),
+                        None => AbsentNullable::Absent,
+                    },
+                    path: AbsentNullable::Value(path),
+                    subfile: AbsentNullable::absent_if_none(subfile),
+                    ..Default::default()
+                })
+                .collect(),
+            fixed_symbol: AbsentNullable::absent_if_none(get_string(&entry.attrs, "vram_symbol")?),
+            follows_segment: AbsentNullable::absent_if_none(get_string(
+                &entry.attrs,
+                "follows_vram",
+            )?),
+            subalign: AbsentNullable::absent_if_none(get_u32(&entry.attrs, "subalign")?),
+            segment_start_align: AbsentNullable::absent_if_none(get_u32(&entry.attrs, "align")?),
+            ..Default::default()
+        };
+
+        match get_string(&entry.attrs, "exclusive_ram_id")? {
+            Some(ram_id) => {
+                // Segments that share the same ram id get placed at the same vram
+                if !vram_classes.iter().any(|x| x.name == ram_id) {
+                    vram_classes.push(VramClassSerial {
+                        name: ram_id.clone(),
+                        fixed_vram: AbsentNullable::absent_if_none(entry.vram.map(u64::from)),
+                        fixed_symbol: AbsentNullable::Absent,
+                        follows_classes: AbsentNullable::Absent,
+                        follows_symbols: AbsentNullable::Absent,
+                        keep_sections: KeepSections::Absent,
+                        max_size: AbsentNullable::Absent,
+                        reserved: AbsentNullable::Absent,
+                        high_water_symbol: AbsentNullable::Absent,
+                        high_water_limit: AbsentNullable::Absent,
+                        no_cross_refs: AbsentNullable::Absent,
+                        alloc_sections: AbsentNullable::Absent,
+                        noload_sections: AbsentNullable::Absent,
+                        metadata: IgnoredMetadata,
+                    });
+                }
+                segment.vram_class = AbsentNullable::Value(ram_id);
+            }
+            None => segment.fixed_vram = AbsentNullable::absent_if_none(entry.vram.map(u64::from)),
+        }
+
+        segments.push(segment);
+    }
+
+    let settings = SettingsSerial {
+        base_path: AbsentNullable::Value(paths.build_path.clone()),
+        hardcoded_gp_value: AbsentNullable::absent_if_none(
+            get_u32(&options, "gp_value")?.map(u64::from),
+        ),
+        subalign: AbsentNullable::absent_if_none(get_u32(&options, "subalign")?),
+        ..Default::default()
+    };
+
+    let document_serial = DocumentSerial {
+        settings: AbsentNullable::Value(settings),
+        vram_classes: AbsentNullable::Value(vram_classes),
+        segments,
+        ..Default::default()
+    };
+
+    document_serial.unserialize()
+}
+
+fn conversion_error(description: &str) -> SlinkyError {
+    SlinkyError::FailedSplatYamlConversion {
+        description: description.to_string(),
+    }
+}
+
+fn value_to_u32(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(n) => n.as_u64().and_then(|x| u32::try_from(x).ok()),
+        Value::String(s) => match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+fn get_u32(attrs: &Mapping, key: &str) -> Result<Option<u32>, SlinkyError> {
+    match attrs.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match value_to_u32(v) {
+            Some(x) => Ok(Some(x)),
+            None => Err(conversion_error(&format!(
+                "`{}` must be a number, but got `{:?}`",
+                key, v
+            ))),
+        },
+    }
+}
+
+fn get_string(attrs: &Mapping, key: &str) -> Result<Option<String>, SlinkyError> {
+    match attrs.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(v) => Err(conversion_error(&format!(
+            "`{}` must be a string, but got `{:?}`",
+            key, v
+        ))),
+    }
+}
+
+/// A segment or subsegment entry, normalized from either its list or its
+/// mapping form.
+struct SplatEntry {
+    start: Option<u32>,
+    kind: Option<String>,
+    name: Option<String>,
+    vram: Option<u32>,
+    dir: PathBuf,
+    subsegments: Option<Vec<Value>>,
+
+    /// Extra positional arguments of the list form, like the object and
+    /// section of a `lib` subsegment.
+    args: Vec<String>,
+
+    attrs: Mapping,
+}
+
+impl SplatEntry {
+    fn parse(value: &Value) -> Result<Self, SlinkyError> {
+        match value {
+            Value::Sequence(seq) => {
+                let start = seq.first().and_then(value_to_u32);
+                let kind = seq.get(1).and_then(|x| x.as_str()).map(str::to_string);
+                let name = seq.get(2).and_then(|x| x.as_str()).map(str::to_string);
+                let args = seq
+                    .iter()
+                    .skip(3)
+                    .filter_map(|x| x.as_str())
+                    .map(str::to_string)
+                    .collect();
+
+                Ok(Self {
+                    start,
+                    kind,
+                    name,
+                    vram: None,
+                    dir: PathBuf::new(),
+                    subsegments: None,
+                    args,
+                    attrs: Mapping::new(),
+                })
+            }
+            Value::Mapping(attrs) => {
+                let start = attrs.get("start").and_then(value_to_u32);
+                let kind = get_string(attrs, "type")?;
+                let name = get_string(attrs, "name")?;
+                let vram = get_u32(attrs, "vram")?;
+                let dir = get_string(attrs, "dir")?.map_or_else(PathBuf::new, PathBuf::from);
+                let subsegments = match attrs.get("subsegments") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::Sequence(s)) => Some(s.clone()),
+                    Some(_) => return Err(conversion_error("`subsegments` must be a list")),
+                };
+
+                Ok(Self {
+                    start,
+                    kind,
+                    name,
+                    vram,
+                    dir,
+                    subsegments,
+                    args: Vec::new(),
+                    attrs: attrs.clone(),
+                })
+            }
+            _ => Err(conversion_error(&format!(
+                "Unexpected segment entry `{:?}`",
+                value
+            ))),
+        }
+    }
+
+    /// splat names unnamed entries after their rom offset.
+    fn name(&self) -> Result<String, SlinkyError> {
+        match (&self.name, self.start) {
+            (Some(name), _) => Ok(name.clone()),
+            (None, Some(start)) => Ok(format!("{:X}", start)),
+            (None, None) => Err(conversion_error(
+                "Found an entry without a name nor a rom offset",
+            )),
+        }
+    }
+}
+
+/// Folders used by splat to place the generated files, relative to the build
+/// folder.
+struct SplatPaths {
+    build_path: PathBuf,
+    asm_path: PathBuf,
+    src_path: PathBuf,
+    asset_path: PathBuf,
+    data_path: PathBuf,
+    lib_path: PathBuf,
+}
+
+impl SplatPaths {
+    fn new(options: &Mapping) -> Result<Self, SlinkyError> {
+        let path_or = |key: &str, default: &str| -> Result<PathBuf, SlinkyError> {
+            Ok(PathBuf::from(
+                get_string(options, key)?.unwrap_or_else(|| default.to_string()),
+            ))
+        };
+
+        let asm_path = path_or("asm_path", "asm")?;
+        let data_path = match get_string(options, "data_path")? {
+            Some(p) => PathBuf::from(p),
+            None => asm_path.join("data"),
+        };
+
+        Ok(Self {
+            build_path: path_or("build_path", "build")?,
+            asm_path,
+            src_path: path_or("src_path", "src")?,
+            asset_path: path_or("asset_path", "assets")?,
+            data_path,
+            lib_path: path_or("lib_path", "lib")?,
+        })
+    }
+
+    /// Returns the object path (and archive member, if any) that splat would
+    /// use for the given entry, or `None` if this entry does not produce a
+    /// linkable file.
+    fn file_for_entry(
+        &self,
+        entry: &SplatEntry,
+        dir: &PathBuf,
+    ) -> Result<Option<(PathBuf, Option<String>)>, SlinkyError> {
+        let kind = match &entry.kind {
+            Some(kind) => kind.as_str(),
+            None => return Ok(None),
+        };
+        let name = entry.name()?;
+
+        let (base, file_name) = match kind {
+            "asm" | "hasm" | "header" | "textbin" => (&self.asm_path, format!("{}.s.o", name)),
+            "c" => (&self.src_path, format!("{}.c.o", name)),
+            "cpp" => (&self.src_path, format!("{}.cpp.o", name)),
+            "data" | "rodata" | "rdata" | "sdata" | "bss" | "sbss" => {
+                (&self.data_path, format!("{}.{}.s.o", name, kind))
+            }
+            // Sections of a C file, which is linked as a whole
+            ".data" | ".rodata" | ".rdata" | ".sdata" | ".bss" | ".sbss" => {
+                (&self.src_path, format!("{}.c.o", name))
+            }
+            "lib" => {
+                let object = match entry.args.first() {
+                    Some(object) => object,
+                    None => {
+                        return Err(conversion_error(&format!(
+                            "`lib` entry `{}` does not specify an object",
+                            name
+                        )))
+                    }
+                };
+                let path = self.lib_path.join(dir).join(format!("{}.a", name));
+                return Ok(Some((path, Some(format!("{}.o", object)))));
+            }
+            "pad" => return Ok(None),
+            _ => (&self.asset_path, format!("{}.bin.o", name)),
+        };
+
+        Ok(Some((base.join(dir).join(file_name), None)))
+    }
+}
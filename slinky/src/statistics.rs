@@ -0,0 +1,105 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::fmt;
+
+use crate::{Document, FileInfo, FileKind, RuntimeSettings};
+
+/// A summary of what was emitted on a linker script.
+///
+/// Useful to catch a typo on a custom option silently excluding most of a
+/// document.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct GenerationStatistics {
+    pub segments_emitted: usize,
+    /// Segments excluded by their conditions.
+    pub segments_skipped: usize,
+
+    /// The amount of emitted files of each kind, in the order each kind is
+    /// first found. The files inside groups are counted too.
+    pub files_per_kind: indexmap::IndexMap<FileKind, usize>,
+    /// Files excluded by their conditions. The files inside excluded segments
+    /// or groups are not counted.
+    pub files_skipped: usize,
+
+    /// Every linker symbol defined by the linker script.
+    pub linker_symbols: usize,
+
+    /// The input sections placed on the `/DISCARD/` block, including the
+    /// sections of the `discard` rules.
+    pub sections_discarded: usize,
+}
+
+impl GenerationStatistics {
+    /// Counts the segments and files of the document emitted for the given
+    /// runtime settings. The linker symbols are left for the caller.
+    pub(crate) fn new(d: &Document, rs: &RuntimeSettings) -> Self {
+        let mut stats = Self::default();
+
+        for segment in &d.segments {
+            if rs.should_emit_entry(
+                &segment.exclude_if_any,
+                &segment.exclude_if_all,
+                &segment.include_if_any,
+                &segment.include_if_all,
+            ) {
+                stats.segments_emitted += 1;
+                stats.count_files(&segment.files, rs);
+            } else {
+                stats.segments_skipped += 1;
+            }
+        }
+
+        stats.sections_discarded = d.settings.sections_denylist.len()
+            + d.settings
+                .discard
+                .iter()
+                .map(|x| x.sections.len())
+                .sum::<usize>();
+
+        stats
+    }
+
+    fn count_files(&mut self, files: &[FileInfo], rs: &RuntimeSettings) {
+        for file in files {
+            if !rs.should_emit_entry(
+                &file.exclude_if_any,
+                &file.exclude_if_all,
+                &file.include_if_any,
+                &file.include_if_all,
+            ) {
+                self.files_skipped += 1;
+                continue;
+            }
+
+            *self.files_per_kind.entry(file.kind.clone()).or_default() += 1;
+            self.count_files(&file.files, rs);
+        }
+    }
+
+    #[must_use]
+    pub fn files_emitted(&self) -> usize {
+        self.files_per_kind.values().sum()
+    }
+}
+
+impl fmt::Display for GenerationStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Segments: {} emitted, {} skipped",
+            self.segments_emitted, self.segments_skipped
+        )?;
+        writeln!(
+            f,
+            "Files: {} emitted, {} skipped",
+            self.files_emitted(),
+            self.files_skipped
+        )?;
+        for (kind, count) in &self.files_per_kind {
+            writeln!(f, "  {}: {}", kind.name(), count)?;
+        }
+        writeln!(f, "Linker symbols: {}", self.linker_symbols)?;
+        write!(f, "Discarded sections: {}", self.sections_discarded)
+    }
+}
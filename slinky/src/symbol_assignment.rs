@@ -1,18 +1,50 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{absent_nullable::AbsentNullable, traits::Serial, Settings, SlinkyError};
+use crate::{
+    absent_nullable::AbsentNullable,
+    ignored_metadata::IgnoredMetadata,
+    linker_symbols_style::{resolve_logical_symbol, resolve_logical_symbols},
+    traits::Serial,
+    Document, Settings, SlinkyError,
+};
 
-#[derive(PartialEq, Debug, Clone)]
+/// Where a [`SymbolAssignment`] is placed on the generated linker script,
+/// besides inside a given segment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolAssignmentPlacement {
+    /// Right before the `SECTIONS` block.
+    BeforeSections,
+    /// After the `SECTIONS` block, at the end of the linker script.
+    AfterSections,
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct SymbolAssignment {
     /// Name of the symbol
     pub name: String,
 
-    /// Value or expression to assign to this symbol
+    /// Value or expression to assign to this symbol.
+    ///
+    /// May reference the symbols generated for the document by their `splat`
+    /// style name wrapped in `{{ }}`, like `{{boot_ROM_END}}`, which are
+    /// replaced with the name used by the `linker_symbols_style` of the
+    /// document. See [`SymbolAssignment::resolved_value`].
+    ///
+    /// Empty if `alias_of` is used instead.
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub value: String,
 
+    /// The `splat` style name of the generated symbol this symbol is an alias
+    /// of, like `boot_ROM_START`. Aliases are tracked as generated symbols, so
+    /// they are also exported to the symbols header and the other exported
+    /// symbol lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias_of: Option<String>,
+
     /// Signals if this assignment should be wrapped in a `PROVIDE` statement.
     /// Can be used with `hidden`.
     pub provide: bool,
@@ -20,9 +52,22 @@ pub struct SymbolAssignment {
     /// Can be used with `provide`.
     pub hidden: bool,
 
+    /// Where the assignment is placed. Defaults to after the `SECTIONS` block
+    /// if neither this nor `inside_segment` are set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placement: Option<SymbolAssignmentPlacement>,
+    /// The name of the segment this assignment is placed after, inside the
+    /// `SECTIONS` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inside_segment: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub include_if_all: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_any: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub exclude_if_all: Vec<(String, String)>,
 }
 
@@ -30,13 +75,21 @@ pub struct SymbolAssignment {
 #[serde(deny_unknown_fields)]
 pub(crate) struct SymbolAssignmentSerial {
     pub name: String,
-    pub value: String,
+    #[serde(default)]
+    pub value: AbsentNullable<String>,
+    #[serde(default)]
+    pub alias_of: AbsentNullable<String>,
 
     #[serde(default)]
     pub provide: AbsentNullable<bool>,
     #[serde(default)]
     pub hidden: AbsentNullable<bool>,
 
+    #[serde(default)]
+    pub placement: AbsentNullable<SymbolAssignmentPlacement>,
+    #[serde(default)]
+    pub inside_segment: AbsentNullable<String>,
+
     #[serde(default)]
     pub include_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
@@ -45,12 +98,49 @@ pub(crate) struct SymbolAssignmentSerial {
     pub exclude_if_any: AbsentNullable<Vec<(String, String)>>,
     #[serde(default)]
     pub exclude_if_all: AbsentNullable<Vec<(String, String)>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl SymbolAssignment {
+    /// Whether this assignment is written after the `SECTIONS` block, along
+    /// the rest of the assignments without a custom placement.
+    #[must_use]
+    pub fn is_placed_after_sections(&self) -> bool {
+        self.inside_segment.is_none()
+            && self.placement != Some(SymbolAssignmentPlacement::BeforeSections)
+    }
+
+    /// Whether this assignment is emitted regardless of the custom options.
+    #[must_use]
+    pub fn is_unconditional(&self) -> bool {
+        self.include_if_any.is_empty()
+            && self.include_if_all.is_empty()
+            && self.exclude_if_any.is_empty()
+            && self.exclude_if_all.is_empty()
+    }
+
+    /// The value of this assignment, replacing every `{{name}}` reference
+    /// with the name given to that symbol by the `linker_symbols_style` of
+    /// the document.
+    pub fn resolved_value(&self, d: &Document) -> Result<String, SlinkyError> {
+        if let Some(alias_of) = &self.alias_of {
+            return resolve_logical_symbol(d, alias_of).ok_or_else(|| {
+                SlinkyError::UnknownLogicalSymbol {
+                    symbol: alias_of.clone(),
+                }
+            });
+        }
+
+        resolve_logical_symbols(d, &self.value)
+    }
 }
 
 impl Serial for SymbolAssignmentSerial {
     type Output = SymbolAssignment;
 
-    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+    fn unserialize(self, settings: &Settings) -> Result<Self::Output, SlinkyError> {
         if self.name.is_empty() {
             return Err(SlinkyError::EmptyValue {
                 name: "name".to_string(),
@@ -58,16 +148,49 @@ impl Serial for SymbolAssignmentSerial {
         }
         let name = self.name;
 
-        if self.value.is_empty() {
-            return Err(SlinkyError::EmptyValue {
-                name: "value".to_string(),
-            });
-        }
-        let value = self.value;
+        let value = self.value.get_non_null_no_default("value")?;
+        let alias_of = self.alias_of.get_non_null_no_default("alias_of")?;
+
+        let value = match (value, &alias_of) {
+            (Some(_), Some(_)) => {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "value".into(),
+                    field2: "alias_of".into(),
+                })
+            }
+            (None, None) => {
+                return Err(SlinkyError::MissingAnyOfOptionalFields {
+                    fields: "'value', 'alias_of'".into(),
+                })
+            }
+            (Some(value), None) if value.is_empty() => {
+                return Err(SlinkyError::EmptyValue {
+                    name: "value".to_string(),
+                })
+            }
+            (None, Some(alias_of)) if alias_of.is_empty() => {
+                return Err(SlinkyError::EmptyValue {
+                    name: "alias_of".to_string(),
+                })
+            }
+            (value, _) => value.unwrap_or_default(),
+        };
 
         let provide = self.provide.get_non_null("provide", || false)?;
         let hidden = self.hidden.get_non_null("hidden", || false)?;
 
+        let placement = self.placement.get_non_null_no_default("placement")?;
+        let inside_segment = self
+            .inside_segment
+            .get_non_null_no_default("inside_segment")?;
+
+        if placement.is_some() && inside_segment.is_some() {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "placement".into(),
+                field2: "inside_segment".into(),
+            });
+        }
+
         let include_if_any = self
             .include_if_any
             .get_non_null_not_empty("include_if_any", Vec::new)?;
@@ -81,11 +204,21 @@ impl Serial for SymbolAssignmentSerial {
             .exclude_if_all
             .get_non_null_not_empty("exclude_if_all", Vec::new)?;
 
+        settings.check_conditions([
+            ("include_if_any", &include_if_any),
+            ("include_if_all", &include_if_all),
+            ("exclude_if_any", &exclude_if_any),
+            ("exclude_if_all", &exclude_if_all),
+        ])?;
+
         Ok(Self::Output {
             name,
             value,
+            alias_of,
             provide,
             hidden,
+            placement,
+            inside_segment,
             include_if_any,
             include_if_all,
             exclude_if_any,
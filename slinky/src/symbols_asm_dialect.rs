@@ -0,0 +1,28 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::{Deserialize, Serialize};
+
+/// How the linker symbols are declared on the assembler include file.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolsAsmDialect {
+    /// `.extern name`, understood by GNU as and most MIPS assemblers.
+    Extern,
+    /// `.extern name, 0`, for assemblers expecting the size of the symbol,
+    /// like IDO's `as`.
+    ExternSized,
+    /// `.globl name`.
+    Globl,
+}
+
+impl SymbolsAsmDialect {
+    #[must_use]
+    pub fn declaration(&self, sym: &str) -> String {
+        match self {
+            SymbolsAsmDialect::Extern => format!(".extern {}", sym),
+            SymbolsAsmDialect::ExternSized => format!(".extern {}, 0", sym),
+            SymbolsAsmDialect::Globl => format!(".globl {}", sym),
+        }
+    }
+}
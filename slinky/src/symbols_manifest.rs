@@ -0,0 +1,93 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::io::Write;
+
+use crate::{EntryPath, SlinkyError};
+
+/// The entry of the document a generated symbol comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct SymbolOrigin {
+    /// Empty for the symbols which don't come from any specific entry.
+    pub entry: EntryPath,
+    /// The field of the entry the symbol is named after, if the symbol is not
+    /// derived from the entry as a whole, like the `linker_offset_name` of a
+    /// file.
+    pub field: Option<String>,
+}
+
+/// Writes the generated symbols as a JSON manifest.
+///
+/// The `symbols` list uses the same format read by `imports`, while
+/// `definitions` maps each symbol to the yaml path of the entry defining it.
+pub(crate) fn write_symbols_manifest<'a>(
+    dst: &mut impl Write,
+    symbols: impl ExactSizeIterator<Item = (&'a String, &'a SymbolOrigin)> + Clone,
+) -> Result<(), SlinkyError> {
+    let mut contents = String::new();
+
+    contents += "{\n";
+
+    contents += "  \"symbols\": [";
+    for (i, (symbol, _)) in symbols.clone().enumerate() {
+        contents += if i == 0 { "\n" } else { ",\n" };
+        contents += &format!("    {}", json_string(symbol));
+    }
+    contents += if symbols.len() == 0 {
+        "],\n"
+    } else {
+        "\n  ],\n"
+    };
+
+    contents += "  \"definitions\": {";
+    for (i, (symbol, origin)) in symbols.clone().enumerate() {
+        let entry = if origin.entry.components.is_empty() {
+            "null".to_string()
+        } else {
+            json_string(&origin.entry.yaml_path())
+        };
+        let field = match &origin.field {
+            Some(field) => json_string(field),
+            None => "null".to_string(),
+        };
+
+        contents += if i == 0 { "\n" } else { ",\n" };
+        contents += &format!(
+            "    {}: {{ \"entry\": {}, \"field\": {} }}",
+            json_string(symbol),
+            entry,
+            field
+        );
+    }
+    contents += if symbols.len() == 0 { "}\n" } else { "\n  }\n" };
+
+    contents += "}\n";
+
+    if let Err(e) = write!(dst, "{}", contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents,
+        });
+    }
+
+    Ok(())
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\t' => escaped += "\\t",
+            c if (c as u32) < 0x20 => escaped += &format!("\\u{:04x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
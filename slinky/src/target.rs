@@ -0,0 +1,75 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    absent_nullable::AbsentNullable, ignored_metadata::IgnoredMetadata, traits::Serial, utils,
+    RuntimeSettings, Settings, SlinkyError,
+};
+
+/// A named preset of custom options, allowing to generate every version of a
+/// project from the same document.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct Target {
+    pub name: String,
+
+    /// Custom options used when generating this target, on top of the ones
+    /// of the base runtime settings.
+    #[serde(serialize_with = "utils::serialize_sorted_map")]
+    pub custom_options: HashMap<String, String>,
+}
+
+impl Target {
+    /// Returns a copy of `base` with the custom options of this target added
+    /// to it. The options of the target take precedence over the ones already
+    /// present on `base`.
+    #[must_use]
+    pub fn runtime_settings(&self, base: &RuntimeSettings) -> RuntimeSettings {
+        let mut rs = base.clone();
+
+        rs.add_custom_options(
+            self.custom_options
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+
+        rs
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TargetSerial {
+    pub name: String,
+
+    #[serde(default)]
+    pub custom_options: AbsentNullable<HashMap<String, String>>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
+}
+
+impl Serial for TargetSerial {
+    type Output = Target;
+
+    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+        if self.name.is_empty() {
+            return Err(SlinkyError::EmptyValue {
+                name: "name".to_string(),
+            });
+        }
+        let name = self.name;
+
+        let custom_options = self
+            .custom_options
+            .get_non_null("custom_options", HashMap::new)?;
+
+        Ok(Self::Output {
+            name,
+            custom_options,
+        })
+    }
+}
@@ -0,0 +1,67 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+//! A set of representative documents, meant to be used by tools integrating
+//! slinky to test themselves against realistic inputs.
+//!
+//! Only available when the `test_fixtures` feature is enabled.
+//!
+//! The documents are guaranteed to be valid and to generate a linker script
+//! without errors. Their contents may change between releases, so tests
+//! should avoid depending on the exact files or segments listed on them.
+
+use crate::{Document, SlinkyError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fixture {
+    /// Short identifier of this fixture.
+    pub name: &'static str,
+    /// What this fixture is meant to exercise.
+    pub description: &'static str,
+    /// The yaml contents of the document.
+    pub yaml: &'static str,
+}
+
+impl Fixture {
+    /// Parses the yaml of this fixture.
+    pub fn document(&self) -> Result<Document, SlinkyError> {
+        Document::from_yaml_str(self.yaml)
+    }
+}
+
+/// A single segment with a handful of files.
+pub const TINY: Fixture = Fixture {
+    name: "tiny",
+    description: "A single segment with a handful of files",
+    yaml: include_str!("../fixtures/tiny.yaml"),
+};
+
+/// Many overlays sharing memory regions through vram classes.
+pub const OVERLAYS: Fixture = Fixture {
+    name: "overlays",
+    description: "Many overlays sharing memory regions through vram classes",
+    yaml: include_str!("../fixtures/overlays.yaml"),
+};
+
+/// A document meant to be used with the [`PartialLinkerWriter`].
+///
+/// [`PartialLinkerWriter`]: crate::PartialLinkerWriter
+pub const PARTIAL_LINKING: Fixture = Fixture {
+    name: "partial_linking",
+    description: "A document meant to be used for partial linking",
+    yaml: include_str!("../fixtures/partial_linking.yaml"),
+};
+
+/// Multiple versions of the same project, listed as
+/// [`targets`](crate::Document::targets).
+///
+/// Every path references the `version` custom option, so one of the targets
+/// must be used when generating it.
+pub const MULTI_VERSION: Fixture = Fixture {
+    name: "multi_version",
+    description: "Multiple versions of the same project selected with custom options",
+    yaml: include_str!("../fixtures/multi_version.yaml"),
+};
+
+/// Every fixture.
+pub const ALL: [Fixture; 4] = [TINY, OVERLAYS, PARTIAL_LINKING, MULTI_VERSION];
@@ -0,0 +1,62 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use crate::Settings;
+
+/// The defaults of the segments built by a known toolchain, picked through
+/// their `toolchain`. Any other toolchain is just a tag and keeps the
+/// defaults of the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ToolchainProfile {
+    alloc_sections: &'static [&'static str],
+    noload_sections: &'static [&'static str],
+    wildcard_sections: bool,
+}
+
+impl ToolchainProfile {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            // IDO never splits its sections, so they are matched exactly
+            "ido" => Some(Self {
+                alloc_sections: &[".text", ".data", ".rodata", ".sdata"],
+                noload_sections: &[".sbss", ".scommon", ".bss", "COMMON"],
+                wildcard_sections: false,
+            }),
+            // Modern GCC emits subsections like `.text.unlikely` or
+            // `.rodata.str1.4`, and places constructors on `.init_array`
+            "gcc_modern" => Some(Self {
+                alloc_sections: &[
+                    ".text",
+                    ".data",
+                    ".rodata",
+                    ".init_array",
+                    ".fini_array",
+                    ".sdata",
+                ],
+                noload_sections: &[".sbss", ".scommon", ".bss", "COMMON"],
+                wildcard_sections: true,
+            }),
+            // armips has no small data sections
+            "armips" => Some(Self {
+                alloc_sections: &[".text", ".data", ".rodata"],
+                noload_sections: &[".bss"],
+                wildcard_sections: false,
+            }),
+            // PSY-Q names its read only data section `.rdata`
+            "psyq" => Some(Self {
+                alloc_sections: &[".text", ".data", ".rdata", ".sdata"],
+                noload_sections: &[".sbss", ".bss"],
+                wildcard_sections: false,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Replaces the defaults of `settings` passed down to the segments with
+    /// the ones of this profile.
+    pub(crate) fn apply(&self, settings: &mut Settings) {
+        settings.alloc_sections = self.alloc_sections.iter().map(|x| x.to_string()).collect();
+        settings.noload_sections = self.noload_sections.iter().map(|x| x.to_string()).collect();
+        settings.wildcard_sections = self.wildcard_sections;
+    }
+}
@@ -2,16 +2,17 @@
 /* SPDX-License-Identifier: MIT */
 
 use crate::{
-    AssertEntry, Document, EscapedPath, RequiredSymbol, Segment, Settings, SlinkyError,
-    SymbolAssignment,
+    AssertEntry, Document, EntryPathComponent, EscapedPath, GenerationStatistics, Import, RawBlock,
+    RequiredSymbol, Segment, Settings, SlinkyError, SymbolAssignment, WrittenFile,
 };
 
 mod private {
     use crate::{
         assert_entry::AssertEntrySerial, file_info::FileInfoSerial, gp_info::GpInfoSerial,
-        required_symbol::RequiredSymbolSerial, segment::SegmentSerial,
-        symbol_assignment::SymbolAssignmentSerial, vram_class::VramClassSerial, LinkerWriter,
-        PartialLinkerWriter,
+        import::ImportSerial, raw_block::RawBlockSerial, required_symbol::RequiredSymbolSerial,
+        runtime_option::RuntimeOptionSerial, segment::SegmentSerial,
+        symbol_assignment::SymbolAssignmentSerial, target::TargetSerial,
+        vram_class::VramClassSerial, LinkerWriter, PartialLinkerWriter,
     };
 
     pub trait Sealed {}
@@ -26,12 +27,20 @@ mod private {
     impl Sealed for SymbolAssignmentSerial {}
     impl Sealed for RequiredSymbolSerial {}
     impl Sealed for AssertEntrySerial {}
+    impl Sealed for RuntimeOptionSerial {}
+    impl Sealed for TargetSerial {}
+    impl Sealed for ImportSerial {}
+    impl Sealed for RawBlockSerial {}
 
     impl<T> Sealed for Vec<T> {}
     impl<T> Sealed for Option<T> {}
 }
 
 pub trait ScriptImporter: private::Sealed {
+    fn add_all_imports(&mut self, imports: &[Import]) -> Result<(), SlinkyError>;
+    /// Raw blocks are placed while adding the rest of the document, so they
+    /// must be added before the segments.
+    fn add_all_raw_blocks(&mut self, raw_blocks: &[RawBlock]) -> Result<(), SlinkyError>;
     fn add_all_segments(&mut self, segments: &[Segment]) -> Result<(), SlinkyError>;
     fn add_entry(&mut self, entry: &str) -> Result<(), SlinkyError>;
     fn add_all_symbol_assignments(
@@ -45,6 +54,8 @@ pub trait ScriptImporter: private::Sealed {
     fn add_all_asserts(&mut self, asserts: &[AssertEntry]) -> Result<(), SlinkyError>;
 
     fn add_whole_document(&mut self, document: &Document) -> Result<(), SlinkyError> {
+        self.add_all_imports(&document.imports)?;
+        self.add_all_raw_blocks(&document.raw)?;
         self.add_all_segments(&document.segments)?;
         if let Some(entry) = &document.entry {
             self.add_entry(entry)?;
@@ -58,10 +69,27 @@ pub trait ScriptImporter: private::Sealed {
 }
 
 pub trait ScriptExporter: private::Sealed {
-    fn export_linker_script_to_file(&self, path: &EscapedPath) -> Result<(), SlinkyError>;
+    /// Returns every file written, which may be more than one for exporters
+    /// producing multiple linker scripts.
+    fn export_linker_script_to_file(
+        &self,
+        path: &EscapedPath,
+    ) -> Result<Vec<WrittenFile>, SlinkyError>;
     fn export_linker_script_to_string(&self) -> Result<String, SlinkyError>;
 
-    fn save_other_files(&self) -> Result<(), SlinkyError>;
+    /// Writes the rest of the files requested by the document, like the
+    /// dependency file or the symbols header, returning every file written.
+    fn save_other_files(&self) -> Result<Vec<WrittenFile>, SlinkyError>;
+
+    /// Makes sure every path referenced by the generated linker scripts is
+    /// listed on their dependency files and vice versa.
+    ///
+    /// Meant to catch emitter bugs that would silently break incremental
+    /// builds, so it is not run automatically.
+    fn check_dependencies(&self) -> Result<(), SlinkyError>;
+
+    /// A summary of what was emitted on the main linker script.
+    fn statistics(&self) -> GenerationStatistics;
 }
 
 pub trait ScriptGenerator: ScriptImporter + ScriptExporter {}
@@ -96,3 +124,24 @@ where
         }
     }
 }
+
+/// Unserializes every entry of the list, attaching the entry that failed to
+/// the returned error.
+pub(crate) fn unserialize_entries<T, F>(
+    entries: Vec<T>,
+    settings: &Settings,
+    component: F,
+) -> Result<Vec<T::Output>, SlinkyError>
+where
+    T: Serial,
+    F: Fn(usize, &T) -> EntryPathComponent,
+{
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let c = component(index, &entry);
+            entry.unserialize(settings).map_err(|e| e.in_entry(c))
+        })
+        .collect()
+}
@@ -2,10 +2,19 @@
 /* SPDX-License-Identifier: MIT */
 
 use std::{
-    fs::{self, File},
-    path::Path,
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
 };
 
+use serde::{Serialize, Serializer};
+
 use crate::SlinkyError;
 
 pub(crate) fn capitalize(s: &str) -> String {
@@ -18,10 +27,123 @@ pub(crate) fn capitalize(s: &str) -> String {
     s.chars().next().expect("").to_uppercase().to_string() + &s[1..]
 }
 
+/// Escapes a string so it can be used inside a C string literal.
+///
+/// Anything that is not printable ASCII is written as an octal escape.
+pub(crate) fn escape_c_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for byte in s.bytes() {
+        match byte {
+            b'\\' => escaped.push_str("\\\\"),
+            b'"' => escaped.push_str("\\\""),
+            b'\t' => escaped.push_str("\\t"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+
+    escaped
+}
+
+// Every access to the filesystem goes through the following functions, so
+// they can be disabled at once when building without the `fs` feature, like on
+// `wasm32-unknown-unknown`.
+
+#[cfg(feature = "fs")]
+pub(crate) fn read_to_string(path: &Path) -> io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+#[cfg(not(feature = "fs"))]
+pub(crate) fn read_to_string(_path: &Path) -> io::Result<String> {
+    Err(filesystem_unavailable())
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn read(path: &Path) -> io::Result<Vec<u8>> {
+    std::fs::read(path)
+}
+
+#[cfg(not(feature = "fs"))]
+pub(crate) fn read(_path: &Path) -> io::Result<Vec<u8>> {
+    Err(filesystem_unavailable())
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    std::fs::canonicalize(path)
+}
+
+#[cfg(not(feature = "fs"))]
+pub(crate) fn canonicalize(_path: &Path) -> io::Result<PathBuf> {
+    Err(filesystem_unavailable())
+}
+
+/// Builds without the `fs` feature may target platforms without a clock.
+#[cfg(feature = "fs")]
+pub(crate) fn current_unix_time() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|x| x.as_secs())
+}
+
+#[cfg(not(feature = "fs"))]
+pub(crate) fn current_unix_time() -> Option<u64> {
+    None
+}
+
+/// Formats a time in seconds since the Unix epoch as a `YYYY-MM-DD` date, in
+/// UTC.
+pub(crate) fn format_date(seconds: u64) -> String {
+    // Converts days to a civil date, as described on
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = (seconds / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(feature = "fs")]
+fn create_dir_all(path: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(path)
+}
+
+#[cfg(not(feature = "fs"))]
+fn create_dir_all(_path: &Path) -> io::Result<()> {
+    Err(filesystem_unavailable())
+}
+
+#[cfg(feature = "fs")]
+fn create_file(path: &Path) -> io::Result<File> {
+    File::create(path)
+}
+
+#[cfg(not(feature = "fs"))]
+fn create_file(_path: &Path) -> io::Result<File> {
+    Err(filesystem_unavailable())
+}
+
+#[cfg(not(feature = "fs"))]
+fn filesystem_unavailable() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "slinky was built without the `fs` feature",
+    )
+}
+
 pub(crate) fn create_file_and_parents(path: &Path) -> Result<File, SlinkyError> {
     // First we make the parents
     if let Some(parent) = path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
+        if let Err(e) = create_dir_all(parent) {
             return Err(SlinkyError::FailedDirCreate {
                 path: parent.to_path_buf(),
                 description: e.to_string(),
@@ -29,7 +151,7 @@ pub(crate) fn create_file_and_parents(path: &Path) -> Result<File, SlinkyError>
         }
     }
 
-    match File::create(path) {
+    match create_file(path) {
         Ok(f) => Ok(f),
         Err(e) => Err(SlinkyError::FailedFileOpen {
             path: path.to_path_buf(),
@@ -37,3 +159,140 @@ pub(crate) fn create_file_and_parents(path: &Path) -> Result<File, SlinkyError>
         }),
     }
 }
+
+/// Writes `contents` to the given path, creating its parent directories if
+/// needed.
+///
+/// If `only_if_changed` is `true` and the file already has the exact same
+/// contents then it is not touched at all. Returns whether the file was
+/// written.
+pub(crate) fn write_file_contents(
+    path: &Path,
+    contents: &[u8],
+    only_if_changed: bool,
+) -> Result<bool, SlinkyError> {
+    if only_if_changed {
+        if let Ok(previous) = read(path) {
+            if previous == contents {
+                return Ok(false);
+            }
+        }
+    }
+
+    let mut f = create_file_and_parents(path)?;
+
+    if let Err(e) = f.write_all(contents) {
+        return Err(SlinkyError::FailedWrite {
+            description: e.to_string(),
+            contents: path.display().to_string(),
+        });
+    }
+
+    Ok(true)
+}
+
+/// Serializes a `HashMap` sorted by its keys, so the output is stable.
+pub(crate) fn serialize_sorted_map<S, K, V>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+pub(crate) fn is_empty_path(path: &Path) -> bool {
+    path.as_os_str().is_empty()
+}
+
+pub(crate) const fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+pub(crate) const fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Whether `text` matches the glob `pattern`, where `*` matches any amount
+/// of characters and `?` matches a single one. Patterns without any of them
+/// must be equal to `text`.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Position of the last `*` and of the text when it was reached, to
+    // backtrack when the rest of the pattern stops matching
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Applies `f` to every item using as many threads as available, keeping the
+/// order of the items on the returned results.
+pub(crate) fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let thread_count = thread::available_parallelism()
+        .map_or(1, |x| x.get())
+        .min(items.len());
+
+    // Spawning threads is not supported on every target, like wasm
+    if thread_count <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    thread::scope(|s| {
+        for _ in 0..thread_count {
+            s.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let item = match items.get(index) {
+                    Some(item) => item,
+                    None => break,
+                };
+
+                let result = f(item);
+                results
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .into_iter()
+        .map(|x| x.expect("Every item was processed"))
+        .collect()
+}
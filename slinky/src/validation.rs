@@ -0,0 +1,742 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    elf, runtime_option, utils, Document, EntryPathComponent, FileInfo, FileKind,
+    LinkerSymbolsStyle, RuntimeSettings, Segment, SlinkyError,
+};
+
+type Conditions<'a> = [&'a Vec<(String, String)>; 4];
+
+pub(crate) fn validate_document(d: &Document) -> Vec<SlinkyError> {
+    let mut errors = Vec::new();
+
+    check_vram_classes(d, &mut errors);
+    check_segments(d, &mut errors);
+    check_runtime_options(d, &mut errors);
+    check_targets(d, &mut errors);
+    check_raw_blocks(d, &mut errors);
+    check_symbol_assignments(d, &mut errors);
+
+    errors
+}
+
+fn check_vram_classes(d: &Document, errors: &mut Vec<SlinkyError>) {
+    let mut seen = HashSet::new();
+
+    for vram_class in &d.vram_classes {
+        if !seen.insert(&vram_class.name) {
+            errors.push(SlinkyError::DuplicatedVramClass {
+                vram_class: Cow::from(vram_class.name.clone()),
+            });
+        }
+
+        for follows_class in &vram_class.follows_classes {
+            if !d.vram_classes.iter().any(|x| x.name == *follows_class) {
+                errors.push(SlinkyError::MissingFollowedVramClass {
+                    vram_class: Cow::from(vram_class.name.clone()),
+                    follows_class: Cow::from(follows_class.clone()),
+                });
+            }
+        }
+
+        if vram_class_follows_itself(d, &vram_class.name) {
+            errors.push(SlinkyError::VramClassCycle {
+                vram_class: Cow::from(vram_class.name.clone()),
+            });
+        }
+    }
+}
+
+fn vram_class_follows_itself(d: &Document, vram_class_name: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut pending = vec![vram_class_name];
+
+    while let Some(current) = pending.pop() {
+        let vram_class = match d.vram_classes.iter().find(|x| x.name == current) {
+            Some(vc) => vc,
+            None => continue,
+        };
+
+        for follows_class in &vram_class.follows_classes {
+            if follows_class == vram_class_name {
+                return true;
+            }
+            if visited.insert(follows_class.as_str()) {
+                pending.push(follows_class);
+            }
+        }
+    }
+
+    false
+}
+
+fn check_segments(d: &Document, errors: &mut Vec<SlinkyError>) {
+    let mut seen = HashSet::new();
+    let mut seen_files = HashSet::new();
+
+    for (index, segment) in d.segments.iter().enumerate() {
+        let conditions = [
+            &segment.include_if_any,
+            &segment.include_if_all,
+            &segment.exclude_if_any,
+            &segment.exclude_if_all,
+        ];
+
+        // Segments can be declared multiple times as long as each declaration
+        // is used on different conditions.
+        if !seen.insert((&segment.name, conditions)) {
+            errors.push(SlinkyError::DuplicatedSegment {
+                segment: Cow::from(segment.name.clone()),
+            });
+        }
+
+        if let Some(follows_segment) = &segment.follows_segment {
+            if !d.segments.iter().any(|x| x.name == *follows_segment) {
+                errors.push(SlinkyError::MissingFollowedSegment {
+                    segment: Cow::from(segment.name.clone()),
+                    follows_segment: Cow::from(follows_segment.clone()),
+                });
+            } else if segment_follows_itself(&d.segments, &segment.name) {
+                errors.push(SlinkyError::SegmentCycle {
+                    segment: Cow::from(segment.name.clone()),
+                });
+            } else if !d.segments[..index]
+                .iter()
+                .any(|x| x.name == *follows_segment)
+            {
+                // The symbols of the followed segment would be referenced
+                // before being assigned
+                errors.push(SlinkyError::SegmentFollowsLaterSegment {
+                    segment: Cow::from(segment.name.clone()),
+                    follows_segment: Cow::from(follows_segment.clone()),
+                });
+            }
+        }
+
+        if let Some(noload_merge) = &segment.noload_merge {
+            match d.segments.iter().find(|x| x.name == *noload_merge) {
+                None => errors.push(SlinkyError::MissingNoloadMergeSegment {
+                    segment: Cow::from(segment.name.clone()),
+                    noload_merge: Cow::from(noload_merge.clone()),
+                }),
+                Some(shared) if shared.noload_merge.is_some() => {
+                    errors.push(SlinkyError::ChainedNoloadMerge {
+                        segment: Cow::from(segment.name.clone()),
+                        noload_merge: Cow::from(noload_merge.clone()),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        if let Some(vram_class) = &segment.vram_class {
+            if !d.vram_classes.iter().any(|x| x.name == *vram_class) {
+                errors.push(SlinkyError::MissingVramClassForSegment {
+                    segment: Cow::from(segment.name.clone()),
+                    vram_class: Cow::from(vram_class.clone()),
+                });
+            }
+        }
+
+        check_files(
+            segment,
+            &segment.files,
+            &segment.dir,
+            &[conditions],
+            &mut seen_files,
+            errors,
+        );
+    }
+}
+
+fn segment_follows_itself(segments: &[Segment], segment_name: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut pending = vec![segment_name];
+
+    while let Some(current) = pending.pop() {
+        // A segment may be declared more than once on different conditions
+        for segment in segments.iter().filter(|x| x.name == current) {
+            if let Some(follows_segment) = &segment.follows_segment {
+                if follows_segment == segment_name {
+                    return true;
+                }
+                if visited.insert(follows_segment.as_str()) {
+                    pending.push(follows_segment);
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Reorders the segments so each one is placed after the segments it depends
+/// on, keeping the original order as much as possible.
+///
+/// A segment depends on the segment it follows and on any segment generating
+/// the symbol used as its `fixed_symbol`. Segments with a `rom_order` are also
+/// kept in that order.
+///
+/// Segments following an undefined segment are left as is, so they can be
+/// reported by the validation instead.
+pub(crate) fn sort_segments_by_dependencies(
+    segments: Vec<Segment>,
+    style: &LinkerSymbolsStyle,
+) -> Result<Vec<Segment>, SlinkyError> {
+    // The `fixed_symbol` may also use the `splat` style names, which are
+    // resolved to the style of the document when generating
+    let mut styles = vec![style];
+    if *style != LinkerSymbolsStyle::Splat {
+        styles.push(&LinkerSymbolsStyle::Splat);
+    }
+
+    let mut symbols_owners: HashMap<String, Vec<&str>> = HashMap::new();
+    for segment in &segments {
+        let name = &segment.symbol_name;
+        for style in &styles {
+            for sym in [
+                style.segment_rom_start(name),
+                style.segment_rom_end(name),
+                style.segment_rom_size(name),
+                style.segment_vram_start(name),
+                style.segment_vram_end(name),
+                style.segment_vram_size(name),
+            ] {
+                symbols_owners.entry(sym).or_default().push(&segment.name);
+            }
+        }
+    }
+
+    let dependencies: Vec<Vec<String>> = segments
+        .iter()
+        .map(|segment| {
+            let mut deps: Vec<String> = segment.follows_segment.iter().cloned().collect();
+            // Every symbol referenced by the expression counts
+            let fixed_symbols = segment
+                .fixed_symbol
+                .iter()
+                .flat_map(|x| x.split(|c: char| !c.is_ascii_alphanumeric() && c != '_'));
+            for sym in fixed_symbols {
+                if let Some(owners) = symbols_owners.get(sym) {
+                    deps.extend(owners.iter().map(|x| x.to_string()));
+                }
+            }
+            deps
+        })
+        .collect();
+
+    // Sort only by the dependencies first, so a cycle between them is not
+    // reported as a `rom_order` problem
+    let pending: Vec<(Segment, Vec<String>)> = segments.into_iter().zip(dependencies).collect();
+    let pending = topological_sort(pending, false)?;
+    let sorted = topological_sort(pending, true)?;
+
+    Ok(sorted.into_iter().map(|(segment, _)| segment).collect())
+}
+
+fn topological_sort(
+    mut pending: Vec<(Segment, Vec<String>)>,
+    use_rom_order: bool,
+) -> Result<Vec<(Segment, Vec<String>)>, SlinkyError> {
+    let mut sorted = Vec::with_capacity(pending.len());
+
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|(segment, deps)| {
+            let deps_placed = !deps
+                .iter()
+                .any(|dep| pending.iter().any(|(x, _)| x.name == *dep));
+            let rom_order_placed = !use_rom_order
+                || segment.rom_order.map_or(true, |rom_order| {
+                    !pending
+                        .iter()
+                        .any(|(x, _)| x.rom_order.map_or(false, |other| other < rom_order))
+                });
+
+            deps_placed && rom_order_placed
+        });
+
+        match ready {
+            Some(index) => sorted.push(pending.remove(index)),
+            None => {
+                let segment = Cow::from(pending[0].0.name.clone());
+                return Err(if use_rom_order {
+                    SlinkyError::RomOrderConflict { segment }
+                } else {
+                    SlinkyError::SegmentCycle { segment }
+                });
+            }
+        }
+    }
+
+    Ok(sorted)
+}
+
+/// Makes sure the segments with a `rom_order` are already placed in that
+/// order.
+pub(crate) fn check_rom_order(segments: &[Segment]) -> Result<(), SlinkyError> {
+    let mut previous: Option<&Segment> = None;
+
+    for segment in segments {
+        let rom_order = match segment.rom_order {
+            Some(rom_order) => rom_order,
+            None => continue,
+        };
+
+        if let Some(prev) = previous {
+            if prev.rom_order.map_or(false, |x| x > rom_order) {
+                return Err(SlinkyError::UnorderedRomOrder {
+                    segment: Cow::from(segment.name.clone()),
+                    previous: Cow::from(prev.name.clone()),
+                });
+            }
+        }
+        previous = Some(segment);
+    }
+
+    Ok(())
+}
+
+fn check_raw_blocks(d: &Document, errors: &mut Vec<SlinkyError>) {
+    for raw_block in &d.raw {
+        if let Some(after_segment) = &raw_block.after_segment {
+            if !d.segments.iter().any(|x| x.name == *after_segment) {
+                errors.push(SlinkyError::MissingRawBlockSegment {
+                    segment: Cow::from(after_segment.clone()),
+                });
+            }
+        }
+    }
+}
+
+fn check_symbol_assignments(d: &Document, errors: &mut Vec<SlinkyError>) {
+    for symbol_assignment in &d.symbol_assignments {
+        if let Some(inside_segment) = &symbol_assignment.inside_segment {
+            if !d.segments.iter().any(|x| x.name == *inside_segment) {
+                errors.push(SlinkyError::MissingSymbolAssignmentSegment {
+                    symbol: Cow::from(symbol_assignment.name.clone()),
+                    segment: Cow::from(inside_segment.clone()),
+                });
+            }
+        }
+    }
+}
+
+fn check_runtime_options(d: &Document, errors: &mut Vec<SlinkyError>) {
+    let mut seen = HashSet::new();
+
+    for runtime_option in &d.runtime_options {
+        if !seen.insert(&runtime_option.name) {
+            errors.push(SlinkyError::DuplicatedRuntimeOption {
+                option: Cow::from(runtime_option.name.clone()),
+            });
+        }
+    }
+}
+
+fn check_targets(d: &Document, errors: &mut Vec<SlinkyError>) {
+    let mut seen = HashSet::new();
+
+    for (index, target) in d.targets.iter().enumerate() {
+        if !seen.insert(&target.name) {
+            errors.push(SlinkyError::DuplicatedTarget {
+                target: Cow::from(target.name.clone()),
+            });
+        }
+
+        let mut custom_options: Vec<(&String, &String)> = target.custom_options.iter().collect();
+        custom_options.sort();
+
+        for (key, value) in custom_options {
+            if let Err(e) = runtime_option::check_custom_option(&d.runtime_options, key, value) {
+                errors.push(e.in_entry(EntryPathComponent::Target {
+                    index,
+                    name: target.name.clone(),
+                }));
+            }
+        }
+    }
+}
+
+fn check_files<'a>(
+    segment: &Segment,
+    files: &'a [FileInfo],
+    dir: &Path,
+    conditions: &[Conditions<'a>],
+    seen_files: &mut HashSet<(PathBuf, &'a str, &'a [String], Vec<Conditions<'a>>)>,
+    errors: &mut Vec<SlinkyError>,
+) {
+    for file in files {
+        let mut file_conditions = conditions.to_vec();
+        file_conditions.push([
+            &file.include_if_any,
+            &file.include_if_all,
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+        ]);
+
+        for (k, v) in &file.section_order {
+            for section in [k, v] {
+                if !segment.alloc_sections.contains(section)
+                    && !segment.noload_sections.contains(section)
+                {
+                    errors.push(SlinkyError::MissingSectionForSegment {
+                        field_name: Cow::from("section_order"),
+                        section: Cow::from(section.clone()),
+                        segment: Cow::from(segment.name.clone()),
+                    });
+                }
+            }
+        }
+
+        for section in file.subsection_order.keys() {
+            if !segment.alloc_sections.contains(section)
+                && !segment.noload_sections.contains(section)
+            {
+                errors.push(SlinkyError::MissingSectionForSegment {
+                    field_name: Cow::from("subsection_order"),
+                    section: Cow::from(section.clone()),
+                    segment: Cow::from(segment.name.clone()),
+                });
+            }
+        }
+
+        for section in file.section_rename.values() {
+            if !segment.alloc_sections.contains(section)
+                && !segment.noload_sections.contains(section)
+            {
+                errors.push(SlinkyError::MissingSectionForSegment {
+                    field_name: Cow::from("section_rename"),
+                    section: Cow::from(section.clone()),
+                    segment: Cow::from(segment.name.clone()),
+                });
+            }
+        }
+
+        for section in file.pad_to_size.keys() {
+            if !segment.alloc_sections.contains(section)
+                && !segment.noload_sections.contains(section)
+            {
+                errors.push(SlinkyError::MissingSectionForSegment {
+                    field_name: Cow::from("pad_to_size"),
+                    section: Cow::from(section.clone()),
+                    segment: Cow::from(segment.name.clone()),
+                });
+            }
+        }
+
+        for section in file.alignment.keys() {
+            if !segment.alloc_sections.contains(section)
+                && !segment.noload_sections.contains(section)
+            {
+                errors.push(SlinkyError::MissingSectionForSegment {
+                    field_name: Cow::from("alignment"),
+                    section: Cow::from(section.clone()),
+                    segment: Cow::from(segment.name.clone()),
+                });
+            }
+        }
+
+        match file.kind {
+            FileKind::Object | FileKind::Archive | FileKind::Binary => {
+                // The same file can be listed multiple times as long as each
+                // entry links different sections or is used on different
+                // conditions.
+                let path = dir.join(&file.path);
+                let key = (
+                    path.clone(),
+                    file.subfile.as_str(),
+                    file.sections.as_slice(),
+                    file_conditions,
+                );
+
+                if !seen_files.insert(key) {
+                    errors.push(SlinkyError::DuplicatedFile {
+                        path,
+                        segment: Cow::from(segment.name.clone()),
+                    });
+                }
+            }
+            FileKind::Group => check_files(
+                segment,
+                &file.files,
+                &dir.join(&file.dir),
+                &file_conditions,
+                seen_files,
+                errors,
+            ),
+            FileKind::Pad | FileKind::LinkerOffset | FileKind::Align => {}
+        }
+    }
+}
+
+pub(crate) fn collect_warnings(d: &Document, rs: &RuntimeSettings) -> Vec<SlinkyError> {
+    let mut warnings = Vec::new();
+
+    for (index, segment) in d.segments.iter().enumerate() {
+        if !rs.should_emit_entry(
+            &segment.exclude_if_any,
+            &segment.exclude_if_all,
+            &segment.include_if_any,
+            &segment.include_if_all,
+        ) {
+            continue;
+        }
+
+        let mut segment_warnings = Vec::new();
+
+        if !segment.external && !has_emitted_files(&segment.files, rs) {
+            segment_warnings.push(SlinkyError::EmptySegment {
+                segment: Cow::from(segment.name.clone()),
+            });
+        }
+
+        for section in &segment.alloc_sections {
+            if segment.noload_sections.contains(section)
+                || d.settings.noload_sections.contains(section)
+            {
+                segment_warnings.push(SlinkyError::NoloadSectionInAllocSections {
+                    section: Cow::from(section.clone()),
+                    segment: Cow::from(segment.name.clone()),
+                });
+            }
+        }
+
+        segment_warnings.extend(check_discarded_sections(d, &segment.files, rs));
+
+        if d.settings.check_object_sections && !segment.external {
+            let dir = d.settings.base_path.join(&segment.dir);
+            segment_warnings.extend(check_object_sections(d, segment, &segment.files, &dir, rs));
+        }
+
+        let component = EntryPathComponent::Segment {
+            index,
+            name: segment.name.clone(),
+        };
+        warnings.extend(
+            segment_warnings
+                .into_iter()
+                .map(|x| x.in_entry(component.clone())),
+        );
+    }
+
+    warnings
+}
+
+fn has_emitted_files(files: &[FileInfo], rs: &RuntimeSettings) -> bool {
+    files.iter().any(|file| {
+        if !rs.should_emit_entry(
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+            &file.include_if_any,
+            &file.include_if_all,
+        ) {
+            return false;
+        }
+
+        match file.kind {
+            FileKind::Group => has_emitted_files(&file.files, rs),
+            _ => true,
+        }
+    })
+}
+
+fn check_discarded_sections(
+    d: &Document,
+    files: &[FileInfo],
+    rs: &RuntimeSettings,
+) -> Vec<SlinkyError> {
+    let denylist = &d.settings.sections_denylist;
+    let mut warnings = Vec::new();
+
+    for (index, file) in files.iter().enumerate() {
+        if !rs.should_emit_entry(
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+            &file.include_if_any,
+            &file.include_if_all,
+        ) {
+            continue;
+        }
+
+        let mut file_warnings = Vec::new();
+
+        for (k, v) in &file.section_order {
+            for section in [k, v] {
+                if denylist.contains(section) {
+                    file_warnings.push(SlinkyError::DiscardedSectionReferenced {
+                        field_name: Cow::from("section_order"),
+                        section: Cow::from(section.clone()),
+                    });
+                }
+            }
+        }
+
+        for section in file.subsection_order.keys() {
+            if denylist.contains(section) {
+                file_warnings.push(SlinkyError::DiscardedSectionReferenced {
+                    field_name: Cow::from("subsection_order"),
+                    section: Cow::from(section.clone()),
+                });
+            }
+        }
+
+        for section in file.section_rename.values() {
+            if denylist.contains(section) {
+                file_warnings.push(SlinkyError::DiscardedSectionReferenced {
+                    field_name: Cow::from("section_rename"),
+                    section: Cow::from(section.clone()),
+                });
+            }
+        }
+
+        for section in file.pad_to_size.keys() {
+            if denylist.contains(section) {
+                file_warnings.push(SlinkyError::DiscardedSectionReferenced {
+                    field_name: Cow::from("pad_to_size"),
+                    section: Cow::from(section.clone()),
+                });
+            }
+        }
+
+        for section in file.alignment.keys() {
+            if denylist.contains(section) {
+                file_warnings.push(SlinkyError::DiscardedSectionReferenced {
+                    field_name: Cow::from("alignment"),
+                    section: Cow::from(section.clone()),
+                });
+            }
+        }
+
+        if file.kind == FileKind::Group {
+            file_warnings.extend(check_discarded_sections(d, &file.files, rs));
+        }
+
+        warnings.extend(
+            file_warnings
+                .into_iter()
+                .map(|x| x.in_entry(EntryPathComponent::File { index })),
+        );
+    }
+
+    warnings
+}
+
+/// Reads the sections of every object of the segment, looking for the ones
+/// which would not be placed by it.
+fn check_object_sections(
+    d: &Document,
+    segment: &Segment,
+    files: &[FileInfo],
+    dir: &Path,
+    rs: &RuntimeSettings,
+) -> Vec<SlinkyError> {
+    let mut warnings = Vec::new();
+
+    for (index, file) in files.iter().enumerate() {
+        if !rs.should_emit_entry(
+            &file.exclude_if_any,
+            &file.exclude_if_all,
+            &file.include_if_any,
+            &file.include_if_all,
+        ) {
+            continue;
+        }
+
+        let mut file_warnings = Vec::new();
+
+        match file.kind {
+            FileKind::Object => {
+                match rs
+                    .escape_path(&dir.join(&file.path))
+                    .and_then(|path| elf::read_alloc_sections(path.as_ref()).map(|x| (path, x)))
+                {
+                    Ok((path, sections)) => {
+                        for section in sections {
+                            if !places_section(d, segment, file, &section) {
+                                file_warnings.push(SlinkyError::UnplacedObjectSection {
+                                    path: path.0.clone(),
+                                    section,
+                                    segment: Cow::from(segment.name.clone()),
+                                });
+                            }
+                        }
+                    }
+                    Err(e) => file_warnings.push(e),
+                }
+            }
+            FileKind::Group => {
+                file_warnings.extend(check_object_sections(
+                    d,
+                    segment,
+                    &file.files,
+                    &dir.join(&file.dir),
+                    rs,
+                ));
+            }
+            _ => {}
+        }
+
+        warnings.extend(
+            file_warnings
+                .into_iter()
+                .map(|x| x.in_entry(EntryPathComponent::File { index })),
+        );
+    }
+
+    warnings
+}
+
+/// Whether the input `section` of `file` is matched by any of the input
+/// section specs the segment writes for it, or is explicitly handled somewhere
+/// else of the script.
+fn places_section(d: &Document, segment: &Segment, file: &FileInfo, section: &str) -> bool {
+    let settings = &d.settings;
+    if settings
+        .sections_allowlist
+        .iter()
+        .chain(&settings.sections_allowlist_extra)
+        .chain(&settings.sections_denylist)
+        .chain(&file.forbid_sections)
+        .any(|x| utils::glob_matches(x, section))
+    {
+        return true;
+    }
+
+    let targets: Vec<&String> = if file.sections.is_empty() {
+        segment
+            .alloc_sections
+            .iter()
+            .chain(&segment.noload_sections)
+            .collect()
+    } else {
+        file.sections.iter().collect()
+    };
+
+    targets
+        .into_iter()
+        .flat_map(|x| {
+            std::iter::once(x).chain(segment.sections_subgroups.get(x).into_iter().flatten())
+        })
+        .any(|target| {
+            let matches_spec = match settings.input_section_patterns.get(target) {
+                Some(patterns) => patterns.iter().any(|x| utils::glob_matches(x, section)),
+                None if file.uses_wildcard(segment, target) => {
+                    utils::glob_matches(&format!("{}*", target), section)
+                }
+                None => target == section,
+            };
+
+            matches_spec
+                || file
+                    .section_rename
+                    .iter()
+                    .any(|(pattern, x)| x == target && utils::glob_matches(pattern, section))
+        })
+}
@@ -1,8 +1,130 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::SlinkyError;
+
 pub static VERSION_MAJOR: u32 = 0;
 pub static VERSION_MINOR: u32 = 3;
 pub static VERSION_PATCH: u32 = 1;
 
 pub static VERSION_TUPLE: (u32, u32, u32) = (VERSION_MAJOR, VERSION_MINOR, VERSION_PATCH);
+
+/// A slinky version, written as `major.minor.patch`.
+///
+/// The patch number may be omitted when parsing, meaning `0`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// The version of this slinky.
+    pub fn current() -> Self {
+        Self::new(VERSION_MAJOR, VERSION_MINOR, VERSION_PATCH)
+    }
+
+    /// Whether this version is the same or newer than `required`.
+    #[must_use]
+    pub fn satisfies(&self, required: &Self) -> bool {
+        self >= required
+    }
+
+    /// Errors if the running slinky is older than `required`.
+    pub fn check_required(required: &Self) -> Result<(), SlinkyError> {
+        let current = Self::current();
+
+        if current.satisfies(required) {
+            Ok(())
+        } else {
+            Err(SlinkyError::UnsupportedSlinkyVersion {
+                required: required.to_string(),
+                current: current.to_string(),
+            })
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = SlinkyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SlinkyError::InvalidVersion {
+            version: s.to_string(),
+        };
+
+        let mut numbers = Vec::new();
+        for part in s.trim().split('.') {
+            numbers.push(part.parse::<u32>().map_err(|_| invalid())?);
+        }
+
+        match numbers[..] {
+            [major, minor] => Ok(Self::new(major, minor, 0)),
+            [major, minor, patch] => Ok(Self::new(major, minor, patch)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Looks for `required_slinky_version` on a document which could not be
+/// parsed, since a document written for a newer slinky may use attributes
+/// this one does not know about.
+///
+/// Errors if the required version is newer than this slinky, so that error is
+/// reported instead of the parsing one.
+pub(crate) fn check_unparsed_document(contents: &str) -> Result<(), SlinkyError> {
+    let value: serde_yaml::Value = match serde_yaml::from_str(contents) {
+        Ok(value) => value,
+        Err(_) => return Ok(()),
+    };
+
+    match value
+        .get("required_slinky_version")
+        .and_then(|x| x.as_str())
+    {
+        Some(required) => match required.parse() {
+            Ok(required) => Version::check_required(&required),
+            Err(_) => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
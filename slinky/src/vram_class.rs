@@ -1,23 +1,72 @@
 /* SPDX-FileCopyrightText: © 2024 decompals */
 /* SPDX-License-Identifier: MIT */
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{absent_nullable::AbsentNullable, traits::Serial, KeepSections, Settings, SlinkyError};
+use crate::{
+    absent_nullable::AbsentNullable,
+    ignored_metadata::IgnoredMetadata,
+    section_entry::{unserialize_section_entries, SectionEntriesSerial, SectionsProperties},
+    traits::Serial,
+    KeepSections, Settings, SlinkyError,
+};
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub struct VramClass {
     pub name: String,
 
-    pub fixed_vram: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_vram: Option<u64>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fixed_symbol: Option<String>,
 
     pub follows_classes: Vec<String>,
 
+    /// Symbols not managed by the document, like the end of a handwritten
+    /// region, which this vram class is also placed after.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub follows_symbols: Vec<String>,
+
+    #[serde(skip_serializing_if = "KeepSections::is_absent")]
     pub keep_sections: KeepSections,
 
+    /// The amount of bytes the biggest segment of this vram class is allowed
+    /// to use.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u32>,
+
+    /// The amount of bytes at the end of `max_size` which must stay unused,
+    /// like a heap placed right after this vram class. If set, emits an
+    /// `ASSERT` making sure the size of this vram class does not go past
+    /// `max_size` minus this amount.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserved: Option<u32>,
+
+    /// Emit a symbol tracking the highest address reached by the noload
+    /// sections of every segment of this vram class.
+    pub high_water_symbol: bool,
+
+    /// If set, emits an `ASSERT` making sure the high water mark does not go
+    /// past this address. Implies `high_water_symbol`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_water_limit: Option<u64>,
+
+    /// Emits a `NOCROSSREFS` directive listing the segments of this vram
+    /// class, so the linker rejects any reference between them.
+    pub no_cross_refs: bool,
+
+    /// The default `alloc_sections` of the segments of this vram class. If
+    /// missing then the ones from the settings are used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alloc_sections: Option<Vec<String>>,
+    /// The default `noload_sections` of the segments of this vram class. If
+    /// missing then the ones from the settings are used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noload_sections: Option<Vec<String>>,
+
     // Settings from below do not come from the document.
+    #[serde(skip)]
     pub emitted: bool,
 }
 
@@ -27,7 +76,7 @@ pub(crate) struct VramClassSerial {
     pub name: String,
 
     #[serde(default)]
-    pub fixed_vram: AbsentNullable<u32>,
+    pub fixed_vram: AbsentNullable<u64>,
 
     #[serde(default)]
     pub fixed_symbol: AbsentNullable<String>,
@@ -35,14 +84,40 @@ pub(crate) struct VramClassSerial {
     #[serde(default)]
     pub follows_classes: AbsentNullable<Vec<String>>,
 
+    #[serde(default)]
+    pub follows_symbols: AbsentNullable<Vec<String>>,
+
     #[serde(default)]
     pub keep_sections: KeepSections,
+
+    #[serde(default)]
+    pub max_size: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub reserved: AbsentNullable<u32>,
+
+    #[serde(default)]
+    pub high_water_symbol: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub high_water_limit: AbsentNullable<u64>,
+
+    #[serde(default)]
+    pub no_cross_refs: AbsentNullable<bool>,
+
+    #[serde(default)]
+    pub alloc_sections: AbsentNullable<SectionEntriesSerial>,
+    #[serde(default)]
+    pub noload_sections: AbsentNullable<SectionEntriesSerial>,
+
+    #[serde(default)]
+    pub metadata: IgnoredMetadata,
 }
 
 impl Serial for VramClassSerial {
     type Output = VramClass;
 
-    fn unserialize(self, _settings: &Settings) -> Result<Self::Output, SlinkyError> {
+    fn unserialize(self, settings: &Settings) -> Result<Self::Output, SlinkyError> {
         if self.name.is_empty() {
             return Err(SlinkyError::EmptyValue {
                 name: "name".to_string(),
@@ -58,6 +133,15 @@ impl Serial for VramClassSerial {
             .follows_classes
             .get_non_null("follows_classes", Vec::new)?;
 
+        let follows_symbols = self
+            .follows_symbols
+            .get_non_null("follows_symbols", Vec::new)?;
+        if follows_symbols.iter().any(|x| x.is_empty()) {
+            return Err(SlinkyError::EmptyValue {
+                name: "follows_symbols".to_string(),
+            });
+        }
+
         if fixed_vram.is_some() {
             if fixed_symbol.is_some() {
                 return Err(SlinkyError::InvalidFieldCombo {
@@ -72,29 +156,118 @@ impl Serial for VramClassSerial {
                     field2: "follows_classes".into(),
                 });
             }
+
+            if !follows_symbols.is_empty() {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "fixed_vram".into(),
+                    field2: "follows_symbols".into(),
+                });
+            }
         }
 
-        if fixed_symbol.is_some() && !follows_classes.is_empty() {
-            return Err(SlinkyError::InvalidFieldCombo {
-                field1: "fixed_symbol".into(),
-                field2: "follows_classes".into(),
-            });
+        if fixed_symbol.is_some() {
+            if !follows_classes.is_empty() {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "fixed_symbol".into(),
+                    field2: "follows_classes".into(),
+                });
+            }
+
+            if !follows_symbols.is_empty() {
+                return Err(SlinkyError::InvalidFieldCombo {
+                    field1: "fixed_symbol".into(),
+                    field2: "follows_symbols".into(),
+                });
+            }
         }
 
-        if fixed_vram.is_none() && fixed_symbol.is_none() && follows_classes.is_empty() {
+        if fixed_vram.is_none()
+            && fixed_symbol.is_none()
+            && follows_classes.is_empty()
+            && follows_symbols.is_empty()
+        {
             return Err(SlinkyError::MissingAnyOfOptionalFields {
-                fields: "'fixed_vram', 'fixed_symbol', 'follows_classes'".into(),
+                fields: "'fixed_vram', 'fixed_symbol', 'follows_classes', 'follows_symbols'".into(),
             });
         }
 
         let keep_sections = self.keep_sections;
 
+        let max_size = self.max_size.get_non_null_no_default("max_size")?;
+
+        let reserved = self.reserved.get_non_null_no_default("reserved")?;
+        if let Some(reserved) = reserved {
+            match max_size {
+                None => {
+                    return Err(SlinkyError::MissingRequiredFieldCombo {
+                        required: "max_size".into(),
+                        other: "reserved".into(),
+                    })
+                }
+                Some(max_size) if reserved > max_size => {
+                    return Err(SlinkyError::ReservedOverMaxSize { reserved, max_size })
+                }
+                Some(_) => {}
+            }
+        }
+
+        let high_water_limit = self
+            .high_water_limit
+            .get_non_null_no_default("high_water_limit")?;
+        let high_water_symbol = self
+            .high_water_symbol
+            .get_non_null("high_water_symbol", || high_water_limit.is_some())?;
+
+        if !high_water_symbol && high_water_limit.is_some() {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "high_water_symbol".into(),
+                field2: "high_water_limit".into(),
+            });
+        }
+
+        let no_cross_refs = self.no_cross_refs.get_non_null("no_cross_refs", || false)?;
+
+        let mut sections_properties = SectionsProperties::default();
+        let alloc_sections = match self.alloc_sections {
+            AbsentNullable::Absent => None,
+            alloc_sections => Some(unserialize_section_entries(
+                alloc_sections,
+                "alloc_sections",
+                || settings.alloc_sections.clone(),
+                &mut sections_properties,
+            )?),
+        };
+        let noload_sections = match self.noload_sections {
+            AbsentNullable::Absent => None,
+            noload_sections => Some(unserialize_section_entries(
+                noload_sections,
+                "noload_sections",
+                || settings.noload_sections.clone(),
+                &mut sections_properties,
+            )?),
+        };
+        // Only the names of the sections are passed down to the segments
+        if !sections_properties.is_empty() {
+            return Err(SlinkyError::InvalidFieldCombo {
+                field1: "vram_class".into(),
+                field2: "section properties".into(),
+            });
+        }
+
         Ok(Self::Output {
             name,
             fixed_vram,
             fixed_symbol,
             follows_classes,
+            follows_symbols,
             keep_sections,
+            max_size,
+            reserved,
+            high_water_symbol,
+            high_water_limit,
+            no_cross_refs,
+            alloc_sections,
+            noload_sections,
 
             emitted: false,
         })
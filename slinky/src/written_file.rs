@@ -0,0 +1,51 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use std::path::PathBuf;
+
+use crate::EscapedPath;
+
+/// A file written to disk by one of the exporters.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WrittenFile {
+    pub path: PathBuf,
+
+    /// `false` if the file already had the exact same contents and was left
+    /// untouched, preserving its modification time.
+    ///
+    /// This can only happen if
+    /// [`RuntimeSettings::write_if_changed`](crate::RuntimeSettings::write_if_changed)
+    /// or [`RuntimeSettings::dry_run`](crate::RuntimeSettings::dry_run) are
+    /// enabled.
+    pub updated: bool,
+
+    /// The contents the file would have been written with.
+    ///
+    /// Only set if
+    /// [`RuntimeSettings::dry_run`](crate::RuntimeSettings::dry_run) is
+    /// enabled, since the file is not written on that case.
+    pub contents: Option<Vec<u8>>,
+}
+
+impl WrittenFile {
+    pub(crate) fn new(path: &EscapedPath, updated: bool) -> Self {
+        let path: &PathBuf = path.as_ref();
+
+        Self {
+            path: path.clone(),
+            updated,
+            contents: None,
+        }
+    }
+
+    pub(crate) fn new_dry_run(path: &EscapedPath, contents: &[u8]) -> Self {
+        let path: &PathBuf = path.as_ref();
+        let updated = crate::utils::read(path).map_or(true, |previous| previous != contents);
+
+        Self {
+            path: path.clone(),
+            updated,
+            contents: Some(contents.to_vec()),
+        }
+    }
+}
@@ -0,0 +1,314 @@
+/* SPDX-FileCopyrightText: © 2024 decompals */
+/* SPDX-License-Identifier: MIT */
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    assert_entry::AssertEntrySerial,
+    document::{segment_settings, DocumentSerial},
+    file_info::FileInfoSerial,
+    formatter,
+    import::ImportSerial,
+    raw_block::RawBlockSerial,
+    required_symbol::RequiredSymbolSerial,
+    runtime_option::RuntimeOptionSerial,
+    segment::SegmentSerial,
+    settings::SettingsSerial,
+    symbol_assignment::SymbolAssignmentSerial,
+    target::TargetSerial,
+    traits::Serial,
+    vram_class::VramClassSerial,
+    Document, FileInfo, FileKind, KeepSections, Segment, Settings, SlinkyError,
+};
+
+/// Serializes the document as canonical yaml, omitting every value which is
+/// the same as its default.
+///
+/// A key is omitted only if the entry it belongs to is still unserialized to
+/// the same value without it, so the defaults given by the settings, the
+/// toolchain profiles or the parent entries are honored.
+pub(crate) fn write_yaml(d: &Document) -> Result<String, SlinkyError> {
+    let mut document = match serde_yaml::to_value(d) {
+        Ok(Value::Mapping(mapping)) => mapping,
+        Ok(_) => unreachable!("documents are always serialized as mappings"),
+        Err(e) => {
+            return Err(SlinkyError::FailedYamlSerialization {
+                description: e.to_string(),
+            })
+        }
+    };
+
+    if let Some(Value::Mapping(settings)) = document.get_mut("settings") {
+        omit_defaults(
+            settings,
+            formatter::SETTINGS_KEYS,
+            |x| from_mapping::<SettingsSerial>(x).and_then(|x| x.unserialize().ok()),
+            &d.settings,
+        );
+    }
+
+    omit_list_defaults::<VramClassSerial>(
+        &mut document,
+        "vram_classes",
+        formatter::VRAM_CLASS_KEYS,
+        &d.vram_classes,
+        d,
+    );
+    if let Some(Value::Sequence(segments)) = document.get_mut("segments") {
+        for (value, segment) in segments.iter_mut().zip(&d.segments) {
+            if let Value::Mapping(mapping) = value {
+                omit_segment_defaults(mapping, segment, d);
+            }
+        }
+    }
+    omit_list_defaults::<SymbolAssignmentSerial>(
+        &mut document,
+        "symbol_assignments",
+        formatter::SYMBOL_ASSIGNMENT_KEYS,
+        &d.symbol_assignments,
+        d,
+    );
+    omit_list_defaults::<RequiredSymbolSerial>(
+        &mut document,
+        "required_symbols",
+        formatter::REQUIRED_SYMBOL_KEYS,
+        &d.required_symbols,
+        d,
+    );
+    omit_list_defaults::<AssertEntrySerial>(
+        &mut document,
+        "asserts",
+        formatter::ASSERT_KEYS,
+        &d.asserts,
+        d,
+    );
+    omit_list_defaults::<SymbolAssignmentSerial>(
+        &mut document,
+        "pre_sections_symbols",
+        formatter::SYMBOL_ASSIGNMENT_KEYS,
+        &d.pre_sections_symbols,
+        d,
+    );
+    omit_list_defaults::<SymbolAssignmentSerial>(
+        &mut document,
+        "post_sections_symbols",
+        formatter::SYMBOL_ASSIGNMENT_KEYS,
+        &d.post_sections_symbols,
+        d,
+    );
+    omit_list_defaults::<AssertEntrySerial>(
+        &mut document,
+        "post_sections_asserts",
+        formatter::ASSERT_KEYS,
+        &d.post_sections_asserts,
+        d,
+    );
+    omit_list_defaults::<RuntimeOptionSerial>(
+        &mut document,
+        "runtime_options",
+        formatter::RUNTIME_OPTION_KEYS,
+        &d.runtime_options,
+        d,
+    );
+    omit_list_defaults::<TargetSerial>(
+        &mut document,
+        "targets",
+        formatter::TARGET_KEYS,
+        &d.targets,
+        d,
+    );
+    omit_list_defaults::<ImportSerial>(
+        &mut document,
+        "imports",
+        formatter::IMPORT_KEYS,
+        &d.imports,
+        d,
+    );
+    omit_list_defaults::<RawBlockSerial>(
+        &mut document,
+        "raw",
+        formatter::RAW_BLOCK_KEYS,
+        &d.raw,
+        d,
+    );
+
+    // Empty lists and an empty `settings` are the same as not having them
+    document.retain(|key, value| {
+        key.as_str() == Some("segments")
+            || !matches!(value, Value::Sequence(x) if x.is_empty())
+                && !matches!(value, Value::Mapping(x) if x.is_empty())
+    });
+
+    let contents = formatter::emit_yaml_value(&document);
+
+    // Writing must never change the meaning of the document
+    if !reads_back_as(&contents, d) {
+        return Err(SlinkyError::FailedYamlSerialization {
+            description: "the written document does not match the original one".to_string(),
+        });
+    }
+
+    Ok(contents)
+}
+
+/// Removes, one by one, every key of the mapping which is still unserialized
+/// to `expected` after removing it.
+///
+/// Values which are `None` are not serialized, but their default may not be,
+/// like a segment without a `toolchain` when the settings have one. So every
+/// key missing from the mapping is written as `null` first, and only the ones
+/// which matter are kept.
+fn omit_defaults<T: PartialEq>(
+    mapping: &mut Mapping,
+    known_keys: &[&str],
+    unserialize: impl Fn(&Mapping) -> Option<T>,
+    expected: &T,
+) {
+    for key in known_keys {
+        let key = Value::from(*key);
+        if !mapping.contains_key(&key) {
+            mapping.insert(key.clone(), Value::Null);
+            // Not every key can be `null`
+            if unserialize(mapping).is_none() {
+                mapping.remove(&key);
+            }
+        }
+    }
+
+    let keys: Vec<Value> = mapping.keys().cloned().collect();
+    for key in keys {
+        if let Some(value) = mapping.remove(&key) {
+            if unserialize(mapping).as_ref() != Some(expected) {
+                mapping.insert(key, value);
+            }
+        }
+    }
+}
+
+fn from_mapping<T: DeserializeOwned>(mapping: &Mapping) -> Option<T> {
+    serde_yaml::from_value(Value::Mapping(mapping.clone())).ok()
+}
+
+fn omit_list_defaults<T>(
+    document: &mut Mapping,
+    key: &str,
+    known_keys: &[&str],
+    entries: &[T::Output],
+    d: &Document,
+) where
+    T: Serial + DeserializeOwned,
+    T::Output: PartialEq,
+{
+    if let Some(Value::Sequence(values)) = document.get_mut(key) {
+        for (value, entry) in values.iter_mut().zip(entries) {
+            if let Value::Mapping(mapping) = value {
+                omit_defaults(
+                    mapping,
+                    known_keys,
+                    |x| from_mapping::<T>(x).and_then(|x| x.unserialize(&d.settings).ok()),
+                    entry,
+                );
+            }
+        }
+    }
+}
+
+fn omit_segment_defaults(mapping: &mut Mapping, segment: &Segment, d: &Document) {
+    let unserialize = |mapping: &Mapping| -> Option<Segment> {
+        let serial: SegmentSerial = from_mapping(mapping)?;
+        let settings = segment_settings(&d.settings, &d.vram_classes, &serial);
+        let mut x = serial.unserialize(&settings).ok()?;
+
+        // Same as `DocumentSerial::unserialize`
+        if let Some(vram_class) = x
+            .vram_class
+            .as_ref()
+            .and_then(|name| d.vram_classes.iter().find(|v| v.name == *name))
+        {
+            x.pass_down_keep_sections(&vram_class.keep_sections);
+        }
+        Some(x)
+    };
+
+    // The files are unserialized with the settings of the segment
+    if let Some(serial) = from_mapping::<SegmentSerial>(mapping) {
+        let settings = segment_settings(&d.settings, &d.vram_classes, &serial);
+        if let Some(Value::Sequence(files)) = mapping.get_mut("files") {
+            let inherited = Inherited {
+                keep_sections: &segment.keep_sections,
+                wildcard_sections: None,
+                emit_file_symbols: None,
+            };
+            omit_files_defaults(files, &segment.files, &settings, &inherited);
+        }
+    }
+
+    omit_defaults(mapping, formatter::SEGMENT_KEYS, unserialize, segment);
+}
+
+/// The values a file receives from the segment or group containing it when
+/// it doesn't set them itself.
+struct Inherited<'a> {
+    keep_sections: &'a KeepSections,
+    wildcard_sections: Option<bool>,
+    emit_file_symbols: Option<bool>,
+}
+
+fn omit_files_defaults(
+    values: &mut [Value],
+    files: &[FileInfo],
+    settings: &Settings,
+    inherited: &Inherited,
+) {
+    for (value, file) in values.iter_mut().zip(files) {
+        let Value::Mapping(mapping) = value else {
+            continue;
+        };
+
+        if file.kind == FileKind::Group {
+            if let Some(Value::Sequence(children)) = mapping.get_mut("files") {
+                let inherited = Inherited {
+                    keep_sections: &file.keep_sections,
+                    wildcard_sections: file.wildcard_sections,
+                    emit_file_symbols: file.emit_file_symbols,
+                };
+                omit_files_defaults(children, &file.files, settings, &inherited);
+            }
+        }
+
+        let unserialize = |mapping: &Mapping| -> Option<FileInfo> {
+            let serial: FileInfoSerial = from_mapping(mapping)?;
+            let mut x = serial.unserialize(settings).ok()?;
+
+            x.pass_down_keep_sections(inherited.keep_sections);
+            if let Some(wildcard_sections) = inherited.wildcard_sections {
+                x.pass_down_wildcard_sections(wildcard_sections);
+            }
+            if let Some(emit_file_symbols) = inherited.emit_file_symbols {
+                x.pass_down_emit_file_symbols(emit_file_symbols);
+            }
+            Some(x)
+        };
+        omit_defaults(mapping, formatter::FILE_KEYS, unserialize, file);
+    }
+}
+
+/// Whether the written yaml is read back as the same document.
+///
+/// The files listed on a `files_from` are written as part of the segment, so
+/// that list is not written.
+fn reads_back_as(contents: &str, d: &Document) -> bool {
+    let Ok(read) = DocumentSerial::from_yaml_str(contents).and_then(|x| x.unserialize()) else {
+        return false;
+    };
+
+    let mut expected = d.clone();
+    expected.included_files = Vec::new();
+    expected.path = None;
+    for segment in &mut expected.segments {
+        segment.files_from = None;
+    }
+
+    read == expected
+}
@@ -3,9 +3,13 @@
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use rstest::rstest;
-use slinky::{RuntimeSettings, ScriptExporter, ScriptImporter, SlinkyError};
+use slinky::{
+    MapIssue, PlacementSuggestion, RuntimeSettings, ScriptChange, ScriptExporter, ScriptImporter,
+    SizeDelta, SlinkyError, VramClassUsage,
+};
 
 fn compare_multiline_strings(expected: &str, generated: &str) {
     // We manually strip the CARRIAGE RETURN (`\r`/`U+000D`) character only from
@@ -63,6 +67,20 @@ fn compare_multiline_strings(expected: &str, generated: &str) {
     assert_eq!(expected_cleaned, generated);
 }
 
+/// A `Write` which can be inspected after giving it away.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 fn create_runtime_settings() -> RuntimeSettings {
     let mut rs = RuntimeSettings::new();
 
@@ -139,6 +157,33 @@ fn test_simple_linker_script_generation(#[files("../tests/test_cases/*.ld")] ld_
     check_ld_generation(&yaml_path, &ld_path).expect("");
 }
 
+#[rstest]
+fn test_streamed_linker_script_generation(#[files("../tests/test_cases/*.ld")] ld_path: PathBuf) {
+    let document = slinky::Document::read_file(&ld_path.with_extension("yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let streamed = SharedBuffer::default();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.set_stream(streamed.clone());
+    writer.add_whole_document(&document).expect("");
+    writer.finish_stream().unwrap();
+
+    assert_eq!(writer.export_linker_script_to_string().unwrap(), "");
+    if !document.segments.is_empty() && !document.settings.single_segment_mode {
+        assert_eq!(
+            writer.check_dependencies(),
+            Err(SlinkyError::StreamedLinkerScript)
+        );
+    }
+
+    let expected_ld_contents =
+        fs::read_to_string(ld_path).expect("unable to read expected ld file");
+    let streamed = streamed.0.lock().unwrap().clone();
+    compare_multiline_strings(&expected_ld_contents, &String::from_utf8(streamed).unwrap());
+}
+
 #[rstest]
 fn test_dependency_d_generation(#[files("../tests/test_cases/*.d")] d_path: PathBuf) {
     let yaml_path = d_path.with_extension("yaml");
@@ -153,138 +198,2315 @@ fn test_symbols_header_generation(#[files("../tests/test_cases/*.h")] h_path: Pa
     check_symbols_header_generation(&yaml_path, &h_path).expect("");
 }
 
-#[rstest]
-#[should_panic]
-fn test_panic_invalid_yamls(#[files("../tests/panics/*.yaml")] path: PathBuf) {
-    slinky::Document::read_file(&path).unwrap();
-}
-
-#[rstest]
-fn test_partial_linking_script_generation(
-    #[files("../tests/partial_linking/*.ld")] ld_path: PathBuf,
-) {
-    let yaml_path = ld_path.with_extension("yaml");
-
-    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+#[test]
+fn test_split_symbols_header() {
+    let path = Path::new("../tests/split_symbols_header/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
     let rs = create_runtime_settings();
 
-    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
     writer.add_whole_document(&document).expect("");
 
-    let expected_ld_contents =
-        fs::read_to_string(ld_path).expect("unable to read expected ld file");
+    let expected = fs::read_to_string("../tests/split_symbols_header/game.h")
+        .expect("unable to read expected file");
+    compare_multiline_strings(&expected, &writer.export_symbol_header_to_string().unwrap());
 
+    let expected = fs::read_to_string("../tests/split_symbols_header/game_rom.h")
+        .expect("unable to read expected file");
     compare_multiline_strings(
-        &expected_ld_contents,
+        &expected,
+        &writer.export_rom_symbol_header_to_string().unwrap(),
+    );
+
+    let expected = fs::read_to_string("../tests/split_symbols_header/game_linker_offsets.h")
+        .expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected,
         &writer
-            .get_main_writer()
-            .export_linker_script_to_string()
+            .export_linker_offsets_symbol_header_to_string()
             .unwrap(),
     );
+}
 
-    for (partial, name) in writer.get_partial_writers() {
-        let mut p = PathBuf::new();
+#[test]
+fn test_banners() {
+    let path = Path::new("../tests/banners/banners.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let mut rs = create_runtime_settings();
+    rs.set_emit_version_comment(true);
 
-        p.push("..");
-        p.push(
-            document
-                .settings
-                .partial_scripts_folder_escaped(&rs)
-                .expect("Not able to escape path")
-                .unwrap(),
-        );
-        p.push(&format!("{}.ld", name));
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
 
-        let expected_partial_ld_contents =
-            fs::read_to_string(p).expect("unable to read expected ld file");
+    let version = slinky::Version::current();
 
-        compare_multiline_strings(
-            &expected_partial_ld_contents,
-            &partial.export_linker_script_to_string().unwrap(),
-        );
-    }
-}
+    let ld = writer.export_linker_script_to_string().unwrap();
+    assert!(ld.starts_with(&format!(
+        "/*\n * Generated by slinky {}, do not edit\n */\n\n",
+        version
+    )));
 
-#[rstest]
-fn test_partial_linking_d_generation(#[files("../tests/partial_linking/*.d")] d_path: PathBuf) {
-    use slinky::EscapedPath;
+    // No banner at all, so the target rule is the first line
+    let target_path = &document.settings.target_path_escaped(&rs).unwrap().unwrap();
+    let d = writer
+        .export_dependencies_file_to_string(target_path)
+        .unwrap();
+    assert!(d.starts_with("build/game.elf:"));
 
-    let yaml_path = d_path.with_extension("yaml");
+    let h = writer.export_symbol_header_to_string().unwrap();
+    assert!(h.starts_with(&format!("// slinky {}\n\n#ifndef ", version)));
+}
 
-    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+#[test]
+fn test_segment_table() {
+    let path = Path::new("../tests/segment_table/game.yaml");
+    let mut document = slinky::Document::read_file(path).expect("unable to read original file");
     let rs = create_runtime_settings();
 
-    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
     writer.add_whole_document(&document).expect("");
 
-    let expected_d_contents = fs::read_to_string(d_path).expect("unable to read expected d file");
+    let expected_header = fs::read_to_string(path.with_file_name("segment_table.h"))
+        .expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected_header,
+        &writer.export_segment_table_header_to_string().unwrap(),
+    );
 
-    let target_path = &document.settings.target_path_escaped(&rs).unwrap().unwrap();
+    let expected_source = fs::read_to_string(path.with_file_name("segment_table.c"))
+        .expect("unable to read expected file");
     compare_multiline_strings(
-        &expected_d_contents,
-        &writer
-            .get_main_writer()
-            .export_dependencies_file_to_string(target_path)
-            .unwrap(),
+        &expected_source,
+        &writer.export_segment_table_to_string().unwrap(),
     );
 
-    for (partial, name) in writer.get_partial_writers() {
-        let mut p = PathBuf::new();
+    // Without a header the source declares the table by itself
+    document.settings.segment_table_header_path = None;
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
 
-        p.push("..");
-        p.push(
-            document
-                .settings
-                .partial_scripts_folder_escaped(&rs)
-                .expect("Unable to escape path")
-                .unwrap(),
-        );
-        p.push(&format!("{}.d", name));
+    let source = writer.export_segment_table_to_string().unwrap();
+    assert!(!source.contains("#include"));
+    assert!(source.contains("} SegmentTableEntry;"));
+}
 
-        let expected_partial_ld_contents =
-            fs::read_to_string(p).expect("unable to read expected d file");
+#[test]
+fn test_common_script() {
+    let path = Path::new("../tests/common_script/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
 
-        let mut partial_target = document
-            .settings
-            .base_path_escaped(&rs)
-            .expect("Failed to escape path");
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    writer.check_dependencies().expect("");
 
-        partial_target.push(
-            document
-                .settings
-                .partial_build_segments_folder_escaped(&rs)
-                .expect("Failed to escape path")
+    for (file_name, generated) in [
+        ("game.ld", writer.export_linker_script_to_string().unwrap()),
+        (
+            "common.ld",
+            writer.export_common_script_to_string().unwrap(),
+        ),
+        (
+            "game.d",
+            writer
+                .export_dependencies_file_to_string(
+                    &document.settings.target_path_escaped(&rs).unwrap().unwrap(),
+                )
                 .unwrap(),
-        );
-        partial_target.push(EscapedPath::from(format!("{}.o", name)));
+        ),
+    ] {
+        let expected = fs::read_to_string(path.with_file_name(file_name))
+            .expect("unable to read expected file");
+        compare_multiline_strings(&expected, &generated);
+    }
 
-        compare_multiline_strings(
-            &expected_partial_ld_contents,
-            &partial
-                .export_dependencies_file_to_string(&partial_target)
+    // The symbols moved to the common script are still tracked
+    assert!(writer.get_linker_symbols().contains("gBootRomStart"));
+
+    // Every version shares the same common script
+    let mut other_rs = RuntimeSettings::new();
+    other_rs.add_custom_options([("version".into(), "jp".into())]);
+    other_rs.set_emit_version_comment(false);
+
+    let mut other_writer = slinky::LinkerWriter::new(&document, &other_rs);
+    other_writer.add_whole_document(&document).expect("");
+
+    assert_eq!(
+        writer.export_common_script_to_string().unwrap(),
+        other_writer.export_common_script_to_string().unwrap()
+    );
+    assert_ne!(
+        writer.export_linker_script_to_string().unwrap(),
+        other_writer.export_linker_script_to_string().unwrap()
+    );
+}
+
+#[test]
+fn test_files_from() {
+    let path = Path::new("../tests/files_from/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    writer.check_dependencies().expect("");
+
+    for (file_name, generated) in [
+        ("game.ld", writer.export_linker_script_to_string().unwrap()),
+        (
+            "game.d",
+            writer
+                .export_dependencies_file_to_string(
+                    &document.settings.target_path_escaped(&rs).unwrap().unwrap(),
+                )
                 .unwrap(),
+        ),
+    ] {
+        let expected = fs::read_to_string(path.with_file_name(file_name))
+            .expect("unable to read expected file");
+        compare_multiline_strings(&expected, &generated);
+    }
+
+    // The files of the document go before the ones of the list
+    let boot = &document.segments[0];
+    assert_eq!(boot.files[0].path, Path::new("src/boot/entry.o"));
+    assert_eq!(boot.files.len(), 4);
+    assert_eq!(
+        boot.files_from.as_deref(),
+        Some(Path::new("../tests/files_from/boot.txt"))
+    );
+}
+
+#[test]
+fn test_noload_merge_not_emitted() {
+    let path = Path::new("../tests/test_cases/noload_merge.yaml");
+    let mut document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let shared = document
+        .segments
+        .iter_mut()
+        .find(|x| x.name == "overlays_bss")
+        .unwrap();
+    shared.exclude_if_any = vec![("version".into(), "us".into())];
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    assert_eq!(
+        writer.add_whole_document(&document),
+        Err(SlinkyError::MissingNoloadMergeSegment {
+            segment: "ovl_title".into(),
+            noload_merge: "overlays_bss".into(),
+        })
+    );
+}
+
+#[test]
+fn test_fingerprint() {
+    let path = Path::new("../tests/segment_table/game.yaml");
+    let mut document = slinky::Document::read_file(path).expect("unable to read original file");
+    let mut rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    let script = writer.export_linker_script_to_string().unwrap();
+    assert_eq!(slinky::read_fingerprint(&script), None);
+
+    rs.set_emit_fingerprint(true);
+    let fingerprint = document.fingerprint(&rs).unwrap();
+    assert_eq!(fingerprint.len(), 16);
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    for contents in [
+        writer.export_linker_script_to_string().unwrap(),
+        writer.export_symbol_header_to_string().unwrap(),
+        writer.export_segment_table_to_string().unwrap(),
+        writer.export_toolchain_objects_to_string().unwrap(),
+    ] {
+        assert_eq!(
+            slinky::read_fingerprint(&contents),
+            Some(fingerprint.as_str())
         );
     }
+
+    // Changing either the custom options or the document changes it
+    let mut jp_rs = rs.clone();
+    jp_rs.add_custom_options([("version".into(), "jp".into())]);
+    assert_ne!(document.fingerprint(&jp_rs).unwrap(), fingerprint);
+
+    document.segments[0].fixed_vram = Some(0x80001000);
+    assert_ne!(document.fingerprint(&rs).unwrap(), fingerprint);
+}
+
+#[test]
+fn test_symbols_manifest() {
+    let path = Path::new("../tests/symbols_manifest/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(path.with_extension("json")).expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected,
+        &writer.export_symbols_manifest_to_string().unwrap(),
+    );
+
+    let origin = writer
+        .get_linker_symbol_origin("boot_data_start_OFFSET")
+        .unwrap();
+    assert_eq!(origin.entry.yaml_path(), "segments[0].files[1]");
+    assert_eq!(origin.field.as_deref(), Some("linker_offset_name"));
+
+    // The manifest can be imported by other documents
+    let import = slinky::Import {
+        path: path.with_extension("json"),
+        symbols: vec!["rom_end".to_string()],
+    };
+    assert_eq!(
+        &import.available_symbols(&rs).unwrap(),
+        writer.get_linker_symbols()
+    );
+}
+
+#[test]
+fn test_file_symbols() {
+    let path = Path::new("../tests/test_cases/file_symbols.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let symbols = writer.get_linker_symbols();
+    assert!(symbols.contains("boot_src_boot_boot_main_TEXT_START"));
+    assert!(symbols.contains("boot_src_boot_boot_main_TEXT_END"));
+    // Overridden by the file and by the group member
+    assert!(!symbols.contains("boot_src_libc_util_TEXT_START"));
+    assert!(!symbols.contains("code_src_code_graph_gfx_TEXT_START"));
+
+    let origin = writer
+        .get_linker_symbol_origin("code_src_code_graph_graph_DATA_END")
+        .unwrap();
+    assert_eq!(origin.entry.yaml_path(), "segments[1].files[2].files[0]");
+    assert_eq!(origin.field.as_deref(), Some("emit_file_symbols"));
+}
+
+#[test]
+fn test_script_items() {
+    let path = Path::new("../tests/test_cases/archives.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let items: Vec<slinky::ScriptItem> = writer.script_items().unwrap().collect();
+    assert!(items.contains(&slinky::ScriptItem::SectionStart {
+        name: ".boot",
+        header: ".boot : AT(boot_ROM_START)",
+    }));
+    assert!(items.contains(&slinky::ScriptItem::SymbolAssignment {
+        name: "boot_TEXT_START",
+        operator: "=",
+        value: ".",
+        provide: false,
+        hidden: false,
+    }));
+    assert!(items.contains(&slinky::ScriptItem::FileEntry {
+        path: "build/lib/libmus.a",
+        member: Some("player.o"),
+        section: ".text*",
+        keep: false,
+    }));
+
+    // Every line of the script is a single item, and the braces are balanced
+    let script = writer.export_linker_script_to_string().unwrap();
+    assert_eq!(items.len(), script.lines().count());
+    let count = |item| items.iter().filter(|x| **x == item).count();
+    assert_eq!(
+        count(slinky::ScriptItem::BlockStart),
+        count(slinky::ScriptItem::BlockEnd)
+    );
+    assert!(!items
+        .iter()
+        .any(|x| matches!(x, slinky::ScriptItem::Other(line) if line.contains(".o("))));
 }
 
 #[rstest]
-fn test_partial_linking_symbols_header_generation(
-    #[files("../tests/partial_linking/*.h")] h_path: PathBuf,
+fn test_vram_class_slack_report_generation(
+    #[files("../tests/test_cases/*.csv")] csv_path: PathBuf,
 ) {
-    let yaml_path = h_path.with_extension("yaml");
+    let yaml_path = csv_path.with_extension("yaml");
+
     let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
     let rs = create_runtime_settings();
 
-    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
     writer.add_whole_document(&document).expect("");
 
-    let expected_h_contents = fs::read_to_string(h_path).expect("unable to read expected h file");
+    let expected_csv_contents =
+        fs::read_to_string(csv_path).expect("unable to read expected csv file");
 
     compare_multiline_strings(
-        &expected_h_contents,
-        &writer
-            .get_main_writer()
-            .export_symbol_header_to_string()
-            .unwrap(),
+        &expected_csv_contents,
+        &writer.export_vram_class_slack_report_to_string().unwrap(),
+    );
+}
+
+#[rstest]
+fn test_symbols_asm_generation(#[files("../tests/test_cases/*.inc")] inc_path: PathBuf) {
+    let yaml_path = inc_path.with_extension("yaml");
+
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_inc_contents =
+        fs::read_to_string(inc_path).expect("unable to read expected inc file");
+
+    compare_multiline_strings(
+        &expected_inc_contents,
+        &writer.export_symbols_asm_to_string().unwrap(),
+    );
+}
+
+#[rstest]
+fn test_symbols_rust_generation(#[files("../tests/test_cases/*.rs")] rs_path: PathBuf) {
+    let yaml_path = rs_path.with_extension("yaml");
+
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_rs_contents =
+        fs::read_to_string(rs_path).expect("unable to read expected rs file");
+
+    compare_multiline_strings(
+        &expected_rs_contents,
+        &writer.export_symbols_rust_to_string().unwrap(),
+    );
+}
+
+#[test]
+fn test_document_cache() {
+    let basic_example = Path::new("../tests/test_cases/basic_example.yaml");
+    let archives = Path::new("../tests/test_cases/archives.yaml");
+
+    let mut cache = slinky::DocumentCache::new(1);
+
+    let first = cache.read_file(basic_example).expect("unable to read file");
+    let second = cache.read_file(basic_example).expect("unable to read file");
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert_eq!(cache.len(), 1);
+
+    // Evicts `basic_example`, since the cache only holds one document
+    cache.read_file(archives).expect("unable to read file");
+    assert_eq!(cache.len(), 1);
+
+    let third = cache.read_file(basic_example).expect("unable to read file");
+    assert!(!std::sync::Arc::ptr_eq(&first, &third));
+    assert_eq!(*first, *third);
+
+    cache.invalidate(basic_example);
+    assert!(cache.is_empty());
+}
+
+/// Overwrites the file, waiting until its modification time changes for
+/// filesystems with a coarse timestamp resolution.
+fn touch_with_contents(path: &Path, contents: &str) {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).unwrap();
+
+    let original = modified(path);
+    loop {
+        fs::write(path, contents).expect("unable to write file");
+        if modified(path) != original {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_document_cache_dependencies() {
+    let folder = std::env::temp_dir().join("slinky_document_cache_dependencies");
+    fs::create_dir_all(&folder).expect("unable to create folder");
+    let main = folder.join("main.yaml");
+    let included = folder.join("included.yaml");
+    let files_list = folder.join("files.txt");
+
+    fs::write(
+        &main,
+        format!(
+            "include: [included.yaml]\nsegments:\n  - name: boot\n    files_from: {}\n",
+            files_list.display()
+        ),
+    )
+    .expect("unable to write file");
+    fs::write(
+        &included,
+        "segments:\n  - name: code\n    files:\n      - { path: a.o }\n",
+    )
+    .expect("unable to write file");
+    fs::write(&files_list, "boot.o\n").expect("unable to write file");
+
+    let mut cache = slinky::DocumentCache::new(1);
+
+    let first = cache.read_file(&main).expect("unable to read file");
+    let second = cache.read_file(&main).expect("unable to read file");
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+
+    touch_with_contents(
+        &included,
+        "segments:\n  - name: code\n    files:\n      - { path: b.o }\n",
+    );
+    let third = cache.read_file(&main).expect("unable to read file");
+    assert_eq!(third.segments[1].files[0].path, PathBuf::from("b.o"));
+
+    touch_with_contents(&files_list, "entry.o\n");
+    let fourth = cache.read_file(&main).expect("unable to read file");
+    assert_eq!(fourth.segments[0].files[0].path, PathBuf::from("entry.o"));
+
+    fs::remove_dir_all(&folder).expect("unable to remove folder");
+}
+
+#[rstest]
+#[should_panic]
+fn test_panic_invalid_yamls(#[files("../tests/panics/*.yaml")] path: PathBuf) {
+    slinky::Document::read_file(&path).unwrap();
+}
+
+#[test]
+fn test_validate() {
+    // Segments and files declared multiple times on different conditions are fine
+    let path = Path::new("../tests/test_cases/conditional_includes.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    assert_eq!(document.validate(), []);
+
+    let path = Path::new("../tests/validation/invalid_document.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let errors = document.validate();
+    for error in &errors {
+        println!("{}", error);
+    }
+
+    // Both classes of the cycle, the undefined followed class, the duplicated
+    // class, the duplicated segment, the duplicated file, the undefined
+    // followed segment, the undefined vram class, both unknown sections, the
+    // duplicated target, the raw block placed after an undefined segment, the
+    // symbol assignment placed inside an undefined segment, both segments of
+    // the `follows_segment` cycle, the segment following a later one, the
+    // noload sections merged into an undefined segment and the ones merged
+    // into a segment which is merged itself
+    assert_eq!(errors.len(), 18);
+}
+
+#[test]
+fn test_conditional_coverage() {
+    let path = Path::new("../tests/validation/coverage.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let rs = slinky::RuntimeSettings::new();
+    assert_eq!(document.configurations(&rs).len(), 4);
+
+    let report = document.conditional_coverage(&rs);
+    assert_eq!(report.configurations, 4);
+
+    // The `eu` file and the `debug` segment, but not the file inside of it
+    let never_emitted: Vec<String> = report.never_emitted.iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        never_emitted,
+        ["segment 'boot', file index 1", "segment 'debug'"]
+    );
+
+    // The group included on every version
+    let always_emitted: Vec<String> = report
+        .always_emitted
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    assert_eq!(always_emitted, ["segment 'boot', file index 3"]);
+
+    // Fixing the compiler turns the `ido` file and symbol into dead entries
+    let mut rs = slinky::RuntimeSettings::new();
+    rs.add_custom_options([("compiler".to_string(), "modern_gcc".to_string())]);
+    let mut document = document;
+    document.runtime_options.retain(|x| x.name != "compiler");
+    let report = document.conditional_coverage(&rs);
+    assert_eq!(report.configurations, 2);
+    assert_eq!(report.never_emitted.len(), 4);
+}
+
+#[test]
+fn test_read_from_reader() {
+    let path = Path::new("../tests/test_cases/basic_example.yaml");
+    let mut expected = slinky::Document::read_file(path).expect("unable to read original file");
+    // Only documents read from files know their path
+    assert_eq!(expected.path.as_deref(), Some(path));
+    expected.path = None;
+
+    let file = fs::File::open(path).expect("unable to open original file");
+    assert_eq!(
+        slinky::Document::read_from_reader(file).expect("unable to read from reader"),
+        expected
+    );
+
+    let contents = fs::read_to_string(path).expect("unable to read original file");
+    assert_eq!(
+        slinky::Document::read_from_str(&contents).expect("unable to read from str"),
+        expected
+    );
+}
+
+#[test]
+fn test_document_queries() {
+    let path = Path::new("../tests/test_cases/metadata.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    assert_eq!(
+        document.segment("boot").map(|x| x.fixed_vram),
+        Some(Some(0x80000400))
+    );
+    assert!(document.segment("missing").is_none());
+
+    let overlays: Vec<&str> = document
+        .segments_in_vram_class("overlays")
+        .map(|x| x.name.as_str())
+        .collect();
+    assert_eq!(overlays, ["ovl_title"]);
+
+    // The files nested on the group are included, right after it
+    let objects: Vec<String> = document
+        .files_of_kind(slinky::FileKind::Object)
+        .map(|x| x.path.display().to_string())
+        .collect();
+    assert_eq!(
+        objects,
+        [
+            "src/boot/boot_main.o",
+            "src/boot/dmadata.o",
+            "osInitialize.o",
+            "src/overlays/title.o"
+        ]
+    );
+    assert_eq!(document.files_of_kind(slinky::FileKind::Group).count(), 1);
+    assert_eq!(document.files().count(), 5);
+}
+
+#[test]
+fn test_includes() {
+    let path = Path::new("../tests/test_cases/includes.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    // Nested includes are relative to the file listing them
+    assert_eq!(
+        document.included_files,
+        [
+            Path::new("../tests/test_cases/includes/overlays.yaml"),
+            Path::new("../tests/test_cases/includes/overlay_templates.yaml"),
+            Path::new("../tests/test_cases/includes/symbols.yaml"),
+        ]
+    );
+
+    // The entries of the included documents go after the ones of the main one
+    let segments: Vec<&str> = document.segments.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(segments, ["boot", "code", "ovl_title", "ovl_file_select"]);
+
+    let rs = create_runtime_settings();
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let d_contents = writer
+        .export_dependencies_file_to_string(&slinky::EscapedPath::from("game.elf".to_string()))
+        .unwrap();
+    assert!(d_contents.contains("    ../tests/test_cases/includes/symbols.yaml\n"));
+    assert!(d_contents.contains("\n../tests/test_cases/includes/overlays.yaml:\n"));
+}
+
+#[test]
+fn test_resolved_segments() {
+    let path = Path::new("../tests/validation/coverage.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let mut rs = slinky::RuntimeSettings::new();
+    rs.add_custom_options([
+        ("version".to_string(), "us".to_string()),
+        ("compiler".to_string(), "modern_gcc".to_string()),
+    ]);
+
+    // The `debug` segment is excluded on `us`
+    let segments = document.resolved_segments(&rs).expect("");
+    let names: Vec<&str> = segments.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, ["boot"]);
+
+    let files: Vec<&Path> = segments[0].files.iter().map(|x| x.path.as_path()).collect();
+    assert_eq!(files, [Path::new("src/boot/boot_main.o"), Path::new("")]);
+    assert_eq!(segments[0].files[1].files.len(), 1);
+    assert!(segments[0].files[1].include_if_any.is_empty());
+}
+
+#[test]
+fn test_required_slinky_version() {
+    let current = slinky::Version::current();
+    assert!(current.satisfies(&"0.3".parse().unwrap()));
+    assert!(!current.satisfies(&slinky::Version::new(current.major + 1, 0, 0)));
+    assert!("0.3.1.4".parse::<slinky::Version>().is_err());
+
+    let document = slinky::Document::from_yaml_str(&format!(
+        "required_slinky_version: {}\nsegments: [{{ name: boot, files: [{{ path: boot.o }}] }}]",
+        current
+    ))
+    .expect("unable to read document");
+    assert_eq!(document.required_slinky_version, Some(current));
+
+    // The outdated version is reported instead of the unknown attribute
+    let err = slinky::Document::from_yaml_str(
+        "required_slinky_version: 999.0.0\nsegments: []\nsome_future_attribute: true",
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        slinky::SlinkyError::UnsupportedSlinkyVersion { .. }
+    ));
+}
+
+#[test]
+fn test_runtime_options() {
+    let path = Path::new("../tests/validation/runtime_options.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    // The duplicated option, the value not allowed on `eu` and the misspelled
+    // option on `jp`
+    let errors = document.validate();
+    for error in &errors {
+        println!("{}", error);
+    }
+    assert_eq!(errors.len(), 3);
+
+    let mut rs = slinky::RuntimeSettings::new();
+    rs.add_custom_options([("version".to_string(), "us".to_string())]);
+    assert!(rs.check_custom_options(&document.runtime_options).is_ok());
+
+    // Options with no `values` accept anything
+    rs.add_custom_options([("video".to_string(), "ntsc".to_string())]);
+    assert!(rs.check_custom_options(&document.runtime_options).is_ok());
+
+    let mut rs = slinky::RuntimeSettings::new();
+    rs.add_custom_options([("verison".to_string(), "us".to_string())]);
+    assert!(matches!(
+        rs.check_custom_options(&document.runtime_options),
+        Err(slinky::SlinkyError::UnknownCustomOption { .. })
+    ));
+
+    let mut rs = slinky::RuntimeSettings::new();
+    rs.add_custom_options([("version".to_string(), "eu".to_string())]);
+    assert!(matches!(
+        rs.check_custom_options(&document.runtime_options),
+        Err(slinky::SlinkyError::InvalidCustomOptionValue { .. })
+    ));
+
+    // Nothing is checked if the document does not declare any option
+    assert!(rs.check_custom_options(&[]).is_ok());
+}
+
+#[test]
+fn test_warnings() {
+    let path = Path::new("../tests/validation/warnings.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    // Warnings are not errors
+    assert_eq!(document.validate(), []);
+
+    // The discarded section referenced by `section_order`, the noload section
+    // listed on `alloc_sections` and the empty segment
+    let rs = create_runtime_settings();
+    let warnings = document.warnings(&rs);
+    for warning in &warnings {
+        println!("{}", warning);
+        assert_eq!(warning.severity, slinky::Severity::Warning);
+    }
+    assert_eq!(warnings.len(), 3);
+
+    // `ovl_jp` is not empty on this version, but its group references a
+    // discarded section
+    let mut rs = slinky::RuntimeSettings::new();
+    rs.add_custom_options([("version".to_string(), "jp".to_string())]);
+    let warnings = document.warnings(&rs);
+    for warning in &warnings {
+        println!("{}", warning);
+    }
+    assert_eq!(warnings.len(), 3);
+    assert_eq!(
+        warnings[2].entry.as_ref().unwrap().to_string(),
+        "segment 'ovl_jp', file index 1, file index 0"
+    );
+    assert_eq!(warnings[2].field.as_deref(), Some("subsection_order"));
+}
+
+#[test]
+fn test_object_sections() {
+    let path = Path::new("../tests/object_sections/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let warnings = document.warnings(&rs);
+    for warning in &warnings {
+        println!("{}", warning);
+    }
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        *warnings[0].error,
+        slinky::SlinkyError::UnplacedObjectSection {
+            path: PathBuf::from("../tests/object_sections/boot.o"),
+            section: ".rodata.str1.1".to_string(),
+            segment: "boot".into(),
+        }
+    );
+    assert_eq!(
+        warnings[0].entry.as_ref().unwrap().to_string(),
+        "segment 'boot', file index 0"
+    );
+
+    let path = Path::new("../tests/object_sections/corrupt.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let warnings = document.warnings(&rs);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+        *warnings[0].error,
+        slinky::SlinkyError::InvalidObject {
+            path: PathBuf::from("../tests/object_sections/corrupt_header.o"),
+            description: "offset out of bounds".to_string(),
+        }
+    );
+    assert_eq!(
+        *warnings[1].error,
+        slinky::SlinkyError::InvalidObject {
+            path: PathBuf::from("../tests/object_sections/corrupt_strtab.o"),
+            description: "section names table out of bounds".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_imports() {
+    let mut rs = create_runtime_settings();
+    rs.add_custom_options([("root".to_string(), "..".to_string())]);
+
+    let document = slinky::Document::read_file(Path::new("../tests/imports/game.yaml"))
+        .expect("unable to read original file");
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer
+        .add_whole_document(&document)
+        .expect("imported symbols should be found");
+
+    let manifest_symbols = document.imports[1]
+        .available_symbols(&rs)
+        .expect("unable to read manifest");
+    assert_eq!(manifest_symbols.len(), 2);
+
+    let document = slinky::Document::read_file(Path::new("../tests/imports/missing_symbol.yaml"))
+        .expect("unable to read original file");
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    assert_eq!(
+        writer.add_whole_document(&document),
+        Err(slinky::SlinkyError::MissingImportedSymbol {
+            symbol: "ipl_CODE_START".to_string(),
+            path: PathBuf::from("{root}/tests/imports/ipl.yaml"),
+        })
+    );
+}
+
+#[test]
+fn test_map_verifier() {
+    let document = slinky::Document::read_file(Path::new("../tests/map_files/basic.yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+    let verifier = slinky::MapVerifier::new(&document, &rs);
+
+    let empty_file = MapIssue::FileWithoutSections {
+        segment: "code".into(),
+        file: "build/src/code/empty.o".into(),
+    };
+    let assert_warning = MapIssue::AssertWarning {
+        index: 1,
+        message: "code is larger than 0x10 bytes".into(),
+    };
+
+    let report = verifier
+        .verify_map_file(Path::new("../tests/map_files/basic.map"))
+        .expect("unable to verify map file");
+    assert_eq!(report.issues, [empty_file.clone(), assert_warning.clone()]);
+
+    let report = verifier
+        .verify_map_file(Path::new("../tests/map_files/basic_broken.map"))
+        .expect("unable to verify map file");
+    assert_eq!(
+        report.issues,
+        [
+            empty_file,
+            MapIssue::FixedVramNotHonored {
+                segment: "ovl_title".into(),
+                expected: 0x80100000,
+                actual: 0x80100010,
+            },
+            MapIssue::RomOverlap {
+                segment: "code".into(),
+                other_segment: "boot".into(),
+            },
+            assert_warning,
+        ]
+    );
+}
+
+#[test]
+fn test_explain_segment() {
+    use slinky::{EmitReason, PlacementStep};
+
+    let document = slinky::Document::read_file(Path::new("../tests/explain/game.yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let explanation = document.explain_segment("ovl_b", &rs).unwrap();
+    assert_eq!(explanation.reason, EmitReason::Unconditional);
+    assert_eq!(
+        explanation.placement,
+        [
+            PlacementStep::OverlayGroup {
+                segment: "ovl_b".into(),
+                overlay_group: "ovls".into(),
+                first: "ovl_a".into(),
+            },
+            PlacementStep::FollowsSegment {
+                segment: "ovl_a".into(),
+                follows: "code".into(),
+            },
+            PlacementStep::PreviousSegment {
+                segment: "code".into(),
+                previous: "boot".into(),
+            },
+            PlacementStep::FixedVram {
+                segment: "boot".into(),
+                vram: 0x80000400,
+            },
+        ]
+    );
+
+    let explanation = document.explain_segment("code", &rs).unwrap();
+    let files: Vec<String> = explanation
+        .files
+        .iter()
+        .map(|x| {
+            format!(
+                "{} {} {}",
+                x.file,
+                x.entry.yaml_path(),
+                x.reason.is_emitted()
+            )
+        })
+        .collect();
+    assert_eq!(
+        files,
+        [
+            "build/src/code/main.o segments[1].files[0] true",
+            "build/src/code/jp.o segments[1].files[1] false",
+            "group build/lib segments[1].files[2] true",
+            "build/lib/libultra.a:* segments[1].files[2].files[0] true",
+        ]
+    );
+    assert!(explanation.files[0]
+        .sections
+        .contains(&(".data".to_string(), ".rodata".to_string())));
+    assert!(explanation.files[1].sections.is_empty());
+
+    let explanation = document.explain_segment("ovl_title", &rs).unwrap();
+    assert_eq!(explanation.reason, EmitReason::NotIncluded);
+    assert!(explanation.placement.is_empty());
+
+    let mut jp_rs = slinky::RuntimeSettings::new();
+    jp_rs.add_custom_options([("version".into(), "jp".into())]);
+    let explanation = document.explain_segment("ovl_title", &jp_rs).unwrap();
+    assert_eq!(
+        explanation.reason,
+        EmitReason::Included {
+            condition: "include_if_any (version=jp)".into()
+        }
+    );
+    assert_eq!(
+        explanation.placement,
+        [
+            PlacementStep::VramClass {
+                segment: "ovl_title".into(),
+                vram_class: "overlays".into(),
+            },
+            PlacementStep::VramClassFixedVram {
+                vram_class: "overlays".into(),
+                vram: 0x80200000,
+            },
+        ]
+    );
+
+    assert!(document.explain_segment("missing", &rs).is_err());
+}
+
+#[test]
+fn test_script_diff() {
+    let old = Path::new("../tests/script_diff/old.ld");
+    let new = Path::new("../tests/script_diff/new.ld");
+
+    let diff = slinky::ScriptDiff::from_linker_scripts(old, old).expect("unable to diff scripts");
+    assert!(diff.is_empty());
+
+    let diff = slinky::ScriptDiff::from_linker_scripts(old, new).expect("unable to diff scripts");
+    assert_eq!(
+        diff.changes,
+        [
+            ScriptChange::SegmentMoved {
+                segment: "code".into(),
+            },
+            ScriptChange::SegmentAttributeChanged {
+                segment: "boot".into(),
+                attribute: "fixed_vram".into(),
+                old: "0x80000400".into(),
+                new: "0x80000460".into(),
+            },
+            ScriptChange::FileMoved {
+                segment: "boot".into(),
+                file: "build/src/boot/dmadata.o".into(),
+            },
+            ScriptChange::FileRemoved {
+                segment: "code".into(),
+                file: "build/src/code/graph.o".into(),
+            },
+            ScriptChange::FileAdded {
+                segment: "code".into(),
+                file: "build/src/code/sched.o".into(),
+            },
+            ScriptChange::SymbolRemoved {
+                symbol: "osRomBase".into(),
+            },
+            ScriptChange::SymbolAdded {
+                symbol: "osResetType".into(),
+                value: "0x8000030C".into(),
+            },
+        ]
+    );
+}
+
+#[rstest]
+#[case("overlay_group")]
+#[case("rom_model_none")]
+#[case("segment_groups")]
+fn test_script_diff_overlays(#[case] name: &str) {
+    let path = PathBuf::from(format!("../tests/test_cases/{}.ld", name));
+
+    let diff =
+        slinky::ScriptDiff::from_linker_scripts(&path, &path).expect("unable to diff scripts");
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn test_script_diff_overlay_group() {
+    let yaml_path = Path::new("../tests/test_cases/overlay_group.yaml");
+    let old = fs::read_to_string(yaml_path.with_extension("ld")).expect("unable to read ld file");
+
+    let contents = fs::read_to_string(yaml_path)
+        .expect("unable to read yaml file")
+        .replace(
+            "- { path: src/audio/audio.o }",
+            "- { path: src/audio/audio.o }\n      - { path: src/audio/synth.o }",
+        );
+    let document = slinky::Document::from_yaml_str(&contents).expect("unable to read document");
+    let rs = create_runtime_settings();
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    let new = writer.export_linker_script_to_string().unwrap();
+
+    let diff =
+        slinky::ScriptDiff::from_linker_script_strs(&old, &new).expect("unable to diff scripts");
+    assert_eq!(
+        diff.changes,
+        [ScriptChange::FileAdded {
+            segment: "audio".into(),
+            file: "build/src/audio/synth.o".into(),
+        }]
+    );
+}
+
+#[test]
+fn test_placement_advisor() {
+    let document = slinky::Document::read_file(Path::new("../tests/map_files/overlays.yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+    let advisor = slinky::PlacementAdvisor::new(&document, &rs);
+
+    let report = advisor
+        .suggest_from_map_file(Path::new("../tests/map_files/overlays.map"))
+        .expect("unable to read map file");
+
+    assert_eq!(
+        report.usages,
+        [
+            VramClassUsage {
+                vram_class: "battle".into(),
+                size: 0x180,
+                max_size: Some(0x100),
+            },
+            VramClassUsage {
+                vram_class: "field".into(),
+                size: 0x300,
+                max_size: Some(0x400),
+            },
+            VramClassUsage {
+                vram_class: "menu".into(),
+                size: 0x100,
+                max_size: None,
+            },
+        ]
+    );
+    assert_eq!(
+        report.suggestions,
+        [
+            PlacementSuggestion {
+                segment: "battle_a".into(),
+                segment_size: 0x180,
+                from_class: "battle".into(),
+                from_class_delta: -0x100,
+                to_class: "field".into(),
+                to_class_delta: 0,
+            },
+            PlacementSuggestion {
+                segment: "battle_a".into(),
+                segment_size: 0x180,
+                from_class: "battle".into(),
+                from_class_delta: -0x100,
+                to_class: "menu".into(),
+                to_class_delta: 0x80,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_size_diff() {
+    let document = slinky::Document::read_file(Path::new("../tests/map_files/basic.yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+    let differ = slinky::SizeDiffer::new(&document, &rs);
+
+    let diff = differ
+        .diff_map_files(
+            Path::new("../tests/map_files/basic.map"),
+            Path::new("../tests/map_files/basic_grown.map"),
+        )
+        .expect("unable to read map files");
+    assert_eq!(diff.segments.len(), 3);
+
+    let changed: Vec<&slinky::SegmentSizeDiff> = diff.changed().collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].segment, "boot");
+    assert_eq!(
+        changed[0].size,
+        SizeDelta {
+            old: Some(0x38),
+            new: Some(0x48),
+        }
+    );
+    assert_eq!(changed[0].size.delta(), 0x10);
+    assert!(changed[0].size.crosses_alignment(0x10));
+    assert!(!changed[0].size.crosses_alignment(0x100));
+
+    let sections: Vec<(&str, i64)> = changed[0]
+        .sections
+        .iter()
+        .filter(|(_, size)| size.is_changed())
+        .map(|(section, size)| (section.as_str(), size.delta()))
+        .collect();
+    assert_eq!(sections, [(".text", 0x10)]);
+    assert_eq!(changed[0].sections.len(), 4);
+
+    // The old build is compared against itself
+    let diff = differ
+        .diff_map_files(
+            Path::new("../tests/map_files/basic.map"),
+            Path::new("../tests/map_files/basic.map"),
+        )
+        .expect("unable to read map files");
+    assert_eq!(diff.changed().count(), 0);
+}
+
+#[rstest]
+fn test_effective_config(#[files("../tests/test_cases/*.ld")] ld_path: PathBuf) {
+    let yaml_path = ld_path.with_extension("yaml");
+
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    // The effective document should be readable again and produce the same linker script
+    let effective_yaml = document
+        .effective_yaml(&rs)
+        .expect("unable to serialize effective document");
+    let effective_path = std::env::temp_dir().join(format!(
+        "slinky_effective_{}",
+        yaml_path.file_name().unwrap().to_str().unwrap()
+    ));
+    fs::write(&effective_path, effective_yaml).expect("unable to write effective document");
+    let effective =
+        slinky::Document::read_file(&effective_path).expect("unable to read effective document");
+    fs::remove_file(&effective_path).expect("unable to remove effective document");
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).unwrap();
+    let mut effective_writer = slinky::LinkerWriter::new(&effective, &rs);
+    effective_writer.add_whole_document(&effective).unwrap();
+
+    compare_multiline_strings(
+        &writer.export_linker_script_to_string().unwrap(),
+        &effective_writer.export_linker_script_to_string().unwrap(),
+    );
+}
+
+#[test]
+fn test_targets() {
+    let document = slinky::Document::read_file(Path::new("../tests/targets/versions.yaml"))
+        .expect("unable to read original file");
+    let base_rs = create_runtime_settings();
+
+    assert_eq!(document.targets.len(), 3);
+    assert!(document.target("ch").is_err());
+
+    // The document is parsed once and reused for every target
+    for target in &document.targets {
+        let rs = target.runtime_settings(&base_rs);
+
+        let mut writer = slinky::LinkerWriter::new(&document, &rs);
+        writer.add_whole_document(&document).unwrap();
+
+        let expected_ld_contents =
+            fs::read_to_string(format!("../tests/targets/versions.{}.ld", target.name))
+                .expect("unable to read expected ld file");
+
+        compare_multiline_strings(
+            &expected_ld_contents,
+            &writer.export_linker_script_to_string().unwrap(),
+        );
+    }
+}
+
+#[test]
+fn test_write_if_changed() {
+    let document = slinky::Document::read_file(Path::new("../tests/test_cases/basic_example.yaml"))
+        .expect("unable to read original file");
+    let mut rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).unwrap();
+
+    let path = std::env::temp_dir().join("slinky_write_if_changed.ld");
+    let escaped = slinky::EscapedPath::from(path.to_str().unwrap().to_string());
+    let _ = fs::remove_file(&path);
+
+    let written = writer.export_linker_script_to_file(&escaped).unwrap();
+    assert_eq!(written.len(), 1);
+    assert!(written[0].updated);
+
+    // Without the flag the file is always written
+    let written = writer.export_linker_script_to_file(&escaped).unwrap();
+    assert!(written[0].updated);
+
+    rs.set_write_if_changed(true);
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).unwrap();
+
+    let written = writer.export_linker_script_to_file(&escaped).unwrap();
+    assert_eq!(written[0].path, path);
+    assert!(!written[0].updated);
+
+    fs::write(&path, "").unwrap();
+    let written = writer.export_linker_script_to_file(&escaped).unwrap();
+    assert!(written[0].updated);
+
+    fs::remove_file(&path).expect("unable to remove generated file");
+}
+
+#[test]
+fn test_symbols_header_user_additions() {
+    let yaml = "\
+settings:
+  symbols_header_keep_user_additions: True
+
+segments:
+  - name: boot
+    files:
+      - { path: src/boot/boot_main.o }
+";
+    let document = slinky::Document::from_yaml_str(yaml).unwrap();
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).unwrap();
+
+    let path = std::env::temp_dir().join("slinky_symbols_header_user_additions.h");
+    let escaped = slinky::EscapedPath::from(path.to_str().unwrap().to_string());
+    let _ = fs::remove_file(&path);
+
+    writer.export_symbol_header_to_file(&escaped).unwrap();
+    let generated = fs::read_to_string(&path).unwrap();
+    assert!(generated.ends_with(
+        "/* Additions below this line are kept when regenerating this header */\n\n#endif\n"
+    ));
+
+    let user_line = "extern char my_custom_symbol[];";
+    fs::write(
+        &path,
+        generated.replace(
+            "regenerating this header */\n",
+            &format!("regenerating this header */\n{}\n", user_line),
+        ),
+    )
+    .unwrap();
+
+    writer.export_symbol_header_to_file(&escaped).unwrap();
+    let regenerated = fs::read_to_string(&path).unwrap();
+    assert!(regenerated.ends_with(&format!(
+        "regenerating this header */\n{}\n\n#endif\n",
+        user_line
+    )));
+    assert_eq!(regenerated.matches(user_line).count(), 1);
+
+    fs::remove_file(&path).expect("unable to remove generated file");
+}
+
+#[test]
+fn test_diagnostics() {
+    let yaml = "\
+segments:
+  - name: boot
+    files:
+      - { path: src/boot/boot_main.o }
+  - name: main
+    files:
+      - { path: src/main/main.o }
+      - { path: src/main/util.o, subfile: util.o }
+";
+
+    let diagnostic = slinky::Document::from_yaml_str_with_diagnostics(yaml).unwrap_err();
+    println!("{}", diagnostic);
+
+    assert_eq!(diagnostic.severity, slinky::Severity::Error);
+    assert_eq!(
+        diagnostic.entry.unwrap().to_string(),
+        "segment 'main', file index 1"
+    );
+    assert_eq!(diagnostic.field.as_deref(), Some("subfile"));
+    assert_eq!(
+        diagnostic.location,
+        Some(slinky::SourceLocation {
+            line: 8,
+            column: 43
+        })
+    );
+
+    // Errors on the yaml syntax itself
+    let diagnostic =
+        slinky::Document::from_yaml_str_with_diagnostics("segments:\n  - name: [boot\n")
+            .unwrap_err();
+    assert!(diagnostic.location.is_some());
+}
+
+#[rstest]
+fn test_generation_errors(#[files("../tests/generation_errors/*.yaml")] path: PathBuf) {
+    let document = slinky::Document::read_file(&path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    assert!(writer.add_whole_document(&document).is_err());
+}
+
+#[rstest]
+fn test_partial_linking_script_generation(
+    #[files("../tests/partial_linking/*.ld")] ld_path: PathBuf,
+) {
+    let yaml_path = ld_path.with_extension("yaml");
+
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_ld_contents =
+        fs::read_to_string(ld_path).expect("unable to read expected ld file");
+
+    compare_multiline_strings(
+        &expected_ld_contents,
+        &writer
+            .get_main_writer()
+            .export_linker_script_to_string()
+            .unwrap(),
+    );
+
+    for (partial, name) in writer.get_partial_writers() {
+        let mut p = PathBuf::new();
+
+        p.push("..");
+        p.push(
+            document
+                .settings
+                .partial_scripts_folder_escaped(&rs)
+                .expect("Not able to escape path")
+                .unwrap(),
+        );
+        p.push(format!("{}.ld", name));
+
+        let expected_partial_ld_contents =
+            fs::read_to_string(p).expect("unable to read expected ld file");
+
+        compare_multiline_strings(
+            &expected_partial_ld_contents,
+            &partial.export_linker_script_to_string().unwrap(),
+        );
+    }
+}
+
+#[test]
+fn test_d_generated_files() {
+    let path = Path::new("../tests/d_generated_files/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+    let target_path = document.settings.target_path_escaped(&rs).unwrap().unwrap();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let d = writer
+        .export_dependencies_file_to_string(&target_path)
+        .unwrap();
+    assert!(d.ends_with(
+        "\nbuild/include/symbols.h build/game.memmap.json: \\\n    ../tests/d_generated_files/game.yaml \\\n    ../tests/d_generated_files/symbols.yaml\n"
+    ));
+
+    // The partial scripts are generated files too
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let d = writer
+        .get_main_writer()
+        .export_dependencies_file_to_string(&target_path)
+        .unwrap();
+    assert!(d.contains(
+        "\nbuild/include/symbols.h build/game.memmap.json build/partial/boot.ld build/partial/code.ld:"
+    ));
+}
+
+#[test]
+fn test_ninja_file_generation() {
+    let path = Path::new("../tests/ninja/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(path.with_extension("ninja")).expect("unable to read expected file");
+    compare_multiline_strings(&expected, &writer.export_ninja_file_to_string().unwrap());
+}
+
+#[test]
+fn test_partial_scripts_order() {
+    let path = Path::new("../tests/partial_linking/vram_classes.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let mut rs = create_runtime_settings();
+    rs.set_dry_run(true);
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    // The partial scripts are generated concurrently, but they must keep the
+    // order of the segments of the document
+    let names: Vec<&String> = writer
+        .get_partial_writers()
+        .iter()
+        .map(|(_, name)| name)
+        .collect();
+    let segment_names: Vec<&String> = document.segments.iter().map(|x| &x.name).collect();
+    assert_eq!(names, segment_names);
+
+    let written = writer.export_partial_scripts_to_files().unwrap();
+    assert_eq!(written.len(), names.len());
+    for (file, (partial, name)) in written.iter().zip(writer.get_partial_writers()) {
+        assert_eq!(file.path.file_stem().unwrap().to_str().unwrap(), name);
+        assert_eq!(
+            file.contents.as_deref().unwrap(),
+            partial.export_linker_script_to_string().unwrap().as_bytes()
+        );
+    }
+}
+
+/// Generates every file of the document on a dry run, returning them.
+fn generate_all_files(path: &Path, rs: &RuntimeSettings) -> Vec<slinky::WrittenFile> {
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, rs);
+    writer.add_whole_document(&document).expect("");
+
+    let script_path = rs.escape_path(Path::new("build/linker_script.ld")).unwrap();
+    let mut written = writer.export_linker_script_to_file(&script_path).unwrap();
+    written.extend(writer.save_other_files().unwrap());
+    written
+}
+
+#[test]
+fn test_reproducible_outputs() {
+    let path = Path::new("../tests/partial_linking/partial_build_path.yaml");
+    let mut rs = create_runtime_settings();
+    rs.set_dry_run(true);
+    rs.set_emit_version_comment(true);
+    rs.set_emit_fingerprint(true);
+
+    let first = generate_all_files(path, &rs);
+    let second = generate_all_files(path, &rs);
+
+    // The script, the dependency file, the header and the partial scripts
+    let extensions: Vec<&str> = first
+        .iter()
+        .map(|x| x.path.extension().unwrap().to_str().unwrap())
+        .collect();
+    for extension in ["ld", "d", "h"] {
+        assert!(extensions.contains(&extension));
+    }
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_banner_date() {
+    let document = slinky::Document::from_yaml_str(
+        "settings:\n  linker_script_banner: /* Generated on {date} */\nsegments:\n  - name: boot\n    files:\n      - { path: src/boot/boot_main.o }\n",
+    )
+    .expect("unable to read document");
+    let mut rs = create_runtime_settings();
+    rs.set_emit_version_comment(true);
+    rs.set_source_date_epoch(Some(1700000000));
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    let script = writer.export_linker_script_to_string().unwrap();
+    assert!(script.starts_with("/* Generated on 2023-11-14 */\n"));
+
+    // Leap days are handled too
+    rs.set_source_date_epoch(Some(951782400));
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    let script = writer.export_linker_script_to_string().unwrap();
+    assert!(script.starts_with("/* Generated on 2000-02-29 */\n"));
+}
+
+#[rstest]
+fn test_dependencies_consistency(#[files("../tests/test_cases/*.ld")] ld_path: PathBuf) {
+    let document = slinky::Document::read_file(&ld_path.with_extension("yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    writer.check_dependencies().unwrap();
+}
+
+#[rstest]
+fn test_partial_linking_dependencies_consistency(
+    #[files("../tests/partial_linking/*.ld")] ld_path: PathBuf,
+) {
+    let document = slinky::Document::read_file(&ld_path.with_extension("yaml"))
+        .expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    writer.check_dependencies().unwrap();
+}
+
+#[rstest]
+fn test_partial_linking_d_generation(#[files("../tests/partial_linking/*.d")] d_path: PathBuf) {
+    let yaml_path = d_path.with_extension("yaml");
+
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_d_contents = fs::read_to_string(d_path).expect("unable to read expected d file");
+
+    let target_path = &document.settings.target_path_escaped(&rs).unwrap().unwrap();
+    compare_multiline_strings(
+        &expected_d_contents,
+        &writer
+            .get_main_writer()
+            .export_dependencies_file_to_string(target_path)
+            .unwrap(),
+    );
+
+    for (partial, name) in writer.get_partial_writers() {
+        let mut p = PathBuf::new();
+
+        p.push("..");
+        p.push(
+            document
+                .settings
+                .partial_scripts_folder_escaped(&rs)
+                .expect("Unable to escape path")
+                .unwrap(),
+        );
+        p.push(format!("{}.d", name));
+
+        let expected_partial_ld_contents =
+            fs::read_to_string(p).expect("unable to read expected d file");
+
+        let mut partial_target = document
+            .settings
+            .base_path_escaped(&rs)
+            .expect("Failed to escape path");
+
+        partial_target.push(
+            document
+                .settings
+                .partial_build_object_path_escaped(&rs, name)
+                .expect("Failed to escape path")
+                .unwrap(),
+        );
+
+        compare_multiline_strings(
+            &expected_partial_ld_contents,
+            &partial
+                .export_dependencies_file_to_string(&partial_target)
+                .unwrap(),
+        );
+    }
+}
+
+#[rstest]
+fn test_partial_linking_symbols_header_generation(
+    #[files("../tests/partial_linking/*.h")] h_path: PathBuf,
+) {
+    let yaml_path = h_path.with_extension("yaml");
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_h_contents = fs::read_to_string(h_path).expect("unable to read expected h file");
+
+    compare_multiline_strings(
+        &expected_h_contents,
+        &writer
+            .get_main_writer()
+            .export_symbol_header_to_string()
+            .unwrap(),
+    );
+}
+
+#[rstest]
+#[case("archives")]
+#[case("basic_example")]
+#[case("conditional_includes")]
+#[case("discard_rules")]
+#[case("hardcoded_gp")]
+#[case("pad_test")]
+#[case("required_syms")]
+fn test_linker_script_import_roundtrip(#[case] name: &str) {
+    let ld_path = PathBuf::from(format!("../tests/test_cases/{}.ld", name));
+
+    let imported =
+        slinky::Document::from_linker_script(&ld_path).expect("unable to import linker script");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&imported.document, &rs);
+    writer.add_whole_document(&imported.document).expect("");
+
+    let expected_ld_contents =
+        fs::read_to_string(ld_path).expect("unable to read expected ld file");
+
+    compare_multiline_strings(
+        &expected_ld_contents,
+        &writer.export_linker_script_to_string().unwrap(),
+    );
+}
+
+#[test]
+fn test_64_bits_addresses() {
+    let path = Path::new("../tests/test_cases/vram_64_bits.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    assert_eq!(document.segments[0].fixed_vram, Some(0xFFFFFFFF80000400));
+    assert_eq!(
+        document.settings.hardcoded_gp_value,
+        Some(0xFFFFFFFF800E4090)
+    );
+
+    let imported =
+        slinky::Document::from_linker_script(&path.with_extension("ld")).expect("unable to import");
+
+    assert_eq!(
+        imported.document.segments[0].fixed_vram,
+        Some(0xFFFFFFFF80000400)
+    );
+    assert_eq!(
+        imported.document.settings.hardcoded_gp_value,
+        Some(0xFFFFFFFF800E4090)
+    );
+}
+
+#[test]
+fn test_fixed_vram_overlap_address_width() {
+    let path = Path::new("../tests/test_cases/fixed_vram_overlaps.yaml");
+    let mut document = slinky::Document::read_file(path).expect("unable to read original file");
+    document.settings.hex_address_width = 16;
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+    let script = writer.export_linker_script_to_string().unwrap();
+
+    assert!(script.contains(
+        "ASSERT((code_VRAM_END <= 0x0000000080200000), \"Error: Segment `code` overlaps with the fixed vram of segment `buffers` (0x0000000080200000)\");"
+    ));
+}
+
+#[test]
+fn test_linker_script_import_overlay() {
+    let imported =
+        slinky::Document::from_linker_script(Path::new("../tests/test_cases/overlay_group.ld"))
+            .expect("unable to import linker script");
+
+    assert!(imported
+        .unsupported
+        .contains(&"`OVERLAY` block".to_string()));
+    assert_eq!(imported.document.segments.first().unwrap().name, "boot");
+    assert_eq!(imported.document.segments.last().unwrap().name, "audio");
+}
+
+#[test]
+fn test_linker_script_import_wildcard_path() {
+    let script = "SECTIONS
+{
+    .boot 0x80000400 : AT(0)
+    {
+        build/src/*.o(.text); /* every other file */
+        build/asm/entry.o(.text);
+    }
+}
+";
+    let imported =
+        slinky::Document::from_linker_script_str(script).expect("unable to import linker script");
+
+    assert_eq!(
+        imported.unsupported,
+        ["Wildcard file pattern `build/src/*.o` inside `.boot`"]
+    );
+    assert_eq!(
+        imported.document.segments[0].files[0].path,
+        PathBuf::from("build/asm/entry.o")
+    );
+}
+
+#[test]
+fn test_linker_script_import_unknown_generated_symbol() {
+    // The `code` segment is imported as `main`, so nothing generates the
+    // `code_VRAM_END` used by the address of `ovl_title`
+    let imported = slinky::Document::from_linker_script(Path::new(
+        "../tests/test_cases/segment_symbol_name.ld",
+    ))
+    .expect("unable to import linker script");
+
+    assert_eq!(imported.document.segments[2].name, "ovl_title");
+    assert_eq!(imported.document.segments[2].fixed_symbol, None);
+    assert_eq!(
+        imported.unsupported.last().unwrap(),
+        "Address `code_VRAM_END` of output section `.ovl_title`, which is named like a symbol the imported document doesn't generate"
+    );
+}
+
+#[cfg(feature = "splat")]
+#[rstest]
+fn test_splat_yaml_conversion(#[files("../tests/splat/*.ld")] ld_path: PathBuf) {
+    let yaml_path = ld_path.with_extension("yaml");
+
+    let document =
+        slinky::Document::from_splat_yaml(&yaml_path).expect("unable to convert splat yaml");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_ld_contents =
+        fs::read_to_string(ld_path).expect("unable to read expected ld file");
+
+    compare_multiline_strings(
+        &expected_ld_contents,
+        &writer.export_linker_script_to_string().unwrap(),
+    );
+}
+
+#[test]
+fn test_generate() {
+    let path = Path::new("../tests/test_cases/basic_example.yaml");
+    let rs = create_runtime_settings();
+
+    let outputs = slinky::generate(path, &rs).expect("unable to generate");
+
+    let expected =
+        fs::read_to_string(path.with_extension("ld")).expect("unable to read expected file");
+    compare_multiline_strings(&expected, &outputs.linker_script);
+    assert!(outputs.partial_scripts.is_empty());
+    assert_eq!(outputs.dependencies, None);
+    assert_eq!(outputs.written, []);
+}
+
+#[test]
+fn test_dry_run() {
+    let path = Path::new("../tests/test_cases/basic_example.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let mut rs = create_runtime_settings();
+    rs.set_dry_run(true);
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).unwrap();
+
+    let expected = fs::read(path.with_extension("ld")).expect("unable to read expected file");
+    let written = writer
+        .export_linker_script_to_file(&slinky::EscapedPath::from(
+            "../tests/test_cases/basic_example.ld".to_string(),
+        ))
+        .unwrap();
+    assert_eq!(written.len(), 1);
+    assert!(!written[0].updated);
+    assert_eq!(written[0].contents.as_ref(), Some(&expected));
+
+    let missing = std::env::temp_dir().join("slinky_dry_run.ld");
+    let _ = fs::remove_file(&missing);
+    let written = writer
+        .export_linker_script_to_file(&slinky::EscapedPath::from(
+            missing.to_str().unwrap().to_string(),
+        ))
+        .unwrap();
+    assert!(written[0].updated);
+    assert!(!missing.exists());
+}
+
+#[test]
+fn test_generate_all() {
+    let paths = [
+        "../tests/test_cases/basic_example.yaml",
+        "../tests/test_cases/includes.yaml",
+        "../tests/test_cases/basic_example.yaml",
+        "../tests/test_cases/non_existing.yaml",
+    ];
+    let rs = create_runtime_settings();
+
+    let results = slinky::batch::generate_all(
+        paths
+            .iter()
+            .map(|x| (PathBuf::from(x), rs.clone()))
+            .collect(),
+    );
+    assert_eq!(results.len(), paths.len());
+
+    for (path, result) in paths.iter().zip(&results).take(3) {
+        let expected = fs::read_to_string(Path::new(path).with_extension("ld"))
+            .expect("unable to read expected file");
+        let outputs = result.as_ref().expect("unable to generate");
+        compare_multiline_strings(&expected, &outputs.linker_script);
+    }
+    assert!(matches!(
+        results[3],
+        Err(slinky::SlinkyError::FailedFileOpen { .. })
+    ));
+}
+
+#[test]
+fn test_generate_both_modes() {
+    let mut document =
+        slinky::Document::read_file(Path::new("../tests/partial_linking/required_syms.yaml"))
+            .expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    // Avoid writing the partial scripts and the symbols header to the tree
+    let folder = std::env::temp_dir().join("slinky_generate_both_modes");
+    document.settings.partial_scripts_folder = Some(folder.clone());
+    document.settings.symbols_header_path = None;
+
+    let outputs = slinky::generate_document(&document, &rs, slinky::GenerationMode::Both)
+        .expect("unable to generate");
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).unwrap();
+    compare_multiline_strings(
+        &writer.export_linker_script_to_string().unwrap(),
+        &outputs.linker_script,
+    );
+
+    let expected_main = fs::read_to_string("../tests/partial_linking/required_syms.ld")
+        .expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected_main,
+        outputs.partial_main_script.as_ref().unwrap(),
+    );
+
+    let expected_boot = fs::read_to_string("../tests/partial_linking/required_syms/boot.ld")
+        .expect("unable to read expected file");
+    assert_eq!(outputs.partial_scripts.len(), 1);
+    assert_eq!(outputs.partial_scripts[0].0, "boot");
+    compare_multiline_strings(&expected_boot, &outputs.partial_scripts[0].1);
+
+    assert_eq!(outputs.written.len(), 1);
+    assert_eq!(outputs.written[0].path, folder.join("boot.ld"));
+
+    fs::remove_dir_all(&folder).expect("unable to remove generated files");
+}
+
+#[cfg(feature = "test_fixtures")]
+#[test]
+fn test_fixtures_corpus() {
+    for fixture in slinky::test_fixtures::ALL {
+        let document = fixture.document().expect("unable to parse fixture");
+        assert_eq!(document.validate(), [], "fixture {}", fixture.name);
+
+        let base_rs = create_runtime_settings();
+        let all_rs: Vec<RuntimeSettings> = if document.targets.is_empty() {
+            vec![base_rs]
+        } else {
+            document
+                .targets
+                .iter()
+                .map(|target| target.runtime_settings(&base_rs))
+                .collect()
+        };
+
+        for rs in &all_rs {
+            let mut writer = slinky::LinkerWriter::new(&document, rs);
+            writer.add_whole_document(&document).unwrap();
+            writer.export_linker_script_to_string().unwrap();
+            writer.check_dependencies().unwrap();
+
+            if document.settings.partial_scripts_folder.is_some() {
+                let mut writer = slinky::PartialLinkerWriter::new(&document, rs);
+                writer.add_whole_document(&document).unwrap();
+                writer.export_linker_script_to_string().unwrap();
+                writer.check_dependencies().unwrap();
+            }
+        }
+    }
+}
+
+#[rstest]
+fn test_format(#[files("../tests/fmt/*.formatted.yaml")] formatted_path: PathBuf) {
+    let file_name = formatted_path.file_name().unwrap().to_str().unwrap();
+    let input_path = formatted_path.with_file_name(file_name.replace(".formatted", ""));
+
+    let input = fs::read_to_string(input_path).expect("unable to read input file");
+    let expected = fs::read_to_string(&formatted_path).expect("unable to read expected file");
+
+    let formatted = slinky::Document::format_yaml_str(&input).expect("unable to format");
+    assert_eq!(formatted.dropped_comments, Vec::<usize>::new());
+    compare_multiline_strings(&expected, &formatted.contents);
+}
+
+#[rstest]
+fn test_format_idempotent(#[files("../tests/test_cases/*.yaml")] yaml_path: PathBuf) {
+    let contents = fs::read_to_string(yaml_path).expect("unable to read file");
+
+    let formatted = slinky::Document::format_yaml_str(&contents).expect("unable to format");
+    let again =
+        slinky::Document::format_yaml_str(&formatted.contents).expect("unable to format again");
+
+    assert_eq!(again.dropped_comments, Vec::<usize>::new());
+    compare_multiline_strings(&formatted.contents, &again.contents);
+}
+
+#[rstest]
+fn test_write_yaml(#[files("../tests/test_cases/*.yaml")] yaml_path: PathBuf) {
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+
+    let written = document.write_yaml_to_string().expect("unable to write");
+    let read_back = slinky::Document::from_yaml_str(&written).expect("unable to read written");
+
+    // The written yaml is already canonical and writing it again is stable
+    let formatted = slinky::Document::format_yaml_str(&written).expect("unable to format");
+    compare_multiline_strings(&written, &formatted.contents);
+    compare_multiline_strings(&written, &read_back.write_yaml_to_string().unwrap());
+}
+
+#[cfg(feature = "splat")]
+#[test]
+fn test_write_yaml_splat() {
+    let document = slinky::Document::from_splat_yaml(Path::new("../tests/splat/basic_splat.yaml"))
+        .expect("unable to import");
+
+    let expected = fs::read_to_string("../tests/splat/basic_splat.slinky.yaml")
+        .expect("unable to read expected file");
+    compare_multiline_strings(&expected, &document.write_yaml_to_string().unwrap());
+}
+
+#[rstest]
+fn test_memory_map_generation(#[files("../tests/memory_map/*.yaml")] yaml_path: PathBuf) {
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_table =
+        fs::read_to_string(yaml_path.with_extension("md")).expect("unable to read expected table");
+    compare_multiline_strings(
+        &expected_table,
+        &writer.export_memory_map_table_to_string().unwrap(),
+    );
+
+    let expected_csv =
+        fs::read_to_string(yaml_path.with_extension("csv")).expect("unable to read expected csv");
+    compare_multiline_strings(
+        &expected_csv,
+        &writer.export_memory_map_csv_to_string().unwrap(),
+    );
+}
+
+#[rstest]
+fn test_binary_rules_generation(#[files("../tests/binary_rules/*.yaml")] yaml_path: PathBuf) {
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(yaml_path.with_extension("mk")).expect("unable to read expected file");
+    compare_multiline_strings(&expected, &writer.export_binary_rules_to_string().unwrap());
+}
+
+#[rstest]
+fn test_objects_list_generation(#[files("../tests/objects_list/*.yaml")] yaml_path: PathBuf) {
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(yaml_path.with_extension("txt")).expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected,
+        &writer
+            .export_objects_list_to_string(slinky::ObjectsListFormat::Lines)
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_segment_objects_list() {
+    let path = Path::new("../tests/objects_list/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    // Paths with spaces are quoted on response files
+    assert_eq!(
+        writer
+            .export_segment_objects_list_to_string("code", slinky::ObjectsListFormat::NinjaRsp)
+            .unwrap(),
+        "build/src/code/main.o \"build/src/code/audio/audio thread.o\" build/src/code/logo.bin.o\n"
+    );
+    assert_eq!(
+        writer
+            .export_segment_objects_list_to_string("missing", slinky::ObjectsListFormat::Lines)
+            .unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn test_toolchain_objects() {
+    let path = Path::new("../tests/objects_list/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    assert_eq!(
+        writer
+            .export_toolchain_objects_list_to_string("ido", slinky::ObjectsListFormat::Lines)
+            .unwrap(),
+        "build/src/boot/boot_main.o\nbuild/lib/libultra.a\n"
+    );
+    assert_eq!(
+        writer.export_toolchain_objects_to_string().unwrap(),
+        "SLINKY_IDO_OBJECTS := \\\n    build/src/boot/boot_main.o \\\n    build/lib/libultra.a\n\nSLINKY_GCC_OBJECTS := \\\n    build/src/code/main.o \\\n    build/src/code/audio/audio thread.o \\\n    build/src/code/logo.bin.o\n"
+    );
+}
+
+#[test]
+fn test_external_segments() {
+    let mut rs = create_runtime_settings();
+    rs.add_custom_options([("fragments_dir".into(), "../tests/external".into())]);
+
+    let path = Path::new("../tests/external/handwritten.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(path.with_extension("ld")).expect("unable to read expected file");
+    compare_multiline_strings(&expected, &writer.export_linker_script_to_string().unwrap());
+    assert!(writer.get_linker_symbols().contains("boot_ROM_END"));
+    writer.check_dependencies().unwrap();
+
+    // The fragment never assigns `boot_ROM_END`
+    let path = Path::new("../tests/external/missing_symbol.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    assert!(matches!(
+        writer.add_whole_document(&document),
+        Err(slinky::SlinkyError::MissingFragmentSymbol { .. })
+    ));
+}
+
+#[rstest]
+fn test_shiftable_linker_script_generation(#[files("../tests/shiftable/*.ld")] ld_path: PathBuf) {
+    let yaml_path = Path::new("../tests/test_cases")
+        .join(ld_path.file_name().unwrap())
+        .with_extension("yaml");
+
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let mut rs = create_runtime_settings();
+    rs.set_flavor(slinky::ScriptFlavor::Shiftable);
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected_ld_contents = fs::read_to_string(ld_path).expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected_ld_contents,
+        &writer.export_linker_script_to_string().unwrap(),
+    );
+}
+
+#[rstest]
+fn test_c_array_export(#[files("../tests/c_array/*.yaml")] yaml_path: PathBuf) {
+    let document = slinky::Document::read_file(&yaml_path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(yaml_path.with_extension("c")).expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected,
+        &writer
+            .export_linker_script_as_c_to_string("linker_script")
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_escape_path_normalization() {
+    let mut rs = create_runtime_settings();
+
+    let escaped = rs
+        .escape_path(Path::new("build\\{version}\\src\\boot\\..\\main.o"))
+        .unwrap();
+    assert_eq!(escaped.to_string(), "build/us/src/main.o");
+
+    let escaped = rs.escape_path(Path::new(".\\src/./a/../b.o")).unwrap();
+    assert_eq!(escaped.to_string(), "src/b.o");
+
+    // Leading `..` can't be collapsed, while the root can't be escaped
+    let escaped = rs.escape_path(Path::new("..\\..\\lib\\x.a")).unwrap();
+    assert_eq!(escaped.to_string(), "../../lib/x.a");
+    let escaped = rs.escape_path(Path::new("/../usr/lib")).unwrap();
+    assert_eq!(escaped.to_string(), "/usr/lib");
+
+    let mut escaped = rs.escape_path(Path::new("build\\")).unwrap();
+    escaped.push(rs.escape_path(Path::new("..\\lib\\x.a")).unwrap());
+    assert_eq!(escaped.to_string(), "lib/x.a");
+
+    // Backslashes are valid file name characters outside Windows
+    rs.set_forward_slashes(false);
+    let escaped = rs.escape_path(Path::new("src\\main.o")).unwrap();
+    if cfg!(windows) {
+        assert_eq!(escaped.to_string(), "src/main.o");
+    } else {
+        assert_eq!(escaped.to_string(), "src\\main.o");
+    }
+}
+
+#[test]
+fn test_statistics() {
+    let path = Path::new("../tests/test_cases/conditional_includes.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let stats = writer.statistics();
+    assert_eq!(stats.segments_emitted, 4);
+    assert_eq!(stats.segments_skipped, 2);
+    assert_eq!(stats.files_emitted(), 21);
+    assert_eq!(stats.files_skipped, 15);
+    assert_eq!(stats.files_per_kind.get(&slinky::FileKind::Group), Some(&1));
+    assert_eq!(
+        stats.files_per_kind.get(&slinky::FileKind::Archive),
+        Some(&1)
+    );
+    assert_eq!(stats.linker_symbols, writer.get_linker_symbols().len());
+    assert_eq!(stats.sections_discarded, 7);
+}
+
+#[test]
+fn test_document_mutation() {
+    let rs = create_runtime_settings();
+    let generate = |document: &slinky::Document| {
+        let mut writer = slinky::LinkerWriter::new(document, &rs);
+        writer.add_whole_document(document).expect("");
+        writer.export_linker_script_to_string().unwrap()
+    };
+
+    let mut document = slinky::Document::from_yaml_str(
+        r#"
+settings:
+  base_path: build
+segments:
+  - name: boot
+    files:
+      - { path: src/boot.o }
+  - name: kanji
+    files:
+      - { path: src/kanji.o }
+  - name: main
+    follows_segment: boot
+    files:
+      - { path: src/main.o }
+"#,
+    )
+    .unwrap();
+    let original = document.clone();
+    let original_script = generate(&document);
+
+    // Failed mutations leave the document untouched
+    assert!(matches!(
+        document.remove_segment("boot"),
+        Err(slinky::SlinkyError::MissingFollowedSegment { .. })
+    ));
+    assert!(matches!(
+        document.add_segment(document.segments[0].clone()),
+        Err(slinky::SlinkyError::DuplicatedSegment { .. })
+    ));
+    assert!(matches!(
+        document.add_file_to_segment("audio", slinky::FileInfo::new_object("a.o".into())),
+        Err(slinky::SlinkyError::MissingSegment { .. })
+    ));
+    assert_eq!(document, original);
+    assert_eq!(generate(&document), original_script);
+
+    let extra = slinky::Document::from_yaml_str(
+        r#"
+segments:
+  - name: audio
+    files:
+      - { path: src/audio.o }
+"#,
+    )
+    .unwrap()
+    .segments
+    .remove(0);
+
+    document.remove_segment("kanji").unwrap();
+    document.insert_segment(1, extra).unwrap();
+    document
+        .add_file_to_segment("main", slinky::FileInfo::new_object("src/util.o".into()))
+        .unwrap();
+    document
+        .update_settings(|settings| settings.hardcoded_gp_value = Some(0x80010000))
+        .unwrap();
+
+    let expected = slinky::Document::from_yaml_str(
+        r#"
+settings:
+  base_path: build
+  hardcoded_gp_value: 0x80010000
+segments:
+  - name: boot
+    files:
+      - { path: src/boot.o }
+  - name: audio
+    files:
+      - { path: src/audio.o }
+  - name: main
+    follows_segment: boot
+    files:
+      - { path: src/main.o }
+      - { path: src/util.o }
+"#,
+    )
+    .unwrap();
+    compare_multiline_strings(&generate(&expected), &generate(&document));
+}
+
+#[test]
+fn test_gdb_script() {
+    let path = Path::new("../tests/gdb_script/game.yaml");
+    let document = slinky::Document::read_file(path).expect("unable to read original file");
+    let rs = create_runtime_settings();
+
+    let mut writer = slinky::PartialLinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let expected =
+        fs::read_to_string(path.with_extension("gdb")).expect("unable to read expected file");
+    compare_multiline_strings(
+        &expected,
+        &writer
+            .get_main_writer()
+            .export_gdb_script_to_string()
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_archive_members() {
+    let rs = create_runtime_settings();
+
+    let document = slinky::Document::read_file(Path::new("../tests/archive_members/libultra.yaml"))
+        .expect("unable to read original file");
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    writer.add_whole_document(&document).expect("");
+
+    let document = slinky::Document::read_file(Path::new(
+        "../tests/generation_errors/archive_missing_member.yaml",
+    ))
+    .expect("unable to read original file");
+    let mut writer = slinky::LinkerWriter::new(&document, &rs);
+    assert_eq!(
+        writer.add_whole_document(&document),
+        Err(slinky::SlinkyError::MissingArchiveMember {
+            path: PathBuf::from("../tests/archive_members/libultra.a"),
+            member: "osVirtualToPhysical.o".to_string(),
+        })
     );
 }
@@ -0,0 +1,13 @@
+SLINKY_BINARY_OBJECTS := \
+    build/assets/textures/logo.rgba16.bin.o \
+    build/assets/audio/sfx_bank.bin.o \
+    build/assets/title/background.ci8.bin.o
+
+build/assets/textures/logo.rgba16.bin.o: build/assets/textures/logo.rgba16.bin
+	$(OBJCOPY) -I binary $(OBJCOPY_BINARY_FLAGS) $< $@
+
+build/assets/audio/sfx_bank.bin.o: build/assets/audio/sfx_bank.bin
+	$(OBJCOPY) -I binary $(OBJCOPY_BINARY_FLAGS) --rename-section .data=.rodata $< $@
+
+build/assets/title/background.ci8.bin.o: build/assets/title/background.ci8.bin
+	$(OBJCOPY) -I binary $(OBJCOPY_BINARY_FLAGS) --rename-section .data=.rodata $< $@
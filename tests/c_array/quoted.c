@@ -0,0 +1,96 @@
+const char linker_script[] =
+    "SECTIONS\n"
+    "{\n"
+    "    __romPos = 0x0;\n"
+    "\n"
+    "    boot_ROM_START = __romPos;\n"
+    "    boot_VRAM = ADDR(.boot);\n"
+    "    boot_alloc_VRAM = .;\n"
+    "\n"
+    "    .boot : AT(boot_ROM_START)\n"
+    "    {\n"
+    "        FILL(0x00000000);\n"
+    "        boot_TEXT_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.text*);\n"
+    "        build/src/boot/dmadata.o(.text*);\n"
+    "        boot_TEXT_END = .;\n"
+    "        boot_TEXT_SIZE = ABSOLUTE(boot_TEXT_END - boot_TEXT_START);\n"
+    "\n"
+    "        boot_DATA_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.data*);\n"
+    "        build/src/boot/dmadata.o(.data*);\n"
+    "        boot_DATA_END = .;\n"
+    "        boot_DATA_SIZE = ABSOLUTE(boot_DATA_END - boot_DATA_START);\n"
+    "\n"
+    "        boot_RODATA_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.rodata*);\n"
+    "        build/src/boot/dmadata.o(.rodata*);\n"
+    "        boot_RODATA_END = .;\n"
+    "        boot_RODATA_SIZE = ABSOLUTE(boot_RODATA_END - boot_RODATA_START);\n"
+    "\n"
+    "        boot_SDATA_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.sdata*);\n"
+    "        build/src/boot/dmadata.o(.sdata*);\n"
+    "        boot_SDATA_END = .;\n"
+    "        boot_SDATA_SIZE = ABSOLUTE(boot_SDATA_END - boot_SDATA_START);\n"
+    "    }\n"
+    "\n"
+    "    boot_alloc_VRAM_END = .;\n"
+    "    boot_alloc_VRAM_SIZE = ABSOLUTE(boot_alloc_VRAM_END - boot_alloc_VRAM);\n"
+    "\n"
+    "    boot_noload_VRAM = .;\n"
+    "\n"
+    "    .boot.noload (NOLOAD) :\n"
+    "    {\n"
+    "        FILL(0x00000000);\n"
+    "        boot_SBSS_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.sbss*);\n"
+    "        build/src/boot/dmadata.o(.sbss*);\n"
+    "        boot_SBSS_END = .;\n"
+    "        boot_SBSS_SIZE = ABSOLUTE(boot_SBSS_END - boot_SBSS_START);\n"
+    "\n"
+    "        boot_SCOMMON_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.scommon*);\n"
+    "        build/src/boot/dmadata.o(.scommon*);\n"
+    "        boot_SCOMMON_END = .;\n"
+    "        boot_SCOMMON_SIZE = ABSOLUTE(boot_SCOMMON_END - boot_SCOMMON_START);\n"
+    "\n"
+    "        boot_BSS_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(.bss*);\n"
+    "        build/src/boot/dmadata.o(.bss*);\n"
+    "        boot_BSS_END = .;\n"
+    "        boot_BSS_SIZE = ABSOLUTE(boot_BSS_END - boot_BSS_START);\n"
+    "\n"
+    "        bootCOMMON_START = .;\n"
+    "        build/src/boot/my \"quoted\" file.o(COMMON*);\n"
+    "        build/src/boot/dmadata.o(COMMON*);\n"
+    "        bootCOMMON_END = .;\n"
+    "        bootCOMMON_SIZE = ABSOLUTE(bootCOMMON_END - bootCOMMON_START);\n"
+    "    }\n"
+    "\n"
+    "    boot_noload_VRAM_END = .;\n"
+    "    boot_noload_VRAM_SIZE = ABSOLUTE(boot_noload_VRAM_END - boot_noload_VRAM);\n"
+    "\n"
+    "    __romPos += SIZEOF(.boot);\n"
+    "    boot_VRAM_END = .;\n"
+    "    boot_VRAM_SIZE = ABSOLUTE(boot_VRAM_END - boot_VRAM);\n"
+    "    boot_ROM_END = __romPos;\n"
+    "    boot_ROM_SIZE = ABSOLUTE(boot_ROM_END - boot_ROM_START);\n"
+    "\n"
+    "    .symtab 0 : { *(.symtab); }\n"
+    "    .strtab 0 : { *(.strtab); }\n"
+    "    .shstrtab 0 : { *(.shstrtab); }\n"
+    "\n"
+    "    /DISCARD/ :\n"
+    "    {\n"
+    "        *(.reginfo);\n"
+    "        *(.MIPS.abiflags);\n"
+    "        *(.MIPS.options);\n"
+    "        *(.note.gnu.build-id);\n"
+    "        *(.interp);\n"
+    "        *(.eh_frame);\n"
+    "        *(.got);\n"
+    "        *(*);\n"
+    "    }\n"
+    "}\n";
+const unsigned int linker_script_len = sizeof(linker_script) - 1;
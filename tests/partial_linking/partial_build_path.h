@@ -0,0 +1,77 @@
+#ifndef HEADER_SYMBOLS_H
+#define HEADER_SYMBOLS_H
+
+extern char boot_ROM_START[];
+extern char boot_VRAM[];
+extern char boot_alloc_VRAM[];
+extern char boot_TEXT_START[];
+extern char boot_TEXT_END[];
+extern char boot_TEXT_SIZE[];
+extern char boot_DATA_START[];
+extern char boot_DATA_END[];
+extern char boot_DATA_SIZE[];
+extern char boot_RODATA_START[];
+extern char boot_RODATA_END[];
+extern char boot_RODATA_SIZE[];
+extern char boot_SDATA_START[];
+extern char boot_SDATA_END[];
+extern char boot_SDATA_SIZE[];
+extern char boot_alloc_VRAM_END[];
+extern char boot_alloc_VRAM_SIZE[];
+extern char boot_noload_VRAM[];
+extern char boot_SBSS_START[];
+extern char boot_SBSS_END[];
+extern char boot_SBSS_SIZE[];
+extern char boot_SCOMMON_START[];
+extern char boot_SCOMMON_END[];
+extern char boot_SCOMMON_SIZE[];
+extern char boot_BSS_START[];
+extern char boot_BSS_END[];
+extern char boot_BSS_SIZE[];
+extern char bootCOMMON_START[];
+extern char bootCOMMON_END[];
+extern char bootCOMMON_SIZE[];
+extern char boot_noload_VRAM_END[];
+extern char boot_noload_VRAM_SIZE[];
+extern char boot_VRAM_END[];
+extern char boot_VRAM_SIZE[];
+extern char boot_ROM_END[];
+extern char boot_ROM_SIZE[];
+extern char main_ROM_START[];
+extern char main_VRAM[];
+extern char main_alloc_VRAM[];
+extern char main_TEXT_START[];
+extern char main_TEXT_END[];
+extern char main_TEXT_SIZE[];
+extern char main_DATA_START[];
+extern char main_DATA_END[];
+extern char main_DATA_SIZE[];
+extern char main_RODATA_START[];
+extern char main_RODATA_END[];
+extern char main_RODATA_SIZE[];
+extern char main_SDATA_START[];
+extern char main_SDATA_END[];
+extern char main_SDATA_SIZE[];
+extern char main_alloc_VRAM_END[];
+extern char main_alloc_VRAM_SIZE[];
+extern char main_noload_VRAM[];
+extern char main_SBSS_START[];
+extern char main_SBSS_END[];
+extern char main_SBSS_SIZE[];
+extern char main_SCOMMON_START[];
+extern char main_SCOMMON_END[];
+extern char main_SCOMMON_SIZE[];
+extern char main_BSS_START[];
+extern char main_BSS_END[];
+extern char main_BSS_SIZE[];
+extern char mainCOMMON_START[];
+extern char mainCOMMON_END[];
+extern char mainCOMMON_SIZE[];
+extern char main_noload_VRAM_END[];
+extern char main_noload_VRAM_SIZE[];
+extern char main_VRAM_END[];
+extern char main_VRAM_SIZE[];
+extern char main_ROM_END[];
+extern char main_ROM_SIZE[];
+
+#endif
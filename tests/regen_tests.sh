@@ -21,6 +21,13 @@ for filepath in tests/test_cases/*.yaml; do
     cargo run --release -- $filepath -o $output --omit-version-comment --custom-options version=us -c compiler=modern_gcc
 done
 
+# The shiftable scripts were deleted above, so they are listed by hand
+for stem in script_flavors; do
+    output=tests/shiftable/$stem.ld
+    echo Generating $output
+    cargo run --release -- tests/test_cases/$stem.yaml -o tests/test_cases/$stem.ld --shiftable-output $output --omit-version-comment -c version=us -c compiler=modern_gcc
+done
+
 for filepath in tests/partial_linking/*.yaml; do
     filename=$(basename -- "$filepath")
     stem="${filename%.*}"
@@ -28,3 +35,9 @@ for filepath in tests/partial_linking/*.yaml; do
     echo Generating $output
     cargo run --release -- $filepath -o $output --omit-version-comment --partial-linking -c version=us -c compiler=modern_gcc
 done
+
+# The fragments of the external segments are read relative to the working
+# directory, which is the crate's folder when running the tests
+output=tests/external/handwritten.ld
+echo Generating $output
+(cd slinky && cargo run --release -p slinky-cli -- ../tests/external/handwritten.yaml -o ../$output --omit-version-comment -c fragments_dir=../tests/external)
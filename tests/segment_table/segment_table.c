@@ -0,0 +1,25 @@
+#include "segment_table.h"
+
+extern char boot_ROM_START[];
+extern char boot_ROM_END[];
+extern char boot_VRAM[];
+extern char boot_VRAM_END[];
+extern char code_ROM_START[];
+extern char code_ROM_END[];
+extern char code_VRAM[];
+extern char code_VRAM_END[];
+extern char ovl_a_ROM_START[];
+extern char ovl_a_ROM_END[];
+extern char ovl_a_VRAM[];
+extern char ovl_a_VRAM_END[];
+extern char heap_ROM_START[];
+extern char heap_ROM_END[];
+extern char heap_VRAM[];
+extern char heap_VRAM_END[];
+
+const SegmentTableEntry gSegmentTable[SEGMENT_ID_MAX] = {
+    { "boot", boot_ROM_START, boot_ROM_END, boot_VRAM, boot_VRAM_END, 0 },
+    { "code", code_ROM_START, code_ROM_END, code_VRAM, code_VRAM_END, SEGMENT_FLAG_COMPRESSED },
+    { "ovl_a", ovl_a_ROM_START, ovl_a_ROM_END, ovl_a_VRAM, ovl_a_VRAM_END, SEGMENT_FLAG_OVERLAY },
+    { "heap", heap_ROM_START, heap_ROM_END, heap_VRAM, heap_VRAM_END, SEGMENT_FLAG_NOLOAD },
+};
@@ -0,0 +1,27 @@
+#ifndef HEADER_SEGMENT_TABLE_H
+#define HEADER_SEGMENT_TABLE_H
+
+typedef struct SegmentTableEntry {
+    const char *name;
+    void *romStart;
+    void *romEnd;
+    void *vramStart;
+    void *vramEnd;
+    unsigned int flags;
+} SegmentTableEntry;
+
+#define SEGMENT_FLAG_OVERLAY (1 << 0)
+#define SEGMENT_FLAG_NOLOAD (1 << 1)
+#define SEGMENT_FLAG_COMPRESSED (1 << 2)
+
+typedef enum SegmentId {
+    SEGMENT_ID_BOOT,
+    SEGMENT_ID_CODE,
+    SEGMENT_ID_OVL_A,
+    SEGMENT_ID_HEAP,
+    SEGMENT_ID_MAX
+} SegmentId;
+
+extern const SegmentTableEntry gSegmentTable[SEGMENT_ID_MAX];
+
+#endif
@@ -0,0 +1,7 @@
+#ifndef HEADER_LINKER_OFFSETS_H
+#define HEADER_LINKER_OFFSETS_H
+
+extern char boot_data_start_OFFSET[];
+extern char code_text_start_OFFSET[];
+
+#endif
@@ -0,0 +1,12 @@
+#ifndef HEADER_ROM_SYMBOLS_H
+#define HEADER_ROM_SYMBOLS_H
+
+extern char boot_ROM_START[];
+extern char boot_ROM_END[];
+extern char boot_ROM_SIZE[];
+extern char code_ROM_START[];
+extern char code_ROM_END[];
+extern char code_ROM_SIZE[];
+extern char gRomEnd[];
+
+#endif
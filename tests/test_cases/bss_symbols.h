@@ -0,0 +1,81 @@
+#ifndef HEADER_SYMBOLS_H
+#define HEADER_SYMBOLS_H
+
+extern char boot_ROM_START[];
+extern char boot_VRAM[];
+extern char boot_alloc_VRAM[];
+extern char boot_TEXT_START[];
+extern char boot_TEXT_END[];
+extern char boot_TEXT_SIZE[];
+extern char boot_DATA_START[];
+extern char boot_DATA_END[];
+extern char boot_DATA_SIZE[];
+extern char boot_RODATA_START[];
+extern char boot_RODATA_END[];
+extern char boot_RODATA_SIZE[];
+extern char boot_SDATA_START[];
+extern char boot_SDATA_END[];
+extern char boot_SDATA_SIZE[];
+extern char boot_alloc_VRAM_END[];
+extern char boot_alloc_VRAM_SIZE[];
+extern char boot_noload_VRAM[];
+extern char boot_SBSS_START[];
+extern char boot_SBSS_END[];
+extern char boot_SBSS_SIZE[];
+extern char boot_SCOMMON_START[];
+extern char boot_SCOMMON_END[];
+extern char boot_SCOMMON_SIZE[];
+extern char boot_BSS_START[];
+extern char boot_BSS_END[];
+extern char boot_BSS_SIZE[];
+extern char bootCOMMON_START[];
+extern char bootCOMMON_END[];
+extern char bootCOMMON_SIZE[];
+extern char boot_noload_VRAM_END[];
+extern char boot_noload_VRAM_SIZE[];
+extern char boot_BSS_VRAM_START[];
+extern char boot_bss_size[];
+extern char boot_VRAM_END[];
+extern char boot_VRAM_SIZE[];
+extern char boot_ROM_END[];
+extern char boot_ROM_SIZE[];
+extern char code_ROM_START[];
+extern char code_VRAM[];
+extern char code_alloc_VRAM[];
+extern char code_TEXT_START[];
+extern char code_TEXT_END[];
+extern char code_TEXT_SIZE[];
+extern char code_DATA_START[];
+extern char code_DATA_END[];
+extern char code_DATA_SIZE[];
+extern char code_RODATA_START[];
+extern char code_RODATA_END[];
+extern char code_RODATA_SIZE[];
+extern char code_SDATA_START[];
+extern char code_SDATA_END[];
+extern char code_SDATA_SIZE[];
+extern char code_alloc_VRAM_END[];
+extern char code_alloc_VRAM_SIZE[];
+extern char code_noload_VRAM[];
+extern char code_SBSS_START[];
+extern char code_SBSS_END[];
+extern char code_SBSS_SIZE[];
+extern char code_SCOMMON_START[];
+extern char code_SCOMMON_END[];
+extern char code_SCOMMON_SIZE[];
+extern char code_BSS_START[];
+extern char code_BSS_END[];
+extern char code_BSS_SIZE[];
+extern char codeCOMMON_START[];
+extern char codeCOMMON_END[];
+extern char codeCOMMON_SIZE[];
+extern char code_noload_VRAM_END[];
+extern char code_noload_VRAM_SIZE[];
+extern char code_BSS_VRAM_START[];
+extern char code_bss_size[];
+extern char code_VRAM_END[];
+extern char code_VRAM_SIZE[];
+extern char code_ROM_END[];
+extern char code_ROM_SIZE[];
+
+#endif
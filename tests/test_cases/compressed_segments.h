@@ -0,0 +1,151 @@
+#ifndef HEADER_SYMBOLS_H
+#define HEADER_SYMBOLS_H
+
+extern char boot_ROM_START[];
+extern char boot_VRAM[];
+extern char boot_alloc_VRAM[];
+extern char boot_TEXT_START[];
+extern char boot_TEXT_END[];
+extern char boot_TEXT_SIZE[];
+extern char boot_DATA_START[];
+extern char boot_DATA_END[];
+extern char boot_DATA_SIZE[];
+extern char boot_RODATA_START[];
+extern char boot_RODATA_END[];
+extern char boot_RODATA_SIZE[];
+extern char boot_SDATA_START[];
+extern char boot_SDATA_END[];
+extern char boot_SDATA_SIZE[];
+extern char boot_alloc_VRAM_END[];
+extern char boot_alloc_VRAM_SIZE[];
+extern char boot_noload_VRAM[];
+extern char boot_SBSS_START[];
+extern char boot_SBSS_END[];
+extern char boot_SBSS_SIZE[];
+extern char boot_SCOMMON_START[];
+extern char boot_SCOMMON_END[];
+extern char boot_SCOMMON_SIZE[];
+extern char boot_BSS_START[];
+extern char boot_BSS_END[];
+extern char boot_BSS_SIZE[];
+extern char bootCOMMON_START[];
+extern char bootCOMMON_END[];
+extern char bootCOMMON_SIZE[];
+extern char boot_noload_VRAM_END[];
+extern char boot_noload_VRAM_SIZE[];
+extern char boot_VRAM_END[];
+extern char boot_VRAM_SIZE[];
+extern char boot_ROM_END[];
+extern char boot_ROM_SIZE[];
+extern char code_ROM_START[];
+extern char code_VRAM[];
+extern char code_alloc_VRAM[];
+extern char code_TEXT_START[];
+extern char code_TEXT_END[];
+extern char code_TEXT_SIZE[];
+extern char code_DATA_START[];
+extern char code_DATA_END[];
+extern char code_DATA_SIZE[];
+extern char code_RODATA_START[];
+extern char code_RODATA_END[];
+extern char code_RODATA_SIZE[];
+extern char code_SDATA_START[];
+extern char code_SDATA_END[];
+extern char code_SDATA_SIZE[];
+extern char code_alloc_VRAM_END[];
+extern char code_alloc_VRAM_SIZE[];
+extern char code_noload_VRAM[];
+extern char code_SBSS_START[];
+extern char code_SBSS_END[];
+extern char code_SBSS_SIZE[];
+extern char code_SCOMMON_START[];
+extern char code_SCOMMON_END[];
+extern char code_SCOMMON_SIZE[];
+extern char code_BSS_START[];
+extern char code_BSS_END[];
+extern char code_BSS_SIZE[];
+extern char codeCOMMON_START[];
+extern char codeCOMMON_END[];
+extern char codeCOMMON_SIZE[];
+extern char code_noload_VRAM_END[];
+extern char code_noload_VRAM_SIZE[];
+extern char code_VRAM_END[];
+extern char code_VRAM_SIZE[];
+extern char code_ROM_END[];
+extern char code_ROM_SIZE[];
+extern char ovl_title_ROM_START[];
+extern char ovl_title_VRAM[];
+extern char ovl_title_alloc_VRAM[];
+extern char ovl_title_TEXT_START[];
+extern char ovl_title_TEXT_END[];
+extern char ovl_title_TEXT_SIZE[];
+extern char ovl_title_DATA_START[];
+extern char ovl_title_DATA_END[];
+extern char ovl_title_DATA_SIZE[];
+extern char ovl_title_RODATA_START[];
+extern char ovl_title_RODATA_END[];
+extern char ovl_title_RODATA_SIZE[];
+extern char ovl_title_SDATA_START[];
+extern char ovl_title_SDATA_END[];
+extern char ovl_title_SDATA_SIZE[];
+extern char ovl_title_alloc_VRAM_END[];
+extern char ovl_title_alloc_VRAM_SIZE[];
+extern char ovl_title_noload_VRAM[];
+extern char ovl_title_SBSS_START[];
+extern char ovl_title_SBSS_END[];
+extern char ovl_title_SBSS_SIZE[];
+extern char ovl_title_SCOMMON_START[];
+extern char ovl_title_SCOMMON_END[];
+extern char ovl_title_SCOMMON_SIZE[];
+extern char ovl_title_BSS_START[];
+extern char ovl_title_BSS_END[];
+extern char ovl_title_BSS_SIZE[];
+extern char ovl_titleCOMMON_START[];
+extern char ovl_titleCOMMON_END[];
+extern char ovl_titleCOMMON_SIZE[];
+extern char ovl_title_noload_VRAM_END[];
+extern char ovl_title_noload_VRAM_SIZE[];
+extern char ovl_title_VRAM_END[];
+extern char ovl_title_VRAM_SIZE[];
+extern char ovl_title_ROM_END[];
+extern char ovl_title_ROM_SIZE[];
+extern char assets_ROM_START[];
+extern char assets_VRAM[];
+extern char assets_alloc_VRAM[];
+extern char assets_TEXT_START[];
+extern char assets_TEXT_END[];
+extern char assets_TEXT_SIZE[];
+extern char assets_DATA_START[];
+extern char assets_DATA_END[];
+extern char assets_DATA_SIZE[];
+extern char assets_RODATA_START[];
+extern char assets_RODATA_END[];
+extern char assets_RODATA_SIZE[];
+extern char assets_SDATA_START[];
+extern char assets_SDATA_END[];
+extern char assets_SDATA_SIZE[];
+extern char assets_alloc_VRAM_END[];
+extern char assets_alloc_VRAM_SIZE[];
+extern char assets_noload_VRAM[];
+extern char assets_SBSS_START[];
+extern char assets_SBSS_END[];
+extern char assets_SBSS_SIZE[];
+extern char assets_SCOMMON_START[];
+extern char assets_SCOMMON_END[];
+extern char assets_SCOMMON_SIZE[];
+extern char assets_BSS_START[];
+extern char assets_BSS_END[];
+extern char assets_BSS_SIZE[];
+extern char assetsCOMMON_START[];
+extern char assetsCOMMON_END[];
+extern char assetsCOMMON_SIZE[];
+extern char assets_noload_VRAM_END[];
+extern char assets_noload_VRAM_SIZE[];
+extern char assets_VRAM_END[];
+extern char assets_VRAM_SIZE[];
+extern char assets_ROM_END[];
+extern char assets_ROM_SIZE[];
+extern char code_COMPRESSED_SIZE[];
+extern char ovl_title_COMPRESSED_SIZE[];
+
+#endif
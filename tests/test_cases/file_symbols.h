@@ -0,0 +1,158 @@
+#ifndef HEADER_SYMBOLS_H
+#define HEADER_SYMBOLS_H
+
+extern char boot_ROM_START[];
+extern char boot_VRAM[];
+extern char boot_alloc_VRAM[];
+extern char boot_TEXT_START[];
+extern char boot_src_boot_boot_main_TEXT_START[];
+extern char boot_src_boot_boot_main_TEXT_END[];
+extern char boot_src_boot_util_TEXT_START[];
+extern char boot_util_TEXT_PAD_START[];
+extern char boot_src_boot_util_TEXT_END[];
+extern char boot_TEXT_END[];
+extern char boot_TEXT_SIZE[];
+extern char boot_DATA_START[];
+extern char boot_src_boot_boot_main_DATA_START[];
+extern char boot_src_boot_boot_main_DATA_END[];
+extern char boot_src_boot_util_DATA_START[];
+extern char boot_src_boot_util_DATA_END[];
+extern char boot_DATA_END[];
+extern char boot_DATA_SIZE[];
+extern char boot_RODATA_START[];
+extern char boot_src_boot_boot_main_RODATA_START[];
+extern char boot_src_boot_boot_main_RODATA_END[];
+extern char boot_src_boot_util_RODATA_START[];
+extern char boot_src_boot_util_RODATA_END[];
+extern char boot_RODATA_END[];
+extern char boot_RODATA_SIZE[];
+extern char boot_SDATA_START[];
+extern char boot_src_boot_boot_main_SDATA_START[];
+extern char boot_src_boot_boot_main_SDATA_END[];
+extern char boot_src_boot_util_SDATA_START[];
+extern char boot_src_boot_util_SDATA_END[];
+extern char boot_SDATA_END[];
+extern char boot_SDATA_SIZE[];
+extern char boot_alloc_VRAM_END[];
+extern char boot_alloc_VRAM_SIZE[];
+extern char boot_noload_VRAM[];
+extern char boot_SBSS_START[];
+extern char boot_src_boot_boot_main_SBSS_START[];
+extern char boot_src_boot_boot_main_SBSS_END[];
+extern char boot_src_boot_util_SBSS_START[];
+extern char boot_src_boot_util_SBSS_END[];
+extern char boot_SBSS_END[];
+extern char boot_SBSS_SIZE[];
+extern char boot_SCOMMON_START[];
+extern char boot_src_boot_boot_main_SCOMMON_START[];
+extern char boot_src_boot_boot_main_SCOMMON_END[];
+extern char boot_src_boot_util_SCOMMON_START[];
+extern char boot_src_boot_util_SCOMMON_END[];
+extern char boot_SCOMMON_END[];
+extern char boot_SCOMMON_SIZE[];
+extern char boot_BSS_START[];
+extern char boot_src_boot_boot_main_BSS_START[];
+extern char boot_src_boot_boot_main_BSS_END[];
+extern char boot_src_boot_util_BSS_START[];
+extern char boot_src_boot_util_BSS_END[];
+extern char boot_BSS_END[];
+extern char boot_BSS_SIZE[];
+extern char bootCOMMON_START[];
+extern char boot_src_boot_boot_mainCOMMON_START[];
+extern char boot_src_boot_boot_mainCOMMON_END[];
+extern char boot_src_boot_utilCOMMON_START[];
+extern char boot_src_boot_utilCOMMON_END[];
+extern char bootCOMMON_END[];
+extern char bootCOMMON_SIZE[];
+extern char boot_noload_VRAM_END[];
+extern char boot_noload_VRAM_SIZE[];
+extern char boot_VRAM_END[];
+extern char boot_VRAM_SIZE[];
+extern char boot_ROM_END[];
+extern char boot_ROM_SIZE[];
+extern char code_ROM_START[];
+extern char code_VRAM[];
+extern char code_alloc_VRAM[];
+extern char code_TEXT_START[];
+extern char code_src_code_main_TEXT_START[];
+extern char code_src_code_main_TEXT_END[];
+extern char code_src_code_graph_graph_TEXT_START[];
+extern char code_src_code_graph_graph_TEXT_END[];
+extern char code_lib_libultra_guMtxF2L_TEXT_START[];
+extern char code_lib_libultra_guMtxF2L_TEXT_END[];
+extern char code_TEXT_END[];
+extern char code_TEXT_SIZE[];
+extern char code_DATA_START[];
+extern char code_src_code_main_DATA_START[];
+extern char code_src_code_main_DATA_END[];
+extern char code_src_code_graph_graph_DATA_START[];
+extern char code_src_code_graph_graph_DATA_END[];
+extern char code_lib_libultra_guMtxF2L_DATA_START[];
+extern char code_lib_libultra_guMtxF2L_DATA_END[];
+extern char code_DATA_END[];
+extern char code_DATA_SIZE[];
+extern char code_RODATA_START[];
+extern char code_src_code_main_RODATA_START[];
+extern char code_src_code_main_RODATA_END[];
+extern char code_src_code_graph_graph_RODATA_START[];
+extern char code_src_code_graph_graph_RODATA_END[];
+extern char code_lib_libultra_guMtxF2L_RODATA_START[];
+extern char code_lib_libultra_guMtxF2L_RODATA_END[];
+extern char code_RODATA_END[];
+extern char code_RODATA_SIZE[];
+extern char code_SDATA_START[];
+extern char code_src_code_main_SDATA_START[];
+extern char code_src_code_main_SDATA_END[];
+extern char code_src_code_graph_graph_SDATA_START[];
+extern char code_src_code_graph_graph_SDATA_END[];
+extern char code_lib_libultra_guMtxF2L_SDATA_START[];
+extern char code_lib_libultra_guMtxF2L_SDATA_END[];
+extern char code_SDATA_END[];
+extern char code_SDATA_SIZE[];
+extern char code_alloc_VRAM_END[];
+extern char code_alloc_VRAM_SIZE[];
+extern char code_noload_VRAM[];
+extern char code_SBSS_START[];
+extern char code_src_code_main_SBSS_START[];
+extern char code_src_code_main_SBSS_END[];
+extern char code_src_code_graph_graph_SBSS_START[];
+extern char code_src_code_graph_graph_SBSS_END[];
+extern char code_lib_libultra_guMtxF2L_SBSS_START[];
+extern char code_lib_libultra_guMtxF2L_SBSS_END[];
+extern char code_SBSS_END[];
+extern char code_SBSS_SIZE[];
+extern char code_SCOMMON_START[];
+extern char code_src_code_main_SCOMMON_START[];
+extern char code_src_code_main_SCOMMON_END[];
+extern char code_src_code_graph_graph_SCOMMON_START[];
+extern char code_src_code_graph_graph_SCOMMON_END[];
+extern char code_lib_libultra_guMtxF2L_SCOMMON_START[];
+extern char code_lib_libultra_guMtxF2L_SCOMMON_END[];
+extern char code_SCOMMON_END[];
+extern char code_SCOMMON_SIZE[];
+extern char code_BSS_START[];
+extern char code_src_code_main_BSS_START[];
+extern char code_src_code_main_BSS_END[];
+extern char code_src_code_graph_graph_BSS_START[];
+extern char code_src_code_graph_graph_BSS_END[];
+extern char code_lib_libultra_guMtxF2L_BSS_START[];
+extern char code_lib_libultra_guMtxF2L_BSS_END[];
+extern char code_BSS_END[];
+extern char code_BSS_SIZE[];
+extern char codeCOMMON_START[];
+extern char code_src_code_mainCOMMON_START[];
+extern char code_src_code_mainCOMMON_END[];
+extern char code_src_code_graph_graphCOMMON_START[];
+extern char code_src_code_graph_graphCOMMON_END[];
+extern char code_lib_libultra_guMtxF2LCOMMON_START[];
+extern char code_lib_libultra_guMtxF2LCOMMON_END[];
+extern char codeCOMMON_END[];
+extern char codeCOMMON_SIZE[];
+extern char code_noload_VRAM_END[];
+extern char code_noload_VRAM_SIZE[];
+extern char code_VRAM_END[];
+extern char code_VRAM_SIZE[];
+extern char code_ROM_END[];
+extern char code_ROM_SIZE[];
+
+#endif
@@ -0,0 +1,80 @@
+#ifndef HEADER_SYMBOLS_H
+#define HEADER_SYMBOLS_H
+
+extern char _bootSegmentRomStart[];
+extern char _bootSegmentStart[];
+extern char _boot_allocSegmentStart[];
+extern char _bootSegmentTextStart[];
+extern char _bootSegmentTextEnd[];
+extern char _bootSegmentTextSize[];
+extern char _bootSegmentDataStart[];
+extern char _bootSegmentDataEnd[];
+extern char _bootSegmentDataSize[];
+extern char _bootSegmentRoDataStart[];
+extern char _bootSegmentRoDataEnd[];
+extern char _bootSegmentRoDataSize[];
+extern char _bootSegmentSdataStart[];
+extern char _bootSegmentSdataEnd[];
+extern char _bootSegmentSdataSize[];
+extern char _boot_allocSegmentEnd[];
+extern char _boot_allocSegmentSize[];
+extern char _boot_noloadSegmentStart[];
+extern char _bootSegmentSbssStart[];
+extern char _bootSegmentSbssEnd[];
+extern char _bootSegmentSbssSize[];
+extern char _bootSegmentScommonStart[];
+extern char _bootSegmentScommonEnd[];
+extern char _bootSegmentScommonSize[];
+extern char _bootSegmentBssStart[];
+extern char _bootSegmentBssEnd[];
+extern char _bootSegmentBssSize[];
+extern char _bootSegmentCOMMONStart[];
+extern char _bootSegmentCOMMONEnd[];
+extern char _bootSegmentCOMMONSize[];
+extern char _boot_noloadSegmentEnd[];
+extern char _boot_noloadSegmentSize[];
+extern char _bootSegmentEnd[];
+extern char _bootSegmentSize[];
+extern char _bootSegmentRomEnd[];
+extern char _bootSegmentRomSize[];
+extern char _codeSegmentRomStart[];
+extern char _codeSegmentStart[];
+extern char _code_allocSegmentStart[];
+extern char _codeSegmentTextStart[];
+extern char _codeSegmentTextEnd[];
+extern char _codeSegmentTextSize[];
+extern char _codeSegmentDataStart[];
+extern char _codeSegmentDataEnd[];
+extern char _codeSegmentDataSize[];
+extern char _codeSegmentRoDataStart[];
+extern char _codeSegmentRoDataEnd[];
+extern char _codeSegmentRoDataSize[];
+extern char _codeSegmentSdataStart[];
+extern char _codeSegmentSdataEnd[];
+extern char _codeSegmentSdataSize[];
+extern char _code_allocSegmentEnd[];
+extern char _code_allocSegmentSize[];
+extern char _code_noloadSegmentStart[];
+extern char _codeSegmentSbssStart[];
+extern char _codeSegmentSbssEnd[];
+extern char _codeSegmentSbssSize[];
+extern char _codeSegmentScommonStart[];
+extern char _codeSegmentScommonEnd[];
+extern char _codeSegmentScommonSize[];
+extern char _codeSegmentBssStart[];
+extern char _codeSegmentBssEnd[];
+extern char _codeSegmentBssSize[];
+extern char _codeSegmentCOMMONStart[];
+extern char _codeSegmentCOMMONEnd[];
+extern char _codeSegmentCOMMONSize[];
+extern char _code_noloadSegmentEnd[];
+extern char _code_noloadSegmentSize[];
+extern char _codeSegmentEnd[];
+extern char _codeSegmentSize[];
+extern char _codeSegmentRomEnd[];
+extern char _codeSegmentRomSize[];
+extern char gCodeVram[];
+extern char gBootRomStart[];
+extern char gCodeTextEnd[];
+
+#endif
@@ -0,0 +1,75 @@
+#[allow(non_upper_case_globals)]
+extern "C" {
+    pub static boot_ROM_START: u8;
+    pub static boot_VRAM: u8;
+    pub static boot_alloc_VRAM: u8;
+    pub static boot_TEXT_START: u8;
+    pub static boot_TEXT_END: u8;
+    pub static boot_TEXT_SIZE: u8;
+    pub static boot_DATA_START: u8;
+    pub static boot_DATA_END: u8;
+    pub static boot_DATA_SIZE: u8;
+    pub static boot_RODATA_START: u8;
+    pub static boot_RODATA_END: u8;
+    pub static boot_RODATA_SIZE: u8;
+    pub static boot_SDATA_START: u8;
+    pub static boot_SDATA_END: u8;
+    pub static boot_SDATA_SIZE: u8;
+    pub static boot_alloc_VRAM_END: u8;
+    pub static boot_alloc_VRAM_SIZE: u8;
+    pub static boot_noload_VRAM: u8;
+    pub static boot_SBSS_START: u8;
+    pub static boot_SBSS_END: u8;
+    pub static boot_SBSS_SIZE: u8;
+    pub static boot_SCOMMON_START: u8;
+    pub static boot_SCOMMON_END: u8;
+    pub static boot_SCOMMON_SIZE: u8;
+    pub static boot_BSS_START: u8;
+    pub static boot_BSS_END: u8;
+    pub static boot_BSS_SIZE: u8;
+    pub static bootCOMMON_START: u8;
+    pub static bootCOMMON_END: u8;
+    pub static bootCOMMON_SIZE: u8;
+    pub static boot_noload_VRAM_END: u8;
+    pub static boot_noload_VRAM_SIZE: u8;
+    pub static boot_VRAM_END: u8;
+    pub static boot_VRAM_SIZE: u8;
+    pub static boot_ROM_END: u8;
+    pub static boot_ROM_SIZE: u8;
+    pub static code_ROM_START: u8;
+    pub static code_VRAM: u8;
+    pub static code_alloc_VRAM: u8;
+    pub static code_TEXT_START: u8;
+    pub static code_TEXT_END: u8;
+    pub static code_TEXT_SIZE: u8;
+    pub static code_DATA_START: u8;
+    pub static code_DATA_END: u8;
+    pub static code_DATA_SIZE: u8;
+    pub static code_RODATA_START: u8;
+    pub static code_RODATA_END: u8;
+    pub static code_RODATA_SIZE: u8;
+    pub static code_SDATA_START: u8;
+    pub static code_SDATA_END: u8;
+    pub static code_SDATA_SIZE: u8;
+    pub static code_alloc_VRAM_END: u8;
+    pub static code_alloc_VRAM_SIZE: u8;
+    pub static code_noload_VRAM: u8;
+    pub static code_SBSS_START: u8;
+    pub static code_SBSS_END: u8;
+    pub static code_SBSS_SIZE: u8;
+    pub static code_SCOMMON_START: u8;
+    pub static code_SCOMMON_END: u8;
+    pub static code_SCOMMON_SIZE: u8;
+    pub static code_BSS_START: u8;
+    pub static code_BSS_END: u8;
+    pub static code_BSS_SIZE: u8;
+    pub static codeCOMMON_START: u8;
+    pub static codeCOMMON_END: u8;
+    pub static codeCOMMON_SIZE: u8;
+    pub static code_noload_VRAM_END: u8;
+    pub static code_noload_VRAM_SIZE: u8;
+    pub static code_VRAM_END: u8;
+    pub static code_VRAM_SIZE: u8;
+    pub static code_ROM_END: u8;
+    pub static code_ROM_SIZE: u8;
+}